@@ -0,0 +1,464 @@
+//! Self-service account management.
+//!
+//! Users change their own email address, avatar and account lifetime:
+//!
+//! - a new email address is only applied once the user confirms it with the
+//!   token sent to it; only the token's hash is stored;
+//! - avatars are stored by the server, the account only records their
+//!   content type;
+//! - deleting an account is requested first and carried out after a grace
+//!   period, during which the user can cancel the request.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use orbis_db::{Database, DatabasePool};
+use sha2::{Digest as _, Sha256};
+use orbis_core::UserId;
+
+/// Account service for self-service changes.
+#[derive(Clone)]
+pub struct AccountService {
+    /// Database connection.
+    db: Database,
+}
+
+impl AccountService {
+    /// Create a new account service.
+    #[must_use]
+    pub const fn new(db: Database) -> Self {
+        Self {
+            db,
+        }
+    }
+
+    /// Start changing the email address of a user.
+    ///
+    /// Replaces any pending change of the user. Returns the token confirming
+    /// the change, to send to the new address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the change cannot be stored.
+    pub async fn request_email_change(
+        &self,
+        user_id: UserId,
+        new_email: &str,
+        expires_at: DateTime<Utc>,
+    ) -> orbis_core::Result<String> {
+        let token = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+        let token_hash = Self::hash_token(&token);
+        let delete = "DELETE FROM email_changes WHERE user_id = $1";
+        let insert = "INSERT INTO email_changes (token_hash, user_id, new_email, expires_at) VALUES ($1, $2, $3, $4)";
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let mut tx = pool
+                    .begin()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(delete)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(insert)
+                    .bind(&token_hash)
+                    .bind(user_id)
+                    .bind(new_email)
+                    .bind(expires_at)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                tx.commit()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let mut tx = pool
+                    .begin()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(delete)
+                    .bind(user_id.to_string())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(insert)
+                    .bind(&token_hash)
+                    .bind(user_id.to_string())
+                    .bind(new_email)
+                    .bind(expires_at.to_rfc3339())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                tx.commit()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            },
+        }
+
+        Ok(token)
+    }
+
+    /// Apply the pending email change of a user confirmed by a token.
+    ///
+    /// Returns the new email address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is unknown, expired or belongs to
+    /// another user, if another user took the address meanwhile, or if the
+    /// update fails.
+    pub async fn confirm_email_change(&self, user_id: UserId, token: &str) -> orbis_core::Result<String> {
+        let token_hash = Self::hash_token(token);
+        let invalid = || orbis_core::Error::validation("Invalid or expired email confirmation token");
+        let select = "SELECT new_email, expires_at FROM email_changes WHERE token_hash = $1 AND user_id = $2";
+        let taken = "SELECT COUNT(*) FROM users WHERE email = $1 AND id <> $2 \
+                     AND tenant_id IS NOT DISTINCT FROM (SELECT tenant_id FROM users WHERE id = $2)";
+        let update = "UPDATE users SET email = $1, updated_at = $2 WHERE id = $3";
+        let delete = "DELETE FROM email_changes WHERE user_id = $1";
+        let now = Utc::now();
+
+        let new_email = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let mut tx = pool
+                    .begin()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                let (new_email, expires_at): (String, DateTime<Utc>) = sqlx::query_as(select)
+                    .bind(&token_hash)
+                    .bind(user_id)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                    .ok_or_else(invalid)?;
+                if expires_at <= now {
+                    return Err(invalid());
+                }
+
+                let count: (i64,) = sqlx::query_as(taken)
+                    .bind(&new_email)
+                    .bind(user_id)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                if count.0 > 0 {
+                    return Err(orbis_core::Error::conflict("Email already in use"));
+                }
+
+                sqlx::query(update)
+                    .bind(&new_email)
+                    .bind(now)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(delete)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                tx.commit()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                new_email
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let mut tx = pool
+                    .begin()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                let (new_email, expires_at): (String, String) = sqlx::query_as(select)
+                    .bind(&token_hash)
+                    .bind(user_id.to_string())
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                    .ok_or_else(invalid)?;
+                let expired = DateTime::parse_from_rfc3339(&expires_at).map_or(true, |at| at <= now);
+                if expired {
+                    return Err(invalid());
+                }
+
+                let count: (i64,) = sqlx::query_as(taken)
+                    .bind(&new_email)
+                    .bind(user_id.to_string())
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                if count.0 > 0 {
+                    return Err(orbis_core::Error::conflict("Email already in use"));
+                }
+
+                sqlx::query(update)
+                    .bind(&new_email)
+                    .bind(now.to_rfc3339())
+                    .bind(user_id.to_string())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                sqlx::query(delete)
+                    .bind(user_id.to_string())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                tx.commit()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                new_email
+            },
+        };
+
+        Ok(new_email)
+    }
+
+    /// Get the email address a user is changing to, if a change is pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn pending_email(&self, user_id: UserId) -> orbis_core::Result<Option<String>> {
+        let sql = "SELECT new_email FROM email_changes WHERE user_id = $1";
+        let row: Option<(String,)> = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query_as(sql).bind(user_id).fetch_optional(pool).await,
+            DatabasePool::Sqlite(ref pool) => {
+                sqlx::query_as(sql)
+                    .bind(user_id.to_string())
+                    .fetch_optional(pool)
+                    .await
+            },
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        Ok(row.map(|(email,)| email))
+    }
+
+    /// Set or clear (`None`) the display name of a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or the update fails.
+    pub async fn set_display_name(&self, user_id: UserId, display_name: Option<&str>) -> orbis_core::Result<()> {
+        let sql = "UPDATE users SET display_name = $1, updated_at = $2 WHERE id = $3";
+        let now = Utc::now();
+
+        let updated = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query(sql)
+                .bind(display_name)
+                .bind(now)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+            DatabasePool::Sqlite(ref pool) => sqlx::query(sql)
+                .bind(display_name)
+                .bind(now.to_rfc3339())
+                .bind(user_id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+        };
+
+        if updated == 0 {
+            return Err(orbis_core::Error::not_found(format!(
+                "User {} not found",
+                user_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Set or clear (`None`) the content type of a user's avatar.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or the update fails.
+    pub async fn set_avatar(&self, user_id: UserId, content_type: Option<&str>) -> orbis_core::Result<()> {
+        let sql = "UPDATE users SET avatar_content_type = $1, updated_at = $2 WHERE id = $3";
+        let now = Utc::now();
+
+        let updated = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query(sql)
+                .bind(content_type)
+                .bind(now)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+            DatabasePool::Sqlite(ref pool) => sqlx::query(sql)
+                .bind(content_type)
+                .bind(now.to_rfc3339())
+                .bind(user_id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+        };
+
+        if updated == 0 {
+            return Err(orbis_core::Error::not_found(format!(
+                "User {} not found",
+                user_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the content type of a user's avatar, if the user has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn avatar(&self, user_id: UserId) -> orbis_core::Result<Option<String>> {
+        let sql = "SELECT avatar_content_type FROM users WHERE id = $1";
+        let row: Option<(Option<String>,)> = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query_as(sql).bind(user_id).fetch_optional(pool).await,
+            DatabasePool::Sqlite(ref pool) => {
+                sqlx::query_as(sql)
+                    .bind(user_id.to_string())
+                    .fetch_optional(pool)
+                    .await
+            },
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        Ok(row.and_then(|(content_type,)| content_type))
+    }
+
+    /// Record that a user asked for their account to be deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or the update fails.
+    pub async fn request_deletion(&self, user_id: UserId, requested_at: DateTime<Utc>) -> orbis_core::Result<()> {
+        let sql = "UPDATE users SET deletion_requested_at = $1, updated_at = $1 WHERE id = $2";
+
+        let updated = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query(sql)
+                .bind(requested_at)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+            DatabasePool::Sqlite(ref pool) => sqlx::query(sql)
+                .bind(requested_at.to_rfc3339())
+                .bind(user_id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+        };
+
+        if updated == 0 {
+            return Err(orbis_core::Error::not_found(format!(
+                "User {} not found",
+                user_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Cancel the deletion request of a user.
+    ///
+    /// Returns whether a request was pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn cancel_deletion(&self, user_id: UserId) -> orbis_core::Result<bool> {
+        let sql = "UPDATE users SET deletion_requested_at = NULL, updated_at = $1 \
+                   WHERE id = $2 AND deletion_requested_at IS NOT NULL";
+        let now = Utc::now();
+
+        let updated = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query(sql)
+                .bind(now)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+            DatabasePool::Sqlite(ref pool) => sqlx::query(sql)
+                .bind(now.to_rfc3339())
+                .bind(user_id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+        };
+
+        Ok(updated > 0)
+    }
+
+    /// Get when a user asked for their account to be deleted, if they did.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn deletion_requested_at(&self, user_id: UserId) -> orbis_core::Result<Option<DateTime<Utc>>> {
+        let sql = "SELECT deletion_requested_at FROM users WHERE id = $1";
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let row: Option<(Option<DateTime<Utc>>,)> = sqlx::query_as(sql)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                Ok(row.and_then(|(at,)| at))
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let row: Option<(Option<String>,)> = sqlx::query_as(sql)
+                    .bind(user_id.to_string())
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                Ok(row
+                    .and_then(|(at,)| at)
+                    .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+                    .map(|at| at.with_timezone(&Utc)))
+            },
+        }
+    }
+
+    /// Delete a user whose deletion was requested at or before `cutoff`.
+    ///
+    /// Returns whether the user was deleted; users who cancelled the request
+    /// or asked again later are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn delete_if_requested_before(&self, user_id: UserId, cutoff: DateTime<Utc>) -> orbis_core::Result<bool> {
+        // Timestamps are stored as RFC 3339 in UTC on SQLite, so they compare as text
+        let sql = "DELETE FROM users WHERE id = $1 AND deletion_requested_at <= $2";
+        let deleted = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query(sql)
+                .bind(user_id)
+                .bind(cutoff)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+            DatabasePool::Sqlite(ref pool) => sqlx::query(sql)
+                .bind(user_id.to_string())
+                .bind(cutoff.to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                .rows_affected(),
+        };
+
+        Ok(deleted > 0)
+    }
+
+    /// Hash a confirmation token for storage.
+    fn hash_token(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+}
@@ -0,0 +1,588 @@
+//! Audit log of security-relevant actions.
+//!
+//! The log is tamper-evident: entries are numbered in the order they are
+//! recorded and hash-chained, each entry's hash covering its content and
+//! the hash of the entry before it (see [`orbis_core::hasher`]). Editing,
+//! removing or reordering entries breaks the chain, which
+//! [`AuditService::verify`] detects.
+//!
+//! The chain is extended by one writer per database: the service keeps the
+//! tail of the chain in memory and serializes writes through it.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, SubsecRound as _, Utc};
+use orbis_core::hasher::{chain_hash, GENESIS_HASH};
+use orbis_db::{Database, DatabasePool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use tokio::sync::Mutex;
+use orbis_core::UserId;
+use uuid::Uuid;
+
+/// Maximum number of entries returned when listing.
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Number of entries checked per query when verifying the chain.
+const VERIFY_BATCH_SIZE: i64 = 1000;
+
+/// Columns selected for an entry.
+const ENTRY_COLUMNS: &str = "id, COALESCE(actor_id, user_id) AS actor, tenant_id, action, resource_type, resource_id, \
+                             details, ip_address, user_agent, created_at, seq, prev_hash, entry_hash";
+
+/// An audit log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Entry ID.
+    pub id: Uuid,
+
+    /// User who performed the action.
+    pub user_id: Option<UserId>,
+
+    /// Tenant the action happened in.
+    pub tenant_id: Option<Uuid>,
+
+    /// Action, such as `impersonation.started`.
+    pub action: String,
+
+    /// Type of the resource acted on.
+    pub resource_type: Option<String>,
+
+    /// ID of the resource acted on.
+    pub resource_id: Option<Uuid>,
+
+    /// Action details.
+    pub details: Value,
+
+    /// Client IP address.
+    pub ip_address: Option<String>,
+
+    /// Client user agent.
+    pub user_agent: Option<String>,
+
+    /// When the action happened.
+    pub created_at: DateTime<Utc>,
+
+    /// Position in the chain (`None` for entries recorded before chaining).
+    pub seq: Option<i64>,
+
+    /// Hash of the previous entry in the chain.
+    pub prev_hash: Option<String>,
+
+    /// Hash of the entry.
+    pub entry_hash: Option<String>,
+}
+
+impl AuditEntry {
+    /// Compute the hash of the entry, chained to the previous entry's hash.
+    #[must_use]
+    pub fn compute_hash(&self, previous: &str) -> String {
+        let content = serde_json::json!({
+            "seq": self.seq,
+            "user_id": self.user_id,
+            "tenant_id": self.tenant_id,
+            "action": self.action,
+            "resource_type": self.resource_type,
+            "resource_id": self.resource_id,
+            "details": self.details,
+            "ip_address": self.ip_address,
+            "user_agent": self.user_agent,
+            "created_at": self.created_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+        });
+        chain_hash(previous, content.to_string().as_bytes())
+    }
+}
+
+/// Data for recording an audit log entry.
+#[derive(Debug, Clone, Default)]
+pub struct NewAuditEntry {
+    /// User who performed the action.
+    pub user_id: Option<UserId>,
+
+    /// Tenant the action happened in.
+    pub tenant_id: Option<Uuid>,
+
+    /// Action.
+    pub action: String,
+
+    /// Type of the resource acted on.
+    pub resource_type: Option<String>,
+
+    /// ID of the resource acted on.
+    pub resource_id: Option<Uuid>,
+
+    /// Action details.
+    pub details: Value,
+
+    /// Client IP address.
+    pub ip_address: Option<String>,
+
+    /// Client user agent.
+    pub user_agent: Option<String>,
+}
+
+impl NewAuditEntry {
+    /// Create an entry for an action by a user on a resource.
+    #[must_use]
+    pub fn new(user_id: UserId, action: &str, resource_type: &str, resource_id: Uuid) -> Self {
+        Self {
+            user_id: Some(user_id),
+            action: action.to_string(),
+            resource_type: Some(resource_type.to_string()),
+            resource_id: Some(resource_id),
+            ..Self::default()
+        }
+    }
+
+    /// Set the action details.
+    #[must_use]
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Filters for listing audit log entries.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    /// Only entries of this user.
+    pub user_id: Option<UserId>,
+
+    /// Only entries whose action starts with this prefix.
+    pub action: Option<String>,
+
+    /// Only entries about this resource type.
+    pub resource_type: Option<String>,
+
+    /// Only entries recorded at or after this time.
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only entries recorded before this time.
+    pub until: Option<DateTime<Utc>>,
+
+    /// Maximum number of entries (capped at 500).
+    pub limit: Option<i64>,
+
+    /// Number of entries to skip.
+    pub offset: Option<i64>,
+}
+
+/// Outcome of verifying the audit chain.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainVerification {
+    /// Number of chained entries checked.
+    pub checked: u64,
+
+    /// Whether the chain is intact.
+    pub valid: bool,
+
+    /// Position of the first entry that does not match the chain.
+    pub broken_at: Option<i64>,
+
+    /// Why the chain is broken.
+    pub error: Option<String>,
+}
+
+/// Last entry of the chain.
+#[derive(Debug, Clone)]
+struct ChainTail {
+    /// Position of the entry.
+    seq: i64,
+
+    /// Hash of the entry.
+    hash: String,
+}
+
+/// Audit service writing to and reading from the audit log.
+#[derive(Clone)]
+pub struct AuditService {
+    /// Database connection.
+    db: Database,
+
+    /// Tail of the chain, loaded on the first write.
+    tail: Arc<Mutex<Option<ChainTail>>>,
+}
+
+impl AuditService {
+    /// Create a new audit service.
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            tail: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Record an entry, appending it to the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be stored.
+    pub async fn record(&self, entry: NewAuditEntry) -> orbis_core::Result<()> {
+        let mut tail = self.tail.lock().await;
+        let previous = match tail.clone() {
+            Some(previous) => previous,
+            None => self.load_tail().await?,
+        };
+        let seq = previous.seq.saturating_add(1);
+
+        let mut stored = AuditEntry {
+            id: orbis_core::new_id(),
+            user_id: entry.user_id,
+            tenant_id: entry.tenant_id,
+            action: entry.action,
+            resource_type: entry.resource_type,
+            resource_id: entry.resource_id,
+            details: entry.details,
+            ip_address: entry.ip_address,
+            user_agent: entry.user_agent,
+            // Stored timestamps keep microseconds, so hash what is stored
+            created_at: Utc::now().trunc_subsecs(6),
+            seq: Some(seq),
+            prev_hash: Some(previous.hash.clone()),
+            entry_hash: None,
+        };
+        let hash = stored.compute_hash(&previous.hash);
+        stored.entry_hash = Some(hash.clone());
+
+        if let Err(e) = self.insert(&stored).await {
+            // Another writer may have extended the chain; reload the tail next time
+            *tail = None;
+            return Err(e);
+        }
+        *tail = Some(ChainTail {
+            seq,
+            hash,
+        });
+        drop(tail);
+
+        tracing::info!(
+            action = %stored.action,
+            user_id = ?stored.user_id,
+            resource_id = ?stored.resource_id,
+            "Audit: {}",
+            stored.action
+        );
+        Ok(())
+    }
+
+    /// List the entries about a resource, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn for_resource(&self, resource_type: &str, resource_id: Uuid) -> orbis_core::Result<Vec<AuditEntry>> {
+        let sql = format!(
+            "SELECT {} FROM audit_logs WHERE resource_type = $1 AND resource_id = $2 ORDER BY created_at",
+            ENTRY_COLUMNS
+        );
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let rows = sqlx::query(&sql)
+                    .bind(resource_type)
+                    .bind(resource_id)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_pg_row).collect()
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let rows = sqlx::query(&sql)
+                    .bind(resource_type)
+                    .bind(resource_id.to_string())
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_sqlite_row).collect()
+            },
+        }
+    }
+
+    /// List the entries of a tenant (`None` for platform entries) matching
+    /// a filter, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn list(&self, tenant_id: Option<Uuid>, filter: &AuditFilter) -> orbis_core::Result<Vec<AuditEntry>> {
+        let limit = filter.limit.unwrap_or(100).clamp(1, MAX_LIST_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+        let action = filter.action.as_ref().map(|action| format!("{}%", action));
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let sql = format!(
+                    "SELECT {} FROM audit_logs WHERE tenant_id IS NOT DISTINCT FROM $1 \
+                     AND ($2::uuid IS NULL OR COALESCE(actor_id, user_id) = $2) \
+                     AND ($3::text IS NULL OR action LIKE $3) \
+                     AND ($4::text IS NULL OR resource_type = $4) \
+                     AND ($5::timestamptz IS NULL OR created_at >= $5) \
+                     AND ($6::timestamptz IS NULL OR created_at < $6) \
+                     ORDER BY created_at DESC, id DESC LIMIT $7 OFFSET $8",
+                    ENTRY_COLUMNS
+                );
+                let rows = sqlx::query(&sql)
+                    .bind(tenant_id)
+                    .bind(filter.user_id)
+                    .bind(action)
+                    .bind(&filter.resource_type)
+                    .bind(filter.since)
+                    .bind(filter.until)
+                    .bind(limit)
+                    .bind(offset)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_pg_row).collect()
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let sql = format!(
+                    "SELECT {} FROM audit_logs WHERE tenant_id IS $1 \
+                     AND ($2 IS NULL OR COALESCE(actor_id, user_id) = $2) \
+                     AND ($3 IS NULL OR action LIKE $3) \
+                     AND ($4 IS NULL OR resource_type = $4) \
+                     AND ($5 IS NULL OR created_at >= $5) \
+                     AND ($6 IS NULL OR created_at < $6) \
+                     ORDER BY created_at DESC, id DESC LIMIT $7 OFFSET $8",
+                    ENTRY_COLUMNS
+                );
+                let rows = sqlx::query(&sql)
+                    .bind(tenant_id.map(|id| id.to_string()))
+                    .bind(filter.user_id.map(|id| id.to_string()))
+                    .bind(action)
+                    .bind(&filter.resource_type)
+                    .bind(filter.since.map(sqlite_time))
+                    .bind(filter.until.map(sqlite_time))
+                    .bind(limit)
+                    .bind(offset)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_sqlite_row).collect()
+            },
+        }
+    }
+
+    /// Verify the chain, from its first entry to its last.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entries cannot be read.
+    pub async fn verify(&self) -> orbis_core::Result<ChainVerification> {
+        let mut checked = 0;
+        let mut previous = ChainTail {
+            seq: 0,
+            hash: GENESIS_HASH.to_owned(),
+        };
+
+        loop {
+            let batch = self.chained_after(previous.seq).await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            for entry in batch {
+                let seq = entry.seq.unwrap_or_default();
+                let expected = previous.seq.saturating_add(1);
+                let error = if seq != expected {
+                    Some(format!("entry {} is missing", expected))
+                } else if entry.prev_hash.as_deref() != Some(previous.hash.as_str()) {
+                    Some(format!(
+                        "entry {} is not linked to entry {}",
+                        seq, previous.seq
+                    ))
+                } else if entry.entry_hash.as_deref() != Some(entry.compute_hash(&previous.hash).as_str()) {
+                    Some(format!("entry {} was modified", seq))
+                } else {
+                    None
+                };
+
+                if let Some(error) = error {
+                    return Ok(ChainVerification {
+                        checked,
+                        valid: false,
+                        broken_at: Some(expected),
+                        error: Some(error),
+                    });
+                }
+
+                checked = checked.saturating_add(1);
+                previous = ChainTail {
+                    seq,
+                    hash: entry.entry_hash.unwrap_or_default(),
+                };
+            }
+        }
+
+        Ok(ChainVerification {
+            checked,
+            valid: true,
+            broken_at: None,
+            error: None,
+        })
+    }
+
+    /// Get the last entry of the chain.
+    async fn load_tail(&self) -> orbis_core::Result<ChainTail> {
+        let sql = "SELECT seq, entry_hash FROM audit_logs WHERE seq IS NOT NULL ORDER BY seq DESC LIMIT 1";
+        let row: Option<(i64, Option<String>)> = match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query_as(sql).fetch_optional(pool).await,
+            DatabasePool::Sqlite(ref pool) => sqlx::query_as(sql).fetch_optional(pool).await,
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        Ok(row.map_or_else(
+            || ChainTail {
+                seq: 0,
+                hash: GENESIS_HASH.to_owned(),
+            },
+            |(seq, hash)| ChainTail {
+                seq,
+                hash: hash.unwrap_or_default(),
+            },
+        ))
+    }
+
+    /// Get a batch of chained entries after a position, in chain order.
+    async fn chained_after(&self, seq: i64) -> orbis_core::Result<Vec<AuditEntry>> {
+        let sql = format!(
+            "SELECT {} FROM audit_logs WHERE seq > $1 ORDER BY seq LIMIT $2",
+            ENTRY_COLUMNS
+        );
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let rows = sqlx::query(&sql)
+                    .bind(seq)
+                    .bind(VERIFY_BATCH_SIZE)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_pg_row).collect()
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                let rows = sqlx::query(&sql)
+                    .bind(seq)
+                    .bind(VERIFY_BATCH_SIZE)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(entry_from_sqlite_row).collect()
+            },
+        }
+    }
+
+    /// Store an entry.
+    async fn insert(&self, entry: &AuditEntry) -> orbis_core::Result<()> {
+        let sql = "INSERT INTO audit_logs (id, user_id, actor_id, tenant_id, action, resource_type, resource_id, \
+                   details, ip_address, user_agent, created_at, seq, prev_hash, entry_hash) \
+                   VALUES ($1, $2, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)";
+
+        match *self.db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                sqlx::query(sql)
+                    .bind(entry.id)
+                    .bind(entry.user_id)
+                    .bind(entry.tenant_id)
+                    .bind(&entry.action)
+                    .bind(&entry.resource_type)
+                    .bind(entry.resource_id)
+                    .bind(&entry.details)
+                    .bind(&entry.ip_address)
+                    .bind(&entry.user_agent)
+                    .bind(entry.created_at)
+                    .bind(entry.seq)
+                    .bind(&entry.prev_hash)
+                    .bind(&entry.entry_hash)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            },
+            DatabasePool::Sqlite(ref pool) => {
+                sqlx::query(sql)
+                    .bind(entry.id.to_string())
+                    .bind(entry.user_id.map(|id| id.to_string()))
+                    .bind(entry.tenant_id.map(|id| id.to_string()))
+                    .bind(&entry.action)
+                    .bind(&entry.resource_type)
+                    .bind(entry.resource_id.map(|id| id.to_string()))
+                    .bind(entry.details.to_string())
+                    .bind(&entry.ip_address)
+                    .bind(&entry.user_agent)
+                    .bind(sqlite_time(entry.created_at))
+                    .bind(entry.seq)
+                    .bind(&entry.prev_hash)
+                    .bind(&entry.entry_hash)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Format a time for storage in SQLite.
+fn sqlite_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+/// Read an entry from a PostgreSQL row.
+fn entry_from_pg_row(row: &sqlx::postgres::PgRow) -> orbis_core::Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: row.try_get("id").map_err(column_error)?,
+        user_id: row.try_get("actor").map_err(column_error)?,
+        tenant_id: row.try_get("tenant_id").map_err(column_error)?,
+        action: row.try_get("action").map_err(column_error)?,
+        resource_type: row.try_get("resource_type").map_err(column_error)?,
+        resource_id: row.try_get("resource_id").map_err(column_error)?,
+        details: row
+            .try_get::<Option<Value>, _>("details")
+            .map_err(column_error)?
+            .unwrap_or_default(),
+        ip_address: row.try_get("ip_address").map_err(column_error)?,
+        user_agent: row.try_get("user_agent").map_err(column_error)?,
+        created_at: row.try_get("created_at").map_err(column_error)?,
+        seq: row.try_get("seq").map_err(column_error)?,
+        prev_hash: row.try_get("prev_hash").map_err(column_error)?,
+        entry_hash: row.try_get("entry_hash").map_err(column_error)?,
+    })
+}
+
+/// Read an entry from a SQLite row.
+fn entry_from_sqlite_row(row: &sqlx::sqlite::SqliteRow) -> orbis_core::Result<AuditEntry> {
+    let id: String = row.try_get("id").map_err(column_error)?;
+    let user_id: Option<String> = row.try_get("actor").map_err(column_error)?;
+    let tenant_id: Option<String> = row.try_get("tenant_id").map_err(column_error)?;
+    let resource_id: Option<String> = row.try_get("resource_id").map_err(column_error)?;
+    let details: Option<String> = row.try_get("details").map_err(column_error)?;
+    let created_at: String = row.try_get("created_at").map_err(column_error)?;
+
+    Ok(AuditEntry {
+        id: id.parse().unwrap_or_default(),
+        user_id: user_id.and_then(|id| id.parse().ok()),
+        tenant_id: tenant_id.and_then(|id| id.parse().ok()),
+        action: row.try_get("action").map_err(column_error)?,
+        resource_type: row.try_get("resource_type").map_err(column_error)?,
+        resource_id: resource_id.and_then(|id| id.parse().ok()),
+        details: details
+            .and_then(|details| serde_json::from_str(&details).ok())
+            .unwrap_or_default(),
+        ip_address: row.try_get("ip_address").map_err(column_error)?,
+        user_agent: row.try_get("user_agent").map_err(column_error)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_default(),
+        seq: row.try_get("seq").map_err(column_error)?,
+        prev_hash: row.try_get("prev_hash").map_err(column_error)?,
+        entry_hash: row.try_get("entry_hash").map_err(column_error)?,
+    })
+}
+
+/// Convert a database error.
+fn column_error(e: sqlx::Error) -> orbis_core::Error {
+    orbis_core::Error::database(e.to_string())
+}
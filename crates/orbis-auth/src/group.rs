@@ -0,0 +1,348 @@
+//! User groups.
+//!
+//! Groups are assigned to users and nest: a group may have a parent, and the
+//! members of a group are members of all its ancestors too. The names of the
+//! groups a user is effectively a member of are carried in the `groups`
+//! claim of their access tokens, so plugin routes and pages can be
+//! restricted to groups without a database lookup on every request.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use orbis_core::UserId;
+use orbis_db::{Database, EntityRepository, Filter, Repository as _};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Maximum length of a group name.
+const MAX_GROUP_NAME_LEN: usize = 64;
+
+orbis_db::entity! {
+    table: "user_groups", id: id;
+
+    /// A group of users.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Group {
+        /// Group ID.
+        pub id: Uuid,
+
+        /// Tenant the group belongs to (multi-tenant deployments only).
+        pub tenant_id: Option<Uuid>,
+
+        /// Name, unique per tenant, as carried in the `groups` claim.
+        pub name: String,
+
+        /// Description.
+        pub description: Option<String>,
+
+        /// Parent group, whose membership the group's members share.
+        pub parent_id: Option<Uuid>,
+
+        /// Creation time.
+        pub created_at: DateTime<Utc>,
+
+        /// Last update time.
+        pub updated_at: DateTime<Utc>,
+    }
+}
+
+orbis_db::entity! {
+    table: "user_group_members", id: id;
+
+    /// Direct membership of a user in a group.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GroupMember {
+        /// Membership ID.
+        pub id: Uuid,
+
+        /// Group.
+        pub group_id: Uuid,
+
+        /// Member.
+        pub user_id: UserId,
+
+        /// When the user was added to the group.
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+/// Data for creating or updating a group.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupData {
+    /// Name (letters, digits, `-`, `_` and `.`).
+    pub name: String,
+
+    /// Description.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Parent group.
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+}
+
+impl GroupData {
+    /// Validate the group data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty, too long, or has other characters.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        let valid = !self.name.is_empty()
+            && self.name.len() <= MAX_GROUP_NAME_LEN
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+        if !valid {
+            return Err(orbis_core::Error::validation(format!(
+                "Group name must be 1-{} letters, digits, '-', '_' or '.'",
+                MAX_GROUP_NAME_LEN
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Group service managing groups and their members.
+#[derive(Clone)]
+pub struct GroupService {
+    /// Groups.
+    groups: EntityRepository<Group>,
+
+    /// Direct memberships.
+    members: EntityRepository<GroupMember>,
+}
+
+impl GroupService {
+    /// Create a new group service.
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self {
+            groups: EntityRepository::new(db.pool().clone()),
+            members: EntityRepository::new(db.pool().clone()),
+        }
+    }
+
+    /// List the groups of a tenant, by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn list(&self, tenant_id: Option<Uuid>) -> orbis_core::Result<Vec<Group>> {
+        self.groups
+            .find_where(&tenant_filter(tenant_id).order_by("name"))
+            .await
+    }
+
+    /// Find a group of a tenant by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn find(&self, id: Uuid, tenant_id: Option<Uuid>) -> orbis_core::Result<Option<Group>> {
+        Ok(self
+            .groups
+            .find_by_id(id)
+            .await?
+            .filter(|group| group.tenant_id == tenant_id))
+    }
+
+    /// Create a group in a tenant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid, the name is taken, or the
+    /// parent does not exist.
+    pub async fn create(&self, tenant_id: Option<Uuid>, data: GroupData) -> orbis_core::Result<Group> {
+        self.check(None, tenant_id, &data).await?;
+
+        let now = Utc::now();
+        self.groups
+            .create(&Group {
+                id: orbis_core::new_id(),
+                tenant_id,
+                name: data.name,
+                description: data.description,
+                parent_id: data.parent_id,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+    }
+
+    /// Update a group of a tenant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist, the data is invalid,
+    /// the name is taken, or the parent does not exist or is the group
+    /// itself or one of its descendants.
+    pub async fn update(&self, id: Uuid, tenant_id: Option<Uuid>, data: GroupData) -> orbis_core::Result<Group> {
+        let group = self
+            .find(id, tenant_id)
+            .await?
+            .ok_or_else(|| orbis_core::Error::not_found("Group not found"))?;
+        self.check(Some(id), tenant_id, &data).await?;
+
+        self.groups
+            .update(&Group {
+                name: data.name,
+                description: data.description,
+                parent_id: data.parent_id,
+                updated_at: Utc::now(),
+                ..group
+            })
+            .await
+    }
+
+    /// Delete a group of a tenant; its subgroups lose their parent.
+    ///
+    /// Returns `false` if the group does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deletion fails.
+    pub async fn delete(&self, id: Uuid, tenant_id: Option<Uuid>) -> orbis_core::Result<bool> {
+        if self.find(id, tenant_id).await?.is_none() {
+            return Ok(false);
+        }
+        self.groups.delete(id).await
+    }
+
+    /// Check the data of a new or updated group.
+    async fn check(&self, id: Option<Uuid>, tenant_id: Option<Uuid>, data: &GroupData) -> orbis_core::Result<()> {
+        data.validate()?;
+
+        let groups = self.list(tenant_id).await?;
+        if groups
+            .iter()
+            .any(|group| group.name == data.name && Some(group.id) != id)
+        {
+            return Err(orbis_core::Error::conflict(format!("Group '{}' already exists", data.name)));
+        }
+
+        let Some(parent_id) = data.parent_id else {
+            return Ok(());
+        };
+        let parents: HashMap<Uuid, Option<Uuid>> = groups.iter().map(|group| (group.id, group.parent_id)).collect();
+        if !parents.contains_key(&parent_id) {
+            return Err(orbis_core::Error::validation("Parent group not found"));
+        }
+
+        // The new parent must not be the group or one of its descendants
+        let mut ancestor = Some(parent_id);
+        let mut visited = HashSet::new();
+        while let Some(current) = ancestor.filter(|current| visited.insert(*current)) {
+            if Some(current) == id {
+                return Err(orbis_core::Error::validation("A group cannot be nested in itself"));
+            }
+            ancestor = parents.get(&current).copied().flatten();
+        }
+
+        Ok(())
+    }
+
+    /// List the direct members of a group.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn members(&self, group_id: Uuid) -> orbis_core::Result<Vec<UserId>> {
+        Ok(self
+            .members
+            .find_where(&Filter::new().equals("group_id", &group_id).order_by("created_at"))
+            .await?
+            .into_iter()
+            .map(|member| member.user_id)
+            .collect())
+    }
+
+    /// Add a user to a group.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user already is a direct member.
+    pub async fn add_member(&self, group_id: Uuid, user_id: UserId) -> orbis_core::Result<GroupMember> {
+        if self.members(group_id).await?.contains(&user_id) {
+            return Err(orbis_core::Error::conflict("User is already a member of the group"));
+        }
+
+        self.members
+            .create(&GroupMember {
+                id: orbis_core::new_id(),
+                group_id,
+                user_id,
+                created_at: Utc::now(),
+            })
+            .await
+    }
+
+    /// Remove a user from a group.
+    ///
+    /// Returns `false` if the user is not a direct member.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deletion fails.
+    pub async fn remove_member(&self, group_id: Uuid, user_id: UserId) -> orbis_core::Result<bool> {
+        let memberships = self
+            .members
+            .find_where(&Filter::new().equals("group_id", &group_id).equals("user_id", &user_id))
+            .await?;
+
+        let mut removed = false;
+        for membership in memberships {
+            removed |= self.members.delete(membership.id).await?;
+        }
+        Ok(removed)
+    }
+
+    /// Get the names of the groups a user of a tenant is a member of: those
+    /// they were added to and all their ancestors, by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn effective_groups(&self, user_id: UserId, tenant_id: Option<Uuid>) -> orbis_core::Result<Vec<String>> {
+        let direct = self
+            .members
+            .find_where(&Filter::new().equals("user_id", &user_id))
+            .await?;
+        if direct.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let groups: HashMap<Uuid, Group> = self
+            .list(tenant_id)
+            .await?
+            .into_iter()
+            .map(|group| (group.id, group))
+            .collect();
+
+        let mut visited = HashSet::new();
+        let mut names = Vec::new();
+        for membership in direct {
+            let mut current = Some(membership.group_id);
+            while let Some(group) = current
+                .filter(|id| visited.insert(*id))
+                .and_then(|id| groups.get(&id))
+            {
+                names.push(group.name.clone());
+                current = group.parent_id;
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Filter groups by tenant.
+fn tenant_filter(tenant_id: Option<Uuid>) -> Filter {
+    tenant_id.map_or_else(
+        || Filter::new().is_null("tenant_id"),
+        |tenant_id| Filter::new().equals("tenant_id", &tenant_id),
+    )
+}
@@ -0,0 +1,163 @@
+//! Admin impersonation of users.
+//!
+//! An admin can act as a user to reproduce issues only that user sees. The
+//! admin gets a short-lived access token for the user, marked with an
+//! `impersonator` claim; it cannot be refreshed, is checked against its
+//! impersonation record on every request so it can be revoked, and is kept
+//! from destructive operations by [`impersonation_allows`].
+
+use chrono::{DateTime, Utc};
+use orbis_db::{Column as _, Database, EntityRepository, Filter, Repository as _};
+use serde::{Deserialize, Serialize};
+use orbis_core::UserId;
+use uuid::Uuid;
+
+/// Path prefixes impersonation tokens can never write to: account,
+/// user, tenant and impersonation management.
+const PROTECTED_PREFIXES: &[&str] = &[
+    "/api/auth/",
+    "/api/users",
+    "/api/tenants",
+    "/api/impersonations",
+];
+
+orbis_db::entity! {
+    table: "impersonations", id: id;
+
+    /// An admin impersonating a user.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Impersonation {
+        /// Impersonation ID (the `jti` of the token).
+        pub id: Uuid,
+
+        /// Admin acting as the user.
+        pub admin_id: UserId,
+
+        /// Impersonated user.
+        pub user_id: UserId,
+
+        /// Tenant of both users (multi-tenant deployments only).
+        pub tenant_id: Option<Uuid>,
+
+        /// Why the admin impersonates the user.
+        pub reason: Option<String>,
+
+        /// When the token expires.
+        pub expires_at: DateTime<Utc>,
+
+        /// When the impersonation was revoked.
+        pub revoked_at: Option<DateTime<Utc>>,
+
+        /// Creation time.
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+impl Impersonation {
+    /// Check if the impersonation is neither revoked nor expired.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+/// Check if an impersonation token may make a request.
+///
+/// Reads are allowed. Deletes and writes to account, user, tenant and
+/// impersonation management are never allowed; other writes only if
+/// `allow_writes` is set.
+#[must_use]
+pub fn impersonation_allows(method: &str, path: &str, allow_writes: bool) -> bool {
+    if matches!(method, "GET" | "HEAD" | "OPTIONS") {
+        return true;
+    }
+    if method == "DELETE" || PROTECTED_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return false;
+    }
+    allow_writes
+}
+
+/// Impersonation service storing impersonation records.
+#[derive(Clone)]
+pub struct ImpersonationService {
+    /// Impersonation records.
+    repository: EntityRepository<Impersonation>,
+}
+
+impl ImpersonationService {
+    /// Create a new impersonation service.
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self {
+            repository: EntityRepository::new(db.pool().clone()),
+        }
+    }
+
+    /// Record the start of an impersonation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be created.
+    pub async fn create(
+        &self,
+        admin_id: UserId,
+        user_id: UserId,
+        tenant_id: Option<Uuid>,
+        reason: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> orbis_core::Result<Impersonation> {
+        self.repository
+            .create(&Impersonation {
+                id: orbis_core::new_id(),
+                admin_id,
+                user_id,
+                tenant_id,
+                reason: reason.map(String::from),
+                expires_at,
+                revoked_at: None,
+                created_at: Utc::now(),
+            })
+            .await
+    }
+
+    /// Find an impersonation by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn find(&self, id: Uuid) -> orbis_core::Result<Option<Impersonation>> {
+        self.repository.find_by_id(id).await
+    }
+
+    /// List impersonations that are neither revoked nor expired, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn list_active(&self) -> orbis_core::Result<Vec<Impersonation>> {
+        let mut impersonations = self
+            .repository
+            .find_where(&Filter::new().is_null("revoked_at").order_by_desc("created_at"))
+            .await?;
+        impersonations.retain(Impersonation::is_active);
+        Ok(impersonations)
+    }
+
+    /// Revoke an impersonation.
+    ///
+    /// Returns `false` if it does not exist or was already revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn revoke(&self, id: Uuid) -> orbis_core::Result<bool> {
+        let revoked = self
+            .repository
+            .update_where(
+                &[("revoked_at", Utc::now().to_value())],
+                &Filter::new().equals("id", &id).is_null("revoked_at"),
+            )
+            .await?;
+        Ok(revoked > 0)
+    }
+}
@@ -1,12 +1,15 @@
 //! JWT token handling.
 
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
 use orbis_config::Config;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use orbis_core::UserId;
 use uuid::Uuid;
 
+use crate::keys::JwtKeyRing;
 use crate::User;
 
 /// JWT claims structure.
@@ -24,6 +27,19 @@ pub struct Claims {
     /// Is admin.
     pub is_admin: bool,
 
+    /// Tenant ID (multi-tenant deployments only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+
+    /// ID of the admin impersonating the user (impersonation tokens only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+
+    /// Names of the groups the user is a member of, directly or through a
+    /// subgroup, when the token was issued.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+
     /// Token type (access or refresh).
     pub token_type: String,
 
@@ -43,9 +59,13 @@ pub struct Claims {
 /// JWT service for token generation and validation.
 #[derive(Clone)]
 pub struct JwtService {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    /// Keys tokens are signed with and verified against.
+    keys: Arc<JwtKeyRing>,
+
+    /// Lifetime of access tokens, in seconds.
     access_token_expiry: i64,
+
+    /// Lifetime of refresh tokens, in seconds.
     refresh_token_expiry: i64,
 }
 
@@ -54,26 +74,21 @@ impl JwtService {
     ///
     /// # Errors
     ///
-    /// Returns an error if the JWT secret is missing.
+    /// Returns an error if the JWT secret is missing or the keys file is invalid.
     pub fn new(config: Arc<Config>) -> orbis_core::Result<Self> {
-        let secret = config.jwt_secret.as_ref().ok_or_else(|| {
-            orbis_core::Error::config("JWT secret is required. Set ORBIS_JWT_SECRET")
-        })?;
-
         Ok(Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            keys: Arc::new(JwtKeyRing::from_config(&config)?),
             access_token_expiry: config.jwt_expiry_seconds as i64,
             refresh_token_expiry: (config.jwt_expiry_seconds as i64) * 24 * 7, // 7 days
         })
     }
 
-    /// Generate an access token for a user.
+    /// Generate an access token for a user and the groups they are a member of.
     ///
     /// # Errors
     ///
     /// Returns an error if token generation fails.
-    pub fn generate_access_token(&self, user: &User) -> orbis_core::Result<String> {
+    pub fn generate_access_token(&self, user: &User, groups: Vec<String>) -> orbis_core::Result<String> {
         let now = Utc::now();
         let exp = now + Duration::seconds(self.access_token_expiry);
 
@@ -82,15 +97,43 @@ impl JwtService {
             username: user.username.clone(),
             email: user.email.clone(),
             is_admin: user.is_admin,
-            token_type: "access".to_string(),
+            tenant_id: user.tenant_id.map(|id| id.to_string()),
+            impersonator: None,
+            groups,
+            token_type: "access".to_owned(),
             iat: now.timestamp(),
             exp: exp.timestamp(),
             nbf: now.timestamp(),
-            jti: Uuid::now_v7().to_string(),
+            jti: orbis_core::new_id().to_string(),
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key)
-            .map_err(|e| orbis_core::Error::auth(format!("Failed to generate token: {}", e)))
+        self.sign(&claims)
+    }
+
+    /// Build the claims of a request authenticated by a client certificate.
+    ///
+    /// The claims are never signed: certificates are verified on every
+    /// connection, so they only describe the user for one request. The
+    /// certificate fingerprint is used as the claims ID.
+    #[must_use]
+    pub fn certificate_claims(&self, user: &User, groups: Vec<String>, fingerprint: &str) -> Claims {
+        let now = Utc::now();
+        let exp = now + Duration::seconds(self.access_token_expiry);
+
+        Claims {
+            sub: user.id.to_string(),
+            username: user.username.clone(),
+            email: user.email.clone(),
+            is_admin: user.is_admin,
+            tenant_id: user.tenant_id.map(|id| id.to_string()),
+            impersonator: None,
+            groups,
+            token_type: "certificate".to_owned(),
+            iat: now.timestamp(),
+            exp: exp.timestamp(),
+            nbf: now.timestamp(),
+            jti: fingerprint.to_owned(),
+        }
     }
 
     /// Generate a refresh token for a user.
@@ -107,42 +150,97 @@ impl JwtService {
             username: user.username.clone(),
             email: user.email.clone(),
             is_admin: user.is_admin,
-            token_type: "refresh".to_string(),
+            tenant_id: user.tenant_id.map(|id| id.to_string()),
+            impersonator: None,
+            groups: Vec::new(),
+            token_type: "refresh".to_owned(),
             iat: now.timestamp(),
             exp: exp.timestamp(),
             nbf: now.timestamp(),
-            jti: Uuid::now_v7().to_string(),
+            jti: orbis_core::new_id().to_string(),
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key)
+        self.sign(&claims)
+    }
+
+    /// Generate an access token letting an admin act as a user.
+    ///
+    /// The token carries the admin's ID in the `impersonator` claim and the
+    /// impersonation ID as its `jti`, and expires at `expires_at`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if token generation fails.
+    pub fn generate_impersonation_token(
+        &self,
+        user: &User,
+        groups: Vec<String>,
+        impersonator_id: UserId,
+        impersonation_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> orbis_core::Result<String> {
+        let now = Utc::now();
+
+        let claims = Claims {
+            sub: user.id.to_string(),
+            username: user.username.clone(),
+            email: user.email.clone(),
+            is_admin: user.is_admin,
+            tenant_id: user.tenant_id.map(|id| id.to_string()),
+            impersonator: Some(impersonator_id.to_string()),
+            groups,
+            token_type: "access".to_owned(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+            nbf: now.timestamp(),
+            jti: impersonation_id.to_string(),
+        };
+
+        self.sign(&claims)
+    }
+
+    /// Sign claims with the current signing key.
+    fn sign(&self, claims: &Claims) -> orbis_core::Result<String> {
+        let key = self.keys.signing_key(Utc::now())?;
+        let mut header = Header::new(key.algorithm());
+        header.kid = Some(key.kid().to_owned());
+
+        encode(&header, claims, key.encoding()?)
             .map_err(|e| orbis_core::Error::auth(format!("Failed to generate token: {}", e)))
     }
 
     /// Validate a token and return the claims.
     ///
+    /// The token must be signed by the key named in its `kid` header, which
+    /// must be active or retired within the grace window.
+    ///
     /// # Errors
     ///
     /// Returns an error if the token is invalid.
     pub fn validate_token(&self, token: &str) -> orbis_core::Result<Claims> {
-        let validation = Validation::default();
+        let header = decode_header(token).map_err(|e| orbis_core::Error::auth(format!("Invalid token: {}", e)))?;
+        let key = self.keys.verification_key(header.kid.as_deref(), Utc::now())?;
+        let validation = Validation::new(key.algorithm());
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)
+        let token_data = decode::<Claims>(token, key.decoding(), &validation)
             .map_err(|e| orbis_core::Error::auth(format!("Invalid token: {}", e)))?;
 
         Ok(token_data.claims)
     }
 
+    /// Get the public keys external services can validate tokens with.
+    #[must_use]
+    pub fn jwks(&self) -> JwkSet {
+        self.keys.jwks(Utc::now())
+    }
+
     /// Decode a token without validation (for debugging).
     ///
     /// # Errors
     ///
     /// Returns an error if the token cannot be decoded.
     pub fn decode_without_validation(&self, token: &str) -> orbis_core::Result<Claims> {
-        let mut validation = Validation::default();
-        validation.insecure_disable_signature_validation();
-        validation.validate_exp = false;
-
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)
+        let token_data = jsonwebtoken::dangerous::insecure_decode::<Claims>(token)
             .map_err(|e| orbis_core::Error::auth(format!("Invalid token: {}", e)))?;
 
         Ok(token_data.claims)
@@ -0,0 +1,643 @@
+//! JWT signing keys and their rotation schedule.
+//!
+//! Every token is signed with the most recently activated key and carries
+//! its ID in the `kid` header. When the next key activates, the previous one
+//! retires: tokens it signed are still accepted for the grace window, so
+//! sessions survive a rotation.
+//!
+//! Keys are HMAC secrets (`HS256`) or RSA (`RS256`) and Ed25519 (`EdDSA`)
+//! key pairs. The public half of key pairs is published in the JWKS, so
+//! other services can validate tokens without being able to sign them. A
+//! key pair with only a public key validates tokens but cannot sign.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::pkcs8::{DecodePrivateKey as _, DecodePublicKey as _};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters,
+    OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use orbis_config::Config;
+use rsa::pkcs1::{DecodeRsaPrivateKey as _, DecodeRsaPublicKey as _};
+use rsa::traits::PublicKeyParts as _;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// ID of the key configured by `jwt_secret` or the JWT key files. Tokens
+/// without a `kid` header were issued before key rotation and are verified
+/// with it.
+pub const DEFAULT_KEY_ID: &str = "default";
+
+/// Clock skew tolerated when checking a key is already active.
+const ACTIVATION_LEEWAY_SECONDS: i64 = 60;
+
+/// Parse a supported JWT signing algorithm (`HS256`, `RS256` or `EdDSA`).
+///
+/// # Errors
+///
+/// Returns an error if the algorithm is not supported.
+pub fn parse_algorithm(name: &str) -> orbis_core::Result<Algorithm> {
+    match name {
+        "HS256" => Ok(Algorithm::HS256),
+        "RS256" => Ok(Algorithm::RS256),
+        "EdDSA" => Ok(Algorithm::EdDSA),
+        _ => Err(orbis_core::Error::config(format!(
+            "Unsupported JWT algorithm '{}'. Use HS256, RS256 or EdDSA",
+            name
+        ))),
+    }
+}
+
+/// A signing key in the JWT keys file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtKeyConfig {
+    /// Key ID, sent as the `kid` header of the tokens it signs.
+    pub kid: String,
+
+    /// Signing algorithm (`HS256`, `RS256` or `EdDSA`).
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+
+    /// HMAC secret (`HS256` only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// PEM private key file, relative to the keys file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key_file: Option<PathBuf>,
+
+    /// PEM public key file, relative to the keys file. Derived from the
+    /// private key if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key_file: Option<PathBuf>,
+
+    /// When the key becomes the signing key (immediately if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activate_at: Option<DateTime<Utc>>,
+}
+
+/// Default key algorithm.
+fn default_algorithm() -> String {
+    "HS256".to_owned()
+}
+
+/// JWT keys file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JwtKeysFile {
+    /// Signing keys, in any order.
+    pub keys: Vec<JwtKeyConfig>,
+}
+
+impl JwtKeysFile {
+    /// Load a keys file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> orbis_core::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            orbis_core::Error::config(format!("Failed to read JWT keys file {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            orbis_core::Error::config(format!("Failed to parse JWT keys file {}: {}", path.display(), e))
+        })
+    }
+}
+
+/// A signing key.
+#[derive(Clone)]
+pub struct JwtKey {
+    /// Key ID.
+    kid: String,
+
+    /// Signing algorithm.
+    algorithm: Algorithm,
+
+    /// Key signing tokens (`None` for verification-only keys).
+    encoding: Option<EncodingKey>,
+
+    /// Key verifying tokens.
+    decoding: DecodingKey,
+
+    /// When the key becomes the signing key.
+    activate_at: DateTime<Utc>,
+
+    /// When the next key replaces it.
+    retire_at: Option<DateTime<Utc>>,
+
+    /// Public key published in the JWKS (`None` for secret keys).
+    public_jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+    /// Create an HMAC key.
+    #[must_use]
+    pub fn hmac(kid: &str, secret: &[u8], activate_at: DateTime<Utc>) -> Self {
+        Self {
+            kid: kid.to_owned(),
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret)),
+            decoding: DecodingKey::from_secret(secret),
+            activate_at,
+            retire_at: None,
+            public_jwk: None,
+        }
+    }
+
+    /// Create an RSA key from PEM (PKCS#1 or PKCS#8) keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither key is given, a key is invalid, or the
+    /// keys do not belong together.
+    pub fn rsa(
+        kid: &str,
+        private_pem: Option<&str>,
+        public_pem: Option<&str>,
+        activate_at: DateTime<Utc>,
+    ) -> orbis_core::Result<Self> {
+        let private = private_pem
+            .map(|pem| {
+                if pem.contains("BEGIN RSA PRIVATE KEY") {
+                    RsaPrivateKey::from_pkcs1_pem(pem).map_err(|e| e.to_string())
+                } else {
+                    RsaPrivateKey::from_pkcs8_pem(pem).map_err(|e| e.to_string())
+                }
+            })
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e))?;
+        let public = public_pem
+            .map(|pem| {
+                if pem.contains("BEGIN RSA PUBLIC KEY") {
+                    RsaPublicKey::from_pkcs1_pem(pem).map_err(|e| e.to_string())
+                } else {
+                    RsaPublicKey::from_public_key_pem(pem).map_err(|e| e.to_string())
+                }
+            })
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e))?;
+
+        let public = match (&private, public) {
+            (Some(private), Some(public)) if private.to_public_key() != public => {
+                return Err(invalid_key(kid, "the public key does not match the private key"));
+            }
+            (_, Some(public)) => public,
+            (Some(private), None) => private.to_public_key(),
+            (None, None) => return Err(invalid_key(kid, "a private or public key is required")),
+        };
+
+        let encoding = private_pem
+            .map(|pem| EncodingKey::from_rsa_pem(pem.as_bytes()))
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e.to_string()))?;
+        let n = public.n().to_bytes_be();
+        let e = public.e().to_bytes_be();
+
+        Ok(Self {
+            kid: kid.to_owned(),
+            algorithm: Algorithm::RS256,
+            encoding,
+            decoding: DecodingKey::from_rsa_raw_components(&n, &e),
+            activate_at,
+            retire_at: None,
+            public_jwk: Some(public_jwk(
+                kid,
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n),
+                    e: URL_SAFE_NO_PAD.encode(e),
+                }),
+            )),
+        })
+    }
+
+    /// Create an Ed25519 key from PEM (PKCS#8 and SPKI) keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither key is given, a key is invalid, or the
+    /// keys do not belong together.
+    pub fn ed25519(
+        kid: &str,
+        private_pem: Option<&str>,
+        public_pem: Option<&str>,
+        activate_at: DateTime<Utc>,
+    ) -> orbis_core::Result<Self> {
+        let private = private_pem
+            .map(ed25519_dalek::SigningKey::from_pkcs8_pem)
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e.to_string()))?;
+        let public = public_pem
+            .map(ed25519_dalek::VerifyingKey::from_public_key_pem)
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e.to_string()))?;
+
+        let public = match (&private, public) {
+            (Some(private), Some(public)) if private.verifying_key() != public => {
+                return Err(invalid_key(kid, "the public key does not match the private key"));
+            }
+            (_, Some(public)) => public,
+            (Some(private), None) => private.verifying_key(),
+            (None, None) => return Err(invalid_key(kid, "a private or public key is required")),
+        };
+
+        let encoding = private_pem
+            .map(|pem| EncodingKey::from_ed_pem(pem.as_bytes()))
+            .transpose()
+            .map_err(|e| invalid_key(kid, &e.to_string()))?;
+
+        Ok(Self {
+            kid: kid.to_owned(),
+            algorithm: Algorithm::EdDSA,
+            encoding,
+            decoding: DecodingKey::from_ed_der(public.as_bytes()),
+            activate_at,
+            retire_at: None,
+            public_jwk: Some(public_jwk(
+                kid,
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(public.as_bytes()),
+                }),
+            )),
+        })
+    }
+
+    /// Create a key pair from PEM key files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the algorithm is not a key pair algorithm, or a
+    /// file cannot be read or holds an invalid key.
+    pub fn from_files(
+        kid: &str,
+        algorithm: Algorithm,
+        private_key_file: Option<&Path>,
+        public_key_file: Option<&Path>,
+        activate_at: DateTime<Utc>,
+    ) -> orbis_core::Result<Self> {
+        let private = private_key_file.map(read_key_file).transpose()?;
+        let public = public_key_file.map(read_key_file).transpose()?;
+
+        if algorithm == Algorithm::RS256 {
+            Self::rsa(kid, private.as_deref(), public.as_deref(), activate_at)
+        } else if algorithm == Algorithm::EdDSA {
+            Self::ed25519(kid, private.as_deref(), public.as_deref(), activate_at)
+        } else {
+            Err(invalid_key(kid, "key files are only used by RS256 and EdDSA keys"))
+        }
+    }
+
+    /// Create a key from a keys file entry.
+    fn from_config(key: &JwtKeyConfig, dir: &Path) -> orbis_core::Result<Self> {
+        if key.kid.is_empty() {
+            return Err(orbis_core::Error::config("JWT keys need a kid"));
+        }
+
+        let activate_at = key.activate_at.unwrap_or(DateTime::UNIX_EPOCH);
+        let algorithm = parse_algorithm(&key.algorithm)?;
+        if algorithm != Algorithm::HS256 {
+            return Self::from_files(
+                &key.kid,
+                algorithm,
+                key.private_key_file.as_ref().map(|path| dir.join(path)).as_deref(),
+                key.public_key_file.as_ref().map(|path| dir.join(path)).as_deref(),
+                activate_at,
+            );
+        }
+
+        let secret = key
+            .secret
+            .as_deref()
+            .filter(|secret| !secret.is_empty())
+            .ok_or_else(|| invalid_key(&key.kid, "HS256 keys need a secret"))?;
+        Ok(Self::hmac(&key.kid, secret.as_bytes(), activate_at))
+    }
+
+    /// Get the key ID.
+    #[must_use]
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// Get the signing algorithm.
+    #[must_use]
+    pub const fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Get the key signing tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key has no private key.
+    pub fn encoding(&self) -> orbis_core::Result<&EncodingKey> {
+        self.encoding.as_ref().ok_or_else(|| {
+            orbis_core::Error::auth(format!("JWT key '{}' has no private key and cannot sign tokens", self.kid))
+        })
+    }
+
+    /// Get the key verifying tokens.
+    #[must_use]
+    pub const fn decoding(&self) -> &DecodingKey {
+        &self.decoding
+    }
+
+    /// Check if tokens signed by the key are accepted at a time.
+    fn accepts_at(&self, now: DateTime<Utc>, grace: Duration) -> bool {
+        self.activate_at - Duration::seconds(ACTIVATION_LEEWAY_SECONDS) <= now
+            && self.retire_at.is_none_or(|retire_at| now < retire_at + grace)
+    }
+}
+
+/// Build the JWKS entry of a public key.
+fn public_jwk(kid: &str, algorithm: KeyAlgorithm, parameters: AlgorithmParameters) -> Jwk {
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(algorithm),
+            key_id: Some(kid.to_owned()),
+            ..CommonParameters::default()
+        },
+        algorithm: parameters,
+    }
+}
+
+/// Read a PEM key file.
+fn read_key_file(path: &Path) -> orbis_core::Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| orbis_core::Error::config(format!("Failed to read JWT key file {}: {}", path.display(), e)))
+}
+
+/// Error for an invalid key.
+fn invalid_key(kid: &str, reason: &str) -> orbis_core::Error {
+    orbis_core::Error::config(format!("Invalid JWT key '{}': {}", kid, reason))
+}
+
+/// The signing keys of a deployment, ordered by activation.
+#[derive(Clone)]
+pub struct JwtKeyRing {
+    /// Keys, oldest first.
+    keys: Vec<JwtKey>,
+
+    /// How long tokens signed by a retired key are still accepted.
+    grace: Duration,
+}
+
+impl JwtKeyRing {
+    /// Create a key ring, retiring each key when the next one activates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no keys or two keys share an ID.
+    pub fn new(mut keys: Vec<JwtKey>, grace: Duration) -> orbis_core::Result<Self> {
+        if keys.is_empty() {
+            return Err(orbis_core::Error::config(
+                "JWT secret is required. Set ORBIS_JWT_SECRET, ORBIS_JWT_PRIVATE_KEY_FILE or ORBIS_JWT_KEYS_FILE",
+            ));
+        }
+
+        let mut kids = HashSet::new();
+        if let Some(key) = keys.iter().find(|key| !kids.insert(key.kid.as_str())) {
+            return Err(orbis_core::Error::config(format!("Duplicate JWT key ID '{}'", key.kid)));
+        }
+
+        keys.sort_by_key(|key| key.activate_at);
+        let activations: Vec<_> = keys.iter().skip(1).map(|key| key.activate_at).collect();
+        for (key, next) in keys.iter_mut().zip(activations) {
+            key.retire_at = Some(next);
+        }
+
+        Ok(Self { keys, grace })
+    }
+
+    /// Create the key ring of a configuration.
+    ///
+    /// The key configured by `jwt_algorithm` (the `jwt_secret`, or the
+    /// `jwt_private_key_file` and `jwt_public_key_file` key pair) is the
+    /// `default` key, active from the start; keys from `jwt_keys_file` take
+    /// over as they activate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no keys or a key is invalid.
+    pub fn from_config(config: &Config) -> orbis_core::Result<Self> {
+        let mut keys = Vec::new();
+        let algorithm = parse_algorithm(&config.jwt_algorithm)?;
+        if algorithm == Algorithm::HS256 {
+            if let Some(secret) = &config.jwt_secret {
+                keys.push(JwtKey::hmac(DEFAULT_KEY_ID, secret.as_bytes(), DateTime::UNIX_EPOCH));
+            }
+        } else if config.jwt_private_key_file.is_some() || config.jwt_public_key_file.is_some() {
+            keys.push(JwtKey::from_files(
+                DEFAULT_KEY_ID,
+                algorithm,
+                config.jwt_private_key_file.as_deref(),
+                config.jwt_public_key_file.as_deref(),
+                DateTime::UNIX_EPOCH,
+            )?);
+        }
+
+        if let Some(path) = &config.jwt_keys_file {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            for key in JwtKeysFile::load(path)?.keys {
+                keys.push(JwtKey::from_config(&key, dir)?);
+            }
+        }
+
+        let grace = Duration::seconds(i64::try_from(config.jwt_key_grace_seconds).unwrap_or(i64::MAX));
+        Self::new(keys, grace)
+    }
+
+    /// Get the key tokens are signed with at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no key has activated yet.
+    pub fn signing_key(&self, now: DateTime<Utc>) -> orbis_core::Result<&JwtKey> {
+        self.keys
+            .iter()
+            .rev()
+            .find(|key| key.activate_at <= now)
+            .ok_or_else(|| orbis_core::Error::auth("No JWT signing key is active yet"))
+    }
+
+    /// Get the key verifying a token with a `kid` header at a time.
+    ///
+    /// Tokens without a `kid` are verified with the `default` key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is unknown, not active yet, or retired
+    /// longer than the grace window.
+    pub fn verification_key(&self, kid: Option<&str>, now: DateTime<Utc>) -> orbis_core::Result<&JwtKey> {
+        let kid = kid.unwrap_or(DEFAULT_KEY_ID);
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.kid == kid)
+            .ok_or_else(|| orbis_core::Error::auth(format!("Unknown JWT key '{}'", kid)))?;
+
+        if !key.accepts_at(now, self.grace) {
+            return Err(orbis_core::Error::auth(format!("JWT key '{}' is not accepted", kid)));
+        }
+        Ok(key)
+    }
+
+    /// Get the public keys external services can verify tokens with.
+    ///
+    /// Lists the keys that are accepted or not active yet, so services can
+    /// fetch a key before it starts signing. Secret keys are never listed.
+    #[must_use]
+    pub fn jwks(&self, now: DateTime<Utc>) -> JwkSet {
+        JwkSet {
+            keys: self
+                .keys
+                .iter()
+                .filter(|key| key.activate_at > now || key.accepts_at(now, self.grace))
+                .filter_map(|key| key.public_jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey as _, EncodePublicKey as _};
+
+    /// Time the `next` key of the test rings activates.
+    fn rotation() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    /// Create an Ed25519 key pair from a seed, as PKCS#8 and SPKI PEM.
+    fn ed25519_pems(seed: u8) -> (String, String) {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]);
+        let private = key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string();
+        let public = key.verifying_key().to_public_key_pem(LineEnding::LF).unwrap();
+        (private, public)
+    }
+
+    /// Get the message of an error result (empty for success).
+    fn error_of<T>(result: orbis_core::Result<T>) -> String {
+        result.err().map(|e| e.to_string()).unwrap_or_default()
+    }
+
+    /// Create a ring rotating from an HMAC `default` key to an Ed25519 `next` key.
+    fn ring(grace: Duration) -> JwtKeyRing {
+        let (private, _) = ed25519_pems(7);
+        JwtKeyRing::new(
+            vec![
+                JwtKey::ed25519("next", Some(&private), None, rotation()).unwrap(),
+                JwtKey::hmac(DEFAULT_KEY_ID, b"top-secret", DateTime::UNIX_EPOCH),
+            ],
+            grace,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_signing_key_at_activation() {
+        let ring = ring(Duration::hours(1));
+        let second = Duration::seconds(1);
+
+        assert_eq!(ring.signing_key(rotation() - second).unwrap().kid(), DEFAULT_KEY_ID);
+        assert_eq!(ring.signing_key(rotation()).unwrap().kid(), "next");
+        assert_eq!(ring.signing_key(rotation()).unwrap().algorithm(), Algorithm::EdDSA);
+        assert!(error_of(ring.signing_key(DateTime::UNIX_EPOCH - second)).contains("No JWT signing key"));
+
+        assert!(error_of(JwtKeyRing::new(Vec::new(), Duration::zero())).contains("JWT secret is required"));
+        let duplicate = vec![
+            JwtKey::hmac("a", b"one", DateTime::UNIX_EPOCH),
+            JwtKey::hmac("a", b"two", rotation()),
+        ];
+        assert!(error_of(JwtKeyRing::new(duplicate, Duration::zero())).contains("Duplicate JWT key ID 'a'"));
+    }
+
+    #[test]
+    fn test_retired_key_grace() {
+        let ring = ring(Duration::hours(1));
+
+        // Tokens without a kid are verified with the default key
+        assert_eq!(ring.verification_key(None, rotation()).unwrap().kid(), DEFAULT_KEY_ID);
+
+        // The retired key is accepted for the grace window only
+        let within = rotation() + Duration::minutes(59);
+        let after = rotation() + Duration::hours(1);
+        ring.verification_key(Some(DEFAULT_KEY_ID), within).unwrap();
+        assert!(error_of(ring.verification_key(Some(DEFAULT_KEY_ID), after)).contains("is not accepted"));
+        ring.verification_key(Some("next"), after).unwrap();
+
+        // The next key is accepted shortly before it activates, to allow for clock skew
+        ring.verification_key(Some("next"), rotation() - Duration::seconds(30)).unwrap();
+        let early = rotation() - Duration::minutes(2);
+        assert!(error_of(ring.verification_key(Some("next"), early)).contains("is not accepted"));
+        assert!(error_of(ring.verification_key(Some("unknown"), rotation())).contains("Unknown JWT key"));
+    }
+
+    #[test]
+    fn test_jwks_lists_public_keys_only() {
+        let ring = ring(Duration::hours(1));
+        let (private, public) = ed25519_pems(7);
+        let (_, other_public) = ed25519_pems(8);
+
+        // The HMAC key is never published; the next key is, before it activates
+        let jwks = ring.jwks(rotation() - Duration::days(1));
+        assert_eq!(jwks.keys.len(), 1);
+        let jwk = serde_json::to_value(jwks.keys.first().unwrap()).unwrap();
+        assert_eq!(jwk.get("kid"), Some(&serde_json::json!("next")));
+        assert_eq!(jwk.get("kty"), Some(&serde_json::json!("OKP")));
+        assert_eq!(jwk.get("crv"), Some(&serde_json::json!("Ed25519")));
+        assert_eq!(jwk.get("use"), Some(&serde_json::json!("sig")));
+        assert!(jwk.get("d").is_none());
+
+        let serialized = serde_json::to_string(&jwks).unwrap();
+        assert!(!serialized.contains("top-secret"));
+        assert!(!serialized.contains(&URL_SAFE_NO_PAD.encode([7; 32])));
+
+        // Tokens signed with the private key verify with the published key
+        let key = ring.signing_key(rotation()).unwrap();
+        let mut header = jsonwebtoken::Header::new(key.algorithm());
+        header.kid = Some(key.kid().to_string());
+        let claims = serde_json::json!({ "sub": "user", "exp": 4_000_000_000u64 });
+        let token = jsonwebtoken::encode(&header, &claims, key.encoding().unwrap()).unwrap();
+        let published = DecodingKey::from_jwk(jwks.keys.first().unwrap()).unwrap();
+        let validation = jsonwebtoken::Validation::new(Algorithm::EdDSA);
+        jsonwebtoken::decode::<serde_json::Value>(&token, &published, &validation).unwrap();
+
+        // A key pair with only a public key verifies but cannot sign
+        let verifying = JwtKey::ed25519("public", None, Some(&public), rotation()).unwrap();
+        assert!(error_of(verifying.encoding()).contains("cannot sign"));
+        let mismatch = JwtKey::ed25519("mismatch", Some(&private), Some(&other_public), rotation());
+        assert!(error_of(mismatch).contains("does not match"));
+    }
+
+    #[test]
+    fn test_rsa_jwk_is_public() {
+        use rsa::pkcs8::EncodePrivateKey as _;
+
+        // A short key keeps the test fast; only the published parameters are checked
+        let private = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap();
+        let pem = private.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let key = JwtKey::rsa("rsa", Some(&pem), None, DateTime::UNIX_EPOCH).unwrap();
+        let ring = JwtKeyRing::new(vec![key], Duration::zero()).unwrap();
+
+        let jwks = ring.jwks(rotation());
+        let jwk = serde_json::to_value(jwks.keys.first().unwrap()).unwrap();
+        assert_eq!(jwk.get("kty"), Some(&serde_json::json!("RSA")));
+        assert_eq!(jwk.get("alg"), Some(&serde_json::json!("RS256")));
+        assert_eq!(jwk.get("e"), Some(&serde_json::json!("AQAB")));
+        assert!(jwk.get("n").is_some());
+        for private_field in ["d", "p", "q", "dp", "dq", "qi"] {
+            assert!(jwk.get(private_field).is_none(), "JWKS exposes '{}'", private_field);
+        }
+    }
+}
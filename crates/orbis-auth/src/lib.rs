@@ -3,26 +3,48 @@
 //! Authentication and authorization for Orbis.
 //! Provides JWT-based authentication, password hashing, and session management.
 
+mod account;
+mod audit;
+mod group;
+mod impersonation;
 mod jwt;
+mod keys;
 mod password;
+mod seed;
 mod session;
+mod tenant;
 mod user;
 
+pub use account::AccountService;
+pub use audit::{AuditEntry, AuditFilter, AuditService, ChainVerification, NewAuditEntry};
+pub use group::{Group, GroupData, GroupMember, GroupService};
+pub use impersonation::{impersonation_allows, Impersonation, ImpersonationService};
 pub use jwt::{Claims, JwtService};
+pub use keys::{parse_algorithm, JwtKey, JwtKeyConfig, JwtKeyRing, JwtKeysFile, DEFAULT_KEY_ID};
 pub use password::PasswordService;
+pub use seed::{UserSeeds, SEED_PASSWORD};
 pub use session::{Session, SessionService};
+pub use tenant::{CreateTenant, Tenant, TenantService};
 pub use user::{CreateUser, User, UserService};
 
+use chrono::{Duration, Utc};
 use orbis_config::Config;
 use orbis_db::Database;
 use std::sync::Arc;
+use orbis_core::UserId;
+use uuid::Uuid;
 
 /// Authentication service combining all auth functionality.
 #[derive(Clone)]
 pub struct AuthService {
+    account: AccountService,
+    audit: AuditService,
+    group: GroupService,
+    impersonation: ImpersonationService,
     jwt: JwtService,
     password: PasswordService,
     session: SessionService,
+    tenant: TenantService,
     user: UserService,
     config: Arc<Config>,
 }
@@ -36,18 +58,52 @@ impl AuthService {
     pub fn new(config: Arc<Config>, db: Database) -> orbis_core::Result<Self> {
         let jwt = JwtService::new(config.clone())?;
         let password = PasswordService::new();
+        let account = AccountService::new(db.clone());
+        let audit = AuditService::new(db.clone());
+        let group = GroupService::new(db.clone());
+        let impersonation = ImpersonationService::new(db.clone());
         let session = SessionService::new(db.clone());
+        let tenant = TenantService::new(db.clone());
         let user = UserService::new(db);
 
         Ok(Self {
+            account,
+            audit,
+            group,
+            impersonation,
             jwt,
             password,
             session,
+            tenant,
             user,
             config,
         })
     }
 
+    /// Get the account service.
+    #[must_use]
+    pub const fn account(&self) -> &AccountService {
+        &self.account
+    }
+
+    /// Get the audit service.
+    #[must_use]
+    pub const fn audit(&self) -> &AuditService {
+        &self.audit
+    }
+
+    /// Get the group service.
+    #[must_use]
+    pub const fn group(&self) -> &GroupService {
+        &self.group
+    }
+
+    /// Get the impersonation service.
+    #[must_use]
+    pub const fn impersonation(&self) -> &ImpersonationService {
+        &self.impersonation
+    }
+
     /// Get the JWT service.
     #[must_use]
     pub const fn jwt(&self) -> &JwtService {
@@ -66,6 +122,12 @@ impl AuthService {
         &self.session
     }
 
+    /// Get the tenant service.
+    #[must_use]
+    pub const fn tenant(&self) -> &TenantService {
+        &self.tenant
+    }
+
     /// Get the user service.
     #[must_use]
     pub const fn user(&self) -> &UserService {
@@ -78,7 +140,7 @@ impl AuthService {
         self.config.auth_enabled || self.config.mode.requires_auth()
     }
 
-    /// Authenticate a user with username/email and password.
+    /// Authenticate a user of a tenant with username/email and password.
     ///
     /// # Errors
     ///
@@ -87,13 +149,14 @@ impl AuthService {
         &self,
         username_or_email: &str,
         password: &str,
+        tenant_id: Option<Uuid>,
         user_agent: Option<&str>,
         ip_address: Option<&str>,
     ) -> orbis_core::Result<AuthResult> {
         // Find user
         let user = self
             .user
-            .find_by_username_or_email(username_or_email)
+            .find_by_username_or_email(username_or_email, tenant_id)
             .await?
             .ok_or_else(|| orbis_core::Error::auth("Invalid credentials"))?;
 
@@ -108,7 +171,8 @@ impl AuthService {
         }
 
         // Generate tokens
-        let access_token = self.jwt.generate_access_token(&user)?;
+        let groups = self.group.effective_groups(user.id, user.tenant_id).await?;
+        let access_token = self.jwt.generate_access_token(&user, groups)?;
         let refresh_token = self.jwt.generate_refresh_token(&user)?;
 
         // Create session
@@ -116,6 +180,7 @@ impl AuthService {
             .session
             .create(
                 user.id,
+                user.tenant_id,
                 &refresh_token,
                 user_agent,
                 ip_address,
@@ -132,12 +197,12 @@ impl AuthService {
         })
     }
 
-    /// Refresh an access token using a refresh token.
+    /// Refresh an access token using a refresh token issued for the given tenant.
     ///
     /// # Errors
     ///
-    /// Returns an error if the refresh token is invalid.
-    pub async fn refresh(&self, refresh_token: &str) -> orbis_core::Result<AuthResult> {
+    /// Returns an error if the refresh token is invalid or belongs to another tenant.
+    pub async fn refresh(&self, refresh_token: &str, tenant_id: Option<Uuid>) -> orbis_core::Result<AuthResult> {
         // Validate refresh token
         let claims = self.jwt.validate_token(refresh_token)?;
 
@@ -152,6 +217,10 @@ impl AuthService {
             .await?
             .ok_or_else(|| orbis_core::Error::auth("Session not found"))?;
 
+        if session.tenant_id != tenant_id {
+            return Err(orbis_core::Error::auth("Session not found"));
+        }
+
         // Check if session is valid
         if session.is_expired() {
             self.session.delete(session.id).await?;
@@ -169,8 +238,9 @@ impl AuthService {
             return Err(orbis_core::Error::auth("Account is disabled"));
         }
 
-        // Generate new access token
-        let access_token = self.jwt.generate_access_token(&user)?;
+        // Generate new access token, with the user's current groups
+        let groups = self.group.effective_groups(user.id, user.tenant_id).await?;
+        let access_token = self.jwt.generate_access_token(&user, groups)?;
 
         Ok(AuthResult {
             user,
@@ -193,6 +263,173 @@ impl AuthService {
         Ok(())
     }
 
+    /// Let an admin act as a user of the same tenant.
+    ///
+    /// Issues an access token for the user marked with the admin as its
+    /// impersonator. It expires after the configured impersonation limit,
+    /// cannot be refreshed, and is recorded in the audit log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the admin is not an active admin, the user does
+    /// not exist, is an admin, or belongs to another tenant.
+    pub async fn impersonate(
+        &self,
+        admin: &User,
+        target_user: UserId,
+        reason: Option<&str>,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> orbis_core::Result<ImpersonationResult> {
+        if !admin.is_admin || !admin.is_active {
+            return Err(orbis_core::Error::unauthorized("Only active admins can impersonate users"));
+        }
+        if admin.id == target_user {
+            return Err(orbis_core::Error::validation("Admins cannot impersonate themselves"));
+        }
+
+        let user = self
+            .user
+            .find_by_id(target_user)
+            .await?
+            .filter(|user| user.tenant_id == admin.tenant_id)
+            .ok_or_else(|| orbis_core::Error::not_found("User not found"))?;
+        if user.is_admin {
+            return Err(orbis_core::Error::unauthorized("Admins cannot be impersonated"));
+        }
+        if !user.is_active {
+            return Err(orbis_core::Error::validation("Account is disabled"));
+        }
+
+        let minutes = i64::try_from(self.config.impersonation_max_minutes).unwrap_or(i64::MAX);
+        let expires_at = Utc::now() + Duration::minutes(minutes);
+        let impersonation = self
+            .impersonation
+            .create(admin.id, user.id, user.tenant_id, reason, expires_at)
+            .await?;
+        let groups = self.group.effective_groups(user.id, user.tenant_id).await?;
+        let access_token =
+            self.jwt
+                .generate_impersonation_token(&user, groups, admin.id, impersonation.id, expires_at)?;
+
+        self.audit
+            .record(NewAuditEntry {
+                ip_address: ip_address.map(String::from),
+                user_agent: user_agent.map(String::from),
+                ..NewAuditEntry::new(admin.id, "impersonation.started", "impersonation", impersonation.id).details(
+                    serde_json::json!({
+                        "user_id": user.id,
+                        "username": user.username,
+                        "reason": reason,
+                        "expires_at": expires_at,
+                    }),
+                )
+            })
+            .await?;
+
+        Ok(ImpersonationResult {
+            user,
+            access_token,
+            impersonation,
+        })
+    }
+
+    /// Check the impersonation an access token was issued for is still active.
+    ///
+    /// Returns `None` for tokens that are not impersonation tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the impersonation was revoked, has expired, or
+    /// does not match the token.
+    pub async fn check_impersonation(&self, claims: &Claims) -> orbis_core::Result<Option<Impersonation>> {
+        let Some(impersonator) = claims.impersonator.as_deref() else {
+            return Ok(None);
+        };
+
+        let id = claims
+            .jti
+            .parse()
+            .map_err(|e| orbis_core::Error::auth(format!("Invalid impersonation token: {}", e)))?;
+        let impersonation = self
+            .impersonation
+            .find(id)
+            .await?
+            .filter(|impersonation| {
+                impersonation.admin_id.to_string() == impersonator && impersonation.user_id.to_string() == claims.sub
+            })
+            .ok_or_else(|| orbis_core::Error::auth("Impersonation not found"))?;
+
+        if !impersonation.is_active() {
+            return Err(orbis_core::Error::auth("Impersonation has ended"));
+        }
+        Ok(Some(impersonation))
+    }
+
+    /// End an impersonation, invalidating its token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the impersonation does not exist or was already revoked.
+    pub async fn revoke_impersonation(
+        &self,
+        id: Uuid,
+        revoked_by: UserId,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> orbis_core::Result<Impersonation> {
+        let impersonation = self
+            .impersonation
+            .find(id)
+            .await?
+            .ok_or_else(|| orbis_core::Error::not_found("Impersonation not found"))?;
+        if !self.impersonation.revoke(id).await? {
+            return Err(orbis_core::Error::conflict("Impersonation was already revoked"));
+        }
+
+        self.audit
+            .record(NewAuditEntry {
+                ip_address: ip_address.map(String::from),
+                user_agent: user_agent.map(String::from),
+                ..NewAuditEntry::new(revoked_by, "impersonation.revoked", "impersonation", id)
+            })
+            .await?;
+
+        Ok(Impersonation {
+            revoked_at: Some(Utc::now()),
+            ..impersonation
+        })
+    }
+
+    /// Authenticate a user of a tenant by a verified client certificate.
+    ///
+    /// The first of the certificate's `identities` (its subject common name
+    /// and email address) matching a username or email wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no active user matches the certificate.
+    pub async fn authenticate_certificate<'a, I: IntoIterator<Item = &'a str>>(
+        &self,
+        identities: I,
+        fingerprint: &str,
+        tenant_id: Option<Uuid>,
+    ) -> orbis_core::Result<(User, Claims)> {
+        for identity in identities {
+            let Some(user) = self.user.find_by_username_or_email(identity, tenant_id).await? else {
+                continue;
+            };
+            if !user.is_active {
+                return Err(orbis_core::Error::auth("Account is disabled"));
+            }
+
+            let groups = self.group.effective_groups(user.id, user.tenant_id).await?;
+            let claims = self.jwt.certificate_claims(&user, groups, fingerprint);
+            return Ok((user, claims));
+        }
+        Err(orbis_core::Error::auth("Client certificate does not belong to a user"))
+    }
+
     /// Validate an access token and return the claims.
     ///
     /// # Errors
@@ -203,6 +440,19 @@ impl AuthService {
     }
 }
 
+/// Result of starting an impersonation.
+#[derive(Debug, Clone)]
+pub struct ImpersonationResult {
+    /// The impersonated user.
+    pub user: User,
+
+    /// Access token acting as the user (cannot be refreshed).
+    pub access_token: String,
+
+    /// Impersonation record.
+    pub impersonation: Impersonation,
+}
+
 /// Authentication result containing user and tokens.
 #[derive(Debug, Clone)]
 pub struct AuthResult {
@@ -0,0 +1,56 @@
+//! Seed users for development and tests.
+
+use async_trait::async_trait;
+use orbis_db::{Database, SeedSet};
+
+use crate::{CreateUser, PasswordService, UserService};
+
+/// Password of the seeded users.
+pub const SEED_PASSWORD: &str = "orbis-dev-password";
+
+/// Seeded users: username, email, display name, admin.
+const SEED_USERS: &[(&str, &str, &str, bool)] = &[
+    ("admin", "admin@example.com", "Admin", true),
+    ("demo", "demo@example.com", "Demo User", false),
+];
+
+/// Seed set creating an admin and a regular user outside any tenant, both
+/// with the password [`SEED_PASSWORD`].
+///
+/// Existing seed users get their password reset instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserSeeds;
+
+#[async_trait]
+impl SeedSet for UserSeeds {
+    fn name(&self) -> &'static str {
+        "users"
+    }
+
+    async fn run(&self, db: &Database) -> orbis_core::Result<()> {
+        let users = UserService::new(db.clone());
+        let password_hash = PasswordService::new().hash(SEED_PASSWORD)?;
+
+        for (username, email, display_name, is_admin) in SEED_USERS {
+            if let Some(user) = users.find_by_username_or_email(username, None).await? {
+                users.set_password(user.id, &password_hash).await?;
+                continue;
+            }
+
+            users
+                .create(
+                    CreateUser {
+                        username: (*username).to_owned(),
+                        email: (*email).to_owned(),
+                        password: SEED_PASSWORD.to_owned(),
+                        display_name: Some((*display_name).to_owned()),
+                        is_admin: *is_admin,
+                        tenant_id: None,
+                    },
+                    password_hash.clone(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
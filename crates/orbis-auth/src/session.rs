@@ -1,6 +1,7 @@
 //! Session management.
 
 use chrono::{DateTime, Duration, Utc};
+use orbis_core::{SessionId, UserId};
 use orbis_db::{Database, DatabasePool};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -9,10 +10,13 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     /// Session ID.
-    pub id: Uuid,
+    pub id: SessionId,
 
     /// User ID.
-    pub user_id: Uuid,
+    pub user_id: UserId,
+
+    /// Tenant the session belongs to (multi-tenant deployments only).
+    pub tenant_id: Option<Uuid>,
 
     /// Token hash (for refresh token lookup).
     pub token_hash: String,
@@ -64,13 +68,14 @@ impl SessionService {
     /// Returns an error if the session cannot be created.
     pub async fn create(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
+        tenant_id: Option<Uuid>,
         token: &str,
         user_agent: Option<&str>,
         ip_address: Option<&str>,
         expiry_seconds: u64,
     ) -> orbis_core::Result<Session> {
-        let id = Uuid::now_v7();
+        let id = SessionId::generate();
         let token_hash = Self::hash_token(token);
         let now = Utc::now();
         let expires_at = now + Duration::seconds(expiry_seconds as i64 * 24 * 7); // 7 days for refresh token
@@ -79,12 +84,13 @@ impl SessionService {
             DatabasePool::Postgres(pool) => {
                 sqlx::query(
                     r"
-                    INSERT INTO sessions (id, user_id, token_hash, user_agent, ip_address, expires_at, created_at)
-                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    INSERT INTO sessions (id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                     ",
                 )
                 .bind(id)
                 .bind(user_id)
+                .bind(tenant_id)
                 .bind(&token_hash)
                 .bind(user_agent)
                 .bind(ip_address)
@@ -97,12 +103,13 @@ impl SessionService {
             DatabasePool::Sqlite(pool) => {
                 sqlx::query(
                     r"
-                    INSERT INTO sessions (id, user_id, token_hash, user_agent, ip_address, expires_at, created_at)
-                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    INSERT INTO sessions (id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                     ",
                 )
                 .bind(id.to_string())
                 .bind(user_id.to_string())
+                .bind(tenant_id.map(|id| id.to_string()))
                 .bind(&token_hash)
                 .bind(user_agent)
                 .bind(ip_address)
@@ -117,6 +124,7 @@ impl SessionService {
         Ok(Session {
             id,
             user_id,
+            tenant_id,
             token_hash,
             user_agent: user_agent.map(String::from),
             ip_address: ip_address.map(String::from),
@@ -135,9 +143,9 @@ impl SessionService {
 
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
-                let row: Option<(Uuid, Uuid, String, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)> =
+                let row: Option<(SessionId, UserId, Option<Uuid>, String, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)> =
                     sqlx::query_as(
-                        "SELECT id, user_id, token_hash, user_agent, ip_address, expires_at, created_at 
+                        "SELECT id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at 
                         FROM sessions WHERE token_hash = $1",
                     )
                     .bind(&token_hash)
@@ -145,10 +153,11 @@ impl SessionService {
                     .await
                     .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
-                Ok(row.map(|(id, user_id, token_hash, user_agent, ip_address, expires_at, created_at)| {
+                Ok(row.map(|(id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at)| {
                     Session {
                         id,
                         user_id,
+                        tenant_id,
                         token_hash,
                         user_agent,
                         ip_address,
@@ -158,9 +167,9 @@ impl SessionService {
                 }))
             }
             DatabasePool::Sqlite(pool) => {
-                let row: Option<(String, String, String, Option<String>, Option<String>, String, String)> =
+                let row: Option<(String, String, Option<String>, String, Option<String>, Option<String>, String, String)> =
                     sqlx::query_as(
-                        "SELECT id, user_id, token_hash, user_agent, ip_address, expires_at, created_at 
+                        "SELECT id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at 
                         FROM sessions WHERE token_hash = $1",
                     )
                     .bind(&token_hash)
@@ -168,10 +177,11 @@ impl SessionService {
                     .await
                     .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
-                Ok(row.map(|(id, user_id, token_hash, user_agent, ip_address, expires_at, created_at)| {
+                Ok(row.map(|(id, user_id, tenant_id, token_hash, user_agent, ip_address, expires_at, created_at)| {
                     Session {
                         id: id.parse().unwrap_or_default(),
                         user_id: user_id.parse().unwrap_or_default(),
+                        tenant_id: tenant_id.and_then(|id| id.parse().ok()),
                         token_hash,
                         user_agent,
                         ip_address,
@@ -192,7 +202,7 @@ impl SessionService {
     /// # Errors
     ///
     /// Returns an error if the deletion fails.
-    pub async fn delete(&self, id: Uuid) -> orbis_core::Result<()> {
+    pub async fn delete(&self, id: SessionId) -> orbis_core::Result<()> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 sqlx::query("DELETE FROM sessions WHERE id = $1")
@@ -217,7 +227,7 @@ impl SessionService {
     /// # Errors
     ///
     /// Returns an error if the deletion fails.
-    pub async fn delete_all_for_user(&self, user_id: Uuid) -> orbis_core::Result<()> {
+    pub async fn delete_all_for_user(&self, user_id: UserId) -> orbis_core::Result<()> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 sqlx::query("DELETE FROM sessions WHERE user_id = $1")
@@ -0,0 +1,321 @@
+//! Tenant management for multi-tenant deployments.
+
+use chrono::{DateTime, Utc};
+use orbis_db::{Database, DatabasePool};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Tenant entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    /// Tenant ID.
+    pub id: Uuid,
+
+    /// Slug used to resolve the tenant (subdomain or header value).
+    pub slug: String,
+
+    /// Display name.
+    pub name: String,
+
+    /// Whether the tenant is active.
+    pub is_active: bool,
+
+    /// Creation time.
+    pub created_at: DateTime<Utc>,
+
+    /// Last update time.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Data for creating a new tenant.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTenant {
+    /// Slug (lowercase letters, digits and hyphens).
+    pub slug: String,
+
+    /// Display name.
+    pub name: String,
+}
+
+impl CreateTenant {
+    /// Validate the tenant data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the slug is not a valid DNS label.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        let valid = !self.slug.is_empty()
+            && self.slug.len() <= 63
+            && !self.slug.starts_with('-')
+            && !self.slug.ends_with('-')
+            && self
+                .slug
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+        if !valid {
+            return Err(orbis_core::Error::validation(
+                "Tenant slug must be 1-63 lowercase letters, digits or hyphens, not starting or ending with a hyphen",
+            ));
+        }
+
+        if self.name.trim().is_empty() {
+            return Err(orbis_core::Error::validation("Tenant name is required"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Tenant service for managing tenants and their plugin configuration.
+#[derive(Clone)]
+pub struct TenantService {
+    /// Database connection.
+    db: Database,
+}
+
+impl TenantService {
+    /// Create a new tenant service.
+    #[must_use]
+    pub const fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Find a tenant by slug.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn find_by_slug(&self, slug: &str) -> orbis_core::Result<Option<Tenant>> {
+        Ok(self
+            .query(
+                "SELECT id, slug, name, is_active, created_at, updated_at FROM tenants WHERE slug = $1",
+                Some(slug),
+            )
+            .await?
+            .pop())
+    }
+
+    /// List all tenants.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn list(&self) -> orbis_core::Result<Vec<Tenant>> {
+        self.query(
+            "SELECT id, slug, name, is_active, created_at, updated_at FROM tenants ORDER BY slug",
+            None,
+        )
+        .await
+    }
+
+    /// Run a tenant query with an optional text parameter.
+    async fn query(&self, sql: &str, param: Option<&str>) -> orbis_core::Result<Vec<Tenant>> {
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query_as::<_, (Uuid, String, String, bool, DateTime<Utc>, DateTime<Utc>)>(sql);
+                if let Some(param) = param {
+                    query = query.bind(param);
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(id, slug, name, is_active, created_at, updated_at)| Tenant {
+                        id,
+                        slug,
+                        name,
+                        is_active,
+                        created_at,
+                        updated_at,
+                    })
+                    .collect())
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query_as::<_, (String, String, String, i32, String, String)>(sql);
+                if let Some(param) = param {
+                    query = query.bind(param);
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(id, slug, name, is_active, created_at, updated_at)| Tenant {
+                        id: id.parse().unwrap_or_default(),
+                        slug,
+                        name,
+                        is_active: is_active != 0,
+                        created_at: DateTime::parse_from_rfc3339(&created_at)
+                            .map(|dt| dt.with_timezone(&Utc))
+                            .unwrap_or_else(|_| Utc::now()),
+                        updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                            .map(|dt| dt.with_timezone(&Utc))
+                            .unwrap_or_else(|_| Utc::now()),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Create a new tenant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid or the tenant cannot be created.
+    pub async fn create(&self, data: CreateTenant) -> orbis_core::Result<Tenant> {
+        data.validate()?;
+
+        if self.find_by_slug(&data.slug).await?.is_some() {
+            return Err(orbis_core::Error::conflict(format!("Tenant '{}' already exists", data.slug)));
+        }
+
+        let id = orbis_core::new_id();
+        let now = Utc::now();
+
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                sqlx::query(
+                    "
+                    INSERT INTO tenants (id, slug, name, is_active, created_at, updated_at)
+                    VALUES ($1, $2, $3, TRUE, $4, $4)
+                    ",
+                )
+                .bind(id)
+                .bind(&data.slug)
+                .bind(&data.name)
+                .bind(now)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            }
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(
+                    "
+                    INSERT INTO tenants (id, slug, name, is_active, created_at, updated_at)
+                    VALUES ($1, $2, $3, 1, $4, $4)
+                    ",
+                )
+                .bind(id.to_string())
+                .bind(&data.slug)
+                .bind(&data.name)
+                .bind(now.to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            }
+        }
+
+        Ok(Tenant {
+            id,
+            slug: data.slug,
+            name: data.name,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Set a tenant's configuration overrides for a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration cannot be saved.
+    pub async fn set_plugin_config(
+        &self,
+        tenant_id: Uuid,
+        plugin_name: &str,
+        config: &HashMap<String, serde_json::Value>,
+    ) -> orbis_core::Result<()> {
+        let config = serde_json::to_value(config)
+            .map_err(|e| orbis_core::Error::serialization(format!("Failed to serialize config: {}", e)))?;
+
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                sqlx::query(
+                    "
+                    INSERT INTO tenant_plugin_configs (tenant_id, plugin_name, config)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (tenant_id, plugin_name) DO UPDATE SET config = EXCLUDED.config
+                    ",
+                )
+                .bind(tenant_id)
+                .bind(plugin_name)
+                .bind(config)
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            }
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(
+                    "
+                    INSERT INTO tenant_plugin_configs (tenant_id, plugin_name, config)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (tenant_id, plugin_name) DO UPDATE SET config = excluded.config
+                    ",
+                )
+                .bind(tenant_id.to_string())
+                .bind(plugin_name)
+                .bind(config.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// List the plugin configuration overrides of every tenant.
+    ///
+    /// Returns `(tenant_id, plugin_name, config)` tuples.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn plugin_configs(
+        &self,
+    ) -> orbis_core::Result<Vec<(Uuid, String, HashMap<String, serde_json::Value>)>> {
+        let query = "SELECT tenant_id, plugin_name, config FROM tenant_plugin_configs";
+
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let rows: Vec<(Uuid, String, serde_json::Value)> = sqlx::query_as(query)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(tenant_id, plugin_name, config)| {
+                        (tenant_id, plugin_name, serde_json::from_value(config).unwrap_or_default())
+                    })
+                    .collect())
+            }
+            DatabasePool::Sqlite(pool) => {
+                let rows: Vec<(String, String, String)> = sqlx::query_as(query)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(tenant_id, plugin_name, config)| {
+                        (
+                            tenant_id.parse().unwrap_or_default(),
+                            plugin_name,
+                            serde_json::from_str(&config).unwrap_or_default(),
+                        )
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
@@ -1,6 +1,7 @@
 //! User management.
 
 use chrono::{DateTime, Utc};
+use orbis_core::UserId;
 use orbis_db::{Database, DatabasePool};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -9,7 +10,7 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     /// User ID.
-    pub id: Uuid,
+    pub id: UserId,
 
     /// Username.
     pub username: String,
@@ -30,6 +31,9 @@ pub struct User {
     /// Whether the user is an admin.
     pub is_admin: bool,
 
+    /// Tenant the user belongs to (multi-tenant deployments only).
+    pub tenant_id: Option<Uuid>,
+
     /// Creation time.
     pub created_at: DateTime<Utc>,
 
@@ -55,6 +59,10 @@ pub struct CreateUser {
     /// Whether the user is an admin.
     #[serde(default)]
     pub is_admin: bool,
+
+    /// Tenant the user belongs to; set by the server from the resolved tenant.
+    #[serde(skip)]
+    pub tenant_id: Option<Uuid>,
 }
 
 /// User service for managing users.
@@ -75,21 +83,22 @@ impl UserService {
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub async fn find_by_id(&self, id: Uuid) -> orbis_core::Result<Option<User>> {
+    pub async fn find_by_id(&self, id: UserId) -> orbis_core::Result<Option<User>> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 let row: Option<(
-                    Uuid,
+                    UserId,
                     String,
                     String,
                     String,
                     Option<String>,
                     bool,
                     bool,
+                    Option<Uuid>,
                     DateTime<Utc>,
                     DateTime<Utc>,
                 )> = sqlx::query_as(
-                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at 
+                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at 
                     FROM users WHERE id = $1",
                 )
                 .bind(id)
@@ -98,7 +107,7 @@ impl UserService {
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
                 Ok(row.map(
-                    |(id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)| {
+                    |(id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)| {
                         User {
                             id,
                             username,
@@ -107,6 +116,7 @@ impl UserService {
                             display_name,
                             is_active,
                             is_admin,
+                            tenant_id,
                             created_at,
                             updated_at,
                         }
@@ -122,10 +132,11 @@ impl UserService {
                     Option<String>,
                     i32,
                     i32,
+                    Option<String>,
                     String,
                     String,
                 )> = sqlx::query_as(
-                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at 
+                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at 
                     FROM users WHERE id = $1",
                 )
                 .bind(id.to_string())
@@ -134,7 +145,7 @@ impl UserService {
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
                 Ok(row.map(
-                    |(id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)| {
+                    |(id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)| {
                         User {
                             id: id.parse().unwrap_or_default(),
                             username,
@@ -143,6 +154,7 @@ impl UserService {
                             display_name,
                             is_active: is_active != 0,
                             is_admin: is_admin != 0,
+                            tenant_id: tenant_id.and_then(|id| id.parse().ok()),
                             created_at: DateTime::parse_from_rfc3339(&created_at)
                                 .map(|dt| dt.with_timezone(&Utc))
                                 .unwrap_or_else(|_| Utc::now()),
@@ -156,7 +168,9 @@ impl UserService {
         }
     }
 
-    /// Find a user by username or email.
+    /// Find a user of a tenant by username or email.
+    ///
+    /// Without a tenant, only users outside any tenant are found.
     ///
     /// # Errors
     ///
@@ -164,30 +178,33 @@ impl UserService {
     pub async fn find_by_username_or_email(
         &self,
         username_or_email: &str,
+        tenant_id: Option<Uuid>,
     ) -> orbis_core::Result<Option<User>> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 let row: Option<(
-                    Uuid,
+                    UserId,
                     String,
                     String,
                     String,
                     Option<String>,
                     bool,
                     bool,
+                    Option<Uuid>,
                     DateTime<Utc>,
                     DateTime<Utc>,
                 )> = sqlx::query_as(
-                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at 
-                    FROM users WHERE username = $1 OR email = $1",
+                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at 
+                    FROM users WHERE (username = $1 OR email = $1) AND tenant_id IS NOT DISTINCT FROM $2",
                 )
                 .bind(username_or_email)
+                .bind(tenant_id)
                 .fetch_optional(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
                 Ok(row.map(
-                    |(id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)| {
+                    |(id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)| {
                         User {
                             id,
                             username,
@@ -196,6 +213,7 @@ impl UserService {
                             display_name,
                             is_active,
                             is_admin,
+                            tenant_id,
                             created_at,
                             updated_at,
                         }
@@ -211,19 +229,21 @@ impl UserService {
                     Option<String>,
                     i32,
                     i32,
+                    Option<String>,
                     String,
                     String,
                 )> = sqlx::query_as(
-                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at 
-                    FROM users WHERE username = $1 OR email = $1",
+                    "SELECT id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at 
+                    FROM users WHERE (username = $1 OR email = $1) AND tenant_id IS $2",
                 )
                 .bind(username_or_email)
+                .bind(tenant_id.map(|id| id.to_string()))
                 .fetch_optional(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
 
                 Ok(row.map(
-                    |(id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)| {
+                    |(id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)| {
                         User {
                             id: id.parse().unwrap_or_default(),
                             username,
@@ -232,6 +252,7 @@ impl UserService {
                             display_name,
                             is_active: is_active != 0,
                             is_admin: is_admin != 0,
+                            tenant_id: tenant_id.and_then(|id| id.parse().ok()),
                             created_at: DateTime::parse_from_rfc3339(&created_at)
                                 .map(|dt| dt.with_timezone(&Utc))
                                 .unwrap_or_else(|_| Utc::now()),
@@ -251,15 +272,15 @@ impl UserService {
     ///
     /// Returns an error if the user cannot be created.
     pub async fn create(&self, data: CreateUser, password_hash: String) -> orbis_core::Result<User> {
-        let id = Uuid::now_v7();
+        let id = UserId::generate();
         let now = Utc::now();
 
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 sqlx::query(
                     r"
-                    INSERT INTO users (id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)
-                    VALUES ($1, $2, $3, $4, $5, TRUE, $6, $7, $7)
+                    INSERT INTO users (id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, TRUE, $6, $7, $8, $8)
                     ",
                 )
                 .bind(id)
@@ -268,6 +289,7 @@ impl UserService {
                 .bind(&password_hash)
                 .bind(&data.display_name)
                 .bind(data.is_admin)
+                .bind(data.tenant_id)
                 .bind(now)
                 .execute(pool)
                 .await
@@ -276,8 +298,8 @@ impl UserService {
             DatabasePool::Sqlite(pool) => {
                 sqlx::query(
                     r"
-                    INSERT INTO users (id, username, email, password_hash, display_name, is_active, is_admin, created_at, updated_at)
-                    VALUES ($1, $2, $3, $4, $5, 1, $6, $7, $7)
+                    INSERT INTO users (id, username, email, password_hash, display_name, is_active, is_admin, tenant_id, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, 1, $6, $7, $8, $8)
                     ",
                 )
                 .bind(id.to_string())
@@ -286,6 +308,7 @@ impl UserService {
                 .bind(&password_hash)
                 .bind(&data.display_name)
                 .bind(if data.is_admin { 1 } else { 0 })
+                .bind(data.tenant_id.map(|id| id.to_string()))
                 .bind(now.to_rfc3339())
                 .execute(pool)
                 .await
@@ -301,57 +324,101 @@ impl UserService {
             display_name: data.display_name,
             is_active: true,
             is_admin: data.is_admin,
+            tenant_id: data.tenant_id,
             created_at: now,
             updated_at: now,
         })
     }
 
-    /// Check if a username exists.
+    /// Replace the password hash of a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or the update fails.
+    pub async fn set_password(&self, id: UserId, password_hash: &str) -> orbis_core::Result<()> {
+        let now = Utc::now();
+
+        let updated = match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                sqlx::query("UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3")
+                    .bind(password_hash)
+                    .bind(now)
+                    .bind(id)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                    .rows_affected()
+            }
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query("UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3")
+                    .bind(password_hash)
+                    .bind(now.to_rfc3339())
+                    .bind(id.to_string())
+                    .execute(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+                    .rows_affected()
+            }
+        };
+
+        if updated == 0 {
+            return Err(orbis_core::Error::not_found(format!("User {} not found", id)));
+        }
+        Ok(())
+    }
+
+    /// Check if a username exists in a tenant, or in platform scope
+    /// without one.
     ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub async fn username_exists(&self, username: &str) -> orbis_core::Result<bool> {
+    pub async fn username_exists(&self, username: &str, tenant_id: Option<Uuid>) -> orbis_core::Result<bool> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
                 let count: (i64,) =
-                    sqlx::query_as("SELECT COUNT(*) FROM users WHERE username = $1")
+                    sqlx::query_as("SELECT COUNT(*) FROM users WHERE username = $1 AND tenant_id IS NOT DISTINCT FROM $2")
                         .bind(username)
+                        .bind(tenant_id)
                         .fetch_one(pool)
                         .await
                         .map_err(|e| orbis_core::Error::database(e.to_string()))?;
                 Ok(count.0 > 0)
             }
             DatabasePool::Sqlite(pool) => {
-                let count: (i64,) =
-                    sqlx::query_as("SELECT COUNT(*) FROM users WHERE username = $1")
-                        .bind(username)
-                        .fetch_one(pool)
-                        .await
-                        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE username = $1 AND tenant_id IS $2")
+                    .bind(username)
+                    .bind(tenant_id.map(|id| id.to_string()))
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
                 Ok(count.0 > 0)
             }
         }
     }
 
-    /// Check if an email exists.
+    /// Check if an email exists in a tenant, or in platform scope
+    /// without one.
     ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub async fn email_exists(&self, email: &str) -> orbis_core::Result<bool> {
+    pub async fn email_exists(&self, email: &str, tenant_id: Option<Uuid>) -> orbis_core::Result<bool> {
         match self.db.pool() {
             DatabasePool::Postgres(pool) => {
-                let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1")
-                    .bind(email)
-                    .fetch_one(pool)
-                    .await
-                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                let count: (i64,) =
+                    sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1 AND tenant_id IS NOT DISTINCT FROM $2")
+                        .bind(email)
+                        .bind(tenant_id)
+                        .fetch_one(pool)
+                        .await
+                        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
                 Ok(count.0 > 0)
             }
             DatabasePool::Sqlite(pool) => {
-                let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1")
+                let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1 AND tenant_id IS $2")
                     .bind(email)
+                    .bind(tenant_id.map(|id| id.to_string()))
                     .fetch_one(pool)
                     .await
                     .map_err(|e| orbis_core::Error::database(e.to_string()))?;
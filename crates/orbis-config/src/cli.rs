@@ -71,6 +71,50 @@ pub struct Cli {
     )]
     pub request_timeout: u64,
 
+    /// Maximum request body size
+    #[arg(long, env = "ORBIS_MAX_BODY_SIZE", help = "Maximum request body size in bytes")]
+    pub max_body_size: Option<usize>,
+
+    /// Default maximum body size of plugin routes
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_MAX_BODY_SIZE",
+        help = "Maximum body size in bytes of plugin routes that declare no limit"
+    )]
+    pub plugin_max_body_size: Option<usize>,
+
+    /// Body read timeout
+    #[arg(
+        long,
+        env = "ORBIS_BODY_READ_TIMEOUT",
+        help = "Seconds a client may take to send a request body"
+    )]
+    pub body_read_timeout: Option<u64>,
+
+    /// Header read timeout
+    #[arg(
+        long,
+        env = "ORBIS_HEADER_READ_TIMEOUT",
+        help = "Seconds a client may take to send request headers"
+    )]
+    pub header_read_timeout: Option<u64>,
+
+    /// Connection read timeout
+    #[arg(
+        long,
+        env = "ORBIS_READ_TIMEOUT",
+        help = "Seconds a connection may wait for data from the client before it is closed"
+    )]
+    pub read_timeout: Option<u64>,
+
+    /// Connection write timeout
+    #[arg(
+        long,
+        env = "ORBIS_WRITE_TIMEOUT",
+        help = "Seconds a connection may wait for the client to accept data before it is closed"
+    )]
+    pub write_timeout: Option<u64>,
+
     // Database configuration
     /// Database URL
     #[arg(long, env = "ORBIS_DB_URL", help = "Database connection URL")]
@@ -176,6 +220,24 @@ pub struct Cli {
     )]
     pub db_run_migrations: bool,
 
+    /// Query cache TTL in milliseconds
+    #[arg(
+        long,
+        env = "ORBIS_DB_QUERY_CACHE_TTL_MS",
+        default_value = "0",
+        help = "How long query results stay cached (ms, 0 disables the cache)"
+    )]
+    pub db_query_cache_ttl_ms: u64,
+
+    /// Query cache capacity
+    #[arg(
+        long,
+        env = "ORBIS_DB_QUERY_CACHE_CAPACITY",
+        default_value = "1000",
+        help = "Most query results kept in the cache"
+    )]
+    pub db_query_cache_capacity: usize,
+
     // TLS configuration
     /// Enable TLS
     #[arg(long, env = "ORBIS_TLS_ENABLED", help = "Enable TLS")]
@@ -202,6 +264,14 @@ pub struct Cli {
     )]
     pub tls_verify: bool,
 
+    /// TLS client authentication mode
+    #[arg(
+        long,
+        env = "ORBIS_TLS_CLIENT_AUTH",
+        help = "Client certificate authentication against the TLS CA: none, optional, or required"
+    )]
+    pub tls_client_auth: Option<String>,
+
     // Logging configuration
     /// Log level
     #[arg(
@@ -246,6 +316,70 @@ pub struct Cli {
     )]
     pub jwt_expiry_seconds: Option<u64>,
 
+    /// JWT signing algorithm
+    #[arg(
+        long,
+        env = "ORBIS_JWT_ALGORITHM",
+        help = "JWT signing algorithm: HS256 (shared secret), RS256 or EdDSA (key pair)"
+    )]
+    pub jwt_algorithm: Option<String>,
+
+    /// JWT private key file
+    #[arg(
+        long,
+        env = "ORBIS_JWT_PRIVATE_KEY_FILE",
+        help = "PEM private key signing tokens (RS256 and EdDSA)"
+    )]
+    pub jwt_private_key_file: Option<PathBuf>,
+
+    /// JWT public key file
+    #[arg(
+        long,
+        env = "ORBIS_JWT_PUBLIC_KEY_FILE",
+        help = "PEM public key validating tokens (RS256 and EdDSA)"
+    )]
+    pub jwt_public_key_file: Option<PathBuf>,
+
+    /// JWT signing keys file
+    #[arg(
+        long,
+        env = "ORBIS_JWT_KEYS_FILE",
+        help = "JSON file of JWT signing keys and their rotation schedule"
+    )]
+    pub jwt_keys_file: Option<PathBuf>,
+
+    /// JWT key grace window
+    #[arg(
+        long,
+        env = "ORBIS_JWT_KEY_GRACE_SECONDS",
+        help = "Seconds tokens signed by a retired JWT key are still accepted"
+    )]
+    pub jwt_key_grace_seconds: Option<u64>,
+
+    /// Longest impersonation in minutes
+    #[arg(
+        long,
+        env = "ORBIS_IMPERSONATION_MAX_MINUTES",
+        help = "Longest an admin can impersonate a user, in minutes"
+    )]
+    pub impersonation_max_minutes: Option<u64>,
+
+    /// Allow impersonation writes
+    #[arg(
+        long,
+        env = "ORBIS_IMPERSONATION_ALLOW_WRITES",
+        help = "Let impersonation tokens make non-destructive writes"
+    )]
+    pub impersonation_allow_writes: bool,
+
+    /// Account deletion grace period in days
+    #[arg(
+        long,
+        env = "ORBIS_ACCOUNT_DELETION_GRACE_DAYS",
+        help = "Days before an account users asked to delete is deleted"
+    )]
+    pub account_deletion_grace_days: Option<u64>,
+
     // Directory configuration
     /// Profiles directory
     #[arg(
@@ -259,6 +393,198 @@ pub struct Cli {
     #[arg(long, env = "ORBIS_PLUGINS_DIR", help = "Directory for plugins")]
     pub plugins_dir: Option<PathBuf>,
 
+    /// Load plugins targeting an incompatible host API with a warning
+    #[arg(
+        long,
+        env = "ORBIS_ALLOW_INCOMPATIBLE_PLUGINS",
+        help = "Load plugins whose core_version excludes the host API version, with a warning"
+    )]
+    pub allow_incompatible_plugins: bool,
+
+    /// Reload plugins when their files change
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_HOT_RELOAD",
+        help = "Watch the plugins directory and reload plugins when their files change (for development)"
+    )]
+    pub plugin_hot_reload: bool,
+
+    /// Enable plugin development tools
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_DEV_MODE",
+        help = "Enable plugin development tools: invoking handlers directly and replaying captured requests"
+    )]
+    pub plugin_dev_mode: bool,
+
+    /// Keep cached plugin route responses on disk
+    #[arg(
+        long,
+        env = "ORBIS_RESPONSE_CACHE_ON_DISK",
+        help = "Keep cached plugin route responses on disk under the data directory, so they survive restarts"
+    )]
+    pub response_cache_on_disk: bool,
+
+    /// Plugin signature policy
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_SIGNATURES",
+        help = "What to do with unsigned or badly signed standalone plugins (off, warn, require)"
+    )]
+    pub plugin_signatures: Option<String>,
+
+    /// Directory of trusted plugin signing keys
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_TRUSTED_KEYS_DIR",
+        help = "Directory of public keys (*.pub) trusted to sign plugins"
+    )]
+    pub plugin_trusted_keys_dir: Option<PathBuf>,
+
+    /// Slow plugin handler threshold
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_SLOW_HANDLER_MS",
+        help = "Record plugin handler calls slower than this as slow and flag handlers whose p95 exceeds it (milliseconds, default: 1000)"
+    )]
+    pub plugin_slow_handler_ms: Option<u64>,
+
+    /// Plugin handler error rate threshold
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_HANDLER_ERROR_RATE",
+        help = "Flag plugin handlers failing more often than this fraction of calls (default: 0.05)"
+    )]
+    pub plugin_handler_error_rate: Option<f64>,
+
+    /// Plugin security policy file
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_POLICY_FILE",
+        help = "JSON file of the security policy plugin host calls are checked against"
+    )]
+    pub plugin_policy_file: Option<PathBuf>,
+
+    /// Plugin resource alerts file
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_ALERTS_FILE",
+        help = "JSON file of plugin resource alert rules and the webhooks alerts are posted to"
+    )]
+    pub plugin_alerts_file: Option<PathBuf>,
+
+    /// Plugin replay directory
+    #[arg(
+        long,
+        env = "ORBIS_PLUGIN_REPLAY_DIR",
+        help = "Directory replay files of recorded plugin handlers are saved in (enables recording)"
+    )]
+    pub plugin_replay_dir: Option<PathBuf>,
+
+    // Tenancy configuration
+    /// Tenancy mode
+    #[arg(
+        long,
+        env = "ORBIS_TENANCY",
+        help = "Multi-tenancy mode: disabled, subdomain, or header"
+    )]
+    pub tenancy: Option<String>,
+
+    /// Tenant header
+    #[arg(
+        long,
+        env = "ORBIS_TENANT_HEADER",
+        help = "Header carrying the tenant slug in header tenancy mode"
+    )]
+    pub tenant_header: Option<String>,
+
+    /// Tenant base domain
+    #[arg(
+        long,
+        env = "ORBIS_TENANT_BASE_DOMAIN",
+        help = "Base domain tenants are subdomains of in subdomain tenancy mode"
+    )]
+    pub tenant_base_domain: Option<String>,
+
+    // Job queue configuration
+    /// Job workers
+    #[arg(
+        long,
+        env = "ORBIS_JOB_WORKERS",
+        help = "Number of background job workers (0 disables job processing)"
+    )]
+    pub job_workers: Option<usize>,
+
+    /// Job visibility timeout
+    #[arg(
+        long,
+        env = "ORBIS_JOB_VISIBILITY_TIMEOUT",
+        help = "Seconds a claimed job is hidden from other workers before it is retried"
+    )]
+    pub job_visibility_timeout: Option<u64>,
+
+    /// Job max attempts
+    #[arg(
+        long,
+        env = "ORBIS_JOB_MAX_ATTEMPTS",
+        help = "Default number of attempts before a job is dead-lettered"
+    )]
+    pub job_max_attempts: Option<u32>,
+
+    // Email configuration
+    /// SMTP host
+    #[arg(
+        long,
+        env = "ORBIS_SMTP_HOST",
+        help = "SMTP server host (emails are logged instead of sent when unset)"
+    )]
+    pub smtp_host: Option<String>,
+
+    /// SMTP port
+    #[arg(long, env = "ORBIS_SMTP_PORT", help = "SMTP server port")]
+    pub smtp_port: Option<u16>,
+
+    /// SMTP security
+    #[arg(
+        long,
+        env = "ORBIS_SMTP_SECURITY",
+        help = "SMTP connection security: starttls, tls, or none"
+    )]
+    pub smtp_security: Option<String>,
+
+    /// SMTP username
+    #[arg(long, env = "ORBIS_SMTP_USERNAME", help = "SMTP username")]
+    pub smtp_username: Option<String>,
+
+    /// SMTP password
+    #[arg(long, env = "ORBIS_SMTP_PASSWORD", help = "SMTP password")]
+    pub smtp_password: Option<String>,
+
+    /// Email sender
+    #[arg(
+        long,
+        env = "ORBIS_EMAIL_FROM",
+        help = "Sender address of outgoing emails, e.g. 'Orbis <noreply@example.com>'"
+    )]
+    pub email_from: Option<String>,
+
+    // Localization configuration
+    /// Locales directory
+    #[arg(
+        long,
+        env = "ORBIS_LOCALES_DIR",
+        help = "Directory of message catalogs (<locale>.json) for localized responses"
+    )]
+    pub locales_dir: Option<PathBuf>,
+
+    /// Default locale
+    #[arg(
+        long,
+        env = "ORBIS_DEFAULT_LOCALE",
+        help = "Locale used when no requested locale has a translation"
+    )]
+    pub default_locale: Option<String>,
+
     /// Data directory
     #[arg(long, env = "ORBIS_DATA_DIR", help = "Data directory")]
     pub data_dir: Option<PathBuf>,
@@ -266,6 +592,10 @@ pub struct Cli {
     /// Subcommand
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Configuration fields set by generated options (see [`Cli::parse_layered`])
+    #[arg(skip)]
+    pub config_overrides: Vec<crate::ConfigOverride>,
 }
 
 /// Available subcommands.
@@ -284,6 +614,15 @@ pub enum Commands {
         action: ProfileCommands,
     },
 
+    /// Run pending database migrations
+    Migrate,
+
+    /// Manage users
+    User {
+        #[command(subcommand)]
+        action: UserCommands,
+    },
+
     /// Database operations
     Db {
         #[command(subcommand)]
@@ -296,6 +635,12 @@ pub enum Commands {
         action: PluginCommands,
     },
 
+    /// Backup operations
+    Backup {
+        #[command(subcommand)]
+        action: BackupCommands,
+    },
+
     /// Generate configuration file
     Config {
         /// Output path
@@ -343,6 +688,53 @@ pub enum ProfileCommands {
     },
 }
 
+/// User management commands.
+///
+/// Passwords not given with `--password` are read from the first line of
+/// standard input.
+#[derive(Subcommand, Debug)]
+pub enum UserCommands {
+    /// Create a user
+    Create {
+        /// Username
+        username: String,
+
+        /// Email address
+        #[arg(long)]
+        email: String,
+
+        /// Password
+        #[arg(long, env = "ORBIS_USER_PASSWORD")]
+        password: Option<String>,
+
+        /// Display name
+        #[arg(long)]
+        display_name: Option<String>,
+
+        /// Grant the admin role
+        #[arg(long)]
+        admin: bool,
+
+        /// Slug of the tenant the user belongs to
+        #[arg(long)]
+        tenant: Option<String>,
+    },
+
+    /// Set a new password and end the user's sessions
+    ResetPassword {
+        /// Username or email address
+        username: String,
+
+        /// New password
+        #[arg(long, env = "ORBIS_USER_PASSWORD")]
+        password: Option<String>,
+
+        /// Slug of the tenant the user belongs to
+        #[arg(long)]
+        tenant: Option<String>,
+    },
+}
+
 /// Database management commands.
 #[derive(Subcommand, Debug)]
 pub enum DbCommands {
@@ -360,6 +752,28 @@ pub enum DbCommands {
         /// Migration name
         name: String,
     },
+
+    /// Seed the database with sample data
+    Seed {
+        /// Environment to seed for (development, test or production)
+        #[arg(long, default_value = "development")]
+        environment: String,
+
+        /// Only run these seed sets
+        #[arg(long = "set")]
+        sets: Vec<String>,
+    },
+}
+
+/// Backup commands.
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    /// Back up the database
+    Run {
+        /// Output file (defaults to the backups directory under the data directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 /// Plugin management commands.
@@ -370,8 +784,16 @@ pub enum PluginCommands {
 
     /// Install a plugin
     Install {
-        /// Plugin path or URL
+        /// Plugin path, URL, or name of a plugin in the configured registry
         source: String,
+
+        /// SHA-256 hash of the plugin file, required to install from a URL
+        #[arg(long)]
+        sha256: Option<String>,
+
+        /// Version requirement of a plugin installed from the registry (e.g. ^1.2)
+        #[arg(long)]
+        version: Option<String>,
     },
 
     /// Uninstall a plugin
@@ -397,4 +819,81 @@ pub enum PluginCommands {
         /// Plugin name
         name: String,
     },
+
+    /// Clear the precompiled plugin module cache
+    ClearCache,
+
+    /// Generate a plugin signing key pair
+    Keygen {
+        /// Secret key file; the public key is written next to it with a `.pub` extension
+        output: PathBuf,
+    },
+
+    /// Sign a standalone plugin, embedding the signature in the WASM file, or an unpacked plugin directory
+    Sign {
+        /// Plugin WASM file or unpacked plugin directory
+        wasm: PathBuf,
+
+        /// Secret key file
+        #[arg(short, long, env = "ORBIS_PLUGIN_SIGNING_KEY")]
+        key: PathBuf,
+
+        /// Output file (defaults to signing in place; directories are always signed in place)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Serve a plugin under development, rebuilding and reloading it when its sources change
+    Dev {
+        /// Plugin crate directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+
+    /// Check the environment for building, signing and publishing plugins
+    Doctor {
+        /// Plugin crate directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Registry URL to check connectivity to
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Print the checks as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Replay a recorded plugin handler invocation against the installed plugin
+    Replay {
+        /// Replay file
+        file: PathBuf,
+    },
+
+    /// Break down the size of a plugin module and the host functions and permissions it uses
+    Analyze {
+        /// Plugin WASM file
+        wasm: PathBuf,
+
+        /// Manifest declaring the plugin's permissions (defaults to the embedded one)
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        /// Previous build to compare sizes with
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Fail if the module grew by more than this many bytes since the baseline
+        #[arg(long, requires = "baseline")]
+        max_growth: Option<u64>,
+
+        /// Number of largest functions to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Print the analysis as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
@@ -2,7 +2,7 @@
 
 use crate::Cli;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 /// Database backend type.
@@ -41,12 +41,32 @@ impl std::fmt::Display for DatabaseBackend {
     }
 }
 
+/// How the database location is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DatabaseMode {
+    /// The URL or connection settings are given (default).
+    #[default]
+    Explicit,
+
+    /// A SQLite file in the active profile's data directory, opened in WAL
+    /// mode and checked for corruption on open.
+    ProfileManaged,
+}
+
+/// File name of profile-managed databases, in the profile's data directory.
+pub const PROFILE_DATABASE_FILE: &str = "orbis.db";
+
 /// Database configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     /// Database backend.
     pub backend: DatabaseBackend,
 
+    /// How the database location is chosen.
+    #[serde(default)]
+    pub mode: DatabaseMode,
+
     /// Database URL (takes precedence over individual settings).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
@@ -99,6 +119,25 @@ pub struct DatabaseConfig {
 
     /// Run migrations on startup.
     pub run_migrations: bool,
+
+    /// How long query results stay cached, in milliseconds (0 disables the cache).
+    #[serde(default)]
+    pub query_cache_ttl_ms: u64,
+
+    /// Most query results kept in the cache.
+    #[serde(default = "default_query_cache_capacity")]
+    pub query_cache_capacity: usize,
+
+    /// Key of an encrypted profile, opening its SQLite database with
+    /// SQLCipher and sealing plugin state. Set when the profile is unlocked,
+    /// never read from configuration.
+    #[serde(skip)]
+    pub encryption_key: Option<orbis_core::EncryptionKey>,
+}
+
+/// Default query cache capacity.
+const fn default_query_cache_capacity() -> usize {
+    1000
 }
 
 impl DatabaseConfig {
@@ -112,6 +151,7 @@ impl DatabaseConfig {
 
         Self {
             backend,
+            mode: file_config.map(|c| c.mode).unwrap_or_default(),
             url: cli.db_url.clone().or_else(|| {
                 file_config.and_then(|c| c.url.clone())
             }),
@@ -143,7 +183,29 @@ impl DatabaseConfig {
             idle_timeout_ms: cli.db_idle_timeout_ms,
             max_lifetime_ms: cli.db_max_lifetime_ms,
             run_migrations: cli.db_run_migrations,
+            query_cache_ttl_ms: cli.db_query_cache_ttl_ms,
+            query_cache_capacity: cli.db_query_cache_capacity,
+            encryption_key: None,
+        }
+    }
+
+    /// Check if the database is a SQLite file managed in the profile's data directory.
+    #[must_use]
+    pub fn is_profile_managed(&self) -> bool {
+        self.mode == DatabaseMode::ProfileManaged
+    }
+
+    /// Get the configuration with the database of a profile-managed mode
+    /// placed in a profile's data directory.
+    ///
+    /// Explicitly configured databases are returned unchanged.
+    #[must_use]
+    pub fn for_profile_dir(&self, profile_dir: &Path) -> Self {
+        let mut config = self.clone();
+        if config.is_profile_managed() {
+            config.path = Some(profile_dir.join(PROFILE_DATABASE_FILE));
         }
+        config
     }
 
     /// Get the database URL.
@@ -181,7 +243,13 @@ impl DatabaseConfig {
             }
             DatabaseBackend::Sqlite => {
                 let path = self.path.as_ref().ok_or_else(|| {
-                    orbis_core::Error::config("Database path is required for SQLite")
+                    if self.is_profile_managed() {
+                        orbis_core::Error::config(
+                            "Profile-managed databases need a data directory. Set ORBIS_DATA_DIR or --data-dir",
+                        )
+                    } else {
+                        orbis_core::Error::config("Database path is required for SQLite")
+                    }
                 })?;
 
                 Ok(format!("sqlite:{}?mode=rwc", path.display()))
@@ -213,12 +281,23 @@ impl DatabaseConfig {
         Duration::from_millis(self.max_lifetime_ms)
     }
 
+    /// Get the query cache TTL as Duration.
+    #[must_use]
+    pub const fn query_cache_ttl(&self) -> Duration {
+        Duration::from_millis(self.query_cache_ttl_ms)
+    }
+
     /// Validate the database configuration.
     ///
     /// # Errors
     ///
     /// Returns an error if the configuration is invalid.
     pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.is_profile_managed() && (self.backend != DatabaseBackend::Sqlite || self.url.is_some()) {
+            return Err(orbis_core::Error::config(
+                "Profile-managed databases are SQLite files: use the sqlite backend without a database URL",
+            ));
+        }
         // Validate that we can construct a URL
         self.database_url()?;
 
@@ -229,6 +308,10 @@ impl DatabaseConfig {
             ));
         }
 
+        if self.encryption_key.is_some() && self.backend != DatabaseBackend::Sqlite {
+            return Err(orbis_core::Error::config("Only SQLite databases can be encrypted"));
+        }
+
         Ok(())
     }
 }
@@ -237,6 +320,7 @@ impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             backend: DatabaseBackend::Sqlite,
+            mode: DatabaseMode::Explicit,
             url: None,
             host: None,
             port: None,
@@ -252,6 +336,9 @@ impl Default for DatabaseConfig {
             idle_timeout_ms: 10000,
             max_lifetime_ms: 60000,
             run_migrations: true,
+            query_cache_ttl_ms: 0,
+            query_cache_capacity: default_query_cache_capacity(),
+            encryption_key: None,
         }
     }
 }
@@ -0,0 +1,133 @@
+//! Email configuration.
+
+use crate::Cli;
+use serde::{Deserialize, Serialize};
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (default).
+    #[default]
+    StartTls,
+
+    /// Implicit TLS from the start of the connection.
+    Tls,
+
+    /// Unencrypted connection (local relays only).
+    None,
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" | "off" => Ok(Self::None),
+            _ => Err(orbis_core::Error::config(format!(
+                "Invalid SMTP security: '{}'. Expected 'starttls', 'tls', or 'none'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Email configuration.
+///
+/// Without an SMTP host, messages are written to the log instead of being sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// SMTP server host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smtp_host: Option<String>,
+
+    /// SMTP server port.
+    pub smtp_port: u16,
+
+    /// SMTP connection security.
+    pub smtp_security: SmtpSecurity,
+
+    /// SMTP username.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smtp_username: Option<String>,
+
+    /// SMTP password.
+    #[serde(skip_serializing)]
+    pub smtp_password: Option<String>,
+
+    /// Sender address (e.g. `Orbis <noreply@example.com>`).
+    pub from: String,
+}
+
+impl EmailConfig {
+    /// Create email config from CLI arguments.
+    pub fn from_cli(cli: &Cli, file_config: Option<&Self>) -> Self {
+        let defaults = Self::default();
+        Self {
+            smtp_host: cli
+                .smtp_host
+                .clone()
+                .or_else(|| file_config.and_then(|c| c.smtp_host.clone())),
+            smtp_port: cli
+                .smtp_port
+                .unwrap_or_else(|| file_config.map_or(defaults.smtp_port, |c| c.smtp_port)),
+            smtp_security: cli
+                .smtp_security
+                .as_deref()
+                .and_then(|security| security.parse().ok())
+                .unwrap_or_else(|| file_config.map(|c| c.smtp_security).unwrap_or_default()),
+            smtp_username: cli
+                .smtp_username
+                .clone()
+                .or_else(|| file_config.and_then(|c| c.smtp_username.clone())),
+            smtp_password: cli
+                .smtp_password
+                .clone()
+                .or_else(|| file_config.and_then(|c| c.smtp_password.clone())),
+            from: cli
+                .email_from
+                .clone()
+                .unwrap_or_else(|| file_config.map_or(defaults.from, |c| c.from.clone())),
+        }
+    }
+
+    /// Check if messages are sent over SMTP.
+    #[must_use]
+    pub const fn is_smtp_enabled(&self) -> bool {
+        self.smtp_host.is_some()
+    }
+
+    /// Validate the email configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender is empty or credentials are incomplete.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.from.trim().is_empty() {
+            return Err(orbis_core::Error::config("Email sender address cannot be empty"));
+        }
+
+        if self.smtp_username.is_some() != self.smtp_password.is_some() {
+            return Err(orbis_core::Error::config(
+                "SMTP username and password must be set together. Set ORBIS_SMTP_USERNAME and ORBIS_SMTP_PASSWORD",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_security: SmtpSecurity::StartTls,
+            smtp_username: None,
+            smtp_password: None,
+            from: "Orbis <noreply@localhost>".to_string(),
+        }
+    }
+}
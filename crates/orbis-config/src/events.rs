@@ -0,0 +1,53 @@
+//! Event delivery configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Delivery of server events to clients (`[events]`), through server-sent
+/// events or long polling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// Events kept for clients resuming from a cursor; a client further
+    /// behind misses the oldest ones.
+    pub buffer_size: usize,
+
+    /// Seconds a poll waits for an event before answering with none. The
+    /// wait is also cut short before the request timeout.
+    pub poll_timeout_seconds: u64,
+
+    /// Most events returned by a single poll.
+    pub poll_max_events: usize,
+}
+
+impl EventsConfig {
+    /// Validate the event delivery configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer, the poll timeout or the poll batch size is zero.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.buffer_size == 0 {
+            return Err(orbis_core::Error::config("The event buffer must keep at least 1 event"));
+        }
+
+        if self.poll_timeout_seconds == 0 {
+            return Err(orbis_core::Error::config("Event polls must wait at least 1 second"));
+        }
+
+        if self.poll_max_events == 0 {
+            return Err(orbis_core::Error::config("Event polls must return at least 1 event"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: 1024,
+            poll_timeout_seconds: 25,
+            poll_max_events: 100,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Virtual host configuration.
+//!
+//! A server can serve several profiles on one port. Each profile is selected
+//! by the hostname or path prefix of requests and has its own database,
+//! plugins and data directory.
+
+use crate::{DatabaseBackend, DatabaseConfig, DatabaseMode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A profile served on a virtual host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualHostConfig {
+    /// Profile name.
+    pub profile: String,
+
+    /// Hostnames selecting the profile, e.g. `acme.example.com`.
+    #[serde(default)]
+    pub hostnames: Vec<String>,
+
+    /// Path prefix selecting the profile, e.g. `/acme`; stripped from requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+
+    /// Database URL of the profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_url: Option<String>,
+
+    /// SQLite database path of the profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<PathBuf>,
+
+    /// Plugins directory of the profile (defaults to the server's).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugins_dir: Option<PathBuf>,
+
+    /// Data directory of the profile (defaults to a `profiles/<name>`
+    /// directory under the server's).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+}
+
+impl VirtualHostConfig {
+    /// Check if a request hostname, without its port, selects the profile.
+    #[must_use]
+    pub fn matches_hostname(&self, hostname: &str) -> bool {
+        self.hostnames
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(hostname))
+    }
+
+    /// Strip the path prefix of the profile from a request path.
+    ///
+    /// Returns `None` if the path is not under the prefix. Prefixes match
+    /// whole segments, so `/acme` matches `/acme/api` but not `/acmes`.
+    #[must_use]
+    pub fn strip_prefix<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let prefix = self.path_prefix.as_deref()?.trim_end_matches('/');
+        let rest = path.strip_prefix(prefix)?;
+        if rest.is_empty() {
+            Some("/")
+        } else {
+            rest.starts_with('/').then_some(rest)
+        }
+    }
+
+    /// Get the database configuration of the profile, based on the server's.
+    ///
+    /// Pool settings are shared; the connection target is the profile's.
+    /// URLs starting with `postgres` select the PostgreSQL backend. Without
+    /// either, a profile-managed database stays profile-managed.
+    #[must_use]
+    pub fn database(&self, base: &DatabaseConfig) -> DatabaseConfig {
+        let mut database = base.clone();
+        if self.database_url.is_some() || self.database_path.is_some() {
+            database.mode = DatabaseMode::Explicit;
+        }
+        if let Some(url) = &self.database_url {
+            database.backend = if url.starts_with("postgres") {
+                DatabaseBackend::Postgres
+            } else {
+                DatabaseBackend::Sqlite
+            };
+            database.url = Some(url.clone());
+        } else if let Some(path) = &self.database_path {
+            database.backend = DatabaseBackend::Sqlite;
+            database.url = None;
+            database.path = Some(path.clone());
+        }
+        database
+    }
+
+    /// Validate the virtual host configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.profile.trim().is_empty() {
+            return Err(orbis_core::Error::config("Virtual host profile name cannot be empty"));
+        }
+
+        if self.hostnames.is_empty() && self.path_prefix.is_none() {
+            return Err(orbis_core::Error::config(format!(
+                "Virtual host '{}' needs hostnames or a path prefix",
+                self.profile
+            )));
+        }
+
+        if let Some(prefix) = &self.path_prefix
+            && (!prefix.starts_with('/') || prefix.trim_end_matches('/').is_empty())
+        {
+            return Err(orbis_core::Error::config(format!(
+                "Invalid path prefix '{}' of virtual host '{}'. Expected a path like '/{}'",
+                prefix, self.profile, self.profile
+            )));
+        }
+
+        // Profiles are isolated, so they never share the server's database
+        if self.database_url.is_none() && self.database_path.is_none() {
+            return Err(orbis_core::Error::config(format!(
+                "Virtual host '{}' needs a database_url or database_path",
+                self.profile
+            )));
+        }
+
+        Ok(())
+    }
+}
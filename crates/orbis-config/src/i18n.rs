@@ -0,0 +1,72 @@
+//! Localization configuration.
+
+use crate::Cli;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Localization configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I18nConfig {
+    /// Directory of message catalogs (`<locale>.json`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locales_dir: Option<PathBuf>,
+
+    /// Locale used when none of the requested locales has a translation.
+    pub default_locale: String,
+}
+
+impl I18nConfig {
+    /// Create localization config from CLI arguments.
+    pub fn from_cli(cli: &Cli, file_config: Option<&Self>) -> Self {
+        Self {
+            locales_dir: cli
+                .locales_dir
+                .clone()
+                .or_else(|| file_config.and_then(|c| c.locales_dir.clone())),
+            default_locale: cli
+                .default_locale
+                .clone()
+                .or_else(|| file_config.map(|c| c.default_locale.clone()))
+                .unwrap_or_else(|| Self::default().default_locale),
+        }
+    }
+
+    /// Validate the localization configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the default locale is not a valid language tag.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        let valid = !self.default_locale.is_empty()
+            && self
+                .default_locale
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+        if !valid {
+            return Err(orbis_core::Error::config(format!(
+                "Invalid default locale: '{}'",
+                self.default_locale
+            )));
+        }
+
+        if let Some(dir) = &self.locales_dir
+            && !dir.is_dir()
+        {
+            return Err(orbis_core::Error::config(format!(
+                "Locales directory does not exist: {}",
+                dir.display()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            locales_dir: None,
+            default_locale: "en".to_string(),
+        }
+    }
+}
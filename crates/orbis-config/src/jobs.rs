@@ -0,0 +1,62 @@
+//! Background job queue configuration.
+
+use crate::Cli;
+use serde::{Deserialize, Serialize};
+
+/// Background job queue configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Number of workers running jobs concurrently (0 disables job processing).
+    pub workers: usize,
+
+    /// Seconds a claimed job stays invisible to other workers before it is retried.
+    pub visibility_timeout_seconds: u64,
+
+    /// Default number of attempts before a job is moved to the dead-letter state.
+    pub max_attempts: u32,
+}
+
+impl JobsConfig {
+    /// Create job queue config from CLI arguments.
+    pub fn from_cli(cli: &Cli, file_config: Option<&Self>) -> Self {
+        let defaults = Self::default();
+        Self {
+            workers: cli
+                .job_workers
+                .unwrap_or_else(|| file_config.map_or(defaults.workers, |c| c.workers)),
+            visibility_timeout_seconds: cli.job_visibility_timeout.unwrap_or_else(|| {
+                file_config.map_or(defaults.visibility_timeout_seconds, |c| c.visibility_timeout_seconds)
+            }),
+            max_attempts: cli
+                .job_max_attempts
+                .unwrap_or_else(|| file_config.map_or(defaults.max_attempts, |c| c.max_attempts)),
+        }
+    }
+
+    /// Validate the job queue configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the visibility timeout or attempt count is zero.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.visibility_timeout_seconds == 0 {
+            return Err(orbis_core::Error::config("Job visibility timeout must be at least 1 second"));
+        }
+
+        if self.max_attempts == 0 {
+            return Err(orbis_core::Error::config("Jobs must be allowed at least 1 attempt"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            visibility_timeout_seconds: 300,
+            max_attempts: 5,
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! Generated options for every configuration field.
+//!
+//! Each field of [`Config`] that holds a value or a list of values can be set
+//! with an `ORBIS_*` environment variable and a `--` flag, both named after
+//! its path: `server.cors_origins` is set with `ORBIS_SERVER_CORS_ORIGINS` or
+//! `--server-cors-origins`. The fields are read from the struct definitions
+//! themselves (through their `Deserialize` implementations), so new fields
+//! get options without further changes.
+//!
+//! Generated options are layered over the configuration file and the
+//! dedicated options: environment variables override them, and flags
+//! override environment variables. Fields whose generated name is already
+//! taken by a dedicated option are left to that option.
+
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use crate::{Cli, Config};
+
+/// Prefix of generated environment variables.
+const ENV_PREFIX: &str = "ORBIS_";
+
+/// Prefix of the argument IDs of generated flags.
+const ARG_PREFIX: &str = "config.";
+
+/// Fields without generated options.
+const EXCLUDED_FIELDS: &[&str] = &["config_file"];
+
+/// Type of value a configuration field holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueKind {
+    /// `true` or `false` (also `1`/`0`, `yes`/`no` and `on`/`off`).
+    Bool,
+
+    /// Whole number.
+    Integer,
+
+    /// Decimal number.
+    Float,
+
+    /// Text, including paths.
+    String,
+
+    /// One of a set of names.
+    Choice(&'static [&'static str]),
+
+    /// Comma-separated values.
+    List(Box<Self>),
+}
+
+impl ValueKind {
+    /// Parse a value of this kind from text.
+    fn parse(&self, raw: &str) -> Result<serde_json::Value, String> {
+        let raw = raw.trim();
+        match *self {
+            Self::Bool => match raw.to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Ok(serde_json::Value::Bool(true)),
+                "false" | "0" | "no" | "off" => Ok(serde_json::Value::Bool(false)),
+                _ => Err(format!("expected true or false, got '{}'", raw)),
+            },
+            Self::Integer => raw
+                .parse::<i64>()
+                .map(serde_json::Value::from)
+                .or_else(|_| raw.parse::<u64>().map(serde_json::Value::from))
+                .map_err(|_invalid| format!("expected a whole number, got '{}'", raw)),
+            Self::Float => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| format!("expected a number, got '{}'", raw)),
+            Self::String | Self::Choice(_) => Ok(serde_json::Value::String(raw.to_owned())),
+            Self::List(ref element) => raw
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| element.parse(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(serde_json::Value::Array),
+        }
+    }
+
+    /// Describe the values of this kind, for help texts.
+    fn describe(&self) -> String {
+        match *self {
+            Self::Bool => "true or false".to_owned(),
+            Self::Integer => "a whole number".to_owned(),
+            Self::Float => "a number".to_owned(),
+            Self::String => "text".to_owned(),
+            Self::Choice(names) => names.join(", "),
+            Self::List(ref element) => format!("a comma-separated list of {}", element.describe()),
+        }
+    }
+}
+
+/// A configuration field settable with generated options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigOption {
+    /// Field path from the root of the configuration.
+    pub path: Vec<&'static str>,
+
+    /// Type of value the field holds.
+    pub kind: ValueKind,
+
+    /// Whether the field can be unset, with an empty value.
+    pub optional: bool,
+}
+
+impl ConfigOption {
+    /// Dotted key of the field (e.g. `server.cors_origins`).
+    #[must_use]
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Environment variable setting the field (e.g. `ORBIS_SERVER_CORS_ORIGINS`).
+    #[must_use]
+    pub fn env_var(&self) -> String {
+        format!("{}{}", ENV_PREFIX, self.path.join("_").to_uppercase())
+    }
+
+    /// Flag setting the field, without its dashes (e.g. `server-cors-origins`).
+    #[must_use]
+    pub fn flag(&self) -> String {
+        self.path.join("-").replace('_', "-")
+    }
+
+    /// Parse a value of the field from text; an empty value unsets optional fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a value of the field's kind.
+    pub fn parse(&self, raw: &str) -> Result<serde_json::Value, String> {
+        if self.optional && raw.trim().is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        self.kind.parse(raw)
+    }
+
+    /// Command line argument of the generated flag and environment variable.
+    fn arg(&self) -> Arg {
+        let arg = Arg::new(format!("{}{}", ARG_PREFIX, self.key()))
+            .long(self.flag())
+            .env(self.env_var())
+            .value_name("VALUE")
+            .action(ArgAction::Set)
+            .help(format!("Set `{}` ({})", self.key(), self.kind.describe()))
+            .help_heading("Configuration")
+            .hide_short_help(true);
+        if self.kind == ValueKind::Bool {
+            arg.num_args(0..=1).default_missing_value("true")
+        } else {
+            arg
+        }
+    }
+}
+
+/// A configuration field set by a generated option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigOverride {
+    /// Field set.
+    pub option: ConfigOption,
+
+    /// Value, as given.
+    pub value: String,
+
+    /// Environment variable or flag the value came from.
+    pub source: String,
+}
+
+/// Get the configuration fields with generated options, leaving out those
+/// whose flag or environment variable is taken by a dedicated option.
+#[must_use]
+pub fn config_options() -> Vec<ConfigOption> {
+    let command = Cli::command();
+    let dedicated = |option: &ConfigOption| {
+        let (flag, env_var) = (option.flag(), option.env_var());
+        command
+            .get_arguments()
+            .any(|arg| arg.get_long() == Some(flag.as_str()) || arg.get_env().is_some_and(|env| *env == *env_var))
+    };
+
+    all_fields()
+        .into_iter()
+        .filter(|option| !option.path.first().is_some_and(|field| EXCLUDED_FIELDS.contains(field)))
+        .filter(|option| !dedicated(option))
+        .collect()
+}
+
+/// Get every field of the configuration holding a value or list of values.
+fn all_fields() -> Vec<ConfigOption> {
+    let mut fields = Vec::new();
+    if let Err(e) = Config::deserialize(Probe::new(Vec::new(), &mut fields)) {
+        tracing::warn!("Could not list all configuration fields: {}", e);
+    }
+    fields
+}
+
+impl Cli {
+    /// Parse command line arguments, including the generated flags and
+    /// environment variables of configuration fields.
+    ///
+    /// Exits with a usage error if the arguments are invalid.
+    #[must_use]
+    pub fn parse_layered() -> Self {
+        use clap::FromArgMatches;
+
+        let options = config_options();
+        let mut command = Self::command().args(options.iter().map(ConfigOption::arg));
+        let matches = command.get_matches_mut();
+        #[allow(clippy::exit, reason = "Usage errors exit like `Parser::parse`")]
+        let mut cli = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.format(&mut command).exit());
+        cli.config_overrides = overrides(&options, &matches);
+        cli
+    }
+}
+
+/// Collect the values of generated options, environment variables first.
+fn overrides(options: &[ConfigOption], matches: &ArgMatches) -> Vec<ConfigOverride> {
+    let mut overrides: Vec<ConfigOverride> = options
+        .iter()
+        .filter_map(|option| {
+            let id = format!("{}{}", ARG_PREFIX, option.key());
+            let value = matches.get_one::<String>(&id)?;
+            let source = if matches.value_source(&id) == Some(clap::parser::ValueSource::EnvVariable) {
+                option.env_var()
+            } else {
+                format!("--{}", option.flag())
+            };
+            Some(ConfigOverride {
+                option: option.clone(),
+                value: value.clone(),
+                source,
+            })
+        })
+        .collect();
+    overrides.sort_by_key(|entry| !entry.source.starts_with(ENV_PREFIX));
+    overrides
+}
+
+/// Apply generated options to a configuration, in order.
+///
+/// # Errors
+///
+/// Returns an error naming the option if a value is invalid.
+pub fn apply_overrides(config: &Config, overrides: &[ConfigOverride]) -> orbis_core::Result<Config> {
+    if overrides.is_empty() {
+        return Ok(config.clone());
+    }
+
+    let mut value = serde_json::to_value(config)
+        .map_err(|e| orbis_core::Error::config(format!("Failed to serialize config: {}", e)))?;
+    for entry in overrides {
+        let invalid = |message: String| {
+            orbis_core::Error::config(format!("Invalid value for {}: {}", entry.source, message))
+        };
+        let parsed = entry.option.parse(&entry.value).map_err(&invalid)?;
+        set_path(&mut value, &entry.option.path, parsed);
+        Config::deserialize(&value).map_err(|e| invalid(e.to_string()))?;
+    }
+
+    let mut layered = Config::deserialize(&value)
+        .map_err(|e| orbis_core::Error::config(format!("Invalid configuration: {}", e)))?;
+
+    // Fields that are never serialized
+    layered.database.encryption_key.clone_from(&config.database.encryption_key);
+    if layered.email.smtp_password.is_none() {
+        layered.email.smtp_password.clone_from(&config.email.smtp_password);
+    }
+    Ok(layered)
+}
+
+/// Set a value in a JSON tree, creating the objects on its path.
+fn set_path(root: &mut serde_json::Value, path: &[&str], value: serde_json::Value) {
+    let Some((last, parents)) = path.split_last() else {
+        *root = value;
+        return;
+    };
+
+    let mut node = root;
+    for key in parents {
+        if !node.is_object() {
+            *node = serde_json::Value::Object(serde_json::Map::new());
+        }
+        let Some(object) = node.as_object_mut() else {
+            return;
+        };
+        node = object
+            .entry((*key).to_owned())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    }
+    if let Some(object) = node.as_object_mut() {
+        object.insert((*last).to_owned(), value);
+    }
+}
+
+/// Deserializer recording the fields of the type it deserializes, feeding
+/// it placeholder values.
+struct Probe<'a> {
+    /// Path of the value being deserialized.
+    path: Vec<&'static str>,
+
+    /// Whether the value is optional.
+    optional: bool,
+
+    /// Fields recorded so far.
+    fields: &'a mut Vec<ConfigOption>,
+}
+
+impl<'a> Probe<'a> {
+    /// Create a probe of a required value.
+    const fn new(path: Vec<&'static str>, fields: &'a mut Vec<ConfigOption>) -> Self {
+        Self {
+            path,
+            optional: false,
+            fields,
+        }
+    }
+
+    /// Record the value as a field of a kind.
+    fn record(self, kind: ValueKind) {
+        if !self.path.is_empty() {
+            self.fields.push(ConfigOption {
+                path: self.path,
+                kind,
+                optional: self.optional,
+            });
+        }
+    }
+}
+
+/// Record a scalar field and feed its visitor a placeholder.
+macro_rules! probe_scalar {
+    ($($method:ident => $kind:ident, $visit:ident($value:expr);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.record(ValueKind::$kind);
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Probe<'_> {
+    type Error = de::value::Error;
+
+    probe_scalar! {
+        deserialize_bool => Bool, visit_bool(false);
+        deserialize_i8 => Integer, visit_i64(0);
+        deserialize_i16 => Integer, visit_i64(0);
+        deserialize_i32 => Integer, visit_i64(0);
+        deserialize_i64 => Integer, visit_i64(0);
+        deserialize_u8 => Integer, visit_u64(0);
+        deserialize_u16 => Integer, visit_u64(0);
+        deserialize_u32 => Integer, visit_u64(0);
+        deserialize_u64 => Integer, visit_u64(0);
+        deserialize_f32 => Float, visit_f64(0.0);
+        deserialize_f64 => Float, visit_f64(0.0);
+        deserialize_char => String, visit_char('-');
+        deserialize_str => String, visit_str("");
+        deserialize_string => String, visit_str("");
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // Untyped values have no generated options
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(Probe {
+            optional: true,
+            ..self
+        })
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut elements = Vec::new();
+        let value = visitor.visit_seq(Element {
+            probe: Some(Probe::new(self.path.clone(), &mut elements)),
+        })?;
+
+        // Only lists of values have generated options
+        if let [ref element] = *elements.as_slice()
+            && element.path == self.path
+            && !matches!(element.kind, ValueKind::List(_))
+        {
+            let kind = ValueKind::List(Box::new(element.kind.clone()));
+            self.record(kind);
+        }
+        Ok(value)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // Maps have no generated options
+        visitor.visit_map(de::value::MapDeserializer::new(std::iter::empty::<((), ())>()))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(Fields {
+            path: self.path,
+            names: fields.iter(),
+            current: None,
+            fields: self.fields,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let first = variants.first().copied().unwrap_or_default();
+        self.record(ValueKind::Choice(variants));
+        visitor.visit_enum(IntoDeserializer::<Self::Error>::into_deserializer(first))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct tuple tuple_struct identifier ignored_any
+    }
+}
+
+/// Fields of a struct being probed.
+struct Fields<'a> {
+    /// Path of the struct.
+    path: Vec<&'static str>,
+
+    /// Names of the fields left.
+    names: std::slice::Iter<'static, &'static str>,
+
+    /// Name of the field whose value is next.
+    current: Option<&'static str>,
+
+    /// Fields recorded so far.
+    fields: &'a mut Vec<ConfigOption>,
+}
+
+impl<'de> MapAccess<'de> for Fields<'_> {
+    type Error = de::value::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        let Some(&name) = self.names.next() else {
+            return Ok(None);
+        };
+        self.current = Some(name);
+        seed.deserialize(IntoDeserializer::<Self::Error>::into_deserializer(name))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let mut path = self.path.clone();
+        path.extend(self.current);
+        seed.deserialize(Probe::new(path, &mut *self.fields))
+    }
+}
+
+/// A single placeholder element of a list being probed.
+struct Element<'a> {
+    /// Probe of the element, until it is taken.
+    probe: Option<Probe<'a>>,
+}
+
+impl<'de> SeqAccess<'de> for Element<'_> {
+    type Error = de::value::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        self.probe.take().map(|probe| seed.deserialize(probe)).transpose()
+    }
+}
@@ -6,18 +6,43 @@
 //! - Command line arguments (highest priority)
 //! - Environment variables (prefixed with `ORBIS_`)
 //! - Configuration file (lowest priority)
+//!
+//! Besides the dedicated options, every configuration field gets a
+//! generated flag and environment variable (see [`Cli::parse_layered`]).
 
 mod cli;
 mod database;
+mod email;
+mod events;
+mod hosts;
+mod i18n;
+mod jobs;
+mod layers;
+mod log_file;
 mod logging;
+mod plugins;
 mod server;
+mod sessions;
+mod storage;
+mod tenancy;
 mod tls;
 
-pub use cli::{Cli, Commands};
-pub use database::{DatabaseConfig, DatabaseBackend};
-pub use logging::{LogConfig, LogFormat};
-pub use server::ServerConfig;
-pub use tls::TlsConfig;
+pub use cli::{BackupCommands, Cli, Commands, DbCommands, PluginCommands, ProfileCommands, UserCommands};
+pub use database::{DatabaseBackend, DatabaseConfig, DatabaseMode, PROFILE_DATABASE_FILE};
+pub use email::{EmailConfig, SmtpSecurity};
+pub use events::EventsConfig;
+pub use hosts::VirtualHostConfig;
+pub use i18n::I18nConfig;
+pub use jobs::JobsConfig;
+pub use layers::{apply_overrides, config_options, ConfigOption, ConfigOverride, ValueKind};
+pub use log_file::LogRotation;
+pub use logging::{LogConfig, LogFormat, LogLevels};
+pub use plugins::{PluginNetworkAccess, PluginPolicyConfig, PluginsConfig};
+pub use server::{CompressionAlgorithm, ServerConfig};
+pub use sessions::{CookieSessionConfig, CsrfProtection, SameSitePolicy};
+pub use storage::{LocalStorageConfig, S3StorageConfig, StorageBackend, StorageConfig};
+pub use tenancy::{TenancyConfig, TenancyMode};
+pub use tls::{ClientAuthMode, TlsConfig};
 
 use orbis_core::{AppMode, RunMode};
 use parking_lot::RwLock;
@@ -47,12 +72,10 @@ pub fn get_config() -> Arc<RwLock<Config>> {
 ///
 /// Returns an error if configuration is invalid.
 pub fn init_config() -> orbis_core::Result<Arc<RwLock<Config>>> {
-    use clap::Parser;
-
     // Load .env file if present
     let _ = dotenvy::dotenv();
 
-    let cli = Cli::parse();
+    let cli = Cli::parse_layered();
     let config = Config::from_cli(&cli)?;
 
     let config = Arc::new(RwLock::new(config));
@@ -84,6 +107,42 @@ pub struct Config {
     /// Logging configuration.
     pub log: LogConfig,
 
+    /// Multi-tenancy configuration.
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+
+    /// Background job queue configuration.
+    #[serde(default)]
+    pub jobs: JobsConfig,
+
+    /// Email configuration.
+    #[serde(default)]
+    pub email: EmailConfig,
+
+    /// Localization configuration.
+    #[serde(default)]
+    pub i18n: I18nConfig,
+
+    /// Plugin policy and its per-plugin overrides (configuration file only).
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+
+    /// Cookie sessions of the web client and their CSRF protection.
+    #[serde(default)]
+    pub cookie_sessions: CookieSessionConfig,
+
+    /// Object storage of plugin files (configuration file only).
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Delivery of server events through server-sent events and long polling.
+    #[serde(default)]
+    pub events: EventsConfig,
+
+    /// Profiles served on virtual hosts of this server (configuration file only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub virtual_hosts: Vec<VirtualHostConfig>,
+
     /// Path to configuration file.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_file: Option<PathBuf>,
@@ -96,6 +155,53 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plugins_dir: Option<PathBuf>,
 
+    /// Load plugins whose `core_version` excludes the host API version, with a warning.
+    #[serde(default)]
+    pub allow_incompatible_plugins: bool,
+
+    /// Watch the plugins directory and reload plugins when their files change.
+    #[serde(default)]
+    pub plugin_hot_reload: bool,
+
+    /// Enable plugin development tools: invoking handlers directly and
+    /// capturing requests to replay them.
+    #[serde(default)]
+    pub plugin_dev_mode: bool,
+
+    /// Keep cached plugin route responses on disk (under the data directory).
+    #[serde(default)]
+    pub response_cache_on_disk: bool,
+
+    /// What to do with unsigned or badly signed standalone plugins
+    /// (`off`, `warn` or `require`).
+    #[serde(default = "default_plugin_signatures")]
+    pub plugin_signatures: String,
+
+    /// Directory of public keys trusted to sign plugins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_trusted_keys_dir: Option<PathBuf>,
+
+    /// Plugin handler calls slower than this are recorded as slow, and
+    /// handlers whose p95 latency exceeds it are flagged, in milliseconds.
+    #[serde(default = "default_plugin_slow_handler_ms")]
+    pub plugin_slow_handler_ms: u64,
+
+    /// Plugin handlers failing more often than this fraction of calls are flagged.
+    #[serde(default = "default_plugin_handler_error_rate")]
+    pub plugin_handler_error_rate: f64,
+
+    /// JSON file of the security policy plugin host calls are checked against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_policy_file: Option<PathBuf>,
+
+    /// JSON file of plugin resource alert rules and the webhooks alerts are posted to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_alerts_file: Option<PathBuf>,
+
+    /// Directory replay files of recorded plugin handlers are saved in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_replay_dir: Option<PathBuf>,
+
     /// Path to data directory.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data_dir: Option<PathBuf>,
@@ -113,10 +219,82 @@ pub struct Config {
 
     /// JWT token expiry in seconds.
     pub jwt_expiry_seconds: u64,
+
+    /// JWT signing algorithm: `HS256` (shared secret), `RS256` or `EdDSA` (key pair).
+    #[serde(default = "default_jwt_algorithm")]
+    pub jwt_algorithm: String,
+
+    /// PEM private key signing tokens (`RS256` and `EdDSA`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_private_key_file: Option<PathBuf>,
+
+    /// PEM public key validating tokens (`RS256` and `EdDSA`); derived from
+    /// the private key if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_public_key_file: Option<PathBuf>,
+
+    /// JSON file of JWT signing keys and when each becomes the signing key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_keys_file: Option<PathBuf>,
+
+    /// Seconds tokens signed by a retired JWT key are still accepted.
+    #[serde(default = "default_jwt_key_grace_seconds")]
+    pub jwt_key_grace_seconds: u64,
+
+    /// Longest an admin can impersonate a user, in minutes.
+    #[serde(default = "default_impersonation_max_minutes")]
+    pub impersonation_max_minutes: u64,
+
+    /// Whether impersonation tokens can make non-destructive writes.
+    #[serde(default)]
+    pub impersonation_allow_writes: bool,
+
+    /// Days before an account a user asked to delete is deleted.
+    #[serde(default = "default_account_deletion_grace_days")]
+    pub account_deletion_grace_days: u64,
+}
+
+/// Default plugin signature policy.
+fn default_plugin_signatures() -> String {
+    "off".to_owned()
+}
+
+/// Default slow plugin handler threshold, in milliseconds.
+const fn default_plugin_slow_handler_ms() -> u64 {
+    1000
+}
+
+/// Default plugin handler error rate threshold.
+const fn default_plugin_handler_error_rate() -> f64 {
+    0.05
+}
+
+/// Default JWT signing algorithm.
+fn default_jwt_algorithm() -> String {
+    "HS256".to_owned()
+}
+
+/// Default JWT key grace window, in seconds (the default refresh token lifetime).
+const fn default_jwt_key_grace_seconds() -> u64 {
+    7 * 24 * 3600
+}
+
+/// Default longest impersonation, in minutes.
+const fn default_impersonation_max_minutes() -> u64 {
+    60
+}
+
+/// Longest account deletion grace period, in days.
+const MAX_ACCOUNT_DELETION_GRACE_DAYS: u64 = 3650;
+
+/// Default account deletion grace period, in days.
+const fn default_account_deletion_grace_days() -> u64 {
+    30
 }
 
 impl Config {
-    /// Create configuration from CLI arguments.
+    /// Create configuration from CLI arguments, then apply the generated
+    /// options they carry.
     ///
     /// # Errors
     ///
@@ -153,13 +331,37 @@ impl Config {
                 || file_config.as_ref().is_some_and(|c| c.auth_enabled)
         };
 
-        Ok(Self {
+        let config = Self {
             mode,
             run_mode,
             server: ServerConfig::from_cli(cli, file_config.as_ref().map(|c| &c.server)),
             database: DatabaseConfig::from_cli(cli, file_config.as_ref().map(|c| &c.database)),
             tls: TlsConfig::from_cli(cli, file_config.as_ref().map(|c| &c.tls)),
             log: LogConfig::from_cli(cli, file_config.as_ref().map(|c| &c.log)),
+            tenancy: TenancyConfig::from_cli(cli, file_config.as_ref().map(|c| &c.tenancy)),
+            jobs: JobsConfig::from_cli(cli, file_config.as_ref().map(|c| &c.jobs)),
+            email: EmailConfig::from_cli(cli, file_config.as_ref().map(|c| &c.email)),
+            i18n: I18nConfig::from_cli(cli, file_config.as_ref().map(|c| &c.i18n)),
+            plugins: file_config
+                .as_ref()
+                .map(|c| c.plugins.clone())
+                .unwrap_or_default(),
+            cookie_sessions: file_config
+                .as_ref()
+                .map(|c| c.cookie_sessions.clone())
+                .unwrap_or_default(),
+            storage: file_config
+                .as_ref()
+                .map(|c| c.storage.clone())
+                .unwrap_or_default(),
+            events: file_config
+                .as_ref()
+                .map(|c| c.events.clone())
+                .unwrap_or_default(),
+            virtual_hosts: file_config
+                .as_ref()
+                .map(|c| c.virtual_hosts.clone())
+                .unwrap_or_default(),
             config_file: cli.config.clone(),
             profiles_dir: cli.profiles_dir.clone().or_else(|| {
                 file_config
@@ -171,6 +373,48 @@ impl Config {
                     .as_ref()
                     .and_then(|c| c.plugins_dir.clone())
             }),
+            allow_incompatible_plugins: cli.allow_incompatible_plugins
+                || file_config.as_ref().is_some_and(|c| c.allow_incompatible_plugins),
+            plugin_hot_reload: cli.plugin_hot_reload
+                || file_config.as_ref().is_some_and(|c| c.plugin_hot_reload),
+            plugin_dev_mode: cli.plugin_dev_mode || file_config.as_ref().is_some_and(|c| c.plugin_dev_mode),
+            response_cache_on_disk: cli.response_cache_on_disk
+                || file_config.as_ref().is_some_and(|c| c.response_cache_on_disk),
+            plugin_signatures: cli.plugin_signatures.clone().unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_plugin_signatures, |c| c.plugin_signatures.clone())
+            }),
+            plugin_slow_handler_ms: cli.plugin_slow_handler_ms.unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_plugin_slow_handler_ms, |c| c.plugin_slow_handler_ms)
+            }),
+            plugin_handler_error_rate: cli.plugin_handler_error_rate.unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_plugin_handler_error_rate, |c| c.plugin_handler_error_rate)
+            }),
+            plugin_policy_file: cli.plugin_policy_file.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.plugin_policy_file.clone())
+            }),
+            plugin_alerts_file: cli.plugin_alerts_file.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.plugin_alerts_file.clone())
+            }),
+            plugin_replay_dir: cli.plugin_replay_dir.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.plugin_replay_dir.clone())
+            }),
+            plugin_trusted_keys_dir: cli.plugin_trusted_keys_dir.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.plugin_trusted_keys_dir.clone())
+            }),
             data_dir: cli.data_dir.clone().or_else(|| {
                 file_config.as_ref().and_then(|c| c.data_dir.clone())
             }),
@@ -191,7 +435,60 @@ impl Config {
                     .map(|c| c.jwt_expiry_seconds)
                     .unwrap_or(3600)
             }),
-        })
+            jwt_algorithm: cli.jwt_algorithm.clone().unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_jwt_algorithm, |c| c.jwt_algorithm.clone())
+            }),
+            jwt_private_key_file: cli.jwt_private_key_file.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.jwt_private_key_file.clone())
+            }),
+            jwt_public_key_file: cli.jwt_public_key_file.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.jwt_public_key_file.clone())
+            }),
+            jwt_keys_file: cli.jwt_keys_file.clone().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|c| c.jwt_keys_file.clone())
+            }),
+            jwt_key_grace_seconds: cli.jwt_key_grace_seconds.unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_jwt_key_grace_seconds, |c| c.jwt_key_grace_seconds)
+            }),
+            impersonation_max_minutes: cli.impersonation_max_minutes.unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_impersonation_max_minutes, |c| c.impersonation_max_minutes)
+            }),
+            impersonation_allow_writes: cli.impersonation_allow_writes
+                || file_config
+                    .as_ref()
+                    .is_some_and(|c| c.impersonation_allow_writes),
+            account_deletion_grace_days: cli.account_deletion_grace_days.unwrap_or_else(|| {
+                file_config
+                    .as_ref()
+                    .map_or_else(default_account_deletion_grace_days, |c| c.account_deletion_grace_days)
+            }),
+        };
+
+        let mut config = apply_overrides(&config, &cli.config_overrides)?;
+        if let Some(dir) = config.profile_data_dir() {
+            config.database = config.database.for_profile_dir(&dir);
+        }
+        Ok(config)
+    }
+
+    /// Get the data directory of the active profile (`default` when none is
+    /// selected), inside the data directory.
+    #[must_use]
+    pub fn profile_data_dir(&self) -> Option<PathBuf> {
+        let profile = self.active_profile.as_deref().unwrap_or("default");
+        self.data_dir.as_ref().map(|dir| dir.join("profiles").join(profile))
     }
 
     /// Load configuration from a TOML file.
@@ -227,10 +524,20 @@ impl Config {
     ///
     /// Returns an error if the configuration is invalid.
     pub fn validate(&self) -> orbis_core::Result<()> {
-        // In client-server mode, JWT secret is required
-        if self.mode.is_client_server() && self.jwt_secret.is_none() {
+        // In client-server mode, JWT signing keys are required
+        let has_default_key = match self.jwt_algorithm.as_str() {
+            "HS256" => self.jwt_secret.is_some(),
+            "RS256" | "EdDSA" => self.jwt_private_key_file.is_some() || self.jwt_public_key_file.is_some(),
+            other => {
+                return Err(orbis_core::Error::config(format!(
+                    "Invalid JWT algorithm '{}'. Use HS256, RS256 or EdDSA",
+                    other
+                )));
+            }
+        };
+        if self.mode.is_client_server() && !has_default_key && self.jwt_keys_file.is_none() {
             return Err(orbis_core::Error::config(
-                "JWT secret is required in client-server mode. Set ORBIS_JWT_SECRET or --jwt-secret",
+                "JWT signing keys are required in client-server mode. Set ORBIS_JWT_SECRET (or ORBIS_JWT_PRIVATE_KEY_FILE with RS256 or EdDSA) or ORBIS_JWT_KEYS_FILE",
             ));
         }
 
@@ -243,9 +550,126 @@ impl Config {
         // Validate TLS config
         self.tls.validate()?;
 
+        // Validate job queue config
+        self.jobs.validate()?;
+        self.email.validate()?;
+        self.i18n.validate()?;
+        self.plugins.validate()?;
+        self.cookie_sessions.validate()?;
+        self.storage.validate()?;
+        self.events.validate()?;
+        self.log.validate()?;
+
+        if self.impersonation_max_minutes == 0 {
+            return Err(orbis_core::Error::config("Impersonation must be allowed for at least 1 minute"));
+        }
+        if self.account_deletion_grace_days > MAX_ACCOUNT_DELETION_GRACE_DAYS {
+            return Err(orbis_core::Error::config(format!(
+                "Account deletion grace period cannot exceed {} days",
+                MAX_ACCOUNT_DELETION_GRACE_DAYS
+            )));
+        }
+
+        // Validate plugin signature policy
+        match self.plugin_signatures.to_lowercase().as_str() {
+            "off" => {}
+            "warn" | "require" if self.plugin_trusted_keys_dir.is_some() => {}
+            "warn" | "require" => {
+                return Err(orbis_core::Error::config(
+                    "Checking plugin signatures requires a trusted keys directory. Set ORBIS_PLUGIN_TRUSTED_KEYS_DIR",
+                ));
+            }
+            other => {
+                return Err(orbis_core::Error::config(format!(
+                    "Invalid plugin signature policy: '{}'. Expected 'off', 'warn' or 'require'",
+                    other
+                )));
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.plugin_handler_error_rate) {
+            return Err(orbis_core::Error::config(format!(
+                "Invalid plugin handler error rate: {}. Expected a fraction between 0 and 1",
+                self.plugin_handler_error_rate
+            )));
+        }
+
+        // Tenants are isolated through authentication, which standalone mode may skip
+        if self.tenancy.mode.is_enabled() {
+            if !self.mode.is_client_server() {
+                return Err(orbis_core::Error::config(
+                    "Multi-tenancy is only available in client-server mode",
+                ));
+            }
+            self.tenancy.validate()?;
+        }
+
+        self.validate_virtual_hosts()
+    }
+
+    /// Validate the virtual hosts and check they select distinct profiles.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a virtual host is invalid or overlaps another.
+    fn validate_virtual_hosts(&self) -> orbis_core::Result<()> {
+        let mut profiles = std::collections::HashSet::new();
+        let mut hostnames = std::collections::HashSet::new();
+        let mut prefixes = std::collections::HashSet::new();
+
+        for host in &self.virtual_hosts {
+            host.validate()?;
+            host.database(&self.database).validate()?;
+
+            if !profiles.insert(host.profile.as_str()) {
+                return Err(orbis_core::Error::config(format!(
+                    "Profile '{}' is served on more than one virtual host",
+                    host.profile
+                )));
+            }
+            for hostname in &host.hostnames {
+                if !hostnames.insert(hostname.to_lowercase()) {
+                    return Err(orbis_core::Error::config(format!(
+                        "Hostname '{}' selects more than one virtual host",
+                        hostname
+                    )));
+                }
+            }
+            if let Some(prefix) = &host.path_prefix
+                && !prefixes.insert(prefix.trim_end_matches('/'))
+            {
+                return Err(orbis_core::Error::config(format!(
+                    "Path prefix '{}' selects more than one virtual host",
+                    prefix
+                )));
+            }
+        }
+
         Ok(())
     }
 
+    /// Get the configuration of a profile served on a virtual host.
+    ///
+    /// The profile shares the server settings and uses its own database,
+    /// plugins directory and data directory.
+    #[must_use]
+    pub fn for_virtual_host(&self, host: &VirtualHostConfig) -> Self {
+        let mut config = self.clone();
+        config.plugins_dir = host.plugins_dir.clone().or_else(|| self.plugins_dir.clone());
+        config.data_dir = host.data_dir.clone().or_else(|| {
+            self.data_dir
+                .as_ref()
+                .map(|dir| dir.join("profiles").join(&host.profile))
+        });
+        config.database = host.database(&self.database);
+        if let Some(dir) = config.data_dir.as_deref() {
+            config.database = config.database.for_profile_dir(dir);
+        }
+        config.active_profile = Some(host.profile.clone());
+        config.virtual_hosts = Vec::new();
+        config
+    }
+
     /// Check if TLS is enabled.
     #[must_use]
     pub const fn is_tls_enabled(&self) -> bool {
@@ -274,14 +698,42 @@ impl Default for Config {
             database: DatabaseConfig::default(),
             tls: TlsConfig::default(),
             log: LogConfig::default(),
+            tenancy: TenancyConfig::default(),
+            jobs: JobsConfig::default(),
+            email: EmailConfig::default(),
+            i18n: I18nConfig::default(),
+            plugins: PluginsConfig::default(),
+            cookie_sessions: CookieSessionConfig::default(),
+            storage: StorageConfig::default(),
+            events: EventsConfig::default(),
+            virtual_hosts: Vec::new(),
             config_file: None,
             profiles_dir: None,
             plugins_dir: None,
+            allow_incompatible_plugins: false,
+            plugin_hot_reload: false,
+            plugin_dev_mode: false,
+            response_cache_on_disk: false,
+            plugin_signatures: default_plugin_signatures(),
+            plugin_trusted_keys_dir: None,
+            plugin_slow_handler_ms: default_plugin_slow_handler_ms(),
+            plugin_handler_error_rate: default_plugin_handler_error_rate(),
+            plugin_policy_file: None,
+            plugin_alerts_file: None,
+            plugin_replay_dir: None,
             data_dir: None,
             active_profile: None,
             auth_enabled: false,
             jwt_secret: None,
             jwt_expiry_seconds: 3600,
+            jwt_algorithm: default_jwt_algorithm(),
+            jwt_private_key_file: None,
+            jwt_public_key_file: None,
+            jwt_keys_file: None,
+            jwt_key_grace_seconds: default_jwt_key_grace_seconds(),
+            impersonation_max_minutes: default_impersonation_max_minutes(),
+            impersonation_allow_writes: false,
+            account_deletion_grace_days: default_account_deletion_grace_days(),
         }
     }
 }
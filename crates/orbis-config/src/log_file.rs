@@ -0,0 +1,193 @@
+//! Log file with size and time based rotation.
+//!
+//! The current log is always written to the configured path. When it is
+//! rotated, it is renamed with the time of the rotation appended
+//! (`orbis.log.20261017T120000`) and a new file is started; the oldest
+//! rotated files beyond the retention are deleted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// When the log file is rotated, besides reaching its size limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// Only when the file reaches its size limit.
+    Never,
+
+    /// Every hour.
+    Hourly,
+
+    /// Every day (default).
+    #[default]
+    Daily,
+}
+
+impl LogRotation {
+    /// Get the period a moment falls in, or `None` if the file is never
+    /// rotated on time.
+    fn period(self, at: DateTime<Utc>) -> Option<String> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(at.format("%Y%m%d%H").to_string()),
+            Self::Daily => Some(at.format("%Y%m%d").to_string()),
+        }
+    }
+}
+
+/// Log file rotated by size and time.
+#[derive(Debug)]
+pub struct RotatingFile {
+    /// Path of the current log file.
+    path: PathBuf,
+
+    /// When the file is rotated on time.
+    rotation: LogRotation,
+
+    /// Size the file is rotated at, in bytes (0 for no limit).
+    max_bytes: u64,
+
+    /// Rotated files kept (0 to keep all).
+    max_files: usize,
+
+    /// Current file, unless it could not be reopened after a rotation.
+    file: Option<File>,
+
+    /// Size of the current file.
+    size: u64,
+
+    /// Period the current file was started in.
+    period: Option<String>,
+}
+
+impl RotatingFile {
+    /// Open the log file, creating it and its directory if needed.
+    ///
+    /// A file left from an earlier period is rotated right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub fn open(path: PathBuf, rotation: LogRotation, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let started = metadata.modified().map_or_else(|_| Utc::now(), DateTime::<Utc>::from);
+        let mut log = Self {
+            path,
+            rotation,
+            max_bytes,
+            max_files,
+            file: Some(file),
+            size: metadata.len(),
+            period: rotation.period(started),
+        };
+
+        if log.size > 0 && log.period != rotation.period(Utc::now()) {
+            log.rotate()?;
+        }
+        Ok(log)
+    }
+
+    /// Rename the current file and start a new one, deleting the oldest
+    /// rotated files beyond the retention.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+
+        let now = Utc::now();
+        let stamp = now.format("%Y%m%dT%H%M%S").to_string();
+        let mut target = rotated_path(&self.path, &stamp);
+        let mut attempt = 1u32;
+        while target.exists() {
+            attempt = attempt.saturating_add(1);
+            target = rotated_path(&self.path, &format!("{}-{}", stamp, attempt));
+        }
+        if self.path.exists() {
+            std::fs::rename(&self.path, &target)?;
+            self.prune();
+        }
+
+        self.file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        self.size = 0;
+        self.period = self.rotation.period(now);
+        Ok(())
+    }
+
+    /// Delete the oldest rotated files beyond the retention.
+    fn prune(&self) {
+        if self.max_files == 0 {
+            return;
+        }
+
+        let mut rotated = rotated_files(&self.path);
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for old in rotated.into_iter().take(excess) {
+            if let Err(e) = std::fs::remove_file(&old) {
+                // Logging from the log writer would recurse
+                drop(writeln!(std::io::stderr(), "Failed to delete old log file {:?}: {}", old, e));
+            }
+        }
+    }
+
+    /// Check if writing some bytes needs a rotation first.
+    fn needs_rotation(&self, len: usize) -> bool {
+        let full = self.max_bytes > 0
+            && self.size > 0
+            && self.size.saturating_add(len as u64) > self.max_bytes;
+        full || (self.period.is_some() && self.period != self.rotation.period(Utc::now()))
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.file.is_none() || self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+
+        let written = match self.file.as_mut() {
+            Some(file) => file.write(buf)?,
+            None => 0,
+        };
+        self.size = self.size.saturating_add(written as u64);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), Write::flush)
+    }
+}
+
+/// Get the path of a rotated log file.
+fn rotated_path(path: &Path, stamp: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(stamp);
+    path.with_file_name(name)
+}
+
+/// List the rotated files of a log file.
+fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let prefix = format!("{}.", path.file_name().unwrap_or_default().to_string_lossy());
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    std::fs::read_dir(dir).map_or_else(
+        |_| Vec::new(),
+        |entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    name.strip_prefix(&prefix)
+                        .is_some_and(|stamp| stamp.starts_with(|c: char| c.is_ascii_digit()))
+                })
+                .map(|entry| entry.path())
+                .collect()
+        },
+    )
+}
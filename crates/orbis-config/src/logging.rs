@@ -1,10 +1,29 @@
 //! Logging configuration.
+//!
+//! Logs are written to stderr and, when `file` is set, exported to a file
+//! rotated by size and time, in JSON by default so log shippers can read it.
+//! Each subsystem's level can be overridden, and the file output and levels
+//! are reconfigured at runtime when the configuration file changes.
 
-use crate::Cli;
+use crate::log_file::{LogRotation, RotatingFile};
+use crate::{Cli, Config};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tracing::Level;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tracing::level_filters::LevelFilter;
+use tracing::{Level, Subscriber};
+use tracing_appender::non_blocking::{NonBlocking, NonBlockingBuilder, WorkerGuard};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::OptionalWriter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer};
+
+/// Installed logging, reconfigured by [`LogConfig::reload`].
+static LOGGING: OnceLock<LogHandle> = OnceLock::new();
 
 /// Log output format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -37,8 +56,61 @@ impl std::str::FromStr for LogFormat {
     }
 }
 
+/// Level overrides per subsystem (`[log.levels]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogLevels {
+    /// Server, API and authentication.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+
+    /// Plugin runtime, including what plugins log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<String>,
+
+    /// Database access and queries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db: Option<String>,
+
+    /// Page and UI schema DSL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dsl: Option<String>,
+}
+
+impl LogLevels {
+    /// Get the subsystems with an override: name, level and log targets.
+    fn overrides(&self) -> impl Iterator<Item = (&'static str, &str, &'static [&'static str])> {
+        [
+            ("server", self.server.as_deref(), &["orbis_server", "orbis_auth"][..]),
+            ("plugins", self.plugins.as_deref(), &["orbis_plugin"][..]),
+            ("db", self.db.as_deref(), &["orbis_db", "sqlx"][..]),
+            ("dsl", self.dsl.as_deref(), &["orbis_plugin_api"][..]),
+        ]
+        .into_iter()
+        .filter_map(|(name, level, targets)| level.map(|level| (name, level, targets)))
+    }
+
+    /// Validate the overrides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a level is not a log level.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        for (name, level, _) in self.overrides() {
+            if level.parse::<LevelFilter>().is_err() {
+                return Err(orbis_core::Error::config(format!(
+                    "Invalid {} log level '{}'. Expected trace, debug, info, warn, error or off",
+                    name, level
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Logging configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LogConfig {
     /// Log level.
     pub level: String,
@@ -46,10 +118,25 @@ pub struct LogConfig {
     /// Log format.
     pub format: LogFormat,
 
-    /// Log file path.
+    /// Log file path; logs are exported there as well as to stderr.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<PathBuf>,
 
+    /// Format of the log file.
+    pub file_format: LogFormat,
+
+    /// When the log file is rotated, besides reaching `max_file_bytes`.
+    pub rotation: LogRotation,
+
+    /// Size the log file is rotated at, in bytes (0 for no limit).
+    pub max_file_bytes: u64,
+
+    /// Rotated log files kept (0 to keep all).
+    pub max_files: usize,
+
+    /// Level overrides per subsystem.
+    pub levels: LogLevels,
+
     /// Include file and line numbers in logs.
     pub include_file_line: bool,
 
@@ -63,6 +150,16 @@ pub struct LogConfig {
     pub include_span_events: bool,
 }
 
+/// Default log file format.
+const fn default_file_format() -> LogFormat {
+    LogFormat::Json
+}
+
+/// Default number of rotated log files kept.
+const fn default_max_files() -> usize {
+    14
+}
+
 impl LogConfig {
     /// Create log config from CLI arguments.
     pub fn from_cli(cli: &Cli, file_config: Option<&LogConfig>) -> Self {
@@ -74,6 +171,11 @@ impl LogConfig {
             file: cli.log_file.clone().or_else(|| {
                 file_config.and_then(|c| c.file.clone())
             }),
+            file_format: file_config.map_or_else(default_file_format, |c| c.file_format),
+            rotation: file_config.map(|c| c.rotation).unwrap_or_default(),
+            max_file_bytes: file_config.map_or(0, |c| c.max_file_bytes),
+            max_files: file_config.map_or_else(default_max_files, |c| c.max_files),
+            levels: file_config.map(|c| c.levels.clone()).unwrap_or_default(),
             include_file_line: file_config.is_some_and(|c| c.include_file_line),
             include_target: file_config.map(|c| c.include_target).unwrap_or(true),
             include_thread_id: file_config.is_some_and(|c| c.include_thread_id),
@@ -81,58 +183,158 @@ impl LogConfig {
         }
     }
 
+    /// Validate the logging configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a level is invalid.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        self.levels.validate()?;
+        self.filter().map(drop)
+    }
+
+    /// Get the configuration with the file output, rotation, retention and
+    /// level overrides re-read from a configuration file.
+    ///
+    /// The level and console format are kept, as they are usually set on the
+    /// command line; a file path from the command line is kept if the
+    /// configuration file has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the settings are invalid.
+    pub fn with_file_settings(&self, path: &Path) -> orbis_core::Result<Self> {
+        let file_config = Config::load_from_file(&path.to_path_buf())?.log;
+        let config = Self {
+            file: file_config.file.or_else(|| self.file.clone()),
+            file_format: file_config.file_format,
+            rotation: file_config.rotation,
+            max_file_bytes: file_config.max_file_bytes,
+            max_files: file_config.max_files,
+            levels: file_config.levels,
+            ..self.clone()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
     /// Initialize the tracing subscriber.
     ///
+    /// `RUST_LOG`, when set, replaces the configured levels. Call
+    /// [`LogConfig::finish`] before exiting to flush the log file.
+    ///
     /// # Errors
     ///
     /// Returns an error if initialization fails.
     pub fn init(&self) -> orbis_core::Result<()> {
-        let filter = EnvFilter::try_new(&self.level).map_err(|e| {
+        let from_env = std::env::var_os(EnvFilter::DEFAULT_ENV).is_some();
+        let (console_filter, console_handle) = reload::Layer::new(self.console_filter(from_env)?);
+        let (file_filter, file_handle) = reload::Layer::new(self.file_filter(from_env)?);
+        let file = LogFileWriter::default();
+        file.open(self)?;
+
+        tracing_subscriber::registry()
+            .with(self.format_layer(self.format, std::io::stderr, true).with_filter(console_filter))
+            .with(self.format_layer(self.file_format, file.clone(), false).with_filter(file_filter))
+            .try_init()
+            .map_err(|e| orbis_core::Error::config(format!("Failed to initialize logging: {}", e)))?;
+
+        let handle = LogHandle {
+            console_filter: Box::new(move |filter| console_handle.reload(filter)),
+            file_filter: Box::new(move |filter| file_handle.reload(filter)),
+            file,
+            from_env,
+        };
+        if LOGGING.set(handle).is_err() {
+            return Err(orbis_core::Error::config("Logging already initialized"));
+        }
+        Ok(())
+    }
+
+    /// Apply the levels and file output to the logging installed by
+    /// [`LogConfig::init`].
+    ///
+    /// Returns `false` if logging was not installed by it. Formats only
+    /// change on restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a level is invalid or the log file cannot be opened.
+    pub fn reload(&self) -> orbis_core::Result<bool> {
+        let Some(handle) = LOGGING.get() else {
+            return Ok(false);
+        };
+
+        let reload_error = |e: reload::Error| orbis_core::Error::config(format!("Failed to reload logging: {}", e));
+        if !handle.from_env {
+            (handle.console_filter)(self.console_filter(false)?).map_err(reload_error)?;
+        }
+        // Without a file the file output formats nothing
+        (handle.file_filter)(self.file_filter(handle.from_env)?).map_err(reload_error)?;
+        handle.file.open(self)?;
+        Ok(true)
+    }
+
+    /// Flush the log file and stop writing to it.
+    pub fn finish() {
+        if let Some(handle) = LOGGING.get() {
+            handle.file.close();
+        }
+    }
+
+    /// Get the filter of the configured level and overrides.
+    fn filter(&self) -> orbis_core::Result<EnvFilter> {
+        let directives: Vec<String> = std::iter::once(self.level.clone())
+            .chain(self.levels.overrides().flat_map(|(_, level, targets)| {
+                targets.iter().map(move |target| format!("{}={}", target, level))
+            }))
+            .collect();
+        EnvFilter::try_new(directives.join(",")).map_err(|e| {
             orbis_core::Error::config(format!("Invalid log level '{}': {}", self.level, e))
-        })?;
-
-        let subscriber = tracing_subscriber::registry().with(filter);
-
-        match self.format {
-            LogFormat::Pretty => {
-                let layer = fmt::layer()
-                    .with_file(self.include_file_line)
-                    .with_line_number(self.include_file_line)
-                    .with_target(self.include_target)
-                    .with_thread_ids(self.include_thread_id)
-                    .pretty();
-
-                subscriber.with(layer).try_init().map_err(|e| {
-                    orbis_core::Error::config(format!("Failed to initialize logging: {}", e))
-                })?;
-            }
-            LogFormat::Json => {
-                let layer = fmt::layer()
-                    .with_file(self.include_file_line)
-                    .with_line_number(self.include_file_line)
-                    .with_target(self.include_target)
-                    .with_thread_ids(self.include_thread_id)
-                    .json();
-
-                subscriber.with(layer).try_init().map_err(|e| {
-                    orbis_core::Error::config(format!("Failed to initialize logging: {}", e))
-                })?;
-            }
-            LogFormat::Compact => {
-                let layer = fmt::layer()
-                    .with_file(self.include_file_line)
-                    .with_line_number(self.include_file_line)
-                    .with_target(self.include_target)
-                    .with_thread_ids(self.include_thread_id)
-                    .compact();
-
-                subscriber.with(layer).try_init().map_err(|e| {
-                    orbis_core::Error::config(format!("Failed to initialize logging: {}", e))
-                })?;
-            }
+        })
+    }
+
+    /// Get the filter of the stderr output.
+    fn console_filter(&self, from_env: bool) -> orbis_core::Result<EnvFilter> {
+        EnvFilter::try_from_default_env()
+            .ok()
+            .filter(|_| from_env)
+            .map_or_else(|| self.filter(), Ok)
+    }
+
+    /// Get the filter of the file output, which is off without a file.
+    fn file_filter(&self, from_env: bool) -> orbis_core::Result<EnvFilter> {
+        if self.file.is_none() {
+            return Ok(EnvFilter::new("off"));
         }
+        self.console_filter(from_env)
+    }
 
-        Ok(())
+    /// Create a layer formatting events to a writer.
+    fn format_layer<S, W>(&self, format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let span_events = if self.include_span_events {
+            FmtSpan::NEW | FmtSpan::CLOSE
+        } else {
+            FmtSpan::NONE
+        };
+        let layer = fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_file(self.include_file_line)
+            .with_line_number(self.include_file_line)
+            .with_target(self.include_target)
+            .with_thread_ids(self.include_thread_id)
+            .with_span_events(span_events);
+
+        match format {
+            LogFormat::Pretty => layer.pretty().boxed(),
+            LogFormat::Json => layer.json().boxed(),
+            LogFormat::Compact => layer.compact().boxed(),
+        }
     }
 
     /// Get the log level as tracing Level.
@@ -155,6 +357,11 @@ impl Default for LogConfig {
             level: "info".to_string(),
             format: LogFormat::Pretty,
             file: None,
+            file_format: default_file_format(),
+            rotation: LogRotation::default(),
+            max_file_bytes: 0,
+            max_files: default_max_files(),
+            levels: LogLevels::default(),
             include_file_line: false,
             include_target: true,
             include_thread_id: false,
@@ -162,3 +369,64 @@ impl Default for LogConfig {
         }
     }
 }
+
+/// Reloadable parts of the installed logging.
+struct LogHandle {
+    /// Replaces the filter of the stderr output.
+    console_filter: Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>,
+
+    /// Replaces the filter of the file output.
+    file_filter: Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>,
+
+    /// Writer of the file output.
+    file: LogFileWriter,
+
+    /// Whether `RUST_LOG` sets the levels, which reloads then keep.
+    from_env: bool,
+}
+
+/// Writer of the log file, replaced when its settings change.
+///
+/// The file is written from a background thread, so logging never waits
+/// for the disk.
+#[derive(Clone, Default)]
+struct LogFileWriter {
+    /// Background writer of the file and the guard flushing it on drop, if there is a file.
+    output: Arc<Mutex<Option<(NonBlocking, WorkerGuard)>>>,
+}
+
+impl LogFileWriter {
+    /// Open the configured log file, replacing the current one.
+    fn open(&self, config: &LogConfig) -> orbis_core::Result<()> {
+        let output = config
+            .file
+            .as_ref()
+            .map(|path| {
+                let file = RotatingFile::open(path.clone(), config.rotation, config.max_file_bytes, config.max_files)
+                    .map_err(|e| orbis_core::Error::config(format!("Failed to open log file {:?}: {}", path, e)))?;
+                // Lines are kept rather than dropped when the disk falls behind
+                Ok::<_, orbis_core::Error>(NonBlockingBuilder::default().lossy(false).finish(file))
+            })
+            .transpose()?;
+
+        // The replaced writer is flushed as its guard drops
+        drop(std::mem::replace(&mut *self.output.lock(), output));
+        Ok(())
+    }
+
+    /// Flush and close the log file.
+    fn close(&self) {
+        drop(self.output.lock().take());
+    }
+}
+
+impl<'writer> MakeWriter<'writer> for LogFileWriter {
+    type Writer = OptionalWriter<NonBlocking>;
+
+    fn make_writer(&'writer self) -> Self::Writer {
+        self.output
+            .lock()
+            .as_ref()
+            .map_or_else(OptionalWriter::none, |output| OptionalWriter::some(output.0.clone()))
+    }
+}
@@ -0,0 +1,123 @@
+//! Plugin policy configuration.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Network access of plugins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginNetworkAccess {
+    /// Plugins requesting the `network` permission can use the network.
+    Allow,
+
+    /// Plugins cannot use the network, even if they request the permission.
+    Deny,
+}
+
+/// Policy applied to plugins.
+///
+/// Unset fields keep the host defaults; in per-plugin overrides, they keep
+/// the values of `[plugins.policy]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginPolicyConfig {
+    /// Whether unsigned plugins load: `true` even when signatures are
+    /// required, `false` even when they are not checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_unsigned: Option<bool>,
+
+    /// Memory a plugin instance can use, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<usize>,
+
+    /// Network access.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<PluginNetworkAccess>,
+
+    /// Enable plugins as soon as they are installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_enable: Option<bool>,
+
+    /// Permissions plugins may request, which make up their sandbox (e.g.
+    /// `database_read`, or the name of a custom permission); plugins
+    /// requesting others are refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_permissions: Option<Vec<String>>,
+}
+
+impl PluginPolicyConfig {
+    /// Fill the unset fields from another policy.
+    #[must_use]
+    pub fn or(&self, fallback: &Self) -> Self {
+        Self {
+            allow_unsigned: self.allow_unsigned.or(fallback.allow_unsigned),
+            max_memory_bytes: self.max_memory_bytes.or(fallback.max_memory_bytes),
+            network: self.network.or(fallback.network),
+            auto_enable: self.auto_enable.or(fallback.auto_enable),
+            allowed_permissions: self
+                .allowed_permissions
+                .clone()
+                .or_else(|| fallback.allowed_permissions.clone()),
+        }
+    }
+
+    /// Check if the policy allows a plugin to request a permission.
+    #[must_use]
+    pub fn allows_permission(&self, permission: &str) -> bool {
+        self.allowed_permissions
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|name| name == permission))
+    }
+
+    /// Validate the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory limit is zero.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.max_memory_bytes == Some(0) {
+            return Err(orbis_core::Error::config("Plugin memory limit must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Plugin configuration (`[plugins]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Policy of all plugins.
+    #[serde(default)]
+    pub policy: PluginPolicyConfig,
+
+    /// Policy overrides by plugin name (`[plugins.override."my-plugin"]`).
+    #[serde(default, rename = "override", skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, PluginPolicyConfig>,
+
+    /// URL of the registry plugins are installed from by name; must use HTTPS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+impl PluginsConfig {
+    /// Get the policy of a plugin: its overrides over the policy of all plugins.
+    #[must_use]
+    pub fn policy_for(&self, plugin: &str) -> PluginPolicyConfig {
+        self.overrides
+            .get(plugin)
+            .map_or_else(|| self.policy.clone(), |overrides| overrides.or(&self.policy))
+    }
+
+    /// Validate the policy and its overrides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the plugin if a policy is invalid.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        self.policy.validate()?;
+        for (plugin, overrides) in &self.overrides {
+            overrides
+                .validate()
+                .map_err(|e| orbis_core::Error::config(format!("Invalid policy of plugin '{}': {}", plugin, e)))?;
+        }
+        Ok(())
+    }
+}
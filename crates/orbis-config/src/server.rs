@@ -23,6 +23,26 @@ pub struct ServerConfig {
     /// Maximum request body size in bytes.
     pub max_body_size: usize,
 
+    /// Maximum body size in bytes of plugin routes that declare no limit.
+    #[serde(default = "default_plugin_max_body_size")]
+    pub plugin_max_body_size: usize,
+
+    /// Seconds a client may take to send a request body.
+    #[serde(default = "default_body_read_timeout")]
+    pub body_read_timeout_seconds: u64,
+
+    /// Seconds a client may take to send request headers.
+    #[serde(default = "default_header_read_timeout")]
+    pub header_read_timeout_seconds: u64,
+
+    /// Seconds a connection may wait for data from the client.
+    #[serde(default = "default_read_timeout")]
+    pub read_timeout_seconds: u64,
+
+    /// Seconds a connection may wait for the client to accept data.
+    #[serde(default = "default_write_timeout")]
+    pub write_timeout_seconds: u64,
+
     /// Enable request logging.
     pub request_logging: bool,
 
@@ -35,6 +55,90 @@ pub struct ServerConfig {
 
     /// Enable compression.
     pub compression: bool,
+
+    /// Algorithms offered to clients.
+    #[serde(default = "default_compression_algorithms")]
+    pub compression_algorithms: Vec<CompressionAlgorithm>,
+
+    /// Smallest response body compressed, in bytes.
+    #[serde(default = "default_compression_min_size")]
+    pub compression_min_size: u64,
+
+    /// Content types compressed; entries ending in `/` match a whole type.
+    #[serde(default = "default_compression_content_types")]
+    pub compression_content_types: Vec<String>,
+
+    /// Seconds plugin responses marked idempotent are replayed for retries
+    /// with the same `Idempotency-Key`.
+    #[serde(default = "default_idempotency_ttl")]
+    pub idempotency_ttl_seconds: u64,
+}
+
+/// Response compression algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    /// gzip (`Content-Encoding: gzip`).
+    Gzip,
+
+    /// Brotli (`Content-Encoding: br`).
+    #[serde(alias = "br")]
+    Brotli,
+}
+
+/// Default compression algorithms.
+fn default_compression_algorithms() -> Vec<CompressionAlgorithm> {
+    vec![CompressionAlgorithm::Brotli, CompressionAlgorithm::Gzip]
+}
+
+/// Default smallest compressed body, in bytes.
+const fn default_compression_min_size() -> u64 {
+    1024
+}
+
+/// Default compressed content types.
+fn default_compression_content_types() -> Vec<String> {
+    [
+        "text/",
+        "application/json",
+        "application/javascript",
+        "application/xml",
+        "application/wasm",
+        "image/svg+xml",
+    ]
+    .into_iter()
+    .map(str::to_owned)
+    .collect()
+}
+
+/// Default maximum body size of plugin routes.
+const fn default_plugin_max_body_size() -> usize {
+    1024 * 1024 // 1MB
+}
+
+/// Default idempotent response lifetime, in seconds.
+const fn default_idempotency_ttl() -> u64 {
+    24 * 60 * 60
+}
+
+/// Default body read timeout, in seconds.
+const fn default_body_read_timeout() -> u64 {
+    30
+}
+
+/// Default header read timeout, in seconds.
+const fn default_header_read_timeout() -> u64 {
+    10
+}
+
+/// Default connection read timeout, in seconds.
+const fn default_read_timeout() -> u64 {
+    60
+}
+
+/// Default connection write timeout, in seconds.
+const fn default_write_timeout() -> u64 {
+    30
 }
 
 impl ServerConfig {
@@ -47,15 +151,38 @@ impl ServerConfig {
                 file_config.and_then(|c| c.url.clone())
             }),
             request_timeout_seconds: cli.request_timeout,
-            max_body_size: file_config
-                .map(|c| c.max_body_size)
-                .unwrap_or(10 * 1024 * 1024), // 10MB
+            max_body_size: cli.max_body_size.unwrap_or_else(|| {
+                file_config.map_or(10 * 1024 * 1024, |c| c.max_body_size) // 10MB
+            }),
+            plugin_max_body_size: cli.plugin_max_body_size.unwrap_or_else(|| {
+                file_config.map_or_else(default_plugin_max_body_size, |c| c.plugin_max_body_size)
+            }),
+            body_read_timeout_seconds: cli.body_read_timeout.unwrap_or_else(|| {
+                file_config.map_or_else(default_body_read_timeout, |c| c.body_read_timeout_seconds)
+            }),
+            header_read_timeout_seconds: cli.header_read_timeout.unwrap_or_else(|| {
+                file_config.map_or_else(default_header_read_timeout, |c| c.header_read_timeout_seconds)
+            }),
+            read_timeout_seconds: cli.read_timeout.unwrap_or_else(|| {
+                file_config.map_or_else(default_read_timeout, |c| c.read_timeout_seconds)
+            }),
+            write_timeout_seconds: cli.write_timeout.unwrap_or_else(|| {
+                file_config.map_or_else(default_write_timeout, |c| c.write_timeout_seconds)
+            }),
             request_logging: file_config.is_some_and(|c| c.request_logging),
             cors_enabled: file_config.is_some_and(|c| c.cors_enabled),
             cors_origins: file_config
                 .map(|c| c.cors_origins.clone())
                 .unwrap_or_default(),
             compression: file_config.map(|c| c.compression).unwrap_or(true),
+            compression_algorithms: file_config
+                .map_or_else(default_compression_algorithms, |c| c.compression_algorithms.clone()),
+            compression_min_size: file_config
+                .map_or_else(default_compression_min_size, |c| c.compression_min_size),
+            compression_content_types: file_config
+                .map_or_else(default_compression_content_types, |c| c.compression_content_types.clone()),
+            idempotency_ttl_seconds: file_config
+                .map_or_else(default_idempotency_ttl, |c| c.idempotency_ttl_seconds),
         }
     }
 
@@ -86,6 +213,32 @@ impl ServerConfig {
             ));
         }
 
+        // Validate limits
+        if self.max_body_size == 0 || self.plugin_max_body_size == 0 {
+            return Err(orbis_core::Error::config("Body size limits must be greater than 0"));
+        }
+
+        if self.body_read_timeout_seconds == 0
+            || self.header_read_timeout_seconds == 0
+            || self.read_timeout_seconds == 0
+            || self.write_timeout_seconds == 0
+        {
+            return Err(orbis_core::Error::config("Read and write timeouts must be greater than 0"));
+        }
+
+        // Connections waiting on a slow handler must outlive the request
+        if self.read_timeout_seconds <= self.request_timeout_seconds {
+            return Err(orbis_core::Error::config(
+                "Read timeout must be greater than the request timeout",
+            ));
+        }
+
+        if self.compression && self.compression_algorithms.is_empty() {
+            return Err(orbis_core::Error::config(
+                "Compression requires at least one compression algorithm",
+            ));
+        }
+
         Ok(())
     }
 
@@ -105,10 +258,19 @@ impl Default for ServerConfig {
             url: None,
             request_timeout_seconds: 30,
             max_body_size: 10 * 1024 * 1024, // 10MB
+            plugin_max_body_size: default_plugin_max_body_size(),
+            body_read_timeout_seconds: default_body_read_timeout(),
+            header_read_timeout_seconds: default_header_read_timeout(),
+            read_timeout_seconds: default_read_timeout(),
+            write_timeout_seconds: default_write_timeout(),
             request_logging: true,
             cors_enabled: true,
             cors_origins: vec!["*".to_string()],
             compression: true,
+            compression_algorithms: default_compression_algorithms(),
+            compression_min_size: default_compression_min_size(),
+            compression_content_types: default_compression_content_types(),
+            idempotency_ttl_seconds: default_idempotency_ttl(),
         }
     }
 }
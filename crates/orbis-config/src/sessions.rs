@@ -0,0 +1,140 @@
+//! Cookie session configuration.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// CSRF protection of requests authenticated by the session cookie.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsrfProtection {
+    /// No token is required; only the `SameSite` attribute of the session
+    /// cookie protects the routes.
+    Off,
+
+    /// The token header must repeat the value of the CSRF cookie.
+    #[default]
+    DoubleSubmit,
+
+    /// The token header must hold the token signed for the session, which
+    /// the client gets at login or from `GET /api/auth/csrf`.
+    Signed,
+}
+
+/// `SameSite` attribute of the session cookie.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSitePolicy {
+    /// Sent with same-site requests only.
+    #[default]
+    Strict,
+
+    /// Also sent when navigating to the site from another one.
+    Lax,
+}
+
+/// Cookie sessions of the web client (`[cookie_sessions]`).
+///
+/// When enabled, login keeps the access token in an `HttpOnly` cookie and
+/// requests may authenticate with it. Unsafe requests authenticated by the
+/// cookie must then pass the CSRF protection of their route group; requests
+/// with an `Authorization` header are exempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CookieSessionConfig {
+    /// Keep access tokens in a session cookie.
+    pub enabled: bool,
+
+    /// Name of the session cookie.
+    pub cookie: String,
+
+    /// `SameSite` attribute of the session cookie.
+    pub same_site: SameSitePolicy,
+
+    /// Only send the cookies over HTTPS.
+    pub secure: bool,
+
+    /// CSRF protection of routes outside the configured groups.
+    pub csrf: CsrfProtection,
+
+    /// Name of the CSRF cookie (double-submit protection).
+    pub csrf_cookie: String,
+
+    /// Header carrying the CSRF token.
+    pub csrf_header: String,
+
+    /// CSRF protection by route group: API path prefix, without the version
+    /// (e.g. `/admin` for `/api/v1/admin/...`), to protection.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, CsrfProtection>,
+}
+
+impl CookieSessionConfig {
+    /// Get the CSRF protection of an API path, without the `/api` prefix or
+    /// version: that of the longest group containing it.
+    #[must_use]
+    pub fn protection_for(&self, path: &str) -> CsrfProtection {
+        self.groups
+            .iter()
+            .filter(|&(prefix, _)| {
+                let prefix = prefix.trim_end_matches('/');
+                path.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|&(prefix, _)| prefix.len())
+            .map_or(self.csrf, |(_, protection)| *protection)
+    }
+
+    /// Validate the cookie session configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a cookie or header name is invalid, or a route
+    /// group is not an absolute path.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        for (what, name) in [
+            ("Session cookie", &self.cookie),
+            ("CSRF cookie", &self.csrf_cookie),
+            ("CSRF header", &self.csrf_header),
+        ] {
+            if !is_token(name) {
+                return Err(orbis_core::Error::config(format!("{} name '{}' is invalid", what, name)));
+            }
+        }
+
+        if self.cookie == self.csrf_cookie {
+            return Err(orbis_core::Error::config("Session and CSRF cookies must have different names"));
+        }
+
+        if let Some(group) = self.groups.keys().find(|group| !group.starts_with('/')) {
+            return Err(orbis_core::Error::config(format!(
+                "CSRF route group '{}' must start with '/'",
+                group
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CookieSessionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cookie: "orbis_session".to_owned(),
+            same_site: SameSitePolicy::Strict,
+            secure: true,
+            csrf: CsrfProtection::DoubleSubmit,
+            csrf_cookie: "orbis_csrf".to_owned(),
+            csrf_header: "x-csrf-token".to_owned(),
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+/// Check a name is an HTTP token, as cookie and header names must be.
+fn is_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
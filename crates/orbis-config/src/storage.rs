@@ -0,0 +1,127 @@
+//! Object storage configuration.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Object storage backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Directory on the local filesystem.
+    #[default]
+    Local,
+
+    /// S3-compatible service (AWS S3, MinIO, R2, ...).
+    S3,
+}
+
+/// Local object storage (`[storage.local]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalStorageConfig {
+    /// Directory the objects are stored in (default: the `.plugin_data`
+    /// directory of the plugins directory).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+}
+
+/// S3-compatible object storage (`[storage.s3]`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3StorageConfig {
+    /// Bucket name.
+    pub bucket: String,
+
+    /// Region the bucket is in.
+    pub region: String,
+
+    /// Service endpoint (default: AWS S3 in the region).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Address the bucket in the path (`endpoint/bucket/key`) rather than in
+    /// the host name, as most self-hosted services need.
+    pub path_style: bool,
+
+    /// Prefix of every object key, to share a bucket.
+    pub prefix: String,
+
+    /// Access key ID (default: `AWS_ACCESS_KEY_ID`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+
+    /// Secret access key (default: `AWS_SECRET_ACCESS_KEY`).
+    #[serde(skip_serializing)]
+    pub secret_access_key: Option<String>,
+}
+
+impl Default for S3StorageConfig {
+    fn default() -> Self {
+        Self {
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            endpoint: None,
+            path_style: false,
+            prefix: String::new(),
+            access_key_id: None,
+            secret_access_key: None,
+        }
+    }
+}
+
+/// Object storage of plugin files (`[storage]`).
+///
+/// Plugins with a `storage_bytes` grant keep files in it, and their data
+/// files live in it. Setting `migrate_from` to the previous backend after
+/// switching makes the move transparent: objects missing from the new
+/// backend are read from the previous one and copied over on first use,
+/// until an admin copies the rest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Backend storing the objects.
+    pub backend: StorageBackend,
+
+    /// Previous backend, read from until everything is migrated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migrate_from: Option<StorageBackend>,
+
+    /// Local backend settings.
+    pub local: LocalStorageConfig,
+
+    /// S3 backend settings.
+    pub s3: S3StorageConfig,
+}
+
+impl StorageConfig {
+    /// Validate the storage configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if S3 is used without a bucket or region, or the
+    /// backend is migrated from itself.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.migrate_from == Some(self.backend) {
+            return Err(orbis_core::Error::config("Storage cannot be migrated from its own backend"));
+        }
+
+        let uses_s3 = self.backend == StorageBackend::S3 || self.migrate_from == Some(StorageBackend::S3);
+        if uses_s3 && (self.s3.bucket.is_empty() || self.s3.region.is_empty()) {
+            return Err(orbis_core::Error::config("S3 storage needs a bucket and a region"));
+        }
+
+        let invalid_endpoint = self
+            .s3
+            .endpoint
+            .as_ref()
+            .filter(|endpoint| !endpoint.starts_with("http://") && !endpoint.starts_with("https://"));
+        if let Some(endpoint) = invalid_endpoint {
+            return Err(orbis_core::Error::config(format!(
+                "S3 endpoint '{}' must be an http:// or https:// URL",
+                endpoint
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! Multi-tenancy configuration.
+
+use crate::Cli;
+use serde::{Deserialize, Serialize};
+
+/// How the tenant of a request is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenancyMode {
+    /// Single-tenant deployment (default).
+    #[default]
+    Disabled,
+
+    /// Tenant slug is the first label of the `Host` subdomain.
+    Subdomain,
+
+    /// Tenant slug is read from a request header.
+    Header,
+}
+
+impl TenancyMode {
+    /// Check if multi-tenancy is enabled.
+    #[must_use]
+    pub const fn is_enabled(self) -> bool {
+        !matches!(self, Self::Disabled)
+    }
+}
+
+impl std::str::FromStr for TenancyMode {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disabled" | "off" => Ok(Self::Disabled),
+            "subdomain" => Ok(Self::Subdomain),
+            "header" => Ok(Self::Header),
+            _ => Err(orbis_core::Error::config(format!(
+                "Invalid tenancy mode: '{}'. Expected 'disabled', 'subdomain', or 'header'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Multi-tenancy configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenancyConfig {
+    /// How tenants are resolved.
+    pub mode: TenancyMode,
+
+    /// Header carrying the tenant slug (header mode).
+    pub header: String,
+
+    /// Base domain tenants are subdomains of (subdomain mode), e.g. `orbis.example.com`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_domain: Option<String>,
+}
+
+impl TenancyConfig {
+    /// Create tenancy config from CLI arguments.
+    pub fn from_cli(cli: &Cli, file_config: Option<&Self>) -> Self {
+        Self {
+            mode: cli
+                .tenancy
+                .as_deref()
+                .and_then(|mode| mode.parse().ok())
+                .unwrap_or_else(|| file_config.map(|c| c.mode).unwrap_or_default()),
+            header: cli.tenant_header.clone().unwrap_or_else(|| {
+                file_config.map_or_else(|| "x-tenant".to_owned(), |c| c.header.clone())
+            }),
+            base_domain: cli.tenant_base_domain.clone().or_else(|| {
+                file_config.and_then(|c| c.base_domain.clone())
+            }),
+        }
+    }
+
+    /// Validate the tenancy configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if subdomain mode has no base domain.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.mode == TenancyMode::Subdomain && self.base_domain.is_none() {
+            return Err(orbis_core::Error::config(
+                "Subdomain tenancy requires a base domain. Set ORBIS_TENANT_BASE_DOMAIN or --tenant-base-domain",
+            ));
+        }
+
+        if self.header.is_empty() {
+            return Err(orbis_core::Error::config("Tenant header cannot be empty"));
+        }
+
+        Ok(())
+    }
+
+    /// Extract the tenant slug from a request host, if it is a subdomain of the base domain.
+    #[must_use]
+    pub fn slug_from_host<'a>(&self, host: &'a str) -> Option<&'a str> {
+        let base = self.base_domain.as_deref()?;
+        let host = host.split(':').next().unwrap_or(host);
+        host.strip_suffix(base)?
+            .strip_suffix('.')
+            .filter(|slug| !slug.is_empty() && !slug.contains('.'))
+    }
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            mode: TenancyMode::Disabled,
+            header: "x-tenant".to_owned(),
+            base_domain: None,
+        }
+    }
+}
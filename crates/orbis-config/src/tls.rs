@@ -4,6 +4,45 @@ use crate::Cli;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+/// Whether clients must present a certificate (mutual TLS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuthMode {
+    /// Client certificates are not requested (default).
+    #[default]
+    None,
+
+    /// Client certificates are verified when presented.
+    Optional,
+
+    /// Connections without a verified client certificate are refused.
+    Required,
+}
+
+impl ClientAuthMode {
+    /// Check if client certificates are requested.
+    #[must_use]
+    pub const fn is_enabled(self) -> bool {
+        !matches!(self, Self::None)
+    }
+}
+
+impl std::str::FromStr for ClientAuthMode {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(Self::None),
+            "optional" => Ok(Self::Optional),
+            "required" => Ok(Self::Required),
+            _ => Err(orbis_core::Error::config(format!(
+                "Invalid TLS client auth mode: '{}'. Expected 'none', 'optional', or 'required'",
+                s
+            ))),
+        }
+    }
+}
+
 /// TLS configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TlsConfig {
@@ -18,7 +57,7 @@ pub struct TlsConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_path: Option<PathBuf>,
 
-    /// Path to CA certificate file.
+    /// Path to CA certificate file, which client certificates must chain to.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ca_path: Option<PathBuf>,
 
@@ -28,6 +67,10 @@ pub struct TlsConfig {
     /// Minimum TLS version.
     #[serde(default = "default_min_version")]
     pub min_version: String,
+
+    /// Whether clients must present a certificate.
+    #[serde(default)]
+    pub client_auth: ClientAuthMode,
 }
 
 fn default_min_version() -> String {
@@ -56,6 +99,11 @@ impl TlsConfig {
             min_version: file_config
                 .map(|c| c.min_version.clone())
                 .unwrap_or_else(default_min_version),
+            client_auth: cli
+                .tls_client_auth
+                .as_deref()
+                .and_then(|mode| mode.parse().ok())
+                .unwrap_or_else(|| file_config.map(|c| c.client_auth).unwrap_or_default()),
         }
     }
 
@@ -107,6 +155,13 @@ impl TlsConfig {
                 }
             }
 
+            // Client certificates are verified against the CA
+            if self.client_auth.is_enabled() && self.ca_path.is_none() {
+                return Err(orbis_core::Error::config(
+                    "TLS CA path is required when client certificate authentication is enabled",
+                ));
+            }
+
             // Validate min version
             match self.min_version.as_str() {
                 "1.2" | "1.3" => {}
@@ -132,6 +187,7 @@ impl Default for TlsConfig {
             ca_path: None,
             verify: true,
             min_version: default_min_version(),
+            client_auth: ClientAuthMode::None,
         }
     }
 }
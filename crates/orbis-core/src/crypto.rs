@@ -0,0 +1,126 @@
+//! Encryption at rest.
+//!
+//! Encrypted profiles derive an [`EncryptionKey`] from a passphrase with
+//! Argon2id. The key opens the profile's SQLite database (through SQLCipher)
+//! and seals files such as plugin state with AES-256-GCM.
+
+use argon2::Argon2;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom as _, SystemRandom};
+
+use crate::{Error, Result};
+
+/// Prefix of sealed data, identifying the format.
+const SEALED_MAGIC: &[u8] = b"ORBISENC1";
+
+/// Length of keys, in bytes.
+const KEY_LEN: usize = 32;
+
+/// Length of generated salts, in bytes.
+pub const SALT_LEN: usize = 16;
+
+/// A 256-bit key encrypting data at rest.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    /// Derive a key from a passphrase and salt with Argon2id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the salt is too short.
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| Error::internal(format!("Failed to derive encryption key: {}", e)))?;
+        Ok(Self(key))
+    }
+
+    /// Generate a random salt for [`EncryptionKey::derive`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the system random generator fails.
+    pub fn generate_salt() -> Result<[u8; SALT_LEN]> {
+        let mut salt = [0; SALT_LEN];
+        random(&mut salt)?;
+        Ok(salt)
+    }
+
+    /// Get the key as a SQLCipher raw key literal (`x'...'`).
+    #[must_use]
+    pub fn sqlcipher_key(&self) -> String {
+        format!("\"x'{}'\"", hex::encode_upper(self.0))
+    }
+
+    /// Encrypt data, prefixing it with the format marker and a random nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encryption fails.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        random(&mut nonce)?;
+
+        let mut sealed = plaintext.to_vec();
+        self.aead()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .map_err(|_unspecified| Error::internal("Failed to encrypt data"))?;
+
+        Ok([SEALED_MAGIC, &nonce, &sealed].concat())
+    }
+
+    /// Decrypt data sealed with [`EncryptionKey::seal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not sealed, was sealed with another
+    /// key, or was modified.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let rest = sealed
+            .strip_prefix(SEALED_MAGIC)
+            .ok_or_else(|| Error::validation("Data is not encrypted"))?;
+        if rest.len() < NONCE_LEN {
+            return Err(Error::validation("Encrypted data is truncated"));
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_unspecified| Error::validation("Encrypted data is truncated"))?;
+
+        let mut plaintext = ciphertext.to_vec();
+        let len = self
+            .aead()?
+            .open_in_place(nonce, Aad::empty(), &mut plaintext)
+            .map_err(|_unspecified| Error::unauthorized("Wrong key or corrupted data"))?
+            .len();
+        plaintext.truncate(len);
+        Ok(plaintext)
+    }
+
+    /// Check if data was sealed with [`EncryptionKey::seal`].
+    #[must_use]
+    pub fn is_sealed(data: &[u8]) -> bool {
+        data.starts_with(SEALED_MAGIC)
+    }
+
+    /// Create the AES-256-GCM cipher of the key.
+    fn aead(&self) -> Result<LessSafeKey> {
+        UnboundKey::new(&AES_256_GCM, &self.0)
+            .map(LessSafeKey::new)
+            .map_err(|_unspecified| Error::internal("Invalid encryption key"))
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Fill a buffer from the system random generator.
+fn random(buffer: &mut [u8]) -> Result<()> {
+    SystemRandom::new()
+        .fill(buffer)
+        .map_err(|_unspecified| Error::internal("Failed to generate random bytes"))
+}
@@ -52,6 +52,18 @@ pub enum Error {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    /// Payload too large error.
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// Timeout error.
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    /// Out of memory error.
+    #[error("Out of memory: {0}")]
+    OutOfMemory(String),
+
     /// Internal error.
     #[error("Internal error: {0}")]
     Internal(String),
@@ -118,6 +130,24 @@ impl Error {
         Self::Conflict(msg.into())
     }
 
+    /// Create a new payload too large error.
+    #[must_use]
+    pub fn payload_too_large(msg: impl Into<String>) -> Self {
+        Self::PayloadTooLarge(msg.into())
+    }
+
+    /// Create a new timeout error.
+    #[must_use]
+    pub fn timeout(msg: impl Into<String>) -> Self {
+        Self::Timeout(msg.into())
+    }
+
+    /// Create a new out of memory error.
+    #[must_use]
+    pub fn out_of_memory(msg: impl Into<String>) -> Self {
+        Self::OutOfMemory(msg.into())
+    }
+
     /// Create a new internal error.
     #[must_use]
     pub fn internal(msg: impl Into<String>) -> Self {
@@ -0,0 +1,53 @@
+//! Content hashing.
+//!
+//! SHA-256 digests as lowercase hex, HMAC-SHA256 signatures, and hash
+//! chains: each link hashes the previous link's hash together with its own
+//! content, so changing, removing or reordering any link changes every hash
+//! after it.
+
+use hmac::{Hmac, Mac as _};
+use ring::digest::{Context, SHA256};
+use subtle::ConstantTimeEq as _;
+
+/// HMAC-SHA256.
+type HmacSha256 = Hmac<sha2::Sha256>;
+
+/// Hash the first link of a chain is linked to.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Hash data with SHA-256, as lowercase hex.
+#[must_use]
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(ring::digest::digest(&SHA256, data))
+}
+
+/// Hash a link of a chain from the previous link's hash and its content.
+#[must_use]
+pub fn chain_hash(previous: &str, content: &[u8]) -> String {
+    let mut context = Context::new(&SHA256);
+    context.update(previous.as_bytes());
+    context.update(b"\n");
+    context.update(content);
+    hex::encode(context.finish())
+}
+
+/// Compute the HMAC-SHA256 of a message.
+#[must_use]
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    // HMAC takes keys of any length, so keying never fails
+    HmacSha256::new_from_slice(key)
+        .map(|mac| mac.chain_update(message).finalize().into_bytes().into())
+        .unwrap_or_default()
+}
+
+/// Check the HMAC-SHA256 of a message, in constant time.
+#[must_use]
+pub fn verify_hmac_sha256(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    HmacSha256::new_from_slice(key).is_ok_and(|mac| mac.chain_update(message).verify_slice(tag).is_ok())
+}
+
+/// Compare secrets in time independent of where they differ.
+#[must_use]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
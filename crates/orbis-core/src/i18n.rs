@@ -0,0 +1,230 @@
+//! Localization of core messages.
+//!
+//! Messages are written in English and used as catalog keys, gettext style.
+//! A catalog is a JSON object mapping source messages to translations; a key
+//! may contain `{}` placeholders matching the variable parts of a message
+//! (e.g. `"Plugin not found: {}"`), which are carried over into the
+//! translation in order.
+//!
+//! Locales are resolved through a fallback chain: each requested locale,
+//! then its parent (`fr-CA` falls back to `fr`), then the default locale.
+//! Messages without a translation are returned unchanged.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Placeholder for the variable part of a message.
+const PLACEHOLDER: &str = "{}";
+
+/// Translations of one locale.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// Translations of messages without placeholders.
+    exact: HashMap<String, String>,
+
+    /// Translations of messages with placeholders, as the literal parts
+    /// between the placeholders and the translation.
+    patterns: Vec<(Vec<String>, String)>,
+}
+
+impl Catalog {
+    /// Create a catalog from source messages and their translations.
+    #[must_use]
+    pub fn new(messages: HashMap<String, String>) -> Self {
+        let mut catalog = Self::default();
+        for (message, translation) in messages {
+            if message.contains(PLACEHOLDER) {
+                let parts = message.split(PLACEHOLDER).map(str::to_owned).collect();
+                catalog.patterns.push((parts, translation));
+            } else {
+                catalog.exact.insert(message, translation);
+            }
+        }
+
+        // Prefer the most specific pattern
+        catalog
+            .patterns
+            .sort_by_key(|(parts, _)| std::cmp::Reverse(parts.iter().map(String::len).sum::<usize>()));
+        catalog
+    }
+
+    /// Translate a message, if the catalog has a translation for it.
+    #[must_use]
+    pub fn translate(&self, message: &str) -> Option<String> {
+        if let Some(translation) = self.exact.get(message) {
+            return Some(translation.clone());
+        }
+
+        self.patterns.iter().find_map(|(parts, translation)| {
+            let values = match_pattern(parts, message)?;
+            let mut values = values.into_iter();
+            let mut output = String::with_capacity(translation.len());
+            let mut pieces = translation.split(PLACEHOLDER).peekable();
+            while let Some(piece) = pieces.next() {
+                output.push_str(piece);
+                if pieces.peek().is_some() {
+                    output.push_str(values.next().unwrap_or_default());
+                }
+            }
+            Some(output)
+        })
+    }
+
+    /// Number of translations in the catalog.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.exact.len() + self.patterns.len()
+    }
+
+    /// Whether the catalog has no translations.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.patterns.is_empty()
+    }
+}
+
+/// Match a message against the literal parts of a pattern, returning the
+/// values of the placeholders.
+fn match_pattern<'a>(parts: &[String], message: &'a str) -> Option<Vec<&'a str>> {
+    let (first, rest) = parts.split_first()?;
+    let (last, middle) = rest.split_last()?;
+
+    let mut remaining = message.strip_prefix(first.as_str())?.strip_suffix(last.as_str())?;
+    let mut values = Vec::with_capacity(rest.len());
+    for part in middle {
+        let (value, after) = remaining.split_once(part.as_str())?;
+        values.push(value);
+        remaining = after;
+    }
+    values.push(remaining);
+    Some(values)
+}
+
+/// Message catalogs with a default locale.
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    /// Locale used when none of the requested locales has a translation.
+    default_locale: String,
+
+    /// Catalogs by lowercase locale.
+    catalogs: HashMap<String, Catalog>,
+}
+
+impl Localizer {
+    /// Create a localizer without catalogs.
+    #[must_use]
+    pub fn new(default_locale: &str) -> Self {
+        Self {
+            default_locale: default_locale.to_lowercase(),
+            catalogs: HashMap::new(),
+        }
+    }
+
+    /// Create a localizer with the catalogs (`<locale>.json`) in a directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a catalog cannot be read.
+    pub fn load(default_locale: &str, dir: &Path) -> crate::Result<Self> {
+        let mut localizer = Self::new(default_locale);
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let messages: HashMap<String, String> = serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| crate::Error::config(format!("Invalid catalog {}: {}", path.display(), e)))?;
+            tracing::debug!("Loaded {} translations for locale {}", messages.len(), locale);
+            localizer.insert(locale, Catalog::new(messages));
+        }
+
+        Ok(localizer)
+    }
+
+    /// Add or replace the catalog of a locale.
+    pub fn insert(&mut self, locale: &str, catalog: Catalog) {
+        self.catalogs.insert(locale.to_lowercase(), catalog);
+    }
+
+    /// Get the default locale.
+    #[must_use]
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    /// Get the locales with a catalog.
+    #[must_use]
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.catalogs.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    /// Build the fallback chain of requested locales, most preferred first.
+    ///
+    /// Each locale is followed by its parents, and the chain ends with the
+    /// default locale.
+    #[must_use]
+    pub fn fallback_chain(&self, requested: &[String]) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut push = |locale: String| {
+            if !locale.is_empty() && !chain.contains(&locale) {
+                chain.push(locale);
+            }
+        };
+
+        for locale in requested {
+            let locale = locale.trim().replace('_', "-").to_lowercase();
+            let mut parts: Vec<&str> = locale.split('-').collect();
+            while !parts.is_empty() {
+                push(parts.join("-"));
+                parts.pop();
+            }
+        }
+        push(self.default_locale.clone());
+        chain
+    }
+
+    /// Translate a message into the first locale of the chain with a
+    /// translation, returning that locale and the translation.
+    #[must_use]
+    pub fn translate(&self, message: &str, chain: &[String]) -> Option<(String, String)> {
+        chain.iter().find_map(|locale| {
+            let translation = self.catalogs.get(locale)?.translate(message)?;
+            Some((locale.clone(), translation))
+        })
+    }
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+/// Parse an `Accept-Language` header into locales, most preferred first.
+///
+/// Wildcards and locales with a quality of zero are skipped.
+#[must_use]
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut locales: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut params = entry.split(';');
+            let locale = params.next()?.trim();
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (!locale.is_empty() && locale != "*" && quality > 0.0).then(|| (locale.to_owned(), quality))
+        })
+        .collect();
+
+    // Stable, so equally preferred locales keep their order
+    locales.sort_by(|a, b| b.1.total_cmp(&a.1));
+    locales.into_iter().map(|(locale, _)| locale).collect()
+}
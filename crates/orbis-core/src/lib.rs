@@ -2,11 +2,22 @@
 //!
 //! Core types, errors, and utilities shared across all Orbis crates.
 
+pub mod crypto;
 pub mod error;
+pub mod hasher;
+pub mod i18n;
 pub mod mode;
 pub mod profile;
+pub mod shutdown;
 pub mod types;
 
+pub use crypto::EncryptionKey;
 pub use error::{Error, Result};
+pub use i18n::Localizer;
 pub use mode::{AppMode, RunMode};
-pub use profile::Profile;
+pub use profile::{KeyDerivation, Profile};
+pub use shutdown::{CancellationToken, DEFAULT_SHUTDOWN_TIMEOUT};
+pub use types::{
+    new_id, reset_id_generator, set_id_generator, IdGenerator, PluginId, ProfileId, SequentialIds, SessionId, UserId,
+    UuidV7Ids,
+};
@@ -1,13 +1,17 @@
 //! Connection profiles for Orbis.
 
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use crate::crypto::EncryptionKey;
+use crate::types::ProfileId;
+
+/// Value sealed with the key of an encrypted profile, to check passphrases.
+const KEY_CHECK: &[u8] = b"orbis-profile-key";
 
 /// A connection profile stores settings for connecting to a server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
     /// Unique identifier for the profile.
-    pub id: Uuid,
+    pub id: ProfileId,
 
     /// Human-readable name for the profile.
     pub name: String,
@@ -40,6 +44,10 @@ pub struct Profile {
     #[serde(default)]
     pub custom: serde_json::Value,
 
+    /// Encryption of the profile's data at rest, if encrypted.
+    #[serde(default)]
+    pub encryption: Option<KeyDerivation>,
+
     /// Creation timestamp.
     pub created_at: chrono::DateTime<chrono::Utc>,
 
@@ -58,7 +66,7 @@ impl Profile {
     pub fn new(name: impl Into<String>) -> Self {
         let now = chrono::Utc::now();
         Self {
-            id: Uuid::now_v7(),
+            id: ProfileId::generate(),
             name: name.into(),
             server_url: None,
             is_default: false,
@@ -67,6 +75,7 @@ impl Profile {
             ca_cert_path: None,
             auth_token: None,
             custom: serde_json::Value::Null,
+            encryption: None,
             created_at: now,
             updated_at: now,
         }
@@ -92,6 +101,64 @@ impl Profile {
         self.use_tls = use_tls;
         self
     }
+
+    /// Check if the profile's data is encrypted at rest.
+    #[must_use]
+    pub const fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+}
+
+/// Key derivation parameters of an encrypted profile.
+///
+/// The key itself is never stored: it is derived from the passphrase each
+/// time the profile is unlocked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyDerivation {
+    /// Salt of the key derivation (hex).
+    pub salt: String,
+
+    /// A known value sealed with the key (hex), to detect wrong passphrases.
+    pub check: String,
+}
+
+impl KeyDerivation {
+    /// Set up encryption with a passphrase, returning the parameters and the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the passphrase is empty or the key cannot be derived.
+    pub fn create(passphrase: &str) -> crate::Result<(Self, EncryptionKey)> {
+        if passphrase.is_empty() {
+            return Err(crate::Error::validation("Passphrase cannot be empty"));
+        }
+
+        let salt = EncryptionKey::generate_salt()?;
+        let key = EncryptionKey::derive(passphrase, &salt)?;
+        let encryption = Self {
+            salt: hex::encode(salt),
+            check: hex::encode(key.seal(KEY_CHECK)?),
+        };
+        Ok((encryption, key))
+    }
+
+    /// Derive the key of the profile from its passphrase.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the passphrase is wrong.
+    pub fn unlock(&self, passphrase: &str) -> crate::Result<EncryptionKey> {
+        let salt = hex::decode(&self.salt)
+            .map_err(|e| crate::Error::config(format!("Invalid profile encryption salt: {}", e)))?;
+        let check = hex::decode(&self.check)
+            .map_err(|e| crate::Error::config(format!("Invalid profile encryption check: {}", e)))?;
+
+        let key = EncryptionKey::derive(passphrase, &salt)?;
+        match key.open(&check) {
+            Ok(value) if value == KEY_CHECK => Ok(key),
+            _ => Err(crate::Error::unauthorized("Wrong passphrase")),
+        }
+    }
 }
 
 impl Default for Profile {
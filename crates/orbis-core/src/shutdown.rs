@@ -0,0 +1,235 @@
+//! Coordinated shutdown.
+//!
+//! Subsystems register with a [`Coordinator`] in one of the ordered
+//! [`Phase`]s. On shutdown, each phase in turn has its cancellation
+//! token cancelled, so its long-running tasks stop, then its cleanups run
+//! concurrently, each bounded by its own timeout. Tokens form a tree:
+//! cancelling the root, once all phases are done, cancels every token.
+
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Time a subsystem gets to clean up unless it registers another timeout.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Phase of a shutdown, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Stop accepting connections.
+    Listeners,
+
+    /// Stop watching files.
+    Watchers,
+
+    /// Drain job workers and background services.
+    Workers,
+
+    /// Release plugins.
+    Plugins,
+
+    /// Close databases and flush storage.
+    Storage,
+}
+
+impl Phase {
+    /// All phases, in order.
+    pub const ALL: [Self; 5] = [Self::Listeners, Self::Watchers, Self::Workers, Self::Plugins, Self::Storage];
+
+    /// Get the position of the phase.
+    const fn index(self) -> usize {
+        match self {
+            Self::Listeners => 0,
+            Self::Watchers => 1,
+            Self::Workers => 2,
+            Self::Plugins => 3,
+            Self::Storage => 4,
+        }
+    }
+}
+
+/// Cleanup future of a subsystem.
+type Cleanup = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A subsystem registered for shutdown.
+struct Subsystem {
+    /// Name, for reporting.
+    name: String,
+
+    /// Phase the subsystem is cleaned up in.
+    phase: Phase,
+
+    /// Time the cleanup may take.
+    timeout: Duration,
+
+    /// Creates the cleanup future.
+    cleanup: Box<dyn FnOnce() -> Cleanup + Send>,
+}
+
+/// Outcome of a shutdown.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    /// Subsystems that cleaned up in time.
+    pub completed: Vec<String>,
+
+    /// Subsystems whose cleanup timed out or panicked.
+    pub timed_out: Vec<String>,
+}
+
+/// Coordinates the shutdown of subsystems in ordered phases.
+pub struct Coordinator {
+    /// Root of the token tree.
+    root: CancellationToken,
+
+    /// Token of each phase, children of the root.
+    phases: Vec<CancellationToken>,
+
+    /// Subsystems waiting for shutdown.
+    subsystems: Mutex<Vec<Subsystem>>,
+
+    /// Set once shutdown started.
+    started: AtomicBool,
+}
+
+impl Coordinator {
+    /// Create a coordinator.
+    #[must_use]
+    pub fn new() -> Self {
+        let root = CancellationToken::new();
+        let phases = Phase::ALL.iter().map(|_| root.child_token()).collect();
+        Self {
+            root,
+            phases,
+            subsystems: Mutex::new(Vec::new()),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// Get a token cancelled when a phase starts.
+    ///
+    /// Long-running tasks stop when their token is cancelled.
+    #[must_use]
+    pub fn token(&self, phase: Phase) -> CancellationToken {
+        self.phases
+            .get(phase.index())
+            .map_or_else(|| self.root.child_token(), CancellationToken::child_token)
+    }
+
+    /// Check if shutdown started.
+    #[must_use]
+    pub fn is_shutting_down(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Register a subsystem cleanup run during a phase, within a timeout.
+    ///
+    /// Subsystems registered after shutdown started are cleaned up right
+    /// away.
+    pub fn register<F, Fut>(&self, name: &str, phase: Phase, timeout: Duration, cleanup: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let subsystem = Subsystem {
+            name: name.to_owned(),
+            phase,
+            timeout,
+            cleanup: Box::new(move || Box::pin(cleanup())),
+        };
+
+        if self.is_shutting_down() {
+            tracing::warn!("Subsystem '{}' registered during shutdown, cleaning up now", subsystem.name);
+            tokio::spawn(run_cleanup(subsystem));
+            return;
+        }
+        if let Ok(mut subsystems) = self.subsystems.lock() {
+            subsystems.push(subsystem);
+        }
+    }
+
+    /// Shut down: cancel each phase in order and run its cleanups.
+    ///
+    /// Only the first call shuts down; later calls wait for it to finish and
+    /// return an empty report.
+    pub async fn shutdown(&self) -> Report {
+        if self.started.swap(true, Ordering::SeqCst) {
+            self.root.cancelled().await;
+            return Report::default();
+        }
+
+        let mut subsystems = self
+            .subsystems
+            .lock()
+            .map(|mut subsystems| std::mem::take(&mut *subsystems))
+            .unwrap_or_default();
+
+        let mut report = Report::default();
+        for phase in Phase::ALL {
+            tracing::debug!("Shutdown phase: {:?}", phase);
+            if let Some(token) = self.phases.get(phase.index()) {
+                token.cancel();
+            }
+
+            let (current, rest): (Vec<_>, Vec<_>) = subsystems.into_iter().partition(|s| s.phase == phase);
+            subsystems = rest;
+
+            let mut cleanups = tokio::task::JoinSet::new();
+            for subsystem in current {
+                cleanups.spawn(run_cleanup(subsystem));
+            }
+            while let Some(result) = cleanups.join_next().await {
+                match result {
+                    Ok((name, true)) => report.completed.push(name),
+                    Ok((name, false)) => report.timed_out.push(name),
+                    Err(e) => report.timed_out.push(format!("cleanup task: {}", e)),
+                }
+            }
+        }
+
+        self.root.cancel();
+        report
+    }
+
+    /// Wait until shutdown finished.
+    pub async fn finished(&self) {
+        self.root.cancelled().await;
+    }
+}
+
+impl Default for Coordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Coordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Coordinator")
+            .field("started", &self.is_shutting_down())
+            .field("subsystems", &self.subsystems.lock().map(|s| s.len()).unwrap_or_default())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Run the cleanup of a subsystem within its timeout.
+///
+/// Returns the subsystem name and whether the cleanup finished in time.
+async fn run_cleanup(subsystem: Subsystem) -> (String, bool) {
+    let finished = tokio::time::timeout(subsystem.timeout, (subsystem.cleanup)()).await.is_ok();
+    if finished {
+        tracing::debug!("Subsystem '{}' shut down", subsystem.name);
+    } else {
+        tracing::warn!(
+            "Subsystem '{}' did not shut down within {:?}",
+            subsystem.name,
+            subsystem.timeout
+        );
+    }
+    (subsystem.name, finished)
+}
@@ -1,6 +1,9 @@
 //! Common types used across Orbis.
 
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use uuid::Uuid;
 
 /// API response wrapper for consistent response format.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -143,3 +146,214 @@ impl<T> PaginatedResponse<T> {
         }
     }
 }
+
+/// Source of new entity IDs.
+///
+/// IDs come from [`UuidV7Ids`] unless another generator is set with
+/// [`set_id_generator`], e.g. [`SequentialIds`] for reproducible tests.
+pub trait IdGenerator: Send + Sync {
+    /// Generate a new ID.
+    fn generate(&self) -> Uuid;
+}
+
+/// Time-ordered UUIDv7 IDs (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7Ids;
+
+impl IdGenerator for UuidV7Ids {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// Deterministic IDs: the seed in the high 64 bits, and a counter starting
+/// at 1 in the low 64 bits.
+#[derive(Debug, Default)]
+pub struct SequentialIds {
+    /// High 64 bits of every ID
+    seed: u64,
+    /// Number of IDs generated so far
+    next: AtomicU64,
+}
+
+impl SequentialIds {
+    /// Create a generator with the given seed.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            next: AtomicU64::new(0),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn generate(&self) -> Uuid {
+        let count = self.next.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+        Uuid::from_u64_pair(self.seed, count)
+    }
+}
+
+/// Generator set with [`set_id_generator`], if any.
+static ID_GENERATOR: RwLock<Option<Arc<dyn IdGenerator>>> = RwLock::new(None);
+
+/// Set the generator of new IDs for the whole process.
+pub fn set_id_generator(generator: Arc<dyn IdGenerator>) {
+    *ID_GENERATOR.write().unwrap_or_else(PoisonError::into_inner) = Some(generator);
+}
+
+/// Go back to generating UUIDv7 IDs.
+pub fn reset_id_generator() {
+    *ID_GENERATOR.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Generate a new ID with the configured generator.
+#[must_use]
+pub fn new_id() -> Uuid {
+    ID_GENERATOR
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .map_or_else(Uuid::now_v7, |generator| generator.generate())
+}
+
+/// Define a typed ID wrapping a [`Uuid`].
+///
+/// IDs serialize as their UUID. In the database, they are stored as `UUID`
+/// on PostgreSQL and as text on SQLite, like bare UUIDs are.
+macro_rules! typed_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(Uuid);
+
+        impl $name {
+            /// Generate a new ID with the configured generator.
+            #[must_use]
+            pub fn generate() -> Self {
+                Self(new_id())
+            }
+
+            /// Wrap an existing UUID.
+            #[must_use]
+            pub const fn from_uuid(id: Uuid) -> Self {
+                Self(id)
+            }
+
+            /// Get the wrapped UUID.
+            #[must_use]
+            pub const fn as_uuid(&self) -> &Uuid {
+                &self.0
+            }
+
+            /// Unwrap the UUID.
+            #[must_use]
+            pub const fn into_uuid(self) -> Uuid {
+                self.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(id: Uuid) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for Uuid {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = uuid::Error;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                s.parse().map(Self)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl sqlx::Type<sqlx::Postgres> for $name {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <Uuid as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q> sqlx::Encode<'q, sqlx::Postgres> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <sqlx::Postgres as sqlx::Database>::ArgumentBuffer<'q>,
+            ) -> std::result::Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                <Uuid as sqlx::Encode<'q, sqlx::Postgres>>::encode_by_ref(&self.0, buf)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $name {
+            fn decode(
+                value: <sqlx::Postgres as sqlx::Database>::ValueRef<'r>,
+            ) -> std::result::Result<Self, sqlx::error::BoxDynError> {
+                <Uuid as sqlx::Decode<'r, sqlx::Postgres>>::decode(value).map(Self)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl sqlx::Type<sqlx::Sqlite> for $name {
+            fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+                <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::sqlite::SqliteTypeInfo) -> bool {
+                <String as sqlx::Type<sqlx::Sqlite>>::compatible(ty)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <sqlx::Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
+            ) -> std::result::Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                <String as sqlx::Encode<'q, sqlx::Sqlite>>::encode(self.0.to_string(), buf)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'r> sqlx::Decode<'r, sqlx::Sqlite> for $name {
+            fn decode(
+                value: <sqlx::Sqlite as sqlx::Database>::ValueRef<'r>,
+            ) -> std::result::Result<Self, sqlx::error::BoxDynError> {
+                Ok(Self(<&str as sqlx::Decode<'r, sqlx::Sqlite>>::decode(value)?.parse()?))
+            }
+        }
+    };
+}
+
+typed_id! {
+    /// ID of a loaded plugin.
+    PluginId
+}
+
+typed_id! {
+    /// ID of a user.
+    UserId
+}
+
+typed_id! {
+    /// ID of a session.
+    SessionId
+}
+
+typed_id! {
+    /// ID of a connection profile.
+    ProfileId
+}
@@ -0,0 +1,17 @@
+-- Profile- and user-scoped settings for Orbis (PostgreSQL)
+-- System-scoped settings stay in the settings table.
+
+CREATE TABLE IF NOT EXISTS scoped_settings (
+    scope VARCHAR(16) NOT NULL,
+    scope_id VARCHAR(64) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, scope_id, key)
+);
+
+-- Triggers for updated_at
+CREATE TRIGGER update_scoped_settings_updated_at
+    BEFORE UPDATE ON scoped_settings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,15 @@
+-- Plugin resource samples for Orbis (PostgreSQL)
+-- Minute samples are rolled up into hourly ones as they age.
+
+CREATE TABLE IF NOT EXISTS plugin_metrics (
+    plugin_name VARCHAR(255) NOT NULL,
+    resolution VARCHAR(10) NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    memory_bytes BIGINT NOT NULL DEFAULT 0,
+    calls BIGINT NOT NULL DEFAULT 0,
+    errors BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (plugin_name, resolution, bucket_start)
+);
+
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_plugin_metrics_resolution_bucket ON plugin_metrics(resolution, bucket_start);
@@ -0,0 +1,11 @@
+-- Feature flag overrides of plugins (PostgreSQL)
+-- Flags without an override use the default from the plugin manifest.
+
+CREATE TABLE IF NOT EXISTS plugin_features (
+    plugin VARCHAR(255) NOT NULL,
+    flag VARCHAR(64) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_by UUID,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (plugin, flag)
+);
@@ -0,0 +1,19 @@
+-- Self-service account management (PostgreSQL)
+
+-- Content type of the user's avatar, when one is stored
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_content_type VARCHAR(255);
+
+-- When the user asked for their account to be deleted; it is deleted once
+-- the grace period has passed unless the request is cancelled
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_requested_at TIMESTAMPTZ;
+
+-- Email address changes waiting for the new address to be verified
+CREATE TABLE IF NOT EXISTS email_changes (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_changes_user_id ON email_changes(user_id);
@@ -0,0 +1,15 @@
+-- Per-tenant usernames and emails (PostgreSQL)
+-- Two tenants may each have an "admin"; names stay unique within a tenant
+-- and within platform scope (tenant_id NULL).
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_username_key;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_tenant_username
+    ON users(tenant_id, username) WHERE tenant_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_tenant_email
+    ON users(tenant_id, email) WHERE tenant_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_platform_username
+    ON users(username) WHERE tenant_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_platform_email
+    ON users(email) WHERE tenant_id IS NULL;
@@ -0,0 +1,44 @@
+-- Multi-tenancy for Orbis (SQLite)
+
+-- Tenants table
+CREATE TABLE IF NOT EXISTS tenants (
+    id TEXT PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Tenant-scoped users and sessions (NULL when tenancy is disabled)
+ALTER TABLE users ADD COLUMN tenant_id TEXT REFERENCES tenants(id) ON DELETE CASCADE;
+ALTER TABLE sessions ADD COLUMN tenant_id TEXT REFERENCES tenants(id) ON DELETE CASCADE;
+
+-- Per-tenant plugin configuration overrides
+CREATE TABLE IF NOT EXISTS tenant_plugin_configs (
+    tenant_id TEXT NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    plugin_name TEXT NOT NULL,
+    config TEXT NOT NULL DEFAULT '{}',
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (tenant_id, plugin_name)
+);
+
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_users_tenant_id ON users(tenant_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_tenant_id ON sessions(tenant_id);
+
+-- Triggers for updated_at (SQLite)
+CREATE TRIGGER IF NOT EXISTS update_tenants_updated_at
+    AFTER UPDATE ON tenants
+    FOR EACH ROW
+BEGIN
+    UPDATE tenants SET updated_at = datetime('now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS update_tenant_plugin_configs_updated_at
+    AFTER UPDATE ON tenant_plugin_configs
+    FOR EACH ROW
+BEGIN
+    UPDATE tenant_plugin_configs SET updated_at = datetime('now')
+    WHERE tenant_id = NEW.tenant_id AND plugin_name = NEW.plugin_name;
+END;
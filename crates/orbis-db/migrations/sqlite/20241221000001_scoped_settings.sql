@@ -0,0 +1,20 @@
+-- Profile- and user-scoped settings for Orbis (SQLite)
+-- System-scoped settings stay in the settings table.
+
+CREATE TABLE IF NOT EXISTS scoped_settings (
+    scope TEXT NOT NULL,
+    scope_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (scope, scope_id, key)
+);
+
+-- Triggers for updated_at (SQLite)
+CREATE TRIGGER IF NOT EXISTS update_scoped_settings_updated_at
+    AFTER UPDATE ON scoped_settings
+    FOR EACH ROW
+BEGIN
+    UPDATE scoped_settings SET updated_at = datetime('now')
+    WHERE scope = NEW.scope AND scope_id = NEW.scope_id AND key = NEW.key;
+END;
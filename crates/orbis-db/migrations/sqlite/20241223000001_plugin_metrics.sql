@@ -0,0 +1,16 @@
+-- Plugin resource samples for Orbis (SQLite)
+-- Minute samples are rolled up into hourly ones as they age. Timestamps are
+-- RFC 3339 strings, as written by the server.
+
+CREATE TABLE IF NOT EXISTS plugin_metrics (
+    plugin_name TEXT NOT NULL,
+    resolution TEXT NOT NULL,
+    bucket_start TEXT NOT NULL,
+    memory_bytes INTEGER NOT NULL DEFAULT 0,
+    calls INTEGER NOT NULL DEFAULT 0,
+    errors INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (plugin_name, resolution, bucket_start)
+);
+
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_plugin_metrics_resolution_bucket ON plugin_metrics(resolution, bucket_start);
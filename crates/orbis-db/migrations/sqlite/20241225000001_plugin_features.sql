@@ -0,0 +1,11 @@
+-- Feature flag overrides of plugins (SQLite)
+-- Flags without an override use the default from the plugin manifest.
+
+CREATE TABLE IF NOT EXISTS plugin_features (
+    plugin TEXT NOT NULL,
+    flag TEXT NOT NULL,
+    enabled INTEGER NOT NULL,
+    updated_by TEXT,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (plugin, flag)
+);
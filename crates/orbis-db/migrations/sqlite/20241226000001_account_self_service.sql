@@ -0,0 +1,19 @@
+-- Self-service account management (SQLite)
+
+-- Content type of the user's avatar, when one is stored
+ALTER TABLE users ADD COLUMN avatar_content_type TEXT;
+
+-- When the user asked for their account to be deleted; it is deleted once
+-- the grace period has passed unless the request is cancelled
+ALTER TABLE users ADD COLUMN deletion_requested_at TEXT;
+
+-- Email address changes waiting for the new address to be verified
+CREATE TABLE IF NOT EXISTS email_changes (
+    token_hash TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_changes_user_id ON email_changes(user_id);
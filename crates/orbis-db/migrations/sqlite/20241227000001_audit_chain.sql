@@ -0,0 +1,14 @@
+-- Tamper-evident audit log (SQLite)
+-- Entries are numbered and hash-chained: each entry's hash covers its
+-- content and the previous entry's hash. The actor and tenant are copied
+-- without foreign keys, so deleting users or tenants does not alter entries.
+
+ALTER TABLE audit_logs ADD COLUMN seq INTEGER;
+ALTER TABLE audit_logs ADD COLUMN actor_id TEXT;
+ALTER TABLE audit_logs ADD COLUMN tenant_id TEXT;
+ALTER TABLE audit_logs ADD COLUMN prev_hash TEXT;
+ALTER TABLE audit_logs ADD COLUMN entry_hash TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_logs_seq ON audit_logs(seq);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_tenant_id ON audit_logs(tenant_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(action);
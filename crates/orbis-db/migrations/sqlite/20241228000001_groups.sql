@@ -0,0 +1,33 @@
+-- User groups (SQLite)
+-- Groups nest: members of a group are also members of its ancestors.
+
+CREATE TABLE IF NOT EXISTS user_groups (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT REFERENCES tenants(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    parent_id TEXT REFERENCES user_groups(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS user_group_members (
+    id TEXT PRIMARY KEY,
+    group_id TEXT NOT NULL REFERENCES user_groups(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (group_id, user_id)
+);
+
+-- Indexes
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_groups_name ON user_groups(COALESCE(tenant_id, ''), name);
+CREATE INDEX IF NOT EXISTS idx_user_groups_parent_id ON user_groups(parent_id);
+CREATE INDEX IF NOT EXISTS idx_user_group_members_user_id ON user_group_members(user_id);
+
+-- Triggers for updated_at (SQLite)
+CREATE TRIGGER IF NOT EXISTS update_user_groups_updated_at
+    AFTER UPDATE ON user_groups
+    FOR EACH ROW
+BEGIN
+    UPDATE user_groups SET updated_at = datetime('now') WHERE id = NEW.id;
+END;
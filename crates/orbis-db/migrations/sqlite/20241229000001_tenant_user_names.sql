@@ -0,0 +1,49 @@
+-- Per-tenant usernames and emails (SQLite)
+-- Two tenants may each have an "admin"; names stay unique within a tenant
+-- and within platform scope (tenant_id NULL). SQLite cannot drop column
+-- constraints, so the table is rebuilt; migrations run with foreign keys
+-- off, keeping dependent rows from cascading away when it is dropped.
+
+CREATE TABLE users_new (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    email TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    display_name TEXT,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    is_admin INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    tenant_id TEXT REFERENCES tenants(id) ON DELETE CASCADE,
+    avatar_content_type TEXT,
+    deletion_requested_at TEXT
+);
+
+INSERT INTO users_new (
+    id, username, email, password_hash, display_name, is_active, is_admin,
+    created_at, updated_at, tenant_id, avatar_content_type, deletion_requested_at
+)
+SELECT
+    id, username, email, password_hash, display_name, is_active, is_admin,
+    created_at, updated_at, tenant_id, avatar_content_type, deletion_requested_at
+FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE INDEX IF NOT EXISTS idx_users_tenant_id ON users(tenant_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_tenant_username
+    ON users(tenant_id, username) WHERE tenant_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_tenant_email
+    ON users(tenant_id, email) WHERE tenant_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_platform_username
+    ON users(username) WHERE tenant_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_platform_email
+    ON users(email) WHERE tenant_id IS NULL;
+
+CREATE TRIGGER IF NOT EXISTS update_users_updated_at
+    AFTER UPDATE ON users
+    FOR EACH ROW
+BEGIN
+    UPDATE users SET updated_at = datetime('now') WHERE id = NEW.id;
+END;
@@ -0,0 +1,312 @@
+//! Query result cache.
+//!
+//! Results are keyed by the normalized SQL and the query parameters, and
+//! remember the tables the query reads. Writers invalidate a table (or pass
+//! their statement to [`QueryCache::invalidate_statement`]) and every cached
+//! result reading it is dropped, so cached reads never outlive a write made
+//! through the cache. Writes made around it are only caught by the TTL.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Keywords followed by the name of a table a statement reads or writes.
+const TABLE_KEYWORDS: &[&str] = &["from", "join", "into", "update", "table"];
+
+/// A cached result.
+struct CachedQuery {
+    /// Tables the query reads.
+    tables: BTreeSet<String>,
+
+    /// Query result.
+    value: Arc<dyn Any + Send + Sync>,
+
+    /// When the entry was stored.
+    stored_at: Instant,
+}
+
+/// Query cache statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct QueryCacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+
+    /// Lookups that missed.
+    pub misses: u64,
+
+    /// Entries dropped by invalidation.
+    pub invalidations: u64,
+
+    /// Cached results.
+    pub entries: usize,
+
+    /// Share of lookups answered from the cache.
+    pub hit_rate: f64,
+}
+
+/// Cache of query results, invalidated by table.
+pub struct QueryCache {
+    /// Cached results by key.
+    entries: RwLock<HashMap<String, CachedQuery>>,
+
+    /// How long results stay fresh.
+    ttl: Duration,
+
+    /// Most results kept.
+    capacity: usize,
+
+    /// Lookups answered from the cache.
+    hits: AtomicU64,
+
+    /// Lookups that missed.
+    misses: AtomicU64,
+
+    /// Entries dropped by invalidation.
+    invalidations: AtomicU64,
+}
+
+impl std::fmt::Debug for QueryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl QueryCache {
+    /// Create a cache keeping at most `capacity` results for `ttl`.
+    #[must_use]
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            ttl,
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Build the key of a query: its normalized SQL and parameters.
+    fn key(sql: &str, params: &str) -> String {
+        format!("{}\u{0}{}", normalize_sql(sql), params)
+    }
+
+    /// Get a fresh cached result.
+    ///
+    /// `params` must be encoded the same way as when the result was stored.
+    #[must_use]
+    pub fn get<V: Clone + Send + Sync + 'static>(&self, sql: &str, params: &str) -> Option<V> {
+        let value = self
+            .entries
+            .read()
+            .get(&Self::key(sql, params))
+            .filter(|entry| entry.stored_at.elapsed() < self.ttl)
+            .and_then(|entry| entry.value.downcast_ref::<V>().cloned());
+
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Store the result of a query.
+    ///
+    /// Queries without a recognizable table are not cached, since no write
+    /// could invalidate them.
+    pub fn insert<V: Clone + Send + Sync + 'static>(&self, sql: &str, params: &str, value: V) {
+        let tables = statement_tables(sql);
+        if tables.is_empty() || self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.write();
+        if entries.len() >= self.capacity {
+            entries.retain(|_, entry| entry.stored_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.capacity
+            && let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone())
+        {
+            entries.remove(&oldest);
+        }
+
+        entries.insert(
+            Self::key(sql, params),
+            CachedQuery {
+                tables,
+                value: Arc::new(value),
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop the results reading a table.
+    pub fn invalidate_table(&self, table: &str) {
+        let table = table.to_lowercase();
+        self.drop_where(|entry| entry.tables.contains(&table));
+    }
+
+    /// Drop the results reading a table a statement writes.
+    pub fn invalidate_statement(&self, sql: &str) {
+        let tables = statement_tables(sql);
+        self.drop_where(|entry| !entry.tables.is_disjoint(&tables));
+    }
+
+    /// Drop every result.
+    pub fn clear(&self) {
+        self.drop_where(|_| true);
+    }
+
+    /// Drop the entries matching a predicate, counting them as invalidations.
+    fn drop_where<F: Fn(&CachedQuery) -> bool>(&self, predicate: F) {
+        let dropped = {
+            let mut entries = self.entries.write();
+            let before = entries.len();
+            entries.retain(|_, entry| !predicate(entry));
+            u64::try_from(before.saturating_sub(entries.len())).unwrap_or(u64::MAX)
+        };
+        self.invalidations.fetch_add(dropped, Ordering::Relaxed);
+    }
+
+    /// Get the cache statistics.
+    #[must_use]
+    pub fn stats(&self) -> QueryCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits.saturating_add(misses);
+
+        QueryCacheStats {
+            hits,
+            misses,
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: self.entries.read().len(),
+            #[allow(clippy::cast_precision_loss, reason = "Hit rates need no exact counts")]
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+        }
+    }
+}
+
+/// Normalize SQL for use in a cache key: collapse whitespace and lowercase
+/// everything outside quoted literals and identifiers.
+#[must_use]
+pub fn normalize_sql(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut space = false;
+
+    for c in sql.trim().chars() {
+        if let Some(open) = quote {
+            normalized.push(c);
+            if c == open {
+                quote = None;
+            }
+        } else if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                normalized.push(' ');
+                space = false;
+            }
+            if matches!(c, '\'' | '"' | '`') {
+                quote = Some(c);
+            }
+            normalized.extend(c.to_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Get the tables a statement reads or writes, lowercased and unquoted.
+///
+/// Every table of a comma-separated list is included, e.g. both tables of
+/// `FROM a x, b y`.
+#[must_use]
+pub fn statement_tables(sql: &str) -> BTreeSet<String> {
+    let normalized = normalize_sql(sql);
+    let mut spaced = String::with_capacity(normalized.len());
+    for c in normalized.chars() {
+        if matches!(c, ',' | '(' | ')' | ';') {
+            spaced.push(' ');
+            spaced.push(c);
+            spaced.push(' ');
+        } else {
+            spaced.push(c);
+        }
+    }
+    let words: Vec<&str> = spaced.split_whitespace().collect();
+
+    let mut tables = BTreeSet::new();
+    let mut rest = words.as_slice();
+    while let &[word, ref tail @ ..] = rest {
+        rest = tail;
+        if !TABLE_KEYWORDS.contains(&word) {
+            continue;
+        }
+
+        // Follow the list of tables, skipping their aliases
+        while let &[name, ref tail @ ..] = rest {
+            let Some(table) = table_name(name) else {
+                break;
+            };
+            tables.insert(table);
+            let after_alias = match *tail {
+                ["as", _, ref after @ ..] => after,
+                [alias, ref after @ ..] if table_name(alias).is_some() => after,
+                _ => tail,
+            };
+            let [",", ref next @ ..] = *after_alias else {
+                rest = tail;
+                break;
+            };
+            rest = next;
+        }
+    }
+    tables
+}
+
+/// Get the table a word names, without its schema and quotes.
+fn table_name(word: &str) -> Option<String> {
+    let table = word.rsplit('.').next().unwrap_or_default();
+    let table = table.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+    let is_name = !table.is_empty() && table.chars().all(|c| c.is_alphanumeric() || c == '_');
+    (is_name && !matches!(table, "select" | "only" | "if")).then(|| table.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_tables() {
+        let tables = |sql: &str| statement_tables(sql).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(tables("SELECT a, b FROM notes WHERE id IN (1, 2)"), ["notes"]);
+        assert_eq!(tables("SELECT * FROM notes n JOIN tags t ON t.note = n.id"), ["notes", "tags"]);
+        assert_eq!(tables("INSERT INTO notes (a, b) VALUES (1, 2)"), ["notes"]);
+        assert_eq!(tables("UPDATE notes SET a = 1, b = 2"), ["notes"]);
+    }
+
+    #[test]
+    fn test_statement_tables_comma_join() {
+        let tables = |sql: &str| statement_tables(sql).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(tables("SELECT * FROM notes, tags WHERE tags.note = notes.id"), ["notes", "tags"]);
+        let sql = "SELECT * FROM notes n, main.tags AS t, users u ORDER BY a, b";
+        assert_eq!(tables(sql), ["notes", "tags", "users"]);
+        assert_eq!(tables("SELECT * FROM notes n, (SELECT * FROM tags) t"), ["notes", "tags"]);
+
+        // A write to any joined table invalidates the cached result
+        let cache = QueryCache::new(Duration::from_secs(60), 8);
+        cache.insert("SELECT * FROM notes, tags", "[]", 1);
+        cache.invalidate_statement("DELETE FROM tags");
+        assert_eq!(cache.get::<i32>("SELECT * FROM notes, tags", "[]"), None);
+    }
+}
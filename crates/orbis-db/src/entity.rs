@@ -0,0 +1,951 @@
+//! Entities and their generated repositories.
+//!
+//! [`entity!`](crate::entity) declares a struct mapped to a table, and
+//! [`EntityRepository`] implements [`Repository`] for it on both backends:
+//! CRUD, filtered lists, and soft delete for entities with a deletion
+//! timestamp column.
+//!
+//! Values are stored the way the hand-written services store them:
+//! PostgreSQL uses native UUID, TIMESTAMPTZ and JSONB columns, while SQLite
+//! stores UUIDs, RFC 3339 timestamps and JSON as TEXT. Comparisons on
+//! SQLite timestamp columns are therefore text comparisons.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use orbis_core::{PluginId, ProfileId, SessionId, UserId};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Postgres, Row as _, Sqlite, ValueRef as _};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{Database, DatabasePool, QueryCache, Repository};
+
+/// A value bound to a query, typed so `NULL` binds with its column type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbValue {
+    /// Boolean.
+    Bool(Option<bool>),
+
+    /// 32-bit integer.
+    Int(Option<i32>),
+
+    /// 64-bit integer.
+    BigInt(Option<i64>),
+
+    /// Floating point number.
+    Float(Option<f64>),
+
+    /// Text.
+    Text(Option<String>),
+
+    /// UUID (TEXT on SQLite).
+    Uuid(Option<Uuid>),
+
+    /// Timestamp (RFC 3339 TEXT on SQLite).
+    Time(Option<DateTime<Utc>>),
+
+    /// JSON (TEXT on SQLite).
+    Json(Option<Value>),
+}
+
+/// A type stored in an entity column.
+pub trait Column: Sized {
+    /// Get the value to bind.
+    fn to_value(&self) -> DbValue;
+
+    /// Get the `NULL` value of the column type.
+    fn null() -> DbValue;
+
+    /// Read the column from a PostgreSQL row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column is missing or has another type.
+    fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self>;
+
+    /// Read the column from a SQLite row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column is missing or has another type.
+    fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self>;
+}
+
+/// Convert a column read error.
+fn column_error(column: &str, e: impl std::fmt::Display) -> orbis_core::Error {
+    orbis_core::Error::database(format!("Failed to read column {}: {}", column, e))
+}
+
+/// Implement [`Column`] for a type both backends decode natively.
+macro_rules! native_column {
+    ($ty:ty, $variant:ident) => {
+        impl Column for $ty {
+            fn to_value(&self) -> DbValue {
+                DbValue::$variant(Some(self.clone()))
+            }
+
+            fn null() -> DbValue {
+                DbValue::$variant(None)
+            }
+
+            fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+                row.try_get(column).map_err(|e| column_error(column, e))
+            }
+
+            fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+                row.try_get(column).map_err(|e| column_error(column, e))
+            }
+        }
+    };
+}
+
+native_column!(bool, Bool);
+native_column!(i32, Int);
+native_column!(i64, BigInt);
+native_column!(f64, Float);
+native_column!(String, Text);
+
+impl Column for Uuid {
+    fn to_value(&self) -> DbValue {
+        DbValue::Uuid(Some(*self))
+    }
+
+    fn null() -> DbValue {
+        DbValue::Uuid(None)
+    }
+
+    fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+        row.try_get(column).map_err(|e| column_error(column, e))
+    }
+
+    fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+        let text: String = row.try_get(column).map_err(|e| column_error(column, e))?;
+        text.parse().map_err(|e| column_error(column, e))
+    }
+}
+
+/// Implement [`Column`] for a typed ID, stored like its UUID.
+macro_rules! id_column {
+    ($($ty:ty),*) => {
+        $(
+            impl Column for $ty {
+                fn to_value(&self) -> DbValue {
+                    DbValue::Uuid(Some(self.into_uuid()))
+                }
+
+                fn null() -> DbValue {
+                    DbValue::Uuid(None)
+                }
+
+                fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+                    Uuid::from_pg(row, column).map(Self::from)
+                }
+
+                fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+                    Uuid::from_sqlite(row, column).map(Self::from)
+                }
+            }
+        )*
+    };
+}
+
+id_column!(PluginId, ProfileId, SessionId, UserId);
+
+impl Column for DateTime<Utc> {
+    fn to_value(&self) -> DbValue {
+        DbValue::Time(Some(*self))
+    }
+
+    fn null() -> DbValue {
+        DbValue::Time(None)
+    }
+
+    fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+        row.try_get(column).map_err(|e| column_error(column, e))
+    }
+
+    fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+        let text: String = row.try_get(column).map_err(|e| column_error(column, e))?;
+        parse_sqlite_time(&text).map_err(|e| column_error(column, e))
+    }
+}
+
+impl Column for Value {
+    fn to_value(&self) -> DbValue {
+        DbValue::Json(Some(self.clone()))
+    }
+
+    fn null() -> DbValue {
+        DbValue::Json(None)
+    }
+
+    fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+        row.try_get(column).map_err(|e| column_error(column, e))
+    }
+
+    fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+        let text: String = row.try_get(column).map_err(|e| column_error(column, e))?;
+        serde_json::from_str(&text).map_err(|e| column_error(column, e))
+    }
+}
+
+impl<T: Column> Column for Option<T> {
+    fn to_value(&self) -> DbValue {
+        self.as_ref().map_or_else(T::null, T::to_value)
+    }
+
+    fn null() -> DbValue {
+        T::null()
+    }
+
+    fn from_pg(row: &PgRow, column: &str) -> orbis_core::Result<Self> {
+        let raw = row.try_get_raw(column).map_err(|e| column_error(column, e))?;
+        if raw.is_null() {
+            return Ok(None);
+        }
+        T::from_pg(row, column).map(Some)
+    }
+
+    fn from_sqlite(row: &SqliteRow, column: &str) -> orbis_core::Result<Self> {
+        let raw = row.try_get_raw(column).map_err(|e| column_error(column, e))?;
+        if raw.is_null() {
+            return Ok(None);
+        }
+        T::from_sqlite(row, column).map(Some)
+    }
+}
+
+/// Parse a SQLite timestamp: RFC 3339, or `datetime('now')` UTC.
+fn parse_sqlite_time(text: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(text).map_or_else(
+        |_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc()),
+        |time| Ok(time.with_timezone(&Utc)),
+    )
+}
+
+/// A struct mapped to a table, implemented by [`entity!`](crate::entity).
+pub trait Entity: Clone + Sized + Send + Sync + Unpin + 'static {
+    /// Table name.
+    const TABLE: &'static str;
+
+    /// Column names, in field order.
+    const COLUMNS: &'static [&'static str];
+
+    /// Primary key column.
+    const ID_COLUMN: &'static str;
+
+    /// Deletion timestamp column, for soft-deleted entities.
+    const SOFT_DELETE_COLUMN: Option<&'static str>;
+
+    /// Get the primary key.
+    fn id(&self) -> Uuid;
+
+    /// Get the column values, in [`COLUMNS`](Self::COLUMNS) order.
+    fn values(&self) -> Vec<DbValue>;
+
+    /// Read an entity from a PostgreSQL row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is missing or has another type.
+    fn from_pg_row(row: &PgRow) -> orbis_core::Result<Self>;
+
+    /// Read an entity from a SQLite row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is missing or has another type.
+    fn from_sqlite_row(row: &SqliteRow) -> orbis_core::Result<Self>;
+}
+
+/// Declare an entity struct and implement [`Entity`] for it.
+///
+/// The first line names the table, the primary key field (a `Uuid`), and
+/// optionally the `Option<DateTime<Utc>>` field marking soft-deleted rows.
+/// Field names are column names.
+///
+/// ```ignore
+/// orbis_db::entity! {
+///     table: "webhooks", id: id, soft_delete: deleted_at;
+///
+///     /// A webhook.
+///     #[derive(Debug, Clone, Serialize, Deserialize)]
+///     pub struct Webhook {
+///         pub id: Uuid,
+///         pub url: String,
+///         pub deleted_at: Option<DateTime<Utc>>,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! entity {
+    (
+        table: $table:literal, id: $id:ident $(, soft_delete: $soft_delete:ident)? $(,)? ;
+
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $( $(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $( $(#[$field_meta])* $field_vis $field : $ty ),*
+        }
+
+        impl $crate::Entity for $name {
+            const TABLE: &'static str = $table;
+            const COLUMNS: &'static [&'static str] = &[$(stringify!($field)),*];
+            const ID_COLUMN: &'static str = stringify!($id);
+            const SOFT_DELETE_COLUMN: Option<&'static str> = {
+                let column: Option<&'static str> = None;
+                $(let column = Some(stringify!($soft_delete));)?
+                column
+            };
+
+            fn id(&self) -> $crate::__private::Uuid {
+                self.$id
+            }
+
+            fn values(&self) -> Vec<$crate::DbValue> {
+                vec![$($crate::Column::to_value(&self.$field)),*]
+            }
+
+            fn from_pg_row(row: &$crate::__private::PgRow) -> $crate::__private::Result<Self> {
+                Ok(Self {
+                    $($field: $crate::Column::from_pg(row, stringify!($field))?),*
+                })
+            }
+
+            fn from_sqlite_row(row: &$crate::__private::SqliteRow) -> $crate::__private::Result<Self> {
+                Ok(Self {
+                    $($field: $crate::Column::from_sqlite(row, stringify!($field))?),*
+                })
+            }
+        }
+    };
+}
+
+/// Comparison in a [`Filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// `=`
+    Eq,
+    /// `<>`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `LIKE`
+    Like,
+}
+
+impl Op {
+    /// SQL operator.
+    const fn sql(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "<>",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Like => "LIKE",
+        }
+    }
+}
+
+/// A condition in a [`Filter`].
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /// Compare a column with a value.
+    Compare(String, Op, DbValue),
+
+    /// Column is `NULL`.
+    IsNull(String),
+
+    /// Column is not `NULL`.
+    IsNotNull(String),
+}
+
+/// Conditions and ordering for listing entities.
+///
+/// Column names are checked against the entity's columns when the filter
+/// is used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    /// Conditions, all of which must hold.
+    conditions: Vec<Condition>,
+
+    /// Order by column, descending if `true`.
+    order_by: Option<(String, bool)>,
+
+    /// Include soft-deleted entities.
+    with_deleted: bool,
+}
+
+impl Filter {
+    /// Create a filter matching every entity.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a comparison.
+    fn compare<V: Column>(mut self, column: &str, op: Op, value: &V) -> Self {
+        self.conditions
+            .push(Condition::Compare(column.to_owned(), op, value.to_value()));
+        self
+    }
+
+    /// Require a column to equal a value.
+    #[must_use]
+    pub fn equals<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Eq, value)
+    }
+
+    /// Require a column to differ from a value.
+    #[must_use]
+    pub fn not_equals<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Ne, value)
+    }
+
+    /// Require a column to be less than a value.
+    #[must_use]
+    pub fn less_than<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Lt, value)
+    }
+
+    /// Require a column to be at most a value.
+    #[must_use]
+    pub fn at_most<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Le, value)
+    }
+
+    /// Require a column to be greater than a value.
+    #[must_use]
+    pub fn greater_than<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Gt, value)
+    }
+
+    /// Require a column to be at least a value.
+    #[must_use]
+    pub fn at_least<V: Column>(self, column: &str, value: &V) -> Self {
+        self.compare(column, Op::Ge, value)
+    }
+
+    /// Require a column to match a `LIKE` pattern.
+    #[must_use]
+    pub fn like(self, column: &str, pattern: &str) -> Self {
+        self.compare(column, Op::Like, &pattern.to_owned())
+    }
+
+    /// Require a column to be `NULL`.
+    #[must_use]
+    pub fn is_null(mut self, column: &str) -> Self {
+        self.conditions.push(Condition::IsNull(column.to_owned()));
+        self
+    }
+
+    /// Require a column not to be `NULL`.
+    #[must_use]
+    pub fn is_not_null(mut self, column: &str) -> Self {
+        self.conditions.push(Condition::IsNotNull(column.to_owned()));
+        self
+    }
+
+    /// Order by a column, ascending.
+    #[must_use]
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by = Some((column.to_owned(), false));
+        self
+    }
+
+    /// Order by a column, descending.
+    #[must_use]
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.order_by = Some((column.to_owned(), true));
+        self
+    }
+
+    /// Include soft-deleted entities.
+    #[must_use]
+    pub const fn with_deleted(mut self) -> Self {
+        self.with_deleted = true;
+        self
+    }
+}
+
+/// A SQL statement being built, with its parameters.
+struct Statement {
+    /// Backend the placeholders are written for.
+    postgres: bool,
+
+    /// SQL text.
+    sql: String,
+
+    /// Bound values.
+    params: Vec<DbValue>,
+}
+
+impl Statement {
+    /// Start a statement.
+    const fn new(pool: &DatabasePool, sql: String) -> Self {
+        Self {
+            postgres: pool.is_postgres(),
+            sql,
+            params: Vec::new(),
+        }
+    }
+
+    /// Add a parameter and get its placeholder (`$n` on PostgreSQL, `?n` on SQLite).
+    fn param(&mut self, value: DbValue) -> String {
+        self.params.push(value);
+        let prefix = if self.postgres { '$' } else { '?' };
+        format!("{}{}", prefix, self.params.len())
+    }
+
+    /// Append a `WHERE` clause for a filter, and its `ORDER BY`.
+    fn filter<T: Entity>(&mut self, filter: &Filter) -> orbis_core::Result<()> {
+        let mut clauses = Vec::new();
+        if !filter.with_deleted
+            && let Some(column) = T::SOFT_DELETE_COLUMN
+        {
+            clauses.push(format!("{} IS NULL", column));
+        }
+
+        for condition in &filter.conditions {
+            let clause = match condition {
+                Condition::Compare(column, op, value) => {
+                    let placeholder = self.param(value.clone());
+                    format!("{} {} {}", checked_column::<T>(column)?, op.sql(), placeholder)
+                }
+                Condition::IsNull(column) => format!("{} IS NULL", checked_column::<T>(column)?),
+                Condition::IsNotNull(column) => format!("{} IS NOT NULL", checked_column::<T>(column)?),
+            };
+            clauses.push(clause);
+        }
+
+        if !clauses.is_empty() {
+            self.sql.push_str(" WHERE ");
+            self.sql.push_str(&clauses.join(" AND "));
+        }
+        if let Some((column, descending)) = &filter.order_by {
+            self.sql.push_str(" ORDER BY ");
+            self.sql.push_str(checked_column::<T>(column)?);
+            self.sql.push_str(if *descending { " DESC" } else { " ASC" });
+        }
+        Ok(())
+    }
+}
+
+/// Check a column belongs to an entity, so it is safe to put in SQL.
+fn checked_column<T: Entity>(column: &str) -> orbis_core::Result<&str> {
+    if T::COLUMNS.contains(&column) {
+        Ok(column)
+    } else {
+        Err(orbis_core::Error::validation(format!(
+            "Unknown column '{}' of {}",
+            column,
+            T::TABLE
+        )))
+    }
+}
+
+/// Bind a value to a PostgreSQL query.
+fn bind_pg(query: Query<'_, Postgres, PgArguments>, value: DbValue) -> Query<'_, Postgres, PgArguments> {
+    match value {
+        DbValue::Bool(value) => query.bind(value),
+        DbValue::Int(value) => query.bind(value),
+        DbValue::BigInt(value) => query.bind(value),
+        DbValue::Float(value) => query.bind(value),
+        DbValue::Text(value) => query.bind(value),
+        DbValue::Uuid(value) => query.bind(value),
+        DbValue::Time(value) => query.bind(value),
+        DbValue::Json(value) => query.bind(value),
+    }
+}
+
+/// Bind a value to a SQLite query, in the TEXT formats SQLite tables use.
+fn bind_sqlite<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: DbValue,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    match value {
+        DbValue::Bool(value) => query.bind(value),
+        DbValue::Int(value) => query.bind(value),
+        DbValue::BigInt(value) => query.bind(value),
+        DbValue::Float(value) => query.bind(value),
+        DbValue::Text(value) => query.bind(value),
+        DbValue::Uuid(value) => query.bind(value.map(|id| id.to_string())),
+        DbValue::Time(value) => query.bind(value.map(|time| time.to_rfc3339())),
+        DbValue::Json(value) => query.bind(value.map(|json| json.to_string())),
+    }
+}
+
+/// Kind of entity write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Write {
+    /// Insert a new row.
+    Insert,
+
+    /// Update the row with the entity's ID.
+    Update,
+
+    /// Insert a row, or update the row with the entity's ID.
+    Upsert,
+}
+
+/// Repository of an [`Entity`] on either backend.
+pub struct EntityRepository<T> {
+    /// Connection pool.
+    pool: DatabasePool,
+
+    /// Cache of read results, invalidated by the repository's writes.
+    cache: Option<Arc<QueryCache>>,
+
+    /// Entity type.
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for EntityRepository<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            cache: self.cache.clone(),
+            entity: PhantomData,
+        }
+    }
+}
+
+impl<T: Entity> EntityRepository<T> {
+    /// Create a repository.
+    #[must_use]
+    pub const fn new(pool: DatabasePool) -> Self {
+        Self {
+            pool,
+            cache: None,
+            entity: PhantomData,
+        }
+    }
+
+    /// Create a repository using a database's pool and query cache.
+    #[must_use]
+    pub fn for_database(db: &Database) -> Self {
+        Self {
+            pool: db.pool().clone(),
+            cache: db.query_cache().cloned(),
+            entity: PhantomData,
+        }
+    }
+
+    /// Drop the cached reads of the entity's table after a write.
+    fn invalidate(&self) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_table(T::TABLE);
+        }
+    }
+
+    /// Start a `SELECT` of all columns.
+    fn select(&self) -> Statement {
+        Statement::new(&self.pool, format!("SELECT {} FROM {}", T::COLUMNS.join(", "), T::TABLE))
+    }
+
+    /// Filter matching one entity by ID.
+    fn by_id(id: Uuid) -> Filter {
+        Filter::new().equals(T::ID_COLUMN, &id)
+    }
+
+    /// Run a query returning entities, through the cache.
+    async fn fetch(&self, statement: Statement) -> orbis_core::Result<Vec<T>> {
+        let Some(cache) = &self.cache else {
+            return self.fetch_rows(statement).await;
+        };
+
+        let (sql, params) = (statement.sql.clone(), format!("{:?}", statement.params));
+        if let Some(entities) = cache.get(&sql, &params) {
+            return Ok(entities);
+        }
+        let entities = self.fetch_rows(statement).await?;
+        cache.insert(&sql, &params, entities.clone());
+        Ok(entities)
+    }
+
+    /// Run a statement returning entities.
+    async fn fetch_rows(&self, statement: Statement) -> orbis_core::Result<Vec<T>> {
+        match &self.pool {
+            DatabasePool::Postgres(pool) => {
+                let query = statement
+                    .params
+                    .into_iter()
+                    .fold(sqlx::query(&statement.sql), bind_pg);
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(T::from_pg_row).collect()
+            }
+            DatabasePool::Sqlite(pool) => {
+                let query = statement
+                    .params
+                    .into_iter()
+                    .fold(sqlx::query(&statement.sql), bind_sqlite);
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(T::from_sqlite_row).collect()
+            }
+        }
+    }
+
+    /// Run a statement returning one integer.
+    async fn fetch_count(&self, statement: Statement) -> orbis_core::Result<i64> {
+        match &self.pool {
+            DatabasePool::Postgres(pool) => statement
+                .params
+                .into_iter()
+                .fold(sqlx::query(&statement.sql), bind_pg)
+                .fetch_one(pool)
+                .await
+                .and_then(|row| row.try_get(0)),
+            DatabasePool::Sqlite(pool) => statement
+                .params
+                .into_iter()
+                .fold(sqlx::query(&statement.sql), bind_sqlite)
+                .fetch_one(pool)
+                .await
+                .and_then(|row| row.try_get(0)),
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))
+    }
+
+    /// Run a write statement and get the number of affected rows.
+    async fn execute(&self, statement: Statement) -> orbis_core::Result<u64> {
+        let affected = match &self.pool {
+            DatabasePool::Postgres(pool) => statement
+                .params
+                .into_iter()
+                .fold(sqlx::query(&statement.sql), bind_pg)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected()),
+            DatabasePool::Sqlite(pool) => statement
+                .params
+                .into_iter()
+                .fold(sqlx::query(&statement.sql), bind_sqlite)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected()),
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        self.invalidate();
+        Ok(affected)
+    }
+
+    /// List the entities matching a filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the filter names an unknown column or the query fails.
+    pub async fn find_where(&self, filter: &Filter) -> orbis_core::Result<Vec<T>> {
+        let mut statement = self.select();
+        statement.filter::<T>(filter)?;
+        self.fetch(statement).await
+    }
+
+    /// List a page of the entities matching a filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the filter names an unknown column or the query fails.
+    pub async fn find_page(&self, filter: &Filter, offset: u32, limit: u32) -> orbis_core::Result<Vec<T>> {
+        let mut statement = self.select();
+        statement.filter::<T>(filter)?;
+        let limit = statement.param(i64::from(limit).to_value());
+        let offset = statement.param(i64::from(offset).to_value());
+        statement.sql = format!("{} LIMIT {} OFFSET {}", statement.sql, limit, offset);
+        self.fetch(statement).await
+    }
+
+    /// Count the entities matching a filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the filter names an unknown column or the query fails.
+    pub async fn count_where(&self, filter: &Filter) -> orbis_core::Result<u64> {
+        let mut statement = Statement::new(&self.pool, format!("SELECT COUNT(*) FROM {}", T::TABLE));
+        statement.filter::<T>(&Filter { order_by: None, ..filter.clone() })?;
+        let count = self.fetch_count(statement).await?;
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    /// Set columns of the entities matching a filter.
+    ///
+    /// Returns the number of updated entities.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is unknown or the update fails.
+    pub async fn update_where(&self, columns: &[(&str, DbValue)], filter: &Filter) -> orbis_core::Result<u64> {
+        let mut statement = Statement::new(&self.pool, format!("UPDATE {} SET ", T::TABLE));
+        let mut assignments = Vec::new();
+        for (column, value) in columns {
+            let column = checked_column::<T>(column)?;
+            let placeholder = statement.param(value.clone());
+            assignments.push(format!("{} = {}", column, placeholder));
+        }
+        statement.sql.push_str(&assignments.join(", "));
+        statement.filter::<T>(&Filter { order_by: None, ..filter.clone() })?;
+        self.execute(statement).await
+    }
+
+    /// Restore a soft-deleted entity.
+    ///
+    /// Returns `false` if it does not exist, is not deleted, or the entity
+    /// is not soft-deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn restore(&self, id: Uuid) -> orbis_core::Result<bool> {
+        let Some(column) = T::SOFT_DELETE_COLUMN else {
+            return Ok(false);
+        };
+        let filter = Self::by_id(id).is_not_null(column).with_deleted();
+        Ok(self
+            .update_where(&[(column, <Option<DateTime<Utc>>>::null())], &filter)
+            .await?
+            > 0)
+    }
+
+    /// Delete an entity for good, even if it is soft-deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn purge(&self, id: Uuid) -> orbis_core::Result<bool> {
+        let mut statement = Statement::new(&self.pool, format!("DELETE FROM {}", T::TABLE));
+        statement.filter::<T>(&Self::by_id(id).with_deleted())?;
+        Ok(self.execute(statement).await? > 0)
+    }
+
+    /// Insert an entity, or update the entity with its ID if there is one.
+    ///
+    /// Soft-deleted entities are updated too, so upserting one with an
+    /// unset deletion time restores it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write fails.
+    pub async fn upsert(&self, entity: &T) -> orbis_core::Result<T> {
+        self.write(entity, Write::Upsert).await
+    }
+
+    /// Insert or update an entity, returning the stored row.
+    async fn write(&self, entity: &T, write: Write) -> orbis_core::Result<T> {
+        let mut statement = Statement::new(&self.pool, String::new());
+        let values = entity.values();
+        if write == Write::Update {
+            let assignments: Vec<_> = T::COLUMNS
+                .iter()
+                .zip(values)
+                .filter(|(column, _)| **column != T::ID_COLUMN)
+                .map(|(column, value)| format!("{} = {}", column, statement.param(value)))
+                .collect();
+            let id = statement.param(entity.id().to_value());
+            statement.sql = format!(
+                "UPDATE {} SET {} WHERE {} = {}",
+                T::TABLE,
+                assignments.join(", "),
+                T::ID_COLUMN,
+                id
+            );
+        } else {
+            let placeholders: Vec<_> = values.into_iter().map(|value| statement.param(value)).collect();
+            statement.sql = format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                T::TABLE,
+                T::COLUMNS.join(", "),
+                placeholders.join(", ")
+            );
+        }
+        if write == Write::Upsert {
+            let assignments: Vec<_> = T::COLUMNS
+                .iter()
+                .filter(|column| **column != T::ID_COLUMN)
+                .map(|column| format!("{} = excluded.{}", column, column))
+                .collect();
+            statement.sql.push_str(" ON CONFLICT (");
+            statement.sql.push_str(T::ID_COLUMN);
+            statement.sql.push_str(") DO UPDATE SET ");
+            statement.sql.push_str(&assignments.join(", "));
+        }
+        statement.sql.push_str(" RETURNING ");
+        statement.sql.push_str(&T::COLUMNS.join(", "));
+
+        let stored = self.fetch_rows(statement).await;
+        self.invalidate();
+        stored?
+            .pop()
+            .ok_or_else(|| orbis_core::Error::not_found(format!("{} {} not found", T::TABLE, entity.id())))
+    }
+}
+
+#[async_trait]
+impl<T> Repository<T> for EntityRepository<T>
+where
+    T: Entity + Serialize + DeserializeOwned,
+{
+    type Error = orbis_core::Error;
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<T>, Self::Error> {
+        Ok(self.find_where(&Self::by_id(id)).await?.pop())
+    }
+
+    async fn find_all(&self) -> Result<Vec<T>, Self::Error> {
+        self.find_where(&Filter::new()).await
+    }
+
+    async fn find_paginated(&self, offset: u32, limit: u32) -> Result<Vec<T>, Self::Error> {
+        self.find_page(&Filter::new().order_by(T::ID_COLUMN), offset, limit)
+            .await
+    }
+
+    async fn count(&self) -> Result<u64, Self::Error> {
+        self.count_where(&Filter::new()).await
+    }
+
+    async fn create(&self, entity: &T) -> Result<T, Self::Error> {
+        self.write(entity, Write::Insert).await
+    }
+
+    async fn update(&self, entity: &T) -> Result<T, Self::Error> {
+        self.write(entity, Write::Update).await
+    }
+
+    /// Delete an entity; soft-deleted entities get their deletion time set.
+    async fn delete(&self, id: Uuid) -> Result<bool, Self::Error> {
+        let Some(column) = T::SOFT_DELETE_COLUMN else {
+            return self.purge(id).await;
+        };
+        Ok(self
+            .update_where(&[(column, Utc::now().to_value())], &Self::by_id(id))
+            .await?
+            > 0)
+    }
+
+    async fn exists(&self, id: Uuid) -> Result<bool, Self::Error> {
+        Ok(self.count_where(&Self::by_id(id)).await? > 0)
+    }
+}
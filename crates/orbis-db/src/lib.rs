@@ -3,17 +3,33 @@
 //! Database layer for Orbis using SQLx with support for PostgreSQL and SQLite.
 //! Provides migration management and a unified interface for both backends.
 
+mod cache;
 mod connection;
+mod entity;
 mod migrations;
 mod pool;
 mod repository;
+mod seed;
 
+pub use cache::{normalize_sql, statement_tables, QueryCache, QueryCacheStats};
 pub use connection::{Connection, DatabaseConnection, QueryExecutor};
+pub use entity::{Column, DbValue, Entity, EntityRepository, Filter};
 pub use migrations::{run_migrations, MigrationRunner};
 pub use pool::{create_pool, DatabasePool};
 pub use repository::{BaseRepository, Repository};
+pub use seed::{SeedEnvironment, SeedReport, SeedSet, Seeder};
+
+/// Items used by the [`entity!`] macro.
+#[doc(hidden)]
+pub mod __private {
+    pub use orbis_core::Result;
+    pub use sqlx::postgres::PgRow;
+    pub use sqlx::sqlite::SqliteRow;
+    pub use uuid::Uuid;
+}
 
 use orbis_config::DatabaseConfig;
+use std::path::Path;
 use std::sync::Arc;
 
 /// Database context holding the connection pool and configuration.
@@ -21,6 +37,7 @@ use std::sync::Arc;
 pub struct Database {
     pool: DatabasePool,
     config: Arc<DatabaseConfig>,
+    query_cache: Option<Arc<QueryCache>>,
 }
 
 impl Database {
@@ -31,9 +48,13 @@ impl Database {
     /// Returns an error if the connection pool cannot be created.
     pub async fn new(config: DatabaseConfig) -> orbis_core::Result<Self> {
         let pool = create_pool(&config).await?;
+        let query_cache = (config.query_cache_ttl_ms > 0).then(|| {
+            Arc::new(QueryCache::new(config.query_cache_ttl(), config.query_cache_capacity))
+        });
         Ok(Self {
             pool,
             config: Arc::new(config),
+            query_cache,
         })
     }
 
@@ -43,6 +64,12 @@ impl Database {
         &self.pool
     }
 
+    /// Get the query result cache, if enabled.
+    #[must_use]
+    pub const fn query_cache(&self) -> Option<&Arc<QueryCache>> {
+        self.query_cache.as_ref()
+    }
+
     /// Get a reference to the configuration.
     #[must_use]
     pub fn config(&self) -> &DatabaseConfig {
@@ -81,6 +108,47 @@ impl Database {
         Ok(())
     }
 
+    /// Write a consistent copy of the database to a file.
+    ///
+    /// SQLite databases are copied with `VACUUM INTO`; PostgreSQL databases
+    /// are dumped as SQL with `pg_dump`, which must be installed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists or the backup fails.
+    pub async fn backup(&self, path: &Path) -> orbis_core::Result<()> {
+        if path.exists() {
+            return Err(orbis_core::Error::conflict(format!("{} already exists", path.display())));
+        }
+
+        match &self.pool {
+            DatabasePool::Postgres(_) => {
+                let output = tokio::process::Command::new("pg_dump")
+                    .arg("--dbname")
+                    .arg(self.config.database_url()?)
+                    .arg("--file")
+                    .arg(path)
+                    .output()
+                    .await
+                    .map_err(|e| orbis_core::Error::database(format!("Failed to run pg_dump: {}", e)))?;
+                if !output.status.success() {
+                    return Err(orbis_core::Error::database(format!(
+                        "pg_dump failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+            }
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query("VACUUM INTO $1")
+                    .bind(path.to_string_lossy().into_owned())
+                    .execute(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(format!("Backup failed: {}", e)))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Close the database connection pool.
     pub async fn close(&self) {
         match &self.pool {
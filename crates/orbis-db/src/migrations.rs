@@ -17,18 +17,51 @@ pub async fn run_migrations(pool: &DatabasePool) -> orbis_core::Result<()> {
                 .await
                 .map_err(|e| orbis_core::Error::database(format!("Migration failed: {}", e)))?;
         }
-        DatabasePool::Sqlite(pool) => {
-            sqlx::migrate!("./migrations/sqlite")
-                .run(pool)
-                .await
-                .map_err(|e| orbis_core::Error::database(format!("Migration failed: {}", e)))?;
-        }
+        DatabasePool::Sqlite(pool) => run_sqlite_migrations(pool).await?,
     }
 
     tracing::info!("Database migrations completed");
     Ok(())
 }
 
+/// Run embedded SQLite migrations with foreign keys off.
+///
+/// SQLite cannot alter column constraints, so migrations rebuild tables
+/// instead; with foreign keys on, dropping the old table would cascade
+/// deletes to dependent rows. The pragma is a no-op inside a transaction,
+/// so it is set on the connection before running them, and the foreign keys
+/// are checked once they are done.
+async fn run_sqlite_migrations(pool: &sqlx::SqlitePool) -> orbis_core::Result<()> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+    let result = sqlx::migrate!("./migrations/sqlite").run_direct(&mut *conn).await;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+    result.map_err(|e| orbis_core::Error::database(format!("Migration failed: {}", e)))?;
+
+    let violations: Vec<(String,)> = sqlx::query_as("SELECT \"table\" FROM pragma_foreign_key_check")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+    if let Some(violation) = violations.first() {
+        return Err(orbis_core::Error::database(format!(
+            "Migration failed: {} foreign key violation(s), first in table '{}'",
+            violations.len(),
+            violation.0
+        )));
+    }
+    Ok(())
+}
+
 /// Migration runner for manual migration management.
 pub struct MigrationRunner<'a> {
     pool: &'a DatabasePool,
@@ -1,7 +1,19 @@
 //! Database connection pool management.
 
 use orbis_config::{DatabaseBackend, DatabaseConfig};
-use sqlx::{PgPool, Sqlite, SqlitePool, migrate::MigrateDatabase as _, postgres::PgPoolOptions, sqlite::SqlitePoolOptions};
+use orbis_core::EncryptionKey;
+use sqlx::{
+    PgPool, Sqlite, SqlitePool,
+    migrate::MigrateDatabase as _,
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
+use std::str::FromStr as _;
+use std::time::Duration;
+
+/// How long connections to profile-managed databases wait for a lock
+/// before failing with "database is locked".
+const MANAGED_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Unified database pool supporting multiple backends.
 #[derive(Clone)]
@@ -81,47 +93,112 @@ pub async fn create_pool(config: &DatabaseConfig) -> orbis_core::Result<Database
             tracing::info!("Connected to PostgreSQL database");
             Ok(DatabasePool::Postgres(pool))
         }
-        DatabaseBackend::Sqlite => {
-            tracing::info!("Connecting to SQLite database...");
-
-            // Ensure the database file's parent directory exists
-            if let Some(path) = &config.path {
-                if let Some(parent) = path.parent() {
-                    if !parent.exists() {
-                        std::fs::create_dir_all(parent).map_err(|e| {
-                            orbis_core::Error::database(format!(
-                                "Failed to create database directory: {}",
-                                e
-                            ))
-                        })?;
-                    }
-                }
-            }
-
-            if !Sqlite::database_exists(&url).await.unwrap_or(false) {
-                tracing::info!("SQLite database does not exist, creating new database...");
-                Sqlite::create_database(&url).await.map_err(|e| {
-                    orbis_core::Error::database(format!(
-                        "Failed to create SQLite database: {}",
-                        e
-                    ))
-                })?;
-            }
+        DatabaseBackend::Sqlite => create_sqlite_pool(config, &url).await,
+    }
+}
 
-            let pool = SqlitePoolOptions::new()
-                .max_connections(config.max_connections)
-                .min_connections(config.min_connections)
-                .acquire_timeout(config.acquire_timeout())
-                .idle_timeout(Some(config.idle_timeout()))
-                .max_lifetime(Some(config.max_lifetime()))
-                .connect(&url)
-                .await
-                .map_err(|e| {
-                    orbis_core::Error::database(format!("Failed to connect to SQLite: {}", e))
-                })?;
+/// Create a SQLite connection pool, creating the database if needed.
+async fn create_sqlite_pool(config: &DatabaseConfig, url: &str) -> orbis_core::Result<DatabasePool> {
+    tracing::info!("Connecting to SQLite database...");
+
+    create_sqlite_database(config, url).await?;
+    let options = sqlite_options(config, url)?;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout())
+        .idle_timeout(Some(config.idle_timeout()))
+        .max_lifetime(Some(config.max_lifetime()))
+        .connect_with(options)
+        .await
+        .map_err(|e| {
+            orbis_core::Error::database(format!("Failed to connect to SQLite: {}", e))
+        })?;
+
+    if config.is_profile_managed() {
+        check_integrity(&pool).await?;
+    }
 
-            tracing::info!("Connected to SQLite database");
-            Ok(DatabasePool::Sqlite(pool))
-        }
+    tracing::info!("Connected to SQLite database");
+    Ok(DatabasePool::Sqlite(pool))
+}
+
+/// Create a SQLite database and its directory if they do not exist.
+async fn create_sqlite_database(config: &DatabaseConfig, url: &str) -> orbis_core::Result<()> {
+    // Ensure the database file's parent directory exists
+    if let Some(parent) = config.path.as_deref().and_then(std::path::Path::parent)
+        && !parent.exists()
+    {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            orbis_core::Error::database(format!(
+                "Failed to create database directory: {}",
+                e
+            ))
+        })?;
     }
+
+    // Encrypted databases are created by their first keyed connection,
+    // as creating them unkeyed writes a plaintext header
+    if config.encryption_key.is_none() && !Sqlite::database_exists(url).await.unwrap_or(false) {
+        tracing::info!("SQLite database does not exist, creating new database...");
+        Sqlite::create_database(url).await.map_err(|e| {
+            orbis_core::Error::database(format!(
+                "Failed to create SQLite database: {}",
+                e
+            ))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Get the connection options of a SQLite database, with its key if encrypted.
+fn sqlite_options(config: &DatabaseConfig, url: &str) -> orbis_core::Result<SqliteConnectOptions> {
+    let mut options = SqliteConnectOptions::from_str(url)
+        .map_err(|e| orbis_core::Error::database(format!("Invalid SQLite URL: {}", e)))?;
+    if config.is_profile_managed() {
+        // Readers don't block the writer, and writers wait for each other
+        options = options
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(MANAGED_BUSY_TIMEOUT);
+    }
+    match &config.encryption_key {
+        Some(key) => encrypt(options, key),
+        None => Ok(options),
+    }
+}
+
+/// Check a SQLite database for corruption.
+///
+/// # Errors
+///
+/// Returns an error listing the first problems found if the database is corrupt.
+async fn check_integrity(pool: &SqlitePool) -> orbis_core::Result<()> {
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| orbis_core::Error::database(format!("Failed to check database integrity: {}", e)))?;
+
+    if problems.iter().all(|problem| problem == "ok") {
+        return Ok(());
+    }
+    Err(orbis_core::Error::database(format!(
+        "Database failed its integrity check, restore it from a backup: {}",
+        problems.into_iter().take(5).collect::<Vec<_>>().join("; ")
+    )))
+}
+
+/// Open SQLite connections with an encryption key (SQLCipher).
+#[cfg(feature = "sqlcipher")]
+fn encrypt(options: SqliteConnectOptions, key: &EncryptionKey) -> orbis_core::Result<SqliteConnectOptions> {
+    // The key pragma runs first on every connection, before the database is read
+    Ok(options.pragma("key", key.sqlcipher_key()))
+}
+
+/// Open SQLite connections with an encryption key (requires the `sqlcipher` feature).
+#[cfg(not(feature = "sqlcipher"))]
+fn encrypt(_options: SqliteConnectOptions, _key: &EncryptionKey) -> orbis_core::Result<SqliteConnectOptions> {
+    Err(orbis_core::Error::config(
+        "Encrypted databases require Orbis to be built with the `sqlcipher` feature",
+    ))
 }
@@ -0,0 +1,190 @@
+//! Database seeding for development and tests.
+//!
+//! A [`Seeder`] runs seed sets in the order they were added, skipping sets
+//! not meant for the target environment. Seed sets must be idempotent
+//! (for example by upserting with [`EntityRepository::upsert`]), so seeding
+//! an already seeded database only brings it back to the seeded state.
+//!
+//! [`EntityRepository::upsert`]: crate::EntityRepository::upsert
+
+use async_trait::async_trait;
+use orbis_config::DatabaseConfig;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{Database, DatabasePool};
+
+/// Environment a database is seeded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedEnvironment {
+    /// Local development.
+    #[default]
+    Development,
+
+    /// Automated tests.
+    Test,
+
+    /// Production deployments.
+    Production,
+}
+
+impl std::str::FromStr for SeedEnvironment {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "development" | "dev" => Ok(Self::Development),
+            "test" => Ok(Self::Test),
+            "production" | "prod" => Ok(Self::Production),
+            _ => Err(orbis_core::Error::config(format!(
+                "Invalid seed environment: '{}'. Expected 'development', 'test' or 'production'",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for SeedEnvironment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Development => write!(f, "development"),
+            Self::Test => write!(f, "test"),
+            Self::Production => write!(f, "production"),
+        }
+    }
+}
+
+/// A set of seed data.
+#[async_trait]
+pub trait SeedSet: Send + Sync {
+    /// Unique name of the set.
+    fn name(&self) -> &str;
+
+    /// Environments the set is run in.
+    fn environments(&self) -> &[SeedEnvironment] {
+        &[SeedEnvironment::Development, SeedEnvironment::Test]
+    }
+
+    /// Insert or update the seed data.
+    ///
+    /// Running a set twice must leave the database as running it once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data cannot be written.
+    async fn run(&self, db: &Database) -> orbis_core::Result<()>;
+}
+
+/// Outcome of a seeding run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    /// Sets that were run, in order.
+    pub applied: Vec<String>,
+
+    /// Sets skipped because they are not meant for the environment.
+    pub skipped: Vec<String>,
+}
+
+/// Ordered seed sets.
+#[derive(Clone, Default)]
+pub struct Seeder {
+    /// Seed sets, in run order.
+    sets: Vec<Arc<dyn SeedSet>>,
+}
+
+impl Seeder {
+    /// Create a seeder without seed sets.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a seed set, run after the sets added before it.
+    #[must_use]
+    pub fn with_set<S: SeedSet + 'static>(mut self, set: S) -> Self {
+        self.sets.push(Arc::new(set));
+        self
+    }
+
+    /// Get the names of the seed sets, in run order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.sets.iter().map(|set| set.name()).collect()
+    }
+
+    /// Run the seed sets for an environment.
+    ///
+    /// If `only` is not empty, only the named sets are run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `only` names an unknown set, two sets share a
+    /// name, or a set fails; the sets before it stay applied.
+    pub async fn run(
+        &self,
+        db: &Database,
+        environment: SeedEnvironment,
+        only: &[String],
+    ) -> orbis_core::Result<SeedReport> {
+        let mut names = HashSet::new();
+        if let Some(set) = self.sets.iter().find(|set| !names.insert(set.name())) {
+            return Err(orbis_core::Error::config(format!("Duplicate seed set '{}'", set.name())));
+        }
+        if let Some(name) = only.iter().find(|name| !names.contains(name.as_str())) {
+            return Err(orbis_core::Error::not_found(format!("Seed set '{}' not found", name)));
+        }
+
+        let mut report = SeedReport::default();
+        for set in &self.sets {
+            if !only.is_empty() && !only.iter().any(|name| name == set.name()) {
+                continue;
+            }
+            if !set.environments().contains(&environment) {
+                report.skipped.push(set.name().to_owned());
+                continue;
+            }
+
+            tracing::info!(set = set.name(), %environment, "Seeding database");
+            set.run(db).await.map_err(|e| {
+                orbis_core::Error::database(format!("Seed set '{}' failed: {}", set.name(), e))
+            })?;
+            report.applied.push(set.name().to_owned());
+        }
+        Ok(report)
+    }
+
+    /// Create a migrated in-memory SQLite database seeded for tests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be created, migrated or seeded.
+    pub async fn test_database(&self) -> orbis_core::Result<Database> {
+        // A single connection that never expires, since every connection
+        // to `sqlite::memory:` opens its own empty database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .map_err(|e| orbis_core::Error::database(format!("Failed to create test database: {}", e)))?;
+
+        let db = Database {
+            pool: DatabasePool::Sqlite(pool),
+            config: Arc::new(DatabaseConfig {
+                url: Some("sqlite::memory:".to_owned()),
+                path: None,
+                max_connections: 1,
+                min_connections: 1,
+                ..DatabaseConfig::default()
+            }),
+            query_cache: None,
+        };
+        db.migrate().await?;
+        self.run(&db, SeedEnvironment::Test, &[]).await?;
+        Ok(db)
+    }
+}
@@ -1,7 +1,7 @@
 //! Example of creating a complete plugin manifest with UI pages.
 
 use orbis_plugin_api::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 fn main() {
     // Create a simple plugin manifest
@@ -12,7 +12,15 @@ fn main() {
         author: Some("Plugin Developer".to_string()),
         homepage: Some("https://example.com".to_string()),
         license: Some("MIT".to_string()),
+        tags: vec!["example".to_string()],
+        category: Some("Examples".to_string()),
+        icon: None,
+        deprecated: None,
+        changelog_url: None,
+        release_notes: None,
         min_orbis_version: Some("0.1.0".to_string()),
+        core_version: Some("^1.0".to_string()),
+        api_version: None,
         dependencies: vec![],
         permissions: vec![
             PluginPermission::DatabaseRead,
@@ -26,10 +34,28 @@ fn main() {
                 description: Some("Fetch data from the plugin".to_string()),
                 requires_auth: true,
                 permissions: vec![],
+                groups: vec![],
                 rate_limit: Some(60),
+                cache: None,
+                max_body_size: None,
+                request: None,
+                api_only: false,
             },
         ],
         pages: vec![create_dashboard_page()],
+        layouts: vec![],
+        theme: None,
+        settings: vec![],
+        features: vec![],
+        search_provider: None,
+        hooks: Vec::new(),
+        activation: PluginActivation::Eager,
+        idle_unload_seconds: None,
+        state_version: None,
+        max_body_size: None,
+        tables: Vec::new(),
+        requirements: Default::default(),
+        limits: Default::default(),
         wasm_entry: Some("plugin.wasm".to_string()),
         config: serde_json::json!({}),
     };
@@ -82,8 +108,13 @@ fn create_dashboard_page() -> PageDefinition {
         requires_auth: true,
         permissions: vec![],
         roles: vec![],
+        groups: vec![],
+        feature_when: None,
         state,
         computed: HashMap::new(),
+        computed_dependencies: BTreeMap::new(),
+        layout: None,
+        regions: HashMap::new(),
         sections: vec![
             // Container with header
             ComponentSchema {
@@ -92,6 +123,7 @@ fn create_dashboard_page() -> PageDefinition {
                 class_name: Some("p-6".to_string()),
                 style: None,
                 visible: None,
+                feature_when: None,
                 children: vec![
                     // Header
                     ComponentSchema {
@@ -100,6 +132,7 @@ fn create_dashboard_page() -> PageDefinition {
                         class_name: Some("text-2xl font-bold mb-4".to_string()),
                         style: None,
                         visible: None,
+                        feature_when: None,
                         children: vec![],
                         events: None,
                         props: {
@@ -115,6 +148,7 @@ fn create_dashboard_page() -> PageDefinition {
                         class_name: None,
                         style: None,
                         visible: Some(serde_json::json!("${state.loading}")),
+                        feature_when: None,
                         children: vec![],
                         events: None,
                         props: {
@@ -130,6 +164,7 @@ fn create_dashboard_page() -> PageDefinition {
                         class_name: None,
                         style: None,
                         visible: Some(serde_json::json!("${!state.loading}")),
+                        feature_when: None,
                         children: vec![],
                         events: None,
                         props: {
@@ -201,5 +236,7 @@ fn create_dashboard_page() -> PageDefinition {
             on_query_change: vec![],
         }),
         dialogs: vec![],
+        cache: None,
+        prefetch: vec![],
     }
 }
@@ -0,0 +1,113 @@
+//! Feature flags.
+//!
+//! A plugin declares feature flags in its manifest's `features` section,
+//! each with a default. Administrators override flags per profile at
+//! runtime; handlers read the resolved flags through the SDK `features` API
+//! and UI schemas show components only when a flag is on (`feature_when`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Longest feature flag name, in bytes.
+pub const MAX_FEATURE_NAME_LENGTH: usize = 64;
+
+/// Feature flag declared by a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    /// Flag name.
+    pub name: String,
+
+    /// Whether the flag is on unless overridden.
+    #[serde(default)]
+    pub default: bool,
+
+    /// Description shown to administrators.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl FeatureFlag {
+    /// Validate the flag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid.
+    pub fn validate(&self) -> crate::Result<()> {
+        let valid = !self.name.is_empty()
+            && self.name.len() <= MAX_FEATURE_NAME_LENGTH
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        if !valid {
+            return Err(crate::Error::manifest(format!(
+                "Invalid feature flag '{}': use 1 to {} letters, digits, '_', '-' and '.'",
+                self.name, MAX_FEATURE_NAME_LENGTH
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve the flags of a plugin, applying overrides to their defaults.
+///
+/// Overrides of flags the plugin does not declare are ignored.
+#[must_use]
+pub fn resolve(flags: &[FeatureFlag], overrides: &BTreeMap<String, bool>) -> BTreeMap<String, bool> {
+    flags
+        .iter()
+        .map(|flag| {
+            let enabled = overrides.get(&flag.name).copied().unwrap_or(flag.default);
+            (flag.name.clone(), enabled)
+        })
+        .collect()
+}
+
+/// Check a `feature_when` condition against resolved flags.
+///
+/// The condition is a flag name, on when the flag is, or a flag name
+/// prefixed with `!`, on when the flag is off. Unknown flags are off.
+#[must_use]
+pub fn condition_holds(condition: &str, flags: &BTreeMap<String, bool>) -> bool {
+    let condition = condition.trim();
+    condition.strip_prefix('!').map_or_else(
+        || flags.get(condition).copied().unwrap_or(false),
+        |flag| !flags.get(flag.trim()).copied().unwrap_or(false),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_and_conditions() {
+        let flags: Vec<FeatureFlag> = serde_json::from_value(json!([
+            {"name": "new-editor", "default": true},
+            {"name": "exports"}
+        ]))
+        .unwrap();
+        for flag in &flags {
+            flag.validate().unwrap();
+        }
+
+        let overrides = BTreeMap::from([("exports".to_string(), true), ("unknown".to_string(), true)]);
+        let resolved = resolve(&flags, &overrides);
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved["new-editor"] && resolved["exports"]);
+
+        let resolved = resolve(&flags, &BTreeMap::from([("new-editor".to_string(), false)]));
+        assert!(condition_holds("!new-editor", &resolved));
+        assert!(!condition_holds("exports", &resolved));
+        assert!(!condition_holds("unknown", &resolved));
+
+        let invalid = FeatureFlag {
+            name: "has space".to_string(),
+            default: false,
+            description: None,
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
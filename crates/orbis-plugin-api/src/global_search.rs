@@ -0,0 +1,208 @@
+//! Global search providers.
+//!
+//! A plugin declares a `search_provider` in its manifest to contribute
+//! results to the shell's global search. The host calls the provider's
+//! handler with the query in `q`, and merges and ranks the results of all
+//! providers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ui::ViewerAccess;
+
+/// Default time a provider has to answer, in milliseconds.
+pub const DEFAULT_SEARCH_TIMEOUT_MS: u64 = 2000;
+
+/// Longest time a provider may ask for, in milliseconds.
+pub const MAX_SEARCH_TIMEOUT_MS: u64 = 10_000;
+
+/// Search provider declared by a plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchProvider {
+    /// Handler called with the query.
+    pub handler: String,
+
+    /// Time the handler has to answer, in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+
+    /// Roles allowed to search this plugin (empty for any authenticated user).
+    #[serde(default)]
+    pub roles: Vec<String>,
+
+    /// Permissions needed to search this plugin.
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl SearchProvider {
+    /// Validate the provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handler is missing or the timeout is out of range.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.handler.is_empty() {
+            return Err(crate::Error::manifest("Search provider handler is required"));
+        }
+
+        if self
+            .timeout_ms
+            .is_some_and(|timeout| timeout == 0 || timeout > MAX_SEARCH_TIMEOUT_MS)
+        {
+            return Err(crate::Error::manifest(format!(
+                "Search provider timeout_ms must be between 1 and {}",
+                MAX_SEARCH_TIMEOUT_MS
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the time the handler has to answer, in milliseconds.
+    #[must_use]
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(DEFAULT_SEARCH_TIMEOUT_MS)
+    }
+
+    /// Check whether a viewer may search this plugin (`None` for anonymous viewers).
+    ///
+    /// Search always requires authentication; admins may search every plugin.
+    #[must_use]
+    pub fn is_accessible_by(&self, viewer: Option<&ViewerAccess>) -> bool {
+        let Some(viewer) = viewer else {
+            return false;
+        };
+
+        if viewer.is_admin() {
+            return true;
+        }
+
+        let has_role = self.roles.is_empty() || self.roles.iter().any(|role| viewer.roles.contains(role));
+        let has_permissions = self
+            .permissions
+            .iter()
+            .all(|permission| viewer.permissions.contains(permission));
+
+        has_role && has_permissions
+    }
+}
+
+/// Result returned by a search provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// Title shown in the result list.
+    pub title: String,
+
+    /// Short description or excerpt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Route of the result within the plugin's pages (e.g. `/items/42`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+
+    /// Icon name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+
+    /// Category used to group results (e.g. `Documents`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// Relevance between 0 and 1; computed from the title when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+impl SearchResult {
+    /// Parse the results out of a provider's response.
+    ///
+    /// Accepts a list of results, an object with a `results` list, or either
+    /// of those wrapped in a handler response `body`. Invalid entries are
+    /// skipped.
+    #[must_use]
+    pub fn parse_response(response: &Value) -> Vec<Self> {
+        let body = response.get("body").unwrap_or(response);
+        let items = body
+            .as_array()
+            .or_else(|| body.get("results").and_then(Value::as_array));
+
+        items
+            .into_iter()
+            .flatten()
+            .filter_map(|item| serde_json::from_value::<Self>(item.clone()).ok())
+            .filter(|result| !result.title.trim().is_empty())
+            .filter(|result| result.route.as_ref().is_none_or(|route| route.starts_with('/')))
+            .collect()
+    }
+
+    /// Get the relevance of the result to a query, between 0 and 1.
+    ///
+    /// The provider's score is used when given; otherwise exact title
+    /// matches rank above prefix matches, which rank above other matches.
+    #[must_use]
+    pub fn relevance(&self, query: &str) -> f64 {
+        if let Some(score) = self.score.filter(|score| score.is_finite()) {
+            return score.clamp(0.0, 1.0);
+        }
+
+        let title = self.title.to_lowercase();
+        let query = query.trim().to_lowercase();
+        if title == query {
+            1.0
+        } else if title.starts_with(&query) {
+            0.8
+        } else if title.contains(&query) {
+            0.6
+        } else {
+            0.3
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_results() {
+        let response = json!({
+            "status": 200,
+            "body": {
+                "results": [
+                    { "title": "Quarterly report", "route": "/reports/1" },
+                    { "title": "Report", "score": 0.4 },
+                    { "title": "" },
+                    { "title": "Bad route", "route": "reports" },
+                    { "description": "no title" }
+                ]
+            }
+        });
+
+        let results = SearchResult::parse_response(&response);
+        assert_eq!(results.len(), 2);
+        assert!((results[0].relevance("report") - 0.6).abs() < f64::EPSILON);
+        assert!((results[1].relevance("report") - 0.4).abs() < f64::EPSILON);
+
+        let bare = SearchResult::parse_response(&json!([{ "title": "Report" }]));
+        assert!((bare[0].relevance(" REPORT ") - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_search_provider_access() {
+        let provider: SearchProvider =
+            serde_json::from_value(json!({ "handler": "search", "roles": ["auditor"] })).unwrap();
+        provider.validate().unwrap();
+        assert_eq!(provider.timeout_ms(), DEFAULT_SEARCH_TIMEOUT_MS);
+
+        assert!(!provider.is_accessible_by(None));
+        assert!(!provider.is_accessible_by(Some(&ViewerAccess::for_user(false))));
+        assert!(provider.is_accessible_by(Some(&ViewerAccess::for_user(true))));
+
+        let slow: SearchProvider =
+            serde_json::from_value(json!({ "handler": "search", "timeout_ms": 60_000 })).unwrap();
+        slow.validate().unwrap_err();
+    }
+}
@@ -0,0 +1,221 @@
+//! Lifecycle hooks.
+//!
+//! A plugin subscribes to host lifecycle events by listing `hooks` in its
+//! manifest. When an event happens, the host calls the handler of every
+//! running subscriber in priority order, with the event as the request body.
+//! Hooks are notifications: a failing or slow hook is logged and skipped,
+//! and never fails the operation that triggered it.
+
+use serde::{Deserialize, Serialize};
+
+/// Default time a hook handler has to run, in milliseconds.
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 1000;
+
+/// Longest time a hook handler may ask for, in milliseconds.
+pub const MAX_HOOK_TIMEOUT_MS: u64 = 10_000;
+
+/// Point in the host lifecycle a plugin can hook into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPoint {
+    /// The server started accepting requests.
+    ServerStarted,
+
+    /// A request is about to be handled.
+    BeforeRequest,
+
+    /// A user logged in.
+    AfterAuth,
+
+    /// The active profile changed.
+    ProfileSwitched,
+
+    /// A plugin was installed while the host was running.
+    PluginInstalled,
+
+    /// A plugin was enabled, disabled, failed or unloaded.
+    PluginStateChanged,
+}
+
+impl HookPoint {
+    /// Get the hook point name, as used in manifests.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ServerStarted => "server_started",
+            Self::BeforeRequest => "before_request",
+            Self::AfterAuth => "after_auth",
+            Self::ProfileSwitched => "profile_switched",
+            Self::PluginInstalled => "plugin_installed",
+            Self::PluginStateChanged => "plugin_state_changed",
+        }
+    }
+}
+
+impl std::fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lifecycle event passed to hook handlers as the request body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "hook", rename_all = "snake_case")]
+pub enum HookEvent {
+    /// The server started accepting requests.
+    ServerStarted {
+        /// Host version.
+        version: String,
+    },
+
+    /// A request is about to be handled.
+    BeforeRequest {
+        /// Request method.
+        method: String,
+
+        /// Request path.
+        path: String,
+    },
+
+    /// A user logged in.
+    AfterAuth {
+        /// User ID.
+        user_id: String,
+
+        /// Username.
+        username: String,
+    },
+
+    /// The active profile changed.
+    ProfileSwitched {
+        /// Profile name or ID.
+        profile: String,
+    },
+
+    /// A plugin was installed while the host was running.
+    PluginInstalled {
+        /// Plugin name.
+        plugin: String,
+
+        /// Plugin version.
+        version: String,
+    },
+
+    /// A plugin was enabled, disabled, failed or unloaded.
+    PluginStateChanged {
+        /// Plugin name.
+        plugin: String,
+
+        /// State before the change, unset when the plugin was loaded.
+        from: Option<String>,
+
+        /// State after the change, unset when the plugin was unloaded.
+        to: Option<String>,
+
+        /// What triggered the change.
+        trigger: String,
+
+        /// Why the state changed.
+        reason: Option<String>,
+    },
+}
+
+impl HookEvent {
+    /// Get the hook point of the event.
+    #[must_use]
+    pub const fn point(&self) -> HookPoint {
+        match self {
+            Self::ServerStarted { .. } => HookPoint::ServerStarted,
+            Self::BeforeRequest { .. } => HookPoint::BeforeRequest,
+            Self::AfterAuth { .. } => HookPoint::AfterAuth,
+            Self::ProfileSwitched { .. } => HookPoint::ProfileSwitched,
+            Self::PluginInstalled { .. } => HookPoint::PluginInstalled,
+            Self::PluginStateChanged { .. } => HookPoint::PluginStateChanged,
+        }
+    }
+}
+
+/// Hook a plugin subscribes to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookSubscription {
+    /// Hook point.
+    pub hook: HookPoint,
+
+    /// Handler called with the event.
+    pub handler: String,
+
+    /// Order among the hook's subscribers; lower runs first.
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Time the handler has to run, in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+impl HookSubscription {
+    /// Validate the subscription.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handler is missing or the timeout is out of range.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.handler.is_empty() {
+            return Err(crate::Error::manifest(format!("Handler of hook '{}' is required", self.hook)));
+        }
+
+        if self
+            .timeout_ms
+            .is_some_and(|timeout| timeout == 0 || timeout > MAX_HOOK_TIMEOUT_MS)
+        {
+            return Err(crate::Error::manifest(format!(
+                "Hook '{}' timeout_ms must be between 1 and {}",
+                self.hook, MAX_HOOK_TIMEOUT_MS
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the time the handler has to run, in milliseconds.
+    #[must_use]
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_hook_subscription() {
+        let hook: HookSubscription =
+            serde_json::from_value(json!({ "hook": "after_auth", "handler": "on_login" })).unwrap();
+        hook.validate().unwrap();
+        assert_eq!(hook.hook, HookPoint::AfterAuth);
+        assert_eq!(hook.priority, 0);
+        assert_eq!(hook.timeout_ms(), DEFAULT_HOOK_TIMEOUT_MS);
+
+        let slow: HookSubscription =
+            serde_json::from_value(json!({ "hook": "server_started", "handler": "warm", "timeout_ms": 60_000 }))
+                .unwrap();
+        slow.validate().unwrap_err();
+
+        serde_json::from_value::<HookSubscription>(json!({ "hook": "on_boot", "handler": "boot" })).unwrap_err();
+    }
+
+    #[test]
+    fn test_hook_event() {
+        let event = HookEvent::PluginInstalled {
+            plugin: "notes".to_string(),
+            version: "1.2.0".to_string(),
+        };
+        assert_eq!(event.point(), HookPoint::PluginInstalled);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({ "hook": "plugin_installed", "plugin": "notes", "version": "1.2.0" })
+        );
+    }
+}
@@ -32,21 +32,40 @@
 //! ```
 
 pub mod error;
+pub mod features;
+pub mod global_search;
+pub mod hooks;
 pub mod manifest;
 pub mod runtime;
 pub mod sdk;
+pub mod security;
+pub mod settings;
 pub mod ui;
+pub mod validation;
 
 // Re-export key types for convenience
 pub use error::{Error, Result};
-pub use manifest::{PluginDependency, PluginManifest, PluginPermission, PluginRoute};
-pub use runtime::{HostFunctions, LogLevel, PluginContext};
+pub use features::{FeatureFlag, MAX_FEATURE_NAME_LENGTH};
+pub use global_search::{SearchProvider, SearchResult, DEFAULT_SEARCH_TIMEOUT_MS, MAX_SEARCH_TIMEOUT_MS};
+pub use hooks::{HookEvent, HookPoint, HookSubscription, DEFAULT_HOOK_TIMEOUT_MS, MAX_HOOK_TIMEOUT_MS};
+pub use manifest::{
+    API_VERSIONS, CURRENT_API_VERSION, FilesystemGrants, HostInfoField, PluginActivation, PluginDependency, PluginManifest, PluginPermission,
+    PluginRequirements, PluginRoute, ResourceLimits, RouteCache, STATE_VERSION_KEY, state_migration_handler,
+};
+pub use runtime::{
+    AbiVersion, HostFunctions, HostInfo, LogLevel, PluginContext, StateWrite, HASH_SECTION, SIGNATURE_SECTION,
+};
+pub use security::{
+    DenyReason, HostCall, PolicyDecision, PolicyDenial, PolicyEffect, PolicyEngine, PolicyMode, PolicyRule, AccessPolicy,
+};
+pub use settings::{SettingDefinition, SettingScope, SettingType};
 pub use ui::{
     AccordionItem, Action, ArgMapping, BreadcrumbItem, ComponentSchema, CustomValidation,
-    DialogDefinition, EventHandlers, FormField, NavigationConfig, NavigationItem, PageDefinition,
-    PageLifecycleHooks, SelectOption, StateFieldDefinition, StateFieldType, TabItem, TableColumn,
-    ToastLevel, ValidationRule,
+    DialogDefinition, EventHandlers, FormField, LayoutDefinition, NavigationConfig, NavigationItem, PageCacheHints,
+    PageDefinition, PageLifecycleHooks, PrefetchCall, SelectOption, StateFieldDefinition, StateFieldType, TabItem, TableColumn,
+    ThemeDefinition, ToastLevel, ValidationRule, ViewerAccess, CONTENT_REGION, REGION_COMPONENT,
 };
+pub use validation::{JsonSchema, JsonType, RequestPart, RequestSchema, RequestViolation, SchemaViolation};
 
 /// Prelude for convenient imports in plugins
 ///
@@ -1,7 +1,37 @@
 //! Plugin manifest definition.
 
 use serde::{Deserialize, Serialize};
-use semver::Version;
+use semver::{Version, VersionReq};
+use std::path::PathBuf;
+
+use crate::validation::RequestSchema;
+
+/// Maximum length of a manifest tag or category, in bytes.
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Maximum length of a deprecation notice, in bytes.
+const MAX_DEPRECATION_LENGTH: usize = 512;
+
+/// Maximum length of release notes, in bytes.
+const MAX_RELEASE_NOTES_LENGTH: usize = 16 * 1024;
+
+/// Core HTTP API versions (`/api/v{N}`) this host serves.
+pub const API_VERSIONS: &[u32] = &[1];
+
+/// Core HTTP API version served on unversioned `/api` paths.
+pub const CURRENT_API_VERSION: u32 = 1;
+
+/// State key holding the schema version of a plugin's stored state.
+///
+/// Reserved: the host sets it once state migrations have run.
+pub const STATE_VERSION_KEY: &str = "__orbis_state_version";
+
+/// Get the name of the handler migrating plugin state from a schema version
+/// to the next one, e.g. `migrate_state_v1_to_v2`.
+#[must_use]
+pub fn state_migration_handler(from: u32) -> String {
+    format!("migrate_state_v{}_to_v{}", from, from.saturating_add(1))
+}
 
 /// Plugin manifest describing the plugin's metadata, routes, and pages.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,10 +58,44 @@ pub struct PluginManifest {
     #[serde(default)]
     pub license: Option<String>,
 
+    /// Free-form tags used to find the plugin (e.g. `reporting`, `crm`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Category the plugin is listed under in the management UI.
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// Plugin icon, as an icon name or an image URL.
+    #[serde(default)]
+    pub icon: Option<String>,
+
+    /// Deprecation notice (e.g. the plugin replacing this one), if the
+    /// plugin is deprecated.
+    #[serde(default)]
+    pub deprecated: Option<String>,
+
+    /// URL of the plugin's changelog.
+    #[serde(default)]
+    pub changelog_url: Option<String>,
+
+    /// Release notes of this version, in Markdown.
+    #[serde(default)]
+    pub release_notes: Option<String>,
+
     /// Minimum Orbis version required.
     #[serde(default)]
     pub min_orbis_version: Option<String>,
 
+    /// Host API (ABI) versions the plugin supports, as a semver range (e.g. `^1.2`).
+    #[serde(default)]
+    pub core_version: Option<String>,
+
+    /// Core HTTP API version (`/api/v{N}`) the plugin's pages and routes
+    /// target; the current version if unset.
+    #[serde(default)]
+    pub api_version: Option<u32>,
+
     /// Plugin dependencies.
     #[serde(default)]
     pub dependencies: Vec<PluginDependency>,
@@ -48,6 +112,66 @@ pub struct PluginManifest {
     #[serde(default)]
     pub pages: Vec<crate::ui::PageDefinition>,
 
+    /// Layouts the plugin's pages can be placed in.
+    #[serde(default)]
+    pub layouts: Vec<crate::ui::LayoutDefinition>,
+
+    /// Theme tokens scoped to the plugin's pages.
+    #[serde(default)]
+    pub theme: Option<crate::ui::ThemeDefinition>,
+
+    /// Typed settings contributed by the plugin.
+    #[serde(default)]
+    pub settings: Vec<crate::settings::SettingDefinition>,
+
+    /// Feature flags administrators can toggle at runtime.
+    #[serde(default)]
+    pub features: Vec<crate::features::FeatureFlag>,
+
+    /// Handler contributing results to the global search.
+    #[serde(default)]
+    pub search_provider: Option<crate::global_search::SearchProvider>,
+
+    /// Lifecycle hooks the plugin subscribes to.
+    #[serde(default)]
+    pub hooks: Vec<crate::hooks::HookSubscription>,
+
+    /// When the plugin's WASM code is compiled and instantiated.
+    #[serde(default)]
+    pub activation: PluginActivation,
+
+    /// Unload a lazily activated plugin after this many seconds without use.
+    #[serde(default)]
+    pub idle_unload_seconds: Option<u64>,
+
+    /// Schema version of the plugin's stored state, 1 if unset.
+    ///
+    /// When it is raised, the host runs a migration handler for each version
+    /// step (see [`state_migration_handler`]) before the plugin starts.
+    #[serde(default)]
+    pub state_version: Option<u32>,
+
+    /// Maximum request body size of the plugin's routes, in bytes.
+    ///
+    /// Capped by the server's maximum body size.
+    #[serde(default)]
+    pub max_body_size: Option<usize>,
+
+    /// Database tables the plugin owns, exported and imported with its data.
+    ///
+    /// Names start with the plugin's table prefix, `plugin_<name>_` with `-`
+    /// changed to `_`. A table can only be owned by one installed plugin.
+    #[serde(default)]
+    pub tables: Vec<String>,
+
+    /// Host resources the plugin needs, beyond its permissions.
+    #[serde(default)]
+    pub requirements: PluginRequirements,
+
+    /// Resource limits enforced by the host.
+    #[serde(default)]
+    pub limits: ResourceLimits,
+
     /// Entry point for WASM plugins (relative path in unpacked/packed).
     #[serde(default)]
     pub wasm_entry: Option<String>,
@@ -58,6 +182,20 @@ pub struct PluginManifest {
 }
 
 impl PluginManifest {
+    /// Get the prefix of the database tables the plugin may own.
+    ///
+    /// Plugin `my-plugin` may own tables named `plugin_my_plugin_*`.
+    #[must_use]
+    pub fn table_prefix(&self) -> String {
+        format!("plugin_{}_", self.name.replace('-', "_"))
+    }
+
+    /// Get the core HTTP API version the plugin's pages and routes target.
+    #[must_use]
+    pub fn target_api_version(&self) -> u32 {
+        self.api_version.unwrap_or(CURRENT_API_VERSION)
+    }
+
     /// Validate the manifest.
     ///
     /// # Errors
@@ -84,19 +222,177 @@ impl PluginManifest {
             crate::Error::manifest(format!("Invalid plugin version '{}': {}", self.version, e))
         })?;
 
+        // Validate tags and category
+        for tag in &self.tags {
+            if tag.trim().is_empty() || tag.len() > MAX_TAG_LENGTH {
+                return Err(crate::Error::manifest(format!(
+                    "Invalid tag '{}': tags must be 1 to {} characters",
+                    tag, MAX_TAG_LENGTH
+                )));
+            }
+        }
+
+        if let Some(category) = &self.category
+            && (category.trim().is_empty() || category.len() > MAX_TAG_LENGTH)
+        {
+            return Err(crate::Error::manifest(format!(
+                "Invalid category '{}': categories must be 1 to {} characters",
+                category, MAX_TAG_LENGTH
+            )));
+        }
+
+        // Validate deprecation and changelog metadata
+        if let Some(notice) = &self.deprecated
+            && (notice.trim().is_empty() || notice.len() > MAX_DEPRECATION_LENGTH)
+        {
+            return Err(crate::Error::manifest(format!(
+                "Invalid deprecation notice: notices must be 1 to {} characters",
+                MAX_DEPRECATION_LENGTH
+            )));
+        }
+
+        if let Some(url) = &self.changelog_url
+            && !(url.starts_with("https://") || url.starts_with("http://"))
+        {
+            return Err(crate::Error::manifest(format!(
+                "Invalid changelog_url '{}': expected an http or https URL",
+                url
+            )));
+        }
+
+        if self.release_notes.as_ref().is_some_and(|notes| notes.len() > MAX_RELEASE_NOTES_LENGTH) {
+            return Err(crate::Error::manifest(format!(
+                "Release notes must be at most {} bytes",
+                MAX_RELEASE_NOTES_LENGTH
+            )));
+        }
+
+        // Validate host API requirement
+        if let Some(core_version) = &self.core_version {
+            VersionReq::parse(core_version).map_err(|e| {
+                crate::Error::manifest(format!("Invalid core_version '{}': {}", core_version, e))
+            })?;
+        }
+
+        if self.state_version == Some(0) {
+            return Err(crate::Error::manifest("Invalid state_version: versions start at 1"));
+        }
+
+        // Validate owned tables
+        let prefix = self.table_prefix();
+        let mut tables = std::collections::HashSet::new();
+        for table in &self.tables {
+            let valid = table.starts_with(&prefix)
+                && table.len() > prefix.len()
+                && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(crate::Error::manifest(format!(
+                    "Invalid table '{}': expected letters, digits and '_' after the prefix '{}'",
+                    table, prefix
+                )));
+            }
+            if !tables.insert(table.as_str()) {
+                return Err(crate::Error::manifest(format!("Duplicate table '{}'", table)));
+            }
+        }
+
         // Validate routes
         for route in &self.routes {
             route.validate()?;
         }
 
-        // Validate pages
+        // Validate layouts
+        let mut layouts = std::collections::HashSet::new();
+        for layout in &self.layouts {
+            layout.validate()?;
+            if !layouts.insert(layout.name.as_str()) {
+                return Err(crate::Error::manifest(format!("Duplicate layout '{}'", layout.name)));
+            }
+        }
+
+        // Validate pages, and that the handlers they prefetch from have a GET route
         for page in &self.pages {
             page.validate()?;
+            page.validate_layout(&self.layouts)?;
+            for call in &page.prefetch {
+                let has_get_route = self
+                    .routes
+                    .iter()
+                    .any(|route| route.handler == call.handler && route.method.eq_ignore_ascii_case("GET"));
+                if !has_get_route {
+                    return Err(crate::Error::manifest(format!(
+                        "Page '{}' prefetches from handler '{}', which has no GET route",
+                        page.route, call.handler
+                    )));
+                }
+            }
+        }
+
+        // Validate theme
+        if let Some(theme) = &self.theme {
+            theme.validate()?;
+        }
+
+        // Validate settings
+        for setting in &self.settings {
+            setting.validate()?;
         }
 
+        // Validate feature flags
+        let mut features = std::collections::HashSet::new();
+        for flag in &self.features {
+            flag.validate()?;
+            if !features.insert(flag.name.as_str()) {
+                return Err(crate::Error::manifest(format!("Duplicate feature flag '{}'", flag.name)));
+            }
+        }
+
+        // Validate search provider
+        if let Some(provider) = &self.search_provider {
+            provider.validate()?;
+        }
+
+        // Validate hooks
+        for hook in &self.hooks {
+            hook.validate()?;
+        }
+
+        // Validate filesystem, environment and host info grants
+        self.requirements.validate(&self.permissions)?;
+
+        self.limits.validate()?;
+
         Ok(())
     }
 
+    /// Place the pages using a layout in it, so every page is a plain
+    /// component tree, and resolve the dependencies of their computed values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page uses an unknown layout or has invalid
+    /// computed values.
+    pub fn compose_pages(&mut self) -> crate::Result<()> {
+        for page in &mut self.pages {
+            page.resolve_computed()?;
+            let Some(name) = page.layout.clone() else {
+                continue;
+            };
+            let layout = self.layouts.iter().find(|layout| layout.name == name).ok_or_else(|| {
+                crate::Error::manifest(format!("Page '{}' uses unknown layout '{}'", page.route, name))
+            })?;
+            page.apply_layout(layout);
+        }
+
+        Ok(())
+    }
+
+    /// Get the schema version of the plugin's stored state.
+    #[must_use]
+    pub fn state_version(&self) -> u32 {
+        self.state_version.unwrap_or(1)
+    }
+
     /// Get the parsed semver version.
     ///
     /// # Errors
@@ -108,6 +404,197 @@ impl PluginManifest {
     }
 }
 
+/// Host resources a plugin needs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginRequirements {
+    /// Filesystem access, through the SDK `files` API.
+    #[serde(default)]
+    pub filesystem: FilesystemGrants,
+
+    /// Environment variables the plugin may read, through the SDK `host::env`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environment: Vec<String>,
+
+    /// Host information the plugin may read, through the SDK `host::info`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_info: Vec<HostInfoField>,
+}
+
+impl PluginRequirements {
+    /// Validate the requirements against the permissions the plugin requests.
+    ///
+    /// Environment variables need the `environment` permission and host
+    /// information the `system` permission.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a requirement is invalid or a permission is missing.
+    pub fn validate(&self, permissions: &[PluginPermission]) -> crate::Result<()> {
+        self.filesystem.validate(permissions)?;
+
+        for name in &self.environment {
+            let mut chars = name.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(crate::Error::manifest(format!(
+                    "Invalid environment variable name '{}': expected letters, digits and '_'",
+                    name
+                )));
+            }
+        }
+
+        if !self.environment.is_empty() && !permissions.contains(&PluginPermission::Environment) {
+            return Err(crate::Error::manifest(
+                "Environment variable requirements require the 'environment' permission",
+            ));
+        }
+
+        if !self.host_info.is_empty() && !permissions.contains(&PluginPermission::System) {
+            return Err(crate::Error::manifest("Host info requirements require the 'system' permission"));
+        }
+
+        Ok(())
+    }
+
+    /// Check if the plugin may read an environment variable.
+    #[must_use]
+    pub fn allows_env(&self, name: &str) -> bool {
+        self.environment.iter().any(|allowed| allowed == name)
+    }
+}
+
+/// Resource limits of a plugin.
+///
+/// Network quotas apply to requests to external hosts made through the
+/// `http_request` host function. Usage is persisted across restarts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Bytes sent and received per day (UTC), unlimited if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_bytes_per_day: Option<u64>,
+
+    /// Requests per minute, unlimited if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_requests_per_minute: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Validate the limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a quota is zero; plugins that must not use the
+    /// network should not request the `network` permission.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.network_bytes_per_day == Some(0) || self.network_requests_per_minute == Some(0) {
+            return Err(crate::Error::manifest(
+                "Network quotas must be positive; omit the 'network' permission to disable network access",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Host information a plugin can request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostInfoField {
+    /// Host name of the machine.
+    Hostname,
+
+    /// Operating system, e.g. `linux`.
+    Os,
+
+    /// CPU architecture, e.g. `x86_64`.
+    Arch,
+
+    /// Orbis version.
+    AppVersion,
+}
+
+/// Filesystem access of a plugin.
+///
+/// Absolute paths name host files and directories, which plugins can only
+/// access under a granted path. Relative paths are inside the plugin's private
+/// temporary space, and `store:` paths in its object storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemGrants {
+    /// Host paths the plugin may read, with everything under them.
+    #[serde(default)]
+    pub read_only: Vec<PathBuf>,
+
+    /// Host paths the plugin may read and write, with everything under them.
+    #[serde(default)]
+    pub read_write: Vec<PathBuf>,
+
+    /// Size of the plugin's temporary space, in bytes (0 for none).
+    #[serde(default)]
+    pub temp_space_bytes: u64,
+
+    /// Size of the plugin's object storage, in bytes (0 for none).
+    #[serde(default)]
+    pub storage_bytes: u64,
+}
+
+impl FilesystemGrants {
+    /// Check if no filesystem access is granted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.read_only.is_empty()
+            && self.read_write.is_empty()
+            && self.temp_space_bytes == 0
+            && self.storage_bytes == 0
+    }
+
+    /// Validate the grants against the permissions the plugin requests.
+    ///
+    /// Granted paths must be absolute and free of `..` components. Reading
+    /// needs the `file_read` permission, and writing, including to the
+    /// temporary space and object storage, the `file_write` permission.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a path is invalid or a permission is missing.
+    pub fn validate(&self, permissions: &[PluginPermission]) -> crate::Result<()> {
+        for path in self.read_only.iter().chain(&self.read_write) {
+            if !path.is_absolute() || path.components().any(|c| c == std::path::Component::ParentDir) {
+                return Err(crate::Error::manifest(format!(
+                    "Invalid filesystem grant '{}': paths must be absolute, without '..'",
+                    path.display()
+                )));
+            }
+        }
+
+        if !self.is_empty() && !permissions.contains(&PluginPermission::FileRead) {
+            return Err(crate::Error::manifest("Filesystem grants require the 'file_read' permission"));
+        }
+
+        if (!self.read_write.is_empty() || self.temp_space_bytes > 0 || self.storage_bytes > 0)
+            && !permissions.contains(&PluginPermission::FileWrite)
+        {
+            return Err(crate::Error::manifest(
+                "Writable filesystem grants, temporary space and object storage require the 'file_write' permission",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Plugin activation mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginActivation {
+    /// Compile and instantiate the plugin when it is loaded.
+    #[default]
+    Eager,
+
+    /// Register routes and pages from the manifest, but only compile and
+    /// instantiate the plugin on first use.
+    Lazy,
+}
+
 /// Plugin dependency.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginDependency {
@@ -150,10 +637,33 @@ pub enum PluginPermission {
     /// Access environment variables.
     Environment,
 
+    /// Send emails.
+    Email,
+
     /// Custom permission.
     Custom(String),
 }
 
+impl PluginPermission {
+    /// Get the permission name used in policies (the custom name for custom
+    /// permissions).
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match *self {
+            Self::DatabaseRead => "database_read",
+            Self::DatabaseWrite => "database_write",
+            Self::FileRead => "file_read",
+            Self::FileWrite => "file_write",
+            Self::Network => "network",
+            Self::System => "system",
+            Self::Shell => "shell",
+            Self::Environment => "environment",
+            Self::Email => "email",
+            Self::Custom(ref name) => name,
+        }
+    }
+}
+
 /// API route definition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginRoute {
@@ -178,9 +688,45 @@ pub struct PluginRoute {
     #[serde(default)]
     pub permissions: Vec<String>,
 
+    /// Groups allowed to call the route (members of subgroups included).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+
     /// Rate limit (requests per minute).
     #[serde(default)]
     pub rate_limit: Option<u32>,
+
+    /// Response caching (`GET` routes only).
+    #[serde(default)]
+    pub cache: Option<RouteCache>,
+
+    /// Maximum request body size, in bytes (the plugin's limit if unset).
+    #[serde(default)]
+    pub max_body_size: Option<usize>,
+
+    /// Schemas requests are checked against before the handler runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<RequestSchema>,
+
+    /// Whether the route is only called by API clients: requests authenticate
+    /// with the `Authorization` header, never the web client's session cookie,
+    /// so they need no CSRF token.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub api_only: bool,
+}
+
+/// Response caching of a `GET` route.
+///
+/// Responses are cached per tenant, user and query string; plugins purge
+/// them after writes with the `cache_invalidate` host function.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteCache {
+    /// How long responses stay fresh, in seconds.
+    pub ttl_seconds: u64,
+
+    /// Request headers the response varies on (e.g. `accept-language`).
+    #[serde(default)]
+    pub vary: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -188,6 +734,15 @@ fn default_true() -> bool {
 }
 
 impl PluginRoute {
+    /// Check whether a caller may call the route (`None` for anonymous
+    /// callers): they need to be in one of its groups, if any. Admins pass.
+    #[must_use]
+    pub fn is_accessible_by(&self, viewer: Option<&crate::ui::ViewerAccess>) -> bool {
+        viewer.map_or(self.groups.is_empty(), |viewer| {
+            viewer.is_admin() || viewer.in_any_group(&self.groups)
+        })
+    }
+
     /// Validate the route.
     ///
     /// # Errors
@@ -217,6 +772,36 @@ impl PluginRoute {
             return Err(crate::Error::manifest("Route handler is required"));
         }
 
+        // Validate caching
+        if let Some(cache) = &self.cache {
+            if !self.method.eq_ignore_ascii_case("GET") {
+                return Err(crate::Error::manifest(format!(
+                    "Route {} {} cannot be cached: only GET routes can",
+                    self.method, self.path
+                )));
+            }
+            if cache.ttl_seconds == 0 {
+                return Err(crate::Error::manifest(format!(
+                    "Cache TTL of route {} must be at least 1 second",
+                    self.path
+                )));
+            }
+        }
+
+        // Validate request schemas
+        if let Some(request) = self.request.as_ref() {
+            let has_body = ["POST", "PUT", "PATCH"].contains(&self.method.to_uppercase().as_str());
+            if request.body.is_some() && !has_body {
+                return Err(crate::Error::manifest(format!(
+                    "Route {} {} cannot declare a body schema: only POST, PUT and PATCH routes have bodies",
+                    self.method, self.path
+                )));
+            }
+            request.validate().map_err(|e| {
+                crate::Error::manifest(format!("Invalid request schema of route {}: {}", self.path, e))
+            })?;
+        }
+
         Ok(())
     }
 
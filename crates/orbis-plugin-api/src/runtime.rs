@@ -42,6 +42,29 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Information about the host running a plugin.
+///
+/// Fields the plugin's manifest does not request under
+/// `requirements.host_info` are redacted (`None`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    /// Host name of the machine.
+    #[serde(default)]
+    pub hostname: Option<String>,
+
+    /// Operating system, e.g. `linux`.
+    #[serde(default)]
+    pub os: Option<String>,
+
+    /// CPU architecture, e.g. `x86_64`.
+    #[serde(default)]
+    pub arch: Option<String>,
+
+    /// Orbis version.
+    #[serde(default)]
+    pub app_version: Option<String>,
+}
+
 /// Context passed to plugin handlers.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginContext {
@@ -68,6 +91,35 @@ pub struct PluginContext {
     /// Whether user is admin.
     #[serde(default)]
     pub is_admin: bool,
+
+    /// Groups the user is a member of, directly or through a subgroup.
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Tenant the request belongs to (multi-tenant deployments only).
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+
+    /// Time by which the request must complete (RFC 3339).
+    #[serde(default)]
+    pub deadline: Option<String>,
+
+    /// Feature flags of the plugin, with the profile's overrides applied.
+    #[serde(default)]
+    pub features: std::collections::BTreeMap<String, bool>,
+}
+
+/// A write to plugin state, as sent to `state_set_many`.
+///
+/// Writes are explicit so that setting a key to `null` is not mistaken for
+/// removing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateWrite {
+    /// Set the key to a value, which may be `null`.
+    Set(serde_json::Value),
+    /// Remove the key.
+    Remove,
 }
 
 /// Log levels for plugin logging.
@@ -86,6 +138,123 @@ pub enum LogLevel {
     Trace = 4,
 }
 
+/// Name of the WASM custom section holding the signature of a plugin.
+pub const SIGNATURE_SECTION: &str = "plugin_signature";
+
+/// Name of the WASM custom section holding the SHA-256 hash of a signed plugin.
+pub const HASH_SECTION: &str = "plugin_hash";
+
+/// Plugin ABI version.
+///
+/// Embedded by `orbis_plugin!` in the [`AbiVersion::SECTION`] custom section
+/// and checked by the loader. Plugins with a different major version are
+/// rejected; plugins with an older minor version are run with compatibility shims.
+///
+/// | Version | Changes |
+/// |---------|---------|
+/// | 1.0     | Unversioned plugins; handlers may return a bare JSON body |
+/// | 1.1     | ABI version section; handlers return a `Response` envelope |
+/// | 1.2     | Request `deadline` in the context; `is_cancelled` host function |
+/// | 1.3     | Batched `state_get_many`, `state_set_many` and `db_query_batch` host functions |
+/// | 1.4     | Request `tenant_id` in the context; state and config are tenant-scoped |
+/// | 1.5     | `data_export` and `data_import` host functions |
+/// | 1.6     | `job_enqueue` host function |
+/// | 1.7     | `media_probe` and `media_thumbnail` host functions |
+/// | 1.8     | `email_send` host function |
+/// | 1.9     | `cache_invalidate` host function |
+/// | 1.10    | `file_read`, `file_write`, `file_list` and `file_remove` host functions |
+/// | 1.11    | `host_info` and `host_env` host functions |
+/// | 1.12    | `groups` host function; user `groups` in the context |
+/// | 1.13    | `file_url` host function; `store:` paths in object storage |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AbiVersion {
+    /// Major version (breaking changes).
+    pub major: u16,
+    /// Minor version (backwards compatible changes).
+    pub minor: u16,
+}
+
+impl AbiVersion {
+    /// ABI version implemented by this SDK and host.
+    pub const CURRENT: Self = Self::new(1, 13);
+
+    /// Version assumed for plugins built before the ABI was versioned.
+    pub const LEGACY: Self = Self::new(1, 0);
+
+    /// Name of the WASM custom section holding the version.
+    pub const SECTION: &'static str = "orbis_abi";
+
+    /// Create a version.
+    #[must_use]
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// Encode as custom section contents (major and minor as little-endian `u16`).
+    #[must_use]
+    pub const fn to_bytes(self) -> [u8; 4] {
+        let major = self.major.to_le_bytes();
+        let minor = self.minor.to_le_bytes();
+        [major[0], major[1], minor[0], minor[1]]
+    }
+
+    /// Decode from custom section contents.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [major_lo, major_hi, minor_lo, minor_hi] => Some(Self::new(
+                u16::from_le_bytes([*major_lo, *major_hi]),
+                u16::from_le_bytes([*minor_lo, *minor_hi]),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Check whether a plugin built against this version can run on the current host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the major version differs or the plugin targets a
+    /// newer minor version than the host supports.
+    pub fn check_compatible(self) -> crate::Result<()> {
+        let current = Self::CURRENT;
+
+        if self.major != current.major {
+            return Err(crate::Error::plugin(format!(
+                "Plugin ABI {} is incompatible with host ABI {}; rebuild the plugin with an orbis-plugin-api release targeting ABI {}.x",
+                self, current, current.major
+            )));
+        }
+
+        if self.minor > current.minor {
+            return Err(crate::Error::plugin(format!(
+                "Plugin ABI {} is newer than host ABI {}; upgrade Orbis to run this plugin",
+                self, current
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the version as a semver version, for matching manifest `core_version` ranges.
+    #[must_use]
+    pub fn to_semver(self) -> semver::Version {
+        semver::Version::new(u64::from(self.major), u64::from(self.minor), 0)
+    }
+
+    /// Check if this version predates the current minor version and needs shims.
+    #[must_use]
+    pub fn needs_shims(self) -> bool {
+        self < Self::CURRENT
+    }
+}
+
+impl std::fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
 /// Host functions that plugins can import and call.
 ///
 /// These functions are implemented by the Orbis runtime and available to all plugins.
@@ -311,6 +480,21 @@ mod tests {
         assert_eq!(LogLevel::Trace as i32, 4);
     }
 
+    #[test]
+    fn test_abi_version_compatibility() {
+        let current = AbiVersion::CURRENT;
+        assert_eq!(AbiVersion::from_bytes(&current.to_bytes()), Some(current));
+        assert_eq!(AbiVersion::from_bytes(&[1, 0]), None);
+
+        assert!(current.check_compatible().is_ok());
+        assert!(AbiVersion::LEGACY.check_compatible().is_ok());
+        assert!(AbiVersion::LEGACY.needs_shims());
+        assert!(!current.needs_shims());
+
+        assert!(AbiVersion::new(current.major + 1, 0).check_compatible().is_err());
+        assert!(AbiVersion::new(current.major, current.minor + 1).check_compatible().is_err());
+    }
+
     #[test]
     fn test_plugin_context_serialization() {
         let context = PluginContext {
@@ -321,6 +505,10 @@ mod tests {
             body: serde_json::json!({}),
             user_id: Some("user123".to_string()),
             is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
         };
 
         let json = serde_json::to_string(&context).unwrap();
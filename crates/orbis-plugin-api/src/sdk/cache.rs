@@ -0,0 +1,49 @@
+//! Route response cache.
+//!
+//! `GET` routes declaring `cache` in the manifest have their responses cached
+//! by the host. After writing data a cached route returns, invalidate the
+//! route so the next request sees the change.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::cache;
+//!
+//! fn create_item(ctx: Context) -> Result<Response> {
+//!     // ... insert the item
+//!     cache::invalidate("/items")?;
+//!     Response::json(&json!({ "created": true }))
+//! }
+//! ```
+
+use super::error::Result;
+
+/// Route argument purging the cached responses of all the plugin's routes.
+pub const ALL_ROUTES: &str = "*";
+
+/// Drop the cached responses of one of the plugin's routes (e.g. `/items`),
+/// for all users and query strings.
+///
+/// # Errors
+///
+/// Returns an error if the route is not a path or [`ALL_ROUTES`].
+#[cfg(target_arch = "wasm32")]
+pub fn invalidate(route: &str) -> Result<()> {
+    let invalidated = unsafe { super::ffi::cache_invalidate(route.as_ptr() as i32, route.len() as i32) };
+    if invalidated == 0 {
+        return Err(super::error::Error::from_host(super::error::Error::internal(format!(
+            "Failed to invalidate cached route '{}'",
+            route
+        ))));
+    }
+    Ok(())
+}
+
+/// Drop the cached responses of a route (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn invalidate(route: &str) -> Result<()> {
+    if route != ALL_ROUTES && !route.starts_with('/') {
+        return Err(super::error::Error::internal(format!("Invalid route '{}'", route)));
+    }
+    Ok(())
+}
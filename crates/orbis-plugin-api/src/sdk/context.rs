@@ -1,8 +1,9 @@
 //! Request context passed to plugin handlers.
 
 use super::error::{Error, Result};
+use super::pagination::Pagination;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Context passed to plugin handlers.
 ///
@@ -39,9 +40,25 @@ pub struct Context {
     #[serde(default)]
     pub is_admin: bool,
 
+    /// Groups the user is a member of, directly or through a subgroup
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Tenant the request belongs to (multi-tenant deployments only)
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+
     /// Request ID for tracing
     #[serde(default)]
     pub request_id: Option<String>,
+
+    /// Time by which the request must complete (RFC 3339)
+    #[serde(default)]
+    pub deadline: Option<String>,
+
+    /// Feature flags of the plugin, with the profile's overrides applied
+    #[serde(default)]
+    pub features: BTreeMap<String, bool>,
 }
 
 impl Context {
@@ -105,6 +122,15 @@ impl Context {
             .map(|(_, v)| v.as_str())
     }
 
+    /// Get the `Idempotency-Key` the client sent to de-duplicate retries
+    ///
+    /// Mark the response with [`Response::idempotent`](super::response::Response::idempotent)
+    /// to have the host replay it for retries with the same key.
+    #[inline]
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.header("Idempotency-Key").filter(|key| !key.is_empty())
+    }
+
     /// Parse the request body as a specific type
     #[inline]
     pub fn body_as<T: for<'de> Deserialize<'de>>(&self) -> Result<T> {
@@ -149,28 +175,57 @@ impl Context {
         }
     }
 
+    /// Get the tenant the request belongs to, in multi-tenant deployments
+    ///
+    /// State and configuration are already scoped to this tenant by the host.
+    #[inline]
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.tenant_id.as_deref()
+    }
+
+    /// Get the time by which the request must complete, as an RFC 3339 timestamp
+    #[inline]
+    pub fn deadline(&self) -> Option<&str> {
+        self.deadline.as_deref()
+    }
+
+    /// Check if the request was cancelled or has passed its deadline
+    ///
+    /// Long-running handlers should poll this and stop early; the host
+    /// interrupts the plugin shortly after either happens anyway.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        super::ffi::is_cancelled()
+    }
+
+    /// Check if a feature flag of the plugin is on
+    ///
+    /// Flags the manifest does not declare are off.
+    #[inline]
+    pub fn feature_enabled(&self, name: &str) -> bool {
+        self.features.get(name).copied().unwrap_or(false)
+    }
+
     /// Check if the request method matches
     #[inline]
     pub fn is_method(&self, method: &str) -> bool {
         self.method.eq_ignore_ascii_case(method)
     }
 
-    /// Get pagination parameters from query string
+    /// Get pagination parameters from the `limit`, `page` and `cursor` query params
     ///
-    /// Returns (page, per_page) with defaults of (1, 20)
-    pub fn pagination(&self) -> (u32, u32) {
-        let page = self.query_param_as("page").unwrap_or(1).max(1);
-        let per_page = self.query_param_as("per_page").unwrap_or(20).clamp(1, 100);
-        (page, per_page)
+    /// Defaults to 20 items on the first page, like core list endpoints.
+    /// See [`Pagination`] for cursor pagination.
+    pub fn pagination(&self) -> Result<Pagination> {
+        Pagination::from_query(&self.query)
     }
 
     /// Get offset/limit for database queries from pagination
     ///
     /// Returns (offset, limit)
-    pub fn pagination_offset(&self) -> (u32, u32) {
-        let (page, per_page) = self.pagination();
-        let offset = (page - 1) * per_page;
-        (offset, per_page)
+    pub fn pagination_offset(&self) -> Result<(u32, u32)> {
+        let pagination = self.pagination()?;
+        Ok((pagination.offset(), pagination.limit))
     }
 }
 
@@ -188,7 +243,8 @@ mod tests {
             "headers": {"Content-Type": "application/json"},
             "body": {"name": "Test"},
             "user_id": "user123",
-            "is_admin": false
+            "is_admin": false,
+            "features": {"exports": true}
         }"#;
 
         let ctx: Context = serde_json::from_str(json).unwrap();
@@ -198,8 +254,11 @@ mod tests {
         assert_eq!(ctx.param("id"), Some("123"));
         assert_eq!(ctx.query_param("page"), Some("2"));
         assert_eq!(ctx.header("content-type"), Some("application/json"));
+        assert_eq!(ctx.idempotency_key(), None);
         assert!(ctx.is_authenticated());
         assert!(!ctx.is_admin);
+        assert!(ctx.feature_enabled("exports"));
+        assert!(!ctx.feature_enabled("beta"));
     }
 
     #[test]
@@ -213,10 +272,15 @@ mod tests {
             body: serde_json::Value::Null,
             user_id: None,
             is_admin: false,
+            groups: Vec::new(),
             request_id: None,
+            tenant_id: None,
+            deadline: None,
+            features: BTreeMap::new(),
         };
 
-        assert_eq!(ctx.pagination(), (3, 50));
-        assert_eq!(ctx.pagination_offset(), (100, 50));
+        let pagination = ctx.pagination().unwrap();
+        assert_eq!((pagination.page, pagination.limit), (3, 50));
+        assert_eq!(ctx.pagination_offset().unwrap(), (100, 50));
     }
 }
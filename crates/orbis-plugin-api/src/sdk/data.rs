@@ -0,0 +1,77 @@
+//! Export and import of plugin data.
+//!
+//! Lets a plugin package its state and data files into a portable ZIP
+//! archive and restore them, e.g. to offer backups or move data between a
+//! standalone and a server deployment.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::data;
+//!
+//! // Package all state and data files
+//! let archive = data::export()?;
+//!
+//! // Restore them later, replacing the current data
+//! data::import(&archive)?;
+//! ```
+//!
+//! Inside a tenant-scoped request only that tenant's state is included.
+//! Plugin database tables are only included in exports made by an admin
+//! through the host.
+
+#[cfg(target_arch = "wasm32")]
+use super::error::Error;
+use super::error::Result;
+
+/// Export the plugin's state and data files as a ZIP archive.
+///
+/// Buffered state writes are flushed first so they are part of the export.
+///
+/// # Errors
+///
+/// Returns an error if flushing buffered writes fails or the host cannot build the archive.
+#[cfg(target_arch = "wasm32")]
+pub fn export() -> Result<Vec<u8>> {
+    super::state::flush()?;
+
+    let ptr = unsafe { super::ffi::data_export() };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::state("Failed to export plugin data")));
+    }
+
+    Ok(unsafe { super::ffi::read_length_prefixed(ptr) })
+}
+
+/// Export the plugin's data (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export() -> Result<Vec<u8>> {
+    super::state::flush()?;
+    Ok(Vec::new())
+}
+
+/// Replace the plugin's state and data files with an archive from [`export`].
+///
+/// Buffered state writes that have not been flushed are discarded.
+///
+/// # Errors
+///
+/// Returns an error if the host rejects the archive.
+#[cfg(target_arch = "wasm32")]
+pub fn import(archive: &[u8]) -> Result<()> {
+    super::state::discard_buffered();
+
+    let result = unsafe { super::ffi::data_import(archive.as_ptr() as i32, archive.len() as i32) };
+    if result == 1 {
+        Ok(())
+    } else {
+        Err(Error::from_host(Error::state("Failed to import plugin data")))
+    }
+}
+
+/// Import plugin data (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import(_archive: &[u8]) -> Result<()> {
+    super::state::discard_buffered();
+    Ok(())
+}
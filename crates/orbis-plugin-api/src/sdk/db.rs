@@ -226,7 +226,7 @@ pub fn query<T: DeserializeOwned>(sql: &str, params: impl ToDbParams) -> Result<
     };
 
     if result_ptr == 0 {
-        return Err(Error::database("Database query failed"));
+        return Err(Error::from_host(Error::database("Database query failed")));
     }
 
     let result_bytes = unsafe { super::ffi::read_length_prefixed(result_ptr) };
@@ -269,7 +269,7 @@ pub fn query_raw(sql: &str, params: impl ToDbParams) -> Result<Vec<DbRow>> {
     };
 
     if result_ptr == 0 {
-        return Err(Error::database("Database query failed"));
+        return Err(Error::from_host(Error::database("Database query failed")));
     }
 
     let result_bytes = unsafe { super::ffi::read_length_prefixed(result_ptr) };
@@ -288,6 +288,57 @@ pub fn query_raw(_sql: &str, _params: impl ToDbParams) -> Result<Vec<DbRow>> {
     Ok(vec![])
 }
 
+/// A query to run as part of a batch
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchQuery {
+    /// SQL statement
+    pub sql: String,
+    /// Statement parameters
+    pub params: Vec<DbValue>,
+}
+
+impl BatchQuery {
+    /// Create a batch query
+    pub fn new(sql: impl Into<String>, params: impl ToDbParams) -> Self {
+        Self {
+            sql: sql.into(),
+            params: params.to_db_params(),
+        }
+    }
+}
+
+/// Run several queries in a single host call and return the rows of each, in order.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let results = db::query_batch(&[
+///     BatchQuery::new("SELECT * FROM users WHERE id = ?", [user_id]),
+///     BatchQuery::new("SELECT * FROM orders WHERE user_id = ?", [user_id]),
+/// ])?;
+/// ```
+#[cfg(target_arch = "wasm32")]
+pub fn query_batch(queries: &[BatchQuery]) -> Result<Vec<Vec<DbRow>>> {
+    let queries_json = serde_json::to_vec(queries)?;
+
+    let result_ptr = unsafe {
+        super::ffi::db_query_batch(queries_json.as_ptr() as i32, queries_json.len() as i32)
+    };
+
+    if result_ptr == 0 {
+        return Err(Error::from_host(Error::database("Database query batch failed")));
+    }
+
+    let result_bytes = unsafe { super::ffi::read_length_prefixed(result_ptr) };
+    Ok(serde_json::from_slice(&result_bytes)?)
+}
+
+/// Run several queries in a single host call (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn query_batch(queries: &[BatchQuery]) -> Result<Vec<Vec<DbRow>>> {
+    Ok(vec![Vec::new(); queries.len()])
+}
+
 /// Query for a single row
 pub fn query_one<T: DeserializeOwned>(sql: &str, params: impl ToDbParams) -> Result<Option<T>> {
     let results = query::<T>(sql, params)?;
@@ -336,7 +387,7 @@ pub fn execute(sql: &str, params: impl ToDbParams) -> Result<i64> {
     };
 
     if result < 0 {
-        return Err(Error::database("Database execute failed"));
+        return Err(Error::from_host(Error::database("Database execute failed")));
     }
 
     Ok(i64::from(result))
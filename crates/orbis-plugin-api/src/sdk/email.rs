@@ -0,0 +1,110 @@
+//! Sending email.
+//!
+//! Emails are queued by the host and delivered in the background, with
+//! retries when delivery fails. Requires the `email` permission.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::email::{self, Email};
+//!
+//! email::send(
+//!     &Email::new("ops@example.com", "Report ready for {{ team }}")
+//!         .text("The weekly report for {{ team }} is ready.")
+//!         .variable("team", "Platform"),
+//! )?;
+//! ```
+
+use super::error::Result;
+use serde::Serialize;
+
+/// An email to send.
+///
+/// The subject and bodies may contain `{{ name }}` placeholders filled from
+/// the variables; values are escaped in the HTML body.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Email {
+    /// Recipient addresses.
+    pub to: Vec<String>,
+
+    /// Subject.
+    pub subject: String,
+
+    /// Plain text body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// HTML body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+
+    /// Template variables.
+    pub variables: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Email {
+    /// Create an email to one recipient.
+    #[must_use]
+    pub fn new(to: &str, subject: &str) -> Self {
+        Self {
+            to: vec![to.to_owned()],
+            subject: subject.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a recipient.
+    #[must_use]
+    pub fn to(mut self, to: &str) -> Self {
+        self.to.push(to.to_owned());
+        self
+    }
+
+    /// Set the plain text body.
+    #[must_use]
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_owned());
+        self
+    }
+
+    /// Set the HTML body.
+    #[must_use]
+    pub fn html(mut self, html: &str) -> Self {
+        self.html = Some(html.to_owned());
+        self
+    }
+
+    /// Set a template variable.
+    #[must_use]
+    pub fn variable<T: Serialize>(mut self, name: &str, value: T) -> Self {
+        self.variables
+            .insert(name.to_owned(), serde_json::to_value(value).unwrap_or_default());
+        self
+    }
+}
+
+/// Queue an email for delivery.
+///
+/// # Errors
+///
+/// Returns an error if the plugin lacks the `email` permission or the host
+/// rejects the email.
+#[cfg(target_arch = "wasm32")]
+pub fn send(email: &Email) -> Result<()> {
+    let json = serde_json::to_vec(email)?;
+
+    let sent = unsafe { super::ffi::email_send(json.as_ptr() as i32, json.len() as i32) };
+    if sent == 0 {
+        return Err(super::error::Error::from_host(super::error::Error::internal(
+            "Failed to send email",
+        )));
+    }
+    Ok(())
+}
+
+/// Queue an email for delivery (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn send(email: &Email) -> Result<()> {
+    serde_json::to_vec(email)?;
+    Ok(())
+}
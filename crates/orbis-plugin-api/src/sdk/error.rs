@@ -1,10 +1,31 @@
 //! Plugin SDK error types.
+//!
+//! SDK operations fail with an [`Error`]. Handlers fail with a
+//! [`HandlerError`], which carries the HTTP status, a machine-readable code
+//! and details for the client; SDK errors convert into it with `?`.
+//!
+//! ```rust,ignore
+//! fn get_note(ctx: Context) -> HandlerResult {
+//!     let id = ctx.param("id").unwrap_or_default();
+//!     let note: Note = state::get(&format!("note:{}", id))?
+//!         .ok_or_else(|| HandlerError::not_found("Note not found").with_detail("id", id))?;
+//!     Ok(Response::json(&note)?)
+//! }
+//! ```
 
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// Result type for plugin operations
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Result type for plugin handlers
+pub type HandlerResult<T = super::response::Response> = std::result::Result<T, HandlerError>;
+
+/// Content type of problem responses.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
 /// Error type for plugin operations
 #[derive(Debug)]
 pub enum Error {
@@ -23,6 +44,9 @@ pub enum Error {
     /// Permission denied
     PermissionDenied(String),
 
+    /// Host call denied by the host's security policy
+    Denied(crate::security::PolicyDenial),
+
     /// Invalid input
     InvalidInput(String),
 
@@ -47,6 +71,7 @@ impl fmt::Display for Error {
             Self::Database(msg) => write!(f, "Database error: {}", msg),
             Self::Http(msg) => write!(f, "HTTP error: {}", msg),
             Self::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            Self::Denied(denial) => write!(f, "Permission denied: {}", denial),
             Self::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
             Self::NotFound(msg) => write!(f, "Not found: {}", msg),
             Self::Internal(msg) => write!(f, "Internal error: {}", msg),
@@ -126,15 +151,231 @@ impl Error {
         Self::Validation(msg.into())
     }
 
+    /// Explain a failed host call.
+    ///
+    /// Returns the host's policy denial if the call was denied, otherwise
+    /// the given error.
+    #[must_use]
+    pub fn from_host(fallback: Self) -> Self {
+        last_denial().map_or(fallback, Self::Denied)
+    }
+
+    /// Get the machine-readable code of this error
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match *self {
+            Self::Json(_) => "SERIALIZATION_ERROR",
+            Self::State(_) => "STATE_ERROR",
+            Self::Database(_) => "DATABASE_ERROR",
+            Self::Http(_) => "HTTP_ERROR",
+            Self::PermissionDenied(_) | Self::Denied(_) => "PERMISSION_DENIED",
+            Self::InvalidInput(_) => "INVALID_INPUT",
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Internal(_) => "INTERNAL_ERROR",
+            Self::Validation(_) => "VALIDATION_ERROR",
+            Self::Timeout(_) => "REQUEST_TIMEOUT",
+        }
+    }
+
     /// Get HTTP status code for this error
     #[must_use]
     pub const fn status_code(&self) -> u16 {
         match self {
             Self::Json(_) | Self::InvalidInput(_) | Self::Validation(_) => 400,
-            Self::PermissionDenied(_) => 403,
+            Self::PermissionDenied(_) | Self::Denied(_) => 403,
             Self::NotFound(_) => 404,
             Self::Timeout(_) => 408,
             Self::State(_) | Self::Database(_) | Self::Http(_) | Self::Internal(_) => 500,
         }
     }
 }
+
+/// Error returned by a plugin handler.
+///
+/// Rendered as an RFC 7807 problem document, which the host turns into its
+/// usual error response, so plugin errors show up in the UI like core ones.
+#[allow(clippy::module_name_repetitions, reason = "Distinguishes handler errors from the SDK `Error`")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HandlerError {
+    /// HTTP status code
+    pub status: u16,
+
+    /// Machine-readable error code, such as `NOT_FOUND`
+    pub code: String,
+
+    /// Message shown to the user
+    pub message: String,
+
+    /// Additional details, such as the invalid fields
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, serde_json::Value>,
+}
+
+impl HandlerError {
+    /// Create a handler error
+    #[must_use]
+    pub fn new<C: Into<String>, M: Into<String>>(status: u16, code: C, message: M) -> Self {
+        Self {
+            status,
+            code: code.into(),
+            message: message.into(),
+            details: BTreeMap::new(),
+        }
+    }
+
+    /// Create a 400 Bad Request error
+    #[must_use]
+    pub fn bad_request<M: Into<String>>(message: M) -> Self {
+        Self::new(400, "BAD_REQUEST", message)
+    }
+
+    /// Create a 401 Unauthorized error
+    #[must_use]
+    pub fn unauthorized<M: Into<String>>(message: M) -> Self {
+        Self::new(401, "AUTH_ERROR", message)
+    }
+
+    /// Create a 403 Forbidden error
+    #[must_use]
+    pub fn forbidden<M: Into<String>>(message: M) -> Self {
+        Self::new(403, "PERMISSION_DENIED", message)
+    }
+
+    /// Create a 404 Not Found error
+    #[must_use]
+    pub fn not_found<M: Into<String>>(message: M) -> Self {
+        Self::new(404, "NOT_FOUND", message)
+    }
+
+    /// Create a 409 Conflict error
+    #[must_use]
+    pub fn conflict<M: Into<String>>(message: M) -> Self {
+        Self::new(409, "CONFLICT", message)
+    }
+
+    /// Create a 422 Unprocessable Entity error
+    #[must_use]
+    pub fn unprocessable<M: Into<String>>(message: M) -> Self {
+        Self::new(422, "VALIDATION_ERROR", message)
+    }
+
+    /// Create a 500 Internal Server Error error
+    #[must_use]
+    pub fn internal<M: Into<String>>(message: M) -> Self {
+        Self::new(500, "INTERNAL_ERROR", message)
+    }
+
+    /// Add a detail
+    ///
+    /// Values that fail to serialize are recorded as `null`.
+    #[must_use]
+    pub fn with_detail<K: Into<String>, V: Serialize>(mut self, key: K, value: V) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+        self.details.insert(key.into(), value);
+        self
+    }
+
+    /// Get the RFC 7807 problem document of this error
+    ///
+    /// `code` and `details` are extension members.
+    #[must_use]
+    pub fn problem(&self) -> serde_json::Value {
+        let mut problem = serde_json::Map::new();
+        problem.insert("type".to_owned(), "about:blank".into());
+        problem.insert("title".to_owned(), status_title(self.status).into());
+        problem.insert("status".to_owned(), self.status.into());
+        problem.insert("detail".to_owned(), self.message.clone().into());
+        problem.insert("code".to_owned(), self.code.clone().into());
+        if !self.details.is_empty() {
+            let details = self.details.clone().into_iter().collect();
+            problem.insert("details".to_owned(), serde_json::Value::Object(details));
+        }
+        serde_json::Value::Object(problem)
+    }
+}
+
+impl fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.code, self.status, self.message)
+    }
+}
+
+impl std::error::Error for HandlerError {}
+
+impl From<&Error> for HandlerError {
+    fn from(err: &Error) -> Self {
+        let error = Self::new(err.status_code(), err.code(), err.to_string());
+        if let Error::Denied(ref denial) = *err {
+            return error.with_detail("denial", denial);
+        }
+        error
+    }
+}
+
+impl From<Error> for HandlerError {
+    fn from(err: Error) -> Self {
+        Self::from(&err)
+    }
+}
+
+impl From<serde_json::Error> for HandlerError {
+    fn from(err: serde_json::Error) -> Self {
+        Error::from(err).into()
+    }
+}
+
+/// Get the reason phrase of common error statuses.
+const fn status_title(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ if status < 500 => "Client Error",
+        _ => "Server Error",
+    }
+}
+
+/// Take the policy denial of the last host call, if it was denied.
+#[cfg(target_arch = "wasm32")]
+pub(super) fn last_denial() -> Option<crate::security::PolicyDenial> {
+    let ptr = unsafe { super::ffi::host_denial() };
+    if ptr == 0 {
+        return None;
+    }
+
+    let bytes = unsafe { super::ffi::read_length_prefixed(ptr) };
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Take the policy denial of the last host call (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+const fn last_denial() -> Option<crate::security::PolicyDenial> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handler_error_problem() {
+        let err = HandlerError::not_found("Note not found").with_detail("id", 42);
+        let problem = err.problem();
+        assert_eq!(problem["status"], 404);
+        assert_eq!(problem["title"], "Not Found");
+        assert_eq!(problem["detail"], "Note not found");
+        assert_eq!(problem["code"], "NOT_FOUND");
+        assert_eq!(problem["details"]["id"], 42);
+
+        let err = HandlerError::from(Error::validation("title is required"));
+        assert_eq!((err.status, err.code.as_str()), (400, "VALIDATION_ERROR"));
+        assert!(err.problem().get("details").is_none());
+    }
+}
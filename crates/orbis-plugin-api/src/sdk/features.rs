@@ -0,0 +1,75 @@
+//! Feature flags of the plugin.
+//!
+//! Flags are declared in the manifest's `features` section with a default,
+//! and administrators may override them per profile while the plugin runs.
+//! The host passes the resolved flags with every request, so a toggle takes
+//! effect on the next request without reloading the plugin.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::features;
+//!
+//! fn export_notes(ctx: Context) -> Result<Response> {
+//!     if !features::enabled("exports") {
+//!         return Err(Error::not_found("Exports are not available"));
+//!     }
+//!     ...
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    /// Flags of the request being handled.
+    static FLAGS: RefCell<BTreeMap<String, bool>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Check if a feature flag is on for the request being handled.
+///
+/// Flags the manifest does not declare are off, as are all flags outside
+/// handlers wrapped with `wrap_handler!`.
+#[must_use]
+pub fn enabled(name: &str) -> bool {
+    FLAGS.with(|flags| flags.borrow().get(name).copied().unwrap_or(false))
+}
+
+/// Get all feature flags for the request being handled.
+#[must_use]
+pub fn all() -> BTreeMap<String, bool> {
+    FLAGS.with(|flags| flags.borrow().clone())
+}
+
+/// Set the flags of the request about to be handled.
+///
+/// Called by handlers wrapped with `wrap_handler!` with the flags of their
+/// context.
+#[doc(hidden)]
+pub fn begin_request(flags: &BTreeMap<String, bool>) {
+    FLAGS.with(|current| current.borrow_mut().clone_from(flags));
+}
+
+/// Forget the flags of the request that was handled.
+#[doc(hidden)]
+pub fn end_request() {
+    FLAGS.with(|flags| flags.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_flags() {
+        assert!(!enabled("exports"));
+
+        begin_request(&BTreeMap::from([("exports".to_string(), true), ("beta".to_string(), false)]));
+        assert!(enabled("exports"));
+        assert!(!enabled("beta"));
+        assert_eq!(all().len(), 2);
+
+        end_request();
+        assert!(!enabled("exports"));
+    }
+}
@@ -15,6 +15,8 @@ unsafe extern "C" {
     pub fn state_get(key_ptr: i32, key_len: i32) -> i32;
     pub fn state_set(key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32) -> i32;
     pub fn state_remove(key_ptr: i32, key_len: i32) -> i32;
+    pub fn state_get_many(keys_ptr: i32, keys_len: i32) -> i32;
+    pub fn state_set_many(entries_ptr: i32, entries_len: i32) -> i32;
 
     // Logging
     pub fn log(level: i32, ptr: i32, len: i32);
@@ -22,6 +24,7 @@ unsafe extern "C" {
     // Database (new)
     pub fn db_query(query_ptr: i32, query_len: i32, params_ptr: i32, params_len: i32) -> i32;
     pub fn db_execute(query_ptr: i32, query_len: i32, params_ptr: i32, params_len: i32) -> i32;
+    pub fn db_query_batch(queries_ptr: i32, queries_len: i32) -> i32;
 
     // HTTP (new)
     pub fn http_request(
@@ -41,9 +44,59 @@ unsafe extern "C" {
     // Config (new)
     pub fn get_config(key_ptr: i32, key_len: i32) -> i32;
 
+    // Host information
+    pub fn host_info() -> i32;
+    pub fn host_env(name_ptr: i32, name_len: i32) -> i32;
+
+    // Group membership
+    pub fn groups() -> i32;
+
+    // Background jobs
+    pub fn job_enqueue(job_ptr: i32, job_len: i32) -> i32;
+
+    // Response cache
+    pub fn cache_invalidate(route_ptr: i32, route_len: i32) -> i32;
+
+    // Email
+    pub fn email_send(email_ptr: i32, email_len: i32) -> i32;
+
+    // Media
+    pub fn media_probe(data_ptr: i32, data_len: i32) -> i32;
+    pub fn media_thumbnail(data_ptr: i32, data_len: i32, width: i32, height: i32) -> i32;
+
+    // Files
+    pub fn file_read(path_ptr: i32, path_len: i32) -> i32;
+    pub fn file_write(path_ptr: i32, path_len: i32, data_ptr: i32, data_len: i32) -> i32;
+    pub fn file_list(path_ptr: i32, path_len: i32) -> i32;
+    pub fn file_remove(path_ptr: i32, path_len: i32) -> i32;
+    pub fn file_url(path_ptr: i32, path_len: i32, ttl_seconds: i32) -> i32;
+
+    // Data export/import
+    pub fn data_export() -> i32;
+    pub fn data_import(archive_ptr: i32, archive_len: i32) -> i32;
+
     // Crypto (new)
     pub fn crypto_hash(algorithm: i32, data_ptr: i32, data_len: i32) -> i32;
     pub fn crypto_random(len: i32) -> i32;
+
+    // Security policy
+    pub fn host_denial() -> i32;
+
+    // Cancellation
+    #[link_name = "is_cancelled"]
+    fn host_is_cancelled() -> i32;
+}
+
+/// Check if the current request was cancelled or has passed its deadline
+#[cfg(target_arch = "wasm32")]
+pub fn is_cancelled() -> bool {
+    unsafe { host_is_cancelled() != 0 }
+}
+
+/// Shadow implementation of is_cancelled for non-WASM targets
+#[cfg(not(target_arch = "wasm32"))]
+pub const fn is_cancelled() -> bool {
+    false
 }
 
 /// Shadow implementation of the log function for non-WASM targets
@@ -179,7 +232,7 @@ pub fn return_json<T: serde::Serialize>(value: &T) -> Result<i32, serde_json::Er
 
 /// Wraps a handler function to handle FFI details automatically
 ///
-/// Converts: `fn(Context) -> Result<Response>` into `extern "C" fn(i32, i32) -> i32`
+/// Converts: `fn(Context) -> Result<Response>` (or `HandlerResult`) into `extern "C" fn(i32, i32) -> i32`
 ///
 /// # Usage
 ///
@@ -212,13 +265,27 @@ macro_rules! wrap_handler {
                 }
             };
 
-            // Call the actual handler
-            match $handler_fn(ctx) {
+            // Call the actual handler, with the feature flags of the request
+            $crate::sdk::features::begin_request(&ctx.features);
+            let result = $handler_fn(ctx);
+            $crate::sdk::features::end_request();
+
+            // Write out state changes buffered during the handler
+            if let Err(e) = $crate::sdk::state::flush() {
+                let error_message = format!("Failed to flush state: {}", e);
+                unsafe { $crate::sdk::ffi::log(0, error_message.as_ptr() as i32, error_message.len() as i32); }
+            }
+
+            // Drop the services of this request
+            $crate::sdk::services::end_request();
+
+            // Handlers may fail with an SDK `Error` or a `HandlerError`
+            match result.map_err(HandlerError::from) {
                 Ok(response) => response.to_raw().unwrap_or(0),
                 Err(e) => {
                     let error_message = format!("Handler error: {}", e);
                     unsafe { $crate::sdk::ffi::log(0, error_message.as_ptr() as i32, error_message.len() as i32); }
-                    Response::error(500, &e.to_string())
+                    Response::problem(&e)
                         .to_raw()
                         .unwrap_or(0)
                 }
@@ -272,6 +339,19 @@ macro_rules! orbis_allocators {
     };
 }
 
+/// Embed the plugin ABI version in the `orbis_abi` custom section
+///
+/// Invoked by `orbis_plugin!`; the loader reads it to reject plugins built
+/// against an incompatible SDK.
+#[macro_export]
+macro_rules! orbis_abi_version {
+    () => {
+        #[used]
+        #[cfg_attr(target_arch = "wasm32", unsafe(link_section = "orbis_abi"))]
+        static ORBIS_ABI_VERSION: [u8; 4] = $crate::runtime::AbiVersion::CURRENT.to_bytes();
+    };
+}
+
 /// Define a complete plugin with minimal boilerplate
 ///
 /// # Example
@@ -325,6 +405,7 @@ macro_rules! orbis_plugin {
         }
 
         $crate::sdk::ffi::orbis_allocators!();
+        $crate::sdk::ffi::orbis_abi_version!();
     };
 
     // With only init
@@ -348,6 +429,7 @@ macro_rules! orbis_plugin {
         }
 
         $crate::sdk::ffi::orbis_allocators!();
+        $crate::sdk::ffi::orbis_abi_version!();
     };
 
     // No init or cleanup (just lifecycle stubs)
@@ -361,11 +443,13 @@ macro_rules! orbis_plugin {
         pub extern "C" fn cleanup() -> i32 {
             1
         }
-        
+
         $crate::sdk::ffi::orbis_allocators!();
+        $crate::sdk::ffi::orbis_abi_version!();
     };
 }
 
 pub use orbis_plugin;
 pub use wrap_handler;
 pub use orbis_allocators;
+pub use orbis_abi_version;
@@ -0,0 +1,163 @@
+//! Filesystem access.
+//!
+//! Files are read and written by the host, within the grants of the plugin
+//! manifest's `requirements.filesystem`:
+//!
+//! - absolute paths must be under a `read_only` or `read_write` path, and
+//!   under a `read_write` path to be written or removed;
+//! - relative paths are in the plugin's private temporary space, limited to
+//!   `temp_space_bytes` and emptied when the plugin is loaded;
+//! - `store:` paths are in the plugin's object storage (a local directory
+//!   or an S3 bucket, as the host is configured), limited to
+//!   `storage_bytes` and kept across reloads. [`url`] gives users a
+//!   temporary link downloading such a file, without going through the
+//!   plugin.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::files;
+//!
+//! let template = files::read("/etc/reports/template.html")?;
+//! files::write("report.html", &render(&template))?;
+//!
+//! for name in files::list("/var/reports")? {
+//!     log::info!("Report: {}", name);
+//! }
+//!
+//! files::write("store:exports/report.zip", &archive)?;
+//! let link = files::url("store:exports/report.zip", Duration::from_secs(3600))?;
+//! ```
+
+#[cfg(target_arch = "wasm32")]
+use super::error::Error;
+use super::error::Result;
+use std::time::Duration;
+
+/// Read a file.
+///
+/// # Errors
+///
+/// Returns an error if the path is not granted or the file cannot be read.
+#[cfg(target_arch = "wasm32")]
+pub fn read(path: &str) -> Result<Vec<u8>> {
+    let ptr = unsafe { super::ffi::file_read(path.as_ptr() as i32, path.len() as i32) };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::internal(format!("Failed to read '{}'", path))));
+    }
+
+    Ok(unsafe { super::ffi::read_length_prefixed(ptr) })
+}
+
+/// Read a file (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(path: &str) -> Result<Vec<u8>> {
+    Err(super::error::Error::internal(format!("File access is only available in WASM ('{}')", path)))
+}
+
+/// Read a UTF-8 text file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not valid UTF-8.
+pub fn read_to_string(path: &str) -> Result<String> {
+    Ok(String::from_utf8(read(path)?)?)
+}
+
+/// Write a file, replacing it if it exists and creating missing parent directories.
+///
+/// # Errors
+///
+/// Returns an error if the path is not writable, the temporary space is
+/// full, or the file cannot be written.
+#[cfg(target_arch = "wasm32")]
+pub fn write(path: &str, data: &[u8]) -> Result<()> {
+    let result = unsafe {
+        super::ffi::file_write(path.as_ptr() as i32, path.len() as i32, data.as_ptr() as i32, data.len() as i32)
+    };
+    if result == 1 {
+        Ok(())
+    } else {
+        Err(Error::from_host(Error::internal(format!("Failed to write '{}'", path))))
+    }
+}
+
+/// Write a file (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(path: &str, data: &[u8]) -> Result<()> {
+    Err(super::error::Error::internal(format!(
+        "File access is only available in WASM ('{}', {} bytes)",
+        path,
+        data.len()
+    )))
+}
+
+/// List the entry names of a directory, sorted. Directory names end with `/`.
+///
+/// Use `"."` to list the temporary space.
+///
+/// # Errors
+///
+/// Returns an error if the path is not granted or is not a directory.
+#[cfg(target_arch = "wasm32")]
+pub fn list(path: &str) -> Result<Vec<String>> {
+    let ptr = unsafe { super::ffi::file_list(path.as_ptr() as i32, path.len() as i32) };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::internal(format!("Failed to list '{}'", path))));
+    }
+
+    let entries = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(serde_json::from_slice(&entries)?)
+}
+
+/// List a directory (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list(path: &str) -> Result<Vec<String>> {
+    Err(super::error::Error::internal(format!("File access is only available in WASM ('{}')", path)))
+}
+
+/// Remove a file, or a directory with its contents.
+///
+/// Granted paths themselves cannot be removed.
+///
+/// # Errors
+///
+/// Returns an error if the path is not writable or cannot be removed.
+#[cfg(target_arch = "wasm32")]
+pub fn remove(path: &str) -> Result<()> {
+    let result = unsafe { super::ffi::file_remove(path.as_ptr() as i32, path.len() as i32) };
+    if result == 1 {
+        Ok(())
+    } else {
+        Err(Error::from_host(Error::internal(format!("Failed to remove '{}'", path))))
+    }
+}
+
+/// Remove a file or directory (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(path: &str) -> Result<()> {
+    Err(super::error::Error::internal(format!("File access is only available in WASM ('{}')", path)))
+}
+
+/// Get a URL downloading a `store:` file without credentials, valid for
+/// `ttl` (at most 7 days; zero for the host default of 15 minutes).
+///
+/// # Errors
+///
+/// Returns an error if the path is not a readable `store:` file.
+#[cfg(target_arch = "wasm32")]
+pub fn url(path: &str, ttl: Duration) -> Result<String> {
+    let ttl_seconds = i32::try_from(ttl.as_secs()).unwrap_or(i32::MAX);
+    let ptr = unsafe { super::ffi::file_url(path.as_ptr() as i32, path.len() as i32, ttl_seconds) };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::internal(format!("Failed to get a URL of '{}'", path))));
+    }
+
+    Ok(String::from_utf8(unsafe { super::ffi::read_length_prefixed(ptr) })?)
+}
+
+/// Get a download URL of a file (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn url(path: &str, _ttl: Duration) -> Result<String> {
+    Err(super::error::Error::internal(format!("File access is only available in WASM ('{}')", path)))
+}
@@ -0,0 +1,57 @@
+//! Group membership of the user making the request.
+//!
+//! Groups nest, and a member of a group is a member of all its ancestors:
+//! the host reports every group the user effectively belongs to, as of when
+//! their access token was issued. Routes and pages can declare `groups` in
+//! the manifest to be restricted to members; handlers use this module for
+//! finer checks.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::groups;
+//!
+//! fn approve_invoice(ctx: Context) -> HandlerResult<Response> {
+//!     if !groups::contains("finance")? {
+//!         return Err(HandlerError::forbidden("Only finance can approve invoices"));
+//!     }
+//!     ...
+//! }
+//! ```
+
+#[cfg(target_arch = "wasm32")]
+use super::error::Error;
+use super::error::Result;
+
+/// Get the names of the groups the user is a member of.
+///
+/// Empty for anonymous requests and background jobs.
+///
+/// # Errors
+///
+/// Returns an error if the host call fails or is denied by policy.
+#[cfg(target_arch = "wasm32")]
+pub fn all() -> Result<Vec<String>> {
+    let ptr = unsafe { super::ffi::groups() };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::internal("Failed to get groups")));
+    }
+
+    let groups = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(serde_json::from_slice(&groups)?)
+}
+
+/// Get the groups of the user (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub const fn all() -> Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+/// Check if the user is a member of a group, directly or through a subgroup.
+///
+/// # Errors
+///
+/// Returns an error if the host call fails or is denied by policy.
+pub fn contains(name: &str) -> Result<bool> {
+    Ok(all()?.iter().any(|group| group == name))
+}
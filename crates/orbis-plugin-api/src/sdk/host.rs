@@ -0,0 +1,70 @@
+//! Host information and environment variables.
+//!
+//! Plugins only see what their manifest requests under `requirements`:
+//! `environment` lists the variables [`env`] may read, and `host_info` the
+//! fields [`info`] fills in. Everything else is redacted.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::host;
+//!
+//! // "requirements": { "environment": ["REPORTS_REGION"], "host_info": ["hostname", "app_version"] }
+//! let region = host::env("REPORTS_REGION")?.unwrap_or_else(|| "eu".into());
+//! let info = host::info()?;
+//! log::info!("Running on {:?} (Orbis {:?})", info.hostname, info.app_version);
+//! ```
+
+#[cfg(target_arch = "wasm32")]
+use super::error::Error;
+use super::error::Result;
+use crate::runtime::HostInfo;
+
+/// Get information about the host.
+///
+/// Fields not requested in the manifest are `None`.
+///
+/// # Errors
+///
+/// Returns an error if the plugin lacks the `system` permission.
+#[cfg(target_arch = "wasm32")]
+pub fn info() -> Result<HostInfo> {
+    let ptr = unsafe { super::ffi::host_info() };
+    if ptr == 0 {
+        return Err(Error::from_host(Error::internal("Failed to get host info")));
+    }
+
+    let info = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(serde_json::from_slice(&info)?)
+}
+
+/// Get host information (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn info() -> Result<HostInfo> {
+    Ok(HostInfo::default())
+}
+
+/// Get an environment variable.
+///
+/// Returns `None` if the variable is unset or not requested in the manifest.
+///
+/// # Errors
+///
+/// Returns an error if the plugin lacks the `environment` permission.
+#[cfg(target_arch = "wasm32")]
+pub fn env(name: &str) -> Result<Option<String>> {
+    let ptr = unsafe { super::ffi::host_env(name.as_ptr() as i32, name.len() as i32) };
+    if ptr == 0 {
+        // A null pointer is an unset variable, unless the call was denied
+        return super::error::last_denial().map_or(Ok(None), |denial| Err(Error::Denied(denial)));
+    }
+
+    let value = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(Some(String::from_utf8(value)?))
+}
+
+/// Get an environment variable (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub const fn env(_name: &str) -> Result<Option<String>> {
+    Ok(None)
+}
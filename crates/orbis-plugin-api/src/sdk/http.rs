@@ -132,7 +132,7 @@ impl Request {
         };
 
         if result_ptr == 0 {
-            return Err(Error::http("HTTP request failed"));
+            return Err(Error::from_host(Error::http("HTTP request failed")));
         }
 
         let result_bytes = unsafe { super::ffi::read_length_prefixed(result_ptr) };
@@ -0,0 +1,124 @@
+//! Background jobs.
+//!
+//! Jobs are persisted by the host and run later by one of the plugin's
+//! handlers, with retries and exponential backoff when the handler fails.
+//! The handler receives the job payload as its request body, and the
+//! `x-orbis-job-id` and `x-orbis-job-attempt` headers.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::jobs::{self, JobOptions};
+//!
+//! // Run `send_report` as soon as a worker is free
+//! let id = jobs::enqueue("send_report", &json!({ "report": 42 }))?;
+//!
+//! // Run `cleanup` in an hour on its own queue, at most 3 times
+//! jobs::enqueue_with("cleanup", &json!({}), JobOptions {
+//!     queue: Some("maintenance".into()),
+//!     delay_seconds: 3600,
+//!     max_attempts: Some(3),
+//! })?;
+//! ```
+
+use super::error::Result;
+use serde::Serialize;
+
+/// Options of an enqueued job.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobOptions {
+    /// Queue to put the job on (the default queue if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<String>,
+
+    /// Seconds to wait before the job may run.
+    pub delay_seconds: u64,
+
+    /// Attempts before the job is dead-lettered (the host default if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+}
+
+/// Enqueue a job for a handler, returning the job ID.
+///
+/// # Errors
+///
+/// Returns an error if the payload cannot be serialized or the host rejects the job.
+pub fn enqueue<T: Serialize>(handler: &str, payload: &T) -> Result<String> {
+    enqueue_with(handler, payload, JobOptions::default())
+}
+
+/// Enqueue a job for a handler with options, returning the job ID.
+///
+/// # Errors
+///
+/// Returns an error if the payload cannot be serialized or the host rejects the job.
+#[cfg(target_arch = "wasm32")]
+pub fn enqueue_with<T: Serialize>(handler: &str, payload: &T, options: JobOptions) -> Result<String> {
+    let job = job_json(handler, payload, options)?;
+
+    let ptr = unsafe { super::ffi::job_enqueue(job.as_ptr() as i32, job.len() as i32) };
+    if ptr == 0 {
+        return Err(super::error::Error::from_host(super::error::Error::internal(format!(
+            "Failed to enqueue job for '{}'",
+            handler
+        ))));
+    }
+
+    let id = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(String::from_utf8(id)?)
+}
+
+/// Enqueue a job (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn enqueue_with<T: Serialize>(handler: &str, payload: &T, options: JobOptions) -> Result<String> {
+    job_json(handler, payload, options)?;
+    Ok(String::new())
+}
+
+/// Job as sent to the host.
+#[derive(Serialize)]
+struct Job<'a, T> {
+    /// Handler to run the job.
+    handler: &'a str,
+
+    /// Job payload.
+    payload: &'a T,
+
+    /// Job options.
+    #[serde(flatten)]
+    options: JobOptions,
+}
+
+/// Serialize a job for the host.
+fn job_json<T: Serialize>(handler: &str, payload: &T, options: JobOptions) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&Job {
+        handler,
+        payload,
+        options,
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_json() {
+        let options = JobOptions {
+            queue: Some("maintenance".to_owned()),
+            delay_seconds: 60,
+            max_attempts: None,
+        };
+        let job: serde_json::Value = serde_json::from_slice(&job_json("cleanup", &[1, 2], options).unwrap()).unwrap();
+        assert_eq!(
+            job,
+            serde_json::json!({
+                "handler": "cleanup",
+                "payload": [1, 2],
+                "queue": "maintenance",
+                "delay_seconds": 60,
+            })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Image processing.
+//!
+//! Images are decoded by the host, so plugins can generate previews without
+//! shipping image codecs in WASM. PNG, JPEG, GIF and WebP are supported;
+//! inputs over 32 MiB or 16384 pixels in width or height are rejected.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::media;
+//!
+//! let info = media::probe(&upload)?;
+//! log::info!("{}x{} {}", info.width, info.height, info.mime_type);
+//!
+//! // PNG fitting within 256x256, keeping the aspect ratio
+//! let preview = media::thumbnail(&upload, 256, 256)?;
+//! ```
+
+use super::error::Result;
+use serde::Deserialize;
+
+/// Information about an image.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ImageInfo {
+    /// Format name (e.g. `png`, `jpeg`)
+    pub format: String,
+
+    /// MIME type
+    pub mime_type: String,
+
+    /// Width in pixels
+    pub width: u32,
+
+    /// Height in pixels
+    pub height: u32,
+
+    /// Size of the encoded image, in bytes
+    pub size: usize,
+}
+
+/// Read the format and dimensions of an image without decoding it.
+///
+/// # Errors
+///
+/// Returns an error if the data is not a supported image or exceeds the limits.
+#[cfg(target_arch = "wasm32")]
+pub fn probe(data: &[u8]) -> Result<ImageInfo> {
+    let ptr = unsafe { super::ffi::media_probe(data.as_ptr() as i32, data.len() as i32) };
+    if ptr == 0 {
+        return Err(super::error::Error::invalid_input("Unsupported or invalid image"));
+    }
+
+    let info = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(serde_json::from_slice(&info)?)
+}
+
+/// Read image information (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn probe(data: &[u8]) -> Result<ImageInfo> {
+    Err(super::error::Error::invalid_input(format!(
+        "Image processing is only available in WASM ({} bytes)",
+        data.len()
+    )))
+}
+
+/// Create a PNG thumbnail fitting within `width` x `height`, keeping the aspect ratio.
+///
+/// Images smaller than the requested size are not enlarged. Both sizes must
+/// be between 1 and 2048 pixels.
+///
+/// # Errors
+///
+/// Returns an error if the data is not a supported image, exceeds the limits,
+/// or takes too long to process.
+#[cfg(target_arch = "wasm32")]
+pub fn thumbnail(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let ptr = unsafe {
+        super::ffi::media_thumbnail(data.as_ptr() as i32, data.len() as i32, width as i32, height as i32)
+    };
+    if ptr == 0 {
+        return Err(super::error::Error::invalid_input("Failed to create thumbnail"));
+    }
+
+    Ok(unsafe { super::ffi::read_length_prefixed(ptr) })
+}
+
+/// Create a thumbnail (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn thumbnail(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    Err(super::error::Error::invalid_input(format!(
+        "Image processing is only available in WASM ({} bytes, {}x{})",
+        data.len(),
+        width,
+        height
+    )))
+}
@@ -48,32 +48,65 @@
 //! - **Database access**: Query and execute SQL with typed results
 //! - **HTTP client**: Make external API calls
 //! - **Event system**: Emit and subscribe to events
+//! - **Response cache**: Invalidate cached route responses after writes
+//! - **Background jobs**: Enqueue persistent jobs with retries
+//! - **Email**: Send templated email through the host
+//! - **Host information**: Read declared environment variables and host metadata
+//! - **Files**: Read and write files within the manifest's filesystem grants
+//! - **Media**: Probe images and generate thumbnails host-side
+//! - **Data portability**: Export and import plugin data as an archive
+//! - **Services**: Share configuration and clients built once in `init` across handlers
+//! - **Feature flags**: Check flags declared in the manifest and toggled by administrators
+//! - **Groups**: Check the group membership of the user making the request
 //! - **Error handling**: Proper Result types with context
 
+pub mod cache;
 pub mod context;
+pub mod data;
 pub mod db;
+pub mod email;
 pub mod error;
+pub mod features;
 pub mod ffi;
+pub mod files;
+pub mod groups;
+pub mod host;
 pub mod http;
+pub mod jobs;
 pub mod log;
+pub mod media;
+pub mod pagination;
 pub mod response;
+pub mod services;
 pub mod state;
 
 // Re-export everything for convenience
 pub use context::Context;
-pub use db::{DbRow, DbValue};
-pub use error::{Error, Result};
+pub use db::{BatchQuery, DbRow, DbValue};
+pub use error::{Error, HandlerError, HandlerResult, Result};
+pub use pagination::Pagination;
 pub use response::Response;
 
 /// Prelude module for convenient imports
 pub mod prelude {
+    pub use super::cache;
     pub use super::context::Context;
-    pub use super::db::{self, DbRow, DbValue};
-    pub use super::error::{Error, Result};
+    pub use super::data;
+    pub use super::db::{self, BatchQuery, DbRow, DbValue};
+    pub use super::email;
+    pub use super::error::{Error, HandlerError, HandlerResult, Result};
+    pub use super::features;
     pub use super::ffi::*;
+    pub use super::files;
+    pub use super::groups;
+    pub use super::host;
     pub use super::http;
+    pub use super::jobs;
     pub use super::log;
+    pub use super::media;
+    pub use super::pagination::Pagination;
     pub use super::response::Response;
+    pub use super::services;
     pub use super::state;
 
     // Re-export serde for convenience
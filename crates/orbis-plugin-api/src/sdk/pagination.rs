@@ -0,0 +1,203 @@
+//! Pagination of list endpoints.
+//!
+//! Plugin list endpoints take the same query parameters as core list
+//! endpoints: `limit` items per page, and either a `page` number or the
+//! `cursor` returned as `next_cursor` with the previous page. Cursors are
+//! opaque to clients; a plugin encodes whatever identifies its position,
+//! such as the last ID returned.
+//!
+//! ```rust,ignore
+//! fn list_notes(ctx: Context) -> Result<Response> {
+//!     let page = ctx.pagination()?;
+//!     let after: i64 = page.cursor_as()?.unwrap_or(0);
+//!
+//!     let notes: Vec<Note> = db::query_as(
+//!         "SELECT * FROM notes WHERE id > ? ORDER BY id LIMIT ?",
+//!         &[&after, &page.limit],
+//!     )?;
+//!     let next = page.next_cursor(&notes, |note| note.id)?;
+//!     Response::paginated(&notes, next)
+//! }
+//! ```
+
+use super::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Items per page when the request does not say.
+pub const DEFAULT_LIMIT: u32 = 20;
+
+/// Most items a page may have.
+pub const MAX_LIMIT: u32 = 100;
+
+/// Pagination requested by a list request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pagination {
+    /// Items per page, between 1 and [`MAX_LIMIT`].
+    pub limit: u32,
+
+    /// Page number, starting at 1, for offset pagination.
+    pub page: u32,
+
+    /// Cursor returned with the previous page, for cursor pagination.
+    pub cursor: Option<String>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            page: 1,
+            cursor: None,
+        }
+    }
+}
+
+impl Pagination {
+    /// Read the `limit`, `page` and `cursor` query parameters.
+    ///
+    /// `per_page` is accepted in place of `limit`. Limits out of range are
+    /// clamped, as core endpoints do.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `limit` or `page` is not a number.
+    pub fn from_query(query: &HashMap<String, String>) -> Result<Self> {
+        let number = |name: &str| -> Result<Option<u32>> {
+            query
+                .get(name)
+                .map(|value| {
+                    value
+                        .parse::<u32>()
+                        .map_err(|_not_a_number| Error::invalid_input(format!("Query parameter '{}' must be a number", name)))
+                })
+                .transpose()
+        };
+
+        let limit = match number("limit")? {
+            Some(limit) => Some(limit),
+            None => number("per_page")?,
+        };
+
+        Ok(Self {
+            limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            page: number("page")?.unwrap_or(1).max(1),
+            cursor: query.get("cursor").filter(|cursor| !cursor.is_empty()).cloned(),
+        })
+    }
+
+    /// Get the number of items before the requested page, for offset pagination.
+    #[must_use]
+    pub const fn offset(&self) -> u32 {
+        self.page.saturating_sub(1).saturating_mul(self.limit)
+    }
+
+    /// Decode the cursor into the position it was created from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cursor was not created by [`encode_cursor`]
+    /// with the same type.
+    pub fn cursor_as<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        self.cursor.as_deref().map(decode_cursor).transpose()
+    }
+
+    /// Get the cursor of the page after a full page of items.
+    ///
+    /// Returns `None` when the page has fewer items than the limit, as
+    /// there is nothing after it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the position cannot be serialized.
+    pub fn next_cursor<T, P, F>(&self, items: &[T], position: F) -> Result<Option<String>>
+    where
+        P: Serialize,
+        F: FnOnce(&T) -> P,
+    {
+        let full = u32::try_from(items.len()).map_or(true, |len| len >= self.limit);
+        match items.last() {
+            Some(last) if full => encode_cursor(&position(last)).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Encode a position as an opaque cursor.
+///
+/// # Errors
+///
+/// Returns an error if the position cannot be serialized.
+pub fn encode_cursor<T: Serialize>(position: &T) -> Result<String> {
+    let json = serde_json::to_vec(position)?;
+    Ok(json
+        .iter()
+        .flat_map(|&byte| [byte >> 4, byte & 0xf])
+        .filter_map(|digit| char::from_digit(u32::from(digit), 16))
+        .collect())
+}
+
+/// Decode a cursor created by [`encode_cursor`].
+///
+/// # Errors
+///
+/// Returns an error if the cursor is malformed or does not hold a `T`.
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Result<T> {
+    let invalid = || Error::invalid_input("Invalid pagination cursor");
+
+    let json = (0..cursor.len())
+        .step_by(2)
+        .map(|i| {
+            cursor
+                .get(i..i.saturating_add(2))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    serde_json::from_slice(&json).map_err(|_wrong_type| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|&(k, v)| (k.to_owned(), v.to_owned())).collect()
+    }
+
+    #[test]
+    fn test_from_query() {
+        assert_eq!(Pagination::from_query(&HashMap::new()).unwrap(), Pagination::default());
+
+        let page = Pagination::from_query(&query(&[("limit", "500"), ("page", "0"), ("cursor", "")])).unwrap();
+        assert_eq!((page.limit, page.page, page.cursor), (MAX_LIMIT, 1, None));
+
+        let page = Pagination::from_query(&query(&[("per_page", "10"), ("page", "3")])).unwrap();
+        assert_eq!((page.limit, page.offset()), (10, 20));
+
+        assert!(Pagination::from_query(&query(&[("limit", "ten")])).is_err());
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let page = Pagination {
+            limit: 2,
+            ..Pagination::default()
+        };
+        let next = page.next_cursor(&[(1, "a"), (2, "b")], |item| item.0).unwrap();
+        assert!(page.next_cursor(&[(3, "c")], |item| item.0).unwrap().is_none());
+
+        let following = Pagination {
+            cursor: next,
+            ..page
+        };
+        assert_eq!(following.cursor_as::<i64>().unwrap(), Some(2));
+
+        let tampered = Pagination {
+            cursor: Some("zz".to_owned()),
+            ..Pagination::default()
+        };
+        assert!(tampered.cursor_as::<i64>().is_err());
+    }
+}
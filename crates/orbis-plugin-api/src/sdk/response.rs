@@ -1,6 +1,6 @@
 //! Response builder for plugin handlers.
 
-use super::error::{Error, Result};
+use super::error::{Error, HandlerError, Result, PROBLEM_CONTENT_TYPE};
 use serde::Serialize;
 use std::collections::HashMap;
 
@@ -16,6 +16,10 @@ pub struct Response {
 
     /// Response body
     pub body: serde_json::Value,
+
+    /// Whether the host may replay this response for retries with the same idempotency key
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
 }
 
 impl Response {
@@ -26,6 +30,7 @@ impl Response {
             status,
             headers: HashMap::new(),
             body,
+            idempotent: false,
         }
     }
 
@@ -43,6 +48,7 @@ impl Response {
             status: 200,
             headers: HashMap::new(),
             body,
+            idempotent: false,
         }
     }
 
@@ -53,6 +59,19 @@ impl Response {
         Ok(Self::new(201, body))
     }
 
+    /// Create a 200 OK response with one page of a cursor-paginated list
+    ///
+    /// The body is `{"items": [...], "next_cursor": ..., "has_more": ...}`;
+    /// see [`Pagination::next_cursor`](super::pagination::Pagination::next_cursor).
+    pub fn paginated<T: Serialize>(items: &[T], next_cursor: Option<String>) -> Result<Self> {
+        let items = serde_json::to_value(items)?;
+        Ok(Self::ok(serde_json::json!({
+            "items": items,
+            "has_more": next_cursor.is_some(),
+            "next_cursor": next_cursor,
+        })))
+    }
+
     /// Create a 204 No Content response
     #[inline]
     pub fn no_content() -> Self {
@@ -60,6 +79,7 @@ impl Response {
             status: 204,
             headers: HashMap::new(),
             body: serde_json::Value::Null,
+            idempotent: false,
         }
     }
 
@@ -108,7 +128,13 @@ impl Response {
     /// Create a response from an SDK Error
     #[inline]
     pub fn from_error(err: &Error) -> Self {
-        Self::error(err.status_code(), &err.to_string())
+        Self::problem(&HandlerError::from(err))
+    }
+
+    /// Create an RFC 7807 problem response from a handler error
+    #[inline]
+    pub fn problem(err: &HandlerError) -> Self {
+        Self::new(err.status, err.problem()).content_type(PROBLEM_CONTENT_TYPE)
     }
 
     /// Add a header to the response
@@ -136,6 +162,45 @@ impl Response {
         self.cache_control("no-store, no-cache, must-revalidate")
     }
 
+    /// Set the ETag of a `GET` response
+    ///
+    /// The host answers requests whose `If-None-Match` matches it with
+    /// `304 Not Modified`. Unquoted tags are quoted; prefix a quoted tag with
+    /// `W/` for a weak ETag. Without one, the host uses a weak ETag hashed
+    /// from the response body.
+    #[inline]
+    pub fn etag(self, tag: &str) -> Self {
+        if tag.starts_with('"') || tag.starts_with("W/") {
+            self.with_header("ETag", tag)
+        } else {
+            self.with_header("ETag", format!("\"{}\"", tag))
+        }
+    }
+
+    /// Set the Last-Modified header of a `GET` response, as an HTTP date
+    /// (`Wed, 21 Oct 2015 07:28:00 GMT`)
+    ///
+    /// The host answers requests whose `If-Modified-Since` is not older with
+    /// `304 Not Modified`, unless they also send `If-None-Match`.
+    #[inline]
+    pub fn last_modified(self, http_date: &str) -> Self {
+        self.with_header("Last-Modified", http_date)
+    }
+
+    /// Mark the response of a mutating request as safe to replay
+    ///
+    /// When the request has an idempotency key (see
+    /// [`Context::idempotency_key`](super::context::Context::idempotency_key)),
+    /// the host keeps this response and returns it for retries with the same
+    /// key, without calling the handler again. Only mark responses of
+    /// requests that completed their changes.
+    #[inline]
+    #[must_use]
+    pub const fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
     /// Serialize response to raw FFI pointer for returning to host
     #[cfg(target_arch = "wasm32")]
     pub fn to_raw(&self) -> Result<i32> {
@@ -156,6 +221,12 @@ impl From<Error> for Response {
     }
 }
 
+impl From<HandlerError> for Response {
+    fn from(err: HandlerError) -> Self {
+        Self::problem(&err)
+    }
+}
+
 /// Builder for paginated responses
 #[derive(Debug, Clone, Serialize)]
 pub struct PaginatedResponse<T> {
@@ -224,6 +295,36 @@ mod tests {
         assert_eq!(resp.body["name"], "Test");
     }
 
+    #[test]
+    fn test_response_problem() {
+        let resp = Response::from(Error::not_found("User not found"));
+
+        assert_eq!(resp.status, 404);
+        assert_eq!(resp.headers.get("Content-Type").map(String::as_str), Some(PROBLEM_CONTENT_TYPE));
+        assert_eq!(resp.body["code"], "NOT_FOUND");
+        assert_eq!(resp.body["detail"], "Not found: User not found");
+    }
+
+    #[test]
+    fn test_response_idempotent() {
+        let resp = Response::json(&serde_json::json!({"id": 1})).unwrap();
+        assert!(serde_json::to_value(&resp).unwrap().get("idempotent").is_none());
+
+        let resp = resp.idempotent();
+        assert_eq!(serde_json::to_value(&resp).unwrap()["idempotent"], true);
+    }
+
+    #[test]
+    fn test_response_paginated() {
+        let resp = Response::paginated(&[1, 2], Some("32".to_owned())).unwrap();
+        assert_eq!(resp.body["items"], serde_json::json!([1, 2]));
+        assert_eq!(resp.body["next_cursor"], "32");
+        assert_eq!(resp.body["has_more"], true);
+
+        let last = Response::paginated::<i32>(&[], None).unwrap();
+        assert_eq!(last.body["has_more"], false);
+    }
+
     #[test]
     fn test_response_error() {
         let resp = Response::not_found("User not found");
@@ -233,6 +334,15 @@ mod tests {
         assert_eq!(resp.body["message"], "User not found");
     }
 
+    #[test]
+    fn test_response_etag() {
+        let resp = Response::ok(serde_json::json!([])).etag("v42");
+        assert_eq!(resp.headers["ETag"], "\"v42\"");
+
+        let resp = Response::ok(serde_json::json!([])).etag("W/\"v42\"");
+        assert_eq!(resp.headers["ETag"], "W/\"v42\"");
+    }
+
     #[test]
     fn test_paginated_response() {
         let items = vec![1, 2, 3];
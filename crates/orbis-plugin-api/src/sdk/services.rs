@@ -0,0 +1,143 @@
+//! Shared services for handlers.
+//!
+//! Services are looked up by type. Those provided in the plugin's `init`
+//! live as long as the plugin instance, so parsed configuration and clients
+//! are built once instead of in every handler call. Services provided while
+//! handling a request are dropped when the handler returns.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use orbis_plugin_api::sdk::services;
+//!
+//! orbis_plugin! {
+//!     init: || {
+//!         services::provide(Settings::load()?);
+//!         Ok(())
+//!     },
+//! }
+//!
+//! fn list_notes(ctx: Context) -> Result<Response> {
+//!     let settings = services::require::<Settings>()?;
+//!
+//!     // Built on first use, then shared for the rest of the request
+//!     let user = services::scoped(|| Account::load(&ctx))?;
+//!     ...
+//! }
+//! ```
+
+use super::error::{Error, Result};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Services keyed by their type.
+type ServiceMap = HashMap<TypeId, Rc<dyn Any>>;
+
+thread_local! {
+    /// Services living as long as the plugin instance.
+    static SHARED: RefCell<ServiceMap> = RefCell::new(HashMap::new());
+
+    /// Services of the request being handled.
+    static SCOPED: RefCell<ServiceMap> = RefCell::new(HashMap::new());
+}
+
+/// Provide a service for all requests, replacing any of the same type.
+///
+/// Call this from the plugin's `init`.
+pub fn provide<T: 'static>(service: T) {
+    SHARED.with(|shared| {
+        shared.borrow_mut().insert(TypeId::of::<T>(), Rc::new(service));
+    });
+}
+
+/// Provide a service for the rest of the request being handled.
+///
+/// Request services take precedence over services of the same type
+/// provided for all requests.
+pub fn provide_scoped<T: 'static>(service: T) {
+    SCOPED.with(|scoped| {
+        scoped.borrow_mut().insert(TypeId::of::<T>(), Rc::new(service));
+    });
+}
+
+/// Get a service, if one was provided.
+#[must_use]
+pub fn get<T: 'static>() -> Option<Rc<T>> {
+    let id = TypeId::of::<T>();
+    SCOPED
+        .with(|scoped| scoped.borrow().get(&id).cloned())
+        .or_else(|| SHARED.with(|shared| shared.borrow().get(&id).cloned()))
+        .and_then(|service| service.downcast::<T>().ok())
+}
+
+/// Get a service that must have been provided.
+///
+/// # Errors
+///
+/// Returns an error if no service of this type was provided.
+pub fn require<T: 'static>() -> Result<Rc<T>> {
+    get().ok_or_else(|| Error::internal(format!("Service {} was not provided", std::any::type_name::<T>())))
+}
+
+/// Get a service of the request being handled, creating it on first use.
+///
+/// # Errors
+///
+/// Returns an error if creating the service fails; the next call tries again.
+pub fn scoped<T: 'static, F: FnOnce() -> Result<T>>(init: F) -> Result<Rc<T>> {
+    if let Some(service) = get() {
+        return Ok(service);
+    }
+
+    let service = Rc::new(init()?);
+    SCOPED.with(|scoped| {
+        scoped.borrow_mut().insert(TypeId::of::<T>(), Rc::clone(&service) as Rc<dyn Any>);
+    });
+    Ok(service)
+}
+
+/// Drop the services of the request that was handled.
+///
+/// Called by handlers wrapped with `wrap_handler!` when they return.
+#[doc(hidden)]
+pub fn end_request() {
+    // Dropped outside the borrow, as services may look up others when dropped
+    let services = SCOPED.with(|scoped| std::mem::take(&mut *scoped.borrow_mut()));
+    drop(services);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Settings(&'static str);
+
+    #[test]
+    fn test_shared_and_scoped_services() {
+        assert!(require::<Settings>().is_err());
+
+        provide(Settings("shared"));
+        assert_eq!(*require::<Settings>().unwrap(), Settings("shared"));
+
+        provide_scoped(Settings("request"));
+        assert_eq!(*require::<Settings>().unwrap(), Settings("request"));
+
+        let mut calls = 0;
+        for _ in 0..2 {
+            let count = scoped(|| {
+                calls += 1;
+                Ok(7_u32)
+            })
+            .unwrap();
+            assert_eq!(*count, 7);
+        }
+        assert_eq!(calls, 1);
+
+        end_request();
+        assert_eq!(*require::<Settings>().unwrap(), Settings("shared"));
+        assert!(get::<u32>().is_none());
+    }
+}
@@ -15,21 +15,74 @@
 //!
 //! // Remove a value
 //! state::remove("counter")?;
+//!
+//! // Buffer writes and send them to the host in one call
+//! state::set_buffered("visits", &10)?;
+//! state::set_buffered("last_visit", &"2024-01-01")?;
+//! state::flush()?; // also done automatically when the handler returns
+//! ```
+//!
+//! # Migrations
+//!
+//! Stored state is versioned by the manifest's `state_version`. When it is
+//! raised, the host runs an exported handler for each step, such as
+//! `migrate_state_v1_to_v2`, before the new version of the plugin starts:
+//!
+//! ```rust,ignore
+//! // manifest.json: "state_version": 2
+//! wrap_handler!(migrate_state_v1_to_v2, migrate_v1_to_v2);
+//!
+//! fn migrate_v1_to_v2(_ctx: Context) -> Result<Response> {
+//!     // v1 stored a bare count, v2 stores it with the time of the last visit
+//!     let count: i64 = state::get("visits")?.unwrap_or(0);
+//!     state::set("visits", &json!({ "count": count, "last": null }))?;
+//!     Response::json(&json!({}))
+//! }
 //! ```
 
 #[allow(unused_imports)]
 use super::error::{Error, Result};
+use crate::runtime::StateWrite;
 use serde::{de::DeserializeOwned, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+thread_local! {
+    /// Writes buffered by `set_buffered`/`remove_buffered`.
+    static PENDING: RefCell<BTreeMap<String, StateWrite>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Get a buffered write for a key, if any.
+fn pending(key: &str) -> Option<StateWrite> {
+    PENDING.with(|pending| pending.borrow().get(key).cloned())
+}
+
+/// Drop a buffered write for a key that is being written directly.
+fn discard_pending(key: &str) {
+    PENDING.with(|pending| {
+        pending.borrow_mut().remove(key);
+    });
+}
 
 /// Get a value from plugin state.
 ///
 /// Returns `None` if the key doesn't exist, or `Some(value)` if it does.
+/// Buffered writes that have not been flushed yet are visible.
 ///
 /// # Errors
 ///
 /// Returns an error if deserialization fails.
-#[cfg(target_arch = "wasm32")]
 pub fn get<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    match pending(key) {
+        Some(StateWrite::Set(value)) => Ok(Some(serde_json::from_value(value)?)),
+        Some(StateWrite::Remove) => Ok(None),
+        None => fetch(key),
+    }
+}
+
+/// Get a value from the host
+#[cfg(target_arch = "wasm32")]
+fn fetch<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
     let ptr = unsafe {
         super::ffi::state_get(key.as_ptr() as i32, key.len() as i32)
     };
@@ -43,12 +96,69 @@ pub fn get<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
     Ok(Some(value))
 }
 
-/// Get a value from plugin state (non-WASM stub)
+/// Get a value from the host (non-WASM stub)
 #[cfg(not(target_arch = "wasm32"))]
-pub fn get<T: DeserializeOwned>(_key: &str) -> Result<Option<T>> {
+const fn fetch<T: DeserializeOwned>(_key: &str) -> Result<Option<T>> {
     Ok(None)
 }
 
+/// Get several values from plugin state in a single host call.
+///
+/// Keys that don't exist are omitted from the result.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let settings: HashMap<String, String> = state::get_many(&["theme", "locale"])?;
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if deserialization fails or the host rejects the operation.
+pub fn get_many<T: DeserializeOwned>(keys: &[&str]) -> Result<HashMap<String, T>> {
+    let mut values = HashMap::with_capacity(keys.len());
+    let mut missing = Vec::new();
+
+    for key in keys {
+        match pending(key) {
+            Some(StateWrite::Set(value)) => {
+                values.insert((*key).to_owned(), serde_json::from_value(value)?);
+            }
+            Some(StateWrite::Remove) => {}
+            None => missing.push(*key),
+        }
+    }
+
+    if !missing.is_empty() {
+        values.extend(fetch_many(&missing)?);
+    }
+
+    Ok(values)
+}
+
+/// Get several values from the host
+#[cfg(target_arch = "wasm32")]
+fn fetch_many<T: DeserializeOwned>(keys: &[&str]) -> Result<HashMap<String, T>> {
+    let keys_json = serde_json::to_vec(keys)?;
+
+    let ptr = unsafe {
+        super::ffi::state_get_many(keys_json.as_ptr() as i32, keys_json.len() as i32)
+    };
+
+    if ptr == 0 {
+        return Err(Error::from_host(Error::state("Failed to get state keys")));
+    }
+
+    let bytes = unsafe { super::ffi::read_length_prefixed(ptr) };
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Get several values from the host (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_many<T: DeserializeOwned>(_keys: &[&str]) -> Result<HashMap<String, T>> {
+    Ok(HashMap::new())
+}
+
 /// Get a value or return a default.
 ///
 /// # Example
@@ -75,6 +185,7 @@ pub fn get_or_else<T: DeserializeOwned, F: FnOnce() -> T>(key: &str, f: F) -> Re
 #[cfg(target_arch = "wasm32")]
 pub fn set<T: Serialize>(key: &str, value: &T) -> Result<()> {
     let value_json = serde_json::to_vec(value)?;
+    discard_pending(key);
 
     let result = unsafe {
         super::ffi::state_set(
@@ -88,13 +199,14 @@ pub fn set<T: Serialize>(key: &str, value: &T) -> Result<()> {
     if result == 1 {
         Ok(())
     } else {
-        Err(Error::state(format!("Failed to set state key: {}", key)))
+        Err(Error::from_host(Error::state(format!("Failed to set state key: {}", key))))
     }
 }
 
 /// Set a value in plugin state (non-WASM stub)
 #[cfg(not(target_arch = "wasm32"))]
-pub fn set<T: Serialize>(_key: &str, _value: &T) -> Result<()> {
+pub fn set<T: Serialize>(key: &str, _value: &T) -> Result<()> {
+    discard_pending(key);
     Ok(())
 }
 
@@ -105,6 +217,7 @@ pub fn set<T: Serialize>(_key: &str, _value: &T) -> Result<()> {
 /// Returns an error if the host rejects the operation.
 #[cfg(target_arch = "wasm32")]
 pub fn remove(key: &str) -> Result<()> {
+    discard_pending(key);
     let result = unsafe {
         super::ffi::state_remove(key.as_ptr() as i32, key.len() as i32)
     };
@@ -112,13 +225,81 @@ pub fn remove(key: &str) -> Result<()> {
     if result == 1 {
         Ok(())
     } else {
-        Err(Error::state(format!("Failed to remove state key: {}", key)))
+        Err(Error::from_host(Error::state(format!("Failed to remove state key: {}", key))))
     }
 }
 
 /// Remove a value from plugin state (non-WASM stub)
 #[cfg(not(target_arch = "wasm32"))]
-pub fn remove(_key: &str) -> Result<()> {
+pub fn remove(key: &str) -> Result<()> {
+    discard_pending(key);
+    Ok(())
+}
+
+/// Buffer a write to plugin state.
+///
+/// Buffered writes are visible to `get` immediately and sent to the host in
+/// a single call by [`flush`], which runs automatically when the handler
+/// returns. Use this for handlers that update many keys.
+///
+/// # Errors
+///
+/// Returns an error if serialization fails.
+pub fn set_buffered<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    let value = serde_json::to_value(value)?;
+    PENDING.with(|pending| {
+        pending.borrow_mut().insert(key.to_owned(), StateWrite::Set(value));
+    });
+    Ok(())
+}
+
+/// Buffer a removal from plugin state.
+///
+/// See [`set_buffered`].
+pub fn remove_buffered(key: &str) {
+    PENDING.with(|pending| {
+        pending.borrow_mut().insert(key.to_owned(), StateWrite::Remove);
+    });
+}
+
+/// Drop buffered writes without sending them to the host.
+pub(crate) fn discard_buffered() {
+    PENDING.with(|pending| pending.borrow_mut().clear());
+}
+
+/// Send buffered writes to the host in a single call.
+///
+/// # Errors
+///
+/// Returns an error if the host rejects the writes; they are dropped either way.
+pub fn flush() -> Result<()> {
+    let entries = PENDING.with(|pending| std::mem::take(&mut *pending.borrow_mut()));
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    write_many(&entries)
+}
+
+/// Write several entries to the host
+#[cfg(target_arch = "wasm32")]
+fn write_many(entries: &BTreeMap<String, StateWrite>) -> Result<()> {
+    let entries_json = serde_json::to_vec(entries)?;
+
+    let result = unsafe {
+        super::ffi::state_set_many(entries_json.as_ptr() as i32, entries_json.len() as i32)
+    };
+
+    if result == 1 {
+        Ok(())
+    } else {
+        Err(Error::from_host(Error::state(format!("Failed to write {} state keys", entries.len()))))
+    }
+}
+
+/// Write several entries to the host (non-WASM stub)
+#[cfg(not(target_arch = "wasm32"))]
+const fn write_many(_entries: &BTreeMap<String, StateWrite>) -> Result<()> {
     Ok(())
 }
 
@@ -184,20 +365,38 @@ pub fn len(key: &str) -> Result<usize> {
 }
 
 /// Check if a key exists in state.
-#[cfg(target_arch = "wasm32")]
 pub fn exists(key: &str) -> bool {
+    pending(key).map_or_else(|| exists_on_host(key), |write| matches!(write, StateWrite::Set(_)))
+}
+
+/// Check if a key exists on the host
+#[cfg(target_arch = "wasm32")]
+fn exists_on_host(key: &str) -> bool {
     let ptr = unsafe {
         super::ffi::state_get(key.as_ptr() as i32, key.len() as i32)
     };
     ptr != 0
 }
 
-/// Check if a key exists in state (non-WASM stub)
+/// Check if a key exists on the host (non-WASM stub)
 #[cfg(not(target_arch = "wasm32"))]
-pub fn exists(_key: &str) -> bool {
+const fn exists_on_host(_key: &str) -> bool {
     false
 }
 
+/// Get the schema version of the plugin's stored state.
+///
+/// This is the manifest's `state_version` once migrations have run, and the
+/// version being migrated from inside a migration handler. State stored
+/// before the plugin declared a version is at version 1.
+///
+/// # Errors
+///
+/// Returns an error if the stored version is malformed.
+pub fn version() -> Result<u32> {
+    Ok(get(crate::manifest::STATE_VERSION_KEY)?.unwrap_or(1))
+}
+
 /// Scoped state access with a prefix.
 ///
 /// Useful for organizing state by feature or entity.
@@ -255,3 +454,45 @@ impl ScopedState {
 pub fn scoped(prefix: impl Into<String>) -> ScopedState {
     ScopedState::new(prefix)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffered_writes() {
+        set_buffered("count", &3).unwrap();
+        set_buffered("name", &"orbis").unwrap();
+        set_buffered("cleared", &None::<i32>).unwrap();
+        remove_buffered("gone");
+
+        assert_eq!(get::<i32>("count").unwrap(), Some(3));
+        assert!(exists("name"));
+        assert!(!exists("gone"));
+
+        // A buffered null is a value, not a removal
+        assert!(exists("cleared"));
+        assert_eq!(pending("cleared"), Some(StateWrite::Set(serde_json::Value::Null)));
+
+        let values: HashMap<String, serde_json::Value> = get_many(&["count", "name", "cleared", "gone"]).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get("cleared"), Some(&serde_json::Value::Null));
+
+        // Direct writes replace buffered ones
+        set("count", &4).unwrap();
+        assert!(pending("count").is_none());
+
+        flush().unwrap();
+        assert!(pending("name").is_none());
+        assert_eq!(get::<String>("name").unwrap(), None);
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(version().unwrap(), 1);
+
+        set_buffered(crate::manifest::STATE_VERSION_KEY, &3).unwrap();
+        assert_eq!(version().unwrap(), 3);
+        discard_buffered();
+    }
+}
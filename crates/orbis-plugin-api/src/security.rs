@@ -0,0 +1,399 @@
+//! Host call authorization.
+//!
+//! Every host function a plugin calls is checked by the [`PolicyEngine`]:
+//! the plugin must hold the permission the function requires, and the call
+//! must not be denied by the tenant's policy overrides or the global policy
+//! set by the administrator. Denied calls fail with a [`PolicyDenial`] the
+//! plugin can read through the SDK.
+//!
+//! In [`PolicyMode::Audit`], calls that would be denied are allowed and only
+//! reported, so a new policy can be tried without breaking plugins.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::PluginPermission;
+
+/// Number of decisions cached before the cache is cleared.
+const MAX_CACHED_DECISIONS: usize = 4096;
+
+/// Get the permission a host function requires, if any.
+#[must_use]
+pub fn required_permission(function: &str) -> Option<PluginPermission> {
+    match function {
+        "db_query" | "db_query_batch" => Some(PluginPermission::DatabaseRead),
+        "db_execute" => Some(PluginPermission::DatabaseWrite),
+        "file_read" | "file_list" | "file_url" => Some(PluginPermission::FileRead),
+        "file_write" | "file_remove" => Some(PluginPermission::FileWrite),
+        "http_request" => Some(PluginPermission::Network),
+        "email_send" => Some(PluginPermission::Email),
+        "host_info" => Some(PluginPermission::System),
+        "host_env" => Some(PluginPermission::Environment),
+        "emit_event" => Some(PluginPermission::Custom("events:emit".to_owned())),
+        _ => None,
+    }
+}
+
+/// Whether policy denials are enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyMode {
+    /// Denied calls fail.
+    #[default]
+    Enforce,
+
+    /// Denied calls are reported but allowed (dry run).
+    Audit,
+}
+
+/// Effect of a matching policy rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    /// Allow the call.
+    Allow,
+
+    /// Deny the call.
+    Deny,
+}
+
+/// Policy rule; unset fields match any call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Plugin name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+
+    /// Host function name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+
+    /// Permission the host function requires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission: Option<PluginPermission>,
+
+    /// Effect when the rule matches.
+    pub effect: PolicyEffect,
+}
+
+impl PolicyRule {
+    /// Check if the rule matches a call.
+    #[must_use]
+    pub fn matches(&self, call: &HostCall<'_>) -> bool {
+        self.plugin.as_deref().is_none_or(|plugin| plugin == call.plugin)
+            && self.function.as_deref().is_none_or(|function| function == call.function)
+            && self
+                .permission
+                .as_ref()
+                .is_none_or(|permission| call.permission.as_ref() == Some(permission))
+    }
+}
+
+/// Host call authorization policy.
+///
+/// Tenant rules are checked first, then the global rules; the first
+/// matching rule decides. Calls matching no rule are allowed. Rules never
+/// grant a permission the plugin's manifest does not request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessPolicy {
+    /// Whether denials are enforced.
+    #[serde(default)]
+    pub mode: PolicyMode,
+
+    /// Global rules set by the administrator.
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+
+    /// Rules overriding the global rules for calls scoped to a tenant.
+    #[serde(default)]
+    pub tenants: HashMap<String, Vec<PolicyRule>>,
+}
+
+/// A host function call to authorize.
+#[derive(Debug, Clone)]
+pub struct HostCall<'a> {
+    /// Calling plugin.
+    pub plugin: &'a str,
+
+    /// Host function called.
+    pub function: &'a str,
+
+    /// Permission the host function requires.
+    pub permission: Option<PluginPermission>,
+
+    /// Tenant the call is scoped to.
+    pub tenant: Option<&'a str>,
+}
+
+impl<'a> HostCall<'a> {
+    /// Describe a call, looking up the permission the function requires.
+    #[must_use]
+    pub fn new(plugin: &'a str, function: &'a str, tenant: Option<&'a str>) -> Self {
+        Self {
+            plugin,
+            function,
+            permission: required_permission(function),
+            tenant,
+        }
+    }
+}
+
+/// Why a host call was denied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DenyReason {
+    /// The plugin's manifest does not request the required permission.
+    MissingPermission {
+        /// Required permission.
+        permission: PluginPermission,
+    },
+
+    /// A global policy rule denies the call.
+    AdminPolicy {
+        /// Index of the rule in the global rules.
+        rule: usize,
+    },
+
+    /// A policy rule of the tenant denies the call.
+    TenantPolicy {
+        /// Tenant ID.
+        tenant: String,
+
+        /// Index of the rule in the tenant's rules.
+        rule: usize,
+    },
+}
+
+impl fmt::Display for DenyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingPermission { ref permission } => write!(f, "missing permission {:?}", permission),
+            Self::AdminPolicy { rule } => write!(f, "denied by policy rule {}", rule),
+            Self::TenantPolicy { ref tenant, rule } => write!(f, "denied by rule {} of tenant '{}'", rule, tenant),
+        }
+    }
+}
+
+/// A denied host call, as reported to the plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyDenial {
+    /// Host function called.
+    pub function: String,
+
+    /// Why the call was denied.
+    #[serde(flatten)]
+    pub reason: DenyReason,
+}
+
+impl fmt::Display for PolicyDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Host call '{}' {}", self.function, self.reason)
+    }
+}
+
+/// Decision on a host call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    /// Why the call is denied, if it is.
+    pub denial: Option<DenyReason>,
+
+    /// Whether a denial is enforced (false in audit mode).
+    pub enforced: bool,
+}
+
+impl PolicyDecision {
+    /// Check if the call may proceed.
+    #[must_use]
+    pub const fn is_allowed(&self) -> bool {
+        self.denial.is_none() || !self.enforced
+    }
+}
+
+/// Cache key of a decision: plugin, function and tenant.
+type DecisionKey = (String, String, Option<String>);
+
+/// Evaluates host calls against plugin permissions and the security policy.
+#[derive(Debug, Default)]
+pub struct PolicyEngine {
+    /// Current policy.
+    policy: RwLock<AccessPolicy>,
+
+    /// Decisions by plugin, function and tenant.
+    cache: Mutex<HashMap<DecisionKey, Option<DenyReason>>>,
+}
+
+impl PolicyEngine {
+    /// Create an engine with a policy.
+    #[must_use]
+    pub fn new(policy: AccessPolicy) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Get the current policy.
+    #[must_use]
+    pub fn policy(&self) -> AccessPolicy {
+        self.policy.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Replace the policy, discarding cached decisions.
+    pub fn set_policy(&self, policy: AccessPolicy) {
+        *self.policy.write().unwrap_or_else(PoisonError::into_inner) = policy;
+        self.clear_cache();
+    }
+
+    /// Discard cached decisions of a plugin, e.g. after its permissions changed.
+    pub fn clear_plugin(&self, plugin: &str) {
+        self.cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|key, _| key.0 != plugin);
+    }
+
+    /// Discard all cached decisions.
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    /// Decide on a host call of a plugin granted the given permissions.
+    ///
+    /// Decisions are cached per plugin, function and tenant, so the granted
+    /// permissions of a plugin must not change without calling
+    /// [`Self::clear_plugin`].
+    pub fn evaluate(&self, call: &HostCall<'_>, granted: &[PluginPermission]) -> PolicyDecision {
+        let key = (
+            call.plugin.to_owned(),
+            call.function.to_owned(),
+            call.tenant.map(str::to_owned),
+        );
+
+        // The policy stays locked until the decision is cached, so a policy
+        // change cannot be overwritten by a decision made under the old one
+        let policy = self.policy.read().unwrap_or_else(PoisonError::into_inner);
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if cache.len() >= MAX_CACHED_DECISIONS && !cache.contains_key(&key) {
+            cache.clear();
+        }
+        let denial = cache
+            .entry(key)
+            .or_insert_with(|| decide(&policy, call, granted))
+            .clone();
+        drop(cache);
+
+        let enforced = policy.mode == PolicyMode::Enforce;
+        drop(policy);
+        PolicyDecision { denial, enforced }
+    }
+}
+
+/// Decide on a host call without the cache.
+fn decide(policy: &AccessPolicy, call: &HostCall<'_>, granted: &[PluginPermission]) -> Option<DenyReason> {
+    if let Some(permission) = call.permission.as_ref()
+        && !granted.contains(permission)
+    {
+        return Some(DenyReason::MissingPermission {
+            permission: permission.clone(),
+        });
+    }
+
+    if let Some(tenant) = call.tenant
+        && let Some((rule, effect)) = first_match(policy.tenants.get(tenant).map_or(&[], Vec::as_slice), call)
+    {
+        return (effect == PolicyEffect::Deny).then(|| DenyReason::TenantPolicy {
+            tenant: tenant.to_owned(),
+            rule,
+        });
+    }
+
+    first_match(&policy.rules, call)
+        .filter(|matched| matched.1 == PolicyEffect::Deny)
+        .map(|(rule, _)| DenyReason::AdminPolicy { rule })
+}
+
+/// Find the first rule matching a call, with its index.
+fn first_match(rules: &[PolicyRule], call: &HostCall<'_>) -> Option<(usize, PolicyEffect)> {
+    rules
+        .iter()
+        .enumerate()
+        .find(|indexed| indexed.1.matches(call))
+        .map(|(index, rule)| (index, rule.effect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> AccessPolicy {
+        serde_json::from_value(json!({
+            "rules": [
+                { "plugin": "exporter", "function": "http_request", "effect": "deny" },
+                { "permission": "email", "effect": "deny" }
+            ],
+            "tenants": {
+                "acme": [{ "permission": "email", "effect": "allow" }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decisions() {
+        let engine = PolicyEngine::new(policy());
+        let granted = [PluginPermission::Network, PluginPermission::Email];
+
+        let missing = engine.evaluate(&HostCall::new("exporter", "db_query", None), &granted);
+        assert_eq!(
+            missing.denial,
+            Some(DenyReason::MissingPermission {
+                permission: PluginPermission::DatabaseRead
+            })
+        );
+        assert!(!missing.is_allowed());
+
+        let admin = engine.evaluate(&HostCall::new("exporter", "http_request", None), &granted);
+        assert_eq!(admin.denial, Some(DenyReason::AdminPolicy { rule: 0 }));
+        assert!(engine.evaluate(&HostCall::new("reports", "http_request", None), &granted).is_allowed());
+
+        // The tenant overrides the global email ban
+        assert_eq!(
+            engine.evaluate(&HostCall::new("reports", "email_send", None), &granted).denial,
+            Some(DenyReason::AdminPolicy { rule: 1 })
+        );
+        assert!(engine.evaluate(&HostCall::new("reports", "email_send", Some("acme")), &granted).is_allowed());
+
+        // Functions without a required permission are allowed by default
+        assert!(engine.evaluate(&HostCall::new("reports", "state_get", None), &[]).is_allowed());
+    }
+
+    #[test]
+    fn test_audit_mode_and_cache() {
+        let engine = PolicyEngine::new(policy());
+        let call = HostCall::new("exporter", "http_request", None);
+
+        assert!(!engine.evaluate(&call, &[PluginPermission::Network]).is_allowed());
+
+        // Changing the policy discards cached decisions
+        engine.set_policy(AccessPolicy {
+            mode: PolicyMode::Audit,
+            ..policy()
+        });
+        let audited = engine.evaluate(&call, &[PluginPermission::Network]);
+        assert!(audited.is_allowed());
+        assert_eq!(audited.denial, Some(DenyReason::AdminPolicy { rule: 0 }));
+
+        let denial = PolicyDenial {
+            function: "http_request".to_string(),
+            reason: DenyReason::AdminPolicy { rule: 0 },
+        };
+        assert_eq!(
+            serde_json::to_value(&denial).unwrap(),
+            json!({ "function": "http_request", "reason": "admin_policy", "rule": 0 })
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Typed setting definitions.
+//!
+//! Settings are declared by the core and by plugins (in the manifest's
+//! `settings` section) with a type, a scope and a default. The host validates
+//! every value against its definition before storing it.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Where a setting value is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingScope {
+    /// One value for the whole installation (admin only).
+    #[default]
+    System,
+
+    /// One value per connection profile.
+    Profile,
+
+    /// One value per user.
+    User,
+}
+
+impl SettingScope {
+    /// Get the scope name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Profile => "profile",
+            Self::User => "user",
+        }
+    }
+}
+
+impl std::str::FromStr for SettingScope {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "system" => Ok(Self::System),
+            "profile" => Ok(Self::Profile),
+            "user" => Ok(Self::User),
+            _ => Err(crate::Error::validation(format!(
+                "Invalid setting scope '{}'. Expected 'system', 'profile', or 'user'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Type of a setting value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingType {
+    /// String value.
+    String,
+
+    /// Integer value.
+    Integer,
+
+    /// Floating-point value.
+    Number,
+
+    /// Boolean value.
+    Boolean,
+
+    /// Arbitrary JSON value.
+    Json,
+}
+
+/// Definition of a setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingDefinition {
+    /// Setting key (plugin settings are namespaced as `<plugin>.<key>` by the host).
+    pub key: String,
+
+    /// Value type.
+    #[serde(rename = "type")]
+    pub setting_type: SettingType,
+
+    /// Scope the value is stored in.
+    #[serde(default)]
+    pub scope: SettingScope,
+
+    /// Default value.
+    #[serde(default)]
+    pub default: Value,
+
+    /// Description shown in settings UIs.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Allowed values (empty for any value of the type).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<Value>,
+
+    /// Minimum value (integer and number settings).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+
+    /// Maximum value (integer and number settings).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+
+    /// Whether the value is redacted when read back.
+    #[serde(default)]
+    pub secret: bool,
+}
+
+impl SettingDefinition {
+    /// Validate the definition itself, including its default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is invalid or the default does not match the definition.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.key.is_empty()
+            || !self
+                .key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        {
+            return Err(crate::Error::manifest(format!(
+                "Invalid setting key '{}': use letters, digits, '_', '-' and '.'",
+                self.key
+            )));
+        }
+
+        if !self.default.is_null() {
+            self.check_value(&self.default)
+                .map_err(|e| crate::Error::manifest(format!("Invalid default for setting '{}': {}", self.key, e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that a value matches the definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing why the value is invalid.
+    pub fn check_value(&self, value: &Value) -> crate::Result<()> {
+        let type_matches = match self.setting_type {
+            SettingType::String => value.is_string(),
+            SettingType::Integer => value.is_i64() || value.is_u64(),
+            SettingType::Number => value.is_number(),
+            SettingType::Boolean => value.is_boolean(),
+            SettingType::Json => true,
+        };
+        if !type_matches {
+            return Err(crate::Error::validation(format!(
+                "Setting '{}' expects a value of type {:?}",
+                self.key, self.setting_type
+            )));
+        }
+
+        if !self.options.is_empty() && !self.options.contains(value) {
+            return Err(crate::Error::validation(format!(
+                "Setting '{}' must be one of {}",
+                self.key,
+                Value::Array(self.options.clone())
+            )));
+        }
+
+        if let Some(number) = value.as_f64()
+            && (self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max))
+        {
+            return Err(crate::Error::validation(format!(
+                "Setting '{}' is out of range",
+                self.key
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_setting_validation() {
+        let definition: SettingDefinition = serde_json::from_value(json!({
+            "key": "sync.interval",
+            "type": "integer",
+            "scope": "user",
+            "default": 60,
+            "min": 10,
+            "max": 3600
+        }))
+        .unwrap();
+
+        definition.validate().unwrap();
+        assert_eq!(definition.scope, SettingScope::User);
+        definition.check_value(&json!(30)).unwrap();
+        assert!(definition.check_value(&json!(5)).is_err());
+        assert!(definition.check_value(&json!("30")).is_err());
+
+        let theme: SettingDefinition = serde_json::from_value(json!({
+            "key": "theme",
+            "type": "string",
+            "default": "purple",
+            "options": ["light", "dark"]
+        }))
+        .unwrap();
+        assert!(theme.validate().is_err());
+    }
+}
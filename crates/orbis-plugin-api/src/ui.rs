@@ -4,7 +4,7 @@
 //! supporting state management, event handling, and complex component compositions.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // =============================================================================
 // State Definition Types
@@ -166,6 +166,44 @@ pub enum Action {
     },
 }
 
+impl Action {
+    /// Check if this action (or a nested one) fetches data from `handler` via `GET`.
+    #[must_use]
+    pub fn fetches(&self, handler: &str) -> bool {
+        match *self {
+            Self::CallApi {
+                ref api,
+                ref method,
+                ref on_success,
+                ..
+            } => {
+                let is_get = method
+                    .as_deref()
+                    .is_none_or(|m| m.eq_ignore_ascii_case("GET"));
+                let target = api.rsplit('.').next().unwrap_or(api);
+
+                (is_get && target == handler) || on_success.iter().any(|a| a.fetches(handler))
+            }
+            Self::DebouncedAction { ref action, .. } => action.fetches(handler),
+            Self::Conditional {
+                ref then,
+                ref else_actions,
+                ..
+            } => then.iter().chain(else_actions).any(|a| a.fetches(handler)),
+            Self::Sequence { ref actions, .. } => actions.iter().any(|a| a.fetches(handler)),
+            Self::UpdateState { .. }
+            | Self::Navigate { .. }
+            | Self::ShowToast { .. }
+            | Self::ShowDialog { .. }
+            | Self::CloseDialog { .. }
+            | Self::SetLoading { .. }
+            | Self::Copy { .. }
+            | Self::OpenUrl { .. }
+            | Self::Emit { .. } => false,
+        }
+    }
+}
+
 /// Argument mapping for API calls.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArgMapping {
@@ -243,6 +281,11 @@ pub struct ComponentSchema {
     #[serde(default)]
     pub visible: Option<serde_json::Value>,
 
+    /// Feature flag of the plugin the component is shown for (`!flag` to
+    /// show it while the flag is off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_when: Option<String>,
+
     /// Child components.
     #[serde(default)]
     pub children: Vec<ComponentSchema>,
@@ -266,6 +309,7 @@ impl ComponentSchema {
             class_name: None,
             style: None,
             visible: None,
+            feature_when: None,
             children: Vec::new(),
             events: None,
             props: HashMap::new(),
@@ -309,6 +353,22 @@ impl ComponentSchema {
 
         Ok(())
     }
+
+    /// Check whether the component is shown with the given feature flags.
+    #[must_use]
+    pub fn is_enabled_by(&self, flags: &BTreeMap<String, bool>) -> bool {
+        self.feature_when
+            .as_deref()
+            .is_none_or(|condition| crate::features::condition_holds(condition, flags))
+    }
+
+    /// Remove the children hidden by the given feature flags, recursively.
+    pub fn retain_features(&mut self, flags: &BTreeMap<String, bool>) {
+        self.children.retain(|child| child.is_enabled_by(flags));
+        for child in &mut self.children {
+            child.retain_features(flags);
+        }
+    }
 }
 
 // =============================================================================
@@ -345,6 +405,46 @@ pub struct PageLifecycleHooks {
     pub on_query_change: Vec<Action>,
 }
 
+/// Page-level caching and revalidation hints.
+///
+/// Applies to the page's data actions (`call_api` actions using `GET`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct PageCacheHints {
+    /// How long fetched data stays fresh, in seconds.
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+
+    /// Refetch data when the window regains focus.
+    #[serde(default)]
+    pub revalidate_on_focus: bool,
+
+    /// Event names that invalidate the cached data.
+    #[serde(default)]
+    pub revalidate_on_events: Vec<String>,
+}
+
+/// Handler call the server makes when a page is requested, returning the
+/// result inline with the page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct PrefetchCall {
+    /// Page state field the result is stored in.
+    pub state: String,
+
+    /// Handler to call, through its `GET` route.
+    pub handler: String,
+
+    /// Query parameters of the call, mapped from those of the page request.
+    #[serde(default)]
+    pub map_args: Vec<ArgMapping>,
+
+    /// Interval, in seconds, at which the client calls the handler again to
+    /// refresh the state field (only fetched with the page if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_seconds: Option<u64>,
+}
+
 /// Enhanced page definition for plugin UI.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -387,6 +487,15 @@ pub struct PageDefinition {
     #[serde(default)]
     pub roles: Vec<String>,
 
+    /// Groups allowed to view the page (members of subgroups included).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+
+    /// Feature flag of the plugin the page is shown for (`!flag` to show it
+    /// while the flag is off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_when: Option<String>,
+
     /// Page-level state definition.
     #[serde(default)]
     pub state: HashMap<String, StateFieldDefinition>,
@@ -395,7 +504,22 @@ pub struct PageDefinition {
     #[serde(default)]
     pub computed: HashMap<String, String>,
 
+    /// State fields and computed values read by each computed value, filled
+    /// when the plugin is loaded so the frontend only recomputes a value when
+    /// one of them changes.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed_dependencies: BTreeMap<String, Vec<String>>,
+
+    /// Layout the page is placed in; the sections fill its `content` region.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+
+    /// Content of the layout's other regions, by region name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub regions: HashMap<String, Vec<ComponentSchema>>,
+
     /// Page sections/content.
+    #[serde(default)]
     pub sections: Vec<ComponentSchema>,
 
     /// Page-level action definitions.
@@ -409,6 +533,15 @@ pub struct PageDefinition {
     /// Dialog definitions.
     #[serde(default)]
     pub dialogs: Vec<DialogDefinition>,
+
+    /// Caching and revalidation hints for page data.
+    #[serde(default)]
+    pub cache: Option<PageCacheHints>,
+
+    /// Handler calls made concurrently by the server when the page is
+    /// requested, so the page opens with its data.
+    #[serde(default)]
+    pub prefetch: Vec<PrefetchCall>,
 }
 
 fn default_true() -> bool {
@@ -434,10 +567,181 @@ impl PageDefinition {
             return Err(crate::Error::schema("Page title is required"));
         }
 
-        for section in &self.sections {
+        for section in self.sections.iter().chain(self.regions.values().flatten()) {
             section.validate()?;
         }
 
+        self.computed_graph()?;
+
+        if self.layout.is_none() && !self.regions.is_empty() {
+            return Err(crate::Error::schema(format!(
+                "Page '{}' fills layout regions without a layout",
+                self.route
+            )));
+        }
+
+        let mut prefetched = std::collections::HashSet::new();
+        for call in &self.prefetch {
+            if call.state.is_empty() || call.handler.is_empty() {
+                return Err(crate::Error::schema(format!(
+                    "Prefetch calls of page '{}' need a state field and a handler",
+                    self.route
+                )));
+            }
+            if !prefetched.insert(call.state.as_str()) {
+                return Err(crate::Error::schema(format!(
+                    "Page '{}' prefetches state field '{}' more than once",
+                    self.route, call.state
+                )));
+            }
+            if call.refresh_seconds == Some(0) {
+                return Err(crate::Error::schema(format!(
+                    "Prefetch call of '{}' on page '{}' needs a positive refresh_seconds",
+                    call.state, self.route
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the page's layout is one of `layouts` and defines the regions
+    /// the page fills.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layout or one of the regions does not exist.
+    pub fn validate_layout(&self, layouts: &[LayoutDefinition]) -> crate::Result<()> {
+        let Some(ref name) = self.layout else {
+            return Ok(());
+        };
+        let layout = layouts.iter().find(|layout| &layout.name == name).ok_or_else(|| {
+            crate::Error::schema(format!("Page '{}' uses unknown layout '{}'", self.route, name))
+        })?;
+
+        let regions = layout.regions();
+        let filled = self
+            .regions
+            .keys()
+            .map(String::as_str)
+            .chain((!self.sections.is_empty()).then_some(CONTENT_REGION));
+        for region in filled {
+            if !regions.contains(&region) {
+                return Err(crate::Error::schema(format!(
+                    "Page '{}' fills region '{}', which layout '{}' does not define",
+                    self.route, region, name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Place the page in a layout: its sections and regions are composed
+    /// into the layout's component tree, which becomes the page's only section.
+    pub fn apply_layout(&mut self, layout: &LayoutDefinition) {
+        let mut regions = std::mem::take(&mut self.regions);
+        let sections = std::mem::take(&mut self.sections);
+        if !sections.is_empty() {
+            regions.insert(CONTENT_REGION.to_owned(), sections);
+        }
+
+        self.sections = vec![layout.compose(&regions)];
+        self.layout = None;
+    }
+
+    /// Get the state fields and computed values each computed value reads.
+    ///
+    /// Computed values read state as `state.<name>`, where `<name>` is a
+    /// state field or another computed value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a computed value reads an unknown name, or if
+    /// computed values read each other in a cycle.
+    pub fn computed_graph(&self) -> crate::Result<BTreeMap<String, Vec<String>>> {
+        let mut graph = BTreeMap::new();
+        let computed: BTreeMap<&String, &String> = self.computed.iter().collect();
+        for (name, expression) in computed {
+            if self.state.contains_key(name) {
+                return Err(crate::Error::schema(format!(
+                    "Page '{}' defines '{}' as both a state field and a computed value",
+                    self.route, name
+                )));
+            }
+
+            let mut dependencies = Vec::new();
+            for reference in state_references(expression) {
+                if !self.state.contains_key(reference.name) && !self.computed.contains_key(reference.name) {
+                    return Err(crate::Error::schema(format!(
+                        "Computed value '{}' of page '{}' reads unknown state field '{}' at offset {}",
+                        name, self.route, reference.name, reference.offset
+                    )));
+                }
+                dependencies.push(reference.name.to_owned());
+            }
+            dependencies.sort();
+            dependencies.dedup();
+            graph.insert(name.clone(), dependencies);
+        }
+
+        // Depth-first search over computed values, reporting the first back edge
+        let mut done = std::collections::BTreeSet::new();
+        for name in graph.keys() {
+            let mut path = Vec::new();
+            self.check_computed_cycle(name, &mut path, &mut done)?;
+        }
+
+        Ok(graph)
+    }
+
+    /// Check no computed value read (directly or not) by `name` reads it.
+    fn check_computed_cycle<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut std::collections::BTreeSet<&'a str>,
+    ) -> crate::Result<()> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        let Some(expression) = self.computed.get(name) else {
+            return Ok(());
+        };
+
+        path.push(name);
+        for reference in state_references(expression) {
+            if !self.computed.contains_key(reference.name) {
+                continue;
+            }
+            if let Some(start) = path.iter().position(|visited| *visited == reference.name) {
+                let mut cycle = path.get(start..).unwrap_or_default().to_vec();
+                cycle.push(reference.name);
+                return Err(crate::Error::schema(format!(
+                    "Computed values of page '{}' read each other in a cycle ({}): '{}' reads '{}' at offset {}",
+                    self.route,
+                    cycle.join(" -> "),
+                    name,
+                    reference.name,
+                    reference.offset
+                )));
+            }
+            self.check_computed_cycle(reference.name, path, done)?;
+        }
+        path.pop();
+        done.insert(name);
+
+        Ok(())
+    }
+
+    /// Fill the dependencies of the page's computed values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the computed values are invalid (see
+    /// [`Self::computed_graph`]).
+    pub fn resolve_computed(&mut self) -> crate::Result<()> {
+        self.computed_dependencies = self.computed_graph()?;
         Ok(())
     }
 
@@ -446,12 +750,328 @@ impl PageDefinition {
     pub fn full_route(&self, plugin_name: &str) -> String {
         format!("/plugins/{}{}", plugin_name, self.route)
     }
+
+    /// Check whether a viewer may access the page (`None` for anonymous viewers).
+    ///
+    /// The viewer needs one of the page's roles, if any, to be in one of
+    /// its groups, if any, and all of its permissions. Admins pass all checks.
+    #[must_use]
+    pub fn is_accessible_by(&self, viewer: Option<&ViewerAccess>) -> bool {
+        let Some(viewer) = viewer else {
+            return !self.requires_auth
+                && self.roles.is_empty()
+                && self.groups.is_empty()
+                && self.permissions.is_empty();
+        };
+
+        if viewer.is_admin() {
+            return true;
+        }
+
+        let has_role = self.roles.is_empty() || self.roles.iter().any(|role| viewer.roles.contains(role));
+        let has_permissions = self
+            .permissions
+            .iter()
+            .all(|permission| viewer.permissions.contains(permission));
+
+        has_role && viewer.in_any_group(&self.groups) && has_permissions
+    }
+
+    /// Check whether the page is shown with the given feature flags.
+    #[must_use]
+    pub fn is_enabled_by(&self, flags: &BTreeMap<String, bool>) -> bool {
+        self.feature_when
+            .as_deref()
+            .is_none_or(|condition| crate::features::condition_holds(condition, flags))
+    }
+
+    /// Get the page without the components hidden by the given feature flags.
+    #[must_use]
+    pub fn with_features(&self, flags: &BTreeMap<String, bool>) -> Self {
+        let mut page = self.clone();
+        page.sections.retain(|section| section.is_enabled_by(flags));
+        let dialog_parts = page
+            .dialogs
+            .iter_mut()
+            .flat_map(|dialog| std::iter::once(&mut dialog.content).chain(dialog.footer.as_mut()));
+        for component in page.sections.iter_mut().chain(dialog_parts) {
+            component.retain_features(flags);
+        }
+        page
+    }
+
+    /// Get the cache TTL (in seconds) for data fetched from `handler`.
+    ///
+    /// Returns `None` unless the page has a TTL hint and loads the handler
+    /// through a `GET` data action.
+    #[must_use]
+    pub fn cache_ttl_for(&self, handler: &str) -> Option<u64> {
+        let ttl = self.cache.as_ref()?.ttl_seconds.filter(|ttl| *ttl > 0)?;
+
+        self.fetches(handler).then_some(ttl)
+    }
+
+    /// Check if the page's data is revalidated when `event` is emitted.
+    #[must_use]
+    pub fn revalidates_on(&self, event: &str) -> bool {
+        self.cache
+            .as_ref()
+            .is_some_and(|cache| cache.revalidate_on_events.iter().any(|name| name == event))
+    }
+
+    /// Check if the page loads data from `handler` through a `GET` data
+    /// action or a prefetch call.
+    #[must_use]
+    pub fn fetches(&self, handler: &str) -> bool {
+        let on_mount = self.hooks.iter().flat_map(|hooks| hooks.on_mount.iter());
+        self.prefetch.iter().any(|call| call.handler == handler)
+            || self
+                .actions
+                .values()
+                .chain(on_mount)
+                .any(|action| action.fetches(handler))
+    }
+}
+
+/// A state field read by an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StateReference<'a> {
+    /// State field (or computed value) name.
+    name: &'a str,
+
+    /// Byte offset of the reference in the expression.
+    offset: usize,
+}
+
+/// Find the `state.<name>` references of an expression.
+fn state_references(expression: &str) -> Vec<StateReference<'_>> {
+    const PREFIX: &str = "state.";
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+
+    let mut references = Vec::new();
+    for (offset, _) in expression.match_indices(PREFIX) {
+        // Skip `mystate.x` and `context.state.x`
+        let preceded = expression
+            .get(..offset)
+            .and_then(|before| before.chars().next_back())
+            .is_some_and(|c| is_identifier(c) || c == '.');
+        if preceded {
+            continue;
+        }
+
+        let rest = expression.get(offset.saturating_add(PREFIX.len())..).unwrap_or_default();
+        let end = rest.find(|c: char| !is_identifier(c)).unwrap_or(rest.len());
+        if let Some(name) = rest.get(..end).filter(|name| !name.is_empty()) {
+            references.push(StateReference { name, offset });
+        }
+    }
+    references
+}
+
+// =============================================================================
+// Layout Types
+// =============================================================================
+
+/// Component type marking a region of a layout.
+pub const REGION_COMPONENT: &str = "Region";
+
+/// Region the sections of a page using a layout are placed in.
+pub const CONTENT_REGION: &str = "content";
+
+/// Reusable page layout with named regions.
+///
+/// The template is a component tree in which `Region` components, each with
+/// a `name`, mark where pages place their content. A region is replaced by
+/// the page's content for it, or by its own children when the page leaves it
+/// empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LayoutDefinition {
+    /// Layout name, referenced by the `layout` of pages.
+    pub name: String,
+
+    /// Layout description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Component tree of the layout.
+    pub template: ComponentSchema,
+}
+
+impl LayoutDefinition {
+    /// Validate the layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty, the template is invalid, or its
+    /// regions are unnamed, duplicated, nested or at its root.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.name.is_empty() {
+            return Err(crate::Error::schema("Layout name is required"));
+        }
+        self.template.validate()?;
+
+        if is_region(&self.template) {
+            return Err(crate::Error::schema(format!(
+                "The root of layout '{}' cannot be a region",
+                self.name
+            )));
+        }
+
+        let mut regions = Vec::new();
+        collect_regions(&self.template, &mut regions);
+        if regions.is_empty() {
+            return Err(crate::Error::schema(format!("Layout '{}' has no regions", self.name)));
+        }
+
+        let mut names = Vec::new();
+        for region in regions {
+            let name = region_name(region)
+                .ok_or_else(|| crate::Error::schema(format!("Regions of layout '{}' need a name", self.name)))?;
+            if names.contains(&name) {
+                return Err(crate::Error::schema(format!(
+                    "Layout '{}' defines region '{}' more than once",
+                    self.name, name
+                )));
+            }
+            if region.children.iter().any(contains_region) {
+                return Err(crate::Error::schema(format!(
+                    "Region '{}' of layout '{}' cannot contain another region",
+                    name, self.name
+                )));
+            }
+            names.push(name);
+        }
+
+        Ok(())
+    }
+
+    /// Get the names of the regions, in template order.
+    #[must_use]
+    pub fn regions(&self) -> Vec<&str> {
+        let mut regions = Vec::new();
+        collect_regions(&self.template, &mut regions);
+        regions.into_iter().filter_map(region_name).collect()
+    }
+
+    /// Compose the layout with the content of its regions, by region name.
+    #[must_use]
+    pub fn compose(&self, regions: &HashMap<String, Vec<ComponentSchema>>) -> ComponentSchema {
+        let mut tree = self.template.clone();
+        fill_regions(&mut tree, regions);
+        tree
+    }
+}
+
+/// Check if a component is a layout region.
+fn is_region(component: &ComponentSchema) -> bool {
+    component.component_type == REGION_COMPONENT
+}
+
+/// Get the name of a layout region.
+fn region_name(component: &ComponentSchema) -> Option<&str> {
+    component
+        .props
+        .get("name")
+        .and_then(serde_json::Value::as_str)
+        .filter(|name| !name.is_empty())
+}
+
+/// Collect the regions under a component, without those nested in regions.
+fn collect_regions<'a>(component: &'a ComponentSchema, regions: &mut Vec<&'a ComponentSchema>) {
+    for child in &component.children {
+        if is_region(child) {
+            regions.push(child);
+        } else {
+            collect_regions(child, regions);
+        }
+    }
+}
+
+/// Check if a component is or contains a layout region.
+fn contains_region(component: &ComponentSchema) -> bool {
+    is_region(component) || component.children.iter().any(contains_region)
+}
+
+/// Replace the regions under a component with their content.
+fn fill_regions(component: &mut ComponentSchema, regions: &HashMap<String, Vec<ComponentSchema>>) {
+    let children = std::mem::take(&mut component.children);
+    for mut child in children {
+        if !is_region(&child) {
+            fill_regions(&mut child, regions);
+            component.children.push(child);
+            continue;
+        }
+
+        match region_name(&child).and_then(|name| regions.get(name)) {
+            Some(content) if !content.is_empty() => component.children.extend(content.iter().cloned()),
+            _ => component.children.append(&mut child.children),
+        }
+    }
 }
 
 // =============================================================================
 // Navigation Types
 // =============================================================================
 
+/// Roles, groups and permissions of an authenticated viewer, for page access checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerAccess {
+    /// Roles (e.g. `admin`, `user`).
+    pub roles: Vec<String>,
+
+    /// Groups the viewer is a member of, directly or through a subgroup.
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Permissions (e.g. `read`, `write`).
+    pub permissions: Vec<String>,
+}
+
+impl ViewerAccess {
+    /// Role granted to administrators.
+    pub const ADMIN_ROLE: &'static str = "admin";
+
+    /// Access of a user account: every user has the `user` role and `read`
+    /// permission; admins also get the `admin` role and `write` permission.
+    #[must_use]
+    pub fn for_user(is_admin: bool) -> Self {
+        if is_admin {
+            Self {
+                roles: vec![Self::ADMIN_ROLE.to_owned(), "user".to_owned()],
+                groups: Vec::new(),
+                permissions: vec![Self::ADMIN_ROLE.to_owned(), "read".to_owned(), "write".to_owned()],
+            }
+        } else {
+            Self {
+                roles: vec!["user".to_owned()],
+                groups: Vec::new(),
+                permissions: vec!["read".to_owned()],
+            }
+        }
+    }
+
+    /// Set the groups the viewer is a member of.
+    #[must_use]
+    pub fn with_groups(mut self, groups: Vec<String>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Check whether the viewer is a member of one of the given groups, or
+    /// no groups are given.
+    #[must_use]
+    pub fn in_any_group(&self, groups: &[String]) -> bool {
+        groups.is_empty() || groups.iter().any(|group| self.groups.contains(group))
+    }
+
+    /// Check whether the viewer has the admin role.
+    #[must_use]
+    pub fn is_admin(&self) -> bool {
+        self.roles.iter().any(|role| role == Self::ADMIN_ROLE)
+    }
+}
+
 /// Navigation menu item.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -490,6 +1110,121 @@ pub struct NavigationConfig {
     pub footer: Vec<NavigationItem>,
 }
 
+// =============================================================================
+// Theme Types
+// =============================================================================
+
+/// Substrings that are never allowed in theme token values.
+const FORBIDDEN_THEME_VALUE_PATTERNS: &[&str] = &[
+    ";", "{", "}", "<", ">", "\\", "url(", "expression(", "@import", "javascript:",
+];
+
+/// Maximum length of a theme token value.
+const MAX_THEME_VALUE_LEN: usize = 256;
+
+/// Theme tokens contributed by a plugin (scoped to its pages) or a profile.
+///
+/// Tokens are plain CSS values keyed by token name (e.g. `primary`, `radius`).
+/// Plugin themes are never applied globally; the host scopes them to the
+/// plugin's own pages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ThemeDefinition {
+    /// Color tokens (light mode).
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+
+    /// Color token overrides applied in dark mode.
+    #[serde(default)]
+    pub dark_colors: HashMap<String, String>,
+
+    /// Spacing and radius tokens.
+    #[serde(default)]
+    pub spacing: HashMap<String, String>,
+
+    /// Typography tokens (font families, sizes, weights).
+    #[serde(default)]
+    pub typography: HashMap<String, String>,
+}
+
+impl ThemeDefinition {
+    /// Check if the theme defines no tokens.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+            && self.dark_colors.is_empty()
+            && self.spacing.is_empty()
+            && self.typography.is_empty()
+    }
+
+    /// Overlay the tokens of another theme on top of this one.
+    pub fn merge(&mut self, other: &Self) {
+        self.colors.extend(other.colors.clone());
+        self.dark_colors.extend(other.dark_colors.clone());
+        self.spacing.extend(other.spacing.clone());
+        self.typography.extend(other.typography.clone());
+    }
+
+    /// Validate token names and values.
+    ///
+    /// Rejects values that could escape a CSS custom property declaration
+    /// and hijack styles outside the theme's scope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a token name or value is invalid.
+    pub fn validate(&self) -> crate::Result<()> {
+        let groups = [
+            ("colors", &self.colors),
+            ("dark_colors", &self.dark_colors),
+            ("spacing", &self.spacing),
+            ("typography", &self.typography),
+        ];
+
+        for (group, tokens) in groups {
+            for (name, value) in tokens {
+                validate_theme_token(group, name, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Validate a single theme token.
+fn validate_theme_token(group: &str, name: &str, value: &str) -> crate::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(crate::Error::schema(format!(
+            "Invalid theme token name '{}' in '{}': use lowercase letters, digits and hyphens",
+            name, group
+        )));
+    }
+
+    if value.is_empty() || value.len() > MAX_THEME_VALUE_LEN {
+        return Err(crate::Error::schema(format!(
+            "Theme token '{}.{}' must be between 1 and {} characters",
+            group, name, MAX_THEME_VALUE_LEN
+        )));
+    }
+
+    let lowered = value.to_lowercase();
+    if let Some(pattern) = FORBIDDEN_THEME_VALUE_PATTERNS
+        .iter()
+        .find(|p| lowered.contains(*p))
+    {
+        return Err(crate::Error::schema(format!(
+            "Theme token '{}.{}' contains forbidden sequence '{}'",
+            group, name, pattern
+        )));
+    }
+
+    Ok(())
+}
+
 // =============================================================================
 // Helper Types for Common Patterns
 // =============================================================================
@@ -633,6 +1368,8 @@ mod tests {
             requires_auth: true,
             permissions: vec!["users.read".to_string()],
             roles: vec![],
+            groups: vec![],
+            feature_when: None,
             state: {
                 let mut map = HashMap::new();
                 map.insert(
@@ -656,10 +1393,15 @@ mod tests {
                 map
             },
             computed: HashMap::new(),
+            computed_dependencies: BTreeMap::new(),
+            layout: None,
+            regions: HashMap::new(),
             sections: vec![ComponentSchema::new("Container").with_id("main")],
             actions: HashMap::new(),
             hooks: None,
             dialogs: vec![],
+            cache: None,
+            prefetch: vec![],
         };
 
         let json = serde_json::to_string_pretty(&page).unwrap();
@@ -670,6 +1412,81 @@ mod tests {
         assert_eq!(parsed.title, "User Management");
     }
 
+    #[test]
+    fn test_page_access() {
+        let mut page: PageDefinition =
+            serde_json::from_str(r#"{"route": "/reports", "title": "Reports", "sections": []}"#).unwrap();
+        let user = ViewerAccess::for_user(false);
+        let admin = ViewerAccess::for_user(true);
+
+        assert!(!page.is_accessible_by(None));
+        assert!(page.is_accessible_by(Some(&user)));
+
+        page.roles = vec!["auditor".to_string()];
+        assert!(!page.is_accessible_by(Some(&user)));
+        assert!(page.is_accessible_by(Some(&admin)));
+
+        page.roles = vec!["auditor".to_string(), "user".to_string()];
+        page.permissions = vec!["read".to_string(), "write".to_string()];
+        assert!(!page.is_accessible_by(Some(&user)));
+
+        page.permissions = vec!["read".to_string()];
+        assert!(page.is_accessible_by(Some(&user)));
+
+        page.requires_auth = false;
+        assert!(!page.is_accessible_by(None));
+        page.roles.clear();
+        page.permissions.clear();
+        assert!(page.is_accessible_by(None));
+    }
+
+    #[test]
+    fn test_page_group_access() {
+        let mut page: PageDefinition =
+            serde_json::from_str(r#"{"route": "/reports", "title": "Reports", "sections": [], "groups": ["finance"]}"#)
+                .unwrap();
+        let user = ViewerAccess::for_user(false);
+        let member = ViewerAccess::for_user(false).with_groups(vec!["finance".to_string(), "staff".to_string()]);
+
+        assert!(!page.is_accessible_by(Some(&user)));
+        assert!(page.is_accessible_by(Some(&member)));
+        assert!(page.is_accessible_by(Some(&ViewerAccess::for_user(true))));
+
+        // Groups apply in addition to roles
+        page.roles = vec!["auditor".to_string()];
+        assert!(!page.is_accessible_by(Some(&member)));
+
+        page.roles.clear();
+        page.requires_auth = false;
+        assert!(!page.is_accessible_by(None));
+    }
+
+    #[test]
+    fn test_page_feature_conditions() {
+        let page: PageDefinition = serde_json::from_value(serde_json::json!({
+            "route": "/notes",
+            "title": "Notes",
+            "feature_when": "notes",
+            "sections": [
+                {"type": "Container", "children": [
+                    {"type": "Button", "id": "export", "feature_when": "exports"},
+                    {"type": "Text", "id": "legacy", "feature_when": "!exports"}
+                ]},
+                {"type": "Editor", "feature_when": "exports"}
+            ]
+        }))
+        .unwrap();
+
+        let flags = BTreeMap::from([("notes".to_string(), true), ("exports".to_string(), false)]);
+        assert!(page.is_enabled_by(&flags));
+        assert!(!page.is_enabled_by(&BTreeMap::new()));
+
+        let shown = page.with_features(&flags);
+        assert_eq!(shown.sections.len(), 1);
+        let children: Vec<_> = shown.sections[0].children.iter().filter_map(|c| c.id.as_deref()).collect();
+        assert_eq!(children, ["legacy"]);
+    }
+
     #[test]
     fn test_complex_page_deserialization() {
         let json = r#"{
@@ -710,4 +1527,241 @@ mod tests {
         assert_eq!(page.sections.len(), 2);
         assert!(page.state.contains_key("users"));
     }
+
+    #[test]
+    fn test_theme_definition_validation() {
+        let mut theme = ThemeDefinition::default();
+        theme.colors.insert("primary".to_string(), "oklch(0.5 0.2 250)".to_string());
+        theme.spacing.insert("card-padding".to_string(), "1.5rem".to_string());
+        theme.validate().unwrap();
+
+        let mut hijack = theme.clone();
+        hijack
+            .colors
+            .insert("primary".to_string(), "red; } body { display: none".to_string());
+        assert!(hijack.validate().is_err());
+
+        let mut bad_name = theme.clone();
+        bad_name.typography.insert("Font Family".to_string(), "Inter".to_string());
+        assert!(bad_name.validate().is_err());
+
+        let mut remote = theme;
+        remote
+            .colors
+            .insert("background".to_string(), "URL(https://evil.example/x.png)".to_string());
+        assert!(remote.validate().is_err());
+    }
+
+    #[test]
+    fn test_theme_definition_merge() {
+        let mut base = ThemeDefinition::default();
+        base.colors.insert("primary".to_string(), "#000".to_string());
+        base.colors.insert("accent".to_string(), "#111".to_string());
+
+        let mut overlay = ThemeDefinition::default();
+        overlay.colors.insert("primary".to_string(), "#fff".to_string());
+        overlay.dark_colors.insert("primary".to_string(), "#222".to_string());
+
+        base.merge(&overlay);
+        assert_eq!(base.colors.get("primary").map(String::as_str), Some("#fff"));
+        assert_eq!(base.colors.get("accent").map(String::as_str), Some("#111"));
+        assert_eq!(base.dark_colors.len(), 1);
+    }
+
+    #[test]
+    fn test_page_cache_ttl_for_handler() {
+        let json = r#"{
+            "route": "/greeting",
+            "title": "Greeting",
+            "sections": [],
+            "cache": { "ttl_seconds": 30, "revalidate_on_events": ["greeting.updated"] },
+            "hooks": {
+                "on_mount": [
+                    { "type": "call_api", "api": "plugin.get_greeting" },
+                    { "type": "call_api", "api": "plugin.create_greeting", "method": "POST" }
+                ]
+            }
+        }"#;
+
+        let page: PageDefinition = serde_json::from_str(json).unwrap();
+        assert_eq!(page.cache_ttl_for("get_greeting"), Some(30));
+        assert_eq!(page.cache_ttl_for("create_greeting"), None);
+        assert_eq!(page.cache_ttl_for("unknown"), None);
+        assert!(page.revalidates_on("greeting.updated"));
+        assert!(!page.revalidates_on("greeting.deleted"));
+    }
+
+    #[test]
+    fn test_page_prefetch() {
+        let json = r#"{
+            "route": "/orders",
+            "title": "Orders",
+            "sections": [],
+            "prefetch": [
+                { "state": "orders", "handler": "list_orders", "map_args": [{ "from": "status", "to": "filter" }] },
+                { "state": "stats", "handler": "order_stats", "refresh_seconds": 30 }
+            ]
+        }"#;
+
+        let page: PageDefinition = serde_json::from_str(json).unwrap();
+        page.validate().unwrap();
+        assert!(page.fetches("list_orders"));
+        assert!(!page.fetches("delete_order"));
+        assert_eq!(page.prefetch[0].map_args[0].to, "filter");
+        assert_eq!(page.prefetch[0].refresh_seconds, None);
+        assert_eq!(page.prefetch[1].refresh_seconds, Some(30));
+
+        let invalid = |prefetch: serde_json::Value| -> PageDefinition {
+            serde_json::from_value(serde_json::json!({ "route": "/x", "title": "X", "prefetch": prefetch })).unwrap()
+        };
+        invalid(serde_json::json!([{ "state": "", "handler": "h" }]))
+            .validate()
+            .unwrap_err();
+        invalid(serde_json::json!([{ "state": "a", "handler": "h", "refresh_seconds": 0 }]))
+            .validate()
+            .unwrap_err();
+        invalid(serde_json::json!([{ "state": "a", "handler": "h" }, { "state": "a", "handler": "g" }]))
+            .validate()
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_layout_composition() {
+        let layout: LayoutDefinition = serde_json::from_value(serde_json::json!({
+            "name": "sidebar",
+            "template": {
+                "type": "Flex",
+                "children": [
+                    { "type": "Region", "name": "header", "children": [{ "type": "Heading", "text": "Default" }] },
+                    { "type": "Container", "children": [
+                        { "type": "Region", "name": "sidebar" },
+                        { "type": "Region", "name": "content" }
+                    ]}
+                ]
+            }
+        }))
+        .unwrap();
+        layout.validate().unwrap();
+        assert_eq!(layout.regions(), vec!["header", "sidebar", "content"]);
+
+        let mut page: PageDefinition = serde_json::from_value(serde_json::json!({
+            "route": "/orders",
+            "title": "Orders",
+            "layout": "sidebar",
+            "regions": { "sidebar": [{ "type": "Text", "text": "Filters" }] },
+            "sections": [{ "type": "Table", "id": "orders" }]
+        }))
+        .unwrap();
+        page.validate().unwrap();
+        page.validate_layout(std::slice::from_ref(&layout)).unwrap();
+
+        page.apply_layout(&layout);
+        assert!(page.layout.is_none());
+        assert!(page.regions.is_empty());
+        assert_eq!(page.sections.len(), 1);
+
+        // Unfilled regions keep their default content; filled ones are replaced
+        let root = &page.sections[0];
+        assert_eq!(root.children[0].component_type, "Heading");
+        let body = &root.children[1];
+        assert_eq!(body.children.len(), 2);
+        assert_eq!(body.children[0].component_type, "Text");
+        assert_eq!(body.children[1].id.as_deref(), Some("orders"));
+    }
+
+    #[test]
+    fn test_layout_validation() {
+        let layout = |template: serde_json::Value| -> LayoutDefinition {
+            serde_json::from_value(serde_json::json!({ "name": "main", "template": template })).unwrap()
+        };
+
+        layout(serde_json::json!({ "type": "Region", "name": "content" }))
+            .validate()
+            .unwrap_err();
+        layout(serde_json::json!({ "type": "Flex" })).validate().unwrap_err();
+        layout(serde_json::json!({ "type": "Flex", "children": [{ "type": "Region" }] }))
+            .validate()
+            .unwrap_err();
+        layout(serde_json::json!({ "type": "Flex", "children": [
+            { "type": "Region", "name": "content" },
+            { "type": "Region", "name": "content" }
+        ]}))
+        .validate()
+        .unwrap_err();
+        layout(serde_json::json!({ "type": "Flex", "children": [
+            { "type": "Region", "name": "content", "children": [{ "type": "Region", "name": "inner" }] }
+        ]}))
+        .validate()
+        .unwrap_err();
+
+        let main = layout(serde_json::json!({ "type": "Flex", "children": [{ "type": "Region", "name": "content" }] }));
+        main.validate().unwrap();
+        let page = |value: serde_json::Value| -> PageDefinition { serde_json::from_value(value).unwrap() };
+
+        page(serde_json::json!({ "route": "/a", "title": "A", "layout": "other" }))
+            .validate_layout(std::slice::from_ref(&main))
+            .unwrap_err();
+        page(serde_json::json!({ "route": "/a", "title": "A", "layout": "main", "regions": { "footer": [] } }))
+            .validate_layout(std::slice::from_ref(&main))
+            .unwrap_err();
+        page(serde_json::json!({ "route": "/a", "title": "A", "regions": { "content": [] } }))
+            .validate()
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_computed_dependencies() {
+        let mut page: PageDefinition = serde_json::from_value(serde_json::json!({
+            "route": "/cart",
+            "title": "Cart",
+            "state": {
+                "items": { "type": "array" },
+                "discount": { "type": "number" }
+            },
+            "computed": {
+                "subtotal": "{{state.items.length * 10}}",
+                "total": "{{state.subtotal - state.discount + state.subtotal}}",
+                "label": "{{context.state.items}} {{mystate.x}}"
+            }
+        }))
+        .unwrap();
+        page.validate().unwrap();
+        page.resolve_computed().unwrap();
+
+        assert_eq!(page.computed_dependencies["subtotal"], vec!["items"]);
+        assert_eq!(page.computed_dependencies["total"], vec!["discount", "subtotal"]);
+        assert!(page.computed_dependencies["label"].is_empty());
+    }
+
+    #[test]
+    fn test_computed_validation() {
+        let page = |computed: serde_json::Value| -> PageDefinition {
+            serde_json::from_value(serde_json::json!({
+                "route": "/a",
+                "title": "A",
+                "state": { "count": { "type": "number" } },
+                "computed": computed
+            }))
+            .unwrap()
+        };
+
+        let error = page(serde_json::json!({ "double": "{{state.cuont * 2}}" }))
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("'cuont' at offset 2"));
+
+        let error = page(serde_json::json!({
+            "a": "{{state.b + state.count}}",
+            "b": "{{state.c}}",
+            "c": "{{1 + state.a}}"
+        }))
+        .validate()
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("(a -> b -> c -> a): 'c' reads 'a' at offset 6"));
+
+        page(serde_json::json!({ "self": "{{state.self}}" })).validate().unwrap_err();
+        page(serde_json::json!({ "count": "{{1}}" })).validate().unwrap_err();
+    }
 }
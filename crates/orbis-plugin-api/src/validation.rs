@@ -0,0 +1,498 @@
+//! Request schemas of plugin routes.
+//!
+//! Routes may declare a schema for their JSON body and query string in the
+//! manifest's `request` section. The host checks requests against it before
+//! invoking the handler and answers `400 Bad Request` with every violation,
+//! so handlers only see requests matching their schema.
+//!
+//! Schemas use a subset of JSON Schema (as found in OpenAPI documents):
+//! `type`, `nullable`, `enum`, `minimum`, `maximum`, `minLength`,
+//! `maxLength`, `properties`, `required`, `additionalProperties`, `items`,
+//! `minItems` and `maxItems`. Other keywords are ignored.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Schemas of a route's request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestSchema {
+    /// Schema of the JSON body (`POST`, `PUT` and `PATCH` routes only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<JsonSchema>,
+
+    /// Schema of the query string, as an object of parameters. Parameters
+    /// declared as integers, numbers or booleans are converted before the
+    /// check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<JsonSchema>,
+}
+
+/// JSON value type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonType {
+    /// String.
+    String,
+
+    /// Integer number.
+    Integer,
+
+    /// Any number.
+    Number,
+
+    /// `true` or `false`.
+    Boolean,
+
+    /// Object.
+    Object,
+
+    /// Array.
+    Array,
+
+    /// `null`.
+    Null,
+}
+
+impl JsonType {
+    /// Check if a value is of this type.
+    #[must_use]
+    pub fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Number => value.is_number(),
+            Self::Boolean => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::Null => value.is_null(),
+        }
+    }
+
+    /// Get the type name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Null => "null",
+        }
+    }
+}
+
+/// Schema of a JSON value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSchema {
+    /// Expected type (any type if unset).
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub json_type: Option<JsonType>,
+
+    /// Whether `null` is accepted besides the type.
+    #[serde(default)]
+    pub nullable: bool,
+
+    /// Allowed values (empty for any value).
+    #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<Value>,
+
+    /// Smallest allowed number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+
+    /// Largest allowed number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+
+    /// Fewest characters of a string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+
+    /// Most characters of a string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+
+    /// Schemas of object properties.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Self>,
+
+    /// Properties an object must have.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+
+    /// Whether objects may have properties missing from `properties`.
+    #[serde(default = "default_true")]
+    pub additional_properties: bool,
+
+    /// Schema of array items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Self>>,
+
+    /// Fewest items of an array.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+
+    /// Most items of an array.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+
+    /// Description, for documentation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Allow additional properties by default, as JSON Schema does.
+const fn default_true() -> bool {
+    true
+}
+
+/// A value not matching its schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the value (empty for the root).
+    pub path: String,
+
+    /// What is wrong with the value.
+    pub message: String,
+}
+
+/// Part of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestPart {
+    /// The JSON body.
+    Body,
+
+    /// The query string.
+    Query,
+}
+
+/// A request part not matching the route's schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestViolation {
+    /// Part of the request.
+    pub location: RequestPart,
+
+    /// JSON pointer to the value within the part (empty for the whole part).
+    pub path: String,
+
+    /// What is wrong with the value.
+    pub message: String,
+}
+
+impl RequestSchema {
+    /// Validate the schemas themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a schema is contradictory.
+    pub fn validate(&self) -> crate::Result<()> {
+        if let Some(body) = self.body.as_ref() {
+            body.validate("body")?;
+        }
+        if let Some(query) = self.query.as_ref() {
+            if query.json_type.is_some_and(|json_type| json_type != JsonType::Object) {
+                return Err(crate::Error::manifest("Query schema must be of type object"));
+            }
+            query.validate("query")?;
+        }
+        Ok(())
+    }
+
+    /// Check a request's query parameters and body.
+    #[must_use]
+    pub fn check(&self, query: &HashMap<String, String>, body: &Value) -> Vec<RequestViolation> {
+        let mut violations = Vec::new();
+
+        if let Some(schema) = self.query.as_ref() {
+            let params = query
+                .iter()
+                .map(|(name, value)| {
+                    let json_type = schema.properties.get(name).and_then(|property| property.json_type);
+                    (name.clone(), coerce_param(value, json_type))
+                })
+                .collect::<Map<_, _>>();
+            violations.extend(
+                schema
+                    .check(&Value::Object(params))
+                    .into_iter()
+                    .map(|violation| violation.at(RequestPart::Query)),
+            );
+        }
+
+        if let Some(schema) = self.body.as_ref() {
+            violations.extend(schema.check(body).into_iter().map(|violation| violation.at(RequestPart::Body)));
+        }
+
+        violations
+    }
+}
+
+/// Convert a query parameter to the type its schema expects, leaving it a
+/// string if it does not parse.
+fn coerce_param(value: &str, json_type: Option<JsonType>) -> Value {
+    let parsed = match json_type {
+        Some(JsonType::Integer) => value.parse::<i64>().ok().map(Value::from),
+        Some(JsonType::Number) => value.parse::<f64>().ok().map(Value::from),
+        Some(JsonType::Boolean) => value.parse::<bool>().ok().map(Value::from),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(value.to_owned()))
+}
+
+impl SchemaViolation {
+    /// Place the violation in a part of a request.
+    fn at(self, location: RequestPart) -> RequestViolation {
+        RequestViolation {
+            location,
+            path: self.path,
+            message: self.message,
+        }
+    }
+}
+
+impl JsonSchema {
+    /// Validate the schema itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema is contradictory.
+    pub fn validate(&self, path: &str) -> crate::Result<()> {
+        let inverted = self.minimum.zip(self.maximum).is_some_and(|(min, max)| min > max)
+            || self.min_length.zip(self.max_length).is_some_and(|(min, max)| min > max)
+            || self.min_items.zip(self.max_items).is_some_and(|(min, max)| min > max);
+        if inverted {
+            return Err(crate::Error::manifest(format!(
+                "Schema at '{}' has a minimum larger than its maximum",
+                path
+            )));
+        }
+
+        if !self.additional_properties
+            && let Some(name) = self.required.iter().find(|name| !self.properties.contains_key(*name))
+        {
+            return Err(crate::Error::manifest(format!(
+                "Schema at '{}' requires '{}', which it does not allow",
+                path, name
+            )));
+        }
+
+        for (name, property) in &self.properties {
+            property.validate(&format!("{}/{}", path, pointer_token(name)))?;
+        }
+        if let Some(items) = self.items.as_ref() {
+            items.validate(&format!("{}/items", path))?;
+        }
+        Ok(())
+    }
+
+    /// Check a value against the schema.
+    #[must_use]
+    pub fn check(&self, value: &Value) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        self.check_at(value, "", &mut violations);
+        violations
+    }
+
+    /// Check a value found at a JSON pointer.
+    fn check_at(&self, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+        let mut violation = |message: String| {
+            violations.push(SchemaViolation {
+                path: path.to_owned(),
+                message,
+            });
+        };
+
+        if value.is_null() && self.nullable {
+            return;
+        }
+        if let Some(json_type) = self.json_type
+            && !json_type.matches(value)
+        {
+            violation(format!("Expected {}, found {}", json_type.as_str(), type_name(value)));
+            return;
+        }
+        if !self.allowed.is_empty() && !self.allowed.contains(value) {
+            violation(format!("Must be one of {}", Value::Array(self.allowed.clone())));
+            return;
+        }
+
+        match *value {
+            Value::String(ref text) => self.check_length(text.chars().count(), &mut violation),
+            Value::Number(ref number) => self.check_range(number.as_f64().unwrap_or_default(), &mut violation),
+            Value::Array(ref items) => self.check_items(items, path, violations),
+            Value::Object(ref object) => self.check_properties(object, path, violations),
+            Value::Null | Value::Bool(_) => {},
+        }
+    }
+
+    /// Check the length of a string.
+    fn check_length<F: FnMut(String)>(&self, length: usize, violation: &mut F) {
+        if let Some(min) = self.min_length
+            && length < min
+        {
+            violation(format!("Must be at least {} characters long", min));
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            violation(format!("Must be at most {} characters long", max));
+        }
+    }
+
+    /// Check the range of a number.
+    fn check_range<F: FnMut(String)>(&self, number: f64, violation: &mut F) {
+        if let Some(min) = self.minimum
+            && number < min
+        {
+            violation(format!("Must be at least {}", min));
+        }
+        if let Some(max) = self.maximum
+            && number > max
+        {
+            violation(format!("Must be at most {}", max));
+        }
+    }
+
+    /// Check the length and items of an array.
+    fn check_items(&self, items: &[Value], path: &str, violations: &mut Vec<SchemaViolation>) {
+        let mut message = None;
+        if self.min_items.is_some_and(|min| items.len() < min) {
+            message = Some(format!("Must have at least {} items", self.min_items.unwrap_or_default()));
+        } else if self.max_items.is_some_and(|max| items.len() > max) {
+            message = Some(format!("Must have at most {} items", self.max_items.unwrap_or_default()));
+        }
+        if let Some(message) = message {
+            violations.push(SchemaViolation {
+                path: path.to_owned(),
+                message,
+            });
+        }
+
+        if let Some(schema) = self.items.as_ref() {
+            for (index, item) in items.iter().enumerate() {
+                schema.check_at(item, &format!("{}/{}", path, index), violations);
+            }
+        }
+    }
+
+    /// Check the properties of an object.
+    fn check_properties(&self, object: &Map<String, Value>, path: &str, violations: &mut Vec<SchemaViolation>) {
+        for name in self.required.iter().filter(|name| !object.contains_key(*name)) {
+            violations.push(SchemaViolation {
+                path: format!("{}/{}", path, pointer_token(name)),
+                message: "Is required".to_owned(),
+            });
+        }
+
+        for (name, value) in object {
+            let property_path = format!("{}/{}", path, pointer_token(name));
+            if let Some(schema) = self.properties.get(name) {
+                schema.check_at(value, &property_path, violations);
+            } else if !self.additional_properties {
+                violations.push(SchemaViolation {
+                    path: property_path,
+                    message: "Is not allowed".to_owned(),
+                });
+            }
+        }
+    }
+}
+
+/// Get the name of a value's type.
+const fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escape an object key for use in a JSON pointer.
+fn pointer_token(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> RequestSchema {
+        serde_json::from_value(json!({
+            "body": {
+                "type": "object",
+                "required": ["title"],
+                "additionalProperties": false,
+                "properties": {
+                    "title": { "type": "string", "minLength": 1, "maxLength": 20 },
+                    "priority": { "type": "integer", "minimum": 1, "maximum": 5 },
+                    "tags": { "type": "array", "maxItems": 2, "items": { "type": "string" } },
+                    "due": { "type": "string", "nullable": true }
+                }
+            },
+            "query": {
+                "type": "object",
+                "properties": {
+                    "page": { "type": "integer", "minimum": 1 },
+                    "sort": { "type": "string", "enum": ["asc", "desc"] }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_request() {
+        let schema = schema();
+        schema.validate().unwrap();
+
+        let query = HashMap::from([("page".to_owned(), "2".to_owned()), ("sort".to_owned(), "asc".to_owned())]);
+        let body = json!({ "title": "Write docs", "priority": 3, "tags": ["docs"], "due": null });
+        assert!(schema.check(&query, &body).is_empty());
+    }
+
+    #[test]
+    fn test_request_violations() {
+        let schema = schema();
+        let query = HashMap::from([("page".to_owned(), "zero".to_owned())]);
+        let body = json!({ "priority": 9, "tags": ["a", 2, "c"], "color": "red" });
+
+        let violations = schema.check(&query, &body);
+        let found: Vec<(RequestPart, &str)> = violations
+            .iter()
+            .map(|violation| (violation.location, violation.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (RequestPart::Query, "/page"),
+                (RequestPart::Body, "/title"),
+                (RequestPart::Body, "/color"),
+                (RequestPart::Body, "/priority"),
+                (RequestPart::Body, "/tags"),
+                (RequestPart::Body, "/tags/1"),
+            ]
+        );
+        assert_eq!(violations[0].message, "Expected integer, found string");
+    }
+
+    #[test]
+    fn test_contradictory_schema() {
+        let schema: RequestSchema = serde_json::from_value(json!({
+            "body": {
+                "type": "object",
+                "properties": { "name": { "type": "string", "minLength": 5, "maxLength": 2 } }
+            }
+        }))
+        .unwrap();
+        assert!(schema.validate().unwrap_err().to_string().contains("/name"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Size and host usage analysis of plugin modules.
+//!
+//! Breaks a WASM module down by section, function and crate, lists the host
+//! functions it imports with the permissions they require, and compares two
+//! builds to catch size regressions. Function names come from the `name`
+//! custom section, so functions of stripped modules are left unnamed.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use wasmparser::{KnownCustom, Name, Parser, Payload, TypeRef};
+
+use orbis_plugin_api::security::required_permission;
+use orbis_plugin_api::PluginPermission;
+
+/// Module host functions are imported from.
+const HOST_MODULE: &str = "env";
+
+/// Crate of functions without a name, or whose name has no path.
+pub const UNKNOWN_CRATE: &str = "<unknown>";
+
+/// Size of a module section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionSize {
+    /// Section name (`custom:<name>` for custom sections).
+    pub name: String,
+
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Size of a function body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionSize {
+    /// Demangled function name (`function[<index>]` if unnamed).
+    pub name: String,
+
+    /// Crate the function belongs to.
+    pub crate_name: String,
+
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Size of the functions of a crate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrateSize {
+    /// Crate name.
+    pub name: String,
+
+    /// Size of the crate's function bodies, in bytes.
+    pub size: u64,
+
+    /// Number of functions.
+    pub functions: usize,
+}
+
+/// Breakdown of a plugin module.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleAnalysis {
+    /// Module size in bytes.
+    pub size: u64,
+
+    /// Sections, largest first.
+    pub sections: Vec<SectionSize>,
+
+    /// Function bodies, largest first.
+    pub functions: Vec<FunctionSize>,
+
+    /// Crates, largest first.
+    pub crates: Vec<CrateSize>,
+
+    /// Host functions imported, sorted.
+    pub host_functions: Vec<String>,
+}
+
+/// Permissions a module uses, compared with those its manifest declares.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PermissionUsage {
+    /// Permissions required by imported host functions.
+    pub used: Vec<PluginPermission>,
+
+    /// Declared permissions no imported host function requires.
+    pub unused: Vec<PluginPermission>,
+
+    /// Permissions required by imported host functions but not declared;
+    /// calls to those functions are denied.
+    pub missing: Vec<PluginPermission>,
+}
+
+/// Size change of a section or crate between two builds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeChange {
+    /// Section or crate name.
+    pub name: String,
+
+    /// Size in the baseline build.
+    pub before: u64,
+
+    /// Size in the new build.
+    pub after: u64,
+
+    /// Difference in bytes (negative if smaller).
+    pub change: i64,
+}
+
+/// Size changes between two builds of a module.
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeDiff {
+    /// Module size change.
+    pub total: SizeChange,
+
+    /// Changed sections, largest change first.
+    pub sections: Vec<SizeChange>,
+
+    /// Changed crates, largest change first.
+    pub crates: Vec<SizeChange>,
+}
+
+impl ModuleAnalysis {
+    /// Analyze a WASM module.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module cannot be parsed.
+    pub fn new(wasm: &[u8]) -> orbis_core::Result<Self> {
+        let parse_error = |e: wasmparser::BinaryReaderError| {
+            orbis_core::Error::plugin(format!("Failed to parse WASM: {}", e))
+        };
+
+        let mut sections = Vec::new();
+        let mut host_functions = Vec::new();
+        let mut imported_functions: u32 = 0;
+        let mut bodies = Vec::new();
+        let mut names = HashMap::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            let payload = payload.map_err(parse_error)?;
+            if let Some((id, range)) = payload.as_section() {
+                let name = if let Payload::CustomSection(ref reader) = payload {
+                    format!("custom:{}", reader.name())
+                } else {
+                    section_name(id).to_owned()
+                };
+                sections.push(SectionSize {
+                    name,
+                    size: byte_count(range.len()),
+                });
+            }
+
+            if let Payload::ImportSection(ref reader) = payload {
+                for import in reader.clone() {
+                    let import = import.map_err(parse_error)?;
+                    if !matches!(import.ty, TypeRef::Func(_)) {
+                        continue;
+                    }
+                    imported_functions = imported_functions.saturating_add(1);
+                    if import.module == HOST_MODULE {
+                        host_functions.push(import.name.to_owned());
+                    }
+                }
+            } else if let Payload::CodeSectionEntry(ref body) = payload {
+                bodies.push(byte_count(body.range().len()));
+            } else if let Payload::CustomSection(ref reader) = payload
+                && let KnownCustom::Name(subsections) = reader.as_known()
+            {
+                function_names(subsections, &mut names).map_err(parse_error)?;
+            }
+        }
+
+        let mut functions: Vec<FunctionSize> = (0u32..)
+            .zip(bodies)
+            .map(|(position, size)| {
+                let index = imported_functions.saturating_add(position);
+                let name = names
+                    .remove(&index)
+                    .unwrap_or_else(|| format!("function[{}]", index));
+                FunctionSize {
+                    crate_name: crate_of(&name).to_owned(),
+                    name,
+                    size,
+                }
+            })
+            .collect();
+        functions.sort_by_key(|function| std::cmp::Reverse(function.size));
+
+        let mut crates: BTreeMap<&str, CrateSize> = BTreeMap::new();
+        for function in &functions {
+            let entry = crates.entry(&function.crate_name).or_insert_with(|| CrateSize {
+                name: function.crate_name.clone(),
+                size: 0,
+                functions: 0,
+            });
+            entry.size = entry.size.saturating_add(function.size);
+            entry.functions = entry.functions.saturating_add(1);
+        }
+        let mut crates: Vec<CrateSize> = crates.into_values().collect();
+        crates.sort_by_key(|krate| std::cmp::Reverse(krate.size));
+
+        sections.sort_by_key(|section| std::cmp::Reverse(section.size));
+        host_functions.sort();
+        host_functions.dedup();
+
+        Ok(Self {
+            size: byte_count(wasm.len()),
+            sections,
+            functions,
+            crates,
+            host_functions,
+        })
+    }
+
+    /// Get the permissions required by the imported host functions.
+    #[must_use]
+    pub fn used_permissions(&self) -> Vec<PluginPermission> {
+        let mut used = Vec::new();
+        for permission in self.host_functions.iter().filter_map(|function| required_permission(function)) {
+            if !used.contains(&permission) {
+                used.push(permission);
+            }
+        }
+        used
+    }
+
+    /// Compare the permissions the module uses with the declared ones.
+    #[must_use]
+    pub fn permissions(&self, declared: &[PluginPermission]) -> PermissionUsage {
+        let used = self.used_permissions();
+        PermissionUsage {
+            unused: declared
+                .iter()
+                .filter(|permission| !used.contains(permission))
+                .cloned()
+                .collect(),
+            missing: used
+                .iter()
+                .filter(|permission| !declared.contains(permission))
+                .cloned()
+                .collect(),
+            used,
+        }
+    }
+
+    /// Get the size changes from a baseline build.
+    #[must_use]
+    pub fn diff(&self, baseline: &Self) -> SizeDiff {
+        let sections = |analysis: &Self| -> BTreeMap<String, u64> {
+            let mut sizes = BTreeMap::new();
+            for section in &analysis.sections {
+                let size: &mut u64 = sizes.entry(section.name.clone()).or_default();
+                *size = size.saturating_add(section.size);
+            }
+            sizes
+        };
+        let crates = |analysis: &Self| -> BTreeMap<String, u64> {
+            analysis
+                .crates
+                .iter()
+                .map(|krate| (krate.name.clone(), krate.size))
+                .collect()
+        };
+
+        SizeDiff {
+            total: SizeChange::new("total".to_owned(), baseline.size, self.size),
+            sections: size_changes(sections(baseline), sections(self)),
+            crates: size_changes(crates(baseline), crates(self)),
+        }
+    }
+}
+
+impl SizeChange {
+    /// Create a size change.
+    fn new(name: String, before: u64, after: u64) -> Self {
+        let signed = |size: u64| i64::try_from(size).unwrap_or(i64::MAX);
+        Self {
+            name,
+            before,
+            after,
+            change: signed(after).saturating_sub(signed(before)),
+        }
+    }
+}
+
+/// Read the function names of a `name` section.
+fn function_names(
+    subsections: wasmparser::NameSectionReader<'_>,
+    names: &mut HashMap<u32, String>,
+) -> Result<(), wasmparser::BinaryReaderError> {
+    for subsection in subsections {
+        let Name::Function(map) = subsection? else {
+            continue;
+        };
+        for naming in map {
+            let naming = naming?;
+            names.insert(naming.index, demangle(naming.name));
+        }
+    }
+    Ok(())
+}
+
+/// Get the changed sizes between two sets of named sizes, largest change first.
+fn size_changes(before: BTreeMap<String, u64>, mut after: BTreeMap<String, u64>) -> Vec<SizeChange> {
+    let mut changes: Vec<SizeChange> = before
+        .into_iter()
+        .map(|(name, size)| {
+            let new_size = after.remove(&name).unwrap_or_default();
+            SizeChange::new(name, size, new_size)
+        })
+        .collect();
+    changes.extend(after.into_iter().map(|(name, size)| SizeChange::new(name, 0, size)));
+    changes.retain(|change| change.change != 0);
+    changes.sort_by_key(|change| std::cmp::Reverse(change.change.unsigned_abs()));
+    changes
+}
+
+/// Convert a length to a byte count.
+fn byte_count(len: usize) -> u64 {
+    u64::try_from(len).unwrap_or(u64::MAX)
+}
+
+/// Get the name of a known section.
+const fn section_name(id: u8) -> &'static str {
+    match id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+/// Demangle a legacy Rust symbol (`_ZN...E`) without its hash; other names
+/// are returned as they are.
+fn demangle(name: &str) -> String {
+    let Some(mut rest) = name.strip_prefix("_ZN") else {
+        return name.to_owned();
+    };
+
+    let mut segments = Vec::new();
+    while let Some(digits) = rest.find(|c: char| !c.is_ascii_digit()).filter(|digits| *digits > 0) {
+        let Some(len) = rest.get(..digits).and_then(|len| len.parse::<usize>().ok()) else {
+            break;
+        };
+        let end = digits.saturating_add(len);
+        let Some(segment) = rest.get(digits..end) else {
+            break;
+        };
+        segments.push(segment);
+        rest = rest.get(end..).unwrap_or_default();
+    }
+    if rest != "E" || segments.is_empty() {
+        return name.to_owned();
+    }
+
+    let is_hash = |segment: &&str| {
+        segment.len() == 17
+            && segment.starts_with('h')
+            && segment.chars().skip(1).all(|c| c.is_ascii_hexdigit())
+    };
+    if segments.last().is_some_and(is_hash) {
+        segments.pop();
+    }
+    segments.join("::")
+}
+
+/// Get the crate of a demangled function name.
+fn crate_of(name: &str) -> &str {
+    let path = name.trim_start_matches('<');
+    path.find("::")
+        .filter(|end| *end > 0)
+        .and_then(|end| path.get(..end))
+        .unwrap_or(UNKNOWN_CRATE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a section (sizes must fit a single LEB128 byte).
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![id, u8::try_from(contents.len()).unwrap()];
+        bytes.extend_from_slice(contents);
+        bytes
+    }
+
+    /// Encode a string (length must fit a single LEB128 byte).
+    fn string(value: &str) -> Vec<u8> {
+        let mut bytes = vec![u8::try_from(value.len()).unwrap()];
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    /// Module importing `db_query` and `log`, with two named functions of
+    /// `nops` extra bytes each.
+    fn module(nops: [usize; 2]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.extend(section(1, &[1, 0x60, 0, 0]));
+
+        let mut imports = vec![2];
+        for name in ["db_query", "log"] {
+            imports.extend(string("env"));
+            imports.extend(string(name));
+            imports.extend([0, 0]);
+        }
+        wasm.extend(section(2, &imports));
+        wasm.extend(section(3, &[2, 0, 0]));
+
+        let mut code = vec![2];
+        for count in nops {
+            let mut body = vec![0];
+            body.extend(std::iter::repeat_n(0x01, count));
+            body.push(0x0b);
+            code.extend(string_bytes(&body));
+        }
+        wasm.extend(section(10, &code));
+
+        let mut function_names = vec![2, 2];
+        function_names.extend(string("_ZN9my_plugin6handle17h0123456789abcdefE"));
+        function_names.push(3);
+        function_names.extend(string("core::fmt::write"));
+        let mut names = string("name");
+        names.push(1);
+        names.extend(string_bytes(&function_names));
+        wasm.extend(section(0, &names));
+        wasm
+    }
+
+    /// Prefix bytes with their length.
+    fn string_bytes(bytes: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![u8::try_from(bytes.len()).unwrap()];
+        encoded.extend_from_slice(bytes);
+        encoded
+    }
+
+    #[test]
+    fn test_module_breakdown() {
+        let wasm = module([10, 2]);
+        let analysis = ModuleAnalysis::new(&wasm).unwrap();
+
+        assert_eq!(analysis.size, wasm.len() as u64);
+        assert_eq!(analysis.host_functions, vec!["db_query", "log"]);
+        assert!(analysis.sections.iter().any(|section| section.name == "custom:name"));
+        assert_eq!(analysis.functions[0].name, "my_plugin::handle");
+        assert_eq!(analysis.functions[0].crate_name, "my_plugin");
+        assert_eq!(analysis.functions[0].size, 12);
+        assert_eq!(analysis.functions[1].crate_name, "core");
+        assert_eq!(analysis.crates[0].name, "my_plugin");
+
+        let usage = analysis.permissions(&[PluginPermission::DatabaseRead, PluginPermission::Network]);
+        assert_eq!(usage.used, vec![PluginPermission::DatabaseRead]);
+        assert_eq!(usage.unused, vec![PluginPermission::Network]);
+        assert!(usage.missing.is_empty());
+        assert_eq!(analysis.permissions(&[]).missing, vec![PluginPermission::DatabaseRead]);
+
+        ModuleAnalysis::new(b"\0asm\x01\0\0\0\x0a\x05").unwrap_err();
+    }
+
+    #[test]
+    fn test_size_diff() {
+        let baseline = ModuleAnalysis::new(&module([10, 2])).unwrap();
+        let analysis = ModuleAnalysis::new(&module([30, 2])).unwrap();
+        let diff = analysis.diff(&baseline);
+
+        assert_eq!(diff.total.change, 20);
+        assert_eq!(diff.crates.len(), 1);
+        assert_eq!(diff.crates[0].name, "my_plugin");
+        assert_eq!(diff.crates[0].change, 20);
+        assert!(diff.sections.iter().any(|section| section.name == "code" && section.change == 20));
+        assert!(analysis.diff(&analysis).sections.is_empty());
+    }
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(demangle("_ZN4core3fmt5write17h0123456789abcdefE"), "core::fmt::write");
+        assert_eq!(demangle("_ZN4core3fmtE"), "core::fmt");
+        assert_eq!(demangle("_ZN4core3fm"), "_ZN4core3fm");
+        assert_eq!(demangle("memcpy"), "memcpy");
+        assert_eq!(crate_of("<alloc::vec::Vec<T> as core::ops::Drop>::drop"), "alloc");
+        assert_eq!(crate_of("memcpy"), UNKNOWN_CRATE);
+    }
+}
@@ -0,0 +1,613 @@
+//! Portable plugin data archives.
+//!
+//! A plugin's namespaced data (key-value state, per-tenant state, database
+//! tables and data files) can be exported into a ZIP archive and imported on
+//! another instance, e.g. when moving from a standalone to a server deployment.
+//!
+//! Archive layout:
+//!
+//! ```text
+//! manifest.json          format version, plugin name and version, export time
+//! state.json             plugin state
+//! tenants/<tenant>.json  state of each tenant
+//! tables/<table>.json    rows of each plugin table, as JSON objects
+//! files/<path>           plugin data files
+//! ```
+//!
+//! Imported archives are held to the same [`ArchiveLimits`] as packed plugins:
+//! a bounded number of entries and decompressed bytes.
+
+use crate::packed::{ArchiveLimits, ArchiveViolation};
+use crate::storage::ObjectStore;
+use crate::PluginManifest;
+use chrono::{DateTime, Utc};
+use orbis_db::{Database, DatabasePool};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+
+/// Current archive format version.
+pub const DATA_ARCHIVE_FORMAT: u32 = 1;
+
+/// Name of the archive manifest entry.
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Name of the plugin state entry.
+const STATE_ENTRY: &str = "state.json";
+
+/// Prefix of tenant state entries.
+const TENANTS_PREFIX: &str = "tenants/";
+
+/// Prefix of table entries.
+const TABLES_PREFIX: &str = "tables/";
+
+/// Prefix of data file entries.
+const FILES_PREFIX: &str = "files/";
+
+/// Key-value state of a plugin scope.
+pub type StateData = HashMap<String, serde_json::Value>;
+
+/// Metadata stored in the archive manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchiveManifest {
+    /// Archive format version.
+    format: u32,
+
+    /// Plugin the data belongs to.
+    plugin: String,
+
+    /// Plugin version at export time, if known.
+    plugin_version: Option<String>,
+
+    /// Export time.
+    exported_at: DateTime<Utc>,
+}
+
+/// Exported data of a plugin.
+#[derive(Debug, Clone)]
+pub struct PluginDataArchive {
+    /// Plugin the data belongs to.
+    pub plugin: String,
+
+    /// Plugin version at export time, if known.
+    pub plugin_version: Option<String>,
+
+    /// Export time.
+    pub exported_at: DateTime<Utc>,
+
+    /// Plugin state.
+    pub state: StateData,
+
+    /// State of each tenant, keyed by tenant ID.
+    pub tenants: BTreeMap<String, StateData>,
+
+    /// Rows of each plugin table, keyed by table name.
+    pub tables: BTreeMap<String, Vec<serde_json::Value>>,
+
+    /// Data files, keyed by `/`-separated path relative to the plugin's files directory.
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl PluginDataArchive {
+    /// Create an empty archive for a plugin.
+    #[must_use]
+    pub fn new(plugin: &str) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            plugin_version: None,
+            exported_at: Utc::now(),
+            state: StateData::new(),
+            tenants: BTreeMap::new(),
+            tables: BTreeMap::new(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Encode the archive as ZIP bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry cannot be serialized or written.
+    pub fn to_zip(&self) -> orbis_core::Result<Vec<u8>> {
+        let manifest = ArchiveManifest {
+            format: DATA_ARCHIVE_FORMAT,
+            plugin: self.plugin.clone(),
+            plugin_version: self.plugin_version.clone(),
+            exported_at: self.exported_at,
+        };
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        write_json(&mut writer, MANIFEST_ENTRY, &manifest)?;
+        write_json(&mut writer, STATE_ENTRY, &self.state)?;
+        for (tenant, state) in &self.tenants {
+            write_json(&mut writer, &format!("{}{}.json", TENANTS_PREFIX, tenant), state)?;
+        }
+        for (table, rows) in &self.tables {
+            write_json(&mut writer, &format!("{}{}.json", TABLES_PREFIX, table), rows)?;
+        }
+        for (path, contents) in &self.files {
+            write_entry(&mut writer, &format!("{}{}", FILES_PREFIX, path), contents)?;
+        }
+
+        let cursor = writer
+            .finish()
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to write data archive: {}", e)))?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Decode an archive from ZIP bytes, within the default limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, uses an unsupported format
+    /// version, contains unsafe paths or exceeds the limits.
+    pub fn from_zip(bytes: &[u8]) -> orbis_core::Result<Self> {
+        Self::from_zip_with_limits(bytes, ArchiveLimits::DEFAULT)
+    }
+
+    /// Decode an archive from ZIP bytes, within the given limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, uses an unsupported format
+    /// version, contains unsafe paths or exceeds the limits.
+    pub fn from_zip_with_limits(bytes: &[u8], limits: ArchiveLimits) -> orbis_core::Result<Self> {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid data archive: {}", e)))?;
+        if zip.len() > limits.max_entries {
+            return Err(ArchiveViolation::TooManyEntries {
+                count: zip.len(),
+                limit: limits.max_entries,
+            }
+            .into());
+        }
+
+        let mut budget = EntryBudget { limits, read: 0 };
+        let manifest: ArchiveManifest = serde_json::from_slice(&budget.read_entry(&mut zip, MANIFEST_ENTRY)?)
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid data archive manifest: {}", e)))?;
+        if manifest.format != DATA_ARCHIVE_FORMAT {
+            return Err(orbis_core::Error::validation(format!(
+                "Unsupported data archive format {} (expected {})",
+                manifest.format, DATA_ARCHIVE_FORMAT
+            )));
+        }
+
+        let mut archive = Self::new(&manifest.plugin);
+        archive.plugin_version = manifest.plugin_version;
+        archive.exported_at = manifest.exported_at;
+
+        let names: Vec<String> = zip.file_names().map(String::from).collect();
+        for name in names {
+            if name.ends_with('/') || name == MANIFEST_ENTRY {
+                continue;
+            }
+            if !is_safe_path(&name) {
+                return Err(orbis_core::Error::validation(format!("Unsafe path in data archive: {}", name)));
+            }
+
+            let contents = budget.read_entry(&mut zip, &name)?;
+            if name == STATE_ENTRY {
+                archive.state = parse_json(&name, &contents)?;
+            } else if let Some(tenant) = json_entry_name(&name, TENANTS_PREFIX) {
+                archive.tenants.insert(tenant.to_string(), parse_json(&name, &contents)?);
+            } else if let Some(table) = json_entry_name(&name, TABLES_PREFIX) {
+                archive.tables.insert(table.to_string(), parse_json(&name, &contents)?);
+            } else if let Some(path) = name.strip_prefix(FILES_PREFIX) {
+                archive.files.insert(path.to_string(), contents);
+            } else {
+                return Err(orbis_core::Error::validation(format!("Unexpected entry in data archive: {}", name)));
+            }
+        }
+
+        Ok(archive)
+    }
+}
+
+/// Write a JSON entry to a ZIP archive.
+fn write_json<W: Write + std::io::Seek, T: Serialize>(
+    writer: &mut zip::ZipWriter<W>,
+    name: &str,
+    value: &T,
+) -> orbis_core::Result<()> {
+    let contents = serde_json::to_vec_pretty(value).map_err(|e| orbis_core::Error::serialization(e.to_string()))?;
+    write_entry(writer, name, &contents)
+}
+
+/// Write an entry to a ZIP archive.
+fn write_entry<W: Write + std::io::Seek>(
+    writer: &mut zip::ZipWriter<W>,
+    name: &str,
+    contents: &[u8],
+) -> orbis_core::Result<()> {
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    writer
+        .start_file(name, options)
+        .map_err(|e| orbis_core::Error::plugin(format!("Failed to write '{}' to data archive: {}", name, e)))?;
+    writer
+        .write_all(contents)
+        .map_err(|e| orbis_core::Error::plugin(format!("Failed to write '{}' to data archive: {}", name, e)))
+}
+
+/// Decompressed bytes read so far from an archive, against its limits.
+struct EntryBudget {
+    /// Limits of the archive.
+    limits: ArchiveLimits,
+
+    /// Decompressed bytes read so far.
+    read: u64,
+}
+
+impl EntryBudget {
+    /// Read an entry of a ZIP archive, within the remaining size budget.
+    fn read_entry<R: Read + std::io::Seek>(
+        &mut self,
+        zip: &mut zip::ZipArchive<R>,
+        name: &str,
+    ) -> orbis_core::Result<Vec<u8>> {
+        let remaining = self.limits.max_size.saturating_sub(self.read);
+        let file = zip
+            .by_name(name)
+            .map_err(|e| orbis_core::Error::validation(format!("Missing '{}' in data archive: {}", name, e)))?;
+
+        // Declared sizes can lie, so read one byte past the budget to catch an overrun
+        let mut contents = Vec::new();
+        let copied = file
+            .take(remaining.saturating_add(1))
+            .read_to_end(&mut contents)
+            .map_err(|e| orbis_core::Error::validation(format!("Failed to read '{}' from data archive: {}", name, e)))?;
+        let copied = u64::try_from(copied).unwrap_or(u64::MAX);
+        if copied > remaining {
+            return Err(ArchiveViolation::TooLarge {
+                limit: self.limits.max_size,
+            }
+            .into());
+        }
+
+        self.read = self.read.saturating_add(copied);
+        Ok(contents)
+    }
+}
+
+/// Parse a JSON entry.
+fn parse_json<T: serde::de::DeserializeOwned>(name: &str, contents: &[u8]) -> orbis_core::Result<T> {
+    serde_json::from_slice(contents)
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid '{}' in data archive: {}", name, e)))
+}
+
+/// Get the name of a `<prefix><name>.json` entry, if the entry matches.
+fn json_entry_name<'a>(entry: &'a str, prefix: &str) -> Option<&'a str> {
+    entry
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .filter(|name| is_identifier(name))
+}
+
+/// Check whether a name only uses letters, digits, '-' and '_'.
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check that an archive path stays inside the directory it is extracted to.
+fn is_safe_path(path: &str) -> bool {
+    !path.contains('\\')
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Read every object under a key prefix, keyed by `/`-separated path relative to it.
+pub async fn read_files(store: &dyn ObjectStore, prefix: &str) -> orbis_core::Result<BTreeMap<String, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    for object in store.list(prefix).await? {
+        let Some(path) = object.key.strip_prefix(prefix) else {
+            continue;
+        };
+        if let Some(contents) = store.get(&object.key).await? {
+            files.insert(path.to_string(), contents);
+        }
+    }
+    Ok(files)
+}
+
+/// Replace the objects under a key prefix with the given files.
+pub async fn write_files(
+    store: &dyn ObjectStore,
+    prefix: &str,
+    files: &BTreeMap<String, Vec<u8>>,
+) -> orbis_core::Result<()> {
+    if let Some(path) = files.keys().find(|path| !is_safe_path(path)) {
+        return Err(orbis_core::Error::validation(format!("Unsafe data file path: {}", path)));
+    }
+
+    for object in store.list(prefix).await? {
+        let replaced = object
+            .key
+            .strip_prefix(prefix)
+            .is_some_and(|path| files.contains_key(path));
+        if !replaced {
+            store.delete(&object.key).await?;
+        }
+    }
+
+    for (path, contents) in files {
+        store.put(&format!("{}{}", prefix, path), contents.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Export the rows of every table owned by a plugin.
+pub async fn export_tables(
+    db: &Database,
+    manifest: &PluginManifest,
+) -> orbis_core::Result<BTreeMap<String, Vec<serde_json::Value>>> {
+    let mut tables = BTreeMap::new();
+
+    for table in plugin_tables(db, manifest).await? {
+        let rows: Vec<String> = match db.pool() {
+            DatabasePool::Postgres(pool) => {
+                sqlx::query_scalar(&format!("SELECT row_to_json(t)::text FROM \"{}\" t", table))
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+            }
+            DatabasePool::Sqlite(pool) => {
+                let columns = sqlite_columns(pool, &table).await?;
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|column| format!("'{}', \"{}\"", column, column))
+                    .collect();
+                sqlx::query_scalar(&format!("SELECT json_object({}) FROM \"{}\"", fields.join(", "), table))
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?
+            }
+        };
+
+        let rows = rows
+            .iter()
+            .map(|row| serde_json::from_str(row).map_err(|e| orbis_core::Error::serialization(e.to_string())))
+            .collect::<orbis_core::Result<Vec<serde_json::Value>>>()?;
+        tables.insert(table, rows);
+    }
+
+    Ok(tables)
+}
+
+/// Replace the rows of a plugin's tables with the exported ones, in one transaction.
+///
+/// Every table must already exist (created by the plugin's migrations) and
+/// be owned by the plugin.
+pub async fn import_tables(
+    db: &Database,
+    manifest: &PluginManifest,
+    tables: &BTreeMap<String, Vec<serde_json::Value>>,
+) -> orbis_core::Result<()> {
+    if tables.is_empty() {
+        return Ok(());
+    }
+
+    let existing = plugin_tables(db, manifest).await?;
+    if let Some(table) = tables.keys().find(|table| !existing.contains(table)) {
+        return Err(orbis_core::Error::validation(format!(
+            "Table '{}' does not exist or is not owned by plugin '{}'",
+            table, manifest.name
+        )));
+    }
+
+    match db.pool() {
+        DatabasePool::Postgres(pool) => {
+            let mut tx = pool.begin().await.map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            for (table, rows) in tables {
+                sqlx::query(&format!("DELETE FROM \"{}\"", table))
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                let insert = format!(
+                    "INSERT INTO \"{0}\" SELECT * FROM json_populate_record(NULL::\"{0}\", $1::json)",
+                    table
+                );
+                for row in rows {
+                    sqlx::query(&insert)
+                        .bind(row.to_string())
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                }
+            }
+            tx.commit().await.map_err(|e| orbis_core::Error::database(e.to_string()))?;
+        }
+        DatabasePool::Sqlite(pool) => {
+            let mut inserts = Vec::with_capacity(tables.len());
+            for table in tables.keys() {
+                let columns = sqlite_columns(pool, table).await?;
+                let names: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+                let values: Vec<String> = columns
+                    .iter()
+                    .map(|column| format!("json_extract($1, '$.\"{}\"')", column))
+                    .collect();
+                inserts.push(format!(
+                    "INSERT INTO \"{}\" ({}) VALUES ({})",
+                    table,
+                    names.join(", "),
+                    values.join(", ")
+                ));
+            }
+
+            let mut tx = pool.begin().await.map_err(|e| orbis_core::Error::database(e.to_string()))?;
+            for ((table, rows), insert) in tables.iter().zip(&inserts) {
+                sqlx::query(&format!("DELETE FROM \"{}\"", table))
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                for row in rows {
+                    sqlx::query(insert)
+                        .bind(row.to_string())
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                }
+            }
+            tx.commit().await.map_err(|e| orbis_core::Error::database(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List the existing tables owned by a plugin: those its manifest lists.
+async fn plugin_tables(db: &Database, manifest: &PluginManifest) -> orbis_core::Result<Vec<String>> {
+    if manifest.tables.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let names: Vec<String> = match db.pool() {
+        DatabasePool::Postgres(pool) => sqlx::query_scalar(
+            "SELECT table_name::text FROM information_schema.tables \
+             WHERE table_schema = current_schema() AND table_type = 'BASE TABLE'",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?,
+        DatabasePool::Sqlite(pool) => sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| orbis_core::Error::database(e.to_string()))?,
+    };
+
+    Ok(names
+        .into_iter()
+        .filter(|name| manifest.tables.contains(name) && is_identifier(name))
+        .collect())
+}
+
+/// Get the column names of a SQLite table.
+async fn sqlite_columns(pool: &sqlx::SqlitePool, table: &str) -> orbis_core::Result<Vec<String>> {
+    sqlx::query_scalar("SELECT name FROM pragma_table_info($1)")
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| orbis_core::Error::database(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let mut archive = PluginDataArchive::new("my-plugin");
+        archive.plugin_version = Some("1.2.0".to_string());
+        archive.state.insert("counter".to_string(), json!(3));
+        archive
+            .tenants
+            .insert("acme".to_string(), StateData::from([("counter".to_string(), json!(7))]));
+        archive
+            .tables
+            .insert("plugin_my_plugin_items".to_string(), vec![json!({"id": 1, "name": "first"})]);
+        archive.files.insert("exports/report.csv".to_string(), b"id,name\n1,first\n".to_vec());
+
+        let decoded = PluginDataArchive::from_zip(&archive.to_zip().unwrap()).unwrap();
+        assert_eq!(decoded.plugin, "my-plugin");
+        assert_eq!(decoded.plugin_version.as_deref(), Some("1.2.0"));
+        assert_eq!(decoded.state, archive.state);
+        assert_eq!(decoded.tenants, archive.tenants);
+        assert_eq!(decoded.tables, archive.tables);
+        assert_eq!(decoded.files, archive.files);
+    }
+
+    #[test]
+    fn test_archive_rejects_unsafe_paths() {
+        let mut archive = PluginDataArchive::new("my-plugin");
+        archive.files.insert("../escape.txt".to_string(), b"nope".to_vec());
+
+        PluginDataArchive::from_zip(&archive.to_zip().unwrap()).unwrap_err();
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(is_safe_path("exports/report.csv"));
+    }
+
+    #[test]
+    fn test_archive_limits() {
+        let mut archive = PluginDataArchive::new("my-plugin");
+        archive.files.insert("big.bin".to_string(), vec![0; 64 * 1024]);
+        archive.files.insert("small.txt".to_string(), b"ok".to_vec());
+        let bytes = archive.to_zip().unwrap();
+
+        // A highly compressible entry decompressing past the limit is cut off
+        let limits = ArchiveLimits {
+            max_entries: 10,
+            max_size: 16 * 1024,
+        };
+        let error = PluginDataArchive::from_zip_with_limits(&bytes, limits).unwrap_err();
+        assert!(error.to_string().contains("decompresses to more than"));
+
+        let limits = ArchiveLimits {
+            max_entries: 2,
+            max_size: 1024 * 1024,
+        };
+        let error = PluginDataArchive::from_zip_with_limits(&bytes, limits).unwrap_err();
+        assert!(error.to_string().contains("entries"));
+
+        let decoded = PluginDataArchive::from_zip(&bytes).unwrap();
+        assert_eq!(decoded.files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tables_of_plugins_sharing_a_prefix() {
+        let dir = std::env::temp_dir().join(format!("orbis-archive-tables-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(orbis_config::DatabaseConfig {
+            path: Some(dir.join("orbis.db")),
+            ..orbis_config::DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        let DatabasePool::Sqlite(pool) = db.pool() else {
+            unreachable!("test databases are SQLite");
+        };
+        for table in ["plugin_my_notes", "plugin_my_plugin_notes"] {
+            sqlx::query(&format!("CREATE TABLE {} (id INTEGER PRIMARY KEY, body TEXT)", table))
+                .execute(pool)
+                .await
+                .unwrap();
+            sqlx::query(&format!("INSERT INTO {} (id, body) VALUES (1, '{}')", table, table))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        // `my` shares the prefix of `my-plugin`'s tables, but only owns the one it lists
+        let manifest = |name: &str, tables: &[&str]| -> PluginManifest {
+            serde_json::from_value(json!({ "name": name, "version": "1.0.0", "tables": tables })).unwrap()
+        };
+        let my = manifest("my", &["plugin_my_notes"]);
+        let my_plugin = manifest("my-plugin", &["plugin_my_plugin_notes"]);
+        my.validate().unwrap();
+        my_plugin.validate().unwrap();
+
+        let exported = export_tables(&db, &my).await.unwrap();
+        assert_eq!(exported.keys().collect::<Vec<_>>(), vec!["plugin_my_notes"]);
+        let exported = export_tables(&db, &my_plugin).await.unwrap();
+        assert_eq!(exported.keys().collect::<Vec<_>>(), vec!["plugin_my_plugin_notes"]);
+
+        // Importing another plugin's table is refused without touching it
+        let tables = BTreeMap::from([("plugin_my_plugin_notes".to_owned(), Vec::new())]);
+        let error = import_tables(&db, &my, &tables).await.unwrap_err();
+        assert!(error.to_string().contains("is not owned by plugin 'my'"));
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM plugin_my_plugin_notes")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+
+        let tables = BTreeMap::from([("plugin_my_notes".to_owned(), vec![json!({ "id": 2, "body": "new" })])]);
+        import_tables(&db, &my, &tables).await.unwrap();
+        assert_eq!(export_tables(&db, &my).await.unwrap(), tables);
+
+        drop(std::fs::remove_dir_all(&dir));
+    }
+}
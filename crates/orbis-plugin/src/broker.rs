@@ -0,0 +1,535 @@
+//! Brokered filesystem access for plugins.
+//!
+//! Plugins never open host files themselves. The SDK `files` API asks the
+//! host, which checks each path against the plugin's
+//! [`FilesystemGrants`]:
+//!
+//! - absolute paths must be under a `read_only` or `read_write` grant, and
+//!   under a `read_write` grant to be written or removed;
+//! - relative paths are inside the plugin's private temporary space, whose
+//!   total size is capped at `temp_space_bytes`;
+//! - `store:` paths, such as `store:exports/report.csv`, are objects in the
+//!   plugin's part of the [`ObjectStore`], whose total size is capped at
+//!   `storage_bytes`, and can be downloaded from URLs given to users.
+//!
+//! Paths with `..` components are rejected, and paths are resolved through
+//! symlinks before checking, so links cannot escape a grant.
+
+use crate::storage::{self, ObjectStore};
+use orbis_plugin_api::FilesystemGrants;
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefix of the paths of objects in the plugin's object storage.
+pub const STORE_PREFIX: &str = "store:";
+
+/// Kind of access to a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Reading or listing.
+    Read,
+
+    /// Writing or removing.
+    Write,
+}
+
+/// Checks and performs the file operations of a plugin.
+#[derive(Debug, Clone)]
+pub struct FileBroker {
+    /// Plugin the broker belongs to, for error messages.
+    plugin: String,
+
+    /// Granted filesystem access.
+    grants: FilesystemGrants,
+
+    /// Directory of the plugin's temporary space.
+    temp_dir: PathBuf,
+
+    /// Object storage of `store:` paths, if available.
+    store: Option<Arc<dyn ObjectStore>>,
+}
+
+impl FileBroker {
+    /// Create a broker for a plugin, with its temporary space in `temp_dir`.
+    #[must_use]
+    pub const fn new(plugin: String, grants: FilesystemGrants, temp_dir: PathBuf) -> Self {
+        Self {
+            plugin,
+            grants,
+            temp_dir,
+            store: None,
+        }
+    }
+
+    /// Keep `store:` paths in an object store.
+    #[must_use]
+    pub fn with_store(mut self, store: Arc<dyn ObjectStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Get the granted filesystem access.
+    #[must_use]
+    pub const fn grants(&self) -> &FilesystemGrants {
+        &self.grants
+    }
+
+    /// Get the directory of the plugin's temporary space.
+    #[must_use]
+    pub fn temp_dir(&self) -> &Path {
+        &self.temp_dir
+    }
+
+    /// Read a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not readable by the plugin or the file cannot be read.
+    pub fn read(&self, path: &str) -> orbis_core::Result<Vec<u8>> {
+        if let Some(object) = path.strip_prefix(STORE_PREFIX) {
+            let (store, key) = self.object(path, object)?;
+            return storage::block_on(store.get(&key))??
+                .ok_or_else(|| orbis_core::Error::not_found(format!("File not found: {}", path)));
+        }
+
+        let target = self.resolve(path, Access::Read)?;
+        std::fs::read(&target).map_err(|e| io_error("read", path, &e))
+    }
+
+    /// Write a file, replacing it if it exists and creating missing parent directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not writable by the plugin, the write
+    /// would exceed the temporary space or object storage, or the file cannot be written.
+    pub fn write(&self, path: &str, contents: &[u8]) -> orbis_core::Result<()> {
+        if let Some(object) = path.strip_prefix(STORE_PREFIX) {
+            let (store, key) = self.object(path, object)?;
+            return storage::block_on(async {
+                let used = store
+                    .list(&self.store_prefix())
+                    .await?
+                    .iter()
+                    .filter(|&existing| existing.key != key)
+                    .fold(0u64, |used, existing| used.saturating_add(existing.size));
+                let needed = used.saturating_add(contents.len() as u64);
+                if needed > self.grants.storage_bytes {
+                    return Err(orbis_core::Error::payload_too_large(format!(
+                        "Plugin '{}' object storage is full: writing '{}' needs {} bytes (max {})",
+                        self.plugin, path, needed, self.grants.storage_bytes
+                    )));
+                }
+                store.put(&key, contents.to_vec()).await
+            })?;
+        }
+
+        let target = self.resolve(path, Access::Write)?;
+
+        if canonical(&self.temp_dir).is_some_and(|temp_dir| target.starts_with(temp_dir)) {
+            let replaced = std::fs::metadata(&target).map_or(0, |metadata| metadata.len());
+            let used = dir_size(&self.temp_dir).saturating_sub(replaced);
+            let needed = used.saturating_add(contents.len() as u64);
+            if needed > self.grants.temp_space_bytes {
+                return Err(orbis_core::Error::payload_too_large(format!(
+                    "Plugin '{}' temporary space is full: writing '{}' needs {} bytes (max {})",
+                    self.plugin, path, needed, self.grants.temp_space_bytes
+                )));
+            }
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| io_error("create the directory of", path, &e))?;
+        }
+        std::fs::write(&target, contents).map_err(|e| io_error("write", path, &e))
+    }
+
+    /// List the names of the entries of a directory, sorted.
+    ///
+    /// Directory names end with `/`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not readable by the plugin or is not a directory.
+    pub fn list(&self, path: &str) -> orbis_core::Result<Vec<String>> {
+        if let Some(object) = path.strip_prefix(STORE_PREFIX) {
+            return self.list_objects(path, object);
+        }
+
+        let target = self.resolve(path, Access::Read)?;
+        let entries = std::fs::read_dir(&target).map_err(|e| io_error("list", path, &e))?;
+        let mut names = entries
+            .map(|entry| {
+                let entry = entry.map_err(|e| io_error("list", path, &e))?;
+                let mut name = entry.file_name().to_string_lossy().into_owned();
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    name.push('/');
+                }
+                Ok(name)
+            })
+            .collect::<orbis_core::Result<Vec<_>>>()?;
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    /// Remove a file, or a directory with its contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not writable by the plugin, is a
+    /// granted path itself, or cannot be removed.
+    pub fn remove(&self, path: &str) -> orbis_core::Result<()> {
+        if let Some(object) = path.strip_prefix(STORE_PREFIX) {
+            let (store, key) = self.object(path, object)?;
+            return storage::block_on(async {
+                let prefix = format!("{}/", key);
+                for nested in store.list(&prefix).await? {
+                    store.delete(&nested.key).await?;
+                }
+                store.delete(&key).await
+            })?;
+        }
+
+        let target = self.resolve(path, Access::Write)?;
+        let is_root = std::iter::once(&self.temp_dir)
+            .chain(&self.grants.read_write)
+            .any(|root| canonical(root).as_deref() == Some(&*target));
+        if is_root {
+            return Err(orbis_core::Error::unauthorized(format!(
+                "Plugin '{}' cannot remove the granted path '{}'",
+                self.plugin, path
+            )));
+        }
+
+        if target.is_dir() {
+            std::fs::remove_dir_all(&target)
+        } else {
+            std::fs::remove_file(&target)
+        }
+        .map_err(|e| io_error("remove", path, &e))
+    }
+
+    /// Get a URL downloading a `store:` file without credentials until it expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not a `store:` path readable by the
+    /// plugin, the file does not exist, or the store cannot issue URLs.
+    pub fn url(&self, path: &str, ttl: Duration) -> orbis_core::Result<String> {
+        let object = path.strip_prefix(STORE_PREFIX).ok_or_else(|| {
+            orbis_core::Error::validation(format!("Only {} paths have download URLs, not '{}'", STORE_PREFIX, path))
+        })?;
+        let (store, key) = self.object(path, object)?;
+        storage::block_on(async {
+            if store.size(&key).await?.is_none() {
+                return Err(orbis_core::Error::not_found(format!("File not found: {}", path)));
+            }
+            store.url(&key, ttl).await
+        })?
+    }
+
+    /// Empty the plugin's temporary space.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary space cannot be removed.
+    pub fn clear_temp(&self) -> orbis_core::Result<()> {
+        if self.temp_dir.exists() {
+            std::fs::remove_dir_all(&self.temp_dir).map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to clear {:?}: {}", self.temp_dir, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Get the prefix of the keys of the plugin's objects.
+    fn store_prefix(&self) -> String {
+        storage::plugin_files_prefix(&self.plugin)
+    }
+
+    /// Get the object store, checking the plugin has object storage.
+    fn store(&self, path: &str) -> orbis_core::Result<&Arc<dyn ObjectStore>> {
+        if self.grants.storage_bytes == 0 {
+            return Err(orbis_core::Error::unauthorized(format!(
+                "Plugin '{}' has no object storage for '{}'",
+                self.plugin, path
+            )));
+        }
+        self.store
+            .as_ref()
+            .ok_or_else(|| orbis_core::Error::plugin("Object storage is not available"))
+    }
+
+    /// Resolve a `store:` path to the object store and the object key.
+    fn object(&self, path: &str, object: &str) -> orbis_core::Result<(&Arc<dyn ObjectStore>, String)> {
+        let store = self.store(path)?;
+        let object = object.trim_matches('/');
+        if object.is_empty() {
+            return Err(orbis_core::Error::unauthorized(format!(
+                "Plugin '{}' cannot use the object storage root '{}' as a file",
+                self.plugin, path
+            )));
+        }
+        if storage::check_key(object).is_err() {
+            return Err(self.denied(path));
+        }
+        Ok((store, format!("{}{}", self.store_prefix(), object)))
+    }
+
+    /// List a `store:` directory: the objects and directories of objects under it.
+    fn list_objects(&self, path: &str, object: &str) -> orbis_core::Result<Vec<String>> {
+        let store = self.store(path)?;
+        let object = object.trim_matches('/');
+        let mut prefix = self.store_prefix();
+        if !object.is_empty() {
+            if storage::check_key(object).is_err() {
+                return Err(self.denied(path));
+            }
+            prefix.push_str(object);
+            prefix.push('/');
+        }
+
+        let names: BTreeSet<String> = storage::block_on(store.list(&prefix))??
+            .into_iter()
+            .filter_map(|existing| {
+                let rest = existing.key.strip_prefix(&prefix)?;
+                Some(rest.split_once('/').map_or_else(|| rest.to_string(), |(dir, _)| format!("{}/", dir)))
+            })
+            .collect();
+        Ok(names.into_iter().collect())
+    }
+
+    /// Resolve a plugin path to a host path, checking the plugin may access it.
+    fn resolve(&self, path: &str, access: Access) -> orbis_core::Result<PathBuf> {
+        let requested = Path::new(path);
+        if requested.components().any(|component| component == Component::ParentDir) {
+            return Err(self.denied(path));
+        }
+
+        if requested.is_relative() {
+            if self.grants.temp_space_bytes == 0 {
+                return Err(orbis_core::Error::unauthorized(format!(
+                    "Plugin '{}' has no temporary space for '{}'",
+                    self.plugin, path
+                )));
+            }
+            std::fs::create_dir_all(&self.temp_dir).map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to create {:?}: {}", self.temp_dir, e))
+            })?;
+            return self.confine(&self.temp_dir.join(requested), &self.temp_dir, path);
+        }
+
+        let roots = match access {
+            Access::Read => self.grants.read_only.iter().chain(&self.grants.read_write).collect::<Vec<_>>(),
+            Access::Write => self.grants.read_write.iter().collect(),
+        };
+        roots
+            .into_iter()
+            .filter(|root| requested.starts_with(root))
+            .find_map(|root| self.confine(requested, root, path).ok())
+            .ok_or_else(|| self.denied(path))
+    }
+
+    /// Resolve symlinks in a path and check it stays under a root.
+    ///
+    /// Paths that do not exist yet are resolved from their closest existing ancestor.
+    fn confine(&self, target: &Path, root: &Path, path: &str) -> orbis_core::Result<PathBuf> {
+        let root = canonical(root).ok_or_else(|| self.denied(path))?;
+
+        let mut existing = target;
+        let mut missing = Vec::new();
+        let resolved = loop {
+            if let Some(resolved) = canonical(existing) {
+                break resolved;
+            }
+            missing.push(existing.file_name().ok_or_else(|| self.denied(path))?);
+            existing = existing.parent().ok_or_else(|| self.denied(path))?;
+        };
+        let resolved = missing.into_iter().rev().fold(resolved, |resolved, name| resolved.join(name));
+
+        if resolved.starts_with(&root) {
+            Ok(resolved)
+        } else {
+            Err(self.denied(path))
+        }
+    }
+
+    /// Create the error for a path the plugin may not access.
+    fn denied(&self, path: &str) -> orbis_core::Error {
+        orbis_core::Error::unauthorized(format!(
+            "Plugin '{}' has no filesystem grant for '{}'",
+            self.plugin, path
+        ))
+    }
+}
+
+/// Resolve a path through symlinks, if it exists.
+fn canonical(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// Get the total size of the files under a directory.
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir).map_or(0, |entries| {
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| match entry.file_type() {
+                Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+                Ok(_) => entry.metadata().map_or(0, |metadata| metadata.len()),
+                Err(_) => 0,
+            })
+            .sum()
+    })
+}
+
+/// Create the error for a failed file operation.
+fn io_error(operation: &str, path: &str, error: &std::io::Error) -> orbis_core::Error {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        orbis_core::Error::not_found(format!("File not found: {}", path))
+    } else {
+        orbis_core::Error::plugin(format!("Failed to {} '{}': {}", operation, path, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a scratch directory with a granted `data` directory and a broker.
+    fn broker(temp_space_bytes: u64) -> (PathBuf, FileBroker) {
+        let root = std::env::temp_dir().join(format!("orbis-broker-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::create_dir_all(root.join("config")).unwrap();
+        std::fs::write(root.join("config/app.toml"), b"name = 'app'").unwrap();
+        std::fs::write(root.join("secret"), b"secret").unwrap();
+
+        let grants = FilesystemGrants {
+            read_only: vec![root.join("config")],
+            read_write: vec![root.join("data")],
+            temp_space_bytes,
+            storage_bytes: 0,
+        };
+        let broker = FileBroker::new("test-plugin".into(), grants, root.join("tmp"));
+        (root, broker)
+    }
+
+    fn path(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_read_only_grant() {
+        let (root, broker) = broker(0);
+
+        assert_eq!(broker.read(&path(&root, "config/app.toml")).unwrap(), b"name = 'app'");
+        assert_eq!(broker.list(&path(&root, "config")).unwrap(), vec!["app.toml".to_string()]);
+        broker.write(&path(&root, "config/app.toml"), b"changed").unwrap_err();
+        broker.remove(&path(&root, "config/app.toml")).unwrap_err();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_read_write_grant() {
+        let (root, broker) = broker(0);
+
+        broker.write(&path(&root, "data/reports/1.json"), b"{}").unwrap();
+        assert_eq!(broker.read(&path(&root, "data/reports/1.json")).unwrap(), b"{}");
+        assert_eq!(broker.list(&path(&root, "data")).unwrap(), vec!["reports/".to_string()]);
+        broker.remove(&path(&root, "data/reports")).unwrap();
+        assert!(!root.join("data/reports").exists());
+        broker.remove(&path(&root, "data")).unwrap_err();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_paths_outside_grants_are_denied() {
+        let (root, broker) = broker(0);
+
+        broker.read(&path(&root, "secret")).unwrap_err();
+        broker.read(&path(&root, "data/../secret")).unwrap_err();
+        // Sibling directories sharing a prefix are not under the grant
+        std::fs::create_dir_all(root.join("database")).unwrap();
+        broker.write(&path(&root, "database/x"), b"x").unwrap_err();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_cannot_escape_grants() {
+        let (root, broker) = broker(0);
+
+        std::os::unix::fs::symlink(root.join("secret"), root.join("data/link")).unwrap();
+        broker.read(&path(&root, "data/link")).unwrap_err();
+        std::os::unix::fs::symlink(&root, root.join("data/parent")).unwrap();
+        broker.write(&path(&root, "data/parent/secret"), b"owned").unwrap_err();
+        assert_eq!(std::fs::read(root.join("secret")).unwrap(), b"secret");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_temp_space_quota() {
+        let (root, broker) = broker(10);
+
+        assert!(broker.list(".").unwrap().is_empty());
+        broker.write("a.bin", &[0; 6]).unwrap();
+        broker.write("b.bin", &[0; 5]).unwrap_err();
+        // Replacing a file only counts the difference
+        broker.write("a.bin", &[0; 10]).unwrap();
+        broker.remove("a.bin").unwrap();
+        broker.write("b.bin", &[0; 5]).unwrap();
+        assert_eq!(broker.list(".").unwrap(), vec!["b.bin".to_string()]);
+
+        broker.clear_temp().unwrap();
+        assert!(!broker.temp_dir().exists());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_object_storage() {
+        let (root, broker) = broker(0);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        broker.write("store:a.txt", b"x").unwrap_err();
+
+        let broker = FileBroker {
+            grants: FilesystemGrants {
+                storage_bytes: 10,
+                ..broker.grants.clone()
+            },
+            ..broker
+        }
+        .with_store(Arc::new(crate::LocalStore::new(root.join("objects"))));
+
+        broker.write("store:exports/a.csv", &[0; 6]).unwrap();
+        assert_eq!(broker.read("store:exports/a.csv").unwrap(), vec![0; 6]);
+        assert!(root.join("objects/files/test-plugin/exports/a.csv").exists());
+        broker.write("store:b.bin", &[0; 5]).unwrap_err();
+        broker.write("store:b.bin", &[0; 4]).unwrap();
+        assert_eq!(broker.list("store:").unwrap(), vec!["b.bin".to_string(), "exports/".to_string()]);
+        assert_eq!(broker.list("store:exports").unwrap(), vec!["a.csv".to_string()]);
+
+        broker.read("store:../other/secret").unwrap_err();
+        broker.remove("store:").unwrap_err();
+        broker.remove("store:exports").unwrap();
+        broker.read("store:exports/a.csv").unwrap_err();
+        broker.url("store:missing", storage::DEFAULT_URL_TTL).unwrap_err();
+        broker.url("a.txt", storage::DEFAULT_URL_TTL).unwrap_err();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_no_temp_space() {
+        let (root, broker) = broker(0);
+
+        broker.write("a.bin", b"x").unwrap_err();
+        broker.read("a.bin").unwrap_err();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
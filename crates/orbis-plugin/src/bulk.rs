@@ -0,0 +1,218 @@
+//! Bulk plugin operations.
+//!
+//! Enabling, disabling or reloading many plugins at once follows their
+//! dependencies: dependencies are enabled and reloaded before the plugins
+//! needing them, and disabled after them. A plugin is skipped when a plugin
+//! it waits on failed, and the outcome of every plugin is reported.
+
+use orbis_plugin_api::PluginManifest;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Operation applied to many plugins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAction {
+    /// Enable the plugins.
+    Enable,
+
+    /// Disable the plugins.
+    Disable,
+
+    /// Reload the plugins from disk.
+    Reload,
+}
+
+impl BulkAction {
+    /// Get the action name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Enable => "enable",
+            Self::Disable => "disable",
+            Self::Reload => "reload",
+        }
+    }
+}
+
+/// Plugin a bulk operation did not apply to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkFailure {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Why the operation failed or was skipped.
+    pub error: String,
+}
+
+/// Outcome of a bulk operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkReport {
+    /// Operation applied.
+    pub action: BulkAction,
+
+    /// Plugins the operation applied to, in the order it did.
+    pub succeeded: Vec<String>,
+
+    /// Plugins the operation failed for.
+    pub failed: Vec<BulkFailure>,
+
+    /// Plugins left alone because a plugin they wait on failed.
+    pub skipped: Vec<BulkFailure>,
+}
+
+impl BulkReport {
+    /// Create an empty report.
+    #[must_use]
+    pub const fn new(action: BulkAction) -> Self {
+        Self {
+            action,
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Check if the operation applied to every plugin.
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.skipped.is_empty()
+    }
+}
+
+/// Order in which a bulk operation visits plugins.
+#[derive(Debug, Default)]
+pub struct BulkPlan {
+    /// Plugin names in the order to visit them.
+    pub order: Vec<String>,
+
+    /// Plugins each plugin waits on.
+    waits_on: HashMap<String, Vec<String>>,
+}
+
+impl BulkPlan {
+    /// Plan an operation over a set of plugins.
+    ///
+    /// Only dependencies within the set are considered. Plugins caught in a
+    /// dependency cycle are visited last.
+    pub fn new(manifests: &[PluginManifest], action: BulkAction) -> Self {
+        let names: HashSet<&str> = manifests.iter().map(|m| m.name.as_str()).collect();
+        let dependencies: HashMap<&str, Vec<&str>> = manifests
+            .iter()
+            .map(|manifest| {
+                let deps = manifest
+                    .dependencies
+                    .iter()
+                    .map(|dep| dep.name.as_str())
+                    .filter(|dep| names.contains(dep) && *dep != manifest.name)
+                    .collect();
+                (manifest.name.as_str(), deps)
+            })
+            .collect();
+
+        // Dependencies first, in manifest order within a level
+        let mut order: Vec<String> = Vec::with_capacity(manifests.len());
+        let mut placed: HashSet<&str> = HashSet::new();
+        loop {
+            let level: Vec<&str> = manifests
+                .iter()
+                .map(|m| m.name.as_str())
+                .filter(|name| !placed.contains(name))
+                .filter(|name| {
+                    dependencies
+                        .get(name)
+                        .is_none_or(|deps| deps.iter().all(|dep| placed.contains(dep)))
+                })
+                .collect();
+            if level.is_empty() {
+                break;
+            }
+            placed.extend(level.iter().copied());
+            order.extend(level.into_iter().map(str::to_owned));
+        }
+        order.extend(
+            manifests
+                .iter()
+                .filter(|m| !placed.contains(m.name.as_str()))
+                .map(|m| m.name.clone()),
+        );
+
+        // Disabling goes the other way: plugins before their dependencies
+        let mut waits_on: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, deps) in &dependencies {
+            for dep in deps {
+                let (waiter, waited) = match action {
+                    BulkAction::Enable | BulkAction::Reload => (*name, *dep),
+                    BulkAction::Disable => (*dep, *name),
+                };
+                waits_on.entry(waiter.to_owned()).or_default().push(waited.to_owned());
+            }
+        }
+        if action == BulkAction::Disable {
+            order.reverse();
+        }
+
+        Self { order, waits_on }
+    }
+
+    /// Get the plugins a plugin waits on.
+    pub fn waits_on(&self, plugin: &str) -> &[String] {
+        self.waits_on.get(plugin).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, deps: &[&str]) -> PluginManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "dependencies": deps
+                .iter()
+                .map(|dep| serde_json::json!({"name": dep, "version": "*"}))
+                .collect::<Vec<_>>(),
+        }))
+        .expect("valid manifest")
+    }
+
+    #[test]
+    fn test_plan_follows_dependencies() {
+        let manifests = vec![
+            manifest("app", &["core", "ui", "absent"]),
+            manifest("ui", &["core"]),
+            manifest("core", &[]),
+            manifest("loop-a", &["loop-b"]),
+            manifest("loop-b", &["loop-a"]),
+        ];
+
+        let enable = BulkPlan::new(&manifests, BulkAction::Enable);
+        assert_eq!(enable.order, ["core", "ui", "app", "loop-a", "loop-b"]);
+        assert_eq!(enable.waits_on("app"), ["core", "ui"]);
+        assert!(enable.waits_on("core").is_empty());
+
+        let disable = BulkPlan::new(&manifests, BulkAction::Disable);
+        assert_eq!(disable.order, ["loop-b", "loop-a", "app", "ui", "core"]);
+        let mut core = disable.waits_on("core").to_vec();
+        core.sort();
+        assert_eq!(core, ["app", "ui"]);
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let mut report = BulkReport::new(BulkAction::Reload);
+        report.succeeded.push("core".to_string());
+        assert!(report.is_success());
+
+        report.skipped.push(BulkFailure {
+            plugin: "app".to_string(),
+            error: "dependency 'ui' failed".to_string(),
+        });
+        assert!(!report.is_success());
+
+        let value = serde_json::to_value(&report).expect("serializable");
+        assert_eq!(value["action"], "reload");
+        assert_eq!(value["skipped"][0]["plugin"], "app");
+    }
+}
@@ -0,0 +1,443 @@
+//! Caches for plugin handler results.
+//!
+//! Pages can declare cache hints (see [`PageCacheHints`](orbis_plugin_api::PageCacheHints)).
+//! Results of `GET` handlers used as page data actions are cached for the
+//! declared TTL, so mostly-static pages don't re-run their handlers on every load.
+//!
+//! Routes can declare response caching (see [`RouteCache`](orbis_plugin_api::RouteCache)).
+//! Their responses are kept in a [`ResponseCache`], in memory and optionally
+//! on disk, until they expire or the plugin invalidates them.
+
+use crate::PluginContext;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Largest response body kept in the response cache, in bytes.
+pub const MAX_CACHED_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// Most responses kept in memory.
+const MAX_RESPONSE_ENTRIES: usize = 10_000;
+
+/// Route argument of [`ResponseCache::invalidate`] purging all routes of a plugin.
+pub const ALL_ROUTES: &str = "*";
+
+/// Cached handler result.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// Plugin that produced the value.
+    plugin: String,
+
+    /// Cached handler result.
+    value: serde_json::Value,
+
+    /// When the entry stops being fresh.
+    expires_at: Instant,
+}
+
+/// Cache of page data handler results keyed by plugin, handler and request.
+#[derive(Debug, Default)]
+pub struct PageDataCache {
+    /// Entries keyed by [`PageDataCache::key`].
+    entries: DashMap<String, CacheEntry>,
+}
+
+impl PageDataCache {
+    /// Create an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the cache key for a handler invocation.
+    ///
+    /// The key includes the tenant and user so cached data never leaks across them.
+    #[must_use]
+    pub fn key(plugin: &str, handler: &str, context: &PluginContext) -> String {
+        let query: BTreeMap<_, _> = context.query.iter().collect();
+        format!(
+            "{}:{}:{}:{}:{}:{}",
+            plugin,
+            handler,
+            context.tenant_id.as_deref().unwrap_or("-"),
+            context.user_id.as_deref().unwrap_or("-"),
+            serde_json::to_string(&query).unwrap_or_default(),
+            context.body
+        )
+    }
+
+    /// Get a fresh cached value.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let entry = self.entries.get(key)?;
+        if entry.expires_at > Instant::now() {
+            return Some(entry.value.clone());
+        }
+        drop(entry);
+
+        self.entries.remove(key);
+        None
+    }
+
+    /// Store a value for the given TTL.
+    pub fn insert(&self, key: String, plugin: &str, value: serde_json::Value, ttl: Duration) {
+        self.entries.insert(
+            key,
+            CacheEntry {
+                plugin: plugin.to_string(),
+                value,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+    }
+
+    /// Drop all cached values for a plugin.
+    pub fn invalidate_plugin(&self, plugin: &str) {
+        self.entries.retain(|_, entry| entry.plugin != plugin);
+    }
+
+    /// Drop all cached values.
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    /// Number of cached entries (including stale ones not yet evicted).
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Cached route response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResponseEntry {
+    /// Cache key, to tell apart files of colliding hashes.
+    key: String,
+
+    /// Plugin that produced the response.
+    plugin: String,
+
+    /// Route path within the plugin.
+    route: String,
+
+    /// Response body.
+    body: String,
+
+    /// When the entry stops being fresh, in seconds since the Unix epoch.
+    expires_at: i64,
+}
+
+impl ResponseEntry {
+    /// Check if the entry is still fresh.
+    fn is_fresh(&self) -> bool {
+        self.expires_at > chrono::Utc::now().timestamp()
+    }
+}
+
+/// Cache of route responses keyed by plugin, route and request.
+///
+/// Entries live in memory and, once a directory is set, on disk as well, so
+/// they survive restarts.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    /// Entries keyed by [`ResponseCache::key`].
+    entries: DashMap<String, ResponseEntry>,
+
+    /// Directory entries are also written to.
+    dir: RwLock<Option<PathBuf>>,
+}
+
+impl ResponseCache {
+    /// Create an empty in-memory cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep entries on disk in the given directory as well.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.write() = Some(dir);
+    }
+
+    /// Build the cache key for a route request.
+    ///
+    /// The key includes the tenant, user, query string (in any parameter
+    /// order) and the values of the headers the route varies on, so cached
+    /// responses never leak across them.
+    #[must_use]
+    pub fn key(
+        plugin: &str,
+        route: &str,
+        tenant: Option<&str>,
+        user: Option<&str>,
+        query: &str,
+        vary: &BTreeMap<String, String>,
+    ) -> String {
+        let mut query: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty()).collect();
+        query.sort_unstable();
+
+        format!(
+            "{}:{}:{}:{}:{}:{}",
+            plugin,
+            route,
+            tenant.unwrap_or("-"),
+            user.unwrap_or("-"),
+            query.join("&"),
+            serde_json::to_string(vary).unwrap_or_default()
+        )
+    }
+
+    /// Get a fresh cached response body.
+    #[must_use]
+    pub fn get(&self, plugin: &str, route: &str, key: &str) -> Option<String> {
+        if let Some(entry) = self.entries.get(key) {
+            if entry.is_fresh() {
+                return Some(entry.body.clone());
+            }
+            drop(entry);
+            self.entries.remove(key);
+            self.remove_file(plugin, route, key);
+            return None;
+        }
+
+        let path = self.file(plugin, route, key)?;
+        let entry: ResponseEntry = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+        if entry.key != key {
+            return None;
+        }
+        if !entry.is_fresh() {
+            self.remove_file(plugin, route, key);
+            return None;
+        }
+
+        let body = entry.body.clone();
+        self.entries.insert(key.to_string(), entry);
+        Some(body)
+    }
+
+    /// Store a response body for the given TTL.
+    ///
+    /// Bodies larger than [`MAX_CACHED_RESPONSE_BYTES`] are not cached.
+    pub fn insert(&self, plugin: &str, route: &str, key: String, body: String, ttl: Duration) {
+        if body.len() > MAX_CACHED_RESPONSE_BYTES {
+            return;
+        }
+        if self.entries.len() >= MAX_RESPONSE_ENTRIES {
+            self.entries.retain(|_, entry| entry.is_fresh());
+            if self.entries.len() >= MAX_RESPONSE_ENTRIES {
+                return;
+            }
+        }
+
+        let ttl = i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX);
+        let entry = ResponseEntry {
+            key,
+            plugin: plugin.to_string(),
+            route: route.to_string(),
+            body,
+            expires_at: chrono::Utc::now().timestamp().saturating_add(ttl),
+        };
+
+        if let Some(path) = self.file(plugin, route, &entry.key) {
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(&path, serde_json::to_vec(&entry).unwrap_or_default()));
+            if let Err(e) = written {
+                tracing::warn!("Failed to write cached response {}: {}", path.display(), e);
+            }
+        }
+        self.entries.insert(entry.key.clone(), entry);
+    }
+
+    /// Drop the cached responses of a plugin route, or of all its routes for [`ALL_ROUTES`].
+    pub fn invalidate(&self, plugin: &str, route: &str) {
+        if route == ALL_ROUTES {
+            self.invalidate_plugin(plugin);
+            return;
+        }
+
+        self.entries
+            .retain(|_, entry| entry.plugin != plugin || entry.route != route);
+        if let Some(dir) = self.dir.read().as_ref() {
+            remove_dir(&dir.join(plugin).join(digest(route)));
+        }
+    }
+
+    /// Drop all cached responses of a plugin.
+    pub fn invalidate_plugin(&self, plugin: &str) {
+        self.entries.retain(|_, entry| entry.plugin != plugin);
+        if let Some(dir) = self.dir.read().as_ref() {
+            remove_dir(&dir.join(plugin));
+        }
+    }
+
+    /// Drop all cached responses.
+    pub fn clear(&self) {
+        self.entries.clear();
+        if let Some(dir) = self.dir.read().as_ref() {
+            remove_dir(dir);
+        }
+    }
+
+    /// Number of responses in memory (including stale ones not yet evicted).
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no responses are in memory.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Path of the file of an entry, if entries are kept on disk.
+    fn file(&self, plugin: &str, route: &str, key: &str) -> Option<PathBuf> {
+        let dir = self.dir.read().clone()?;
+        Some(dir.join(plugin).join(digest(route)).join(format!("{}.json", digest(key))))
+    }
+
+    /// Remove the file of an entry.
+    fn remove_file(&self, plugin: &str, route: &str, key: &str) {
+        if let Some(path) = self.file(plugin, route, key)
+            && let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!("Failed to remove cached response {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 digest, used as a file name.
+fn digest(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+/// Remove a cache directory.
+fn remove_dir(dir: &std::path::Path) {
+    if let Err(e) = std::fs::remove_dir_all(dir)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!("Failed to remove cached responses in {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CancellationFlag;
+    use std::collections::HashMap;
+
+    fn context(user: Option<&str>) -> PluginContext {
+        PluginContext {
+            method: "GET".to_string(),
+            path: "/greeting".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: user.map(String::from),
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_is_scoped_per_user() {
+        let cache = PageDataCache::new();
+        let alice = PageDataCache::key("greeter", "get_greeting", &context(Some("alice")));
+        let bob = PageDataCache::key("greeter", "get_greeting", &context(Some("bob")));
+
+        cache.insert(alice.clone(), "greeter", serde_json::json!("hi alice"), Duration::from_secs(60));
+
+        assert_eq!(cache.get(&alice), Some(serde_json::json!("hi alice")));
+        assert_eq!(cache.get(&bob), None);
+
+        let other_tenant = PluginContext {
+            tenant_id: Some("globex".to_string()),
+            ..context(Some("alice"))
+        };
+        assert_eq!(cache.get(&PageDataCache::key("greeter", "get_greeting", &other_tenant)), None);
+    }
+
+    #[test]
+    fn test_cache_expiry_and_invalidation() {
+        let cache = PageDataCache::new();
+        let key = PageDataCache::key("greeter", "get_greeting", &context(None));
+
+        cache.insert(key.clone(), "greeter", serde_json::json!(1), Duration::ZERO);
+        assert_eq!(cache.get(&key), None);
+        assert!(cache.is_empty());
+
+        cache.insert(key.clone(), "greeter", serde_json::json!(1), Duration::from_secs(60));
+        cache.invalidate_plugin("greeter");
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn test_response_cache_vary_and_invalidation() {
+        let cache = ResponseCache::new();
+        let french = BTreeMap::from([("accept-language".to_string(), "fr".to_string())]);
+
+        let key = ResponseCache::key("greeter", "/greeting", None, Some("alice"), "a=1&b=2", &BTreeMap::new());
+        let french_key = ResponseCache::key("greeter", "/greeting", None, Some("alice"), "a=1&b=2", &french);
+        assert_ne!(key, french_key);
+        assert_eq!(
+            key,
+            ResponseCache::key("greeter", "/greeting", None, Some("alice"), "b=2&a=1", &BTreeMap::new())
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("greeter", "/greeting", None, Some("bob"), "a=1&b=2", &BTreeMap::new())
+        );
+
+        cache.insert("greeter", "/greeting", key.clone(), "hi".to_string(), Duration::from_secs(60));
+        cache.insert("greeter", "/other", "other".to_string(), "x".to_string(), Duration::from_secs(60));
+        assert_eq!(cache.get("greeter", "/greeting", &key).as_deref(), Some("hi"));
+        assert_eq!(cache.get("greeter", "/greeting", &french_key), None);
+
+        cache.invalidate("greeter", "/greeting");
+        assert_eq!(cache.get("greeter", "/greeting", &key), None);
+        assert_eq!(cache.get("greeter", "/other", "other").as_deref(), Some("x"));
+
+        cache.invalidate("greeter", ALL_ROUTES);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_response_cache_on_disk() {
+        let dir = std::env::temp_dir().join(format!("orbis-response-cache-{}", uuid::Uuid::new_v4()));
+        let key = ResponseCache::key("greeter", "/greeting", None, None, "", &BTreeMap::new());
+
+        let cache = ResponseCache::new();
+        cache.set_dir(dir.clone());
+        cache.insert("greeter", "/greeting", key.clone(), "hi".to_string(), Duration::from_secs(60));
+
+        // A new cache over the same directory sees the entry
+        let restarted = ResponseCache::new();
+        restarted.set_dir(dir.clone());
+        assert_eq!(restarted.get("greeter", "/greeting", &key).as_deref(), Some("hi"));
+
+        restarted.invalidate("greeter", "/greeting");
+        let again = ResponseCache::new();
+        again.set_dir(dir.clone());
+        assert_eq!(again.get("greeter", "/greeting", &key), None);
+
+        again.clear();
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,279 @@
+//! Request capture for plugin development.
+//!
+//! In development mode, the requests handled by each plugin handler are
+//! captured with their outcome, so they can be inspected and replayed against
+//! the handler after changing the plugin. The most recent requests of each
+//! handler are kept; credential headers are never captured.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::PluginContext;
+
+/// Number of requests captured per handler.
+pub const MAX_CAPTURED_REQUESTS: usize = 20;
+
+/// Headers left out of captured requests.
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "x-api-key",
+];
+
+/// A request handled by a plugin handler.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedRequest {
+    /// Capture ID, used to replay the request.
+    pub id: Uuid,
+
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler name.
+    pub handler: String,
+
+    /// Context the handler was invoked with, without credential headers.
+    pub context: PluginContext,
+
+    /// Handler result, if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+
+    /// Handler error, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Time the handler took, in milliseconds.
+    pub duration_ms: u64,
+
+    /// When the request was handled.
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Recent requests of plugin handlers, captured while enabled.
+#[derive(Debug, Default)]
+pub struct RequestCapture {
+    /// Capture requests.
+    enabled: AtomicBool,
+
+    /// Most recent requests per plugin and handler, oldest first.
+    entries: DashMap<(String, String), VecDeque<CapturedRequest>>,
+}
+
+impl RequestCapture {
+    /// Create a disabled capture.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable capturing requests; disabling drops those captured.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.entries.clear();
+        }
+    }
+
+    /// Check if requests are captured.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Capture a request and its outcome, returning the capture ID if enabled.
+    pub fn record(
+        &self,
+        plugin: &str,
+        handler: &str,
+        context: &PluginContext,
+        outcome: &orbis_core::Result<serde_json::Value>,
+        duration: Duration,
+    ) -> Option<Uuid> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let context = redacted(context);
+        let (result, error) = match *outcome {
+            Ok(ref value) => (Some(value.clone()), None),
+            Err(ref e) => (None, Some(e.to_string())),
+        };
+        let request = CapturedRequest {
+            id: orbis_core::new_id(),
+            plugin: plugin.to_owned(),
+            handler: handler.to_owned(),
+            context,
+            result,
+            error,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            captured_at: Utc::now(),
+        };
+        let id = request.id;
+
+        let mut entries = self
+            .entries
+            .entry((plugin.to_owned(), handler.to_owned()))
+            .or_default();
+        if entries.len() >= MAX_CAPTURED_REQUESTS {
+            entries.pop_front();
+        }
+        entries.push_back(request);
+        drop(entries);
+        Some(id)
+    }
+
+    /// Get the captured requests of a plugin, or of one of its handlers,
+    /// most recent first.
+    #[must_use]
+    pub fn list(&self, plugin: &str, handler: Option<&str>) -> Vec<CapturedRequest> {
+        let mut requests: Vec<CapturedRequest> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.key().0 == plugin && handler.is_none_or(|handler| entry.key().1 == handler))
+            .flat_map(|entry| entry.value().iter().cloned().collect::<Vec<_>>())
+            .collect();
+        requests.sort_by_key(|request| std::cmp::Reverse(request.id));
+        requests
+    }
+
+    /// Get a captured request of a plugin.
+    #[must_use]
+    pub fn get(&self, plugin: &str, id: Uuid) -> Option<CapturedRequest> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.key().0 == plugin)
+            .find_map(|entry| {
+                entry
+                    .value()
+                    .iter()
+                    .find(|request| request.id == id)
+                    .cloned()
+            })
+    }
+
+    /// Drop the captured requests of a plugin.
+    pub fn clear(&self, plugin: &str) {
+        self.entries.retain(|key, _| key.0 != plugin);
+    }
+}
+
+/// Copy a request context without its credential headers and deadline, to
+/// keep it for replay.
+pub fn redacted(context: &PluginContext) -> PluginContext {
+    let mut context = context.clone();
+    context.headers.retain(|name, _| {
+        !REDACTED_HEADERS
+            .iter()
+            .any(|redacted| name.eq_ignore_ascii_case(redacted))
+    });
+    context.deadline = None;
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(body: serde_json::Value) -> PluginContext {
+        PluginContext {
+            method: "POST".to_string(),
+            path: "/notes".to_string(),
+            headers: [
+                ("authorization".to_string(), "Bearer secret".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            query: std::collections::HashMap::new(),
+            body,
+            user_id: Some("user".to_string()),
+            is_admin: false,
+            tenant_id: None,
+            deadline: Some(Utc::now()),
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: crate::CancellationFlag::new(),
+        }
+    }
+
+    #[test]
+    fn test_capture_only_when_enabled() {
+        let capture = RequestCapture::new();
+        let outcome = Ok(serde_json::json!({"id": 1}));
+        assert!(capture
+            .record(
+                "notes",
+                "create",
+                &context(serde_json::Value::Null),
+                &outcome,
+                Duration::ZERO
+            )
+            .is_none());
+        assert!(capture.list("notes", None).is_empty());
+
+        capture.set_enabled(true);
+        let id = capture
+            .record(
+                "notes",
+                "create",
+                &context(serde_json::json!({"title": "a"})),
+                &outcome,
+                Duration::from_millis(3),
+            )
+            .expect("captured");
+
+        let request = capture.get("notes", id).expect("request");
+        assert_eq!(request.handler, "create");
+        assert_eq!(request.duration_ms, 3);
+        assert_eq!(request.result, Some(serde_json::json!({"id": 1})));
+        assert!(request.context.deadline.is_none());
+        assert!(!request.context.headers.contains_key("authorization"));
+        assert!(request.context.headers.contains_key("content-type"));
+        assert!(capture.get("other", id).is_none());
+
+        capture.set_enabled(false);
+        assert!(capture.get("notes", id).is_none());
+    }
+
+    #[test]
+    fn test_capture_is_bounded_per_handler() {
+        let capture = RequestCapture::new();
+        capture.set_enabled(true);
+        let error = Err(orbis_core::Error::plugin("failed"));
+        for i in 0..=MAX_CAPTURED_REQUESTS {
+            capture.record(
+                "notes",
+                "create",
+                &context(serde_json::json!(i)),
+                &error,
+                Duration::ZERO,
+            );
+        }
+        capture.record(
+            "notes",
+            "list",
+            &context(serde_json::Value::Null),
+            &error,
+            Duration::ZERO,
+        );
+
+        let created = capture.list("notes", Some("create"));
+        assert_eq!(created.len(), MAX_CAPTURED_REQUESTS);
+        assert_eq!(
+            created[0].context.body,
+            serde_json::json!(MAX_CAPTURED_REQUESTS)
+        );
+        assert!(created[0].error.is_some());
+        assert_eq!(capture.list("notes", None).len(), MAX_CAPTURED_REQUESTS + 1);
+
+        capture.clear("notes");
+        assert!(capture.list("notes", None).is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+//! Plugin compatibility with the host API version.
+
+use chrono::{DateTime, Utc};
+use orbis_plugin_api::{AbiVersion, PluginManifest, API_VERSIONS};
+use semver::{Comparator, Op, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// What to do with plugins whose `core_version` excludes the host API version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatibilityPolicy {
+    /// Refuse to load the plugin.
+    #[default]
+    Refuse,
+
+    /// Log a warning and load the plugin anyway.
+    Warn,
+}
+
+/// Result of checking a plugin against the host API version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatibilityStatus {
+    /// The plugin's `core_version` includes the host API version.
+    Compatible,
+
+    /// The plugin does not declare a `core_version`.
+    Unspecified,
+
+    /// The plugin targets a newer host API than this host provides.
+    RequiresNewerHost,
+
+    /// The plugin's `core_version` excludes the host API version.
+    Incompatible,
+
+    /// The plugin targets a core HTTP API version this host does not serve.
+    UnsupportedApiVersion,
+}
+
+/// Compatibility of a plugin with the host API version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCompatibility {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Plugin version.
+    pub plugin_version: String,
+
+    /// Host API range required by the plugin.
+    pub core_version: Option<String>,
+
+    /// Host API version it was checked against.
+    pub host_api_version: String,
+
+    /// Core HTTP API version the plugin's pages and routes target.
+    #[serde(default = "default_api_version")]
+    pub api_version: u32,
+
+    /// Check result.
+    pub status: CompatibilityStatus,
+
+    /// Whether the plugin was loaded despite an incompatibility.
+    pub overridden: bool,
+
+    /// When the check was made.
+    pub checked_at: DateTime<Utc>,
+}
+
+impl PluginCompatibility {
+    /// Check a plugin manifest against the host API version.
+    #[must_use]
+    pub fn check(manifest: &PluginManifest) -> Self {
+        let host = AbiVersion::CURRENT.to_semver();
+
+        let api_version = manifest.target_api_version();
+        let status = match manifest.core_version.as_deref().map(VersionReq::parse) {
+            _ if !API_VERSIONS.contains(&api_version) => CompatibilityStatus::UnsupportedApiVersion,
+            None => CompatibilityStatus::Unspecified,
+            Some(Ok(requirement)) if requirement.matches(&host) => CompatibilityStatus::Compatible,
+            Some(Ok(requirement)) if requirement.comparators.iter().any(|c| minimum(c) > host) => {
+                CompatibilityStatus::RequiresNewerHost
+            }
+            Some(Ok(_) | Err(_)) => CompatibilityStatus::Incompatible,
+        };
+
+        Self {
+            plugin: manifest.name.clone(),
+            plugin_version: manifest.version.clone(),
+            core_version: manifest.core_version.clone(),
+            host_api_version: host.to_string(),
+            api_version,
+            status,
+            overridden: false,
+            checked_at: Utc::now(),
+        }
+    }
+
+    /// Check if the plugin can run on this host.
+    #[must_use]
+    pub const fn is_compatible(&self) -> bool {
+        matches!(self.status, CompatibilityStatus::Compatible | CompatibilityStatus::Unspecified)
+    }
+
+    /// Describe an incompatibility.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let required = self.core_version.as_deref().unwrap_or("*");
+        match self.status {
+            CompatibilityStatus::Compatible | CompatibilityStatus::Unspecified => {
+                format!("Plugin '{}' is compatible with host API {}", self.plugin, self.host_api_version)
+            }
+            CompatibilityStatus::RequiresNewerHost => format!(
+                "Plugin '{}' requires host API {} but this host provides {}; upgrade Orbis to run this plugin",
+                self.plugin, required, self.host_api_version
+            ),
+            CompatibilityStatus::Incompatible => format!(
+                "Plugin '{}' requires host API {} which excludes host API {}",
+                self.plugin, required, self.host_api_version
+            ),
+            CompatibilityStatus::UnsupportedApiVersion => format!(
+                "Plugin '{}' targets core API v{} but this host serves {}",
+                self.plugin,
+                self.api_version,
+                API_VERSIONS.iter().map(|version| format!("v{}", version)).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// Core HTTP API version of checks recorded before plugins declared one.
+const fn default_api_version() -> u32 {
+    orbis_plugin_api::CURRENT_API_VERSION
+}
+
+/// Lowest version a comparator can match.
+fn minimum(comparator: &Comparator) -> Version {
+    let version = Version::new(
+        comparator.major,
+        comparator.minor.unwrap_or(0),
+        comparator.patch.unwrap_or(0),
+    );
+
+    match comparator.op {
+        // `>1.2` excludes 1.2.x, so the first match is the next minor
+        Op::Greater if comparator.patch.is_none() => Version::new(version.major, version.minor.saturating_add(1), 0),
+        Op::Greater => Version::new(version.major, version.minor, version.patch.saturating_add(1)),
+        Op::Less | Op::LessEq => Version::new(0, 0, 0),
+        // `Op` is non-exhaustive; treat unknown operators as inclusive
+        Op::Exact | Op::GreaterEq | Op::Tilde | Op::Caret | Op::Wildcard | _ => version,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(core_version: Option<&str>) -> PluginManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": "compat",
+            "version": "1.0.0",
+            "core_version": core_version,
+        }))
+        .expect("valid manifest")
+    }
+
+    #[test]
+    fn test_compatibility_status() {
+        let host = AbiVersion::CURRENT;
+
+        let check = |core_version: &str| PluginCompatibility::check(&manifest(Some(core_version))).status;
+
+        assert_eq!(
+            PluginCompatibility::check(&manifest(None)).status,
+            CompatibilityStatus::Unspecified
+        );
+        assert_eq!(check(&format!("^{}.0", host.major)), CompatibilityStatus::Compatible);
+        assert_eq!(
+            check(&format!(">={}.{}", host.major, host.minor + 1)),
+            CompatibilityStatus::RequiresNewerHost
+        );
+        assert_eq!(check(&format!("^{}", host.major + 1)), CompatibilityStatus::RequiresNewerHost);
+        assert_eq!(check(&format!("<{}.0", host.major)), CompatibilityStatus::Incompatible);
+
+        let mut future = manifest(Some(&format!("^{}.0", host.major)));
+        future.api_version = Some(API_VERSIONS.iter().max().copied().unwrap_or(0) + 1);
+        let compatibility = PluginCompatibility::check(&future);
+        assert_eq!(compatibility.status, CompatibilityStatus::UnsupportedApiVersion);
+        assert!(!compatibility.is_compatible());
+    }
+}
@@ -0,0 +1,210 @@
+//! Feature flag overrides.
+//!
+//! Plugins declare feature flags with defaults in their manifest.
+//! Administrators override them per profile: overrides are stored in the
+//! profile's database and kept in memory, and the resolved flags are passed
+//! to handlers with every request, so toggles apply without reloading.
+
+use dashmap::DashMap;
+use orbis_db::{Database, DatabasePool};
+use orbis_plugin_api::PluginManifest;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use orbis_core::UserId;
+
+/// Feature flag of a plugin and its resolved value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureStatus {
+    /// Flag name.
+    pub name: String,
+
+    /// Description from the manifest.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Default from the manifest.
+    pub default: bool,
+
+    /// Override set by an administrator, if any.
+    #[serde(rename = "override")]
+    pub override_value: Option<bool>,
+
+    /// Whether the flag is on.
+    pub enabled: bool,
+}
+
+/// Feature flag overrides of a profile, by plugin.
+#[derive(Debug, Default)]
+pub struct FeatureOverrides {
+    /// Overrides by plugin, then flag.
+    overrides: DashMap<String, BTreeMap<String, bool>>,
+}
+
+impl FeatureOverrides {
+    /// Create an empty set of overrides.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the overrides of a plugin.
+    #[must_use]
+    pub fn get(&self, plugin: &str) -> BTreeMap<String, bool> {
+        self.overrides.get(plugin).map(|flags| flags.clone()).unwrap_or_default()
+    }
+
+    /// Set or clear (`None`) the override of a flag.
+    pub fn set(&self, plugin: &str, flag: &str, enabled: Option<bool>) {
+        match enabled {
+            Some(enabled) => {
+                self.overrides
+                    .entry(plugin.to_owned())
+                    .or_default()
+                    .insert(flag.to_owned(), enabled);
+            }
+            None => {
+                if let Some(mut flags) = self.overrides.get_mut(plugin) {
+                    flags.remove(flag);
+                }
+                self.overrides.remove_if(plugin, |_, flags| flags.is_empty());
+            }
+        }
+    }
+
+    /// Resolve the flags of a plugin.
+    #[must_use]
+    pub fn resolve(&self, manifest: &PluginManifest) -> BTreeMap<String, bool> {
+        orbis_plugin_api::features::resolve(&manifest.features, &self.get(&manifest.name))
+    }
+
+    /// Describe the flags of a plugin.
+    #[must_use]
+    pub fn statuses(&self, manifest: &PluginManifest) -> Vec<FeatureStatus> {
+        let overrides = self.get(&manifest.name);
+        manifest
+            .features
+            .iter()
+            .map(|flag| {
+                let override_value = overrides.get(&flag.name).copied();
+                FeatureStatus {
+                    name: flag.name.clone(),
+                    description: flag.description.clone(),
+                    default: flag.default,
+                    override_value,
+                    enabled: override_value.unwrap_or(flag.default),
+                }
+            })
+            .collect()
+    }
+
+    /// Load the overrides stored in the database, replacing those in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn load(&self, db: &Database) -> orbis_core::Result<()> {
+        let query = "SELECT plugin, flag, enabled FROM plugin_features";
+        let rows: Vec<(String, String, bool)> = match *db.pool() {
+            DatabasePool::Postgres(ref pool) => sqlx::query_as(query).fetch_all(pool).await,
+            DatabasePool::Sqlite(ref pool) => sqlx::query_as(query).fetch_all(pool).await,
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        self.overrides.clear();
+        for (plugin, flag, enabled) in rows {
+            self.set(&plugin, &flag, Some(enabled));
+        }
+
+        Ok(())
+    }
+
+    /// Store the override of a flag in the database, then apply it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write fails.
+    pub async fn store(
+        &self,
+        db: &Database,
+        plugin: &str,
+        flag: &str,
+        enabled: Option<bool>,
+        changed_by: Option<UserId>,
+    ) -> orbis_core::Result<()> {
+        let db_err = |e: sqlx::Error| orbis_core::Error::database(e.to_string());
+        let delete = "DELETE FROM plugin_features WHERE plugin = $1 AND flag = $2";
+
+        match *db.pool() {
+            DatabasePool::Postgres(ref pool) => {
+                let query = enabled.map_or_else(
+                    || sqlx::query(delete).bind(plugin).bind(flag),
+                    |enabled| {
+                        sqlx::query(
+                            "INSERT INTO plugin_features (plugin, flag, enabled, updated_by, updated_at)
+                             VALUES ($1, $2, $3, $4, NOW())
+                             ON CONFLICT (plugin, flag) DO UPDATE
+                             SET enabled = EXCLUDED.enabled, updated_by = EXCLUDED.updated_by, updated_at = NOW()",
+                        )
+                        .bind(plugin)
+                        .bind(flag)
+                        .bind(enabled)
+                        .bind(changed_by)
+                    },
+                );
+                query.execute(pool).await.map_err(db_err)?;
+            }
+            DatabasePool::Sqlite(ref pool) => {
+                let query = enabled.map_or_else(
+                    || sqlx::query(delete).bind(plugin).bind(flag),
+                    |enabled| {
+                        sqlx::query(
+                            "INSERT OR REPLACE INTO plugin_features (plugin, flag, enabled, updated_by, updated_at)
+                             VALUES ($1, $2, $3, $4, datetime('now'))",
+                        )
+                        .bind(plugin)
+                        .bind(flag)
+                        .bind(enabled)
+                        .bind(changed_by.map(|id| id.to_string()))
+                    },
+                );
+                query.execute(pool).await.map_err(db_err)?;
+            }
+        }
+
+        self.set(plugin, flag, enabled);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_resolve() {
+        let manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "notes",
+            "version": "1.0.0",
+            "features": [
+                {"name": "exports", "default": false, "description": "CSV exports"},
+                {"name": "new-editor", "default": true}
+            ]
+        }))
+        .expect("valid manifest");
+
+        let overrides = FeatureOverrides::new();
+        overrides.set("notes", "exports", Some(true));
+        overrides.set("notes", "new-editor", Some(false));
+        overrides.set("notes", "new-editor", None);
+
+        let flags = overrides.resolve(&manifest);
+        assert_eq!(flags, BTreeMap::from([("exports".to_owned(), true), ("new-editor".to_owned(), true)]));
+
+        let statuses = overrides.statuses(&manifest);
+        assert_eq!(statuses[0].override_value, Some(true));
+        assert_eq!(statuses[1].override_value, None);
+
+        overrides.set("notes", "exports", None);
+        assert!(overrides.get("notes").is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+//! Plugin state change history.
+//!
+//! Every change of a plugin's [`PluginState`], including it being loaded and
+//! unloaded, is recorded with what triggered it and published on a broadcast
+//! channel, so dashboards can follow plugins being enabled, disabled or
+//! failing. The most recent changes of each plugin are kept, also after the
+//! plugin is unloaded.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::sync::broadcast;
+
+use crate::{HookEvent, PluginState};
+
+/// Number of state changes kept per plugin.
+pub const MAX_STATE_HISTORY: usize = 100;
+
+/// Capacity of the state change channel.
+const STATE_CHANNEL_CAPACITY: usize = 64;
+
+/// What triggered a state change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateTrigger {
+    /// The plugin was loaded from the plugins directory or installed.
+    Load,
+
+    /// The state saved by the previous session was restored.
+    Restore,
+
+    /// A user or administrator asked for it.
+    Manual,
+
+    /// The plugin was reloaded.
+    Reload,
+
+    /// A resource alert policy acted on the plugin.
+    Alert,
+
+    /// The plugin failed.
+    Crash,
+
+    /// The plugin was unloaded or uninstalled.
+    Unload,
+}
+
+impl StateTrigger {
+    /// Get the trigger name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Load => "load",
+            Self::Restore => "restore",
+            Self::Manual => "manual",
+            Self::Reload => "reload",
+            Self::Alert => "alert",
+            Self::Crash => "crash",
+            Self::Unload => "unload",
+        }
+    }
+}
+
+/// Cause of a state change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateCause {
+    /// What triggered the change.
+    pub trigger: StateTrigger,
+
+    /// User who asked for the change, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+
+    /// Why the state changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl StateCause {
+    /// Create a cause without an actor or reason.
+    #[must_use]
+    pub const fn new(trigger: StateTrigger) -> Self {
+        Self {
+            trigger,
+            actor: None,
+            reason: None,
+        }
+    }
+
+    /// Set the user who asked for the change.
+    #[must_use]
+    pub fn by(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Set why the state changed.
+    #[must_use]
+    pub fn because(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// A change of a plugin's state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateTransition {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// State before the change, unset when the plugin was loaded.
+    pub from: Option<PluginState>,
+
+    /// State after the change, unset when the plugin was unloaded.
+    pub to: Option<PluginState>,
+
+    /// Cause of the change.
+    #[serde(flatten)]
+    pub cause: StateCause,
+
+    /// When the change happened.
+    pub at: DateTime<Utc>,
+}
+
+impl StateTransition {
+    /// Get the hook event announcing the change to plugins.
+    #[must_use]
+    pub fn hook_event(&self) -> HookEvent {
+        HookEvent::PluginStateChanged {
+            plugin: self.plugin.clone(),
+            from: self.from.map(|state| state.as_str().to_owned()),
+            to: self.to.map(|state| state.as_str().to_owned()),
+            trigger: self.cause.trigger.as_str().to_owned(),
+            reason: self.cause.reason.clone(),
+        }
+    }
+}
+
+/// Recorded and broadcast plugin state changes.
+#[derive(Debug)]
+pub struct StateHistory {
+    /// Most recent changes per plugin, oldest first.
+    entries: DashMap<String, VecDeque<StateTransition>>,
+
+    /// Change sender.
+    sender: broadcast::Sender<StateTransition>,
+}
+
+impl StateHistory {
+    /// Create an empty history.
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(STATE_CHANNEL_CAPACITY);
+        Self {
+            entries: DashMap::new(),
+            sender,
+        }
+    }
+
+    /// Record a state change happening now and publish it, unless the state
+    /// is unchanged.
+    pub fn record(
+        &self,
+        plugin: &str,
+        from: Option<PluginState>,
+        to: Option<PluginState>,
+        cause: StateCause,
+    ) -> Option<StateTransition> {
+        if from == to {
+            return None;
+        }
+
+        let transition = StateTransition {
+            plugin: plugin.to_string(),
+            from,
+            to,
+            cause,
+            at: Utc::now(),
+        };
+        tracing::debug!(
+            "Plugin '{}' changed state from {:?} to {:?} ({:?})",
+            plugin,
+            from,
+            to,
+            transition.cause.trigger
+        );
+
+        {
+            let mut entries = self.entries.entry(plugin.to_string()).or_default();
+            if entries.len() >= MAX_STATE_HISTORY {
+                entries.pop_front();
+            }
+            entries.push_back(transition.clone());
+        }
+
+        if self.sender.send(transition.clone()).is_err() {
+            tracing::trace!("No state change subscribers");
+        }
+        Some(transition)
+    }
+
+    /// Get the recorded changes of a plugin, most recent first.
+    #[must_use]
+    pub fn get(&self, plugin: &str) -> Vec<StateTransition> {
+        self.entries
+            .get(plugin)
+            .map(|r| r.value().iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if any change of a plugin was recorded.
+    #[must_use]
+    pub fn contains(&self, plugin: &str) -> bool {
+        self.entries.contains_key(plugin)
+    }
+
+    /// Subscribe to state changes.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<StateTransition> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for StateHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_subscribe() {
+        let history = StateHistory::new();
+        let mut rx = history.subscribe();
+
+        let cause = StateCause::new(StateTrigger::Manual).by("admin").because("maintenance");
+        history.record("notes", Some(PluginState::Running), Some(PluginState::Disabled), cause);
+        assert!(history
+            .record("notes", Some(PluginState::Disabled), Some(PluginState::Disabled), StateCause::new(StateTrigger::Manual))
+            .is_none());
+
+        let transition = rx.recv().await.expect("transition");
+        assert_eq!(transition.to, Some(PluginState::Disabled));
+        assert_eq!(transition.cause.actor.as_deref(), Some("admin"));
+
+        let value = serde_json::to_value(&transition).expect("serializable");
+        assert_eq!(value["from"], "running");
+        assert_eq!(value["trigger"], "manual");
+        assert_eq!(value["reason"], "maintenance");
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let history = StateHistory::new();
+        for i in 0..=MAX_STATE_HISTORY {
+            let (from, to) = if i % 2 == 0 {
+                (PluginState::Running, PluginState::Disabled)
+            } else {
+                (PluginState::Disabled, PluginState::Running)
+            };
+            history.record("notes", Some(from), Some(to), StateCause::new(StateTrigger::Manual));
+        }
+
+        let entries = history.get("notes");
+        assert_eq!(entries.len(), MAX_STATE_HISTORY);
+        assert_eq!(entries.first().and_then(|t| t.to), Some(PluginState::Disabled));
+        assert!(history.get("other").is_empty());
+    }
+}
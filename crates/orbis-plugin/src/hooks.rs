@@ -0,0 +1,182 @@
+//! Registry of plugin lifecycle hook subscriptions.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use orbis_plugin_api::{HookPoint, PluginManifest};
+use parking_lot::RwLock;
+use serde::Serialize;
+
+/// A plugin handler subscribed to a hook point.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredHook {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler called with the event.
+    pub handler: String,
+
+    /// Order among the hook's subscribers; lower runs first.
+    pub priority: i32,
+
+    /// Time the handler has to run.
+    #[serde(skip)]
+    pub timeout: Duration,
+}
+
+/// Outcome of running one hook handler.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookOutcome {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler called.
+    pub handler: String,
+
+    /// Time the handler ran, in milliseconds.
+    pub duration_ms: u64,
+
+    /// Error message if the handler failed or timed out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Hook subscriptions of loaded plugins, by hook point.
+#[derive(Debug, Default)]
+pub struct HookRegistry {
+    /// Subscribers of each hook point, in execution order.
+    hooks: RwLock<HashMap<HookPoint, Vec<RegisteredHook>>>,
+}
+
+impl HookRegistry {
+    /// Create an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the hooks a plugin's manifest subscribes to, replacing any
+    /// previously registered hooks of the plugin.
+    pub fn register(&self, manifest: &PluginManifest) {
+        insert_plugin(&mut self.hooks.write(), manifest);
+    }
+
+    /// Remove the hooks of a plugin.
+    pub fn unregister(&self, plugin: &str) {
+        remove_plugin(&mut self.hooks.write(), plugin);
+    }
+
+    /// Get the subscribers of a hook point, in execution order.
+    #[must_use]
+    pub fn subscribers(&self, point: HookPoint) -> Vec<RegisteredHook> {
+        self.hooks.read().get(&point).cloned().unwrap_or_default()
+    }
+
+    /// Check if a hook point has subscribers.
+    #[must_use]
+    pub fn has_subscribers(&self, point: HookPoint) -> bool {
+        self.hooks.read().get(&point).is_some_and(|subscribers| !subscribers.is_empty())
+    }
+
+    /// Get the subscribers of every hook point.
+    #[must_use]
+    pub fn all(&self) -> HashMap<HookPoint, Vec<RegisteredHook>> {
+        self.hooks.read().clone()
+    }
+}
+
+/// Add the hooks of a plugin, replacing its previous hooks.
+fn insert_plugin(hooks: &mut HashMap<HookPoint, Vec<RegisteredHook>>, manifest: &PluginManifest) {
+    remove_plugin(hooks, &manifest.name);
+
+    for subscription in &manifest.hooks {
+        let subscribers = hooks.entry(subscription.hook).or_default();
+        subscribers.push(RegisteredHook {
+            plugin: manifest.name.clone(),
+            handler: subscription.handler.clone(),
+            priority: subscription.priority,
+            timeout: Duration::from_millis(subscription.timeout_ms()),
+        });
+
+        // Ties run in plugin name order, so execution order is stable
+        subscribers.sort_by(|a, b| (a.priority, &a.plugin).cmp(&(b.priority, &b.plugin)));
+    }
+}
+
+/// Remove the hooks of a plugin from every hook point.
+fn remove_plugin(hooks: &mut HashMap<HookPoint, Vec<RegisteredHook>>, plugin: &str) {
+    for subscribers in hooks.values_mut() {
+        subscribers.retain(|hook| hook.plugin != plugin);
+    }
+    hooks.retain(|_, subscribers| !subscribers.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, hooks: serde_json::Value) -> PluginManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "hooks": hooks,
+        }))
+        .expect("valid manifest")
+    }
+
+    fn order(registry: &HookRegistry, point: HookPoint) -> Vec<String> {
+        registry
+            .subscribers(point)
+            .into_iter()
+            .map(|hook| format!("{}.{}", hook.plugin, hook.handler))
+            .collect()
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let registry = HookRegistry::new();
+        registry.register(&manifest(
+            "audit",
+            serde_json::json!([
+                { "hook": "after_auth", "handler": "record_login", "priority": 10 },
+                { "hook": "server_started", "handler": "warm_up", "timeout_ms": 5000 }
+            ]),
+        ));
+        registry.register(&manifest(
+            "security",
+            serde_json::json!([{ "hook": "after_auth", "handler": "check_login", "priority": -5 }]),
+        ));
+        registry.register(&manifest(
+            "analytics",
+            serde_json::json!([{ "hook": "after_auth", "handler": "track", "priority": 10 }]),
+        ));
+
+        assert_eq!(
+            order(&registry, HookPoint::AfterAuth),
+            ["security.check_login", "analytics.track", "audit.record_login"]
+        );
+        assert_eq!(
+            registry.subscribers(HookPoint::ServerStarted).first().map(|hook| hook.timeout),
+            Some(Duration::from_secs(5))
+        );
+        assert!(!registry.has_subscribers(HookPoint::BeforeRequest));
+    }
+
+    #[test]
+    fn test_unregister_and_replace() {
+        let registry = HookRegistry::new();
+        registry.register(&manifest(
+            "audit",
+            serde_json::json!([{ "hook": "plugin_installed", "handler": "on_install" }]),
+        ));
+        registry.register(&manifest(
+            "audit",
+            serde_json::json!([{ "hook": "plugin_installed", "handler": "on_install_v2" }]),
+        ));
+        assert_eq!(order(&registry, HookPoint::PluginInstalled), ["audit.on_install_v2"]);
+
+        registry.unregister("audit");
+        assert!(!registry.has_subscribers(HookPoint::PluginInstalled));
+        assert!(registry.all().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Idempotent plugin requests.
+//!
+//! Clients retrying a mutating request, for example after a dropped
+//! connection, send the same `Idempotency-Key` header. Responses a plugin
+//! marks idempotent are kept in an [`IdempotencyStore`] and replayed for
+//! those retries instead of running the handler again.
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::MAX_CACHED_RESPONSE_BYTES;
+
+/// Request header carrying the idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Longest idempotency key accepted, in bytes.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Most requests tracked at once.
+const MAX_IDEMPOTENT_ENTRIES: usize = 10_000;
+
+/// What to do with a request carrying an idempotency key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyStatus {
+    /// First request with the key; run the handler.
+    Started,
+
+    /// Retry of a completed request; answer with its response body.
+    Replay(String),
+
+    /// Retry of a request still running.
+    InFlight,
+
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+/// Request tracked under an idempotency key.
+#[derive(Debug, Clone)]
+struct IdempotencyEntry {
+    /// Fingerprint of the request, to tell apart reused keys.
+    fingerprint: String,
+
+    /// Response body, once the request completed.
+    body: Option<String>,
+
+    /// When the entry is dropped.
+    expires_at: Instant,
+}
+
+impl IdempotencyEntry {
+    /// Check if the entry is still in use.
+    fn is_live(&self) -> bool {
+        self.expires_at > Instant::now()
+    }
+}
+
+/// Responses of idempotent requests, keyed by plugin, route, user and
+/// idempotency key.
+#[derive(Debug, Default)]
+pub struct IdempotencyStore {
+    /// Entries keyed by [`IdempotencyStore::key`].
+    entries: DashMap<String, IdempotencyEntry>,
+}
+
+impl IdempotencyStore {
+    /// Create an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the store key for a request.
+    ///
+    /// The key includes the tenant and user, so clients can't replay each
+    /// other's responses by guessing keys.
+    #[must_use]
+    pub fn key(
+        plugin: &str,
+        route: &str,
+        tenant: Option<&str>,
+        user: Option<&str>,
+        idempotency_key: &str,
+    ) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            plugin,
+            route,
+            tenant.unwrap_or("-"),
+            user.unwrap_or("-"),
+            idempotency_key
+        )
+    }
+
+    /// Fingerprint a request by its method, query and body.
+    #[must_use]
+    pub fn fingerprint(method: &str, query: &HashMap<String, String>, body: &serde_json::Value) -> String {
+        let query: BTreeMap<_, _> = query.iter().collect();
+        let mut hasher = Sha256::new();
+        hasher.update(method.as_bytes());
+        hasher.update([0]);
+        hasher.update(serde_json::to_string(&query).unwrap_or_default().as_bytes());
+        hasher.update([0]);
+        hasher.update(body.to_string().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Start a request, unless it is a retry.
+    ///
+    /// Started requests are tracked for at most `lease`, after which a retry
+    /// runs the handler again; complete or release them before that.
+    pub fn begin(&self, key: &str, fingerprint: &str, lease: Duration) -> IdempotencyStatus {
+        if self.entries.len() >= MAX_IDEMPOTENT_ENTRIES {
+            self.entries.retain(|_, entry| entry.is_live());
+        }
+
+        let started = IdempotencyEntry {
+            fingerprint: fingerprint.to_string(),
+            body: None,
+            expires_at: Instant::now() + lease,
+        };
+        match self.entries.entry(key.to_string()) {
+            Entry::Occupied(mut occupied) => {
+                let entry = occupied.get();
+                if !entry.is_live() {
+                    occupied.insert(started);
+                    return IdempotencyStatus::Started;
+                }
+                if entry.fingerprint != fingerprint {
+                    return IdempotencyStatus::Mismatch;
+                }
+                entry
+                    .body
+                    .clone()
+                    .map_or(IdempotencyStatus::InFlight, IdempotencyStatus::Replay)
+            }
+            Entry::Vacant(vacant) => {
+                vacant.insert(started);
+                IdempotencyStatus::Started
+            }
+        }
+    }
+
+    /// Keep the response of a started request for the given TTL.
+    ///
+    /// Bodies larger than [`MAX_CACHED_RESPONSE_BYTES`] are not kept; the
+    /// request is released instead.
+    pub fn complete(&self, key: &str, body: String, ttl: Duration) {
+        if body.len() > MAX_CACHED_RESPONSE_BYTES {
+            self.release(key);
+            return;
+        }
+        if let Some(mut entry) = self.entries.get_mut(key) {
+            entry.body = Some(body);
+            entry.expires_at = Instant::now() + ttl;
+        }
+    }
+
+    /// Forget a started request, so retries run the handler again.
+    pub fn release(&self, key: &str) {
+        self.entries.remove_if(key, |_, entry| entry.body.is_none());
+    }
+
+    /// Number of tracked requests (including expired ones not yet evicted).
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no requests are tracked.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEASE: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn test_replay_and_mismatch() {
+        let store = IdempotencyStore::new();
+        let key = IdempotencyStore::key("notes", "POST /notes", None, Some("alice"), "retry-1");
+        let create = IdempotencyStore::fingerprint("POST", &HashMap::new(), &serde_json::json!({"title": "a"}));
+        let other = IdempotencyStore::fingerprint("POST", &HashMap::new(), &serde_json::json!({"title": "b"}));
+
+        assert_eq!(store.begin(&key, &create, LEASE), IdempotencyStatus::Started);
+        assert_eq!(store.begin(&key, &create, LEASE), IdempotencyStatus::InFlight);
+
+        store.complete(&key, "{\"id\":1}".to_string(), Duration::from_secs(60));
+        assert_eq!(store.begin(&key, &create, LEASE), IdempotencyStatus::Replay("{\"id\":1}".to_string()));
+        assert_eq!(store.begin(&key, &other, LEASE), IdempotencyStatus::Mismatch);
+
+        // Other users don't share keys
+        let bob = IdempotencyStore::key("notes", "POST /notes", None, Some("bob"), "retry-1");
+        assert_eq!(store.begin(&bob, &create, LEASE), IdempotencyStatus::Started);
+    }
+
+    #[test]
+    fn test_release_and_expiry() {
+        let store = IdempotencyStore::new();
+        let fingerprint = IdempotencyStore::fingerprint("DELETE", &HashMap::new(), &serde_json::Value::Null);
+
+        assert_eq!(store.begin("a", &fingerprint, LEASE), IdempotencyStatus::Started);
+        store.release("a");
+        assert_eq!(store.begin("a", &fingerprint, LEASE), IdempotencyStatus::Started);
+
+        // Abandoned requests stop blocking retries once their lease is over
+        assert_eq!(store.begin("b", &fingerprint, Duration::ZERO), IdempotencyStatus::Started);
+        assert_eq!(store.begin("b", &fingerprint, LEASE), IdempotencyStatus::Started);
+
+        // Completed requests are replayed until they expire
+        store.complete("a", "{}".to_string(), Duration::ZERO);
+        store.release("a");
+        assert_eq!(store.begin("a", &fingerprint, LEASE), IdempotencyStatus::Started);
+    }
+}
@@ -17,36 +17,176 @@
 //! - Access database through controlled API
 //! - Secure WASM sandboxing
 
+mod analysis;
+mod archive;
+mod broker;
+mod bulk;
+mod cache;
+mod capture;
+mod compat;
+mod features;
+mod history;
+mod hooks;
+mod idempotency;
 mod loader;
+mod media;
+mod merkle;
+mod module_cache;
+mod operation;
+mod packed;
+mod quota;
 mod registry;
+mod registry_client;
+mod reload;
+mod remote;
+mod replay;
+mod resolver;
 mod runtime;
 mod sandbox;
+mod search;
+mod signing;
+mod storage;
 mod watcher;
 
+pub use analysis::{
+    CrateSize, FunctionSize, ModuleAnalysis, PermissionUsage, SectionSize, SizeChange, SizeDiff, UNKNOWN_CRATE,
+};
+pub use archive::{PluginDataArchive, StateData, DATA_ARCHIVE_FORMAT};
+pub use broker::FileBroker;
+pub use bulk::{BulkAction, BulkFailure, BulkReport};
+pub use cache::{PageDataCache, ResponseCache, ALL_ROUTES, MAX_CACHED_RESPONSE_BYTES};
+pub use capture::{CapturedRequest, RequestCapture, MAX_CAPTURED_REQUESTS};
+pub use compat::{CompatibilityPolicy, CompatibilityStatus, PluginCompatibility};
+pub use features::{FeatureOverrides, FeatureStatus};
+pub use history::{StateCause, StateHistory, StateTransition, StateTrigger, MAX_STATE_HISTORY};
+pub use hooks::{HookOutcome, HookRegistry, RegisteredHook};
+pub use idempotency::{IdempotencyStatus, IdempotencyStore, IDEMPOTENCY_KEY_HEADER, MAX_IDEMPOTENCY_KEY_LEN};
 pub use loader::{PluginLoader, PluginSource};
-pub use registry::{PluginInfo, PluginRegistry, PluginState};
-pub use runtime::{PluginContext, PluginRuntime};
+pub use media::{
+    ImageInfo, MAX_IMAGE_DIMENSION, MAX_MEDIA_INPUT_BYTES, MAX_THUMBNAIL_DIMENSION, MEDIA_TIME_LIMIT,
+};
+pub use module_cache::{ModuleCache, DEFAULT_MODULE_CACHE_SIZE};
+pub use operation::{
+    OperationKind, OperationProgress, OperationStage, OperationStatus, PluginOperation, PluginOperations,
+};
+pub use packed::{
+    ArchiveLimits, ArchiveViolation, PackedArchive, PackedError, PackedResult, DEFAULT_MAX_ARCHIVE_ENTRIES,
+    DEFAULT_MAX_ARCHIVE_SIZE,
+};
+pub use quota::{NetworkQuotas, NetworkUsage};
+pub use registry::{
+    PluginFilters, PluginInfo, MAX_TRAP_REPORTS, PluginLoadReport, PluginLoadTiming, PluginRegistry, PluginState,
+    VersionChange,
+};
+pub use registry_client::{registry_url, RegistryClient, RegistryIndex, RegistryVersion};
+pub use reload::{ReloadEvent, ReloadEvents, ReloadStage};
+pub use remote::{RemoteFetcher, RemoteSource, REMOTE_CACHE_DIR};
+pub use replay::{
+    RecordedCall, ReplayBytes, ReplayCapture, ReplayOutcome, ReplayRecording, REPLAY_FORMAT_VERSION,
+};
+pub use resolver::{dependency_issues, resolve_load_order, DependencyIssue, LoadOrder};
+pub use runtime::{
+    AlertCondition, AlertRule, CancelOnDrop, CancellationFlag, EmailSink, EventSink, HandlerFlag, HandlerStats,
+    HandlerStatsReport, HandlerThresholds, JobSink, PluginContext, PluginEmail, PluginEvent, PluginJob,
+    PluginResourceMonitor, PluginRuntime,
+    RequestSummary, ResourceAlert, ResourceSample, SlowInvocation, SnapshotInfo, TrapFrame, TrapReport, TrapSink,
+    LATENCY_SAMPLES, MAX_SAMPLE_HISTORY, MAX_SLOW_INVOCATIONS,
+};
 pub use sandbox::SandboxConfig;
+pub use search::{
+    SearchHit, SearchRequest, SearchResponse, DEFAULT_SEARCH_LIMIT, MAX_SEARCH_LIMIT, MAX_SEARCH_QUERY_LENGTH,
+};
+pub use merkle::{hash_dir, MerkleManifest};
+pub use signing::{
+    decode_public_key, decode_signing_key, encode_public_key, encode_signing_key, generate_key, key_id,
+    read_directory_signature, sign, sign_directory, DirectorySignature, Keyring, PluginSignature, SignaturePolicy,
+    SignatureStatus, DIRECTORY_SIGNATURE_FILE, PUBLIC_KEY_EXTENSION,
+};
+pub use storage::{
+    migrate as migrate_storage, open as open_storage, open_backend as open_storage_backend, plugin_files_prefix,
+    LocalStore, MigratingStore, MigrationReport, ObjectInfo, ObjectStore, S3Store, UrlSigner, DEFAULT_URL_TTL,
+    LOCAL_DOWNLOAD_PATH, MAX_URL_TTL, PLUGIN_FILES_PREFIX,
+};
 pub use watcher::{PluginChangeEvent, PluginChangeKind, PluginWatcher, WatcherConfig};
 
 // Re-export public API types from orbis-plugin-api
 pub use orbis_plugin_api::{
-    AccordionItem, Action, ArgMapping, BreadcrumbItem, ComponentSchema, CustomValidation,
-    DialogDefinition, Error as PluginApiError, EventHandlers, FormField, NavigationConfig,
-    NavigationItem, PageDefinition, PageLifecycleHooks, PluginDependency, PluginManifest,
-    PluginPermission, PluginRoute, Result as PluginApiResult, SelectOption, StateFieldDefinition,
-    StateFieldType, TabItem, TableColumn, ToastLevel, ValidationRule,
+    AbiVersion, API_VERSIONS, CURRENT_API_VERSION, AccordionItem, Action, ArgMapping, BreadcrumbItem, ComponentSchema, CustomValidation,
+    DialogDefinition, Error as PluginApiError, EventHandlers, FeatureFlag, FilesystemGrants, FormField, HookEvent, HookPoint,
+    LayoutDefinition,
+    HookSubscription, HostCall, HostInfo, HostInfoField, NavigationConfig,
+    NavigationItem, PageCacheHints, PageDefinition, PageLifecycleHooks, PluginActivation, PluginDependency, PluginManifest,
+    PluginRequirements, PrefetchCall,
+    DenyReason, PluginPermission, PluginRoute, PolicyDecision, PolicyDenial, PolicyEffect, PolicyEngine, PolicyMode,
+    PolicyRule, AccessPolicy, RequestPart, RequestSchema, RequestViolation,
+    Result as PluginApiResult, RouteCache, SearchProvider, SearchResult, SelectOption, SettingDefinition, SettingScope,
+    SettingType, StateFieldDefinition,
+    StateFieldType, TabItem, TableColumn, ThemeDefinition, ToastLevel, ValidationRule, ViewerAccess,
 };
 
 use orbis_db::Database;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use orbis_core::{PluginId, UserId};
 use uuid::Uuid;
+use runtime::StagedInstance;
+
+/// Maximum number of plugins initialized concurrently at startup.
+const MAX_PARALLEL_LOADS: usize = 8;
+
+/// Result of loading a plugin and the time it took.
+type TimedLoad = (orbis_core::Result<PluginInfo>, Duration);
+
+/// Plugin found while scanning the plugins directory.
+struct PluginCandidate {
+    /// Path the plugin was found at.
+    path: PathBuf,
+
+    /// Plugin flavor, for logging.
+    flavor: &'static str,
+
+    /// Plugin source.
+    source: PluginSource,
+
+    /// Plugin manifest.
+    manifest: PluginManifest,
+}
 
 /// Plugin manager handling all plugin operations.
 pub struct PluginManager {
     registry: PluginRegistry,
     loader: PluginLoader,
     runtime: PluginRuntime,
+    page_cache: PageDataCache,
+    /// Responses replayed for retried requests.
+    idempotency: IdempotencyStore,
+    /// Feature flag overrides of the profile.
+    features: FeatureOverrides,
+    /// Requests captured for replay in development mode.
+    capture: RequestCapture,
+    /// Last time each plugin handled a request, for idle unloading.
+    last_used: dashmap::DashMap<String, Instant>,
+    /// Keys trusted to sign plugins.
+    keyring: parking_lot::RwLock<Keyring>,
+    /// What to do with unsigned or badly signed standalone plugins.
+    signature_policy: parking_lot::RwLock<SignaturePolicy>,
+    /// What to do with plugins targeting an incompatible host API.
+    compatibility_policy: parking_lot::RwLock<CompatibilityPolicy>,
+    /// Hot reload lifecycle events.
+    reload_events: ReloadEvents,
+    /// Install and reload operations in progress.
+    operations: PluginOperations,
+    /// Lifecycle hooks plugins subscribe to.
+    hooks: HookRegistry,
+    /// Downloads remote plugins.
+    remote: RemoteFetcher,
+    /// Registry plugins are installed from by name, if one is configured.
+    registry_client: parking_lot::RwLock<Option<RegistryClient>>,
+    /// Where trap reports go besides the registry, such as the audit log.
+    trap_sink: parking_lot::RwLock<Option<TrapSink>>,
     plugins_dir: PathBuf,
     db: Database,
 }
@@ -68,13 +208,29 @@ impl PluginManager {
         // State file in plugin directory
         let state_file = plugins_dir.join(".plugin_states.json");
 
+        let registry = PluginRegistry::with_persistence(state_file);
         let runtime = PluginRuntime::new();
         runtime.set_plugins_dir(plugins_dir.clone());
+        runtime.set_network_quotas(Arc::clone(registry.network_quotas()));
 
         Ok(Self {
-            registry: PluginRegistry::with_persistence(state_file),
+            registry,
             loader:   PluginLoader::new(),
             runtime,
+            page_cache: PageDataCache::new(),
+            idempotency: IdempotencyStore::new(),
+            features: FeatureOverrides::new(),
+            capture: RequestCapture::new(),
+            last_used: dashmap::DashMap::new(),
+            compatibility_policy: parking_lot::RwLock::new(CompatibilityPolicy::default()),
+            keyring: parking_lot::RwLock::new(Keyring::new()),
+            signature_policy: parking_lot::RwLock::new(SignaturePolicy::default()),
+            reload_events: ReloadEvents::new(),
+            operations: PluginOperations::new(),
+            hooks: HookRegistry::new(),
+            remote: RemoteFetcher::new(plugins_dir.join(REMOTE_CACHE_DIR)),
+            registry_client: parking_lot::RwLock::new(None),
+            trap_sink: parking_lot::RwLock::new(None),
             plugins_dir,
             db,
         })
@@ -98,6 +254,138 @@ impl PluginManager {
         &self.runtime
     }
 
+    /// Get the page data cache.
+    #[must_use]
+    pub const fn page_cache(&self) -> &PageDataCache {
+        &self.page_cache
+    }
+
+    /// Get the hot reload event channel.
+    #[must_use]
+    pub const fn reload_events(&self) -> &ReloadEvents {
+        &self.reload_events
+    }
+
+    /// Get the install and reload operations in progress.
+    #[must_use]
+    pub const fn operations(&self) -> &PluginOperations {
+        &self.operations
+    }
+
+    /// Get the lifecycle hook registry.
+    #[must_use]
+    pub const fn hooks(&self) -> &HookRegistry {
+        &self.hooks
+    }
+
+    /// Enable the precompiled module cache in the given directory.
+    ///
+    /// Must be called before plugins are loaded to take effect on startup.
+    pub fn set_module_cache_dir(&self, dir: PathBuf, max_size: u64) {
+        self.runtime.set_module_cache(ModuleCache::new(dir, max_size));
+    }
+
+    /// Get the route response cache.
+    #[must_use]
+    pub fn response_cache(&self) -> &ResponseCache {
+        self.runtime.response_cache()
+    }
+
+    /// Get the store of responses replayed for retried requests.
+    #[must_use]
+    pub const fn idempotency(&self) -> &IdempotencyStore {
+        &self.idempotency
+    }
+
+    /// Get the feature flag overrides.
+    #[must_use]
+    pub const fn features(&self) -> &FeatureOverrides {
+        &self.features
+    }
+
+    /// Get the requests captured for replay in development mode.
+    #[must_use]
+    pub const fn capture(&self) -> &RequestCapture {
+        &self.capture
+    }
+
+    /// Keep cached route responses on disk in the given directory as well.
+    pub fn set_response_cache_dir(&self, dir: PathBuf) {
+        self.runtime.response_cache().set_dir(dir);
+    }
+
+    /// Set what to do with plugins whose `core_version` excludes the host API version.
+    pub fn set_compatibility_policy(&self, policy: CompatibilityPolicy) {
+        *self.compatibility_policy.write() = policy;
+    }
+
+    /// Set the configured plugin policy and its per-plugin overrides.
+    ///
+    /// Must be called before plugins are loaded to apply to them on startup.
+    pub fn set_plugin_policy(&self, config: orbis_config::PluginsConfig) {
+        self.runtime.set_plugin_policy(config);
+    }
+
+    /// Set the keys trusted to sign plugins.
+    pub fn set_keyring(&self, keyring: Keyring) {
+        *self.keyring.write() = keyring;
+    }
+
+    /// Set what to do with unsigned or badly signed standalone plugins.
+    ///
+    /// Other plugin flavors cannot be signed and count as unsigned.
+    pub fn set_signature_policy(&self, policy: SignaturePolicy) {
+        *self.signature_policy.write() = policy;
+    }
+
+    /// Set the registry plugins are installed from by name.
+    pub fn set_registry_client(&self, client: RegistryClient) {
+        *self.registry_client.write() = Some(client);
+    }
+
+    /// Set where trap reports are sent, such as the host's audit log.
+    ///
+    /// Reports are kept in the registry either way.
+    pub fn set_trap_sink(&self, sink: TrapSink) {
+        *self.trap_sink.write() = Some(sink);
+    }
+
+    /// Get the registry plugins are installed from by name, if one is configured.
+    #[must_use]
+    pub fn registry_client(&self) -> Option<RegistryClient> {
+        self.registry_client.read().clone()
+    }
+
+    /// Resolve a plugin published in the registry to the newest version
+    /// matching a requirement and the remote source of its file, which
+    /// [`PluginManager::install_remote`] installs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no registry is configured or the plugin cannot be
+    /// resolved.
+    pub async fn resolve_from_registry(
+        &self,
+        name: &str,
+        requirement: Option<&str>,
+    ) -> orbis_core::Result<(String, RemoteSource)> {
+        let client = self
+            .registry_client()
+            .ok_or_else(|| orbis_core::Error::config("No plugin registry is configured"))?;
+        client.resolve(name, requirement).await
+    }
+
+    /// Remove all precompiled modules, returning the number of entries removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be read.
+    pub fn clear_module_cache(&self) -> orbis_core::Result<usize> {
+        self.runtime
+            .module_cache()
+            .map_or(Ok(0), |cache| cache.clear())
+    }
+
     /// Load all plugins from the plugins directory.
     ///
     /// Scans for:
@@ -105,75 +393,94 @@ impl PluginManager {
     /// - Packed: .zip files
     /// - Standalone: .wasm files
     ///
+    /// Plugins are initialized in dependency order, with independent plugins
+    /// loaded concurrently. Timings are recorded in the registry's load report.
+    ///
     /// # Errors
     ///
     /// Returns an error if loading fails.
-    pub async fn load_all(&self) -> orbis_core::Result<Vec<PluginInfo>> {
+    pub async fn load_all(self: &Arc<Self>) -> orbis_core::Result<Vec<PluginInfo>> {
         tracing::info!("Loading plugins from {:?}", self.plugins_dir);
 
-        let mut loaded = Vec::new();
+        let started = Instant::now();
+        let mut timings = Vec::new();
+        let candidates = Arc::new(self.scan_plugins(&mut timings)?);
 
-        let entries = std::fs::read_dir(&self.plugins_dir).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to read plugins directory: {}", e))
-        })?;
+        // Load plugins level by level so dependencies are initialized first;
+        // plugins within a level are independent and load concurrently
+        let manifests: Vec<PluginManifest> = candidates.iter().map(|c| c.manifest.clone()).collect();
+        let order = resolver::resolve_load_order(&manifests);
 
-        for entry in entries {
-            let entry = entry.map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to read directory entry: {}", e))
-            })?;
+        // Plugins with unsatisfied dependencies are registered, but never started
+        for unresolved in &order.unresolved {
+            if let Some(candidate) = candidates.get(unresolved.0) {
+                timings.push(self.register_blocked(candidate, unresolved.1.clone()));
+            }
+        }
 
-            let path = entry.path();
+        let parallelism = Self::load_parallelism();
+        let mut loaded: Vec<PluginInfo> = Vec::new();
 
-            if path.is_dir() {
-                // Unpacked plugin: directory containing manifest.json or plugin.wasm
-                let has_manifest = path.join("manifest.json").exists();
-                let has_wasm = path.join("plugin.wasm").exists();
-                
-                if has_manifest || has_wasm {
-                    match self.load_plugin(&path).await {
-                        Ok(info) => {
-                            tracing::info!("Loaded unpacked plugin: {} v{}", info.manifest.name, info.manifest.version);
-                            loaded.push(info);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to load unpacked plugin from {:?}: {}", path, e);
-                        }
-                    }
-                }
-            } else if let Some(ext) = path.extension() {
-                match ext.to_str() {
-                    Some("wasm") => {
-                        // Standalone plugin: single WASM file with embedded manifest
-                        match self.load_plugin(&path).await {
-                            Ok(info) => {
-                                tracing::info!("Loaded standalone plugin: {} v{}", info.manifest.name, info.manifest.version);
-                                loaded.push(info);
-                            }
-                            Err(e) => {
-                                tracing::warn!("Failed to load standalone plugin from {:?}: {}", path, e);
-                            }
-                        }
+        for level in &order.levels {
+            let level: Vec<usize> = level
+                .iter()
+                .copied()
+                .filter(|index| {
+                    // Skip plugins whose dependencies failed to initialize
+                    let Some(candidate) = candidates.get(*index) else {
+                        return false;
+                    };
+                    let failed = Self::failed_dependency(candidate, &manifests, &loaded);
+                    if let Some(dep) = failed {
+                        let issue = DependencyIssue::Unavailable { dependency: dep.to_string() };
+                        timings.push(self.register_blocked(candidate, vec![issue]));
                     }
-                    Some("zip") => {
-                        // Packed plugin: ZIP archive containing WASM, manifest, and assets
-                        match self.load_plugin(&path).await {
-                            Ok(info) => {
-                                tracing::info!("Loaded packed plugin: {} v{}", info.manifest.name, info.manifest.version);
-                                loaded.push(info);
-                            }
-                            Err(e) => {
-                                tracing::warn!("Failed to load packed plugin from {:?}: {}", path, e);
-                            }
-                        }
+                    failed.is_none()
+                })
+                .collect();
+
+            for (index, result, elapsed) in self.load_level(&candidates, level, parallelism).await? {
+                let Some(candidate) = candidates.get(index) else {
+                    continue;
+                };
+                let duration_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+                match result {
+                    Ok(info) => {
+                        tracing::info!(
+                            "Loaded {} plugin: {} v{} ({}ms)",
+                            candidate.flavor, info.manifest.name, info.manifest.version, duration_ms
+                        );
+                        timings.push(PluginLoadTiming {
+                            name: info.manifest.name.clone(),
+                            duration_ms,
+                            error: None,
+                            violation: None,
+                        });
+                        loaded.push(info);
                     }
-                    _ => {
-                        // Ignore other file types
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to load {} plugin from {:?}: {}",
+                            candidate.flavor, candidate.path, e
+                        );
+                        timings.push(PluginLoadTiming {
+                            name: candidate.manifest.name.clone(),
+                            duration_ms,
+                            error: Some(e.to_string()),
+                            violation: None,
+                        });
                     }
                 }
             }
         }
 
-        tracing::info!("Loaded {} plugins", loaded.len());
+        let total_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        tracing::info!("Loaded {} plugins in {}ms", loaded.len(), total_ms);
+        self.registry.set_load_report(PluginLoadReport {
+            total_ms,
+            parallelism,
+            plugins: timings,
+        });
         
         // Restore saved states (enabled/disabled) from previous session
         self.registry.restore_states()?;
@@ -182,7 +489,7 @@ impl PluginManager {
         // Note: Must get updated state from registry, not stale loaded vector
         for plugin in &loaded {
             if let Some(info) = self.registry.get(&plugin.manifest.name) {
-                if info.state == PluginState::Running {
+                if info.state == PluginState::Running && info.manifest.activation == PluginActivation::Eager {
                     tracing::info!("Auto-starting previously running plugin: {}", info.manifest.name);
                     if let Err(e) = self.runtime.start(&info.manifest.name).await {
                         tracing::error!("Failed to auto-start plugin {}: {}", info.manifest.name, e);
@@ -194,73 +501,814 @@ impl PluginManager {
         Ok(loaded)
     }
 
+    /// Scan the plugins directory and read the manifest of every plugin found.
+    ///
+    /// Plugins whose manifest cannot be read are recorded in `timings`.
+    fn scan_plugins(&self, timings: &mut Vec<PluginLoadTiming>) -> orbis_core::Result<Vec<PluginCandidate>> {
+        let entries = std::fs::read_dir(&self.plugins_dir).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to read plugins directory: {}", e))
+        })?;
+
+        let mut candidates = Vec::new();
+
+        for entry in entries {
+            let entry = entry.map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to read directory entry: {}", e))
+            })?;
+
+            let path = entry.path();
+
+            let flavor = if path.is_dir() {
+                // Unpacked plugin: directory containing manifest.json or plugin.wasm
+                let has_manifest = path.join("manifest.json").exists();
+                let has_wasm = path.join("plugin.wasm").exists();
+
+                if !has_manifest && !has_wasm {
+                    continue;
+                }
+                "unpacked"
+            } else {
+                match path.extension().and_then(|ext| ext.to_str()) {
+                    // Standalone plugin: single WASM file with embedded manifest
+                    Some("wasm") => "standalone",
+                    // Packed plugin: ZIP archive containing WASM, manifest, and assets
+                    Some("zip") => "packed",
+                    // Ignore other file types
+                    Some(_) | None => continue,
+                }
+            };
+
+            // Reject unsafe archives before anything is read from them
+            if flavor == "packed"
+                && let Err(e) = self.loader.check_packed(&path)
+            {
+                tracing::warn!("Failed to load {} plugin from {:?}: {}", flavor, path, e);
+                timings.push(PluginLoadTiming {
+                    name: path.display().to_string(),
+                    duration_ms: 0,
+                    error: Some(e.to_string()),
+                    violation: e.violation().cloned(),
+                });
+                continue;
+            }
+
+            let manifest = PluginSource::from_path(&path)
+                .and_then(|source| Ok((self.loader.load_manifest(&source)?, source)));
+
+            match manifest {
+                Ok((manifest, source)) => candidates.push(PluginCandidate {
+                    path,
+                    flavor,
+                    source,
+                    manifest,
+                }),
+                Err(e) => {
+                    tracing::warn!("Failed to load {} plugin from {:?}: {}", flavor, path, e);
+                    timings.push(PluginLoadTiming {
+                        name: path.display().to_string(),
+                        duration_ms: 0,
+                        error: Some(e.to_string()),
+                        violation: None,
+                    });
+                }
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Register a scanned plugin that cannot start because of its dependencies,
+    /// in the error state and without initializing it, returning its load timing.
+    fn register_blocked(&self, candidate: &PluginCandidate, issues: Vec<DependencyIssue>) -> PluginLoadTiming {
+        let reason = Self::describe_issues(&issues);
+        tracing::warn!(
+            "Failed to load {} plugin from {:?}: {}",
+            candidate.flavor, candidate.path, reason
+        );
+
+        self.registry.register(PluginInfo {
+            id: PluginId::generate(),
+            manifest: candidate.manifest.clone(),
+            source: candidate.source.clone(),
+            state: PluginState::Error,
+            loaded_at: chrono::Utc::now(),
+            dependency_issues: issues,
+        });
+
+        PluginLoadTiming {
+            name: candidate.manifest.name.clone(),
+            duration_ms: 0,
+            error: Some(reason),
+            violation: None,
+        }
+    }
+
+    /// Check a plugin's dependencies against the plugins already registered.
+    ///
+    /// Plugins blocked on their own dependencies do not count as installed.
+    fn registered_dependency_issues(&self, manifest: &PluginManifest) -> Vec<DependencyIssue> {
+        let plugins = self.registry.list();
+        let installed: HashMap<&str, &str> = plugins
+            .iter()
+            .filter(|info| !info.is_blocked() && info.manifest.name != manifest.name)
+            .map(|info| (info.manifest.name.as_str(), info.manifest.version.as_str()))
+            .collect();
+        resolver::dependency_issues(manifest, &installed)
+    }
+
+    /// Describe dependency issues in a single line.
+    fn describe_issues(issues: &[DependencyIssue]) -> String {
+        issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    }
+
+    /// Find a dependency that was found during the scan but failed to load.
+    fn failed_dependency<'a>(
+        candidate: &'a PluginCandidate,
+        manifests: &[PluginManifest],
+        loaded: &[PluginInfo],
+    ) -> Option<&'a str> {
+        candidate
+            .manifest
+            .dependencies
+            .iter()
+            .find(|dep| {
+                manifests.iter().any(|m| m.name == dep.name)
+                    && !loaded.iter().any(|info| info.manifest.name == dep.name)
+            })
+            .map(|dep| dep.name.as_str())
+    }
+
+    /// Load a group of independent plugins, given by candidate index,
+    /// concurrently on blocking threads so the async workers stay free.
+    ///
+    /// Results are returned in the order of `level`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the loading threads panicked.
+    async fn load_level(
+        self: &Arc<Self>,
+        candidates: &Arc<Vec<PluginCandidate>>,
+        level: Vec<usize>,
+        parallelism: usize,
+    ) -> orbis_core::Result<Vec<(usize, orbis_core::Result<PluginInfo>, Duration)>> {
+        let manager = Arc::clone(self);
+        let candidates = Arc::clone(candidates);
+        tokio::task::spawn_blocking(move || {
+            let level: Vec<(usize, &PluginCandidate)> = level
+                .into_iter()
+                .filter_map(|index| Some((index, candidates.get(index)?)))
+                .collect();
+            manager.load_level_blocking(&level, parallelism)
+        })
+        .await
+        .map_err(|e| orbis_core::Error::plugin(format!("Failed to load plugins: {}", e)))
+    }
+
+    /// Load a group of independent plugins concurrently on scoped threads.
+    ///
+    /// Results are returned in the order of `candidates`, with their index.
+    fn load_level_blocking(
+        &self,
+        candidates: &[(usize, &PluginCandidate)],
+        parallelism: usize,
+    ) -> Vec<(usize, orbis_core::Result<PluginInfo>, Duration)> {
+        let next = AtomicUsize::new(0);
+        let results: parking_lot::Mutex<Vec<Option<TimedLoad>>> =
+            parking_lot::Mutex::new((0..candidates.len()).map(|_| None).collect());
+
+        std::thread::scope(|scope| {
+            for _ in 0..parallelism.min(candidates.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(_, candidate)) = candidates.get(index) else {
+                        break;
+                    };
+
+                    let started = Instant::now();
+                    let result =
+                        self.register_and_initialize(candidate.source.clone(), candidate.manifest.clone(), None);
+                    if let Some(slot) = results.lock().get_mut(index) {
+                        *slot = Some((result, started.elapsed()));
+                    }
+                });
+            }
+        });
+
+        candidates
+            .iter()
+            .zip(results.into_inner())
+            .filter_map(|(candidate, result)| result.map(|(result, elapsed)| (candidate.0, result, elapsed)))
+            .collect()
+    }
+
+    /// Maximum number of plugins initialized concurrently.
+    fn load_parallelism() -> usize {
+        std::thread::available_parallelism()
+            .map_or(1, std::num::NonZeroUsize::get)
+            .min(MAX_PARALLEL_LOADS)
+    }
+
     /// Load a single plugin from a path.
     ///
     /// # Errors
     ///
     /// Returns an error if the plugin cannot be loaded.
     pub async fn load_plugin(&self, path: &PathBuf) -> orbis_core::Result<PluginInfo> {
+        self.load_plugin_from(path, None)
+    }
+
+    /// Install a plugin from a path as a tracked operation.
+    ///
+    /// Each stage is reported to the operation, which can be cancelled
+    /// through [`PluginManager::operations`]. A cancelled install leaves no
+    /// trace of the plugin. The installed version and its release notes are
+    /// recorded in the registry, and the plugin is enabled if its policy sets
+    /// `auto_enable`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be loaded or the operation was
+    /// cancelled.
+    pub async fn install_plugin(&self, path: &PathBuf, operation: &PluginOperation) -> orbis_core::Result<PluginInfo> {
+        let info = self.tracked(operation, async { self.load_plugin_from(path, Some(operation)) }).await?;
+        self.registry.record_version_change(VersionChange::new(None, &info.manifest));
+        self.auto_enable(info).await
+    }
+
+    /// Fetch a remote plugin, install it into the plugins directory and load it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be fetched, verified or loaded.
+    pub async fn load_remote(&self, source: &RemoteSource) -> orbis_core::Result<PluginInfo> {
+        self.load_remote_from(source, None).await
+    }
+
+    /// Fetch a remote plugin and install it as a tracked operation.
+    ///
+    /// Like [`PluginManager::install_plugin`], stages are reported to the
+    /// operation and the installed version is recorded in the registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be fetched, verified or loaded,
+    /// or the operation was cancelled.
+    pub async fn install_remote(&self, source: &RemoteSource, operation: &PluginOperation) -> orbis_core::Result<PluginInfo> {
+        let info = self.tracked(operation, self.load_remote_from(source, Some(operation))).await?;
+        self.registry.record_version_change(VersionChange::new(None, &info.manifest));
+        self.auto_enable(info).await
+    }
+
+    /// Enable a newly installed plugin if its policy enables plugins on install.
+    async fn auto_enable(&self, info: PluginInfo) -> orbis_core::Result<PluginInfo> {
+        if self.runtime.plugin_policy(&info.manifest.name).auto_enable != Some(true) {
+            return Ok(info);
+        }
+        let cause = StateCause::new(StateTrigger::Load).because("enabled on install");
+        self.enable_plugin_with(&info.manifest.name, cause).await?;
+        Ok(self.registry.get(&info.manifest.name).unwrap_or(info))
+    }
+
+    /// Fetch a remote plugin, verify it, then copy it into the plugins directory and load it.
+    ///
+    /// Nothing is written into the plugins directory unless the fetched file
+    /// has the expected hash, is a safe archive and passes the signature policy.
+    async fn load_remote_from(&self, source: &RemoteSource, operation: Option<&PluginOperation>) -> orbis_core::Result<PluginInfo> {
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::Downloading)?;
+        }
+        let cached = self.remote.fetch(source).await?;
+
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::VerifyingSignature)?;
+        }
+        let fetched = PluginSource::from_path(&cached)?;
+        if let PluginSource::Packed(zip_path) = &fetched {
+            self.loader.check_packed(zip_path)?;
+        }
+        let manifest = self.loader.load_manifest(&fetched)?;
+        if let Some(operation) = operation {
+            operation.set_plugin(&manifest.name);
+        }
+        self.check_signature(&fetched, &manifest)?;
+
+        let target = self.plugins_dir.join(source.file_name()?);
+        if target.exists() {
+            return Err(orbis_core::Error::conflict(format!("{} already exists", target.display())));
+        }
+
+        // Copy under an ignored name, so the watcher only sees the finished file
+        let staging = self.plugins_dir.join(format!("{}.tmp", source.file_name()?));
+        std::fs::copy(&cached, &staging)?;
+        std::fs::rename(&staging, &target).inspect_err(|_| {
+            if let Err(e) = std::fs::remove_file(&staging) {
+                tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+            }
+        })?;
+
+        self.load_plugin_from(&target, operation)
+    }
+
+    /// Read a plugin from a path, then register and initialize it.
+    fn load_plugin_from(&self, path: &PathBuf, operation: Option<&PluginOperation>) -> orbis_core::Result<PluginInfo> {
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::Downloading)?;
+        }
         let source = PluginSource::from_path(path)?;
+        if let PluginSource::Packed(zip_path) = &source {
+            self.loader.check_packed(zip_path)?;
+        }
         let manifest = self.loader.load_manifest(&source)?;
+        if let Some(operation) = operation {
+            operation.set_plugin(&manifest.name);
+        }
+
+        self.register_and_initialize(source, manifest, operation)
+    }
+
+    /// Run a tracked operation, reporting its outcome.
+    async fn tracked<T, F>(&self, operation: &PluginOperation, future: F) -> orbis_core::Result<T>
+    where
+        F: std::future::Future<Output = orbis_core::Result<T>>,
+    {
+        self.operations.track(operation);
+        let result = future.await;
+        self.operations.untrack(operation.id());
+        operation.finish(&result);
+        result
+    }
+
+    /// Validate, register and initialize a plugin on the current thread.
+    ///
+    /// If the operation is cancelled after the plugin was registered, the
+    /// registration is rolled back.
+    fn register_and_initialize(
+        &self,
+        source: PluginSource,
+        mut manifest: PluginManifest,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<PluginInfo> {
+        self.validate_for_load(&source, &mut manifest, operation)?;
+
+        // Check if plugin already exists; a plugin blocked on its dependencies
+        // is replaced, so it can be retried once they are installed
+        if let Some(existing) = self.registry.get(&manifest.name) {
+            if !existing.is_blocked() {
+                return Err(orbis_core::Error::plugin(format!(
+                    "Plugin '{}' is already loaded",
+                    manifest.name
+                )));
+            }
+            self.registry.unregister(&manifest.name);
+        }
+
+        // Check its dependencies are installed with compatible versions
+        let dependency_issues = self.registered_dependency_issues(&manifest);
+        if !dependency_issues.is_empty() {
+            let reason = Self::describe_issues(&dependency_issues);
+            let name = manifest.name.clone();
+            self.registry.register(PluginInfo {
+                id: PluginId::generate(),
+                manifest,
+                source,
+                state: PluginState::Error,
+                loaded_at: chrono::Utc::now(),
+                dependency_issues,
+            });
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' has unsatisfied dependencies: {}",
+                name, reason
+            )));
+        }
+
+        // Create plugin info
+        let info = PluginInfo {
+            id: PluginId::generate(),
+            manifest,
+            source: source.clone(),
+            state: PluginState::Loaded,
+            loaded_at: chrono::Utc::now(),
+            dependency_issues: Vec::new(),
+        };
+
+        // Register the plugin
+        self.registry.register(info.clone());
+        self.hooks.register(&info.manifest);
+
+        if let Err(e) = self.initialize_registered(&info, &source, operation) {
+            if operation.is_some_and(PluginOperation::is_cancelled) {
+                self.runtime.deactivate(&info.manifest.name);
+                self.registry.unregister(&info.manifest.name);
+                self.hooks.unregister(&info.manifest.name);
+            } else {
+                let cause = StateCause::new(StateTrigger::Crash).because(e.to_string());
+                let _ = self.registry.set_state(&info.manifest.name, PluginState::Error, cause);
+            }
+            return Err(e);
+        }
+
+        Ok(info)
+    }
+
+    /// Validate a plugin before it is loaded: its manifest, host API
+    /// compatibility, signature and requested permissions.
+    fn validate_for_load(
+        &self,
+        source: &PluginSource,
+        manifest: &mut PluginManifest,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<()> {
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::VerifyingSignature)?;
+        }
+
+        // Validate manifest, and compose pages
+        manifest.validate()?;
+        manifest.compose_pages()?;
+
+        // Check the plugin supports this host API version
+        self.check_compatibility(manifest)?;
+
+        // Check no other plugin owns the plugin's tables
+        self.check_table_ownership(manifest)?;
+
+        // Check the plugin is signed by a trusted key
+        self.check_signature(source, manifest)?;
+
+        // Check the plugin only requests permissions its policy allows
+        let policy = self.runtime.plugin_policy(&manifest.name);
+        if let Some(permission) = manifest
+            .permissions
+            .iter()
+            .find(|permission| !policy.allows_permission(permission.name()))
+        {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' requests the '{}' permission, which its policy does not allow",
+                manifest.name,
+                permission.name()
+            )));
+        }
+
+        if let Some(notice) = manifest.deprecated.as_ref() {
+            tracing::warn!("Plugin '{}' is deprecated: {}", manifest.name, notice);
+        }
+
+        Ok(())
+    }
+
+    /// Initialize a registered plugin in the runtime, unless deferred until first use.
+    fn initialize_registered(
+        &self,
+        info: &PluginInfo,
+        source: &PluginSource,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<()> {
+        let lazy = info.manifest.activation == PluginActivation::Lazy;
+        if lazy {
+            tracing::debug!("Deferring activation of lazy plugin: {}", info.manifest.name);
+        } else {
+            self.runtime.initialize_blocking(info, source, operation)?;
+        }
+
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::Starting)?;
+        }
+        if !lazy {
+            self.registry.set_snapshot(&info.manifest.name, self.runtime.snapshot_info(&info.manifest.name));
+        }
+        Ok(())
+    }
+
+    /// Check a plugin against the host API version, recording the result in the registry.
+    fn check_compatibility(&self, manifest: &PluginManifest) -> orbis_core::Result<()> {
+        let mut compatibility = PluginCompatibility::check(manifest);
+        if compatibility.is_compatible() {
+            self.registry.set_compatibility(compatibility);
+            return Ok(());
+        }
+
+        let message = compatibility.describe();
+        let policy = *self.compatibility_policy.read();
+        match policy {
+            CompatibilityPolicy::Refuse => {
+                self.registry.set_compatibility(compatibility);
+                Err(orbis_core::Error::plugin(message))
+            }
+            CompatibilityPolicy::Warn => {
+                tracing::warn!("{}; loading anyway", message);
+                compatibility.overridden = true;
+                self.registry.set_compatibility(compatibility);
+                Ok(())
+            }
+        }
+    }
+
+    /// Check none of a plugin's tables is owned by another registered plugin.
+    fn check_table_ownership(&self, manifest: &PluginManifest) -> orbis_core::Result<()> {
+        if manifest.tables.is_empty() {
+            return Ok(());
+        }
+
+        for other in self.registry.list() {
+            if other.manifest.name == manifest.name {
+                continue;
+            }
+            if let Some(table) = manifest.tables.iter().find(|table| other.manifest.tables.contains(table)) {
+                return Err(orbis_core::Error::plugin(format!(
+                    "Table '{}' of plugin '{}' is already owned by plugin '{}'",
+                    table, manifest.name, other.manifest.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify a plugin's signature, applying the signature policy.
+    fn check_signature(&self, source: &PluginSource, manifest: &PluginManifest) -> orbis_core::Result<()> {
+        let policy = *self.signature_policy.read();
+        let allow_unsigned = self.runtime.plugin_policy(&manifest.name).allow_unsigned;
+        if policy == SignaturePolicy::Off && allow_unsigned != Some(false) {
+            return Ok(());
+        }
+
+        let keyring = self.keyring.read().clone();
+        let problem = match self.loader.verify_signature(source, &keyring) {
+            Ok(SignatureStatus::Verified { key_id }) => {
+                tracing::debug!("Plugin '{}' is signed by {}", manifest.name, key_id);
+                return Ok(());
+            }
+            // The plugin policy decides for unsigned plugins when it is set
+            Ok(SignatureStatus::Unsigned) if allow_unsigned == Some(true) => return Ok(()),
+            Ok(SignatureStatus::Unsigned) if allow_unsigned == Some(false) => {
+                return Err(orbis_core::Error::plugin(format!(
+                    "Plugin '{}' is not signed, and its policy does not allow unsigned plugins",
+                    manifest.name
+                )));
+            }
+            Ok(SignatureStatus::Unsigned) => "is not signed".to_owned(),
+            Err(e) => format!("failed signature verification: {}", e),
+        };
+
+        let message = format!("Plugin '{}' {}", manifest.name, problem);
+        if policy == SignaturePolicy::Require {
+            return Err(orbis_core::Error::plugin(message));
+        }
+        tracing::warn!("{}; loading anyway", message);
+        Ok(())
+    }
+
+    /// Unload a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be unloaded.
+    pub async fn unload_plugin(&self, name: &str) -> orbis_core::Result<()> {
+        self.unload_plugin_with(name, StateCause::new(StateTrigger::Unload)).await
+    }
+
+    /// Unload a plugin, recording the cause in its state history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be unloaded.
+    pub async fn unload_plugin_with(&self, name: &str, cause: StateCause) -> orbis_core::Result<()> {
+        let info = self.registry.get(name).ok_or_else(|| {
+            orbis_core::Error::plugin(format!("Plugin '{}' not found", name))
+        })?;
+
+        // Stop the plugin runtime (ignore errors if not running)
+        let _ = self.runtime.stop(&info.manifest.name).await;
+
+        // Clear runtime, page data and response caches
+        self.runtime.clear_cache(name);
+        self.page_cache.invalidate_plugin(name);
+        self.runtime.response_cache().invalidate_plugin(name);
+        self.last_used.remove(name);
+
+        // Unregister the plugin
+        self.registry.unregister_with(name, cause);
+        self.hooks.unregister(name);
+
+        tracing::info!("Unloaded plugin: {}", name);
+        Ok(())
+    }
+
+    /// Export all data of a plugin (state, tenant states, tables and data files) as a ZIP archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin is not running or its data cannot be read.
+    pub async fn export_plugin_data(&self, name: &str) -> orbis_core::Result<Vec<u8>> {
+        let info = self
+            .registry
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+
+        let mut archive = self.runtime.export_data(name).await?;
+        archive.plugin_version = Some(info.manifest.version.clone());
+        archive.tables = archive::export_tables(&self.db, &info.manifest).await?;
+
+        tracing::info!("Exported data of plugin: {}", name);
+        archive.to_zip()
+    }
+
+    /// Replace all data of a plugin with the contents of an archive from [`Self::export_plugin_data`].
+    ///
+    /// Tables are imported first, in one transaction, so a failed import leaves
+    /// the plugin's state and files untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid, belongs to another plugin,
+    /// or the data cannot be written.
+    pub async fn import_plugin_data(&self, name: &str, bytes: &[u8]) -> orbis_core::Result<()> {
+        let info = self
+            .registry
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+
+        let archive = PluginDataArchive::from_zip_with_limits(bytes, self.loader.archive_limits())?;
+        if archive.plugin != name {
+            return Err(orbis_core::Error::validation(format!(
+                "Data archive belongs to plugin '{}', not '{}'",
+                archive.plugin, name
+            )));
+        }
+
+        archive::import_tables(&self.db, &info.manifest, &archive.tables).await?;
+        self.runtime.import_data(name, &archive).await?;
+        self.page_cache.invalidate_plugin(name);
+        self.runtime.response_cache().invalidate_plugin(name);
+
+        tracing::info!("Imported data of plugin: {}", name);
+        Ok(())
+    }
+
+    /// Search all running plugins whose search provider the viewer may access.
+    ///
+    /// Providers are queried concurrently. A provider that fails or does not
+    /// answer within its timeout is interrupted and reported in `failed`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty or too long.
+    pub async fn search(self: &std::sync::Arc<Self>, mut request: SearchRequest) -> orbis_core::Result<SearchResponse> {
+        request.normalize()?;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for info in self.registry.list_by_state(PluginState::Running) {
+            let Some(provider) = info
+                .manifest
+                .search_provider
+                .filter(|provider| provider.is_accessible_by(request.viewer.as_ref()))
+            else {
+                continue;
+            };
+
+            let timeout = Duration::from_millis(provider.timeout_ms());
+            let cancellation = CancellationFlag::new();
+            let context = PluginContext {
+                method: "GET".to_string(),
+                path: "/search".to_string(),
+                headers: std::collections::HashMap::new(),
+                query: std::collections::HashMap::from([
+                    ("q".to_string(), request.query.clone()),
+                    ("limit".to_string(), request.limit.to_string()),
+                ]),
+                body: serde_json::Value::Null,
+                user_id: request.user_id.clone(),
+                is_admin: request.is_admin,
+                tenant_id: request.tenant_id.clone(),
+                deadline: chrono::Duration::from_std(timeout)
+                    .ok()
+                    .and_then(|timeout| chrono::Utc::now().checked_add_signed(timeout)),
+                groups: Vec::new(),
+                features: std::collections::BTreeMap::new(),
+                cancellation: cancellation.clone(),
+            };
+
+            let manager = std::sync::Arc::clone(self);
+            let plugin = info.manifest.name;
+            tasks.spawn(async move {
+                // Interrupt the provider if it outlives its timeout
+                let _guard = cancellation.cancel_on_drop();
+                let result = tokio::time::timeout(timeout, manager.execute_route(&plugin, &provider.handler, context))
+                    .await
+                    .unwrap_or_else(|_| Err(orbis_core::Error::plugin("Search provider timed out")));
+                (plugin, result)
+            });
+        }
+
+        let mut response = SearchResponse {
+            query: request.query.clone(),
+            ..SearchResponse::default()
+        };
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((plugin, result)) = joined else {
+                continue;
+            };
+
+            match result {
+                Ok(value) => response.results.extend(
+                    SearchResult::parse_response(&value)
+                        .into_iter()
+                        .map(|result| SearchHit::new(&plugin, &request.query, result)),
+                ),
+                Err(e) => {
+                    tracing::warn!("Search provider of plugin {} failed: {}", plugin, e);
+                    response.failed.push(plugin);
+                }
+            }
+        }
 
-        // Validate manifest
-        manifest.validate()?;
+        response.rank(request.limit);
+        Ok(response)
+    }
 
-        // Check if plugin already exists
-        if self.registry.get(&manifest.name).is_some() {
-            return Err(orbis_core::Error::plugin(format!(
-                "Plugin '{}' is already loaded",
-                manifest.name
-            )));
+    /// Run the hook handlers subscribed to an event.
+    ///
+    /// Handlers of running plugins are called one at a time in priority
+    /// order, each with its own timeout. A failing or timed out handler is
+    /// logged and does not stop the others.
+    pub async fn run_hooks(&self, event: &HookEvent, user_id: Option<String>) -> Vec<HookOutcome> {
+        let point = event.point();
+        if !self.hooks.has_subscribers(point) {
+            return Vec::new();
         }
 
-        // Create plugin info
-        let info = PluginInfo {
-            id: Uuid::now_v7(),
-            manifest: manifest.clone(),
-            source: source.clone(),
-            state: PluginState::Loaded,
-            loaded_at: chrono::Utc::now(),
+        let body = match serde_json::to_value(event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("Failed to serialize {} hook event: {}", point, e);
+                return Vec::new();
+            }
         };
 
-        // Register the plugin
-        self.registry.register(info.clone());
+        let mut outcomes = Vec::new();
+        for hook in self.hooks.subscribers(point) {
+            if self.registry.get(&hook.plugin).is_none_or(|info| info.state != PluginState::Running) {
+                continue;
+            }
 
-        // Initialize the plugin in the runtime
-        self.runtime.initialize(&info, &source).await?;
+            let cancellation = CancellationFlag::new();
+            let context = PluginContext {
+                method: "POST".to_string(),
+                path: format!("/hooks/{}", point),
+                headers: std::collections::HashMap::new(),
+                query: std::collections::HashMap::new(),
+                body: body.clone(),
+                user_id: user_id.clone(),
+                is_admin: false,
+                tenant_id: None,
+                deadline: chrono::Duration::from_std(hook.timeout)
+                    .ok()
+                    .and_then(|timeout| chrono::Utc::now().checked_add_signed(timeout)),
+                groups: Vec::new(),
+                features: std::collections::BTreeMap::new(),
+                cancellation: cancellation.clone(),
+            };
 
-        Ok(info)
+            let started = Instant::now();
+            let result = {
+                // Interrupt the handler if it outlives its timeout
+                let _guard = cancellation.cancel_on_drop();
+                tokio::time::timeout(hook.timeout, self.execute_handler(&hook.plugin, &hook.handler, context))
+                    .await
+                    .unwrap_or_else(|_| Err(orbis_core::Error::plugin("Hook handler timed out")))
+            };
+
+            let error = result.err().map(|e| {
+                tracing::warn!("Hook {} of plugin {} ({}) failed: {}", point, hook.plugin, hook.handler, e);
+                e.to_string()
+            });
+            outcomes.push(HookOutcome {
+                plugin: hook.plugin,
+                handler: hook.handler,
+                duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+                error,
+            });
+        }
+
+        outcomes
     }
 
-    /// Unload a plugin.
+    /// Enable a plugin.
     ///
     /// # Errors
     ///
-    /// Returns an error if the plugin cannot be unloaded.
-    pub async fn unload_plugin(&self, name: &str) -> orbis_core::Result<()> {
-        let info = self.registry.get(name).ok_or_else(|| {
-            orbis_core::Error::plugin(format!("Plugin '{}' not found", name))
-        })?;
-
-        // Stop the plugin runtime (ignore errors if not running)
-        let _ = self.runtime.stop(&info.manifest.name).await;
-
-        // Clear runtime cache
-        self.runtime.clear_cache(name);
-
-        // Unregister the plugin
-        self.registry.unregister(name);
-
-        tracing::info!("Unloaded plugin: {}", name);
-        Ok(())
+    /// Returns an error if the plugin cannot be enabled.
+    pub async fn enable_plugin(&self, name: &str) -> orbis_core::Result<()> {
+        self.enable_plugin_with(name, StateCause::new(StateTrigger::Manual)).await
     }
 
-    /// Enable a plugin.
+    /// Enable a plugin, recording the cause in its state history.
     ///
     /// # Errors
     ///
     /// Returns an error if the plugin cannot be enabled.
-    pub async fn enable_plugin(&self, name: &str) -> orbis_core::Result<()> {
+    pub async fn enable_plugin_with(&self, name: &str, cause: StateCause) -> orbis_core::Result<()> {
         // Check if plugin exists in registry
         let info = self.registry.get(name).ok_or_else(|| {
             orbis_core::Error::plugin(format!(
@@ -273,15 +1321,24 @@ impl PluginManager {
         if info.state == PluginState::Running {
             return Ok(()); // Already enabled
         }
+
+        if info.is_blocked() {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' cannot be enabled: {}",
+                name,
+                Self::describe_issues(&info.dependency_issues)
+            )));
+        }
         
-        // If the plugin is not loaded in runtime, re-initialize it
-        if !self.runtime.is_running(name) {
+        // If the plugin is not loaded in runtime, re-initialize it (lazy plugins wait for first use)
+        if !self.runtime.is_running(name) && info.manifest.activation == PluginActivation::Eager {
             // Need to reload the plugin into runtime
             self.runtime.initialize(&info, &info.source).await?;
+            self.registry.set_snapshot(name, self.runtime.snapshot_info(name));
         }
         
         // Update state
-        self.registry.set_state(name, PluginState::Running)?;
+        self.registry.set_state(name, PluginState::Running, cause)?;
         
         tracing::info!("Enabled plugin: {}", name);
         Ok(())
@@ -293,6 +1350,15 @@ impl PluginManager {
     ///
     /// Returns an error if the plugin cannot be disabled.
     pub async fn disable_plugin(&self, name: &str) -> orbis_core::Result<()> {
+        self.disable_plugin_with(name, StateCause::new(StateTrigger::Manual)).await
+    }
+
+    /// Disable a plugin, recording the cause in its state history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be disabled.
+    pub async fn disable_plugin_with(&self, name: &str, cause: StateCause) -> orbis_core::Result<()> {
         // Check if plugin exists
         let info = self.registry.get(name).ok_or_else(|| {
             orbis_core::Error::plugin(format!("Plugin '{}' not found", name))
@@ -305,9 +1371,11 @@ impl PluginManager {
         
         // Stop the runtime instance if it exists (ignore errors if not running)
         let _ = self.runtime.stop(name).await;
+        self.page_cache.invalidate_plugin(name);
+        self.runtime.response_cache().invalidate_plugin(name);
         
         // Update state
-        self.registry.set_state(name, PluginState::Disabled)?;
+        self.registry.set_state(name, PluginState::Disabled, cause)?;
         
         tracing::info!("Disabled plugin: {}", name);
         Ok(())
@@ -315,12 +1383,36 @@ impl PluginManager {
 
     /// Reload a plugin (hot reload).
     ///
-    /// Unloads the current version and reloads from disk.
+    /// The new version is loaded from disk and validated before it replaces
+    /// the current one, taking over its state. If it fails to load, the
+    /// current version keeps running.
     ///
     /// # Errors
     ///
     /// Returns an error if the plugin cannot be reloaded.
     pub async fn reload_plugin(&self, name: &str) -> orbis_core::Result<PluginInfo> {
+        self.reload_plugin_from(name, None).await
+    }
+
+    /// Reload a plugin as a tracked operation.
+    ///
+    /// Each stage is reported to the operation, which can be cancelled
+    /// through [`PluginManager::operations`] until the new version is swapped
+    /// in. Like a failed reload, a cancelled reload keeps the current version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be reloaded or the operation was
+    /// cancelled.
+    pub async fn reload_plugin_tracked(&self, name: &str, operation: &PluginOperation) -> orbis_core::Result<PluginInfo> {
+        operation.set_plugin(name);
+        self.tracked(operation, self.reload_plugin_from(name, Some(operation))).await
+    }
+
+    /// Reload a plugin, reporting stages to the operation, if any.
+    ///
+    /// A new version is recorded in the registry with its release notes.
+    async fn reload_plugin_from(&self, name: &str, operation: Option<&PluginOperation>) -> orbis_core::Result<PluginInfo> {
         // Get current plugin info to find the source path
         let old_info = self.registry.get(name).ok_or_else(|| {
             orbis_core::Error::plugin(format!("Plugin '{}' not found", name))
@@ -339,23 +1431,50 @@ impl PluginManager {
 
         tracing::info!("Hot reloading plugin: {}", name);
 
-        // Stop the plugin runtime
-        self.runtime.stop(name).await?;
+        // Load and validate the new version next to the running one; the
+        // operation can no longer be cancelled once it is swapped in
+        let compatibility = self.registry.compatibility(name);
+        let staged = self.stage_plugin(&source_path, name, operation).and_then(|staged| {
+            if let Some(operation) = operation {
+                operation.enter(OperationStage::Starting)?;
+            }
+            Ok(staged)
+        });
+        let (new_info, staged) = match staged {
+            Ok(staged) => staged,
+            Err(e) => return Err(self.keep_previous_version(&old_info, compatibility, e)),
+        };
+
+        // Swap the new version in, handing over the state of the old one
+        let lazy = new_info.manifest.activation == PluginActivation::Lazy;
+        let replaced = self.runtime.commit(name, staged, !lazy);
+        if old_info.state == PluginState::Running
+            && !lazy
+            && let Err(e) = self.runtime.start(name).await
+        {
+            self.runtime.restore(name, replaced);
+            return Err(self.keep_previous_version(&old_info, compatibility, e));
+        }
 
-        // Unregister the old version
-        self.registry.unregister(name);
+        self.registry.replace(new_info.clone(), StateCause::new(StateTrigger::Reload));
+        self.hooks.unregister(name);
+        self.hooks.register(&new_info.manifest);
+        if !lazy {
+            self.registry.set_snapshot(name, self.runtime.snapshot_info(name));
+        }
 
-        // Clear runtime cache for this plugin
-        self.runtime.clear_cache(name);
+        // Clear page data and response caches of the old version
+        self.page_cache.invalidate_plugin(name);
+        self.runtime.response_cache().invalidate_plugin(name);
 
-        // Load the new version
-        let new_info = self.load_plugin(&source_path).await?;
+        if new_info.manifest.version != old_info.manifest.version {
+            self.registry
+                .record_version_change(VersionChange::new(Some(&old_info.manifest), &new_info.manifest));
+        }
 
         // Start the new version if it was running before
         if old_info.state == PluginState::Running {
-            self.runtime.start(&new_info.manifest.name).await?;
-            self.registry
-                .set_state(&new_info.manifest.name, PluginState::Running)?;
+            self.registry.set_state(name, PluginState::Running, StateCause::new(StateTrigger::Reload))?;
         }
 
         tracing::info!(
@@ -367,31 +1486,239 @@ impl PluginManager {
         Ok(new_info)
     }
 
-    /// Reload a plugin by path (for file watcher events).
+    /// Load and validate a new version of a plugin from a path, without
+    /// touching the running version.
     ///
-    /// # Errors
+    /// Lazily activated plugins are compiled too, so a broken version is
+    /// caught before it replaces the running one.
+    fn stage_plugin(
+        &self,
+        path: &PathBuf,
+        name: &str,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<(PluginInfo, StagedInstance)> {
+        if let Some(operation) = operation {
+            operation.enter(OperationStage::Downloading)?;
+        }
+        let source = PluginSource::from_path(path)?;
+        if let PluginSource::Packed(ref zip_path) = source {
+            self.loader.check_packed(zip_path)?;
+        }
+        let mut manifest = self.loader.load_manifest(&source)?;
+        if manifest.name != name {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' was renamed to '{}'; install it as a new plugin instead",
+                name, manifest.name
+            )));
+        }
+
+        self.validate_for_load(&source, &mut manifest, operation)?;
+
+        let dependency_issues = self.registered_dependency_issues(&manifest);
+        if !dependency_issues.is_empty() {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' has unsatisfied dependencies: {}",
+                name,
+                Self::describe_issues(&dependency_issues)
+            )));
+        }
+
+        let info = PluginInfo {
+            id: PluginId::generate(),
+            manifest,
+            source,
+            state: PluginState::Loaded,
+            loaded_at: chrono::Utc::now(),
+            dependency_issues: Vec::new(),
+        };
+        let staged = self.runtime.prepare(&info, &info.source, operation)?;
+
+        Ok((info, staged))
+    }
+
+    /// Give up on a reload, keeping the running version of the plugin.
+    fn keep_previous_version(
+        &self,
+        old_info: &PluginInfo,
+        compatibility: Option<PluginCompatibility>,
+        error: orbis_core::Error,
+    ) -> orbis_core::Error {
+        // Staging checked the compatibility of the new version
+        if let Some(compatibility) = compatibility {
+            self.registry.set_compatibility(compatibility);
+        }
+        tracing::warn!(
+            "Hot reload of plugin '{}' failed, keeping v{}: {}",
+            old_info.manifest.name,
+            old_info.manifest.version,
+            error
+        );
+        error
+    }
+
+    /// Enable all plugins, dependencies first.
+    pub async fn enable_all(&self, cause: StateCause) -> BulkReport {
+        self.run_bulk(BulkAction::Enable, None, cause).await
+    }
+
+    /// Disable all plugins, dependencies last.
+    pub async fn disable_all(&self, cause: StateCause) -> BulkReport {
+        self.run_bulk(BulkAction::Disable, None, cause).await
+    }
+
+    /// Reload all plugins, dependencies first.
+    pub async fn reload_all(&self) -> BulkReport {
+        self.run_bulk(BulkAction::Reload, None, StateCause::new(StateTrigger::Reload)).await
+    }
+
+    /// Apply an action to the given plugins, or to all plugins.
     ///
-    /// Returns an error if the plugin cannot be reloaded.
-    pub async fn reload_plugin_by_path(&self, path: &PathBuf) -> orbis_core::Result<Option<PluginInfo>> {
-        // Find plugin by path
-        let plugin_name = self.registry.list().iter().find_map(|info| {
-            let source_path = match &info.source {
-                PluginSource::Unpacked(p) | PluginSource::Standalone(p) | PluginSource::Packed(p) => {
-                    Some(p)
-                }
-                PluginSource::Remote(_) => None,
-            };
+    /// Plugins are visited one at a time in dependency order (see
+    /// [`BulkAction`]). A failure does not stop the operation, but plugins
+    /// waiting on a failed plugin are skipped. The cause is recorded for
+    /// enabled and disabled plugins.
+    pub async fn run_bulk(&self, action: BulkAction, plugins: Option<&[String]>, cause: StateCause) -> BulkReport {
+        let mut report = BulkReport::new(action);
+        let manifests: Vec<PluginManifest> = self
+            .registry
+            .list()
+            .into_iter()
+            .filter(|info| plugins.is_none_or(|plugins| plugins.contains(&info.manifest.name)))
+            .map(|info| info.manifest)
+            .collect();
+        for name in plugins.unwrap_or_default() {
+            if !manifests.iter().any(|manifest| &manifest.name == name) {
+                report.failed.push(BulkFailure {
+                    plugin: name.clone(),
+                    error: format!("Plugin '{}' not found", name),
+                });
+            }
+        }
+
+        let plan = bulk::BulkPlan::new(&manifests, action);
+        let mut blocked: std::collections::HashSet<String> = std::collections::HashSet::new();
+        for name in &plan.order {
+            if let Some(waited) = plan.waits_on(name).iter().find(|waited| blocked.contains(*waited)) {
+                let relation = if action == BulkAction::Disable { "dependent" } else { "dependency" };
+                report.skipped.push(BulkFailure {
+                    plugin: name.clone(),
+                    error: format!("{} '{}' failed", relation, waited),
+                });
+                blocked.insert(name.clone());
+                continue;
+            }
 
-            if let Some(sp) = source_path {
-                // Check if the changed path is within or is the plugin path
-                if path.starts_with(sp) || path == sp {
-                    return Some(info.manifest.name.clone());
+            let result = match action {
+                BulkAction::Enable => self.enable_plugin_with(name, cause.clone()).await,
+                BulkAction::Disable => self.disable_plugin_with(name, cause.clone()).await,
+                BulkAction::Reload => self.reload_plugin(name).await.map(|_| ()),
+            };
+            match result {
+                Ok(()) => report.succeeded.push(name.clone()),
+                Err(e) => {
+                    tracing::warn!("Failed to {} plugin {}: {}", action.as_str(), name, e);
+                    report.failed.push(BulkFailure {
+                        plugin: name.clone(),
+                        error: e.to_string(),
+                    });
+                    blocked.insert(name.clone());
                 }
             }
-            None
-        });
+        }
+
+        tracing::info!(
+            "Bulk {}: {} succeeded, {} failed, {} skipped",
+            action.as_str(),
+            report.succeeded.len(),
+            report.failed.len(),
+            report.skipped.len()
+        );
+        report
+    }
+
+    /// Load the feature flag overrides stored in the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn load_feature_overrides(&self) -> orbis_core::Result<()> {
+        self.features.load(&self.db).await
+    }
+
+    /// Get the feature flags of a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin is not found.
+    pub fn feature_flags(&self, name: &str) -> orbis_core::Result<Vec<FeatureStatus>> {
+        let info = self
+            .registry
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
 
-        if let Some(name) = plugin_name {
+        Ok(self.features.statuses(&info.manifest))
+    }
+
+    /// Get the resolved feature flags of a plugin (empty if it is not found).
+    #[must_use]
+    pub fn resolved_features(&self, name: &str) -> std::collections::BTreeMap<String, bool> {
+        self.registry
+            .get(name)
+            .map(|info| self.features.resolve(&info.manifest))
+            .unwrap_or_default()
+    }
+
+    /// Override a feature flag of a plugin, or go back to its default (`None`).
+    ///
+    /// The override applies from the next request; cached page data and
+    /// responses of the plugin are dropped, as they may depend on the flag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin or flag is not found, or the override
+    /// cannot be stored.
+    pub async fn set_feature(
+        &self,
+        name: &str,
+        flag: &str,
+        enabled: Option<bool>,
+        changed_by: Option<UserId>,
+    ) -> orbis_core::Result<FeatureStatus> {
+        let info = self
+            .registry
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+        let not_declared = || orbis_core::Error::not_found(format!("Plugin '{}' has no feature flag '{}'", name, flag));
+        if !info.manifest.features.iter().any(|feature| feature.name == flag) {
+            return Err(not_declared());
+        }
+
+        self.features.store(&self.db, name, flag, enabled, changed_by).await?;
+        self.page_cache.invalidate_plugin(name);
+        self.response_cache().invalidate_plugin(name);
+
+        tracing::info!(
+            plugin = %name,
+            flag = %flag,
+            enabled = ?enabled,
+            changed_by = ?changed_by,
+            "Plugin feature flag changed"
+        );
+
+        self.features
+            .statuses(&info.manifest)
+            .into_iter()
+            .find(|status| status.name == flag)
+            .ok_or_else(not_declared)
+    }
+
+    /// Reload a plugin by path (for file watcher events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be reloaded.
+    pub async fn reload_plugin_by_path(&self, path: &PathBuf) -> orbis_core::Result<Option<PluginInfo>> {
+        if let Some(name) = self.plugin_at_path(path) {
             let info = self.reload_plugin(&name).await?;
             Ok(Some(info))
         } else {
@@ -407,7 +1734,7 @@ impl PluginManager {
                         }
                         Err(e) => {
                             tracing::warn!("Failed to load plugin from {:?}: {}", root, e);
-                            Ok(None)
+                            Err(e)
                         }
                     }
                 } else {
@@ -419,6 +1746,72 @@ impl PluginManager {
         }
     }
 
+    /// Apply a change reported by the watcher: reload the affected plugin,
+    /// load a new one, or unload one whose files were removed.
+    ///
+    /// Each step and its outcome is reported on the reload event channel.
+    /// Changes to files outside any plugin are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be reloaded, loaded or unloaded.
+    pub async fn apply_change(&self, event: &PluginChangeEvent) -> orbis_core::Result<Option<PluginInfo>> {
+        let path = &event.path;
+        let loaded = self.plugin_at_path(path);
+        let emit = |stage, plugin| self.reload_events.emit(ReloadEvent::new(stage, plugin, path.clone()));
+
+        // The plugin's own file or folder was removed
+        if let Some(name) = &loaded
+            && !path.exists()
+            && self
+                .registry
+                .get(name)
+                .is_some_and(|info| info.source.path() == Some(path.as_path()))
+        {
+            self.unload_plugin(name).await?;
+            emit(ReloadStage::Unloaded, loaded.clone());
+            return Ok(None);
+        }
+
+        if loaded.is_none() && (!path.exists() || Self::find_plugin_root(path).is_none()) {
+            return Ok(None);
+        }
+
+        let plugin = loaded.or_else(|| event.plugin_id.clone());
+        emit(ReloadStage::Reloading, plugin.clone());
+
+        let started = Instant::now();
+        match self.reload_plugin_by_path(path).await {
+            Ok(info) => {
+                if let Some(info) = &info {
+                    let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+                    emit(
+                        ReloadStage::Reloaded {
+                            version: info.manifest.version.clone(),
+                            duration_ms,
+                        },
+                        Some(info.manifest.name.clone()),
+                    );
+                }
+                Ok(info)
+            }
+            Err(e) => {
+                emit(ReloadStage::ReloadFailed { error: e.to_string() }, plugin);
+                Err(e)
+            }
+        }
+    }
+
+    /// Find the loaded plugin whose source is or contains a path.
+    fn plugin_at_path(&self, path: &Path) -> Option<String> {
+        self.registry.list().iter().find_map(|info| {
+            info.source
+                .path()
+                .filter(|source| path.starts_with(source))
+                .map(|_| info.manifest.name.clone())
+        })
+    }
+
     /// Find the plugin root directory from a file path.
     fn find_plugin_root(path: &PathBuf) -> Option<PathBuf> {
         // If it's a WASM or ZIP file, that's the root
@@ -445,9 +1838,9 @@ impl PluginManager {
     pub fn create_watcher(&self) -> PluginWatcher {
         PluginWatcher::new(WatcherConfig {
             watch_dir: self.plugins_dir.clone(),
-            debounce_duration: std::time::Duration::from_millis(500),
-            recursive: true,
+            ..WatcherConfig::default()
         })
+        .with_events(self.reload_events.clone())
     }
 
     /// Get all registered routes from plugins.
@@ -467,6 +1860,8 @@ impl PluginManager {
     }
 
     /// Get all registered pages from plugins.
+    ///
+    /// Pages and components hidden by the plugins' feature flags are left out.
     #[must_use]
     pub fn get_all_pages(&self) -> Vec<(String, PageDefinition)> {
         self.registry
@@ -474,16 +1869,40 @@ impl PluginManager {
             .iter()
             .filter(|info| info.state == PluginState::Running)
             .flat_map(|info| {
+                let features = self.features.resolve(&info.manifest);
                 info.manifest
                     .pages
                     .iter()
-                    .map(|page| (info.manifest.name.clone(), page.clone()))
+                    .filter(|page| page.is_enabled_by(&features))
+                    .map(|page| (info.manifest.name.clone(), page.with_features(&features)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Get theme contributions from all running plugins.
+    #[must_use]
+    pub fn get_all_themes(&self) -> Vec<(String, ThemeDefinition)> {
+        self.registry
+            .list()
+            .iter()
+            .filter(|info| info.state == PluginState::Running)
+            .filter_map(|info| {
+                info.manifest
+                    .theme
+                    .as_ref()
+                    .filter(|theme| !theme.is_empty())
+                    .map(|theme| (info.manifest.name.clone(), theme.clone()))
             })
             .collect()
     }
 
     /// Execute a plugin route handler.
     ///
+    /// `GET` handlers used as page data actions are served from the page data
+    /// cache when the page declares a TTL; any other method invalidates the
+    /// plugin's cached data.
+    ///
     /// # Errors
     ///
     /// Returns an error if execution fails.
@@ -493,6 +1912,201 @@ impl PluginManager {
         handler: &str,
         context: PluginContext,
     ) -> orbis_core::Result<serde_json::Value> {
-        self.runtime.execute(plugin_name, handler, context).await
+        self.activate(plugin_name).await?;
+
+        if !context.method.eq_ignore_ascii_case("GET") {
+            // Mutations may change what the plugin's pages display
+            let result = self.execute_handler(plugin_name, handler, context).await;
+            self.page_cache.invalidate_plugin(plugin_name);
+            return result;
+        }
+
+        let Some(ttl) = self.page_cache_ttl(plugin_name, handler) else {
+            return self.execute_handler(plugin_name, handler, context).await;
+        };
+
+        // Clients revalidating (on focus or event) bypass the cached value
+        let revalidate = context
+            .headers
+            .get("cache-control")
+            .is_some_and(|v| v.contains("no-cache"));
+
+        let key = PageDataCache::key(plugin_name, handler, &context);
+        if let Some(value) = self.page_cache.get(&key).filter(|_| !revalidate) {
+            tracing::trace!("Page data cache hit: {}.{}", plugin_name, handler);
+            return Ok(value);
+        }
+
+        let value = self.execute_handler(plugin_name, handler, context).await?;
+        self.page_cache.insert(key, plugin_name, value.clone(), ttl);
+        Ok(value)
+    }
+
+    /// Execute a handler, recording a trap report if the plugin trapped.
+    async fn execute_handler(
+        &self,
+        plugin_name: &str,
+        handler: &str,
+        context: PluginContext,
+    ) -> orbis_core::Result<serde_json::Value> {
+        // Handlers see the flags as they are now, so toggles apply without reloads
+        let mut context = context;
+        context.features = self.resolved_features(plugin_name);
+        let captured = self.capture.is_enabled().then(|| (context.clone(), Instant::now()));
+        let result = self.runtime.execute(plugin_name, handler, context).await;
+        if let Some((context, started)) = captured {
+            self.capture.record(plugin_name, handler, &context, &result, started.elapsed());
+        }
+
+        if result.is_err()
+            && let Some(report) = self.runtime.take_trap(plugin_name)
+        {
+            tracing::error!(
+                target: "audit",
+                plugin = %report.plugin,
+                handler = %report.handler,
+                user_id = report.request.user_id.as_deref().unwrap_or("anonymous"),
+                trap_code = report.trap_code.as_deref().unwrap_or("none"),
+                "Plugin trapped"
+            );
+            if let Some(sink) = self.trap_sink.read().as_ref()
+                && sink.send(report.clone()).is_err()
+            {
+                tracing::trace!("Trap sink of plugin '{}' is closed", plugin_name);
+            }
+            self.registry.record_trap(report);
+        }
+
+        result
+    }
+
+    /// Replay a captured request against its handler, optionally with another body.
+    ///
+    /// The replay runs with the plugin as it is now, and is captured too.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request was not captured or execution fails.
+    pub async fn replay(
+        &self,
+        plugin_name: &str,
+        id: Uuid,
+        body: Option<serde_json::Value>,
+    ) -> orbis_core::Result<serde_json::Value> {
+        let captured = self.capture.get(plugin_name, id).ok_or_else(|| {
+            orbis_core::Error::not_found(format!("Captured request {} of plugin '{}' not found", id, plugin_name))
+        })?;
+
+        let mut context = captured.context;
+        if let Some(body) = body {
+            context.body = body;
+        }
+        context.cancellation = CancellationFlag::new();
+        self.execute_route(plugin_name, &captured.handler, context).await
+    }
+
+    /// Replay a recorded handler invocation from its replay file.
+    ///
+    /// The handler runs against the recorded host call results instead of
+    /// the host, so it runs as it did when recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not a replay file, or the plugin is not
+    /// running or fails to activate.
+    pub async fn replay_recording(&self, path: &Path) -> orbis_core::Result<ReplayOutcome> {
+        let recording = ReplayRecording::load(path)?;
+        self.activate(&recording.plugin).await?;
+        self.runtime.replay(&recording).await
+    }
+
+    /// Make sure a running plugin has a runtime instance, creating it on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lazily activated plugin fails to initialize.
+    pub async fn activate(&self, name: &str) -> orbis_core::Result<()> {
+        self.last_used.insert(name.to_string(), Instant::now());
+
+        if self.runtime.is_running(name) {
+            return Ok(());
+        }
+
+        let Some(info) = self.registry.get(name) else {
+            return Ok(());
+        };
+
+        if info.state == PluginState::Running && info.manifest.activation == PluginActivation::Lazy {
+            let started = Instant::now();
+            self.runtime.initialize(&info, &info.source).await?;
+            self.registry.set_snapshot(name, self.runtime.snapshot_info(name));
+            tracing::info!("Activated lazy plugin: {} ({}ms)", name, started.elapsed().as_millis());
+        }
+
+        Ok(())
+    }
+
+    /// Unload lazily activated plugins that have been idle longer than their
+    /// `idle_unload_seconds`, returning the names of the unloaded plugins.
+    ///
+    /// Plugin state is kept; the plugin is reactivated on its next use.
+    pub fn unload_idle_plugins(&self) -> Vec<String> {
+        let idle: Vec<String> = self
+            .registry
+            .list()
+            .into_iter()
+            .filter(|info| info.manifest.activation == PluginActivation::Lazy)
+            .filter_map(|info| {
+                let timeout = Duration::from_secs(info.manifest.idle_unload_seconds?);
+                let last_used = self.last_used.get(&info.manifest.name).map(|t| *t)?;
+                (last_used.elapsed() >= timeout).then_some(info.manifest.name)
+            })
+            .collect();
+
+        idle.into_iter()
+            .filter(|name| {
+                self.last_used.remove(name);
+                self.page_cache.invalidate_plugin(name);
+                self.runtime.deactivate(name)
+            })
+            .inspect(|name| tracing::info!("Unloaded idle plugin: {}", name))
+            .collect()
+    }
+
+    /// Release the runtime instances of all plugins at shutdown.
+    ///
+    /// Plugin state is persisted as it changes and is kept; in-flight
+    /// handlers finish on the instances they hold.
+    pub fn shutdown(&self) {
+        let released = self
+            .registry
+            .list()
+            .iter()
+            .filter(|info| self.runtime.deactivate(&info.manifest.name))
+            .count();
+        self.page_cache.clear();
+        tracing::info!("Released {} plugin instances", released);
+    }
+
+    /// Drop the cached page data of plugins with pages revalidating on an
+    /// emitted event, so clients refetching on the event get fresh data.
+    pub fn revalidate_event(&self, event: &str) {
+        for info in self.registry.list() {
+            if info.manifest.pages.iter().any(|page| page.revalidates_on(event)) {
+                tracing::trace!("Revalidating page data of {} on '{}'", info.manifest.name, event);
+                self.page_cache.invalidate_plugin(&info.manifest.name);
+            }
+        }
+    }
+
+    /// Get the shortest cache TTL declared by the plugin's pages for a handler.
+    fn page_cache_ttl(&self, plugin_name: &str, handler: &str) -> Option<std::time::Duration> {
+        let info = self.registry.get(plugin_name)?;
+        info.manifest
+            .pages
+            .iter()
+            .filter_map(|page| page.cache_ttl_for(handler))
+            .min()
+            .map(std::time::Duration::from_secs)
     }
 }
@@ -1,7 +1,10 @@
 //! Plugin loader for loading plugins from various sources.
 
-use orbis_plugin_api::PluginManifest;
-use std::path::PathBuf;
+use crate::packed::{ArchiveLimits, PackedArchive, PackedResult};
+use crate::remote::RemoteSource;
+use crate::signing::{read_directory_signature, Keyring, SignatureStatus};
+use orbis_plugin_api::{AbiVersion, PluginManifest};
+use std::path::{Path, PathBuf};
 
 /// Plugin source location and flavor.
 #[derive(Debug, Clone)]
@@ -15,8 +18,8 @@ pub enum PluginSource {
     /// Standalone: Single WASM file with embedded manifest.
     Standalone(PathBuf),
 
-    /// Remote URL (for future use).
-    Remote(String),
+    /// Remote: plugin file fetched from a URL or its mirrors.
+    Remote(RemoteSource),
 }
 
 impl PluginSource {
@@ -52,6 +55,15 @@ impl PluginSource {
         }
     }
 
+    /// Get the path of the plugin on disk, if it is not remote.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Packed(path) | Self::Unpacked(path) | Self::Standalone(path) => Some(path),
+            Self::Remote(_) => None,
+        }
+    }
+
     /// Get the manifest path for this source.
     /// Returns None for standalone plugins (manifest is embedded in WASM).
     #[must_use]
@@ -90,13 +102,31 @@ impl Default for PluginSource {
 }
 
 /// Plugin loader for loading plugin manifests and code.
-pub struct PluginLoader;
+pub struct PluginLoader {
+    /// Limits packed plugins must stay within.
+    archive_limits: ArchiveLimits,
+}
 
 impl PluginLoader {
     /// Create a new plugin loader.
     #[must_use]
     pub const fn new() -> Self {
-        Self
+        Self {
+            archive_limits: ArchiveLimits::DEFAULT,
+        }
+    }
+
+    /// Set the limits packed plugins must stay within.
+    #[must_use]
+    pub const fn with_archive_limits(mut self, limits: ArchiveLimits) -> Self {
+        self.archive_limits = limits;
+        self
+    }
+
+    /// Get the limits packed plugins must stay within.
+    #[must_use]
+    pub const fn archive_limits(&self) -> ArchiveLimits {
+        self.archive_limits
     }
 
     /// Load a plugin manifest from a source.
@@ -115,11 +145,11 @@ impl PluginLoader {
                 
                 // Try to load external manifest first
                 if manifest_path.exists() {
-                    let content = std::fs::read_to_string(&manifest_path).map_err(|e| {
+                    let content = read_unpacked_file(dir, Path::new("manifest.json")).map_err(|e| {
                         orbis_core::Error::plugin(format!("Failed to read manifest: {}", e))
                     })?;
                     
-                    let manifest: PluginManifest = serde_json::from_str(&content).map_err(|e| {
+                    let manifest: PluginManifest = serde_json::from_slice(&content).map_err(|e| {
                         orbis_core::Error::plugin(format!("Failed to parse manifest: {}", e))
                     })?;
                     
@@ -128,9 +158,9 @@ impl PluginLoader {
                 
                 // Fallback to embedded manifest in WASM
                 // Try common names first
-                let wasm_path = dir.join("plugin.wasm");
-                if wasm_path.exists() {
-                    return self.extract_embedded_manifest(&wasm_path);
+                if dir.join("plugin.wasm").exists() {
+                    let wasm_bytes = read_unpacked_file(dir, Path::new("plugin.wasm"))?;
+                    return self.extract_embedded_manifest_from_bytes(&wasm_bytes);
                 }
                 
                 // Look for any .wasm file in the directory
@@ -138,7 +168,8 @@ impl PluginLoader {
                     for entry in entries.flatten() {
                         let path = entry.path();
                         if path.extension().and_then(|s| s.to_str()) == Some("wasm") {
-                            return self.extract_embedded_manifest(&path);
+                            let wasm_bytes = read_unpacked_file(dir, Path::new(&entry.file_name()))?;
+                            return self.extract_embedded_manifest_from_bytes(&wasm_bytes);
                         }
                     }
                 }
@@ -158,67 +189,32 @@ impl PluginLoader {
             }
             
             PluginSource::Remote(_) => {
-                Err(orbis_core::Error::plugin("Remote plugins not yet supported"))
+                Err(orbis_core::Error::plugin("Remote plugins must be fetched before they are loaded"))
             }
         }
     }
     
     /// Extract manifest from ZIP archive.
-    fn load_manifest_from_zip(&self, zip_path: &PathBuf) -> orbis_core::Result<PluginManifest> {
-        use std::io::Read;
-        
-        let file = std::fs::File::open(zip_path).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to open ZIP file: {}", e))
-        })?;
-        
-        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to read ZIP archive: {}", e))
-        })?;
-        
-        // Try to find manifest.json in the archive
-        if let Ok(mut file) = archive.by_name("manifest.json") {
-            let mut content = String::new();
-            file.read_to_string(&mut content).map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to read manifest from ZIP: {}", e))
-            })?;
-            
-            let manifest: PluginManifest = serde_json::from_str(&content).map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to parse manifest: {}", e))
-            })?;
-            
-            return Ok(manifest);
-        }
+    fn load_manifest_from_zip(&self, zip_path: &Path) -> orbis_core::Result<PluginManifest> {
+        let mut archive = PackedArchive::open(zip_path, self.archive_limits)?;
         
-        // Try in subdirectory (common pattern)
-        if let Ok(mut file) = archive.by_name("plugin/manifest.json") {
-            let mut content = String::new();
-            file.read_to_string(&mut content).map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to read manifest from ZIP: {}", e))
-            })?;
-            
-            let manifest: PluginManifest = serde_json::from_str(&content).map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to parse manifest: {}", e))
-            })?;
-            
-            return Ok(manifest);
-        }
-        
-        // Fallback: try to find WASM file and extract embedded manifest
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).map_err(|e| {
-                orbis_core::Error::plugin(format!("Failed to access ZIP entry: {}", e))
-            })?;
-            
-            if file.name().ends_with(".wasm") {
-                let mut wasm_bytes = Vec::new();
-                file.read_to_end(&mut wasm_bytes).map_err(|e| {
-                    orbis_core::Error::plugin(format!("Failed to read WASM from ZIP: {}", e))
+        // Try to find manifest.json in the archive, or in a subdirectory (common pattern)
+        for name in ["manifest.json", "plugin/manifest.json"] {
+            if let Some(content) = archive.read(name)? {
+                let manifest: PluginManifest = serde_json::from_slice(&content).map_err(|e| {
+                    orbis_core::Error::plugin(format!("Failed to parse manifest: {}", e))
                 })?;
                 
-                return self.extract_embedded_manifest_from_bytes(&wasm_bytes);
+                return Ok(manifest);
             }
         }
         
+        // Fallback: try to find WASM file and extract embedded manifest
+        if let Some(name) = archive.file_names().into_iter().find(|name| name.ends_with(".wasm")) {
+            let wasm_bytes = archive.read(&name)?.unwrap_or_default();
+            return self.extract_embedded_manifest_from_bytes(&wasm_bytes);
+        }
+        
         Err(orbis_core::Error::plugin(
             "No manifest.json or .wasm file found in ZIP archive"
         ))
@@ -267,6 +263,82 @@ impl PluginLoader {
         ))
     }
 
+    /// Verify the signature of a standalone or unpacked plugin.
+    ///
+    /// Only standalone and unpacked plugins can be signed; other sources are
+    /// reported as unsigned. The files of an unpacked plugin are checked
+    /// against their hashes when they are read, not here.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be read or its signature is
+    /// invalid or made by a key not in the keyring.
+    pub fn verify_signature(&self, source: &PluginSource, keyring: &Keyring) -> orbis_core::Result<SignatureStatus> {
+        match *source {
+            PluginSource::Standalone(ref wasm_path) => {
+                let wasm_bytes = std::fs::read(wasm_path).map_err(|e| {
+                    orbis_core::Error::plugin(format!("Failed to read WASM file: {}", e))
+                })?;
+                keyring.verify(&wasm_bytes)
+            }
+            PluginSource::Unpacked(ref dir) => read_directory_signature(dir)?
+                .map_or(Ok(SignatureStatus::Unsigned), |signature| keyring.verify_directory(&signature)),
+            PluginSource::Packed(_) | PluginSource::Remote(_) => Ok(SignatureStatus::Unsigned),
+        }
+    }
+
+    /// Read the ABI version a plugin was built against.
+    ///
+    /// Plugins without an [`AbiVersion::SECTION`] custom section predate ABI
+    /// versioning and are reported as [`AbiVersion::LEGACY`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WASM cannot be parsed or the section is malformed.
+    pub fn read_abi_version(&self, wasm_bytes: &[u8]) -> orbis_core::Result<AbiVersion> {
+        use wasmparser::{Parser, Payload};
+
+        for payload in Parser::new(0).parse_all(wasm_bytes) {
+            let payload = payload.map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to parse WASM: {}", e))
+            })?;
+
+            if let Payload::CustomSection(reader) = payload
+                && reader.name() == AbiVersion::SECTION
+            {
+                return AbiVersion::from_bytes(reader.data()).ok_or_else(|| {
+                    orbis_core::Error::plugin("Malformed ABI version section in plugin")
+                });
+            }
+        }
+
+        Ok(AbiVersion::LEGACY)
+    }
+
+    /// Read the ABI version of plugin code and check it against the host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin targets an incompatible ABI version.
+    pub fn check_abi_version(&self, manifest: &PluginManifest, wasm_bytes: &[u8]) -> orbis_core::Result<AbiVersion> {
+        let version = self.read_abi_version(wasm_bytes)?;
+
+        version.check_compatible().map_err(|e| {
+            orbis_core::Error::plugin(format!("Cannot load plugin '{}': {}", manifest.name, e))
+        })?;
+
+        if version.needs_shims() {
+            tracing::debug!(
+                "Plugin '{}' uses ABI {}, enabling compatibility shims for ABI {}",
+                manifest.name,
+                version,
+                AbiVersion::CURRENT
+            );
+        }
+
+        Ok(version)
+    }
+
     /// Load plugin WASM code.
     ///
     /// # Errors
@@ -275,11 +347,8 @@ impl PluginLoader {
     pub fn load_code(&self, source: &PluginSource, manifest: &PluginManifest) -> orbis_core::Result<Vec<u8>> {
         match source {
             PluginSource::Unpacked(dir) => {
-                let wasm_path = if let Some(entry) = &manifest.wasm_entry {
-                    dir.join(entry)
-                } else {
-                    dir.join("plugin.wasm")
-                };
+                let wasm_entry = Path::new(manifest.wasm_entry.as_deref().unwrap_or("plugin.wasm"));
+                let wasm_path = dir.join(wasm_entry);
                 
                 if !wasm_path.exists() {
                     return Err(orbis_core::Error::plugin(format!(
@@ -288,7 +357,7 @@ impl PluginLoader {
                     )));
                 }
                 
-                std::fs::read(&wasm_path).map_err(|e| {
+                read_unpacked_file(dir, wasm_entry).map_err(|e| {
                     orbis_core::Error::plugin(format!("Failed to read WASM file: {}", e))
                 })
             }
@@ -304,42 +373,58 @@ impl PluginLoader {
             }
             
             PluginSource::Remote(_) => {
-                Err(orbis_core::Error::plugin("Remote plugins not yet supported"))
+                Err(orbis_core::Error::plugin("Remote plugins must be fetched before they are loaded"))
             }
         }
     }
     
     /// Load WASM from ZIP archive.
-    fn load_wasm_from_zip(&self, zip_path: &PathBuf, manifest: &PluginManifest) -> orbis_core::Result<Vec<u8>> {
-        use std::io::Read;
-        
-        let file = std::fs::File::open(zip_path).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to open ZIP file: {}", e))
-        })?;
-        
-        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to read ZIP archive: {}", e))
-        })?;
+    fn load_wasm_from_zip(&self, zip_path: &Path, manifest: &PluginManifest) -> orbis_core::Result<Vec<u8>> {
+        let mut archive = PackedArchive::open(zip_path, self.archive_limits)?;
         
         // Determine WASM filename
         let wasm_name = manifest.wasm_entry.as_deref().unwrap_or("plugin.wasm");
         
         // Try to find the WASM file
-        let mut wasm_file = if let Ok(file) = archive.by_name(wasm_name) {
-            file
-        } else if let Ok(file) = archive.by_name(&format!("plugin/{}", wasm_name)) {
-            file
-        } else {
-            return Err(orbis_core::Error::plugin(format!("WASM file '{}' not found in ZIP", wasm_name)));
-        };
-        
-        let mut wasm_bytes = Vec::new();
-        wasm_file.read_to_end(&mut wasm_bytes).map_err(|e| {
-            orbis_core::Error::plugin(format!("Failed to read WASM from ZIP: {}", e))
-        })?;
-        
-        Ok(wasm_bytes)
+        if let Some(wasm_bytes) = archive.read(wasm_name)? {
+            return Ok(wasm_bytes);
+        }
+        archive.read(&format!("plugin/{}", wasm_name))?.ok_or_else(|| {
+            orbis_core::Error::plugin(format!("WASM file '{}' not found in ZIP", wasm_name))
+        })
+    }
+
+    /// Check a packed plugin against the archive limits without loading it.
+    ///
+    /// Every entry is decompressed, so entries that decompress to more than
+    /// they declare are caught as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read or is unsafe.
+    pub fn check_packed(&self, zip_path: &Path) -> PackedResult<()> {
+        PackedArchive::open(zip_path, self.archive_limits)?.verify()
+    }
+
+    /// Extract a packed plugin into a directory, replacing it if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is unsafe or cannot be extracted, in
+    /// which case the directory is left untouched.
+    pub fn extract_packed(&self, zip_path: &Path, dest: &Path) -> PackedResult<()> {
+        PackedArchive::open(zip_path, self.archive_limits)?.extract(dest)
+    }
+}
+
+/// Read a file of an unpacked plugin, checking it against its hash if the
+/// plugin is signed.
+fn read_unpacked_file(dir: &Path, relative: &Path) -> orbis_core::Result<Vec<u8>> {
+    let contents = std::fs::read(dir.join(relative))?;
+    if let Some(signature) = read_directory_signature(dir)? {
+        signature.manifest.verify_file(relative, &contents)?;
     }
+    Ok(contents)
 }
 
 impl Default for PluginLoader {
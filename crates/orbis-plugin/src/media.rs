@@ -0,0 +1,210 @@
+//! Host-side image processing.
+//!
+//! Decoding runs in the host so plugins can generate previews without
+//! shipping codecs in WASM. Every operation is bounded: inputs, image
+//! dimensions and decoder allocations are limited, and work that does not
+//! finish within [`MEDIA_TIME_LIMIT`] is abandoned.
+
+use image::{ImageFormat, ImageReader, Limits};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Largest accepted input, in bytes.
+pub const MAX_MEDIA_INPUT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Largest accepted image width or height, in pixels.
+pub const MAX_IMAGE_DIMENSION: u32 = 16 * 1024;
+
+/// Largest thumbnail width or height, in pixels.
+pub const MAX_THUMBNAIL_DIMENSION: u32 = 2048;
+
+/// Time an operation may take before it is abandoned.
+pub const MEDIA_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// Most memory the decoder may allocate, in bytes.
+const MAX_DECODER_ALLOC: u64 = 512 * 1024 * 1024;
+
+/// Information about an image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageInfo {
+    /// Format name (e.g. `png`, `jpeg`).
+    pub format: String,
+
+    /// MIME type.
+    pub mime_type: String,
+
+    /// Width in pixels.
+    pub width: u32,
+
+    /// Height in pixels.
+    pub height: u32,
+
+    /// Size of the encoded image, in bytes.
+    pub size: usize,
+}
+
+/// Read the format and dimensions of an image without decoding it.
+///
+/// # Errors
+///
+/// Returns an error if the input is too large, not a supported image, or
+/// exceeds the dimension limits.
+pub fn probe(bytes: &[u8]) -> orbis_core::Result<ImageInfo> {
+    let reader = reader(bytes)?;
+    let format = reader
+        .format()
+        .ok_or_else(|| orbis_core::Error::validation("Unsupported image format"))?;
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid image: {}", e)))?;
+    check_dimensions(width, height)?;
+
+    Ok(ImageInfo {
+        format: format.extensions_str().first().copied().unwrap_or("unknown").to_string(),
+        mime_type: format.to_mime_type().to_string(),
+        width,
+        height,
+        size: bytes.len(),
+    })
+}
+
+/// Create a PNG thumbnail fitting within `width` x `height`, keeping the aspect ratio.
+///
+/// Images smaller than the requested size are not enlarged.
+///
+/// # Errors
+///
+/// Returns an error if the size is out of range, the input is not a
+/// supported image within the limits, or processing takes too long.
+pub fn thumbnail(bytes: &[u8], width: u32, height: u32) -> orbis_core::Result<Vec<u8>> {
+    if width == 0 || height == 0 || width > MAX_THUMBNAIL_DIMENSION || height > MAX_THUMBNAIL_DIMENSION {
+        return Err(orbis_core::Error::validation(format!(
+            "Thumbnail size must be between 1 and {} pixels",
+            MAX_THUMBNAIL_DIMENSION
+        )));
+    }
+
+    // Check the header before handing the work to a thread
+    probe(bytes)?;
+
+    let input = bytes.to_vec();
+    with_time_limit(move || {
+        let image = reader(&input)?
+            .decode()
+            .map_err(|e| orbis_core::Error::validation(format!("Failed to decode image: {}", e)))?;
+
+        let thumbnail = if image.width() <= width && image.height() <= height {
+            image
+        } else {
+            image.thumbnail(width, height)
+        };
+
+        let mut output = Cursor::new(Vec::new());
+        thumbnail
+            .write_to(&mut output, ImageFormat::Png)
+            .map_err(|e| orbis_core::Error::internal(format!("Failed to encode thumbnail: {}", e)))?;
+        Ok(output.into_inner())
+    })
+}
+
+/// Create a reader with the decoder limits applied.
+fn reader(bytes: &[u8]) -> orbis_core::Result<ImageReader<Cursor<&[u8]>>> {
+    if bytes.len() > MAX_MEDIA_INPUT_BYTES {
+        return Err(orbis_core::Error::validation(format!(
+            "Image is larger than {} bytes",
+            MAX_MEDIA_INPUT_BYTES
+        )));
+    }
+
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid image: {}", e)))?;
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODER_ALLOC);
+    reader.limits(limits);
+
+    Ok(reader)
+}
+
+/// Check image dimensions against the limits.
+fn check_dimensions(width: u32, height: u32) -> orbis_core::Result<()> {
+    if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        return Err(orbis_core::Error::validation(format!(
+            "Image is larger than {} pixels in width or height",
+            MAX_IMAGE_DIMENSION
+        )));
+    }
+    Ok(())
+}
+
+/// Run work on a separate thread, giving up after [`MEDIA_TIME_LIMIT`].
+///
+/// Abandoned work keeps running until it finishes, but its result is dropped
+/// and the caller is released.
+fn with_time_limit<T, F>(work: F) -> orbis_core::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> orbis_core::Result<T> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::sync_channel(1);
+    std::thread::Builder::new()
+        .name("orbis-media".to_string())
+        .spawn(move || {
+            if sender.send(work()).is_err() {
+                tracing::trace!("Media operation finished after its time limit");
+            }
+        })
+        .map_err(|e| orbis_core::Error::internal(format!("Failed to start media worker: {}", e)))?;
+
+    receiver.recv_timeout(MEDIA_TIME_LIMIT).unwrap_or_else(|_| {
+        Err(orbis_core::Error::plugin(format!(
+            "Media operation took longer than {} seconds",
+            MEDIA_TIME_LIMIT.as_secs()
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut bytes, ImageFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_probe_and_thumbnail() {
+        let image = png(400, 200);
+
+        let info = probe(&image).unwrap();
+        assert_eq!(info.format, "png");
+        assert_eq!(info.mime_type, "image/png");
+        assert_eq!((info.width, info.height), (400, 200));
+        assert_eq!(info.size, image.len());
+
+        let preview = probe(&thumbnail(&image, 100, 100).unwrap()).unwrap();
+        assert_eq!((preview.width, preview.height), (100, 50));
+
+        // Small images are not enlarged
+        let small = probe(&thumbnail(&png(20, 10), 100, 100).unwrap()).unwrap();
+        assert_eq!((small.width, small.height), (20, 10));
+    }
+
+    #[test]
+    fn test_media_limits() {
+        probe(b"not an image").unwrap_err();
+        thumbnail(&png(10, 10), 0, 10).unwrap_err();
+        thumbnail(&png(10, 10), MAX_THUMBNAIL_DIMENSION + 1, 10).unwrap_err();
+        probe(&vec![0; MAX_MEDIA_INPUT_BYTES + 1]).unwrap_err();
+    }
+}
@@ -0,0 +1,223 @@
+//! Merkle tree hashing of plugin directories.
+//!
+//! An unpacked plugin is hashed as a [`MerkleManifest`]: the SHA-256 hash of
+//! each file by its `/`-separated path relative to the plugin directory, and
+//! a root hash over all of them. Files are ordered by path, so the root only
+//! depends on the paths and contents of the files.
+//!
+//! Signing the root signs the whole directory, while each file can still be
+//! checked on its own against the manifest when it is read, without hashing
+//! the files that are never read.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use crate::signing::DIRECTORY_SIGNATURE_FILE;
+
+/// Prefix of leaf hashes, so a leaf cannot be taken for a node.
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix of node hashes.
+const NODE_PREFIX: u8 = 1;
+
+/// Hashes of the files of a directory, and their root hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleManifest {
+    /// Root hash of the tree, as hex.
+    pub root: String,
+
+    /// SHA-256 hash of each file by relative path, as hex.
+    pub files: BTreeMap<String, String>,
+}
+
+impl MerkleManifest {
+    /// Create a manifest from file hashes, computing the root hash.
+    #[must_use]
+    pub fn from_files(files: BTreeMap<String, String>) -> Self {
+        Self {
+            root: root_hash(&files),
+            files,
+        }
+    }
+
+    /// Check the root hash matches the file hashes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the root hash was not computed from the files.
+    pub fn verify_root(&self) -> orbis_core::Result<()> {
+        if root_hash(&self.files) != self.root {
+            return Err(orbis_core::Error::plugin("Plugin file hashes do not match their root hash"));
+        }
+        Ok(())
+    }
+
+    /// Check the contents of a file against its hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not in the manifest or its contents
+    /// changed.
+    pub fn verify_file(&self, path: &Path, contents: &[u8]) -> orbis_core::Result<()> {
+        let name = relative_name(path)
+            .ok_or_else(|| orbis_core::Error::plugin(format!("Invalid plugin file path: {}", path.display())))?;
+        let hash = self
+            .files
+            .get(&name)
+            .ok_or_else(|| orbis_core::Error::plugin(format!("Plugin file '{}' was added after signing", name)))?;
+        if *hash != hex::encode(Sha256::digest(contents)) {
+            return Err(orbis_core::Error::plugin(format!("Plugin file '{}' was modified after signing", name)));
+        }
+        Ok(())
+    }
+}
+
+/// Hash the files of a directory.
+///
+/// Subdirectories are hashed recursively. Symbolic links are not followed,
+/// and the directory signature file is left out.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read or a path is not UTF-8.
+pub fn hash_dir(dir: &Path) -> orbis_core::Result<MerkleManifest> {
+    let mut files = BTreeMap::new();
+    hash_files(dir, dir, &mut files)?;
+    files.remove(DIRECTORY_SIGNATURE_FILE);
+    Ok(MerkleManifest::from_files(files))
+}
+
+/// Hash the files under `dir`, by path relative to `root`.
+fn hash_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> orbis_core::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        #[allow(clippy::filetype_is_file, reason = "Only regular files are part of a plugin")]
+        if file_type.is_dir() {
+            hash_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            let name = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(relative_name)
+                .ok_or_else(|| orbis_core::Error::plugin(format!("Invalid plugin file path: {}", path.display())))?;
+            files.insert(name, hex::encode(Sha256::digest(std::fs::read(&path)?)));
+        }
+    }
+    Ok(())
+}
+
+/// Get the `/`-separated name of a relative path, if it stays within its
+/// directory and is UTF-8.
+fn relative_name(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Compute the root hash of file hashes, as hex.
+///
+/// Leaves hash each path with its file hash, in path order. Each level
+/// hashes pairs of nodes, carrying an odd last node up unchanged.
+fn root_hash(files: &BTreeMap<String, String>) -> String {
+    let mut level: Vec<Vec<u8>> = files
+        .iter()
+        .map(|(path, hash)| {
+            Sha256::new()
+                .chain_update([LEAF_PREFIX])
+                .chain_update(path.as_bytes())
+                .chain_update([0])
+                .chain_update(hash.as_bytes())
+                .finalize()
+                .to_vec()
+        })
+        .collect();
+    if level.is_empty() {
+        return hex::encode(Sha256::digest([]));
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match *pair {
+                [ref left, ref right] => Sha256::new()
+                    .chain_update([NODE_PREFIX])
+                    .chain_update(left)
+                    .chain_update(right)
+                    .finalize()
+                    .to_vec(),
+                [ref single] => single.clone(),
+                _ => Vec::new(),
+            })
+            .collect();
+    }
+    hex::encode(level.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("orbis-merkle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("assets/icons")).unwrap();
+        std::fs::write(dir.join("manifest.json"), br#"{"name":"test"}"#).unwrap();
+        std::fs::write(dir.join("plugin.wasm"), b"\0asm\x01\0\0\0").unwrap();
+        std::fs::write(dir.join("assets/style.css"), b"body {}").unwrap();
+        std::fs::write(dir.join("assets/icons/logo.svg"), b"<svg/>").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_hash_dir_is_deterministic() {
+        let dir = plugin_dir();
+        let manifest = hash_dir(&dir).unwrap();
+        assert_eq!(
+            manifest.files.keys().collect::<Vec<_>>(),
+            ["assets/icons/logo.svg", "assets/style.css", "manifest.json", "plugin.wasm"]
+        );
+        manifest.verify_root().unwrap();
+
+        // Same contents elsewhere, and the signature file is left out
+        let copy = plugin_dir();
+        std::fs::write(copy.join(DIRECTORY_SIGNATURE_FILE), b"{}").unwrap();
+        assert_eq!(hash_dir(&copy).unwrap(), manifest);
+
+        // Any change to a path or its contents changes the root
+        std::fs::rename(copy.join("assets/style.css"), copy.join("assets/main.css")).unwrap();
+        assert_ne!(hash_dir(&copy).unwrap().root, manifest.root);
+        std::fs::write(dir.join("assets/style.css"), b"body { margin: 0 }").unwrap();
+        assert_ne!(hash_dir(&dir).unwrap().root, manifest.root);
+
+        let mut forged = manifest;
+        forged.files.insert("extra.js".to_string(), hex::encode(Sha256::digest(b"")));
+        forged.verify_root().unwrap_err();
+        assert_eq!(MerkleManifest::from_files(BTreeMap::new()).root, hex::encode(Sha256::digest([])));
+
+        std::fs::remove_dir_all(dir).unwrap();
+        std::fs::remove_dir_all(copy).unwrap();
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let dir = plugin_dir();
+        let manifest = hash_dir(&dir).unwrap();
+
+        manifest.verify_file(Path::new("plugin.wasm"), b"\0asm\x01\0\0\0").unwrap();
+        manifest.verify_file(Path::new("./assets/icons/logo.svg"), b"<svg/>").unwrap();
+        manifest.verify_file(Path::new("plugin.wasm"), b"\0asm\x01\0\0\x01").unwrap_err();
+        manifest.verify_file(Path::new("other.wasm"), b"").unwrap_err();
+        manifest.verify_file(Path::new("../plugin.wasm"), b"\0asm\x01\0\0\0").unwrap_err();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+//! On-disk cache of precompiled plugin modules.
+//!
+//! Compiling large WASM modules dominates plugin cold-start time. Compiled
+//! modules are serialized with [`Module::serialize`] and stored under
+//! `data_dir/cache`, keyed by the WASM hash and the engine's compatibility
+//! hash (which covers the wasmtime version, target and engine settings).
+
+use sha2::{Digest, Sha256};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use wasmtime::{Engine, Module};
+
+/// Default maximum total size of cached modules (512 MB).
+pub const DEFAULT_MODULE_CACHE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// File extension for serialized modules.
+const CACHE_EXTENSION: &str = "cwasm";
+
+/// Cache of serialized, precompiled plugin modules.
+#[derive(Debug, Clone)]
+pub struct ModuleCache {
+    /// Directory holding serialized modules.
+    dir: PathBuf,
+
+    /// Maximum total size of cached modules in bytes.
+    max_size: u64,
+}
+
+impl ModuleCache {
+    /// Create a cache in the given directory.
+    #[must_use]
+    pub const fn new(dir: PathBuf, max_size: u64) -> Self {
+        Self { dir, max_size }
+    }
+
+    /// Get the cache directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Build the cache key for WASM code compiled by an engine.
+    #[must_use]
+    pub fn key(engine: &Engine, code: &[u8]) -> String {
+        let mut engine_hasher = DefaultHasher::new();
+        engine.precompile_compatibility_hash().hash(&mut engine_hasher);
+
+        format!(
+            "{:x}-{:016x}-{}-{}",
+            Sha256::digest(code),
+            engine_hasher.finish(),
+            std::env::consts::ARCH,
+            std::env::consts::OS
+        )
+    }
+
+    /// Path of the cache file for a key.
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, CACHE_EXTENSION))
+    }
+
+    /// Load a precompiled module, if cached.
+    ///
+    /// Entries that fail to deserialize are removed.
+    #[must_use]
+    pub fn load(&self, engine: &Engine, key: &str) -> Option<Module> {
+        let path = self.path(key);
+        if !path.exists() {
+            return None;
+        }
+
+        // SAFETY: cache files are only written by `store` from `Module::serialize`
+        // output of a compatible engine (the key includes the engine's compatibility hash).
+        match unsafe { Module::deserialize_file(engine, &path) } {
+            Ok(module) => {
+                tracing::debug!("Loaded precompiled module from {:?}", path);
+                Some(module)
+            }
+            Err(e) => {
+                tracing::warn!("Discarding unusable cached module {:?}: {}", path, e);
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Serialize a compiled module into the cache and enforce the size limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module cannot be serialized or written.
+    pub fn store(&self, key: &str, module: &Module) -> orbis_core::Result<()> {
+        let bytes = module.serialize().map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize module: {}", e))
+        })?;
+
+        if bytes.len() as u64 > self.max_size {
+            tracing::debug!("Compiled module too large to cache: {} bytes", bytes.len());
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to create module cache directory: {}", e))
+        })?;
+
+        // Write to a temporary file first so readers never see partial entries
+        let path = self.path(key);
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, &bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to write cached module: {}", e))
+        })?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to write cached module: {}", e))
+        })?;
+
+        self.evict()
+    }
+
+    /// List cache entries with their size and modification time.
+    fn entries(&self) -> orbis_core::Result<Vec<(PathBuf, u64, std::time::SystemTime)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to read module cache directory: {}", e))
+        })?;
+
+        Ok(entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == CACHE_EXTENSION))
+            .filter_map(|path| {
+                let metadata = std::fs::metadata(&path).ok()?;
+                let modified = metadata.modified().ok()?;
+                Some((path, metadata.len(), modified))
+            })
+            .collect())
+    }
+
+    /// Remove the least recently written entries until the cache fits its size limit.
+    fn evict(&self) -> orbis_core::Result<()> {
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total <= self.max_size {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in entries {
+            if total <= self.max_size {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                tracing::debug!("Evicted cached module {:?}", path);
+                total = total.saturating_sub(size);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Total size of cached modules in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be read.
+    pub fn size(&self) -> orbis_core::Result<u64> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Remove all cached modules, returning the number of entries removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be read.
+    pub fn clear(&self) -> orbis_core::Result<usize> {
+        let entries = self.entries()?;
+        let removed = entries
+            .iter()
+            .filter(|(path, _, _)| std::fs::remove_file(path).is_ok())
+            .count();
+
+        tracing::info!("Cleared {} cached plugin modules", removed);
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("orbis-module-cache-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn test_module_cache_roundtrip() {
+        let engine = Engine::default();
+        let code = b"\0asm\x01\0\0\0";
+        let cache = ModuleCache::new(temp_dir("roundtrip"), DEFAULT_MODULE_CACHE_SIZE);
+        let key = ModuleCache::key(&engine, code);
+
+        assert!(cache.load(&engine, &key).is_none());
+
+        let module = Module::new(&engine, code).expect("compile module");
+        cache.store(&key, &module).expect("store module");
+        assert!(cache.load(&engine, &key).is_some());
+        assert!(cache.size().expect("cache size") > 0);
+
+        assert_eq!(cache.clear().expect("clear cache"), 1);
+        assert!(cache.load(&engine, &key).is_none());
+
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn test_module_cache_size_limit() {
+        let engine = Engine::default();
+        let code = b"\0asm\x01\0\0\0";
+        let cache = ModuleCache::new(temp_dir("limit"), 0);
+        let key = ModuleCache::key(&engine, code);
+
+        let module = Module::new(&engine, code).expect("compile module");
+        cache.store(&key, &module).expect("store module");
+        assert!(cache.load(&engine, &key).is_none());
+
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+}
@@ -0,0 +1,328 @@
+//! Tracked plugin operations.
+//!
+//! Installing and reloading a plugin go through several stages, from reading
+//! the package to starting the plugin. A [`PluginOperation`] reports each
+//! stage to a callback as it is entered, so the desktop app can show
+//! progress, and can be cancelled by ID between stages.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::CancellationFlag;
+
+/// Kind of plugin operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// Installing a new plugin.
+    Install,
+
+    /// Reloading a loaded plugin.
+    Reload,
+}
+
+/// Stage of a plugin operation, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStage {
+    /// Reading the package and manifest from the plugin source.
+    Downloading,
+
+    /// Validating the manifest, compatibility and signature.
+    VerifyingSignature,
+
+    /// Extracting the WASM code from the package.
+    Extracting,
+
+    /// Compiling the WASM code.
+    CompilingWasm,
+
+    /// Opening the plugin's persisted state and files.
+    Migrating,
+
+    /// Starting the plugin.
+    Starting,
+}
+
+impl OperationStage {
+    /// All stages, in order.
+    pub const ALL: [Self; 6] = [
+        Self::Downloading,
+        Self::VerifyingSignature,
+        Self::Extracting,
+        Self::CompilingWasm,
+        Self::Migrating,
+        Self::Starting,
+    ];
+
+    /// Get the 1-based position of the stage.
+    #[must_use]
+    pub fn step(self) -> usize {
+        Self::ALL.iter().position(|stage| *stage == self).map_or(0, |index| index.saturating_add(1))
+    }
+}
+
+/// Status of a plugin operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OperationStatus {
+    /// The operation entered a stage.
+    Running {
+        /// Stage entered.
+        stage: OperationStage,
+
+        /// Position of the stage.
+        step: usize,
+
+        /// Number of stages.
+        total_steps: usize,
+    },
+
+    /// The operation succeeded.
+    Completed,
+
+    /// The operation failed.
+    Failed {
+        /// Error message.
+        error: String,
+    },
+
+    /// The operation was cancelled.
+    Cancelled,
+}
+
+/// Progress of a plugin operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    /// Operation ID.
+    pub operation_id: Uuid,
+
+    /// Kind of operation.
+    pub kind: OperationKind,
+
+    /// Plugin name, once known.
+    pub plugin: Option<String>,
+
+    /// Status of the operation.
+    #[serde(flatten)]
+    pub status: OperationStatus,
+
+    /// When the progress was reported.
+    pub at: DateTime<Utc>,
+}
+
+/// Callback receiving operation progress.
+type ProgressReporter = Arc<dyn Fn(OperationProgress) + Send + Sync>;
+
+/// A cancellable plugin operation reporting its progress.
+#[derive(Clone)]
+pub struct PluginOperation {
+    /// Operation ID.
+    id: Uuid,
+
+    /// Kind of operation.
+    kind: OperationKind,
+
+    /// Plugin name, once known.
+    plugin: Arc<RwLock<Option<String>>>,
+
+    /// Set when the operation is cancelled.
+    cancellation: CancellationFlag,
+
+    /// Progress callback.
+    reporter: ProgressReporter,
+}
+
+impl PluginOperation {
+    /// Create an operation reporting progress to a callback.
+    pub fn new<F>(kind: OperationKind, reporter: F) -> Self
+    where
+        F: Fn(OperationProgress) + Send + Sync + 'static,
+    {
+        Self {
+            id: orbis_core::new_id(),
+            kind,
+            plugin: Arc::new(RwLock::new(None)),
+            cancellation: CancellationFlag::new(),
+            reporter: Arc::new(reporter),
+        }
+    }
+
+    /// Get the operation ID.
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Get the kind of operation.
+    #[must_use]
+    pub const fn kind(&self) -> OperationKind {
+        self.kind
+    }
+
+    /// Cancel the operation; it stops before entering its next stage.
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    /// Check if the operation was cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Enter a stage, reporting it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation was cancelled.
+    pub fn enter(&self, stage: OperationStage) -> orbis_core::Result<()> {
+        if self.is_cancelled() {
+            return Err(orbis_core::Error::plugin(format!("Operation {} was cancelled", self.id)));
+        }
+
+        self.report(OperationStatus::Running {
+            stage,
+            step: stage.step(),
+            total_steps: OperationStage::ALL.len(),
+        });
+        Ok(())
+    }
+
+    /// Set the name of the plugin the operation is about.
+    pub fn set_plugin(&self, name: &str) {
+        *self.plugin.write() = Some(name.to_string());
+    }
+
+    /// Report the outcome of the operation.
+    pub fn finish<T>(&self, result: &orbis_core::Result<T>) {
+        let status = match result {
+            Ok(_) => OperationStatus::Completed,
+            Err(_) if self.is_cancelled() => OperationStatus::Cancelled,
+            Err(e) => OperationStatus::Failed { error: e.to_string() },
+        };
+        self.report(status);
+    }
+
+    /// Send progress to the callback.
+    fn report(&self, status: OperationStatus) {
+        (self.reporter)(OperationProgress {
+            operation_id: self.id,
+            kind: self.kind,
+            plugin: self.plugin.read().clone(),
+            status,
+            at: Utc::now(),
+        });
+    }
+}
+
+impl std::fmt::Debug for PluginOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginOperation")
+            .field("id", &self.id)
+            .field("kind", &self.kind)
+            .field("plugin", &self.plugin.read())
+            .field("cancelled", &self.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Operations in progress, by ID.
+#[derive(Debug, Default)]
+pub struct PluginOperations {
+    /// Running operations.
+    running: DashMap<Uuid, PluginOperation>,
+}
+
+impl PluginOperations {
+    /// Create an empty set of operations.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track an operation until it finishes.
+    pub(crate) fn track(&self, operation: &PluginOperation) {
+        self.running.insert(operation.id(), operation.clone());
+    }
+
+    /// Stop tracking a finished operation.
+    pub(crate) fn untrack(&self, id: Uuid) {
+        self.running.remove(&id);
+    }
+
+    /// Cancel a running operation.
+    ///
+    /// Returns `false` if no operation with this ID is running.
+    #[must_use]
+    pub fn cancel(&self, id: Uuid) -> bool {
+        self.running.get(&id).is_some_and(|operation| {
+            operation.cancel();
+            true
+        })
+    }
+
+    /// List running operations.
+    #[must_use]
+    pub fn list(&self) -> Vec<PluginOperation> {
+        self.running.iter().map(|entry| entry.value().clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    fn recorded() -> (PluginOperation, Arc<Mutex<Vec<OperationProgress>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let operation = PluginOperation::new(OperationKind::Install, move |progress| sink.lock().push(progress));
+        (operation, events)
+    }
+
+    #[test]
+    fn test_stages_are_reported() {
+        let (operation, events) = recorded();
+
+        operation.enter(OperationStage::Downloading).unwrap();
+        operation.set_plugin("test");
+        operation.enter(OperationStage::CompilingWasm).unwrap();
+        operation.finish(&Ok(()));
+
+        let events = events.lock().clone();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].plugin, None);
+        assert_eq!(
+            events[1].status,
+            OperationStatus::Running {
+                stage: OperationStage::CompilingWasm,
+                step: 4,
+                total_steps: 6
+            }
+        );
+        assert_eq!(events[1].plugin.as_deref(), Some("test"));
+        assert_eq!(events[2].status, OperationStatus::Completed);
+    }
+
+    #[test]
+    fn test_cancelled_operation_stops() {
+        let (operation, events) = recorded();
+        let operations = PluginOperations::new();
+        operations.track(&operation);
+
+        operation.enter(OperationStage::Downloading).unwrap();
+        assert!(operations.cancel(operation.id()));
+        let result = operation.enter(OperationStage::VerifyingSignature);
+        result.as_ref().unwrap_err();
+        operation.finish(&result);
+        operations.untrack(operation.id());
+
+        assert!(!operations.cancel(operation.id()));
+        assert_eq!(events.lock().last().unwrap().status, OperationStatus::Cancelled);
+    }
+}
@@ -0,0 +1,484 @@
+//! Safe access to packed (ZIP) plugins.
+//!
+//! Packed plugins may come from untrusted sources, so an archive is checked
+//! before anything is read from it: its number of entries, the sizes its
+//! entries declare, and their paths, which must stay inside the plugin
+//! directory and must not be symbolic links. Declared sizes can lie, so
+//! reads also stop once the decompressed bytes exceed the size limit.
+//!
+//! Extraction streams entries into a temporary directory next to the
+//! destination and renames it into place once complete, so a rejected or
+//! failed extraction leaves nothing behind.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Default maximum number of entries in a packed plugin.
+pub const DEFAULT_MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+/// Default maximum decompressed size of a packed plugin, in bytes.
+pub const DEFAULT_MAX_ARCHIVE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Limits a packed plugin must stay within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveLimits {
+    /// Maximum number of entries, including directories.
+    pub max_entries: usize,
+
+    /// Maximum total decompressed size, in bytes.
+    pub max_size: u64,
+}
+
+impl ArchiveLimits {
+    /// Default limits.
+    pub const DEFAULT: Self = Self {
+        max_entries: DEFAULT_MAX_ARCHIVE_ENTRIES,
+        max_size: DEFAULT_MAX_ARCHIVE_SIZE,
+    };
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Why a packed plugin was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArchiveViolation {
+    /// The archive has more entries than allowed.
+    #[error("archive has {count} entries, more than the limit of {limit}")]
+    TooManyEntries {
+        /// Number of entries.
+        count: usize,
+
+        /// Maximum number of entries.
+        limit: usize,
+    },
+
+    /// The archive decompresses to more bytes than allowed.
+    #[error("archive decompresses to more than {limit} bytes")]
+    TooLarge {
+        /// Maximum decompressed size, in bytes.
+        limit: u64,
+    },
+
+    /// An entry path leaves the plugin directory through `..`.
+    #[error("entry '{entry}' escapes the plugin directory")]
+    PathTraversal {
+        /// Entry name.
+        entry: String,
+    },
+
+    /// An entry path is absolute.
+    #[error("entry '{entry}' has an absolute path")]
+    AbsolutePath {
+        /// Entry name.
+        entry: String,
+    },
+
+    /// An entry is a symbolic link.
+    #[error("entry '{entry}' is a symbolic link")]
+    Symlink {
+        /// Entry name.
+        entry: String,
+    },
+}
+
+impl From<ArchiveViolation> for orbis_core::Error {
+    fn from(violation: ArchiveViolation) -> Self {
+        Self::validation(format!("Unsafe plugin archive: {}", violation))
+    }
+}
+
+/// Error reading a packed plugin.
+#[derive(Debug, thiserror::Error)]
+pub enum PackedError {
+    /// The archive violates the limits.
+    #[error("Unsafe plugin archive: {0}")]
+    Unsafe(#[from] ArchiveViolation),
+
+    /// The archive could not be read or extracted.
+    #[error(transparent)]
+    Failed(#[from] orbis_core::Error),
+}
+
+impl PackedError {
+    /// Get the violation, if the archive was rejected as unsafe.
+    #[must_use]
+    pub const fn violation(&self) -> Option<&ArchiveViolation> {
+        match self {
+            Self::Unsafe(violation) => Some(violation),
+            Self::Failed(_) => None,
+        }
+    }
+}
+
+impl From<PackedError> for orbis_core::Error {
+    fn from(error: PackedError) -> Self {
+        match error {
+            PackedError::Unsafe(violation) => violation.into(),
+            PackedError::Failed(error) => error,
+        }
+    }
+}
+
+/// Result of reading a packed plugin.
+pub type PackedResult<T> = Result<T, PackedError>;
+
+/// A packed plugin whose entries were checked against the limits.
+pub struct PackedArchive {
+    /// Opened archive.
+    archive: zip::ZipArchive<File>,
+
+    /// Limits the archive is held to.
+    limits: ArchiveLimits,
+
+    /// Decompressed bytes read so far.
+    read: u64,
+}
+
+impl PackedArchive {
+    /// Open a packed plugin and check its entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read or an entry violates
+    /// the limits.
+    pub fn open(path: &Path, limits: ArchiveLimits) -> PackedResult<Self> {
+        let file = File::open(path)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to open ZIP file: {}", e)))?;
+        let archive = zip::ZipArchive::new(file)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read ZIP archive: {}", e)))?;
+
+        let mut packed = Self { archive, limits, read: 0 };
+        packed.check_entries()?;
+        Ok(packed)
+    }
+
+    /// Check the number, paths and declared sizes of the entries.
+    fn check_entries(&mut self) -> PackedResult<()> {
+        let count = self.archive.len();
+        if count > self.limits.max_entries {
+            return Err(ArchiveViolation::TooManyEntries {
+                count,
+                limit: self.limits.max_entries,
+            }
+            .into());
+        }
+
+        let mut declared: u64 = 0;
+        for index in 0..count {
+            let entry = self.archive.by_index_raw(index).map_err(entry_error)?;
+            check_entry_path(entry.name())?;
+            if entry.is_symlink() {
+                return Err(ArchiveViolation::Symlink {
+                    entry: entry.name().to_string(),
+                }
+                .into());
+            }
+
+            declared = declared.saturating_add(entry.size());
+            if declared > self.limits.max_size {
+                return Err(ArchiveViolation::TooLarge {
+                    limit: self.limits.max_size,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the names of the file entries, in archive order.
+    #[must_use]
+    pub fn file_names(&self) -> Vec<String> {
+        self.archive
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(String::from)
+            .collect()
+    }
+
+    /// Read an entry, or `None` if the archive has no entry of that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be read or reading it exceeds
+    /// the size limit.
+    pub fn read(&mut self, name: &str) -> PackedResult<Option<Vec<u8>>> {
+        let Some(index) = self.archive.index_for_name(name) else {
+            return Ok(None);
+        };
+
+        let mut contents = Vec::new();
+        self.copy_entry(index, &mut contents)?;
+        Ok(Some(contents))
+    }
+
+    /// Read every entry, to find entries decompressing to more than they
+    /// declare.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry cannot be read or the archive exceeds
+    /// the size limit.
+    pub fn verify(&mut self) -> PackedResult<()> {
+        for index in 0..self.archive.len() {
+            self.copy_entry(index, &mut std::io::sink())?;
+        }
+        Ok(())
+    }
+
+    /// Extract the archive into a directory, replacing it if it exists.
+    ///
+    /// Entries are written to a temporary directory next to `dest`, which is
+    /// renamed to `dest` once every entry was extracted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry cannot be extracted or the archive
+    /// exceeds the size limit; `dest` is then left untouched.
+    pub fn extract(mut self, dest: &Path) -> PackedResult<()> {
+        let parent = dest.parent().unwrap_or_else(|| Path::new("."));
+        let name = dest.file_name().map_or_else(|| "plugin".into(), |name| name.to_string_lossy());
+        let staging = parent.join(format!(".{}.extracting-{}", name, uuid::Uuid::new_v4()));
+
+        let result = self
+            .extract_into(&staging)
+            .and_then(|()| replace_dir(&staging, dest).map_err(PackedError::from));
+        if result.is_err()
+            && let Err(e) = std::fs::remove_dir_all(&staging)
+        {
+            tracing::warn!("Failed to remove staging directory {:?}: {}", staging, e);
+        }
+        result
+    }
+
+    /// Write every entry under a new directory.
+    fn extract_into(&mut self, dir: &Path) -> PackedResult<()> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to create {:?}: {}", dir, e)))?;
+
+        for index in 0..self.archive.len() {
+            let (name, is_dir) = {
+                let entry = self.archive.by_index_raw(index).map_err(entry_error)?;
+                (entry.name().to_string(), entry.is_dir())
+            };
+            let path = dir.join(&name);
+
+            if is_dir {
+                std::fs::create_dir_all(&path)
+                    .map_err(|e| orbis_core::Error::plugin(format!("Failed to create {:?}: {}", path, e)))?;
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| orbis_core::Error::plugin(format!("Failed to create {:?}: {}", parent, e)))?;
+            }
+            let mut file = File::create(&path)
+                .map_err(|e| orbis_core::Error::plugin(format!("Failed to create {:?}: {}", path, e)))?;
+            self.copy_entry(index, &mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decompress an entry into a writer, within the remaining size budget.
+    fn copy_entry<W: std::io::Write>(&mut self, index: usize, writer: &mut W) -> PackedResult<()> {
+        let remaining = self.limits.max_size.saturating_sub(self.read);
+        let mut entry = self.archive.by_index(index).map_err(entry_error)?;
+        let name = entry.name().to_string();
+
+        // Read one byte past the budget to tell a full budget from an overrun
+        let copied = std::io::copy(&mut (&mut entry).take(remaining.saturating_add(1)), writer)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read '{}' from ZIP: {}", name, e)))?;
+        if copied > remaining {
+            return Err(ArchiveViolation::TooLarge {
+                limit: self.limits.max_size,
+            }
+            .into());
+        }
+
+        self.read = self.read.saturating_add(copied);
+        Ok(())
+    }
+}
+
+/// Check that an entry path stays inside the directory it is extracted to.
+///
+/// # Errors
+///
+/// Returns the violation if the path is absolute or escapes through `..`.
+fn check_entry_path(name: &str) -> Result<(), ArchiveViolation> {
+    let entry = || name.to_string();
+    let normalized = name.replace('\\', "/");
+
+    // Windows drive letters are absolute too, whatever platform extracts them
+    let has_drive = normalized.as_bytes().get(1) == Some(&b':');
+    if normalized.starts_with('/') || has_drive {
+        return Err(ArchiveViolation::AbsolutePath { entry: entry() });
+    }
+
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            Component::ParentDir => return Err(ArchiveViolation::PathTraversal { entry: entry() }),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(ArchiveViolation::AbsolutePath { entry: entry() });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert a ZIP entry error.
+fn entry_error(error: zip::result::ZipError) -> orbis_core::Error {
+    orbis_core::Error::plugin(format!("Failed to access ZIP entry: {}", error))
+}
+
+/// Move a directory into place, replacing an existing one.
+fn replace_dir(source: &Path, dest: &Path) -> orbis_core::Result<()> {
+    // Keep the old directory until the new one is in place
+    let previous = dest.exists().then(|| {
+        let mut name = dest.as_os_str().to_os_string();
+        name.push(format!(".previous-{}", uuid::Uuid::new_v4()));
+        PathBuf::from(name)
+    });
+    if let Some(previous) = &previous {
+        std::fs::rename(dest, previous)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to replace {:?}: {}", dest, e)))?;
+    }
+
+    if let Err(e) = std::fs::rename(source, dest) {
+        if let Some(previous) = previous.as_ref()
+            && let Err(restore) = std::fs::rename(previous, dest)
+        {
+            tracing::warn!("Failed to restore {:?} from {:?}: {}", dest, previous, restore);
+        }
+        return Err(orbis_core::Error::plugin(format!("Failed to move plugin into {:?}: {}", dest, e)));
+    }
+
+    if let Some(previous) = previous
+        && let Err(e) = std::fs::remove_dir_all(&previous)
+    {
+        tracing::warn!("Failed to remove replaced plugin {:?}: {}", previous, e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Write a ZIP archive of entries to a temporary file.
+    fn zip_file(entries: &[(&str, &[u8])]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("orbis-packed-{}.zip", uuid::Uuid::new_v4()));
+        let mut writer = zip::ZipWriter::new(File::create(&path).expect("create zip"));
+        let options =
+            zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in entries {
+            writer.start_file(*name, options).expect("start entry");
+            writer.write_all(contents).expect("write entry");
+        }
+        writer.finish().expect("finish zip");
+        path
+    }
+
+    /// Get the violation an archive was rejected for.
+    fn rejected(result: PackedResult<PackedArchive>) -> ArchiveViolation {
+        match result {
+            Err(PackedError::Unsafe(violation)) => violation,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("archive accepted"),
+        }
+    }
+
+    #[test]
+    fn test_entry_paths() {
+        assert!(check_entry_path("plugin/manifest.json").is_ok());
+        assert!(check_entry_path("./plugin.wasm").is_ok());
+        assert!(matches!(
+            check_entry_path("../outside.txt"),
+            Err(ArchiveViolation::PathTraversal { .. })
+        ));
+        assert!(matches!(
+            check_entry_path("assets\\..\\..\\outside.txt"),
+            Err(ArchiveViolation::PathTraversal { .. })
+        ));
+        assert!(matches!(check_entry_path("/etc/passwd"), Err(ArchiveViolation::AbsolutePath { .. })));
+        assert!(matches!(check_entry_path("C:/Windows/win.ini"), Err(ArchiveViolation::AbsolutePath { .. })));
+    }
+
+    #[test]
+    fn test_limits() {
+        let path = zip_file(&[("manifest.json", b"{}"), ("a.txt", b"a"), ("b.txt", b"b")]);
+        let few = ArchiveLimits {
+            max_entries: 2,
+            ..ArchiveLimits::default()
+        };
+        assert_eq!(
+            rejected(PackedArchive::open(&path, few)),
+            ArchiveViolation::TooManyEntries { count: 3, limit: 2 }
+        );
+
+        // Highly compressible content, as in a ZIP bomb
+        let bomb = zip_file(&[("plugin.wasm", &vec![0_u8; 64 * 1024])]);
+        let small = ArchiveLimits {
+            max_size: 1024,
+            ..ArchiveLimits::default()
+        };
+        assert_eq!(
+            rejected(PackedArchive::open(&bomb, small)),
+            ArchiveViolation::TooLarge { limit: 1024 }
+        );
+
+        let mut archive = PackedArchive::open(&path, ArchiveLimits::default()).expect("open archive");
+        assert_eq!(archive.read("manifest.json").expect("read"), Some(b"{}".to_vec()));
+        assert_eq!(archive.read("missing.json").expect("read"), None);
+        archive.verify().expect("verify");
+
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(bomb);
+    }
+
+    #[test]
+    fn test_extract_replaces_destination() {
+        let path = zip_file(&[("manifest.json", b"{}"), ("assets/logo.svg", b"<svg/>")]);
+        let dest = std::env::temp_dir().join(format!("orbis-extract-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dest).expect("create destination");
+        std::fs::write(dest.join("stale.txt"), b"old").expect("write stale file");
+
+        PackedArchive::open(&path, ArchiveLimits::default())
+            .expect("open archive")
+            .extract(&dest)
+            .expect("extract");
+        assert_eq!(std::fs::read(dest.join("assets/logo.svg")).expect("extracted"), b"<svg/>");
+        assert!(!dest.join("stale.txt").exists());
+
+        // A rejected archive leaves the destination as it was
+        let bomb = zip_file(&[("plugin.wasm", &vec![0_u8; 64 * 1024])]);
+        let mut archive = PackedArchive::open(&bomb, ArchiveLimits::default()).expect("open archive");
+        archive.limits.max_size = 1024;
+        assert!(archive.extract(&dest).is_err());
+        assert!(dest.join("manifest.json").exists());
+
+        let staging = format!(".{}.extracting-", dest.file_name().expect("name").to_string_lossy());
+        let leftovers = std::fs::read_dir(dest.parent().expect("parent"))
+            .expect("read temp dir")
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&staging))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let _ = std::fs::remove_dir_all(dest);
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(bomb);
+    }
+}
@@ -0,0 +1,269 @@
+//! Per-plugin network quotas.
+//!
+//! Requests to external hosts count against the plugin's
+//! [`ResourceLimits`]: requests per minute, and bytes sent and received per
+//! UTC day. Usage is kept per plugin and, with persistence, written to disk
+//! after each request so quotas survive restarts.
+
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use dashmap::DashMap;
+use orbis_plugin_api::ResourceLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Network usage of a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkUsage {
+    /// Day the daily counters belong to (UTC).
+    pub day: NaiveDate,
+
+    /// Bytes sent and received during the day.
+    pub bytes_today: u64,
+
+    /// Start of the minute the request counter belongs to.
+    pub minute: DateTime<Utc>,
+
+    /// Requests made during the minute.
+    pub requests_this_minute: u32,
+
+    /// Requests made since the last reset.
+    pub total_requests: u64,
+
+    /// Bytes sent and received since the last reset.
+    pub total_bytes: u64,
+
+    /// Requests refused for exceeding a quota since the last reset.
+    pub denied_requests: u64,
+
+    /// When the usage was last reset.
+    pub since: DateTime<Utc>,
+}
+
+impl NetworkUsage {
+    /// Create empty usage starting at a time.
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            day: now.date_naive(),
+            bytes_today: 0,
+            minute: start_of_minute(now),
+            requests_this_minute: 0,
+            total_requests: 0,
+            total_bytes: 0,
+            denied_requests: 0,
+            since: now,
+        }
+    }
+
+    /// Start new periods for counters whose period has ended.
+    fn roll(&mut self, now: DateTime<Utc>) {
+        if now.date_naive() != self.day {
+            self.day = now.date_naive();
+            self.bytes_today = 0;
+        }
+        let minute = start_of_minute(now);
+        if minute != self.minute {
+            self.minute = minute;
+            self.requests_this_minute = 0;
+        }
+    }
+}
+
+/// Tracks the network usage of plugins against their quotas.
+#[derive(Debug, Default)]
+pub struct NetworkQuotas {
+    /// Usage per plugin.
+    usage: DashMap<String, NetworkUsage>,
+
+    /// File usage is persisted to, if any.
+    file: Option<PathBuf>,
+}
+
+impl NetworkQuotas {
+    /// Create in-memory quota tracking.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create quota tracking persisted to a file, loading the usage it holds.
+    #[must_use]
+    pub fn with_persistence(file: PathBuf) -> Self {
+        let usage = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<BTreeMap<String, NetworkUsage>>(&bytes).ok())
+            .unwrap_or_default();
+
+        Self {
+            usage: usage.into_iter().collect(),
+            file: Some(file),
+        }
+    }
+
+    /// Admit a request of a plugin, counting it against its quotas.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin exceeded its requests per minute or
+    /// already used its bytes for the day.
+    pub fn admit(&self, plugin: &str, limits: &ResourceLimits) -> orbis_core::Result<()> {
+        self.admit_at(plugin, limits, Utc::now())
+    }
+
+    /// Count bytes sent or received by a plugin.
+    pub fn record_bytes(&self, plugin: &str, bytes: u64) {
+        let now = Utc::now();
+        {
+            let mut usage = self.usage.entry(plugin.to_string()).or_insert_with(|| NetworkUsage::new(now));
+            usage.roll(now);
+            usage.bytes_today = usage.bytes_today.saturating_add(bytes);
+            usage.total_bytes = usage.total_bytes.saturating_add(bytes);
+        }
+        self.persist();
+    }
+
+    /// Get the network usage of a plugin, if it made requests.
+    #[must_use]
+    pub fn usage(&self, plugin: &str) -> Option<NetworkUsage> {
+        self.usage.get(plugin).map(|usage| {
+            let mut usage = usage.clone();
+            usage.roll(Utc::now());
+            usage
+        })
+    }
+
+    /// Reset the network usage of a plugin, restoring its full quotas.
+    pub fn reset(&self, plugin: &str) {
+        self.usage.remove(plugin);
+        self.persist();
+    }
+
+    /// Admit a request at a given time.
+    fn admit_at(&self, plugin: &str, limits: &ResourceLimits, now: DateTime<Utc>) -> orbis_core::Result<()> {
+        let error = {
+            let mut usage = self.usage.entry(plugin.to_string()).or_insert_with(|| NetworkUsage::new(now));
+            usage.roll(now);
+
+            let error = if let Some(max) = limits.network_requests_per_minute
+                && usage.requests_this_minute >= max
+            {
+                Some(format!(
+                    "Plugin '{}' exceeded its quota of {} network requests per minute",
+                    plugin, max
+                ))
+            } else if let Some(max) = limits.network_bytes_per_day
+                && usage.bytes_today >= max
+            {
+                Some(format!("Plugin '{}' used its quota of {} network bytes for today", plugin, max))
+            } else {
+                None
+            };
+
+            if error.is_some() {
+                usage.denied_requests = usage.denied_requests.saturating_add(1);
+            } else {
+                usage.requests_this_minute = usage.requests_this_minute.saturating_add(1);
+                usage.total_requests = usage.total_requests.saturating_add(1);
+            }
+            drop(usage);
+            error
+        };
+        self.persist();
+        error.map_or(Ok(()), |error| Err(orbis_core::Error::payload_too_large(error)))
+    }
+
+    /// Write usage to the persistence file, if any.
+    fn persist(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        let usage: BTreeMap<_, _> = self
+            .usage
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let result = serde_json::to_vec(&usage)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                if let Some(parent) = file.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(file, bytes).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist network usage to {:?}: {}", file, e);
+        }
+    }
+}
+
+/// Get the start of the minute of a time.
+fn start_of_minute(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.with_second(0)
+        .and_then(|time| time.with_nanosecond(0))
+        .unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn limits() -> ResourceLimits {
+        ResourceLimits {
+            network_bytes_per_day: Some(100),
+            network_requests_per_minute: Some(2),
+        }
+    }
+
+    #[test]
+    fn test_requests_per_minute() {
+        let quotas = NetworkQuotas::new();
+        let now = Utc::now();
+
+        quotas.admit_at("test", &limits(), now).unwrap();
+        quotas.admit_at("test", &limits(), now).unwrap();
+        quotas.admit_at("test", &limits(), now).unwrap_err();
+        // Other plugins have their own quotas
+        quotas.admit_at("other", &limits(), now).unwrap();
+        // The next minute starts a new window
+        quotas.admit_at("test", &limits(), now + Duration::minutes(1)).unwrap();
+
+        let usage = quotas.usage("test").unwrap();
+        assert_eq!(usage.total_requests, 3);
+        assert_eq!(usage.denied_requests, 1);
+    }
+
+    #[test]
+    fn test_bytes_per_day() {
+        let quotas = NetworkQuotas::new();
+        let now = Utc::now();
+
+        quotas.admit_at("test", &limits(), now).unwrap();
+        quotas.record_bytes("test", 100);
+        quotas
+            .admit_at("test", &limits(), now + Duration::minutes(1))
+            .unwrap_err();
+        quotas.admit_at("test", &limits(), now + Duration::days(1)).unwrap();
+
+        quotas.reset("test");
+        assert!(quotas.usage("test").is_none());
+        quotas.admit_at("test", &ResourceLimits::default(), now).unwrap();
+    }
+
+    #[test]
+    fn test_usage_persists() {
+        let file = std::env::temp_dir().join(format!("orbis-network-{}.json", uuid::Uuid::new_v4()));
+
+        let quotas = NetworkQuotas::with_persistence(file.clone());
+        quotas.admit("test", &limits()).unwrap();
+        quotas.record_bytes("test", 42);
+
+        let restored = NetworkQuotas::with_persistence(file.clone());
+        let usage = restored.usage("test").unwrap();
+        assert_eq!(usage.total_requests, 1);
+        assert_eq!(usage.bytes_today, 42);
+
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -1,15 +1,17 @@
 //! Plugin registry for tracking loaded plugins.
 
-use super::{PluginSource};
+use super::{ArchiveViolation, DependencyIssue, NetworkQuotas, StateCause, StateHistory, StateTransition, StateTrigger, NetworkUsage, PluginCompatibility, PluginSource, SnapshotInfo, TrapReport};
 use orbis_plugin_api::PluginManifest;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use orbis_core::PluginId;
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Plugin state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PluginState {
     /// Plugin is loaded but not running.
@@ -25,11 +27,24 @@ pub enum PluginState {
     Error,
 }
 
+impl PluginState {
+    /// Get the state name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Loaded => "loaded",
+            Self::Running => "running",
+            Self::Disabled => "disabled",
+            Self::Error => "error",
+        }
+    }
+}
+
 /// Information about a loaded plugin.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginInfo {
     /// Plugin ID.
-    pub id: Uuid,
+    pub id: PluginId,
 
     /// Plugin manifest.
     pub manifest: PluginManifest,
@@ -43,12 +58,149 @@ pub struct PluginInfo {
 
     /// When the plugin was loaded.
     pub loaded_at: DateTime<Utc>,
+
+    /// Unsatisfied dependencies keeping the plugin from starting.
+    #[serde(default)]
+    pub dependency_issues: Vec<DependencyIssue>,
+}
+
+impl PluginInfo {
+    /// Whether the plugin cannot start because of its dependencies.
+    #[must_use]
+    pub const fn is_blocked(&self) -> bool {
+        !self.dependency_issues.is_empty()
+    }
+}
+
+/// Version change of a plugin, recorded when it is installed or reloaded
+/// as a new version, with the new version's release notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionChange {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Version before the change, unset for an install.
+    pub previous_version: Option<String>,
+
+    /// Version after the change.
+    pub version: String,
+
+    /// Release notes of the new version, in Markdown.
+    pub release_notes: Option<String>,
+
+    /// URL of the plugin's changelog.
+    pub changelog_url: Option<String>,
+
+    /// Deprecation notice of the new version, if it is deprecated.
+    pub deprecated: Option<String>,
+
+    /// When the change happened.
+    pub changed_at: DateTime<Utc>,
+}
+
+impl VersionChange {
+    /// Describe the change from a previous manifest, if any, to a new one.
+    #[must_use]
+    pub fn new(previous: Option<&PluginManifest>, manifest: &PluginManifest) -> Self {
+        Self {
+            plugin: manifest.name.clone(),
+            previous_version: previous.map(|previous| previous.version.clone()),
+            version: manifest.version.clone(),
+            release_notes: manifest.release_notes.clone(),
+            changelog_url: manifest.changelog_url.clone(),
+            deprecated: manifest.deprecated.clone(),
+            changed_at: Utc::now(),
+        }
+    }
+}
+
+/// Filters of a plugin search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginFilters {
+    /// Only plugins in this category (case-insensitive).
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// Only plugins with this tag (case-insensitive).
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// Only plugins in this state.
+    #[serde(default)]
+    pub state: Option<PluginState>,
 }
 
+impl PluginFilters {
+    /// Check if a plugin passes the filters.
+    #[must_use]
+    pub fn matches(&self, info: &PluginInfo) -> bool {
+        let manifest = &info.manifest;
+        self.state.is_none_or(|state| info.state == state)
+            && self.category.as_ref().is_none_or(|category| {
+                manifest
+                    .category
+                    .as_ref()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(category))
+            })
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| manifest.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+}
+
+/// Load timing for a single plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginLoadTiming {
+    /// Plugin name (or path if the manifest could not be read).
+    pub name: String,
+
+    /// Time spent loading and initializing the plugin, in milliseconds.
+    pub duration_ms: u64,
+
+    /// Error message if loading failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Why a packed plugin was rejected as unsafe, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violation: Option<ArchiveViolation>,
+}
+
+/// Report of the last startup load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginLoadReport {
+    /// Total wall-clock load time, in milliseconds.
+    pub total_ms: u64,
+
+    /// Maximum number of plugins loaded concurrently.
+    pub parallelism: usize,
+
+    /// Per-plugin timings, in load order.
+    pub plugins: Vec<PluginLoadTiming>,
+}
+
+/// Number of trap reports kept per plugin.
+pub const MAX_TRAP_REPORTS: usize = 20;
+
 /// Registry for tracking loaded plugins.
 pub struct PluginRegistry {
     plugins: DashMap<String, PluginInfo>,
     state_file: Option<PathBuf>,
+    /// Report of the last startup load.
+    load_report: parking_lot::RwLock<Option<PluginLoadReport>>,
+    /// Memory snapshots of initialized plugins.
+    snapshots: DashMap<String, SnapshotInfo>,
+    /// Most recent trap reports per plugin, oldest first.
+    traps: DashMap<String, VecDeque<TrapReport>>,
+    /// Host API compatibility of every plugin checked, including refused ones.
+    compatibility: DashMap<String, PluginCompatibility>,
+    /// Network usage of plugins, checked against their quotas.
+    network_quotas: Arc<NetworkQuotas>,
+    /// Last version change of each plugin, kept across reloads.
+    version_changes: DashMap<String, VersionChange>,
+    /// State changes of plugins, kept across reloads.
+    history: StateHistory,
 }
 
 impl PluginRegistry {
@@ -58,15 +210,30 @@ impl PluginRegistry {
         Self {
             plugins: DashMap::new(),
             state_file: None,
+            load_report: parking_lot::RwLock::new(None),
+            snapshots: DashMap::new(),
+            traps: DashMap::new(),
+            compatibility: DashMap::new(),
+            network_quotas: Arc::new(NetworkQuotas::new()),
+            version_changes: DashMap::new(),
+            history: StateHistory::new(),
         }
     }
     
     /// Create a plugin registry with persistence.
     #[must_use]
     pub fn with_persistence(state_file: PathBuf) -> Self {
+        let network_file = state_file.with_file_name(".plugin_network_usage.json");
         let mut registry = Self {
             plugins: DashMap::new(),
             state_file: Some(state_file),
+            load_report: parking_lot::RwLock::new(None),
+            snapshots: DashMap::new(),
+            traps: DashMap::new(),
+            compatibility: DashMap::new(),
+            network_quotas: Arc::new(NetworkQuotas::with_persistence(network_file)),
+            version_changes: DashMap::new(),
+            history: StateHistory::new(),
         };
         
         // Load existing state
@@ -77,12 +244,38 @@ impl PluginRegistry {
 
     /// Register a plugin.
     pub fn register(&self, info: PluginInfo) {
+        self.history
+            .record(&info.manifest.name, None, Some(info.state), StateCause::new(StateTrigger::Load));
         self.plugins.insert(info.manifest.name.clone(), info);
     }
 
     /// Unregister a plugin.
     pub fn unregister(&self, name: &str) -> Option<PluginInfo> {
-        self.plugins.remove(name).map(|(_, info)| info)
+        self.unregister_with(name, StateCause::new(StateTrigger::Unload))
+    }
+
+    /// Unregister a plugin, recording the cause in its state history.
+    pub fn unregister_with(&self, name: &str, cause: StateCause) -> Option<PluginInfo> {
+        self.snapshots.remove(name);
+        self.traps.remove(name);
+        self.compatibility.remove(name);
+        let info = self.plugins.remove(name).map(|(_, info)| info)?;
+        self.history.record(name, Some(info.state), None, cause);
+        Some(info)
+    }
+
+    /// Replace a registered plugin with a new version, recording the cause
+    /// in its state history.
+    ///
+    /// Trap reports and the memory snapshot of the previous version are dropped.
+    pub fn replace(&self, info: PluginInfo, cause: StateCause) -> Option<PluginInfo> {
+        let name = info.manifest.name.clone();
+        self.snapshots.remove(&name);
+        self.traps.remove(&name);
+        let state = info.state;
+        let previous = self.plugins.insert(name.clone(), info);
+        self.history.record(&name, previous.as_ref().map(|info| info.state), Some(state), cause);
+        previous
     }
 
     /// Get a plugin by name.
@@ -107,24 +300,162 @@ impl PluginRegistry {
             .collect()
     }
 
-    /// Set plugin state.
+    /// Search plugins by name, description and tags, sorted by name.
+    ///
+    /// The query matches case-insensitively anywhere in those fields; an
+    /// empty query matches every plugin passing the filters.
+    #[must_use]
+    pub fn search(&self, query: &str, filters: &PluginFilters) -> Vec<PluginInfo> {
+        let query = query.trim().to_lowercase();
+        let mut plugins: Vec<PluginInfo> = self
+            .plugins
+            .iter()
+            .filter(|r| filters.matches(r.value()) && matches_query(r.value(), &query))
+            .map(|r| r.value().clone())
+            .collect();
+        plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+        plugins
+    }
+
+    /// Record the report of a startup load.
+    pub fn set_load_report(&self, report: PluginLoadReport) {
+        *self.load_report.write() = Some(report);
+    }
+
+    /// Get the report of the last startup load.
+    #[must_use]
+    pub fn load_report(&self) -> Option<PluginLoadReport> {
+        self.load_report.read().clone()
+    }
+
+    /// Record the memory snapshot of an initialized plugin.
+    pub fn set_snapshot(&self, name: &str, snapshot: Option<SnapshotInfo>) {
+        match snapshot {
+            Some(snapshot) => {
+                self.snapshots.insert(name.to_string(), snapshot);
+            }
+            None => {
+                self.snapshots.remove(name);
+            }
+        }
+    }
+
+    /// Get the memory snapshot of a plugin, if one was taken.
+    #[must_use]
+    pub fn snapshot(&self, name: &str) -> Option<SnapshotInfo> {
+        self.snapshots.get(name).map(|r| r.value().clone())
+    }
+
+    /// Record the result of a plugin compatibility check.
+    pub fn set_compatibility(&self, compatibility: PluginCompatibility) {
+        self.compatibility.insert(compatibility.plugin.clone(), compatibility);
+    }
+
+    /// Get the compatibility of a plugin, if it was checked.
+    #[must_use]
+    pub fn compatibility(&self, name: &str) -> Option<PluginCompatibility> {
+        self.compatibility.get(name).map(|r| r.value().clone())
+    }
+
+    /// Get the compatibility of every plugin checked, sorted by name.
+    #[must_use]
+    pub fn compatibility_report(&self) -> Vec<PluginCompatibility> {
+        let mut report: Vec<_> = self.compatibility.iter().map(|r| r.value().clone()).collect();
+        report.sort_by(|a, b| a.plugin.cmp(&b.plugin));
+        report
+    }
+
+    /// Get the network usage tracking of plugins, to enforce quotas with.
+    #[must_use]
+    pub const fn network_quotas(&self) -> &Arc<NetworkQuotas> {
+        &self.network_quotas
+    }
+
+    /// Get the network usage of a plugin, if it made requests since the last reset.
+    #[must_use]
+    pub fn network_usage(&self, name: &str) -> Option<NetworkUsage> {
+        self.network_quotas.usage(name)
+    }
+
+    /// Reset the network usage of a plugin, restoring its full quotas.
+    pub fn reset_network_usage(&self, name: &str) {
+        self.network_quotas.reset(name);
+    }
+
+    /// Record the version change of a plugin, replacing the previous one.
+    pub fn record_version_change(&self, change: VersionChange) {
+        self.version_changes.insert(change.plugin.clone(), change);
+    }
+
+    /// Get the last version change of a plugin, if it was installed or
+    /// reloaded as a new version since startup.
+    #[must_use]
+    pub fn version_change(&self, name: &str) -> Option<VersionChange> {
+        self.version_changes.get(name).map(|r| r.value().clone())
+    }
+
+    /// Get the running plugins that are deprecated, sorted by name.
+    #[must_use]
+    pub fn deprecated_running(&self) -> Vec<PluginInfo> {
+        let mut plugins: Vec<PluginInfo> = self
+            .plugins
+            .iter()
+            .filter(|r| r.value().state == PluginState::Running && r.value().manifest.deprecated.is_some())
+            .map(|r| r.value().clone())
+            .collect();
+        plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+        plugins
+    }
+
+    /// Record a plugin trap, dropping the oldest report beyond [`MAX_TRAP_REPORTS`].
+    pub fn record_trap(&self, report: TrapReport) {
+        let mut reports = self.traps.entry(report.plugin.clone()).or_default();
+        if reports.len() >= MAX_TRAP_REPORTS {
+            reports.pop_front();
+        }
+        reports.push_back(report);
+    }
+
+    /// Get the recorded trap reports of a plugin, most recent first.
+    #[must_use]
+    pub fn trap_reports(&self, name: &str) -> Vec<TrapReport> {
+        self.traps
+            .get(name)
+            .map(|r| r.value().iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Set plugin state, recording the change in its state history.
+    ///
+    /// Returns the recorded change, or `None` if the state was unchanged.
     ///
     /// # Errors
     ///
     /// Returns an error if the plugin is not found.
-    pub fn set_state(&self, name: &str, state: PluginState) -> orbis_core::Result<()> {
+    pub fn set_state(
+        &self,
+        name: &str,
+        state: PluginState,
+        cause: StateCause,
+    ) -> orbis_core::Result<Option<StateTransition>> {
         // Update state in a separate scope to release lock before saving
-        {
+        let previous = {
             let mut entry = self.plugins.get_mut(name).ok_or_else(|| {
                 orbis_core::Error::plugin(format!("Plugin '{}' not found", name))
             })?;
-            entry.value_mut().state = state;
-        } // Lock released here
+            std::mem::replace(&mut entry.value_mut().state, state)
+        }; // Lock released here
         
         // Now safe to call save_state which iterates over plugins
         let _ = self.save_state();
         
-        Ok(())
+        Ok(self.history.record(name, Some(previous), Some(state), cause))
+    }
+
+    /// Get the state history of plugins.
+    #[must_use]
+    pub const fn history(&self) -> &StateHistory {
+        &self.history
     }
 
     /// Check if a plugin exists.
@@ -161,8 +492,11 @@ impl PluginRegistry {
                 state: PluginState,
             }
             
+            // Plugins blocked on their dependencies keep no state, so they come
+            // back as loaded once their dependencies are satisfied
             let states: Vec<PluginStateRecord> = self.plugins
                 .iter()
+                .filter(|entry| !entry.value().is_blocked())
                 .map(|entry| PluginStateRecord {
                     name: entry.key().clone(),
                     state: entry.value().state,
@@ -245,11 +579,22 @@ impl PluginRegistry {
             let states: Vec<PluginStateRecord> = serde_json::from_str(&contents)
                 .map_err(|e| orbis_core::Error::plugin(format!("Failed to parse state file: {}", e)))?;
             
-            // Apply saved states to matching plugins
+            // Apply saved states to matching plugins, except those blocked on
+            // their dependencies
             for record in states {
-                if let Some(mut entry) = self.plugins.get_mut(&record.name) {
-                    entry.value_mut().state = record.state;
+                let previous = self
+                    .plugins
+                    .get_mut(&record.name)
+                    .filter(|entry| !entry.value().is_blocked())
+                    .map(|mut entry| std::mem::replace(&mut entry.value_mut().state, record.state));
+                if let Some(previous) = previous {
                     tracing::info!("Restored state for plugin '{}': {:?}", record.name, record.state);
+                    self.history.record(
+                        &record.name,
+                        Some(previous),
+                        Some(record.state),
+                        StateCause::new(StateTrigger::Restore),
+                    );
                 }
             }
         }
@@ -258,8 +603,163 @@ impl PluginRegistry {
     }
 }
 
+/// Check if a plugin's name, description or tags contain a lowercase query.
+fn matches_query(info: &PluginInfo, query: &str) -> bool {
+    let manifest = &info.manifest;
+    query.is_empty()
+        || manifest.name.to_lowercase().contains(query)
+        || manifest.description.to_lowercase().contains(query)
+        || manifest.tags.iter().any(|tag| tag.to_lowercase().contains(query))
+}
+
 impl Default for PluginRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(name: &str, tags: &[&str], category: Option<&str>, state: PluginState) -> PluginInfo {
+        let manifest = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "description": format!("The {} plugin", name),
+            "tags": tags,
+            "category": category,
+        }))
+        .expect("valid manifest");
+
+        PluginInfo {
+            id: PluginId::generate(),
+            manifest,
+            source: PluginSource::Unpacked(PathBuf::from(name)),
+            state,
+            loaded_at: Utc::now(),
+            dependency_issues: Vec::new(),
+        }
+    }
+
+    fn registry() -> PluginRegistry {
+        let registry = PluginRegistry::new();
+        registry.register(plugin("invoices", &["Billing", "pdf"], Some("Finance"), PluginState::Running));
+        registry.register(plugin("ledger", &["billing"], Some("finance"), PluginState::Disabled));
+        registry.register(plugin("crm", &["contacts"], Some("Sales"), PluginState::Running));
+        registry
+    }
+
+    fn names(plugins: &[PluginInfo]) -> Vec<&str> {
+        plugins.iter().map(|p| p.manifest.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_query() {
+        let registry = registry();
+        let filters = PluginFilters::default();
+
+        assert_eq!(names(&registry.search("", &filters)), ["crm", "invoices", "ledger"]);
+        assert_eq!(names(&registry.search("BILL", &filters)), ["invoices", "ledger"]);
+        assert_eq!(names(&registry.search("the crm", &filters)), ["crm"]);
+        assert!(registry.search("inventory", &filters).is_empty());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let registry = registry();
+
+        let finance = PluginFilters {
+            category: Some("FINANCE".to_owned()),
+            ..PluginFilters::default()
+        };
+        assert_eq!(names(&registry.search("", &finance)), ["invoices", "ledger"]);
+
+        let running_billing = PluginFilters {
+            tag: Some("billing".to_owned()),
+            state: Some(PluginState::Running),
+            ..PluginFilters::default()
+        };
+        assert_eq!(names(&registry.search("", &running_billing)), ["invoices"]);
+
+        let pdf = PluginFilters {
+            tag: Some("pdf".to_owned()),
+            ..PluginFilters::default()
+        };
+        assert!(registry.search("ledger", &pdf).is_empty());
+    }
+
+    #[test]
+    fn test_state_history() {
+        let registry = registry();
+        let cause = StateCause::new(StateTrigger::Manual).by("admin");
+        let transition = registry
+            .set_state("crm", PluginState::Disabled, cause)
+            .expect("set state")
+            .expect("state changed");
+        assert_eq!(transition.from, Some(PluginState::Running));
+        assert!(registry
+            .set_state("crm", PluginState::Disabled, StateCause::new(StateTrigger::Manual))
+            .expect("set state")
+            .is_none());
+
+        registry.unregister("crm");
+        let history = registry.history().get("crm");
+        let changes: Vec<_> = history.iter().map(|t| (t.from, t.to, t.cause.trigger)).collect();
+        assert_eq!(
+            changes,
+            [
+                (Some(PluginState::Disabled), None, StateTrigger::Unload),
+                (Some(PluginState::Running), Some(PluginState::Disabled), StateTrigger::Manual),
+                (None, Some(PluginState::Running), StateTrigger::Load),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let registry = registry();
+        let previous = registry
+            .replace(
+                plugin("crm", &["leads"], Some("Sales"), PluginState::Loaded),
+                StateCause::new(StateTrigger::Reload),
+            )
+            .expect("previous version");
+        assert_eq!(previous.manifest.tags, ["contacts"]);
+        assert_eq!(registry.get("crm").expect("crm").manifest.tags, ["leads"]);
+
+        let history = registry.history().get("crm");
+        let last = history.first().map(|t| (t.from, t.to, t.cause.trigger));
+        assert_eq!(last, Some((Some(PluginState::Running), Some(PluginState::Loaded), StateTrigger::Reload)));
+    }
+
+    #[test]
+    fn test_version_changes_and_deprecation() {
+        let registry = registry();
+        let old = registry.get("invoices").expect("invoices").manifest;
+        let new: PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "invoices",
+            "version": "1.1.0",
+            "deprecated": "Use billing instead",
+            "changelog_url": "https://example.com/invoices/CHANGELOG.md",
+            "release_notes": "- Export to PDF",
+        }))
+        .expect("valid manifest");
+
+        registry.record_version_change(VersionChange::new(Some(&old), &new));
+        let change = registry.version_change("invoices").expect("version change");
+        assert_eq!(change.previous_version.as_deref(), Some("1.0.0"));
+        assert_eq!(change.version, "1.1.0");
+        assert_eq!(change.release_notes.as_deref(), Some("- Export to PDF"));
+        assert!(registry.version_change("crm").is_none());
+
+        assert!(registry.deprecated_running().is_empty());
+        registry.unregister("invoices");
+        registry.register(PluginInfo {
+            manifest: new,
+            ..plugin("invoices", &[], None, PluginState::Running)
+        });
+        assert_eq!(names(&registry.deprecated_running()), ["invoices"]);
+        assert!(registry.version_change("invoices").is_some());
+    }
+}
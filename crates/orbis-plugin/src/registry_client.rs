@@ -0,0 +1,364 @@
+//! Installing plugins from a remote plugin registry.
+//!
+//! A registry is an HTTPS server publishing the versions of each plugin at
+//! `{registry}/plugins/{name}.json`:
+//!
+//! ```json
+//! {
+//!   "name": "my-plugin",
+//!   "versions": [
+//!     { "version": "1.2.0", "url": "files/my-plugin-1.2.0.zip", "sha256": "…" }
+//!   ]
+//! }
+//! ```
+//!
+//! File URLs may be relative to the index. A version resolves to a
+//! [`RemoteSource`], so the plugin is fetched, checked against its hash and
+//! cached like any remote plugin; its signature is then checked against the
+//! signature policy before it is installed.
+
+use crate::remote::RemoteSource;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Time allowed to fetch an index.
+const INDEX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size of an index, in bytes.
+const MAX_INDEX_SIZE: usize = 1024 * 1024;
+
+/// Versions of a plugin published in a registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    /// Plugin name.
+    pub name: String,
+
+    /// Published versions, in any order.
+    #[serde(default)]
+    pub versions: Vec<RegistryVersion>,
+}
+
+/// A published version of a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryVersion {
+    /// Semantic version.
+    pub version: String,
+
+    /// URL of the plugin file, absolute or relative to the index.
+    pub url: String,
+
+    /// Mirrors of the plugin file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// SHA-256 hash of the plugin file, as hex.
+    pub sha256: String,
+
+    /// File name to install the plugin as; defaults to the last segment of the URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+
+    /// Whether the version was withdrawn; withdrawn versions are never resolved.
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+impl RegistryIndex {
+    /// Get the newest version matching a requirement, or the newest version
+    /// without one.
+    ///
+    /// Withdrawn versions and versions that are not semantic versions are
+    /// skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the requirement is invalid or no version matches it.
+    pub fn resolve(&self, requirement: Option<&str>) -> orbis_core::Result<&RegistryVersion> {
+        let requirement = requirement.map_or(Ok(semver::VersionReq::STAR), semver::VersionReq::parse);
+        let Ok(requirement) = requirement else {
+            return Err(orbis_core::Error::validation("Invalid version requirement"));
+        };
+
+        self.versions
+            .iter()
+            .filter(|version| !version.yanked)
+            .filter_map(|version| semver::Version::parse(&version.version).ok().map(|parsed| (parsed, version)))
+            .filter(|pair| requirement.matches(&pair.0))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|pair| pair.1)
+            .ok_or_else(|| {
+                orbis_core::Error::not_found(format!(
+                    "No published version of plugin '{}' matches {}",
+                    self.name, requirement
+                ))
+            })
+    }
+}
+
+impl RegistryVersion {
+    /// Get the remote source of the plugin file, resolving URLs relative to the index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a URL is invalid or the source does not validate.
+    pub fn source(&self, index_url: &url::Url) -> orbis_core::Result<RemoteSource> {
+        let join = |url: &str| {
+            index_url
+                .join(url)
+                .map(String::from)
+                .map_err(|e| orbis_core::Error::validation(format!("Invalid plugin URL '{}': {}", url, e)))
+        };
+
+        let source = RemoteSource {
+            url: join(&self.url)?,
+            mirrors: self.mirrors.iter().map(|mirror| join(mirror)).collect::<orbis_core::Result<_>>()?,
+            sha256: self.sha256.clone(),
+            file_name: self.file_name.clone(),
+        };
+        source.validate()?;
+        Ok(source)
+    }
+}
+
+/// Client of a remote plugin registry.
+#[derive(Debug, Clone)]
+pub struct RegistryClient {
+    /// Base URL of the registry, ending in '/'.
+    url: url::Url,
+
+    /// HTTP client.
+    client: reqwest::Client,
+}
+
+impl RegistryClient {
+    /// Create a client of a registry.
+    ///
+    /// Registries must be served over HTTPS, except on the loopback address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn new(url: &str) -> orbis_core::Result<Self> {
+        let url = registry_url(url)?;
+        let client = reqwest::Client::builder()
+            .timeout(INDEX_TIMEOUT)
+            .user_agent(concat!("orbis/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+
+        Ok(Self { url, client })
+    }
+
+    /// Get the base URL of the registry.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// Get the URL of the index of a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin name is invalid.
+    pub fn index_url(&self, name: &str) -> orbis_core::Result<url::Url> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(orbis_core::Error::validation(format!("Invalid plugin name '{}'", name)));
+        }
+        self.url
+            .join(&format!("plugins/{}.json", name))
+            .map_err(|e| orbis_core::Error::validation(e.to_string()))
+    }
+
+    /// Fetch the published versions of a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry does not publish the plugin or cannot
+    /// be reached.
+    pub async fn index(&self, name: &str) -> orbis_core::Result<RegistryIndex> {
+        let url = self.index_url(name)?;
+        let response = self.client.get(url.clone()).send().await.map_err(http_error)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(orbis_core::Error::not_found(format!("Plugin '{}' is not in the registry", name)));
+        }
+        if !response.status().is_success() {
+            return Err(orbis_core::Error::plugin(format!("Registry responded {} for {}", response.status(), url)));
+        }
+        if response.content_length().is_some_and(|len| len > MAX_INDEX_SIZE as u64) {
+            return Err(orbis_core::Error::plugin(format!("Index of plugin '{}' is too large", name)));
+        }
+
+        let body = response.bytes().await.map_err(http_error)?;
+        if body.len() > MAX_INDEX_SIZE {
+            return Err(orbis_core::Error::plugin(format!("Index of plugin '{}' is too large", name)));
+        }
+        let index: RegistryIndex = serde_json::from_slice(&body)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid index of plugin '{}': {}", name, e)))?;
+        if index.name != name {
+            return Err(orbis_core::Error::plugin(format!(
+                "Registry returned the index of '{}' for plugin '{}'",
+                index.name, name
+            )));
+        }
+
+        Ok(index)
+    }
+
+    /// Resolve a plugin to the newest version matching a requirement and the
+    /// remote source of its file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be fetched, no version matches or
+    /// the version's source is invalid.
+    pub async fn resolve(&self, name: &str, requirement: Option<&str>) -> orbis_core::Result<(String, RemoteSource)> {
+        let index = self.index(name).await?;
+        let version = index.resolve(requirement)?;
+        let source = version.source(&self.index_url(name)?)?;
+        Ok((version.version.clone(), source))
+    }
+}
+
+/// Parse the base URL of a registry.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid, or not HTTPS outside the loopback address.
+pub fn registry_url(url: &str) -> orbis_core::Result<url::Url> {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return Err(orbis_core::Error::validation(format!("Invalid registry URL '{}'", url)));
+    };
+
+    let loopback = match parsed.host() {
+        Some(url::Host::Domain(host)) => host == "localhost",
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    if parsed.scheme() != "https" && !(parsed.scheme() == "http" && loopback) {
+        return Err(orbis_core::Error::validation(format!("Registry URL '{}' must use HTTPS", url)));
+    }
+
+    // Without a trailing '/', joining paths would replace the last segment
+    if !parsed.path().ends_with('/') {
+        parsed.set_path(&format!("{}/", parsed.path()));
+    }
+    Ok(parsed)
+}
+
+/// Convert an HTTP client error.
+fn http_error(error: reqwest::Error) -> orbis_core::Error {
+    orbis_core::Error::plugin(format!("Registry request failed: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    const HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+    /// Create a published version.
+    fn version(version: &str, yanked: bool) -> RegistryVersion {
+        RegistryVersion {
+            version: version.into(),
+            url: format!("files/my-plugin-{}.zip", version),
+            mirrors: Vec::new(),
+            sha256: HASH.into(),
+            file_name: None,
+            yanked,
+        }
+    }
+
+    /// Create an index of versions.
+    fn index(versions: Vec<RegistryVersion>) -> RegistryIndex {
+        RegistryIndex {
+            name: "my-plugin".into(),
+            versions,
+        }
+    }
+
+    #[test]
+    fn test_registry_url() {
+        let url = registry_url("https://plugins.example.com/orbis").expect("https");
+        assert_eq!(url.as_str(), "https://plugins.example.com/orbis/");
+        assert!(registry_url("http://127.0.0.1:8080").is_ok());
+        assert!(registry_url("http://localhost/registry/").is_ok());
+        assert!(registry_url("http://plugins.example.com").is_err());
+        assert!(registry_url("file:///srv/registry").is_err());
+        assert!(registry_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_resolve_picks_newest_match() {
+        let index = index(vec![
+            version("1.0.0", false),
+            version("1.4.2", false),
+            version("1.5.0", true),
+            version("2.0.0-beta.1", false),
+            version("not-semver", false),
+            version("2.1.0", false),
+        ]);
+
+        assert_eq!(index.resolve(None).expect("newest").version, "2.1.0");
+        assert_eq!(index.resolve(Some("^1")).expect("newest 1.x").version, "1.4.2");
+        assert_eq!(index.resolve(Some("=1.0.0")).expect("exact").version, "1.0.0");
+        assert!(index.resolve(Some("=1.5.0")).is_err());
+        assert!(index.resolve(Some("^3")).is_err());
+        assert!(index.resolve(Some("not a requirement")).is_err());
+    }
+
+    #[test]
+    fn test_source_resolves_relative_urls() {
+        let client = RegistryClient::new("https://plugins.example.com/orbis").expect("client");
+        let index_url = client.index_url("my-plugin").expect("index url");
+        assert_eq!(index_url.as_str(), "https://plugins.example.com/orbis/plugins/my-plugin.json");
+        assert!(client.index_url("../secrets").is_err());
+
+        let mut published = version("1.0.0", false);
+        published.mirrors.push("https://mirror.example.com/my-plugin-1.0.0.zip".into());
+        let source = published.source(&index_url).expect("source");
+        assert_eq!(source.url, "https://plugins.example.com/orbis/plugins/files/my-plugin-1.0.0.zip");
+        assert_eq!(source.mirrors, vec!["https://mirror.example.com/my-plugin-1.0.0.zip".to_string()]);
+        assert_eq!(source.file_name().expect("file name"), "my-plugin-1.0.0.zip");
+
+        published.sha256 = "abc".into();
+        assert!(published.source(&index_url).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_fetches_index() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let base = format!("http://{}/registry", listener.local_addr().expect("address"));
+        let body = serde_json::to_string(&index(vec![version("1.0.0", false), version("1.1.0", false)]))
+            .expect("serialize index");
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = vec![0u8; 4096];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(buffer.get(..read).unwrap_or_default()).into_owned();
+                let (status, body) = if request.starts_with("GET /registry/plugins/my-plugin.json ") {
+                    ("200 OK", body.as_str())
+                } else {
+                    ("404 Not Found", "")
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body.as_bytes()).await;
+            }
+        });
+
+        let client = RegistryClient::new(&base).expect("client");
+        let (version, source) = client.resolve("my-plugin", Some("~1.0")).await.expect("resolve");
+        assert_eq!(version, "1.0.0");
+        assert_eq!(source.url, format!("{}/plugins/files/my-plugin-1.0.0.zip", base));
+
+        let error = client.index("other-plugin").await.expect_err("not published");
+        assert!(matches!(error, orbis_core::Error::NotFound(_)), "{}", error);
+    }
+}
@@ -0,0 +1,173 @@
+//! Hot reload lifecycle events.
+//!
+//! The watcher and the plugin manager report each step of a hot reload
+//! (change detected, waiting for the change to settle, reloading, and the
+//! outcome) on a broadcast channel, so the desktop app and the server can
+//! show reload status and errors to plugin developers.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::PluginChangeKind;
+
+/// Capacity of the reload event channel.
+const RELOAD_CHANNEL_CAPACITY: usize = 64;
+
+/// Step of a hot reload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ReloadStage {
+    /// A file of a plugin changed.
+    ChangeDetected {
+        /// Kind of change.
+        kind: PluginChangeKind,
+    },
+
+    /// The changed file is still being written; the reload waits until its
+    /// size settles.
+    Debouncing,
+
+    /// The plugin is being reloaded.
+    Reloading,
+
+    /// The plugin was reloaded (or loaded, if it is new).
+    Reloaded {
+        /// Version now running.
+        version: String,
+
+        /// Time spent reloading, in milliseconds.
+        duration_ms: u64,
+    },
+
+    /// The plugin was unloaded because its files were removed.
+    Unloaded,
+
+    /// The reload failed; the plugin is not loaded.
+    ReloadFailed {
+        /// Error message.
+        error: String,
+    },
+}
+
+/// A hot reload lifecycle event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadEvent {
+    /// Step of the reload.
+    #[serde(flatten)]
+    pub stage: ReloadStage,
+
+    /// Plugin name, or the ID derived from the path if the plugin is not
+    /// loaded.
+    pub plugin: Option<String>,
+
+    /// Path that changed.
+    pub path: PathBuf,
+
+    /// When the event happened.
+    pub at: DateTime<Utc>,
+}
+
+impl ReloadEvent {
+    /// Create an event happening now.
+    #[must_use]
+    pub fn new(stage: ReloadStage, plugin: Option<String>, path: PathBuf) -> Self {
+        Self {
+            stage,
+            plugin,
+            path,
+            at: Utc::now(),
+        }
+    }
+}
+
+/// Broadcast channel of reload events.
+#[derive(Debug, Clone)]
+pub struct ReloadEvents {
+    /// Event sender.
+    sender: broadcast::Sender<ReloadEvent>,
+
+    /// Most recent event, for status reporting.
+    last: Arc<RwLock<Option<ReloadEvent>>>,
+}
+
+impl ReloadEvents {
+    /// Create a channel without subscribers.
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            last: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Subscribe to reload events.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<ReloadEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Get the most recent event, if any.
+    #[must_use]
+    pub fn last(&self) -> Option<ReloadEvent> {
+        self.last.read().clone()
+    }
+
+    /// Publish an event; events are dropped when nobody is subscribed.
+    pub fn emit(&self, event: ReloadEvent) {
+        *self.last.write() = Some(event.clone());
+        if self.sender.send(event).is_err() {
+            tracing::trace!("No reload event subscribers");
+        }
+    }
+}
+
+impl Default for ReloadEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let event = ReloadEvent::new(
+            ReloadStage::ReloadFailed {
+                error: "Invalid manifest".to_owned(),
+            },
+            Some("hello-plugin".to_owned()),
+            PathBuf::from("plugins/hello-plugin/plugin.wasm"),
+        );
+
+        let value = serde_json::to_value(&event).expect("serializable");
+        assert_eq!(value["stage"], "reload_failed");
+        assert_eq!(value["error"], "Invalid manifest");
+        assert_eq!(value["plugin"], "hello-plugin");
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events() {
+        let events = ReloadEvents::new();
+        let mut rx = events.subscribe();
+
+        events.emit(ReloadEvent::new(ReloadStage::Reloading, None, PathBuf::from("plugin.wasm")));
+        let event = rx.recv().await.expect("event");
+        assert!(matches!(event.stage, ReloadStage::Reloading));
+    }
+
+    #[test]
+    fn test_last_event_is_kept_without_subscribers() {
+        let events = ReloadEvents::new();
+        assert!(events.last().is_none());
+
+        events.emit(ReloadEvent::new(ReloadStage::Unloaded, None, PathBuf::from("plugin.wasm")));
+        assert!(matches!(events.last().map(|event| event.stage), Some(ReloadStage::Unloaded)));
+    }
+}
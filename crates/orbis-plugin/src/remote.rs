@@ -0,0 +1,401 @@
+//! Fetching plugins from remote URLs.
+//!
+//! A remote plugin is identified by the SHA-256 hash of its file. The file
+//! is downloaded from its primary URL, or from the first mirror that serves
+//! it, into a cache keyed by that hash, so a plugin is only fetched once.
+//! Interrupted downloads are resumed with HTTP range requests, from any
+//! mirror, since every mirror serves the same bytes. A download whose hash
+//! does not match is discarded.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Name of the directory of fetched plugins, inside the plugins directory.
+pub const REMOTE_CACHE_DIR: &str = ".cache";
+
+/// Time allowed to connect to a server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extension of partially downloaded files.
+const PARTIAL_EXTENSION: &str = "part";
+
+/// A plugin file published at a URL and its mirrors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteSource {
+    /// Primary URL of the plugin file.
+    pub url: String,
+
+    /// Mirrors of the plugin file, tried in order when the primary URL fails.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+
+    /// SHA-256 hash of the plugin file, as hex.
+    pub sha256: String,
+
+    /// File name to install the plugin as, ending in `.wasm` or `.zip`.
+    ///
+    /// Defaults to the last segment of the primary URL.
+    #[serde(default)]
+    pub file_name: Option<String>,
+}
+
+impl RemoteSource {
+    /// Create a remote source without mirrors.
+    #[must_use]
+    pub fn new(url: impl Into<String>, sha256: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            mirrors: Vec::new(),
+            sha256: sha256.into(),
+            file_name: None,
+        }
+    }
+
+    /// Add a mirror.
+    #[must_use]
+    pub fn with_mirror(mut self, url: impl Into<String>) -> Self {
+        self.mirrors.push(url.into());
+        self
+    }
+
+    /// Get the primary URL followed by the mirrors.
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+    }
+
+    /// Get the file name to install the plugin as.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no file name is set and the primary URL does not
+    /// end in one.
+    pub fn file_name(&self) -> orbis_core::Result<String> {
+        if let Some(name) = &self.file_name {
+            return Ok(name.clone());
+        }
+
+        url::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.path_segments()?.next_back().map(String::from))
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| orbis_core::Error::validation(format!("Cannot determine a file name from {}", self.url)))
+    }
+
+    /// Check the hash, URLs and file name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid field.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.sha256.len() != 64 || !self.sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(orbis_core::Error::validation("sha256 must be 64 hexadecimal characters"));
+        }
+
+        for url in self.urls() {
+            let scheme = url::Url::parse(url).map(|url| url.scheme().to_owned());
+            if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+                return Err(orbis_core::Error::validation(format!("Invalid plugin URL: {}", url)));
+            }
+        }
+
+        let file_name = self.file_name()?;
+        let path = Path::new(&file_name);
+        let is_plain = path.file_name().is_some_and(|name| name == path.as_os_str());
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        if !is_plain || !matches!(extension, Some("wasm" | "zip")) {
+            return Err(orbis_core::Error::validation(format!(
+                "Invalid plugin file name '{}'. Expected a .wasm or .zip file",
+                file_name
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Downloads remote plugins into a cache keyed by content hash.
+#[derive(Debug, Clone)]
+pub struct RemoteFetcher {
+    /// Directory of fetched plugins.
+    cache_dir: PathBuf,
+
+    /// Maximum size of a plugin file, in bytes.
+    max_size: u64,
+
+    /// HTTP client.
+    client: reqwest::Client,
+}
+
+impl RemoteFetcher {
+    /// Create a fetcher caching plugins in a directory.
+    #[must_use]
+    pub fn new(cache_dir: PathBuf) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .user_agent(concat!("orbis/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            cache_dir,
+            max_size: crate::DEFAULT_MAX_ARCHIVE_SIZE,
+            client,
+        }
+    }
+
+    /// Set the maximum size of a plugin file.
+    #[must_use]
+    pub const fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Get the directory of fetched plugins.
+    #[must_use]
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Get the cached path of a plugin file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is invalid.
+    pub fn cached_path(&self, source: &RemoteSource) -> orbis_core::Result<PathBuf> {
+        source.validate()?;
+        let file_name = source.file_name()?;
+        let extension = Path::new(&file_name).extension().and_then(|ext| ext.to_str()).unwrap_or("wasm");
+        Ok(self.cache_dir.join(format!("{}.{}", source.sha256.to_lowercase(), extension)))
+    }
+
+    /// Fetch a plugin file, returning its path in the cache.
+    ///
+    /// A cached file with the expected hash is returned without fetching it
+    /// again. Otherwise the primary URL and the mirrors are tried in order
+    /// until one serves a file with the expected hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is invalid or no URL serves the file.
+    pub async fn fetch(&self, source: &RemoteSource) -> orbis_core::Result<PathBuf> {
+        let cached = self.cached_path(source)?;
+        let expected = source.sha256.to_lowercase();
+
+        if cached.exists() {
+            if hash_file(&cached)? == expected {
+                tracing::debug!("Using cached plugin {}", cached.display());
+                return Ok(cached);
+            }
+            tracing::warn!("Discarding cached plugin {} with a wrong hash", cached.display());
+            std::fs::remove_file(&cached)?;
+        }
+
+        std::fs::create_dir_all(&self.cache_dir)?;
+        let mut partial = cached.clone().into_os_string();
+        partial.push(format!(".{}", PARTIAL_EXTENSION));
+        let partial = PathBuf::from(partial);
+
+        let mut failures = Vec::new();
+        for url in source.urls() {
+            // A failed download is kept, so the next mirror resumes it
+            if let Err(e) = self.download(url, &partial).await {
+                tracing::warn!("Failed to fetch plugin from {}: {}", url, e);
+                failures.push(format!("{}: {}", url, e));
+                continue;
+            }
+
+            let hash = hash_file(&partial)?;
+            if hash == expected {
+                std::fs::rename(&partial, &cached)?;
+                tracing::info!("Fetched plugin from {}", url);
+                return Ok(cached);
+            }
+
+            tracing::warn!("Plugin fetched from {} has hash {}, expected {}", url, hash, expected);
+            failures.push(format!("{}: content hash {} does not match", url, hash));
+            std::fs::remove_file(&partial)?;
+        }
+
+        Err(orbis_core::Error::plugin(format!(
+            "Failed to fetch plugin {}: {}",
+            source.file_name()?,
+            failures.join("; ")
+        )))
+    }
+
+    /// Download a URL into a file, resuming from the bytes already in it.
+    async fn download(&self, url: &str, path: &Path) -> orbis_core::Result<()> {
+        let offset = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await.map_err(http_error)?;
+
+        // The file is already complete; its hash tells whether it is right
+        if offset > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(());
+        }
+        if !response.status().is_success() {
+            return Err(orbis_core::Error::plugin(format!("server responded {}", response.status())));
+        }
+
+        // Servers ignoring the range send the whole file again
+        let resumed = offset > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let mut written = if resumed { offset } else { 0 };
+        if resumed {
+            tracing::debug!("Resuming download of {} at byte {}", url, offset);
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(path)
+            .await?;
+        while let Some(chunk) = response.chunk().await.map_err(http_error)? {
+            written = written.saturating_add(chunk.len() as u64);
+            if written > self.max_size {
+                drop(file);
+                if let Err(e) = std::fs::remove_file(path) {
+                    tracing::warn!("Failed to remove partial download {}: {}", path.display(), e);
+                }
+                return Err(orbis_core::Error::plugin(format!("plugin is larger than {} bytes", self.max_size)));
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+
+        Ok(())
+    }
+}
+
+/// Convert an HTTP client error.
+fn http_error(error: reqwest::Error) -> orbis_core::Error {
+    orbis_core::Error::plugin(error.to_string())
+}
+
+/// Hash a file with SHA-256, as lowercase hex.
+fn hash_file(path: &Path) -> orbis_core::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+
+    /// Serve a file over HTTP, honouring range requests, and count requests.
+    async fn serve(contents: &'static [u8]) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}/plugin.wasm", listener.local_addr().expect("address"));
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buffer = vec![0_u8; 4096];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(buffer.get(..read).unwrap_or_default()).to_lowercase();
+
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+                let (status, body) = match start {
+                    Some(start) => ("206 Partial Content", contents.get(start..).unwrap_or_default()),
+                    None => ("200 OK", contents),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body).await;
+            }
+        });
+
+        (url, requests)
+    }
+
+    /// Create a fetcher with an empty cache.
+    fn fetcher() -> RemoteFetcher {
+        RemoteFetcher::new(std::env::temp_dir().join(format!("orbis-remote-{}", uuid::Uuid::new_v4())))
+    }
+
+    /// Hash bytes with SHA-256, as hex.
+    fn sha256(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn test_validate() {
+        let hash = sha256(b"plugin");
+        assert!(RemoteSource::new("https://example.com/plugins/my-plugin.wasm", &hash).validate().is_ok());
+        assert!(RemoteSource::new("https://example.com/plugins/my-plugin.wasm", "abc").validate().is_err());
+        assert!(RemoteSource::new("ftp://example.com/my-plugin.wasm", &hash).validate().is_err());
+        assert!(RemoteSource::new("https://example.com/my-plugin.exe", &hash).validate().is_err());
+        assert!(RemoteSource::new("https://example.com/my-plugin.wasm", &hash)
+            .with_mirror("file:///tmp/my-plugin.wasm")
+            .validate()
+            .is_err());
+
+        let mut source = RemoteSource::new("https://example.com/download?id=1", &hash);
+        assert!(source.validate().is_err());
+        source.file_name = Some("my-plugin.zip".into());
+        assert!(source.validate().is_ok());
+        source.file_name = Some("../my-plugin.zip".into());
+        assert!(source.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_falls_back_to_mirror_and_caches() {
+        const CONTENTS: &[u8] = b"\0asm plugin contents";
+        let (url, requests) = serve(CONTENTS).await;
+        let fetcher = fetcher();
+
+        // Nothing listens on port 9 of the loopback address
+        let source = RemoteSource::new("http://127.0.0.1:9/plugin.wasm", sha256(CONTENTS)).with_mirror(&url);
+        let path = fetcher.fetch(&source).await.expect("fetch from mirror");
+        assert_eq!(std::fs::read(&path).expect("cached file"), CONTENTS);
+
+        fetcher.fetch(&source).await.expect("fetch from cache");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let _ = std::fs::remove_dir_all(fetcher.cache_dir());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_resumes_and_checks_hash() {
+        const CONTENTS: &[u8] = b"\0asm plugin contents";
+        let (url, _) = serve(CONTENTS).await;
+        let fetcher = fetcher();
+
+        let source = RemoteSource::new(&url, sha256(CONTENTS));
+        let cached = fetcher.cached_path(&source).expect("cached path");
+        std::fs::create_dir_all(fetcher.cache_dir()).expect("create cache");
+        let partial = cached.with_extension(format!("wasm.{}", PARTIAL_EXTENSION));
+        std::fs::write(&partial, CONTENTS.get(..5).unwrap_or_default()).expect("write partial");
+
+        let path = fetcher.fetch(&source).await.expect("resume");
+        assert_eq!(std::fs::read(&path).expect("cached file"), CONTENTS);
+        assert!(!partial.exists());
+
+        let wrong = RemoteSource::new(&url, sha256(b"other contents"));
+        let error = fetcher.fetch(&wrong).await.expect_err("hash mismatch");
+        assert!(error.to_string().contains("does not match"));
+
+        let _ = std::fs::remove_dir_all(fetcher.cache_dir());
+    }
+}
@@ -0,0 +1,595 @@
+//! Deterministic replay of plugin handlers.
+//!
+//! While a handler is recorded, each host function call it makes is added
+//! to a [`ReplayRecording`] with the arguments it read from plugin memory and
+//! what the host answered, and the recording is saved as a replay file.
+//!
+//! Replaying the file runs the handler again with the recorded context,
+//! answering each host call from the recording instead of the host. The
+//! handler sees exactly what it saw when recorded, without reading or
+//! changing state, the database, files or the network, so bugs seen in
+//! production can be reproduced locally. A call the recording does not have
+//! next, e.g. after the plugin changed, fails and ends the replay's
+//! reproduction.
+//!
+//! Credential headers are left out of recorded contexts, and only core
+//! module plugins are recorded.
+
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::capture::redacted;
+use crate::PluginContext;
+
+/// Format version of replay files.
+pub const REPLAY_FORMAT_VERSION: u32 = 1;
+
+/// Bytes exchanged with a host function, base64-encoded in replay files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayBytes(pub Vec<u8>);
+
+impl Serialize for ReplayBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ReplayBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A host function call made by a recorded handler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    /// Host function name.
+    pub function: String,
+
+    /// Arguments read from plugin memory, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input: Vec<ReplayBytes>,
+
+    /// Scalar arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<i64>,
+
+    /// Value returned to the plugin, unless the result was written to its memory.
+    #[serde(default)]
+    pub value: i64,
+
+    /// Result written to plugin memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<ReplayBytes>,
+
+    /// Error the call failed with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A recorded handler invocation, saved as a replay file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRecording {
+    /// Replay file format version.
+    pub version: u32,
+
+    /// Recording ID.
+    pub id: Uuid,
+
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler name.
+    pub handler: String,
+
+    /// Context the handler was invoked with, without credential headers.
+    pub context: PluginContext,
+
+    /// Host function calls, in the order the handler made them.
+    pub calls: Vec<RecordedCall>,
+
+    /// Handler result, if it succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+
+    /// Handler error, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// When the handler was invoked.
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl ReplayRecording {
+    /// Create a recording of a handler invocation and its outcome.
+    #[must_use]
+    pub fn new(
+        plugin: &str,
+        handler: &str,
+        context: &PluginContext,
+        calls: Vec<RecordedCall>,
+        outcome: &orbis_core::Result<serde_json::Value>,
+    ) -> Self {
+        let (result, error) = split_outcome(outcome);
+        Self {
+            version: REPLAY_FORMAT_VERSION,
+            id: orbis_core::new_id(),
+            plugin: plugin.to_owned(),
+            handler: handler.to_owned(),
+            context: redacted(context),
+            calls,
+            result,
+            error,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    /// Load a replay file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not a replay file, or
+    /// has a format version this host does not replay.
+    pub fn load(path: &Path) -> orbis_core::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let recording: Self = serde_json::from_slice(&bytes).map_err(|e| {
+            orbis_core::Error::validation(format!("Invalid replay file {}: {}", path.display(), e))
+        })?;
+        if recording.version != REPLAY_FORMAT_VERSION {
+            return Err(orbis_core::Error::validation(format!(
+                "Replay file {} has format version {}; this host replays version {}",
+                path.display(),
+                recording.version,
+                REPLAY_FORMAT_VERSION
+            )));
+        }
+        Ok(recording)
+    }
+
+    /// Save the recording to a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> orbis_core::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Outcome of replaying a recording.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayOutcome {
+    /// Handler result, if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+
+    /// Handler error, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Whether the handler made the recorded calls and ended as recorded.
+    pub reproduced: bool,
+
+    /// First call the handler made that the recording does not have next.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<String>,
+
+    /// Number of recorded calls the handler made.
+    pub replayed_calls: usize,
+
+    /// Number of calls in the recording.
+    pub recorded_calls: usize,
+}
+
+impl ReplayOutcome {
+    /// Compare the outcome of a replay with its recording.
+    pub(crate) fn new(
+        recording: &ReplayRecording,
+        outcome: &orbis_core::Result<serde_json::Value>,
+        tape: Option<HostTape>,
+    ) -> Self {
+        let (result, error) = split_outcome(outcome);
+        let (replayed_calls, divergence) = match tape {
+            Some(HostTape::Replaying {
+                position, divergence, ..
+            }) => (position, divergence),
+            Some(HostTape::Recording { .. }) | None => (0, None),
+        };
+        let recorded_calls = recording.calls.len();
+        Self {
+            reproduced: divergence.is_none()
+                && replayed_calls == recorded_calls
+                && result == recording.result
+                && error == recording.error,
+            result,
+            error,
+            divergence,
+            replayed_calls,
+            recorded_calls,
+        }
+    }
+}
+
+/// Split a handler outcome into its result and error message.
+fn split_outcome(outcome: &orbis_core::Result<serde_json::Value>) -> (Option<serde_json::Value>, Option<String>) {
+    match *outcome {
+        Ok(ref value) => (Some(value.clone()), None),
+        Err(ref e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Host function calls of a handler invocation, being recorded or replayed.
+#[derive(Debug)]
+pub enum HostTape {
+    /// Calls are made against the host and recorded.
+    Recording {
+        /// Calls made so far.
+        calls: Vec<RecordedCall>,
+        /// Bytes the host wrote to plugin memory during the current call.
+        output: Option<Vec<u8>>,
+    },
+
+    /// Calls are answered from a recording.
+    Replaying {
+        /// Recorded calls not made yet.
+        calls: VecDeque<RecordedCall>,
+        /// Number of recorded calls made.
+        position: usize,
+        /// First call the recording did not have next.
+        divergence: Option<String>,
+    },
+}
+
+impl HostTape {
+    /// Start recording calls.
+    pub(crate) const fn recording() -> Self {
+        Self::Recording {
+            calls: Vec::new(),
+            output: None,
+        }
+    }
+
+    /// Start answering calls from recorded ones.
+    pub(crate) fn replaying(calls: &[RecordedCall]) -> Self {
+        Self::Replaying {
+            calls: calls.iter().cloned().collect(),
+            position: 0,
+            divergence: None,
+        }
+    }
+
+    /// Check if calls are answered from a recording.
+    pub(crate) const fn is_replaying(&self) -> bool {
+        matches!(*self, Self::Replaying { .. })
+    }
+
+    /// Note bytes the host wrote to plugin memory as the result of the
+    /// current call.
+    pub(crate) fn wrote(&mut self, bytes: &[u8]) {
+        if let Self::Recording { ref mut output, .. } = *self {
+            *output = Some(bytes.to_vec());
+        }
+    }
+
+    /// Take the bytes the host wrote during the current call.
+    pub(crate) const fn take_output(&mut self) -> Option<Vec<u8>> {
+        match *self {
+            Self::Recording { ref mut output, .. } => output.take(),
+            Self::Replaying { .. } => None,
+        }
+    }
+
+    /// Record a call.
+    pub(crate) fn record(&mut self, call: RecordedCall) {
+        if let Self::Recording { ref mut calls, .. } = *self {
+            calls.push(call);
+        }
+    }
+
+    /// Answer a call from the next recorded one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording does not have this call next, and
+    /// for every call after that.
+    pub(crate) fn next(&mut self, function: &str, input: &[Vec<u8>], args: &[i64]) -> orbis_core::Result<RecordedCall> {
+        let Self::Replaying {
+            ref mut calls,
+            ref mut position,
+            ref mut divergence,
+        } = *self
+        else {
+            return Err(orbis_core::Error::plugin("Host calls are not being replayed"));
+        };
+        if let Some(ref divergence) = *divergence {
+            return Err(orbis_core::Error::plugin(format!("Replay diverged: {}", divergence)));
+        }
+
+        let matches = calls.front().is_some_and(|call| {
+            call.function == function
+                && call.args == args
+                && call.input.iter().map(|bytes| bytes.0.as_slice()).eq(input.iter().map(Vec::as_slice))
+        });
+        let Some(call) = calls.pop_front().filter(|_| matches) else {
+            let found = divergence.insert(format!(
+                "call {} to '{}' does not match the recording",
+                position.saturating_add(1),
+                function
+            ));
+            return Err(orbis_core::Error::plugin(format!("Replay diverged: {}", found)));
+        };
+        *position = position.saturating_add(1);
+        Ok(call)
+    }
+
+    /// Get the recorded calls.
+    pub(crate) fn into_calls(self) -> Vec<RecordedCall> {
+        match self {
+            Self::Recording { calls, .. } => calls,
+            Self::Replaying { calls, .. } => calls.into(),
+        }
+    }
+}
+
+/// Value a host function returns to the plugin, as recorded.
+pub trait TapeValue: Sized {
+    /// Get the recorded value.
+    fn to_raw(&self) -> i64;
+
+    /// Get the value from its recorded form.
+    fn from_raw(raw: i64) -> Self;
+}
+
+impl TapeValue for () {
+    fn to_raw(&self) -> i64 {
+        0
+    }
+
+    fn from_raw(_raw: i64) -> Self {}
+}
+
+impl TapeValue for u32 {
+    fn to_raw(&self) -> i64 {
+        i64::from(*self)
+    }
+
+    fn from_raw(raw: i64) -> Self {
+        Self::try_from(raw).unwrap_or_default()
+    }
+}
+
+impl TapeValue for u64 {
+    fn to_raw(&self) -> i64 {
+        i64::try_from(*self).unwrap_or(i64::MAX)
+    }
+
+    fn from_raw(raw: i64) -> Self {
+        Self::try_from(raw).unwrap_or_default()
+    }
+}
+
+/// Handlers whose invocations are recorded, and where their replay files go.
+///
+/// Each recorded handler, or plugin for all its handlers, has a number of
+/// invocations left to record, so recording in production stays bounded.
+#[derive(Debug)]
+pub struct ReplayCapture {
+    /// Directory replay files are saved in, by plugin.
+    dir: PathBuf,
+
+    /// Invocations left to record by plugin and handler (`None` for all
+    /// handlers of the plugin).
+    targets: DashMap<(String, Option<String>), usize>,
+}
+
+impl ReplayCapture {
+    /// Create a capture saving replay files under a directory.
+    #[must_use]
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            targets: DashMap::new(),
+        }
+    }
+
+    /// Get the directory replay files are saved in.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record the next invocations of a plugin's handler, or of all its
+    /// handlers.
+    pub fn record(&self, plugin: &str, handler: Option<&str>, invocations: usize) {
+        let key = (plugin.to_owned(), handler.map(str::to_owned));
+        if invocations == 0 {
+            self.targets.remove(&key);
+        } else {
+            self.targets.insert(key, invocations);
+        }
+    }
+
+    /// Stop recording a plugin's handlers.
+    pub fn stop(&self, plugin: &str) {
+        self.targets.retain(|key, _| key.0 != plugin);
+    }
+
+    /// Get the recorded handlers, with the invocations left to record.
+    #[must_use]
+    pub fn targets(&self) -> Vec<(String, Option<String>, usize)> {
+        let mut targets: Vec<_> = self
+            .targets
+            .iter()
+            .map(|entry| (entry.key().0.clone(), entry.key().1.clone(), *entry.value()))
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    /// Check if an invocation of a handler is recorded, counting it if so.
+    pub(crate) fn take(&self, plugin: &str, handler: &str) -> bool {
+        [Some(handler.to_owned()), None].into_iter().any(|handler| {
+            let key = (plugin.to_owned(), handler);
+            let Some(mut left) = self.targets.get_mut(&key) else {
+                return false;
+            };
+            *left = left.saturating_sub(1);
+            let done = *left == 0;
+            drop(left);
+            if done {
+                self.targets.remove_if(&key, |_, left| *left == 0);
+            }
+            true
+        })
+    }
+
+    /// Save a recording, returning the path of its replay file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, recording: &ReplayRecording) -> orbis_core::Result<PathBuf> {
+        let path = self
+            .dir
+            .join(file_name(&recording.plugin))
+            .join(format!("{}-{}.json", file_name(&recording.handler), recording.id));
+        recording.save(&path)?;
+        Ok(path)
+    }
+}
+
+/// Get a name safe to use as a file name.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(function: &str, input: &[u8], output: Option<&[u8]>) -> RecordedCall {
+        RecordedCall {
+            function: function.to_string(),
+            input: vec![ReplayBytes(input.to_vec())],
+            args: Vec::new(),
+            value: 0,
+            output: output.map(|bytes| ReplayBytes(bytes.to_vec())),
+            error: None,
+        }
+    }
+
+    fn context() -> PluginContext {
+        PluginContext {
+            method: "GET".to_string(),
+            path: "/notes".to_string(),
+            headers: [("authorization".to_string(), "Bearer secret".to_string())]
+                .into_iter()
+                .collect(),
+            query: std::collections::HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: Some("user".to_string()),
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: crate::CancellationFlag::new(),
+        }
+    }
+
+    #[test]
+    fn test_replay_answers_recorded_calls() {
+        let mut tape = HostTape::recording();
+        tape.wrote(b"\"draft\"");
+        let output = tape.take_output();
+        assert_eq!(output.as_deref(), Some(&b"\"draft\""[..]));
+        tape.record(call("state_get", b"status", output.as_deref()));
+        tape.record(call("db_execute", b"DELETE FROM notes", None));
+        assert!(tape.take_output().is_none());
+        let calls = tape.into_calls();
+
+        let mut tape = HostTape::replaying(&calls);
+        assert!(tape.is_replaying());
+        let answered = tape.next("state_get", &[b"status".to_vec()], &[]).unwrap();
+        assert_eq!(answered.output, Some(ReplayBytes(b"\"draft\"".to_vec())));
+
+        let recording = ReplayRecording::new("notes", "list", &context(), calls, &Ok(serde_json::json!([])));
+        let outcome = ReplayOutcome::new(&recording, &Ok(serde_json::json!([])), Some(tape));
+        assert!(!outcome.reproduced);
+        assert_eq!((outcome.replayed_calls, outcome.recorded_calls), (1, 2));
+    }
+
+    #[test]
+    fn test_replay_diverges_on_other_calls() {
+        let calls = vec![call("state_get", b"status", None), call("http_request", b"GET", None)];
+        let mut tape = HostTape::replaying(&calls);
+
+        tape.next("state_get", &[b"other".to_vec()], &[]).unwrap_err();
+        // Once diverged, later calls fail too, even if they were recorded
+        tape.next("http_request", &[b"GET".to_vec()], &[]).unwrap_err();
+
+        let recording = ReplayRecording::new("notes", "list", &context(), calls, &Ok(serde_json::Value::Null));
+        let outcome = ReplayOutcome::new(&recording, &Ok(serde_json::Value::Null), Some(tape));
+        assert!(!outcome.reproduced);
+        assert_eq!(outcome.replayed_calls, 0);
+        assert_eq!(
+            outcome.divergence.as_deref(),
+            Some("call 1 to 'state_get' does not match the recording")
+        );
+    }
+
+    #[test]
+    fn test_capture_saves_replay_files() {
+        let dir = std::env::temp_dir().join(format!("orbis-replay-{}", Uuid::new_v4()));
+        let capture = ReplayCapture::new(dir.clone());
+        capture.record("notes", Some("list"), 2);
+        capture.record("tasks", None, 1);
+        assert!(!capture.take("notes", "create"));
+        assert!(capture.take("notes", "list"));
+        assert!(capture.take("tasks", "any"));
+        assert!(!capture.take("tasks", "any"));
+        assert_eq!(capture.targets(), [("notes".to_string(), Some("list".to_string()), 1)]);
+        capture.stop("notes");
+        assert!(!capture.take("notes", "list"));
+
+        let mut output = call("crypto_random", b"", Some(&[0, 159, 255]));
+        output.args = vec![3];
+        let recording = ReplayRecording::new(
+            "notes",
+            "../list",
+            &context(),
+            vec![output],
+            &Err(orbis_core::Error::plugin("failed")),
+        );
+        let path = capture.save(&recording).unwrap();
+        assert!(path.starts_with(dir.join("notes")));
+        assert!(path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("___list-")));
+
+        let loaded = ReplayRecording::load(&path).unwrap();
+        assert_eq!(loaded.calls, recording.calls);
+        assert_eq!(loaded.error, recording.error);
+        assert!(!loaded.context.headers.contains_key("authorization"));
+
+        let mut future = loaded;
+        future.version = REPLAY_FORMAT_VERSION + 1;
+        future.save(&path).unwrap();
+        ReplayRecording::load(&path).unwrap_err();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+//! Dependency resolution for plugin load order.
+
+use orbis_plugin_api::PluginManifest;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Why a plugin's dependencies cannot be satisfied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DependencyIssue {
+    /// A required dependency is not installed.
+    Missing {
+        /// Dependency name.
+        dependency: String,
+        /// Version requirement declared by the plugin.
+        requirement: String,
+    },
+
+    /// An installed dependency's version does not satisfy the requirement.
+    Incompatible {
+        /// Dependency name.
+        dependency: String,
+        /// Version requirement declared by the plugin.
+        requirement: String,
+        /// Installed version of the dependency.
+        found: String,
+    },
+
+    /// The declared requirement is not a valid semver range.
+    InvalidRequirement {
+        /// Dependency name.
+        dependency: String,
+        /// Version requirement declared by the plugin.
+        requirement: String,
+    },
+
+    /// The dependency is installed but could not be loaded itself.
+    Unavailable {
+        /// Dependency name.
+        dependency: String,
+    },
+
+    /// The plugin depends on itself through its dependencies.
+    Cycle,
+}
+
+impl fmt::Display for DependencyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Missing { ref dependency, ref requirement } => {
+                write!(f, "missing dependency '{dependency}' ({requirement})")
+            }
+            Self::Incompatible { ref dependency, ref requirement, ref found } => {
+                write!(f, "dependency '{dependency}' {found} does not satisfy {requirement}")
+            }
+            Self::InvalidRequirement { ref dependency, ref requirement } => {
+                write!(f, "invalid version requirement '{requirement}' for dependency '{dependency}'")
+            }
+            Self::Unavailable { ref dependency } => write!(f, "dependency '{dependency}' cannot be loaded"),
+            Self::Cycle => write!(f, "dependency cycle detected"),
+        }
+    }
+}
+
+/// Load order computed from plugin dependencies.
+#[derive(Debug, Default)]
+pub struct LoadOrder {
+    /// Groups of plugin indices; every plugin's dependencies are in earlier levels,
+    /// so plugins within a level can be loaded concurrently.
+    pub levels: Vec<Vec<usize>>,
+
+    /// Plugins that cannot be loaded, with the reasons.
+    pub unresolved: Vec<(usize, Vec<DependencyIssue>)>,
+}
+
+/// Resolve the load order of a set of plugins from their declared dependencies.
+///
+/// Required dependencies must be present with a matching version; optional
+/// dependencies only affect ordering when present, but must match too.
+#[must_use]
+pub fn resolve_load_order(manifests: &[PluginManifest]) -> LoadOrder {
+    let by_name: HashMap<&str, usize> = manifests
+        .iter()
+        .enumerate()
+        .map(|(index, manifest)| (manifest.name.as_str(), index))
+        .collect();
+    let versions: HashMap<&str, &str> = manifests
+        .iter()
+        .map(|manifest| (manifest.name.as_str(), manifest.version.as_str()))
+        .collect();
+
+    let mut order = LoadOrder::default();
+    let mut unresolved: HashSet<usize> = HashSet::new();
+
+    // Check that dependencies are present and version-compatible
+    for (index, manifest) in manifests.iter().enumerate() {
+        let issues = dependency_issues(manifest, &versions);
+        if !issues.is_empty() {
+            unresolved.insert(index);
+            order.unresolved.push((index, issues));
+        }
+    }
+
+    let mut placed: HashSet<usize> = HashSet::new();
+    let mut pending: Vec<usize> = (0..manifests.len()).filter(|i| !unresolved.contains(i)).collect();
+
+    while !pending.is_empty() {
+        let mut level = Vec::new();
+        let mut blocked = Vec::new();
+
+        for &index in &pending {
+            // Pending indices all come from `manifests`
+            let Some(manifest) = manifests.get(index) else {
+                continue;
+            };
+            let deps: Vec<usize> = manifest
+                .dependencies
+                .iter()
+                .filter_map(|dep| by_name.get(dep.name.as_str()).copied())
+                .collect();
+
+            if let Some(failed) = deps.iter().find(|dep| unresolved.contains(dep)) {
+                let dependency = manifests.get(*failed).map(|m| m.name.clone()).unwrap_or_default();
+                blocked.push((index, vec![DependencyIssue::Unavailable { dependency }]));
+            } else if deps.iter().all(|dep| placed.contains(dep)) {
+                level.push(index);
+            }
+        }
+
+        for (index, issues) in blocked {
+            unresolved.insert(index);
+            order.unresolved.push((index, issues));
+        }
+
+        if level.is_empty() {
+            // Everything left depends on something that never gets placed
+            for index in pending.iter().filter(|index| !unresolved.contains(index)) {
+                order.unresolved.push((*index, vec![DependencyIssue::Cycle]));
+            }
+            break;
+        }
+
+        placed.extend(level.iter().copied());
+        pending.retain(|index| !placed.contains(index) && !unresolved.contains(index));
+        order.levels.push(level);
+    }
+
+    order
+}
+
+/// Check a plugin's dependencies against the versions of installed plugins,
+/// keyed by plugin name.
+///
+/// Every requirement must be a valid semver range, required dependencies must be
+/// installed, and installed dependencies (optional ones included) must match
+/// their requirement.
+#[must_use]
+pub fn dependency_issues(manifest: &PluginManifest, installed: &HashMap<&str, &str>) -> Vec<DependencyIssue> {
+    manifest
+        .dependencies
+        .iter()
+        .filter_map(|dep| {
+            let dependency = dep.name.clone();
+            let requirement = dep.version.clone();
+            let Ok(range) = semver::VersionReq::parse(&dep.version) else {
+                return Some(DependencyIssue::InvalidRequirement { dependency, requirement });
+            };
+
+            let Some(found) = installed.get(dep.name.as_str()) else {
+                return (!dep.optional).then_some(DependencyIssue::Missing { dependency, requirement });
+            };
+
+            let matches = semver::Version::parse(found).is_ok_and(|version| range.matches(&version));
+            (!matches).then(|| DependencyIssue::Incompatible {
+                dependency,
+                requirement,
+                found: (*found).to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orbis_plugin_api::PluginDependency;
+
+    fn manifest(name: &str, version: &str, deps: &[(&str, &str)]) -> PluginManifest {
+        with_deps(name, version, deps, false)
+    }
+
+    fn with_deps(name: &str, version: &str, deps: &[(&str, &str)], optional: bool) -> PluginManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": version,
+            "dependencies": deps
+                .iter()
+                .map(|(name, version)| PluginDependency {
+                    name: (*name).to_string(),
+                    version: (*version).to_string(),
+                    optional,
+                })
+                .collect::<Vec<_>>(),
+        }))
+        .expect("valid manifest")
+    }
+
+    #[test]
+    fn test_resolve_levels() {
+        let manifests = vec![
+            manifest("app", "1.0.0", &[("core", "^1.0"), ("ui", "^1.0")]),
+            manifest("core", "1.2.0", &[]),
+            manifest("ui", "1.0.0", &[("core", "^1.0")]),
+            manifest("standalone", "1.0.0", &[]),
+        ];
+
+        let order = resolve_load_order(&manifests);
+        assert!(order.unresolved.is_empty());
+        assert_eq!(order.levels, vec![vec![1, 3], vec![2], vec![0]]);
+    }
+
+    #[test]
+    fn test_resolve_unresolvable() {
+        let manifests = vec![
+            manifest("a", "1.0.0", &[("missing", "*")]),
+            manifest("b", "1.0.0", &[("a", "*")]),
+            manifest("c", "1.0.0", &[("core", "^2.0")]),
+            manifest("core", "1.0.0", &[]),
+            manifest("x", "1.0.0", &[("y", "*")]),
+            manifest("y", "1.0.0", &[("x", "*")]),
+        ];
+
+        let order = resolve_load_order(&manifests);
+        assert_eq!(order.levels, vec![vec![3]]);
+
+        let unresolved: HashSet<usize> = order.unresolved.iter().map(|(index, _)| *index).collect();
+        assert_eq!(unresolved, HashSet::from([0, 1, 2, 4, 5]));
+    }
+
+    #[test]
+    fn test_resolve_issues() {
+        let manifests = vec![
+            manifest("a", "1.0.0", &[("missing", "^1.0")]),
+            manifest("b", "1.0.0", &[("a", "*")]),
+            manifest("c", "1.0.0", &[("core", "^2.0")]),
+            manifest("core", "1.0.0", &[]),
+            manifest("d", "1.0.0", &[("core", "not a range")]),
+        ];
+
+        let order = resolve_load_order(&manifests);
+        let issues: HashMap<usize, Vec<DependencyIssue>> = order.unresolved.into_iter().collect();
+        assert_eq!(
+            issues.get(&0),
+            Some(&vec![DependencyIssue::Missing {
+                dependency: "missing".to_string(),
+                requirement: "^1.0".to_string(),
+            }])
+        );
+        assert_eq!(
+            issues.get(&1),
+            Some(&vec![DependencyIssue::Unavailable { dependency: "a".to_string() }])
+        );
+        assert_eq!(
+            issues.get(&2),
+            Some(&vec![DependencyIssue::Incompatible {
+                dependency: "core".to_string(),
+                requirement: "^2.0".to_string(),
+                found: "1.0.0".to_string(),
+            }])
+        );
+        assert!(matches!(
+            issues.get(&4).map(Vec::as_slice),
+            Some([DependencyIssue::InvalidRequirement { .. }])
+        ));
+    }
+
+    #[test]
+    fn test_optional_dependencies() {
+        let plugin = with_deps("app", "1.0.0", &[("extras", "^1.0")], true);
+
+        // Absent optional dependencies are fine, but present ones must match
+        assert!(dependency_issues(&plugin, &HashMap::new()).is_empty());
+        assert!(dependency_issues(&plugin, &HashMap::from([("extras", "1.4.0")])).is_empty());
+        assert_eq!(dependency_issues(&plugin, &HashMap::from([("extras", "2.0.0")])).len(), 1);
+    }
+}
@@ -0,0 +1,444 @@
+//! Component model execution backend.
+//!
+//! Plugins compiled as WASM components (e.g. via `componentize-js`,
+//! TinyGo or `componentize-py`) implement the `orbis:plugin/plugin` world
+//! defined in `wit/plugin.wit` instead of the raw pointer/length module ABI.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use orbis_plugin_api::StateWrite;
+use wasmtime::component::{Component, HasSelf, Linker, ResourceTable};
+use wasmtime::{Engine, Store};
+use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView};
+
+use super::{handler_error, media, storage, BatchQuery, PluginContext, StoreData};
+
+wasmtime::component::bindgen!({
+    path: "wit",
+    world: "plugin",
+    imports: { default: trappable },
+});
+
+use orbis::plugin::host::{Host, LogLevel};
+
+impl Host for StoreData {
+    fn log(&mut self, level: LogLevel, message: String) -> wasmtime::Result<()> {
+        self.check_limits()?;
+        self.authorize("log")?;
+
+        let plugin_name = &self.plugin_name;
+        match level {
+            LogLevel::Error => tracing::error!("[Plugin: {}] {}", plugin_name, message),
+            LogLevel::Warn => tracing::warn!("[Plugin: {}] {}", plugin_name, message),
+            LogLevel::Info => tracing::info!("[Plugin: {}] {}", plugin_name, message),
+            LogLevel::Debug => tracing::debug!("[Plugin: {}] {}", plugin_name, message),
+            LogLevel::Trace => tracing::trace!("[Plugin: {}] {}", plugin_name, message),
+        }
+
+        Ok(())
+    }
+
+    fn state_get(&mut self, key: String) -> wasmtime::Result<Option<String>> {
+        self.check_limits()?;
+        self.authorize("state_get")?;
+
+        Ok(self.state.get(&key).map(|value| value.to_string()))
+    }
+
+    fn state_set(&mut self, key: String, value: String) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("state_set") {
+            return Ok(Err(e.to_string()));
+        }
+
+        match serde_json::from_str(&value) {
+            Ok(value) => {
+                self.state.set(key, value);
+                Ok(Ok(()))
+            }
+            Err(e) => Ok(Err(format!("Failed to parse state value: {}", e))),
+        }
+    }
+
+    fn state_remove(&mut self, key: String) -> wasmtime::Result<()> {
+        self.check_limits()?;
+        self.authorize("state_remove")?;
+
+        self.state.remove(&key);
+        Ok(())
+    }
+
+    fn state_get_many(&mut self, keys: Vec<String>) -> wasmtime::Result<Vec<(String, String)>> {
+        self.check_limits()?;
+        self.authorize("state_get_many")?;
+
+        Ok(self
+            .state
+            .get_many(&keys)
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect())
+    }
+
+    fn state_set_many(&mut self, entries: Vec<(String, Option<String>)>) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("state_set_many") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let mut parsed = HashMap::with_capacity(entries.len());
+        for (key, value) in entries {
+            let write = match value.as_deref().map(serde_json::from_str).transpose() {
+                Ok(Some(value)) => StateWrite::Set(value),
+                Ok(None) => StateWrite::Remove,
+                Err(e) => return Ok(Err(format!("Failed to parse state value for '{}': {}", key, e))),
+            };
+            parsed.insert(key, write);
+        }
+
+        self.state.apply(parsed);
+        Ok(Ok(()))
+    }
+
+    fn is_cancelled(&mut self) -> wasmtime::Result<bool> {
+        Ok(self.interruption().is_some())
+    }
+
+    fn job_enqueue(&mut self, job: String) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("job_enqueue") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self
+            .enqueue_job(job.as_bytes())
+            .map(|id| id.to_string())
+            .map_err(|e| e.to_string()))
+    }
+
+    fn cache_invalidate(&mut self, route: String) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("cache_invalidate") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.invalidate_cache(&route).map_err(|e| e.to_string()))
+    }
+
+    fn email_send(&mut self, email: String) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("email_send") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.send_email(email.as_bytes()).map_err(|e| e.to_string()))
+    }
+
+    fn media_probe(&mut self, data: Vec<u8>) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("media_probe") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(media::probe(&data)
+            .and_then(|info| {
+                serde_json::to_string(&info).map_err(|e| orbis_core::Error::plugin(e.to_string()))
+            })
+            .map_err(|e| e.to_string()))
+    }
+
+    fn media_thumbnail(&mut self, data: Vec<u8>, width: u32, height: u32) -> wasmtime::Result<Result<Vec<u8>, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("media_thumbnail") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(media::thumbnail(&data, width, height).map_err(|e| e.to_string()))
+    }
+
+    fn file_read(&mut self, path: String) -> wasmtime::Result<Result<Vec<u8>, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("file_read") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.files().and_then(|files| files.read(&path)).map_err(|e| e.to_string()))
+    }
+
+    fn file_write(&mut self, path: String, data: Vec<u8>) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("file_write") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.files().and_then(|files| files.write(&path, &data)).map_err(|e| e.to_string()))
+    }
+
+    fn file_list(&mut self, path: String) -> wasmtime::Result<Result<Vec<String>, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("file_list") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.files().and_then(|files| files.list(&path)).map_err(|e| e.to_string()))
+    }
+
+    fn file_remove(&mut self, path: String) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("file_remove") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.files().and_then(|files| files.remove(&path)).map_err(|e| e.to_string()))
+    }
+
+    fn file_url(&mut self, path: String, ttl_seconds: u32) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("file_url") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let ttl = if ttl_seconds == 0 {
+            storage::DEFAULT_URL_TTL
+        } else {
+            Duration::from_secs(u64::from(ttl_seconds))
+        };
+        Ok(self.files().and_then(|files| files.url(&path, ttl)).map_err(|e| e.to_string()))
+    }
+
+    fn data_export(&mut self) -> wasmtime::Result<Result<Vec<u8>, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("data_export") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.export_data().map_err(|e| e.to_string()))
+    }
+
+    fn data_import(&mut self, archive: Vec<u8>) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("data_import") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(self.import_data(&archive).map_err(|e| e.to_string()))
+    }
+
+    fn get_config(&mut self, key: String) -> wasmtime::Result<Option<String>> {
+        self.check_limits()?;
+        self.authorize("get_config")?;
+
+        Ok(self.config.get(&key).map(|value| value.to_string()))
+    }
+
+    fn host_info(&mut self) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("host_info") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(serde_json::to_string(&self.requested_host_info()).map_err(|e| e.to_string()))
+    }
+
+    fn host_env(&mut self, name: String) -> wasmtime::Result<Result<Option<String>, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("host_env") {
+            return Ok(Err(e.to_string()));
+        }
+
+        Ok(Ok(self.env(&name)))
+    }
+
+    fn groups(&mut self) -> wasmtime::Result<Vec<String>> {
+        self.check_limits()?;
+        self.authorize("groups")?;
+
+        Ok(self.groups.clone())
+    }
+
+    fn db_query(&mut self, sql: String, params: String) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("db_query") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let params: Vec<serde_json::Value> = match serde_json::from_str(&params) {
+            Ok(params) => params,
+            Err(e) => return Ok(Err(format!("Invalid params JSON: {}", e))),
+        };
+
+        Ok(self
+            .run_query(&sql, &params)
+            .map(|rows| rows.to_string())
+            .map_err(|e| e.to_string()))
+    }
+
+    fn db_query_batch(&mut self, queries: String) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("db_query_batch") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let queries: Vec<BatchQuery> = match serde_json::from_str(&queries) {
+            Ok(queries) => queries,
+            Err(e) => return Ok(Err(format!("Invalid queries JSON: {}", e))),
+        };
+
+        Ok(self
+            .run_query_batch(&queries)
+            .map(|results| serde_json::Value::Array(results).to_string())
+            .map_err(|e| e.to_string()))
+    }
+
+    fn db_execute(&mut self, sql: String, params: String) -> wasmtime::Result<Result<i64, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("db_execute") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let params: Vec<serde_json::Value> = match serde_json::from_str(&params) {
+            Ok(params) => params,
+            Err(e) => return Ok(Err(format!("Invalid params JSON: {}", e))),
+        };
+
+        Ok(self
+            .run_statement(&sql, &params)
+            .map(|affected| i64::try_from(affected).unwrap_or(i64::MAX))
+            .map_err(|e| e.to_string()))
+    }
+
+    fn http_request(
+        &mut self,
+        method: String,
+        url: String,
+        headers: String,
+        body: Vec<u8>,
+    ) -> wasmtime::Result<Result<String, String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("http_request") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let host = url::Url::parse(&url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(String::from));
+        if let Some(host) = host
+            && !self.sandbox.can_access_network(&host)
+        {
+            return Ok(Err(format!("Plugin is not allowed to access host: {}", host)));
+        }
+
+        let headers: HashMap<String, String> = match serde_json::from_str(&headers) {
+            Ok(headers) => headers,
+            Err(e) => return Ok(Err(format!("Invalid headers JSON: {}", e))),
+        };
+
+        let response = match self.send_http_request(&method, &url, &headers, body) {
+            Ok(response) => response.to_string(),
+            Err(e) => return Ok(Err(e.to_string())),
+        };
+        Ok(Ok(response))
+    }
+
+    fn emit_event(&mut self, event: String, payload: String) -> wasmtime::Result<Result<(), String>> {
+        self.check_limits()?;
+        if let Err(e) = self.authorize("emit_event") {
+            return Ok(Err(e.to_string()));
+        }
+
+        let payload: serde_json::Value = match serde_json::from_str(&payload) {
+            Ok(payload) => payload,
+            Err(e) => return Ok(Err(format!("Invalid payload JSON: {}", e))),
+        };
+
+        Ok(self.publish_event(event, payload).map_err(|e| e.to_string()))
+    }
+}
+
+/// WASI context of a component.
+///
+/// Components get no filesystem, environment, arguments or sockets, and
+/// their standard streams are discarded: they reach the host through the
+/// `orbis:plugin/host` interface, where calls are authorized and metered.
+pub(super) struct ComponentWasi {
+    /// WASI context
+    ctx: WasiCtx,
+    /// Resources, such as streams, the component uses
+    table: ResourceTable,
+}
+
+impl ComponentWasi {
+    /// Create a sandboxed WASI context.
+    fn new() -> Self {
+        Self {
+            ctx: WasiCtx::builder().allow_tcp(false).allow_udp(false).build(),
+            table: ResourceTable::new(),
+        }
+    }
+}
+
+impl WasiView for StoreData {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        let wasi = self.wasi.get_or_insert_with(ComponentWasi::new);
+        WasiCtxView {
+            ctx: &mut wasi.ctx,
+            table: &mut wasi.table,
+        }
+    }
+}
+
+/// Create the linker of component imports: WASI 0.2 and the host interface.
+///
+/// It only depends on the engine, so the runtime builds it once.
+pub(super) fn linker(engine: &Engine) -> orbis_core::Result<Linker<StoreData>> {
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to register WASI functions: {}", e))
+    })?;
+    Plugin::add_to_linker::<StoreData, HasSelf<StoreData>>(&mut linker, |data| data).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to register component host functions: {}", e))
+    })?;
+    Ok(linker)
+}
+
+/// Compile a component from its binary and resolve its imports.
+pub(super) fn compile(
+    engine: &Engine,
+    linker: &Linker<StoreData>,
+    code: &[u8],
+) -> orbis_core::Result<PluginPre<StoreData>> {
+    let component = Component::new(engine, code).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to compile WASM component: {}", e))
+    })?;
+
+    linker
+        .instantiate_pre(&component)
+        .and_then(PluginPre::new)
+        .map_err(|e| orbis_core::Error::plugin(format!("Failed to link plugin component: {}", e)))
+}
+
+/// Execute a handler exported through the component's `handle` function.
+pub(super) fn execute(
+    store: &mut Store<StoreData>,
+    pre: &PluginPre<StoreData>,
+    handler: &str,
+    context: &PluginContext,
+) -> orbis_core::Result<serde_json::Value> {
+    let plugin = pre.instantiate(&mut *store).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to instantiate plugin component: {}", e))
+    })?;
+
+    let context_json = serde_json::to_string(context).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to serialize context: {}", e))
+    })?;
+
+    let result = plugin
+        .call_handle(&mut *store, handler, &context_json)
+        .map_err(|e| handler_error(store, handler, context, &e))?
+        .map_err(|e| orbis_core::Error::plugin(format!("Handler '{}' failed: {}", handler, e)))?;
+
+    serde_json::from_str(&result).map_err(|e| {
+        orbis_core::Error::plugin(format!("Failed to parse result JSON: {}", e))
+    })
+}
@@ -0,0 +1,4925 @@
+//! Plugin runtime for executing plugin code.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use wasmtime::{
+    AsContextMut, Caller, Engine, Instance, Linker, Memory, Module, ResourceLimiter, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc, UpdateDeadline, Val, WasmBacktraceDetails,
+};
+
+use orbis_plugin_api::{AbiVersion, HostCall, HostInfo, HostInfoField, PluginPermission, PluginRequirements, ResourceLimits, PolicyDenial, PolicyEngine};
+use orbis_plugin_api::{state_migration_handler, PluginManifest, StateWrite, STATE_VERSION_KEY};
+
+use super::archive::{self, PluginDataArchive};
+use super::media;
+use super::storage::{self, LocalStore, ObjectStore};
+use super::replay::{HostTape, RecordedCall, ReplayBytes, ReplayCapture, ReplayOutcome, ReplayRecording, TapeValue};
+use super::{
+    FileBroker, ModuleCache, NetworkQuotas, OperationStage, PluginInfo, PluginOperation, PluginSource, ResponseCache,
+    SandboxConfig, ALL_ROUTES,
+};
+use orbis_config::{PluginNetworkAccess, PluginPolicyConfig, PluginsConfig};
+use orbis_core::EncryptionKey;
+use orbis_db::QueryCache;
+
+mod component;
+mod resources;
+mod snapshot;
+mod stats;
+mod tenant;
+mod trap;
+
+use snapshot::MemorySnapshot;
+use tenant::{TenantOverrides, TenantScopes};
+pub use resources::{
+    AlertCondition, AlertRule, PluginResourceMonitor, ResourceAlert, ResourceSample, MAX_SAMPLE_HISTORY,
+};
+pub use snapshot::SnapshotInfo;
+pub use stats::{
+    HandlerFlag, HandlerStats, HandlerStatsReport, HandlerThresholds, SlowInvocation, LATENCY_SAMPLES,
+    MAX_SLOW_INVOCATIONS,
+};
+pub use trap::{RequestSummary, TrapFrame, TrapReport, TrapSink};
+
+/// Maximum size for WASM memory allocations (256MB)
+const MAX_ALLOCATION_SIZE: usize = 256 * 1024 * 1024;
+
+/// Interval between engine epoch increments; the granularity of deadline checks
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Timeout of an outbound HTTP request made by a plugin
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size of an HTTP response body returned to a plugin (16MB)
+const MAX_HTTP_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Cancellation flag for a plugin execution.
+///
+/// Set when the request being handled is abandoned (for example when the
+/// client disconnects); running plugins are interrupted at the next epoch tick.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationFlag(Arc<AtomicBool>);
+
+impl CancellationFlag {
+    /// Create a new, unset flag.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the execution as cancelled.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the execution has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Get a guard that cancels the execution when dropped.
+    ///
+    /// Hold this in the request future so that dropping the future cancels the plugin.
+    #[must_use]
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// Guard that cancels a [`CancellationFlag`] when dropped.
+#[derive(Debug)]
+pub struct CancelOnDrop(CancellationFlag);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Context passed to plugin handlers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginContext {
+    /// Request method.
+    pub method: String,
+
+    /// Request path.
+    pub path: String,
+
+    /// Request headers.
+    pub headers: std::collections::HashMap<String, String>,
+
+    /// Query parameters.
+    pub query: std::collections::HashMap<String, String>,
+
+    /// Request body (as JSON).
+    #[serde(default)]
+    pub body: serde_json::Value,
+
+    /// User ID (if authenticated).
+    #[serde(default)]
+    pub user_id: Option<String>,
+
+    /// User is admin.
+    #[serde(default)]
+    pub is_admin: bool,
+
+    /// Tenant the request belongs to (multi-tenant deployments only).
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+
+    /// Time by which the request must complete.
+    #[serde(default)]
+    pub deadline: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Groups the user is a member of, directly or through a subgroup.
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Feature flags of the plugin, resolved by the plugin manager.
+    #[serde(default)]
+    pub features: std::collections::BTreeMap<String, bool>,
+
+    /// Cancellation flag for the request.
+    #[serde(skip)]
+    pub cancellation: CancellationFlag,
+}
+
+/// Plugin state storage - each plugin has its own isolated state
+#[derive(Debug, Clone, Default)]
+pub struct PluginState {
+    /// Key-value state storage (JSON values)
+    data: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    /// Path to persist state to disk (if set)
+    persist_path: Arc<RwLock<Option<std::path::PathBuf>>>,
+    /// Key sealing persisted state (encrypted profiles only)
+    key: Option<Arc<EncryptionKey>>,
+}
+
+impl PluginState {
+    /// Create a new plugin state
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(RwLock::new(HashMap::new())),
+            persist_path: Arc::new(RwLock::new(None)),
+            key: None,
+        }
+    }
+
+    /// Create a new plugin state with persistence
+    #[must_use]
+    pub fn with_persistence(path: std::path::PathBuf) -> Self {
+        Self::with_encrypted_persistence(path, None)
+    }
+
+    /// Create a new plugin state with persistence, sealed with a key if given.
+    ///
+    /// Unsealed state is still loaded, and sealed on the next write. State
+    /// that cannot be opened with the key is left untouched on disk and not
+    /// persisted, so a wrong key never overwrites it.
+    #[must_use]
+    pub fn with_encrypted_persistence(path: std::path::PathBuf, key: Option<Arc<EncryptionKey>>) -> Self {
+        let mut state = Self::new();
+        state.key = key;
+        *state.persist_path.write() = Some(path.clone());
+        
+        // Try to load existing state
+        if let Ok(contents) = std::fs::read(&path) {
+            match state.unseal(contents) {
+                Ok(contents) => {
+                    if let Ok(data) = serde_json::from_slice::<HashMap<String, serde_json::Value>>(&contents) {
+                        *state.data.write() = data;
+                        tracing::debug!("Loaded plugin state from {:?}", path);
+                    } else {
+                        tracing::warn!("Failed to parse plugin state from {:?}", path);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to open plugin state {:?}, not persisting it: {}", path, e);
+                    *state.persist_path.write() = None;
+                }
+            }
+        }
+        
+        state
+    }
+
+    /// Open persisted state sealed with the key; unsealed state is returned as is.
+    fn unseal(&self, contents: Vec<u8>) -> orbis_core::Result<Vec<u8>> {
+        if !EncryptionKey::is_sealed(&contents) {
+            return Ok(contents);
+        }
+        self.key
+            .as_ref()
+            .ok_or_else(|| orbis_core::Error::unauthorized("State is encrypted and no key is set"))?
+            .open(&contents)
+    }
+
+    /// Save state to disk if persistence is enabled
+    fn persist(&self) {
+        if let Some(ref path) = *self.persist_path.read() {
+            let data = self.data.read().clone();
+            if let Ok(json) = serde_json::to_vec_pretty(&data) {
+                // Ensure parent directory exists
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+
+                let contents = match &self.key {
+                    Some(key) => match key.seal(&json) {
+                        Ok(sealed) => sealed,
+                        Err(e) => {
+                            tracing::error!("Failed to encrypt plugin state for {:?}: {}", path, e);
+                            return;
+                        }
+                    },
+                    None => json,
+                };
+                if let Err(e) = std::fs::write(path, contents) {
+                    tracing::error!("Failed to persist plugin state to {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    /// Get a value from the state
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        self.data.read().get(key).cloned()
+    }
+
+    /// Set a value in the state
+    pub fn set(&self, key: String, value: serde_json::Value) {
+        self.data.write().insert(key, value);
+        self.persist();
+    }
+
+    /// Remove a value from the state
+    pub fn remove(&self, key: &str) -> Option<serde_json::Value> {
+        let result = self.data.write().remove(key);
+        self.persist();
+        result
+    }
+
+    /// Get several values at once; missing keys are omitted
+    #[must_use]
+    pub fn get_many(&self, keys: &[String]) -> HashMap<String, serde_json::Value> {
+        let data = self.data.read();
+        keys.iter()
+            .filter_map(|key| data.get(key).map(|value| (key.clone(), value.clone())))
+            .collect()
+    }
+
+    /// Apply several writes at once, persisting only once
+    pub fn apply(&self, entries: HashMap<String, StateWrite>) {
+        {
+            let mut data = self.data.write();
+            for (key, write) in entries {
+                match write {
+                    StateWrite::Set(value) => data.insert(key, value),
+                    StateWrite::Remove => data.remove(&key),
+                };
+            }
+        }
+        self.persist();
+    }
+
+    /// Clear all state
+    pub fn clear(&self) {
+        self.data.write().clear();
+        self.persist();
+    }
+
+    /// Get all keys
+    #[must_use]
+    pub fn keys(&self) -> Vec<String> {
+        self.data.read().keys().cloned().collect()
+    }
+
+    /// Get a copy of all entries
+    #[must_use]
+    pub fn entries(&self) -> HashMap<String, serde_json::Value> {
+        self.data.read().clone()
+    }
+
+    /// Replace all entries at once
+    pub fn replace(&self, data: HashMap<String, serde_json::Value>) {
+        *self.data.write() = data;
+        self.persist();
+    }
+
+    /// Copy the state, without persisting the copy
+    #[must_use]
+    pub(crate) fn detached(&self) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(self.entries())),
+            ..Self::new()
+        }
+    }
+}
+
+/// Plugin configuration storage
+#[derive(Debug, Clone, Default)]
+pub struct PluginConfig {
+    /// Configuration values
+    data: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+}
+
+impl PluginConfig {
+    /// Create a new plugin config
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Create plugin config from manifest settings
+    #[must_use]
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(settings.clone())),
+        }
+    }
+
+    /// Get a config value
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        self.data.read().get(key).cloned()
+    }
+
+    /// Set a config value
+    pub fn set(&self, key: String, value: serde_json::Value) {
+        self.data.write().insert(key, value);
+    }
+
+    /// Create a copy of this config with some values replaced
+    #[must_use]
+    pub fn with_overrides(&self, overrides: &HashMap<String, serde_json::Value>) -> Self {
+        let mut data = self.data.read().clone();
+        data.extend(overrides.iter().map(|(key, value)| (key.clone(), value.clone())));
+        Self::from_settings(&data)
+    }
+}
+
+/// A query in a `db_query_batch` host call
+#[derive(Debug, Deserialize)]
+struct BatchQuery {
+    /// SQL statement
+    sql: String,
+    /// Statement parameters
+    #[serde(default)]
+    params: Vec<serde_json::Value>,
+}
+
+/// Maximum number of queries in a `db_query_batch` host call
+const MAX_BATCH_QUERIES: usize = 100;
+
+/// A background job enqueued by a plugin through the `job_enqueue` host function.
+///
+/// The job runs `handler` of the enqueuing plugin with `payload` as the request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginJob {
+    /// Job ID, assigned by the host
+    #[serde(skip_deserializing)]
+    pub id: uuid::Uuid,
+    /// Plugin that enqueued the job, set by the host
+    #[serde(skip_deserializing)]
+    pub plugin: String,
+    /// Tenant the job was enqueued for, set by the host
+    #[serde(skip_deserializing)]
+    pub tenant_id: Option<String>,
+    /// Handler to run
+    pub handler: String,
+    /// Payload passed as the request body
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    /// Queue to put the job on (the default queue if unset)
+    #[serde(default)]
+    pub queue: Option<String>,
+    /// Seconds to wait before the job may run
+    #[serde(default)]
+    pub delay_seconds: u64,
+    /// Attempts before the job is dead-lettered (the host default if unset)
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+/// Channel plugin jobs are sent to, drained by the host's job queue.
+pub type JobSink = tokio::sync::mpsc::UnboundedSender<PluginJob>;
+
+/// An email sent by a plugin through the `email_send` host function.
+///
+/// `subject`, `text` and `html` may contain `{{ name }}` placeholders filled
+/// from `variables`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginEmail {
+    /// Plugin that sent the email, set by the host
+    #[serde(skip_deserializing)]
+    pub plugin: String,
+    /// Tenant the email was sent for, set by the host
+    #[serde(skip_deserializing)]
+    pub tenant_id: Option<String>,
+    /// Recipient addresses
+    pub to: Vec<String>,
+    /// Subject
+    pub subject: String,
+    /// Plain text body
+    #[serde(default)]
+    pub text: Option<String>,
+    /// HTML body
+    #[serde(default)]
+    pub html: Option<String>,
+    /// Template variables
+    #[serde(default)]
+    pub variables: serde_json::Value,
+}
+
+/// Channel plugin emails are sent to, drained by the host's email service.
+pub type EmailSink = tokio::sync::mpsc::UnboundedSender<PluginEmail>;
+
+/// An event emitted by a plugin through the `emit_event` host function.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginEvent {
+    /// Plugin that emitted the event
+    pub plugin: String,
+    /// Tenant the event was emitted for
+    pub tenant_id: Option<String>,
+    /// Event name
+    pub event: String,
+    /// Event payload
+    pub payload: serde_json::Value,
+}
+
+/// Channel plugin events are sent to, published by the host's event hub.
+pub type EventSink = tokio::sync::mpsc::UnboundedSender<PluginEvent>;
+
+/// Store limiter enforcing the sandbox memory limit and recording the
+/// largest memory the plugin grew to.
+///
+/// Denied growth fails the `memory.grow` instruction, so plugins can handle
+/// it; if the handler fails afterwards, the failure is reported as running
+/// out of memory.
+struct MemoryLimiter {
+    /// Sandbox limits
+    limits: StoreLimits,
+    /// Largest linear memory size granted, in bytes
+    peak: usize,
+    /// Largest linear memory size denied by the limit, in bytes
+    denied: Option<usize>,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> wasmtime::Result<bool> {
+        let allowed = self.limits.memory_growing(current, desired, maximum)?;
+        if allowed {
+            self.peak = self.peak.max(desired);
+        } else {
+            self.denied = Some(self.denied.map_or(desired, |denied| denied.max(desired)));
+        }
+        Ok(allowed)
+    }
+
+    fn memory_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
+        self.limits.memory_grow_failed(error)
+    }
+
+    fn table_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> wasmtime::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
+        self.limits.table_grow_failed(error)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
+
+/// Store data combining WASM state and host data
+pub struct StoreData {
+    /// Memory limits for the WASM instance
+    limits: MemoryLimiter,
+    /// Plugin state storage
+    state: PluginState,
+    /// Plugin configuration
+    config: PluginConfig,
+    /// Plugin name for logging and permissions
+    plugin_name: String,
+    /// Sandbox configuration
+    sandbox: Arc<SandboxConfig>,
+    /// Call counter for max_calls enforcement
+    call_count: u64,
+    /// Execution start time for time limit enforcement
+    start_time: Instant,
+    /// Request deadline, if any
+    deadline: Option<Instant>,
+    /// Request cancellation flag
+    cancellation: CancellationFlag,
+    /// Where to record a report if the plugin traps
+    last_trap: Arc<parking_lot::Mutex<Option<TrapReport>>>,
+    /// Object store of the plugin's data files (unset for tenant-scoped calls)
+    object_store: Option<Arc<dyn ObjectStore>>,
+    /// Broker of the plugin's filesystem access
+    files: Option<Arc<FileBroker>>,
+    /// Host resources the plugin's manifest requests
+    requirements: Arc<PluginRequirements>,
+    /// Resource limits of the plugin's manifest
+    resource_limits: ResourceLimits,
+    /// Network usage tracking, if the host enables quotas
+    network_quotas: Option<Arc<NetworkQuotas>>,
+    /// Tenant the call is scoped to, if any
+    tenant: Option<String>,
+    /// Groups of the user the request is made by
+    groups: Vec<String>,
+    /// Where jobs enqueued by the plugin are sent, if the host runs a job queue
+    jobs: Option<JobSink>,
+    /// Where emails sent by the plugin go, if the host runs an email service
+    email: Option<EmailSink>,
+    /// Where events emitted by the plugin go, if the host publishes them
+    events: Option<EventSink>,
+    /// Route response cache the plugin can invalidate
+    response_cache: Option<Arc<ResponseCache>>,
+    /// Query result cache, if the host enables one
+    query_cache: Option<Arc<QueryCache>>,
+    /// Permissions the plugin's manifest requests
+    permissions: Arc<[PluginPermission]>,
+    /// Host call authorization policy
+    policy: Arc<PolicyEngine>,
+    /// Denial of the last host call, until the plugin reads it
+    denial: Option<PolicyDenial>,
+    /// Host calls being recorded or replayed, if any
+    tape: Option<HostTape>,
+    /// WASI context of a component, created on its first WASI call
+    wasi: Option<component::ComponentWasi>,
+}
+
+impl StoreData {
+    /// Create new store data
+    fn new(plugin_name: String, sandbox: Arc<SandboxConfig>, state: PluginState, config: PluginConfig) -> Self {
+        let limits = MemoryLimiter {
+            limits: StoreLimitsBuilder::new().memory_size(sandbox.memory_limit).build(),
+            peak: 0,
+            denied: None,
+        };
+
+        Self {
+            limits,
+            state,
+            config,
+            plugin_name,
+            sandbox,
+            call_count: 0,
+            start_time: Instant::now(),
+            deadline: None,
+            cancellation: CancellationFlag::new(),
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: None,
+            tenant: None,
+            groups: Vec::new(),
+            jobs: None,
+            email: None,
+            events: None,
+            response_cache: None,
+            query_cache: None,
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+            denial: None,
+            tape: None,
+            wasi: None,
+        }
+    }
+
+    /// Authorize a host function call against the plugin's permissions and
+    /// the security policy.
+    ///
+    /// In audit mode, calls that would be denied are logged and allowed.
+    fn authorize(&mut self, function: &str) -> orbis_core::Result<()> {
+        self.denial = None;
+        let call = HostCall::new(&self.plugin_name, function, self.tenant.as_deref());
+        let decision = self.policy.evaluate(&call, &self.permissions);
+        let Some(reason) = decision.denial.clone() else {
+            return Ok(());
+        };
+
+        let denial = PolicyDenial {
+            function: function.to_string(),
+            reason,
+        };
+        if decision.is_allowed() {
+            tracing::warn!("[Plugin: {}] Policy audit: {}", self.plugin_name, denial);
+            return Ok(());
+        }
+
+        tracing::warn!("[Plugin: {}] {}", self.plugin_name, denial);
+        let message = denial.to_string();
+        self.denial = Some(denial);
+        Err(orbis_core::Error::plugin(message))
+    }
+
+    /// Enqueue a background job running one of the plugin's handlers, returning its ID.
+    fn enqueue_job(&self, job: &[u8]) -> orbis_core::Result<uuid::Uuid> {
+        let sink = self
+            .jobs
+            .as_ref()
+            .ok_or_else(|| orbis_core::Error::plugin("Background jobs are not available"))?;
+
+        let mut job: PluginJob = serde_json::from_slice(job)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid job: {}", e)))?;
+        if job.handler.is_empty() {
+            return Err(orbis_core::Error::plugin("Job handler cannot be empty"));
+        }
+        job.id = orbis_core::new_id();
+        job.plugin.clone_from(&self.plugin_name);
+        job.tenant_id.clone_from(&self.tenant);
+
+        let id = job.id;
+        if sink.send(job).is_err() {
+            return Err(orbis_core::Error::plugin("Job queue is not running"));
+        }
+        Ok(id)
+    }
+
+    /// Queue an email for delivery by the host's email service.
+    fn send_email(&self, email: &[u8]) -> orbis_core::Result<()> {
+        let sink = self
+            .email
+            .as_ref()
+            .ok_or_else(|| orbis_core::Error::plugin("Email is not available"))?;
+
+        let mut email: PluginEmail = serde_json::from_slice(email)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid email: {}", e)))?;
+        if email.to.is_empty() {
+            return Err(orbis_core::Error::plugin("Email needs at least one recipient"));
+        }
+        if email.text.is_none() && email.html.is_none() {
+            return Err(orbis_core::Error::plugin("Email needs a text or HTML body"));
+        }
+        email.plugin.clone_from(&self.plugin_name);
+        email.tenant_id.clone_from(&self.tenant);
+
+        if sink.send(email).is_err() {
+            return Err(orbis_core::Error::plugin("Email service is not running"));
+        }
+        Ok(())
+    }
+
+    /// Publish an event through the host's event hub.
+    ///
+    /// Events are dropped if the host does not publish them.
+    fn publish_event(&self, event: String, payload: serde_json::Value) -> orbis_core::Result<()> {
+        if event.is_empty() {
+            return Err(orbis_core::Error::plugin("Event name cannot be empty"));
+        }
+
+        tracing::debug!("[Plugin: {}] Emitting event '{}'", self.plugin_name, event);
+        let event = PluginEvent {
+            plugin: self.plugin_name.clone(),
+            tenant_id: self.tenant.clone(),
+            event,
+            payload,
+        };
+        if self.events.as_ref().is_none_or(|sink| sink.send(event).is_err()) {
+            tracing::trace!("[Plugin: {}] No event subscribers", self.plugin_name);
+        }
+        Ok(())
+    }
+
+    /// Drop the cached responses of one of the plugin's routes.
+    fn invalidate_cache(&self, route: &str) -> orbis_core::Result<()> {
+        if route != ALL_ROUTES && !route.starts_with('/') {
+            return Err(orbis_core::Error::plugin(format!(
+                "Invalid route '{}': expected a path starting with '/' or '*'",
+                route
+            )));
+        }
+
+        if let Some(cache) = &self.response_cache {
+            cache.invalidate(&self.plugin_name, route);
+        }
+        Ok(())
+    }
+
+    /// Get the broker of the plugin's filesystem access.
+    fn files(&self) -> orbis_core::Result<&FileBroker> {
+        self.files
+            .as_deref()
+            .ok_or_else(|| orbis_core::Error::plugin("Filesystem access is not available"))
+    }
+
+    /// Count a request to an external host against the plugin's network quotas.
+    fn admit_network_request(&self) -> orbis_core::Result<()> {
+        self.network_quotas
+            .as_ref()
+            .map_or(Ok(()), |quotas| quotas.admit(&self.plugin_name, &self.resource_limits))
+    }
+
+    /// Count bytes sent to or received from an external host.
+    fn record_network_bytes(&self, bytes: usize) {
+        if let Some(quotas) = &self.network_quotas {
+            quotas.record_bytes(&self.plugin_name, bytes as u64);
+        }
+    }
+
+    /// Send an HTTP request to an external host, metering it against the plugin's quotas.
+    ///
+    /// The host must already be checked against the sandbox. Redirects are not
+    /// followed, since their target would bypass that check. Transport failures
+    /// are reported in the response's `error` field, as the SDK expects.
+    fn send_http_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &HashMap<String, String>,
+        body: Vec<u8>,
+    ) -> orbis_core::Result<serde_json::Value> {
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid HTTP method '{}': {}", method, e)))?;
+
+        self.admit_network_request()?;
+        let sent = url
+            .len()
+            .saturating_add(headers.iter().map(|(name, value)| name.len().saturating_add(value.len())).sum())
+            .saturating_add(body.len());
+        self.record_network_bytes(sent);
+
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to create HTTP client: {}", e)))?;
+        let mut request = client.request(method, url).body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = storage::block_on(async {
+            let mut response = request.send().await?;
+            let status = response.status().as_u16();
+            let headers: HashMap<String, String> = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len().saturating_add(chunk.len()) > MAX_HTTP_RESPONSE_SIZE {
+                    return Ok(serde_json::json!({
+                        "status": status,
+                        "headers": headers,
+                        "error": format!("Response body exceeds {} bytes", MAX_HTTP_RESPONSE_SIZE),
+                    }));
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok::<_, reqwest::Error>(serde_json::json!({ "status": status, "headers": headers, "body": body }))
+        })?;
+
+        let response = response.unwrap_or_else(|e| serde_json::json!({ "status": 0, "error": e.to_string() }));
+        self.record_network_bytes(response.to_string().len());
+        Ok(response)
+    }
+
+    /// Get the host information the plugin requests, with everything else redacted.
+    fn requested_host_info(&self) -> HostInfo {
+        let granted = |field| self.requirements.host_info.contains(&field);
+        HostInfo {
+            hostname: granted(HostInfoField::Hostname)
+                .then(|| hostname::get().ok().map(|name| name.to_string_lossy().into_owned()))
+                .flatten(),
+            os: granted(HostInfoField::Os).then(|| std::env::consts::OS.to_owned()),
+            arch: granted(HostInfoField::Arch).then(|| std::env::consts::ARCH.to_owned()),
+            app_version: granted(HostInfoField::AppVersion).then(|| env!("CARGO_PKG_VERSION").to_owned()),
+        }
+    }
+
+    /// Get an environment variable the plugin requests.
+    ///
+    /// Variables the manifest does not request read as unset.
+    fn env(&self, name: &str) -> Option<String> {
+        if !self.requirements.allows_env(name) {
+            tracing::warn!(
+                "[Plugin: {}] Redacted environment variable '{}' not declared in requirements",
+                self.plugin_name,
+                name
+            );
+            return None;
+        }
+        std::env::var(name).ok()
+    }
+
+    /// Export the data of the current scope (state and data files) as a ZIP archive.
+    fn export_data(&self) -> orbis_core::Result<Vec<u8>> {
+        let mut archive = PluginDataArchive::new(&self.plugin_name);
+        archive.state = self.state.entries();
+        if let Some(store) = self.object_store.as_ref() {
+            let prefix = storage::plugin_files_prefix(&self.plugin_name);
+            archive.files = storage::block_on(archive::read_files(store.as_ref(), &prefix))??;
+        }
+        archive.to_zip()
+    }
+
+    /// Replace the data of the current scope with the contents of a ZIP archive.
+    fn import_data(&self, bytes: &[u8]) -> orbis_core::Result<()> {
+        let archive = PluginDataArchive::from_zip(bytes)?;
+        if archive.plugin != self.plugin_name {
+            return Err(orbis_core::Error::plugin(format!(
+                "Data archive belongs to plugin '{}'",
+                archive.plugin
+            )));
+        }
+
+        if let Some(store) = self.object_store.as_ref() {
+            let prefix = storage::plugin_files_prefix(&self.plugin_name);
+            storage::block_on(archive::write_files(store.as_ref(), &prefix, &archive.files))??;
+        }
+        self.state.replace(archive.state);
+        Ok(())
+    }
+
+    /// Run a batch of queries, returning the rows of each query in order.
+    fn run_query_batch(&self, queries: &[BatchQuery]) -> orbis_core::Result<Vec<serde_json::Value>> {
+        if queries.len() > MAX_BATCH_QUERIES {
+            return Err(orbis_core::Error::plugin(format!(
+                "Query batch too large: {} queries (max {})",
+                queries.len(),
+                MAX_BATCH_QUERIES
+            )));
+        }
+
+        queries
+            .iter()
+            .map(|query| {
+                tracing::trace!(
+                    "[Plugin: {}] Batch query: {} ({} params)",
+                    self.plugin_name,
+                    query.sql,
+                    query.params.len()
+                );
+                self.run_query(&query.sql, &query.params)
+            })
+            .collect()
+    }
+
+    /// Run a query, answering from the query cache when possible.
+    fn run_query(&self, sql: &str, params: &[serde_json::Value]) -> orbis_core::Result<serde_json::Value> {
+        // Plugins and tenants see different data, so they never share results
+        let key = format!(
+            "{}:{}:{}",
+            self.plugin_name,
+            self.tenant.as_deref().unwrap_or("-"),
+            serde_json::Value::from(params.to_vec())
+        );
+        if let Some(rows) = self
+            .query_cache
+            .as_ref()
+            .and_then(|cache| cache.get::<serde_json::Value>(sql, &key))
+        {
+            return Ok(rows);
+        }
+
+        // TODO: Actually execute query against database
+        let rows = serde_json::Value::Array(Vec::new());
+        if let Some(cache) = &self.query_cache {
+            cache.insert(sql, &key, rows.clone());
+        }
+        Ok(rows)
+    }
+
+    /// Run a statement, dropping the cached queries of the tables it writes.
+    fn run_statement(&self, sql: &str, _params: &[serde_json::Value]) -> orbis_core::Result<u64> {
+        // TODO: Actually execute statement against database
+        if let Some(cache) = &self.query_cache {
+            cache.invalidate_statement(sql);
+        }
+        Ok(0)
+    }
+
+    /// Set the request deadline and cancellation flag for this execution.
+    fn set_request(&mut self, deadline: Option<chrono::DateTime<chrono::Utc>>, cancellation: CancellationFlag) {
+        self.deadline = deadline.map(|deadline| {
+            let remaining = (deadline - chrono::Utc::now()).to_std().unwrap_or_default();
+            Instant::now() + remaining
+        });
+        self.cancellation = cancellation;
+    }
+
+    /// Check whether execution must be interrupted, returning the reason.
+    fn interruption(&self) -> Option<String> {
+        if self.cancellation.is_cancelled() {
+            return Some(format!("Plugin '{}' execution cancelled", self.plugin_name));
+        }
+
+        let now = Instant::now();
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            return Some(format!("Plugin '{}' exceeded request deadline", self.plugin_name));
+        }
+
+        if now.duration_since(self.start_time).as_millis() > u128::from(self.sandbox.time_limit_ms) {
+            return Some(format!(
+                "Plugin '{}' exceeded time limit: {}ms",
+                self.plugin_name, self.sandbox.time_limit_ms
+            ));
+        }
+
+        None
+    }
+
+    /// Check if execution should continue
+    fn check_limits(&mut self) -> orbis_core::Result<()> {
+        // Check call count
+        self.call_count += 1;
+        if self.call_count > self.sandbox.max_calls {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' exceeded maximum calls: {}",
+                self.plugin_name, self.sandbox.max_calls
+            )));
+        }
+
+        // Check execution time
+        let elapsed = self.start_time.elapsed();
+        if elapsed.as_millis() > u128::from(self.sandbox.time_limit_ms) {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' exceeded time limit: {}ms",
+                self.plugin_name, self.sandbox.time_limit_ms
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert a handler execution error, recording a trap report if the plugin trapped.
+///
+/// Failures after the memory limit denied growth are out of memory errors.
+fn handler_error(
+    store: &Store<StoreData>,
+    handler: &str,
+    context: &PluginContext,
+    error: &wasmtime::Error,
+) -> orbis_core::Error {
+    let data = store.data();
+    if let Some(reason) = data.interruption() {
+        return orbis_core::Error::plugin(reason);
+    }
+
+    let out_of_memory = data.limits.denied.map(|requested| {
+        orbis_core::Error::out_of_memory(format!(
+            "Plugin '{}' ran out of memory in handler '{}': growing to {} bytes exceeds its limit of {} bytes",
+            data.plugin_name, handler, requested, data.sandbox.memory_limit
+        ))
+    });
+
+    let Some(report) = TrapReport::from_error(&data.plugin_name, handler, context, error) else {
+        return out_of_memory.unwrap_or_else(|| {
+            orbis_core::Error::plugin(format!("Failed to execute handler '{}': {}", handler, error))
+        });
+    };
+
+    tracing::error!(
+        plugin = %report.plugin,
+        handler = %report.handler,
+        method = %report.request.method,
+        path = %report.request.path,
+        "Plugin trapped: {}\n{}",
+        report.message,
+        report.backtrace()
+    );
+
+    let message = format!(
+        "Plugin '{}' trapped in handler '{}': {}",
+        report.plugin,
+        handler,
+        report.trap_code.as_deref().unwrap_or(&report.message)
+    );
+    *data.last_trap.lock() = Some(report);
+
+    out_of_memory.unwrap_or_else(|| orbis_core::Error::plugin(message))
+}
+
+/// Compiled plugin code.
+///
+/// Plugins are either core WASM modules using the pointer/length ABI, or
+/// WASM components implementing the `orbis:plugin/plugin` WIT world.
+/// Components get the `orbis:plugin/host` interface and a sandboxed WASI
+/// 0.2, and are pre-instantiated when compiled.
+enum PluginCode {
+    /// Core WASM module.
+    Module(Module),
+    /// WASM component, with its imports resolved.
+    Component(component::PluginPre<StoreData>),
+}
+
+impl PluginCode {
+    /// Compile plugin code, detecting whether the binary is a module or a component.
+    ///
+    /// Modules are loaded from and stored in the precompiled module cache when one is set.
+    fn compile(
+        engine: &Engine,
+        linker: &wasmtime::component::Linker<StoreData>,
+        code: &[u8],
+        cache: Option<&ModuleCache>,
+    ) -> orbis_core::Result<Self> {
+        if wasmparser::Parser::is_component(code) {
+            return component::compile(engine, linker, code).map(Self::Component);
+        }
+
+        let key = cache.map(|_| ModuleCache::key(engine, code));
+        if let (Some(cache), Some(key)) = (cache, key.as_deref())
+            && let Some(module) = cache.load(engine, key)
+        {
+            return Ok(Self::Module(module));
+        }
+
+        let module = Module::new(engine, code).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to compile WASM module: {}", e))
+        })?;
+
+        if let (Some(cache), Some(key)) = (cache, key.as_deref())
+            && let Err(e) = cache.store(key, &module)
+        {
+            tracing::warn!("Failed to cache compiled module: {}", e);
+        }
+
+        Ok(Self::Module(module))
+    }
+}
+
+/// Plugin runtime instance.
+struct PluginInstance {
+    engine: Engine,
+    code: PluginCode,
+    /// ABI version the plugin was built against
+    abi_version: AbiVersion,
+    sandbox_config: Arc<SandboxConfig>,
+    state: PluginState,
+    config: PluginConfig,
+    /// Memory snapshot taken after `init`, kept for restarts
+    snapshot: Option<Arc<MemorySnapshot>>,
+    /// Set when the instance restarted from its snapshot instead of running
+    /// `init` (after a crash or idle unloading); every instantiation restores it
+    restore_snapshot: bool,
+    /// Report of the last trap, until collected
+    last_trap: Arc<parking_lot::Mutex<Option<TrapReport>>>,
+    /// Per-tenant state and configuration, handed over to the next version on reload
+    tenants: Arc<TenantScopes>,
+    /// Object store of the plugin's data files, if persistence is enabled
+    object_store: Option<Arc<dyn ObjectStore>>,
+    /// Broker of the plugin's filesystem access
+    files: Option<Arc<FileBroker>>,
+    /// Host resources the plugin's manifest requests
+    requirements: Arc<PluginRequirements>,
+    /// Resource limits of the plugin's manifest
+    resource_limits: ResourceLimits,
+    /// Network usage tracking, shared with the runtime so it can be set after loading
+    network_quotas: Arc<RwLock<Option<Arc<NetworkQuotas>>>>,
+    /// Job sink, shared with the runtime so it can be set after loading
+    jobs: Arc<RwLock<Option<JobSink>>>,
+    /// Email sink, shared with the runtime so it can be set after loading
+    email: Arc<RwLock<Option<EmailSink>>>,
+    /// Event sink, shared with the runtime so it can be set after loading
+    events: Arc<RwLock<Option<EventSink>>>,
+    /// Route response cache, shared with the runtime
+    response_cache: Arc<ResponseCache>,
+    /// Query result cache, shared with the runtime so it can be set after loading
+    query_cache: Arc<RwLock<Option<Arc<QueryCache>>>>,
+    /// Permissions the plugin's manifest requests
+    permissions: Arc<[PluginPermission]>,
+    /// Host call authorization policy, shared with the runtime
+    policy: Arc<PolicyEngine>,
+}
+
+impl PluginInstance {
+    /// Create a store for running this plugin, with memory limits and fuel set.
+    ///
+    /// With a tenant, the plugin sees that tenant's state and configuration.
+    fn new_store(&self, plugin_name: &str, tenant: Option<&str>) -> orbis_core::Result<Store<StoreData>> {
+        let (state, config) = tenant.map_or_else(
+            || Ok((self.state.clone(), self.config.clone())),
+            |tenant| {
+                Ok::<_, orbis_core::Error>((
+                    self.tenants.state(plugin_name, tenant)?,
+                    self.tenants.config(tenant, &self.config),
+                ))
+            },
+        )?;
+        let mut store_data = StoreData::new(plugin_name.to_string(), self.sandbox_config.clone(), state, config);
+        store_data.last_trap = self.last_trap.clone();
+        // Data files are shared by all tenants, so only unscoped calls see them
+        store_data.object_store = if tenant.is_none() { self.object_store.clone() } else { None };
+        store_data.files = self.files.clone();
+        store_data.requirements = Arc::clone(&self.requirements);
+        store_data.resource_limits = self.resource_limits;
+        store_data.network_quotas = self.network_quotas.read().clone();
+        store_data.tenant = tenant.map(String::from);
+        store_data.jobs = self.jobs.read().clone();
+        store_data.email = self.email.read().clone();
+        store_data.events = self.events.read().clone();
+        store_data.query_cache = self.query_cache.read().clone();
+        store_data.response_cache = Some(Arc::clone(&self.response_cache));
+        store_data.permissions = Arc::clone(&self.permissions);
+        store_data.policy = Arc::clone(&self.policy);
+        let mut store = Store::new(&self.engine, store_data);
+        store.limiter(|data| &mut data.limits);
+
+        // Check deadlines and cancellation on every epoch tick
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(|store| {
+            store
+                .data()
+                .interruption()
+                .map_or(Ok(UpdateDeadline::Continue(1)), |reason| Err(wasmtime::Error::msg(reason)))
+        });
+
+        // Add fuel for execution
+        store
+            .set_fuel(u64::from(self.sandbox_config.time_limit_ms) * 1000)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to set fuel: {}", e)))?;
+
+        Ok(store)
+    }
+
+    /// Get the sandbox configuration.
+    #[must_use]
+    pub fn sandbox_config(&self) -> &SandboxConfig {
+        &self.sandbox_config
+    }
+
+    /// Get the plugin state
+    #[must_use]
+    pub const fn state(&self) -> &PluginState {
+        &self.state
+    }
+
+    /// Get the plugin config
+    #[must_use]
+    pub const fn config(&self) -> &PluginConfig {
+        &self.config
+    }
+}
+
+/// Plugin instance prepared for a plugin, not yet its current instance.
+pub struct StagedInstance {
+    /// Prepared instance.
+    instance: PluginInstance,
+
+    /// Plugin state after its migrations, stored once the instance is installed.
+    migrated: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// Instance and memory snapshot of a plugin replaced by a staged instance.
+pub struct ReplacedInstance {
+    /// Replaced instance, unset if the plugin had none.
+    instance: Option<Arc<PluginInstance>>,
+
+    /// Replaced memory snapshot.
+    snapshot: Option<Arc<MemorySnapshot>>,
+}
+
+/// Plugin runtime for executing plugin code.
+#[derive(Clone)]
+pub struct PluginRuntime {
+    instances:    DashMap<String, Arc<PluginInstance>>,
+    engine:       Engine,
+    plugins_dir:  Arc<RwLock<Option<std::path::PathBuf>>>,
+    module_cache: Arc<RwLock<Option<ModuleCache>>>,
+    /// Post-init memory snapshots, kept across deactivation for warm restarts
+    snapshots:    DashMap<String, Arc<MemorySnapshot>>,
+    /// Per-tenant configuration overrides, keyed by plugin name
+    tenant_overrides: DashMap<String, TenantOverrides>,
+    /// Where plugins send the background jobs they enqueue
+    job_sink: Arc<RwLock<Option<JobSink>>>,
+    /// Where plugins send the emails they send
+    email_sink: Arc<RwLock<Option<EmailSink>>>,
+    /// Where plugins send the events they emit
+    event_sink: Arc<RwLock<Option<EventSink>>>,
+    /// Linker of component host functions and WASI, built once for the engine
+    component_linker: Arc<std::sync::OnceLock<wasmtime::component::Linker<StoreData>>>,
+    /// Cached route responses, invalidated by plugins after writes
+    response_cache: Arc<ResponseCache>,
+    /// Cached plugin query results, invalidated by plugin writes
+    query_cache: Arc<RwLock<Option<Arc<QueryCache>>>>,
+    /// Object storage of plugin files (a local directory by default)
+    object_store: Arc<RwLock<Option<Arc<dyn ObjectStore>>>>,
+    /// Network usage of plugins, checked against their quotas
+    network_quotas: Arc<RwLock<Option<Arc<NetworkQuotas>>>>,
+    /// Key sealing persisted plugin state (encrypted profiles only)
+    state_key: Arc<RwLock<Option<Arc<EncryptionKey>>>>,
+    /// Latencies and error rates of every handler
+    handler_stats: Arc<HandlerStats>,
+    /// Memory use and failures of every plugin, with alerts
+    resource_monitor: Arc<PluginResourceMonitor>,
+    /// Host call authorization policy
+    policy: Arc<PolicyEngine>,
+    /// Configured plugin policy, with its per-plugin overrides
+    plugin_policy: Arc<RwLock<PluginsConfig>>,
+    /// Handlers recorded for deterministic replay, and where replay files go
+    replay_capture: Arc<RwLock<Option<Arc<ReplayCapture>>>>,
+}
+
+impl PluginRuntime {
+    /// Create a new plugin runtime.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true); // Enable fuel consumption for execution limits
+        config.epoch_interruption(true); // Enable epoch-based interruption for deadlines
+        config.max_wasm_stack(512 * 1024); // 512KB max stack
+        config.wasm_backtrace_details(WasmBacktraceDetails::Enable); // Source locations in trap reports
+
+        let engine = Engine::new(&config).expect("Failed to create WASM engine");
+        Self::spawn_epoch_ticker(&engine);
+
+        Self {
+            instances:    DashMap::new(),
+            engine,
+            plugins_dir:  Arc::new(RwLock::new(None)),
+            module_cache: Arc::new(RwLock::new(None)),
+            snapshots:    DashMap::new(),
+            tenant_overrides: DashMap::new(),
+            job_sink: Arc::new(RwLock::new(None)),
+            email_sink: Arc::new(RwLock::new(None)),
+            event_sink: Arc::new(RwLock::new(None)),
+            component_linker: Arc::default(),
+            response_cache: Arc::new(ResponseCache::new()),
+            query_cache: Arc::new(RwLock::new(None)),
+            object_store: Arc::new(RwLock::new(None)),
+            network_quotas: Arc::new(RwLock::new(None)),
+            state_key: Arc::new(RwLock::new(None)),
+            handler_stats: Arc::new(HandlerStats::new()),
+            resource_monitor: Arc::new(PluginResourceMonitor::new()),
+            policy: Arc::new(PolicyEngine::default()),
+            plugin_policy: Arc::new(RwLock::new(PluginsConfig::default())),
+            replay_capture: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Increment the engine epoch periodically until the engine is dropped.
+    fn spawn_epoch_ticker(engine: &Engine) {
+        let engine = engine.weak();
+        let spawned = std::thread::Builder::new()
+            .name("orbis-plugin-epoch".to_string())
+            .spawn(move || {
+                while let Some(engine) = engine.upgrade() {
+                    engine.increment_epoch();
+                    drop(engine);
+                    std::thread::sleep(EPOCH_TICK);
+                }
+            });
+
+        if let Err(e) = spawned {
+            tracing::error!("Failed to start plugin epoch ticker: {}", e);
+        }
+    }
+
+    /// Set the plugins directory for state persistence.
+    pub fn set_plugins_dir(&self, plugins_dir: std::path::PathBuf) {
+        *self.plugins_dir.write() = Some(plugins_dir);
+    }
+
+    /// Get the linker of component imports, building it on first use.
+    fn component_linker(&self) -> orbis_core::Result<&wasmtime::component::Linker<StoreData>> {
+        if let Some(linker) = self.component_linker.get() {
+            return Ok(linker);
+        }
+        let linker = component::linker(&self.engine)?;
+        Ok(self.component_linker.get_or_init(|| linker))
+    }
+
+    /// Set the precompiled module cache.
+    pub fn set_module_cache(&self, cache: ModuleCache) {
+        *self.module_cache.write() = Some(cache);
+    }
+
+    /// Set where jobs enqueued by plugins are sent.
+    ///
+    /// Without a sink, the `job_enqueue` host function fails.
+    pub fn set_job_sink(&self, sink: JobSink) {
+        *self.job_sink.write() = Some(sink);
+    }
+
+    /// Get the route response cache.
+    #[must_use]
+    pub const fn response_cache(&self) -> &Arc<ResponseCache> {
+        &self.response_cache
+    }
+
+    /// Get the handler execution statistics.
+    #[must_use]
+    pub const fn handler_stats(&self) -> &Arc<HandlerStats> {
+        &self.handler_stats
+    }
+
+    /// Get the plugin resource monitor.
+    #[must_use]
+    pub const fn resource_monitor(&self) -> &Arc<PluginResourceMonitor> {
+        &self.resource_monitor
+    }
+
+    /// Get the host call authorization policy engine.
+    #[must_use]
+    pub const fn policy(&self) -> &Arc<PolicyEngine> {
+        &self.policy
+    }
+
+    /// Set the configured plugin policy.
+    ///
+    /// Only plugins initialized afterwards get its memory limit and network
+    /// access.
+    pub fn set_plugin_policy(&self, config: PluginsConfig) {
+        *self.plugin_policy.write() = config;
+    }
+
+    /// Get the configured policy of a plugin.
+    #[must_use]
+    pub fn plugin_policy(&self, plugin_name: &str) -> PluginPolicyConfig {
+        self.plugin_policy.read().policy_for(plugin_name)
+    }
+
+    /// Set where emails sent by plugins go.
+    ///
+    /// Without a sink, the `email_send` host function fails.
+    pub fn set_email_sink(&self, sink: EmailSink) {
+        *self.email_sink.write() = Some(sink);
+    }
+
+    /// Set where events emitted by plugins go.
+    ///
+    /// Without a sink, emitted events are dropped.
+    pub fn set_event_sink(&self, sink: EventSink) {
+        *self.event_sink.write() = Some(sink);
+    }
+
+    /// Set the cache of plugin query results.
+    ///
+    /// Without a cache, every `db_query` runs against the database.
+    pub fn set_query_cache(&self, cache: Arc<QueryCache>) {
+        *self.query_cache.write() = Some(cache);
+    }
+
+    /// Set the object storage of plugin files.
+    ///
+    /// Without one, plugin files are kept in the plugins directory. Only
+    /// plugins loaded afterwards use it.
+    pub fn set_object_store(&self, store: Arc<dyn ObjectStore>) {
+        *self.object_store.write() = Some(store);
+    }
+
+    /// Get the object storage of plugin files, if set.
+    #[must_use]
+    pub fn object_store(&self) -> Option<Arc<dyn ObjectStore>> {
+        self.object_store.read().clone()
+    }
+
+    /// Set the key sealing the state plugins persist.
+    ///
+    /// Only plugins loaded afterwards seal their state.
+    pub fn set_state_key(&self, key: EncryptionKey) {
+        *self.state_key.write() = Some(Arc::new(key));
+    }
+
+    /// Set where plugin network usage is tracked.
+    ///
+    /// Without tracking, network quotas are not enforced.
+    pub fn set_network_quotas(&self, quotas: Arc<NetworkQuotas>) {
+        *self.network_quotas.write() = Some(quotas);
+    }
+
+    /// Set where replay files of recorded handlers are saved, enabling recording.
+    pub fn set_replay_capture(&self, capture: ReplayCapture) {
+        *self.replay_capture.write() = Some(Arc::new(capture));
+    }
+
+    /// Get the handlers recorded for replay, if recording is enabled.
+    #[must_use]
+    pub fn replay_capture(&self) -> Option<Arc<ReplayCapture>> {
+        self.replay_capture.read().clone()
+    }
+
+    /// Get the precompiled module cache, if set.
+    #[must_use]
+    pub fn module_cache(&self) -> Option<ModuleCache> {
+        self.module_cache.read().clone()
+    }
+
+    /// Check if a plugin has a specific permission.
+    #[must_use]
+    pub fn has_permission(&self, plugin_name: &str, permission: &str) -> bool {
+        self.instances
+            .get(plugin_name)
+            .map(|instance| instance.sandbox_config().has_permission(permission))
+            .unwrap_or(false)
+    }
+
+    /// Check if a plugin is allowed to access a network host.
+    #[must_use]
+    pub fn can_access_network(&self, plugin_name: &str, host: &str) -> bool {
+        self.instances
+            .get(plugin_name)
+            .map(|instance| instance.sandbox_config().can_access_network(host))
+            .unwrap_or(false)
+    }
+
+    /// Check if a plugin is allowed to access a file path.
+    #[must_use]
+    pub fn can_access_path(&self, plugin_name: &str, path: &str) -> bool {
+        self.instances
+            .get(plugin_name)
+            .map(|instance| instance.sandbox_config().can_access_path(path))
+            .unwrap_or(false)
+    }
+
+    /// Initialize a plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if initialization fails.
+    pub async fn initialize(
+        &self,
+        info: &PluginInfo,
+        source: &PluginSource,
+    ) -> orbis_core::Result<()> {
+        self.initialize_blocking(info, source, None)
+    }
+
+    /// Initialize a plugin on the current thread.
+    ///
+    /// Compilation is CPU-bound; this is used to initialize plugins from
+    /// worker threads during parallel startup loading. Each stage is reported
+    /// to the operation, if any.
+    pub(crate) fn initialize_blocking(
+        &self,
+        info: &PluginInfo,
+        source: &PluginSource,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<()> {
+        let staged = self.prepare(info, source, operation)?;
+        if let Some(migrated) = staged.migrated {
+            staged.instance.state.replace(migrated);
+        }
+        self.publish_snapshot(&info.manifest.name, staged.instance.snapshot.clone());
+        self.instances.insert(info.manifest.name.clone(), Arc::new(staged.instance));
+        Ok(())
+    }
+
+    /// Compile and set up a plugin instance without making it the plugin's
+    /// current instance, so it can be swapped in with [`PluginRuntime::commit`].
+    pub(crate) fn prepare(
+        &self,
+        info: &PluginInfo,
+        source: &PluginSource,
+        operation: Option<&PluginOperation>,
+    ) -> orbis_core::Result<StagedInstance> {
+        let enter = |stage| operation.map_or(Ok(()), |operation| operation.enter(stage));
+
+        enter(OperationStage::Extracting)?;
+        let loader = super::PluginLoader::new();
+        let code = loader.load_code(source, &info.manifest)?;
+        let abi_version = loader.check_abi_version(&info.manifest, &code)?;
+        let code_hash = {
+            use sha2::{Digest, Sha256};
+            format!("{:x}", Sha256::digest(&code))
+        };
+
+        enter(OperationStage::CompilingWasm)?;
+        let linker = self.component_linker()?;
+        let code = PluginCode::compile(&self.engine, linker, &code, self.module_cache.read().as_ref())?;
+
+        enter(OperationStage::Migrating)?;
+
+        // Create state with persistence if plugins directory is set
+        let state_dir = self.plugins_dir.read().as_ref().map(|dir| dir.join(".plugin_data"));
+        let state_key = self.state_key.read().clone();
+        let state = if let Some(ref state_dir) = state_dir {
+            let state_file = state_dir.join(format!("{}.json", info.manifest.name));
+            PluginState::with_encrypted_persistence(state_file, state_key.clone())
+        } else {
+            PluginState::new()
+        };
+        let object_store = self.object_store().or_else(|| {
+            state_dir
+                .as_ref()
+                .map(|dir| Arc::new(LocalStore::new(dir.clone())) as Arc<dyn ObjectStore>)
+        });
+        let mut files = FileBroker::new(
+            info.manifest.name.clone(),
+            info.manifest.requirements.filesystem.clone(),
+            state_dir.as_ref().map_or_else(|| std::env::temp_dir().join("orbis-plugins"), |dir| dir.join("tmp"))
+                .join(&info.manifest.name),
+        );
+        if let Some(store) = object_store.as_ref() {
+            files = files.with_store(Arc::clone(store));
+        }
+        // Temporary space does not outlive the plugin
+        files.clear_temp()?;
+        let tenants = Arc::new(TenantScopes::new(
+            state_dir.map(|dir| dir.join("tenants")),
+            state_key,
+            self.tenant_overrides.entry(info.manifest.name.clone()).or_default().clone(),
+        ));
+        
+        // Extract config from manifest
+        let config = if let Some(obj) = info.manifest.config.as_object() {
+            PluginConfig::from_settings(&obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        } else {
+            PluginConfig::new()
+        };
+
+        // The configured policy can take network access away and change the
+        // memory limit
+        let plugin_policy = self.plugin_policy(&info.manifest.name);
+        let permissions: Vec<PluginPermission> = info
+            .manifest
+            .permissions
+            .iter()
+            .filter(|permission| {
+                **permission != PluginPermission::Network
+                    || plugin_policy.network != Some(PluginNetworkAccess::Deny)
+            })
+            .cloned()
+            .collect();
+        let mut sandbox_config =
+            SandboxConfig::from_permissions(&permissions).with_filesystem(&info.manifest.requirements.filesystem);
+        if let Some(limit) = plugin_policy.max_memory_bytes {
+            sandbox_config = sandbox_config.with_memory_limit(limit);
+        }
+
+        let mut instance = PluginInstance {
+            engine: self.engine.clone(),
+            code,
+            abi_version,
+            sandbox_config: Arc::new(sandbox_config),
+            state,
+            config,
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants,
+            object_store,
+            files: Some(Arc::new(files)),
+            requirements: Arc::new(info.manifest.requirements.clone()),
+            resource_limits: info.manifest.limits,
+            network_quotas: Arc::clone(&self.network_quotas),
+            jobs: self.job_sink.clone(),
+            email: self.email_sink.clone(),
+            events: self.event_sink.clone(),
+            response_cache: Arc::clone(&self.response_cache),
+            query_cache: Arc::clone(&self.query_cache),
+            permissions: permissions.into(),
+            policy: Arc::clone(&self.policy),
+        };
+
+        let migrated = Self::migrate_state(&info.manifest, &mut instance)?;
+        let restarted = self
+            .snapshots
+            .get(&info.manifest.name)
+            .is_some_and(|snapshot| snapshot.code_hash() == code_hash);
+        instance.snapshot = self.snapshot(&info.manifest.name, &instance, code_hash)?;
+        instance.restore_snapshot = restarted && instance.snapshot.is_some();
+
+        Ok(StagedInstance { instance, migrated })
+    }
+
+    /// Run the migrations of a plugin's state up to its manifest's
+    /// `state_version`, returning the state to store.
+    ///
+    /// Migrations run against a copy of the stored state, which is left
+    /// untouched until the instance is installed, or if a migration fails.
+    /// Tenants' state is not migrated.
+    fn migrate_state(
+        manifest: &PluginManifest,
+        instance: &mut PluginInstance,
+    ) -> orbis_core::Result<Option<HashMap<String, serde_json::Value>>> {
+        let name = &manifest.name;
+        let target = manifest.state_version();
+        let stored = match instance.state.get(STATE_VERSION_KEY) {
+            Some(version) => version.as_u64().and_then(|version| u32::try_from(version).ok()).ok_or_else(|| {
+                orbis_core::Error::plugin(format!("Plugin '{}' has an invalid state version: {}", name, version))
+            })?,
+            // New state starts at the current version
+            None if instance.state.keys().is_empty() => {
+                let version = (STATE_VERSION_KEY.to_string(), serde_json::json!(target));
+                return Ok((target > 1).then(|| HashMap::from([version])));
+            }
+            // State stored before the plugin declared a version
+            None => 1,
+        };
+
+        if stored > target {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' state is at version {}, newer than its state_version {}; restore the state from \
+                 a backup to downgrade it",
+                name, stored, target
+            )));
+        }
+        if stored == target {
+            return Ok(None);
+        }
+
+        let copy = instance.state.detached();
+        let persisted = std::mem::replace(&mut instance.state, copy);
+        let result = Self::run_migrations(name, instance, stored, target);
+        let migrated = std::mem::replace(&mut instance.state, persisted);
+        result?;
+
+        Ok(Some(migrated.entries()))
+    }
+
+    /// Run the migration handler of each state version step, recording the
+    /// version reached after each.
+    fn run_migrations(name: &str, instance: &PluginInstance, from: u32, to: u32) -> orbis_core::Result<()> {
+        for version in from..to {
+            let next = version.saturating_add(1);
+            let handler = state_migration_handler(version);
+            tracing::info!("Migrating state of plugin '{}' from version {} to {}", name, version, next);
+
+            let context = PluginContext {
+                method: "POST".to_string(),
+                path: format!("/state/migrate/{}", next),
+                headers: HashMap::new(),
+                query: HashMap::new(),
+                body: serde_json::json!({ "from": version, "to": next }),
+                user_id: None,
+                is_admin: false,
+                tenant_id: None,
+                deadline: None,
+                groups: Vec::new(),
+                features: std::collections::BTreeMap::new(),
+                cancellation: CancellationFlag::new(),
+            };
+            let (result, _, _) = Self::execute_blocking(instance, name, &handler, &context, None);
+            result.map_err(|e| {
+                orbis_core::Error::plugin(format!(
+                    "Plugin '{}' failed to migrate its state from version {} to {}: {}",
+                    name, version, next, e
+                ))
+            })?;
+            instance.state.set(STATE_VERSION_KEY.to_string(), serde_json::json!(next));
+        }
+
+        Ok(())
+    }
+
+    /// Swap a staged instance in for a plugin's current one, returning what it
+    /// replaced for [`PluginRuntime::restore`].
+    ///
+    /// The staged instance takes over the state of the current one, including
+    /// its tenants' state, and stores the state migrated while it was staged. Without `activate` (for lazily activated plugins),
+    /// the current instance is only dropped, and the next use creates an
+    /// instance of the new version.
+    pub(crate) fn commit(&self, name: &str, staged: StagedInstance, activate: bool) -> ReplacedInstance {
+        let mut instance = staged.instance;
+        let current = self.instances.get(name).map(|current| Arc::clone(current.value()));
+        if let Some(current) = current.as_ref() {
+            instance.state = current.state.clone();
+            instance.tenants = Arc::clone(&current.tenants);
+        }
+        if let Some(migrated) = staged.migrated {
+            instance.state.replace(migrated);
+        }
+
+        let replaced = ReplacedInstance {
+            instance: current,
+            snapshot: self.snapshots.get(name).map(|snapshot| Arc::clone(snapshot.value())),
+        };
+
+        self.publish_snapshot(name, instance.snapshot.clone());
+        if activate {
+            self.instances.insert(name.to_string(), Arc::new(instance));
+        } else {
+            self.instances.remove(name);
+        }
+        self.handler_stats.clear_plugin(name);
+        self.resource_monitor.clear_plugin(name);
+        self.policy.clear_plugin(name);
+        tracing::debug!("Swapped in new instance of plugin: {}", name);
+
+        replaced
+    }
+
+    /// Put back the instance replaced by [`PluginRuntime::commit`], with its
+    /// state. State migrated by the staged instance is kept.
+    pub(crate) fn restore(&self, name: &str, replaced: ReplacedInstance) {
+        match replaced.instance {
+            Some(instance) => {
+                self.instances.insert(name.to_string(), instance);
+            }
+            None => {
+                self.instances.remove(name);
+            }
+        }
+        self.publish_snapshot(name, replaced.snapshot);
+        tracing::debug!("Restored previous instance of plugin: {}", name);
+    }
+
+    /// Keep a plugin's memory snapshot for later instantiations, or forget it.
+    fn publish_snapshot(&self, name: &str, snapshot: Option<Arc<MemorySnapshot>>) {
+        match snapshot {
+            Some(snapshot) => {
+                self.snapshots.insert(name.to_string(), snapshot);
+            }
+            None => {
+                self.snapshots.remove(name);
+            }
+        }
+    }
+
+    /// Start a plugin.
+    ///
+    /// This is called when a plugin is enabled. The heavy initialization
+    /// (WASM compilation and module instantiation) happens in `initialize()`.
+    /// This method just verifies the plugin is ready to run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be started.
+    pub async fn start(&self, name: &str) -> orbis_core::Result<()> {
+        // Just verify the plugin is initialized
+        let _instance = self.instances.get(name).ok_or_else(|| {
+            orbis_core::Error::plugin(format!("Plugin '{}' not initialized", name))
+        })?;
+
+        tracing::debug!("Started plugin: {}", name);
+        Ok(())
+    }
+
+    /// Stop a plugin.
+    ///
+    /// This is called when a plugin is disabled. It clears runtime state
+    /// but keeps the compiled WASM module cached for fast re-enable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin cannot be stopped.
+    pub async fn stop(&self, name: &str) -> orbis_core::Result<()> {
+        // Just verify plugin exists, don't remove it (keep module cached)
+        if let Some(instance) = self.instances.get(name) {
+            // Only clear runtime state, not the instance itself
+            instance.state.clear();
+            instance.tenants.clear();
+            tracing::debug!("Stopped plugin: {}", name);
+        }
+        Ok(())
+    }
+
+    /// Execute a plugin handler.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if execution fails.
+    pub async fn execute(
+        &self,
+        plugin_name: &str,
+        handler: &str,
+        context: PluginContext,
+    ) -> orbis_core::Result<serde_json::Value> {
+        let instance = self
+            .instances
+            .get(plugin_name)
+            .map(|instance| instance.clone())
+            .ok_or_else(|| {
+                orbis_core::Error::plugin(format!("Plugin '{}' not running", plugin_name))
+            })?;
+
+        // Run on a blocking thread so dropping the request future can cancel execution
+        let plugin_name = plugin_name.to_string();
+        let handler = handler.to_string();
+        let _cancel_on_drop = context.cancellation.cancel_on_drop();
+        let stats = self.handler_stats.clone();
+        let monitor = self.resource_monitor.clone();
+        let capture = self
+            .replay_capture()
+            .filter(|capture| matches!(instance.code, PluginCode::Module(_)) && capture.take(&plugin_name, &handler));
+        tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            let tape = capture.is_some().then(HostTape::recording);
+            let (result, peak_memory, tape) = Self::execute_blocking(&instance, &plugin_name, &handler, &context, tape);
+            stats.record(&plugin_name, &handler, started.elapsed(), peak_memory, result.is_err(), &context);
+            monitor.record(&plugin_name, peak_memory, result.is_err());
+            if let Some(capture) = capture {
+                let calls = tape.map(HostTape::into_calls).unwrap_or_default();
+                let recording = ReplayRecording::new(&plugin_name, &handler, &context, calls, &result);
+                match capture.save(&recording) {
+                    Ok(path) => tracing::info!("Recorded {}.{} for replay: {}", plugin_name, handler, path.display()),
+                    Err(e) => tracing::warn!("Failed to save replay of {}.{}: {}", plugin_name, handler, e),
+                }
+            }
+            result
+        })
+        .await
+        .map_err(|e| orbis_core::Error::plugin(format!("Plugin execution task failed: {}", e)))?
+    }
+
+    /// Execute a plugin handler on the current thread, recording or
+    /// replaying its host calls on the tape if given, and also returning the
+    /// peak linear memory it used, in bytes, and the tape.
+    fn execute_blocking(
+        instance: &PluginInstance,
+        plugin_name: &str,
+        handler: &str,
+        context: &PluginContext,
+        tape: Option<HostTape>,
+    ) -> (orbis_core::Result<serde_json::Value>, usize, Option<HostTape>) {
+        // Create store for execution
+        let mut store = match instance.new_store(plugin_name, context.tenant_id.as_deref()) {
+            Ok(store) => store,
+            Err(e) => return (Err(e), 0, tape),
+        };
+        store
+            .data_mut()
+            .set_request(context.deadline, context.cancellation.clone());
+        store.data_mut().groups.clone_from(&context.groups);
+        store.data_mut().tape = tape;
+
+        let result = match &instance.code {
+            PluginCode::Module(module) => Self::run_module(instance, &mut store, module, handler, context),
+            PluginCode::Component(component) => component::execute(&mut store, component, handler, context),
+        };
+        let tape = store.data_mut().tape.take();
+        (result, store.data().limits.peak, tape)
+    }
+
+    /// Replay a recorded handler invocation against the running plugin.
+    ///
+    /// The handler runs with the recorded context, and its host calls are
+    /// answered from the recording, so it runs as it did when recorded
+    /// without touching state, the database, files or the network.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin is not running or is a component.
+    pub async fn replay(&self, recording: &ReplayRecording) -> orbis_core::Result<ReplayOutcome> {
+        let instance = self
+            .instances
+            .get(&recording.plugin)
+            .map(|instance| instance.clone())
+            .ok_or_else(|| {
+                orbis_core::Error::plugin(format!("Plugin '{}' not running", recording.plugin))
+            })?;
+        if !matches!(instance.code, PluginCode::Module(_)) {
+            return Err(orbis_core::Error::plugin(format!(
+                "Plugin '{}' is a component; only core module plugins can be replayed",
+                recording.plugin
+            )));
+        }
+
+        let recording = recording.clone();
+        tokio::task::spawn_blocking(move || {
+            let tape = HostTape::replaying(&recording.calls);
+            let (result, _, tape) =
+                Self::execute_blocking(&instance, &recording.plugin, &recording.handler, &recording.context, Some(tape));
+            ReplayOutcome::new(&recording, &result, tape)
+        })
+        .await
+        .map_err(|e| orbis_core::Error::plugin(format!("Plugin replay task failed: {}", e)))
+    }
+
+    /// Run a handler of a core module plugin in a store.
+    fn run_module(
+        instance: &PluginInstance,
+        store: &mut Store<StoreData>,
+        module: &Module,
+        handler: &str,
+        context: &PluginContext,
+    ) -> orbis_core::Result<serde_json::Value> {
+
+        // Create linker with host functions
+        let mut linker = Linker::new(&instance.engine);
+        Self::register_host_functions(&mut linker)?;
+
+        // Instantiate the module
+        let wasm_instance = linker
+            .instantiate(&mut *store, module)
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to instantiate plugin: {}", e))
+            })?;
+
+        // Get memory for data transfer
+        let memory = wasm_instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| orbis_core::Error::plugin("Plugin memory not found"))?;
+
+        // A restarted instance skipped `init`, so each run starts from the post-init state
+        if instance.restore_snapshot
+            && let Some(snapshot) = instance.snapshot.as_ref()
+        {
+            snapshot.restore(&mut *store, &wasm_instance, &memory)?;
+        }
+
+        // Serialize context to JSON
+        let context_json = serde_json::to_vec(context).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize context: {}", e))
+        })?;
+
+        // Allocate memory in WASM for the context
+        let (context_ptr, context_len) =
+            Self::allocate_and_write(&mut *store, &memory, &wasm_instance, &context_json)?;
+
+        // Get the handler function
+        let handler_func = wasm_instance
+            .get_func(&mut *store, handler)
+            .ok_or_else(|| {
+                orbis_core::Error::plugin(format!("Handler '{}' not found", handler))
+            })?;
+
+        // Call the handler with (ptr: i32, len: i32) -> i32 signature
+        // The return value is a pointer to the result JSON
+        let handler_typed: TypedFunc<(i32, i32), i32> = handler_func.typed(&*store).map_err(|e| {
+            orbis_core::Error::plugin(format!("Handler '{}' has wrong signature: {}", handler, e))
+        })?;
+
+        let result_ptr = handler_typed
+            .call(&mut *store, (context_ptr as i32, context_len as i32))
+            .map_err(|e| handler_error(store, handler, context, &e))?;
+
+        // Read the result from WASM memory
+        let mut result = Self::read_result(&mut *store, &memory, result_ptr as u32)?;
+        if instance.abi_version.needs_shims() {
+            result = Self::apply_abi_shims(instance.abi_version, result);
+        }
+
+        // Deallocate the context memory
+        Self::deallocate(&mut *store, &wasm_instance, context_ptr, context_len)?;
+
+        Ok(result)
+    }
+
+    /// Check if a plugin is running.
+    #[must_use]
+    pub fn is_running(&self, name: &str) -> bool {
+        self.instances.contains_key(name)
+    }
+
+    /// Clear cached data for a plugin.
+    ///
+    /// This is used during hot reload to ensure fresh module compilation.
+    pub fn clear_cache(&self, name: &str) {
+        if let Some((_, instance)) = self.instances.remove(name) {
+            instance.state.clear();
+            instance.tenants.clear();
+        }
+        self.snapshots.remove(name);
+        self.handler_stats.clear_plugin(name);
+        self.resource_monitor.clear_plugin(name);
+        self.policy.clear_plugin(name);
+        tracing::debug!("Cleared cache for plugin: {}", name);
+    }
+
+    /// Drop a plugin's compiled instance while keeping its state.
+    ///
+    /// Used to unload idle lazily activated plugins; the next call to
+    /// `initialize()` recreates the instance from its memory snapshot.
+    pub fn deactivate(&self, name: &str) -> bool {
+        let removed = self.instances.remove(name).is_some();
+        if removed {
+            tracing::debug!("Deactivated plugin: {}", name);
+        }
+        removed
+    }
+
+    /// Take the report of a plugin's last trap, if it has trapped since the last call.
+    #[must_use]
+    pub fn take_trap(&self, name: &str) -> Option<TrapReport> {
+        self.instances.get(name).and_then(|instance| instance.last_trap.lock().take())
+    }
+
+    /// Get information about a plugin's post-init memory snapshot.
+    #[must_use]
+    pub fn snapshot_info(&self, name: &str) -> Option<SnapshotInfo> {
+        self.snapshots.get(name).map(|snapshot| snapshot.info())
+    }
+
+    /// Get the memory snapshot for a plugin instance, taking one if needed.
+    ///
+    /// An existing snapshot is reused when the plugin code is unchanged, so
+    /// reactivation skips `init`. Components are not snapshotted.
+    fn snapshot(
+        &self,
+        plugin_name: &str,
+        instance: &PluginInstance,
+        code_hash: String,
+    ) -> orbis_core::Result<Option<Arc<MemorySnapshot>>> {
+        let PluginCode::Module(module) = &instance.code else {
+            return Ok(None);
+        };
+
+        if let Some(snapshot) = self.snapshots.get(plugin_name)
+            && snapshot.code_hash() == code_hash
+        {
+            tracing::debug!("Reusing memory snapshot for plugin: {}", plugin_name);
+            return Ok(Some(snapshot.clone()));
+        }
+
+        let mut store = instance.new_store(plugin_name, None)?;
+        let mut linker = Linker::new(&instance.engine);
+        Self::register_host_functions(&mut linker)?;
+
+        let wasm_instance = linker.instantiate(&mut store, module).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to instantiate plugin: {}", e))
+        })?;
+
+        let Some(snapshot) = MemorySnapshot::capture(&mut store, module, &wasm_instance, code_hash)? else {
+            return Ok(None);
+        };
+
+        let snapshot = Arc::new(snapshot);
+        tracing::debug!(
+            "Took {} byte memory snapshot for plugin: {}",
+            snapshot.info().size_bytes,
+            plugin_name
+        );
+
+        Ok(Some(snapshot))
+    }
+
+    /// Get plugin state (for inspection/debugging)
+    #[must_use]
+    pub fn get_state(&self, name: &str) -> Option<PluginState> {
+        self.instances.get(name).map(|i| i.state.clone())
+    }
+
+    /// Get a tenant's plugin state (for inspection/debugging)
+    #[must_use]
+    pub fn get_tenant_state(&self, name: &str, tenant: &str) -> Option<PluginState> {
+        self.instances
+            .get(name)
+            .and_then(|instance| instance.tenants.state(name, tenant).ok())
+    }
+
+    /// Export a plugin's state, tenant states and data files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin is not running or its files cannot be read.
+    pub async fn export_data(&self, name: &str) -> orbis_core::Result<PluginDataArchive> {
+        let (mut archive, object_store) = {
+            let instance = self
+                .instances
+                .get(name)
+                .ok_or_else(|| orbis_core::Error::plugin(format!("Plugin '{}' is not running", name)))?;
+
+            let mut archive = PluginDataArchive::new(name);
+            archive.state = instance.state.entries();
+            archive.tenants = instance.tenants.export(name);
+            (archive, instance.object_store.clone())
+        };
+        if let Some(store) = object_store {
+            archive.files = archive::read_files(store.as_ref(), &storage::plugin_files_prefix(name)).await?;
+        }
+
+        Ok(archive)
+    }
+
+    /// Replace a plugin's state, tenant states and data files with the contents of an archive.
+    ///
+    /// Database tables in the archive are not touched; see
+    /// [`PluginManager::import_plugin_data`](crate::PluginManager::import_plugin_data).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plugin is not running or the data cannot be written.
+    pub async fn import_data(&self, name: &str, archive: &PluginDataArchive) -> orbis_core::Result<()> {
+        let object_store = self
+            .instances
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::plugin(format!("Plugin '{}' is not running", name)))?
+            .object_store
+            .clone();
+        if let Some(store) = object_store {
+            archive::write_files(store.as_ref(), &storage::plugin_files_prefix(name), &archive.files).await?;
+        }
+
+        let instance = self
+            .instances
+            .get(name)
+            .ok_or_else(|| orbis_core::Error::plugin(format!("Plugin '{}' is not running", name)))?;
+        instance.state.replace(archive.state.clone());
+        instance.tenants.import(name, &archive.tenants)
+    }
+
+    /// Set a tenant's configuration overrides for a plugin.
+    ///
+    /// Overrides are kept across plugin reloads and apply from the next execution.
+    pub fn set_tenant_config(&self, name: &str, tenant: &str, overrides: HashMap<String, serde_json::Value>) {
+        self.tenant_overrides
+            .entry(name.to_string())
+            .or_default()
+            .insert(tenant.to_string(), overrides);
+    }
+
+    /// Register host functions that plugins can call
+    fn register_host_functions(linker: &mut Linker<StoreData>) -> orbis_core::Result<()> {
+        // State management functions
+        linker
+            .func_wrap(
+                "env",
+                "state_get",
+                |mut caller: Caller<'_, StoreData>, key_ptr: i32, key_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "state_get", &[(key_ptr, key_len)], &[], |caller| {
+                        Self::host_state_get(caller, key_ptr as u32, key_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("state_get error: {}", e);
+                            0 // Return null pointer on error
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register state_get: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "state_set",
+                |mut caller: Caller<'_, StoreData>,
+                 key_ptr: i32,
+                 key_len: i32,
+                 value_ptr: i32,
+                 value_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "state_set",
+                        &[(key_ptr, key_len), (value_ptr, value_len)],
+                        &[],
+                        |caller| {
+                            Self::host_state_set(
+                                caller,
+                                key_ptr as u32,
+                                key_len as u32,
+                                value_ptr as u32,
+                                value_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(()) => 1, // Success
+                        Err(e) => {
+                            tracing::error!("state_set error: {}", e);
+                            0 // Failure
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register state_set: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "state_remove",
+                |mut caller: Caller<'_, StoreData>, key_ptr: i32, key_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "state_remove", &[(key_ptr, key_len)], &[], |caller| {
+                        Self::host_state_remove(caller, key_ptr as u32, key_len as u32)
+                    }) {
+                        Ok(()) => 1, // Success
+                        Err(e) => {
+                            tracing::error!("state_remove error: {}", e);
+                            0 // Failure
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register state_remove: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "state_get_many",
+                |mut caller: Caller<'_, StoreData>, keys_ptr: i32, keys_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "state_get_many", &[(keys_ptr, keys_len)], &[], |caller| {
+                        Self::host_state_get_many(caller, keys_ptr as u32, keys_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("state_get_many error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register state_get_many: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "state_set_many",
+                |mut caller: Caller<'_, StoreData>, entries_ptr: i32, entries_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "state_set_many", &[(entries_ptr, entries_len)], &[], |caller| {
+                        Self::host_state_set_many(caller, entries_ptr as u32, entries_len as u32)
+                    }) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("state_set_many error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register state_set_many: {}", e))
+            })?;
+
+        // Logging functions
+        linker
+            .func_wrap(
+                "env",
+                "log",
+                |mut caller: Caller<'_, StoreData>, level: i32, ptr: i32, len: i32| {
+                    if let Err(e) = Self::host_log(&mut caller, level, ptr as u32, len as u32) {
+                        tracing::error!("log error: {}", e);
+                    }
+                },
+            )
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to register log: {}", e)))?;
+
+        // Memory management functions
+        linker
+            .func_wrap("env", "allocate", |_caller: Caller<'_, StoreData>, size: i32| -> i32 {
+                // This is a placeholder - actual allocation happens in WASM
+                size
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register allocate: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "deallocate",
+                |_caller: Caller<'_, StoreData>, _ptr: i32, _size: i32| {
+                    // Placeholder for WASM-side deallocation
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register deallocate: {}", e))
+            })?;
+
+        // Database functions
+        linker
+            .func_wrap(
+                "env",
+                "db_query",
+                |mut caller: Caller<'_, StoreData>,
+                 query_ptr: i32,
+                 query_len: i32,
+                 params_ptr: i32,
+                 params_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "db_query",
+                        &[(query_ptr, query_len), (params_ptr, params_len)],
+                        &[],
+                        |caller| {
+                            Self::host_db_query(
+                                caller,
+                                query_ptr as u32,
+                                query_len as u32,
+                                params_ptr as u32,
+                                params_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("db_query error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register db_query: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "db_execute",
+                |mut caller: Caller<'_, StoreData>,
+                 query_ptr: i32,
+                 query_len: i32,
+                 params_ptr: i32,
+                 params_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "db_execute",
+                        &[(query_ptr, query_len), (params_ptr, params_len)],
+                        &[],
+                        |caller| {
+                            Self::host_db_execute(
+                                caller,
+                                query_ptr as u32,
+                                query_len as u32,
+                                params_ptr as u32,
+                                params_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(rows) => rows as i32,
+                        Err(e) => {
+                            tracing::error!("db_execute error: {}", e);
+                            -1
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register db_execute: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "db_query_batch",
+                |mut caller: Caller<'_, StoreData>, queries_ptr: i32, queries_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "db_query_batch", &[(queries_ptr, queries_len)], &[], |caller| {
+                        Self::host_db_query_batch(caller, queries_ptr as u32, queries_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("db_query_batch error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register db_query_batch: {}", e))
+            })?;
+
+        // HTTP functions
+        linker
+            .func_wrap(
+                "env",
+                "http_request",
+                |mut caller: Caller<'_, StoreData>,
+                 method_ptr: i32,
+                 method_len: i32,
+                 url_ptr: i32,
+                 url_len: i32,
+                 headers_ptr: i32,
+                 headers_len: i32,
+                 body_ptr: i32,
+                 body_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "http_request",
+                        &[
+                            (method_ptr, method_len),
+                            (url_ptr, url_len),
+                            (headers_ptr, headers_len),
+                            (body_ptr, body_len),
+                        ],
+                        &[],
+                        |caller| {
+                            Self::host_http_request(
+                                caller,
+                                method_ptr as u32,
+                                method_len as u32,
+                                url_ptr as u32,
+                                url_len as u32,
+                                headers_ptr as u32,
+                                headers_len as u32,
+                                body_ptr as u32,
+                                body_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("http_request error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register http_request: {}", e))
+            })?;
+
+        // Event functions
+        linker
+            .func_wrap(
+                "env",
+                "emit_event",
+                |mut caller: Caller<'_, StoreData>,
+                 event_ptr: i32,
+                 event_len: i32,
+                 payload_ptr: i32,
+                 payload_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "emit_event",
+                        &[(event_ptr, event_len), (payload_ptr, payload_len)],
+                        &[],
+                        |caller| {
+                            Self::host_emit_event(
+                                caller,
+                                event_ptr as u32,
+                                event_len as u32,
+                                payload_ptr as u32,
+                                payload_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("emit_event error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register emit_event: {}", e))
+            })?;
+
+        // Config functions
+        linker
+            .func_wrap(
+                "env",
+                "get_config",
+                |mut caller: Caller<'_, StoreData>, key_ptr: i32, key_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "get_config", &[(key_ptr, key_len)], &[], |caller| {
+                        Self::host_get_config(caller, key_ptr as u32, key_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("get_config error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register get_config: {}", e))
+            })?;
+
+        // Host information functions
+        linker
+            .func_wrap("env", "host_info", |mut caller: Caller<'_, StoreData>| -> i32 {
+                match Self::taped(&mut caller, "host_info", &[], &[], |caller| {
+                    Self::host_get_info(caller)
+                }) {
+                    Ok(ptr) => ptr as i32,
+                    Err(e) => {
+                        tracing::error!("host_info error: {}", e);
+                        0
+                    }
+                }
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register host_info: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "host_env",
+                |mut caller: Caller<'_, StoreData>, name_ptr: i32, name_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "host_env", &[(name_ptr, name_len)], &[], |caller| {
+                        Self::host_get_env(caller, name_ptr as u32, name_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("host_env error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register host_env: {}", e))
+            })?;
+
+        // Group membership functions
+        linker
+            .func_wrap("env", "groups", |mut caller: Caller<'_, StoreData>| -> i32 {
+                match Self::taped(&mut caller, "groups", &[], &[], Self::host_groups) {
+                    Ok(ptr) => ptr as i32,
+                    Err(e) => {
+                        tracing::error!("groups error: {}", e);
+                        0
+                    }
+                }
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register groups: {}", e))
+            })?;
+
+        // Cancellation functions
+        linker
+            .func_wrap("env", "is_cancelled", |caller: Caller<'_, StoreData>| -> i32 {
+                i32::from(caller.data().interruption().is_some())
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register is_cancelled: {}", e))
+            })?;
+
+        // Security policy functions
+        linker
+            .func_wrap("env", "host_denial", |mut caller: Caller<'_, StoreData>| -> i32 {
+                match Self::taped(&mut caller, "host_denial", &[], &[], |caller| {
+                    Self::host_denial(caller)
+                }) {
+                    Ok(ptr) => ptr as i32,
+                    Err(e) => {
+                        tracing::error!("host_denial error: {}", e);
+                        0
+                    }
+                }
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register host_denial: {}", e))
+            })?;
+
+        // Job functions
+        linker
+            .func_wrap(
+                "env",
+                "job_enqueue",
+                |mut caller: Caller<'_, StoreData>, job_ptr: i32, job_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "job_enqueue", &[(job_ptr, job_len)], &[], |caller| {
+                        Self::host_job_enqueue(caller, job_ptr as u32, job_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("job_enqueue error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register job_enqueue: {}", e))
+            })?;
+
+        // Cache functions
+        linker
+            .func_wrap(
+                "env",
+                "cache_invalidate",
+                |mut caller: Caller<'_, StoreData>, route_ptr: i32, route_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "cache_invalidate", &[(route_ptr, route_len)], &[], |caller| {
+                        Self::host_cache_invalidate(caller, route_ptr as u32, route_len as u32)
+                    }) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("cache_invalidate error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register cache_invalidate: {}", e))
+            })?;
+
+        // Email functions
+        linker
+            .func_wrap(
+                "env",
+                "email_send",
+                |mut caller: Caller<'_, StoreData>, email_ptr: i32, email_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "email_send", &[(email_ptr, email_len)], &[], |caller| {
+                        Self::host_email_send(caller, email_ptr as u32, email_len as u32)
+                    }) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("email_send error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register email_send: {}", e))
+            })?;
+
+        // Media functions
+        linker
+            .func_wrap(
+                "env",
+                "media_probe",
+                |mut caller: Caller<'_, StoreData>, data_ptr: i32, data_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "media_probe", &[(data_ptr, data_len)], &[], |caller| {
+                        Self::host_media_probe(caller, data_ptr as u32, data_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("media_probe error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register media_probe: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "media_thumbnail",
+                |mut caller: Caller<'_, StoreData>, data_ptr: i32, data_len: i32, width: i32, height: i32| -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "media_thumbnail",
+                        &[(data_ptr, data_len)],
+                        &[i64::from(width), i64::from(height)],
+                        |caller| {
+                            Self::host_media_thumbnail(
+                                caller,
+                                data_ptr as u32,
+                                data_len as u32,
+                                width as u32,
+                                height as u32,
+                            )
+                        },
+                    ) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("media_thumbnail error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register media_thumbnail: {}", e))
+            })?;
+
+        // File functions
+        linker
+            .func_wrap(
+                "env",
+                "file_read",
+                |mut caller: Caller<'_, StoreData>, path_ptr: i32, path_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "file_read", &[(path_ptr, path_len)], &[], |caller| {
+                        Self::host_file_read(caller, path_ptr as u32, path_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("file_read error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register file_read: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "file_write",
+                |mut caller: Caller<'_, StoreData>, path_ptr: i32, path_len: i32, data_ptr: i32, data_len: i32| -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "file_write",
+                        &[(path_ptr, path_len), (data_ptr, data_len)],
+                        &[],
+                        |caller| {
+                            Self::host_file_write(
+                                caller,
+                                path_ptr as u32,
+                                path_len as u32,
+                                data_ptr as u32,
+                                data_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("file_write error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register file_write: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "file_list",
+                |mut caller: Caller<'_, StoreData>, path_ptr: i32, path_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "file_list", &[(path_ptr, path_len)], &[], |caller| {
+                        Self::host_file_list(caller, path_ptr as u32, path_len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("file_list error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register file_list: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "file_remove",
+                |mut caller: Caller<'_, StoreData>, path_ptr: i32, path_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "file_remove", &[(path_ptr, path_len)], &[], |caller| {
+                        Self::host_file_remove(caller, path_ptr as u32, path_len as u32)
+                    }) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("file_remove error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register file_remove: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "file_url",
+                |mut caller: Caller<'_, StoreData>, path_ptr: i32, path_len: i32, ttl_seconds: i32| -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "file_url",
+                        &[(path_ptr, path_len)],
+                        &[i64::from(ttl_seconds)],
+                        |caller| Self::host_file_url(caller, path_ptr as u32, path_len as u32, ttl_seconds as u32),
+                    ) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("file_url error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register file_url: {}", e))
+            })?;
+
+        // Data export/import functions
+        linker
+            .func_wrap("env", "data_export", |mut caller: Caller<'_, StoreData>| -> i32 {
+                match Self::taped(&mut caller, "data_export", &[], &[], |caller| {
+                    Self::host_data_export(caller)
+                }) {
+                    Ok(ptr) => ptr as i32,
+                    Err(e) => {
+                        tracing::error!("data_export error: {}", e);
+                        0
+                    }
+                }
+            })
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register data_export: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "data_import",
+                |mut caller: Caller<'_, StoreData>, archive_ptr: i32, archive_len: i32| -> i32 {
+                    match Self::taped(&mut caller, "data_import", &[(archive_ptr, archive_len)], &[], |caller| {
+                        Self::host_data_import(caller, archive_ptr as u32, archive_len as u32)
+                    }) {
+                        Ok(()) => 1,
+                        Err(e) => {
+                            tracing::error!("data_import error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register data_import: {}", e))
+            })?;
+
+        // Crypto functions
+        linker
+            .func_wrap(
+                "env",
+                "crypto_hash",
+                |mut caller: Caller<'_, StoreData>,
+                 algorithm: i32,
+                 data_ptr: i32,
+                 data_len: i32|
+                 -> i32 {
+                    match Self::taped(
+                        &mut caller,
+                        "crypto_hash",
+                        &[(data_ptr, data_len)],
+                        &[i64::from(algorithm)],
+                        |caller| {
+                            Self::host_crypto_hash(
+                                caller,
+                                algorithm,
+                                data_ptr as u32,
+                                data_len as u32,
+                            )
+                        },
+                    ) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("crypto_hash error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register crypto_hash: {}", e))
+            })?;
+
+        linker
+            .func_wrap(
+                "env",
+                "crypto_random",
+                |mut caller: Caller<'_, StoreData>, len: i32| -> i32 {
+                    match Self::taped(&mut caller, "crypto_random", &[], &[i64::from(len)], |caller| {
+                        Self::host_crypto_random(caller, len as u32)
+                    }) {
+                        Ok(ptr) => ptr as i32,
+                        Err(e) => {
+                            tracing::error!("crypto_random error: {}", e);
+                            0
+                        }
+                    }
+                },
+            )
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to register crypto_random: {}", e))
+            })?;
+
+        Ok(())
+    }
+
+    /// Run a host function through the store's tape, if the handler is
+    /// recorded or replayed.
+    ///
+    /// Recording keeps the memory regions and scalar arguments the call read,
+    /// with what it returned or wrote to plugin memory. Replaying answers the
+    /// call from the recording without running it.
+    fn taped<T: TapeValue>(
+        caller: &mut Caller<'_, StoreData>,
+        function: &str,
+        regions: &[(i32, i32)],
+        args: &[i64],
+        call: impl FnOnce(&mut Caller<'_, StoreData>) -> orbis_core::Result<T>,
+    ) -> orbis_core::Result<T> {
+        let Some(replaying) = caller.data().tape.as_ref().map(HostTape::is_replaying) else {
+            return call(caller);
+        };
+
+        let memory = Self::get_memory(caller)?;
+        let input = regions
+            .iter()
+            .map(|&(ptr, len)| Self::read_memory(caller, &memory, ptr as u32, len as u32))
+            .collect::<orbis_core::Result<Vec<_>>>()?;
+
+        if replaying {
+            let recorded = caller
+                .data_mut()
+                .tape
+                .as_mut()
+                .map_or_else(|| Err(orbis_core::Error::plugin("Replay ended")), |tape| tape.next(function, &input, args))?;
+            if let Some(error) = recorded.error {
+                return Err(orbis_core::Error::plugin(error));
+            }
+            return recorded.output.map_or_else(
+                || Ok(T::from_raw(recorded.value)),
+                |output| Self::allocate_and_write_bytes(caller, &output.0).map(|(ptr, _)| T::from_raw(i64::from(ptr))),
+            );
+        }
+
+        if let Some(ref mut tape) = caller.data_mut().tape {
+            tape.take_output();
+        }
+        let result = call(caller);
+        if let Some(ref mut tape) = caller.data_mut().tape {
+            let output = tape.take_output();
+            tape.record(RecordedCall {
+                function: function.to_owned(),
+                input: input.into_iter().map(ReplayBytes).collect(),
+                args: args.to_vec(),
+                value: result.as_ref().map_or(0, TapeValue::to_raw),
+                output: output.filter(|_| result.is_ok()).map(ReplayBytes),
+                error: result.as_ref().err().map(|e| {
+                    if let orbis_core::Error::Plugin(ref message) = *e {
+                        message.clone()
+                    } else {
+                        e.to_string()
+                    }
+                }),
+            });
+        }
+        result
+    }
+
+    /// Host function: Enqueue a background job, returning its ID
+    fn host_job_enqueue(
+        caller: &mut Caller<'_, StoreData>,
+        job_ptr: u32,
+        job_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("job_enqueue")?;
+
+        let memory = Self::get_memory(caller)?;
+        let job = Self::read_memory(caller, &memory, job_ptr, job_len)?;
+        let id = caller.data().enqueue_job(&job)?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, id.to_string().as_bytes())?;
+        Ok(ptr)
+    }
+
+    /// Host function: Drop the cached responses of a route
+    fn host_cache_invalidate(
+        caller: &mut Caller<'_, StoreData>,
+        route_ptr: u32,
+        route_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("cache_invalidate")?;
+
+        let memory = Self::get_memory(caller)?;
+        let route = Self::read_memory(caller, &memory, route_ptr, route_len)?;
+        let route = String::from_utf8(route)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid route: {}", e)))?;
+        caller.data().invalidate_cache(&route)
+    }
+
+    /// Host function: Queue an email for delivery
+    fn host_email_send(
+        caller: &mut Caller<'_, StoreData>,
+        email_ptr: u32,
+        email_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("email_send")?;
+
+        let memory = Self::get_memory(caller)?;
+        let email = Self::read_memory(caller, &memory, email_ptr, email_len)?;
+        caller.data().send_email(&email)
+    }
+
+    /// Host function: Read the format and dimensions of an image
+    fn host_media_probe(
+        caller: &mut Caller<'_, StoreData>,
+        data_ptr: u32,
+        data_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("media_probe")?;
+
+        let memory = Self::get_memory(caller)?;
+        let data = Self::read_memory(caller, &memory, data_ptr, data_len)?;
+        let info = serde_json::to_vec(&media::probe(&data)?)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to serialize media info: {}", e)))?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &info)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Create a PNG thumbnail of an image
+    fn host_media_thumbnail(
+        caller: &mut Caller<'_, StoreData>,
+        data_ptr: u32,
+        data_len: u32,
+        width: u32,
+        height: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("media_thumbnail")?;
+
+        let memory = Self::get_memory(caller)?;
+        let data = Self::read_memory(caller, &memory, data_ptr, data_len)?;
+        let thumbnail = media::thumbnail(&data, width, height)?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &thumbnail)?;
+        Ok(ptr)
+    }
+
+    /// Read a file path argument from plugin memory
+    fn read_path(caller: &mut Caller<'_, StoreData>, path_ptr: u32, path_len: u32) -> orbis_core::Result<String> {
+        let memory = Self::get_memory(caller)?;
+        let path = Self::read_memory(caller, &memory, path_ptr, path_len)?;
+        String::from_utf8(path).map_err(|e| orbis_core::Error::plugin(format!("Invalid path: {}", e)))
+    }
+
+    /// Host function: Read a file through the plugin's filesystem grants
+    fn host_file_read(caller: &mut Caller<'_, StoreData>, path_ptr: u32, path_len: u32) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("file_read")?;
+
+        let path = Self::read_path(caller, path_ptr, path_len)?;
+        let contents = caller.data().files()?.read(&path)?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &contents)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Write a file through the plugin's filesystem grants
+    fn host_file_write(
+        caller: &mut Caller<'_, StoreData>,
+        path_ptr: u32,
+        path_len: u32,
+        data_ptr: u32,
+        data_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("file_write")?;
+
+        let path = Self::read_path(caller, path_ptr, path_len)?;
+        let memory = Self::get_memory(caller)?;
+        let data = Self::read_memory(caller, &memory, data_ptr, data_len)?;
+        caller.data().files()?.write(&path, &data)
+    }
+
+    /// Host function: List a directory through the plugin's filesystem grants
+    fn host_file_list(caller: &mut Caller<'_, StoreData>, path_ptr: u32, path_len: u32) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("file_list")?;
+
+        let path = Self::read_path(caller, path_ptr, path_len)?;
+        let entries = serde_json::to_vec(&caller.data().files()?.list(&path)?)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to serialize entries: {}", e)))?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &entries)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Remove a file or directory through the plugin's filesystem grants
+    fn host_file_remove(caller: &mut Caller<'_, StoreData>, path_ptr: u32, path_len: u32) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("file_remove")?;
+
+        let path = Self::read_path(caller, path_ptr, path_len)?;
+        caller.data().files()?.remove(&path)
+    }
+
+    /// Host function: Get a download URL of a file in the plugin's object storage
+    fn host_file_url(
+        caller: &mut Caller<'_, StoreData>,
+        path_ptr: u32,
+        path_len: u32,
+        ttl_seconds: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("file_url")?;
+
+        let path = Self::read_path(caller, path_ptr, path_len)?;
+        let ttl = if ttl_seconds == 0 {
+            storage::DEFAULT_URL_TTL
+        } else {
+            Duration::from_secs(u64::from(ttl_seconds))
+        };
+        let url = caller.data().files()?.url(&path, ttl)?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, url.as_bytes())?;
+        Ok(ptr)
+    }
+
+    /// Host function: Export the plugin's data as a ZIP archive
+    fn host_data_export(caller: &mut Caller<'_, StoreData>) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("data_export")?;
+
+        let bytes = caller.data().export_data()?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Replace the plugin's data with a ZIP archive
+    fn host_data_import(
+        caller: &mut Caller<'_, StoreData>,
+        archive_ptr: u32,
+        archive_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("data_import")?;
+
+        let memory = Self::get_memory(caller)?;
+        let bytes = Self::read_memory(caller, &memory, archive_ptr, archive_len)?;
+        caller.data().import_data(&bytes)
+    }
+
+    /// Host function: Get state value
+    fn host_state_get(
+        caller: &mut Caller<'_, StoreData>,
+        key_ptr: u32,
+        key_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("state_get")?;
+
+        let memory = Self::get_memory(caller)?;
+        let key_bytes = Self::read_memory(caller, &memory, key_ptr, key_len)?;
+        let key = String::from_utf8(key_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in state key: {}", e))
+        })?;
+
+        let value = caller.data().state.get(&key);
+
+        if let Some(val) = value {
+            let val_bytes = serde_json::to_vec(&val).map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to serialize state value: {}", e))
+            })?;
+
+            let (ptr, _) = Self::allocate_and_write_bytes(caller, &val_bytes)?;
+            Ok(ptr)
+        } else {
+            Ok(0) // Null pointer for missing key
+        }
+    }
+
+    /// Host function: Set state value
+    fn host_state_set(
+        caller: &mut Caller<'_, StoreData>,
+        key_ptr: u32,
+        key_len: u32,
+        value_ptr: u32,
+        value_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("state_set")?;
+
+        let memory = Self::get_memory(caller)?;
+        let key_bytes = Self::read_memory(caller, &memory, key_ptr, key_len)?;
+        let value_bytes = Self::read_memory(caller, &memory, value_ptr, value_len)?;
+
+        let key = String::from_utf8(key_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in state key: {}", e))
+        })?;
+
+        let value: serde_json::Value = serde_json::from_slice(&value_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to parse state value: {}", e))
+        })?;
+
+        caller.data().state.set(key, value);
+        Ok(())
+    }
+
+    /// Host function: Remove state value
+    fn host_state_remove(
+        caller: &mut Caller<'_, StoreData>,
+        key_ptr: u32,
+        key_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("state_remove")?;
+
+        let memory = Self::get_memory(caller)?;
+        let key_bytes = Self::read_memory(caller, &memory, key_ptr, key_len)?;
+        let key = String::from_utf8(key_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in state key: {}", e))
+        })?;
+
+        caller.data().state.remove(&key);
+        Ok(())
+    }
+
+    /// Host function: Get several state values
+    ///
+    /// Takes a JSON array of keys and returns a JSON object of the keys that exist.
+    fn host_state_get_many(
+        caller: &mut Caller<'_, StoreData>,
+        keys_ptr: u32,
+        keys_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("state_get_many")?;
+
+        let memory = Self::get_memory(caller)?;
+        let keys_bytes = Self::read_memory(caller, &memory, keys_ptr, keys_len)?;
+        let keys: Vec<String> = serde_json::from_slice(&keys_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid keys JSON: {}", e)))?;
+
+        let values = caller.data().state.get_many(&keys);
+        let values_bytes = serde_json::to_vec(&values).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize state values: {}", e))
+        })?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &values_bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Set several state values
+    ///
+    /// Takes a JSON object of keys to [`StateWrite`]s.
+    fn host_state_set_many(
+        caller: &mut Caller<'_, StoreData>,
+        entries_ptr: u32,
+        entries_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("state_set_many")?;
+
+        let memory = Self::get_memory(caller)?;
+        let entries_bytes = Self::read_memory(caller, &memory, entries_ptr, entries_len)?;
+        let entries: HashMap<String, StateWrite> = serde_json::from_slice(&entries_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid state entries JSON: {}", e)))?;
+
+        caller.data().state.apply(entries);
+        Ok(())
+    }
+
+    /// Host function: Log message
+    fn host_log(
+        caller: &mut Caller<'_, StoreData>,
+        level: i32,
+        ptr: u32,
+        len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("log")?;
+
+        let memory = Self::get_memory(caller)?;
+        let msg_bytes = Self::read_memory(caller, &memory, ptr, len)?;
+        let msg = String::from_utf8_lossy(&msg_bytes);
+
+        let plugin_name = &caller.data().plugin_name;
+
+        match level {
+            0 => tracing::error!("[Plugin: {}] {}", plugin_name, msg),
+            1 => tracing::warn!("[Plugin: {}] {}", plugin_name, msg),
+            2 => tracing::info!("[Plugin: {}] {}", plugin_name, msg),
+            3 => tracing::debug!("[Plugin: {}] {}", plugin_name, msg),
+            _ => tracing::trace!("[Plugin: {}] {}", plugin_name, msg),
+        }
+
+        Ok(())
+    }
+
+    /// Host function: Take the denial of the last host call
+    ///
+    /// Returns a null pointer if the last call was not denied.
+    fn host_denial(caller: &mut Caller<'_, StoreData>) -> orbis_core::Result<u32> {
+        let Some(denial) = caller.data_mut().denial.take() else {
+            return Ok(0);
+        };
+
+        let bytes = serde_json::to_vec(&denial)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to serialize denial: {}", e)))?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Query database
+    fn host_db_query(
+        caller: &mut Caller<'_, StoreData>,
+        query_ptr: u32,
+        query_len: u32,
+        params_ptr: u32,
+        params_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("db_query")?;
+
+        let memory = Self::get_memory(caller)?;
+        let query_bytes = Self::read_memory(caller, &memory, query_ptr, query_len)?;
+        let query = String::from_utf8(query_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in query: {}", e))
+        })?;
+
+        let params_bytes = Self::read_memory(caller, &memory, params_ptr, params_len)?;
+        let params: Vec<serde_json::Value> = serde_json::from_slice(&params_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid params JSON: {}", e)))?;
+
+        let result = caller.data().run_query(&query, &params)?;
+        let result_bytes = serde_json::to_vec(&result).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize result: {}", e))
+        })?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &result_bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Run several queries
+    ///
+    /// Takes a JSON array of `{sql, params}` objects and returns a JSON array
+    /// with the rows of each query.
+    fn host_db_query_batch(
+        caller: &mut Caller<'_, StoreData>,
+        queries_ptr: u32,
+        queries_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("db_query_batch")?;
+
+        let memory = Self::get_memory(caller)?;
+        let queries_bytes = Self::read_memory(caller, &memory, queries_ptr, queries_len)?;
+        let queries: Vec<BatchQuery> = serde_json::from_slice(&queries_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid queries JSON: {}", e)))?;
+
+        let results = caller.data().run_query_batch(&queries)?;
+        let result_bytes = serde_json::to_vec(&results).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize result: {}", e))
+        })?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &result_bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Execute database statement
+    fn host_db_execute(
+        caller: &mut Caller<'_, StoreData>,
+        query_ptr: u32,
+        query_len: u32,
+        params_ptr: u32,
+        params_len: u32,
+    ) -> orbis_core::Result<u64> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("db_execute")?;
+
+        let memory = Self::get_memory(caller)?;
+        let query_bytes = Self::read_memory(caller, &memory, query_ptr, query_len)?;
+        let query = String::from_utf8(query_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in query: {}", e))
+        })?;
+
+        let params_bytes = Self::read_memory(caller, &memory, params_ptr, params_len)?;
+        let params: Vec<serde_json::Value> = serde_json::from_slice(&params_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid params JSON: {}", e)))?;
+
+        caller.data().run_statement(&query, &params)
+    }
+
+    /// Host function: Make HTTP request
+    fn host_http_request(
+        caller: &mut Caller<'_, StoreData>,
+        method_ptr: u32,
+        method_len: u32,
+        url_ptr: u32,
+        url_len: u32,
+        headers_ptr: u32,
+        headers_len: u32,
+        body_ptr: u32,
+        body_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("http_request")?;
+
+        let memory = Self::get_memory(caller)?;
+
+        let method_bytes = Self::read_memory(caller, &memory, method_ptr, method_len)?;
+        let method = String::from_utf8(method_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in method: {}", e))
+        })?;
+
+        let url_bytes = Self::read_memory(caller, &memory, url_ptr, url_len)?;
+        let url = String::from_utf8(url_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in URL: {}", e))
+        })?;
+
+        // Check if URL host is allowed
+        if let Ok(parsed_url) = url::Url::parse(&url) {
+            if let Some(host) = parsed_url.host_str() {
+                if !caller.data().sandbox.can_access_network(host) {
+                    return Err(orbis_core::Error::plugin(format!(
+                        "Plugin is not allowed to access host: {}",
+                        host
+                    )));
+                }
+            }
+        }
+
+        let headers_bytes = Self::read_memory(caller, &memory, headers_ptr, headers_len)?;
+        let headers: HashMap<String, String> = serde_json::from_slice(&headers_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid headers JSON: {}", e)))?;
+
+        let body_bytes = Self::read_memory(caller, &memory, body_ptr, body_len)?;
+
+        let response = caller.data().send_http_request(&method, &url, &headers, body_bytes)?;
+        let response_bytes = serde_json::to_vec(&response).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to serialize response: {}", e))
+        })?;
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &response_bytes)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Emit event
+    fn host_emit_event(
+        caller: &mut Caller<'_, StoreData>,
+        event_ptr: u32,
+        event_len: u32,
+        payload_ptr: u32,
+        payload_len: u32,
+    ) -> orbis_core::Result<()> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("emit_event")?;
+
+        let memory = Self::get_memory(caller)?;
+
+        let event_bytes = Self::read_memory(caller, &memory, event_ptr, event_len)?;
+        let event_name = String::from_utf8(event_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in event name: {}", e))
+        })?;
+
+        let payload_bytes = Self::read_memory(caller, &memory, payload_ptr, payload_len)?;
+        let payload: serde_json::Value = serde_json::from_slice(&payload_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid payload JSON: {}", e)))?;
+
+        caller.data().publish_event(event_name, payload)
+    }
+
+    /// Host function: Get config value
+    fn host_get_config(
+        caller: &mut Caller<'_, StoreData>,
+        key_ptr: u32,
+        key_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("get_config")?;
+
+        let memory = Self::get_memory(caller)?;
+        let key_bytes = Self::read_memory(caller, &memory, key_ptr, key_len)?;
+        let key = String::from_utf8(key_bytes).map_err(|e| {
+            orbis_core::Error::plugin(format!("Invalid UTF-8 in config key: {}", e))
+        })?;
+
+        let value = caller.data().config.get(&key);
+
+        if let Some(val) = value {
+            let val_bytes = serde_json::to_vec(&val).map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to serialize config value: {}", e))
+            })?;
+
+            let (ptr, _) = Self::allocate_and_write_bytes(caller, &val_bytes)?;
+            Ok(ptr)
+        } else {
+            Ok(0) // Null pointer for missing key
+        }
+    }
+
+    /// Host function: Get the host information the plugin requests
+    fn host_get_info(caller: &mut Caller<'_, StoreData>) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("host_info")?;
+
+        let info = serde_json::to_vec(&caller.data().requested_host_info())
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to serialize host info: {}", e)))?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &info)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Get the groups of the user the request is made by
+    fn host_groups(caller: &mut Caller<'_, StoreData>) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("groups")?;
+
+        let groups = serde_json::to_vec(&caller.data().groups)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to serialize groups: {}", e)))?;
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &groups)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Get an environment variable the plugin requests
+    fn host_get_env(caller: &mut Caller<'_, StoreData>, name_ptr: u32, name_len: u32) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("host_env")?;
+
+        let memory = Self::get_memory(caller)?;
+        let name = Self::read_memory(caller, &memory, name_ptr, name_len)?;
+        let name = String::from_utf8(name)
+            .map_err(|e| orbis_core::Error::plugin(format!("Invalid environment variable name: {}", e)))?;
+
+        match caller.data().env(&name) {
+            Some(value) => {
+                let (ptr, _) = Self::allocate_and_write_bytes(caller, value.as_bytes())?;
+                Ok(ptr)
+            }
+            None => Ok(0), // Null pointer for unset or redacted variables
+        }
+    }
+
+    /// Host function: Hash data
+    fn host_crypto_hash(
+        caller: &mut Caller<'_, StoreData>,
+        algorithm: i32,
+        data_ptr: u32,
+        data_len: u32,
+    ) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("crypto_hash")?;
+
+        let memory = Self::get_memory(caller)?;
+        let data = Self::read_memory(caller, &memory, data_ptr, data_len)?;
+
+        use sha2::{Digest, Sha256, Sha512};
+
+        let hash: Vec<u8> = match algorithm {
+            0 => {
+                // SHA-256
+                let mut hasher = Sha256::new();
+                hasher.update(&data);
+                hasher.finalize().to_vec()
+            }
+            1 => {
+                // SHA-512
+                let mut hasher = Sha512::new();
+                hasher.update(&data);
+                hasher.finalize().to_vec()
+            }
+            _ => {
+                return Err(orbis_core::Error::plugin(format!(
+                    "Unknown hash algorithm: {}",
+                    algorithm
+                )));
+            }
+        };
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &hash)?;
+        Ok(ptr)
+    }
+
+    /// Host function: Generate random bytes
+    fn host_crypto_random(caller: &mut Caller<'_, StoreData>, len: u32) -> orbis_core::Result<u32> {
+        caller.data_mut().check_limits()?;
+        caller.data_mut().authorize("crypto_random")?;
+
+        if len > 1024 * 1024 {
+            return Err(orbis_core::Error::plugin(format!(
+                "Random bytes request too large: {} bytes",
+                len
+            )));
+        }
+
+        use rand::RngCore;
+        let mut bytes = vec![0u8; len as usize];
+        rand::rng().fill_bytes(&mut bytes);
+
+        let (ptr, _) = Self::allocate_and_write_bytes(caller, &bytes)?;
+        Ok(ptr)
+    }
+
+    /// Get memory from caller
+    fn get_memory(caller: &mut Caller<'_, StoreData>) -> orbis_core::Result<Memory> {
+        caller
+            .get_export("memory")
+            .and_then(|e| e.into_memory())
+            .ok_or_else(|| orbis_core::Error::plugin("Memory export not found"))
+    }
+
+    /// Read memory from WASM
+    fn read_memory(
+        caller: &mut Caller<'_, StoreData>,
+        memory: &Memory,
+        ptr: u32,
+        len: u32,
+    ) -> orbis_core::Result<Vec<u8>> {
+        if len as usize > MAX_ALLOCATION_SIZE {
+            return Err(orbis_core::Error::plugin(format!(
+                "Memory read too large: {} bytes",
+                len
+            )));
+        }
+
+        let mut buffer = vec![0u8; len as usize];
+        memory
+            .read(caller, ptr as usize, &mut buffer)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read memory: {}", e)))?;
+
+        Ok(buffer)
+    }
+
+    /// Allocate memory in WASM and write data
+    fn allocate_and_write(
+        store: &mut Store<StoreData>,
+        memory: &Memory,
+        instance: &Instance,
+        data: &[u8],
+    ) -> orbis_core::Result<(u32, u32)> {
+        let len = data.len() as u32;
+
+        if data.len() > MAX_ALLOCATION_SIZE {
+            return Err(orbis_core::Error::plugin(format!(
+                "Allocation too large: {} bytes",
+                data.len()
+            )));
+        }
+
+        // Try to get allocate function from WASM
+        let alloc_func = instance.get_func(&mut *store, "allocate").ok_or_else(|| {
+            orbis_core::Error::plugin("allocate function not found in WASM module")
+        })?;
+
+        let alloc_typed: TypedFunc<i32, i32> = alloc_func.typed(&*store).map_err(|e| {
+            orbis_core::Error::plugin(format!("allocate function has wrong signature: {}", e))
+        })?;
+
+        // Attempt allocation and capture any traps with additional diagnostics
+        let ptr = match alloc_typed.call(&mut *store, len as i32) {
+            Ok(p) => p as u32,
+            Err(e) => {
+                // Gather memory/sandbox diagnostics
+                let pages = memory.size(&*store);
+                let mem_bytes = (pages as usize) * 65536usize;
+                let sandbox_limit = store.data().sandbox.memory_limit;
+                let plugin_name = store.data().plugin_name.clone();
+
+                tracing::error!(
+                    plugin = %plugin_name,
+                    requested = %len,
+                    memory_pages = %pages,
+                    memory_bytes = %mem_bytes,
+                    sandbox_limit = %sandbox_limit,
+                    "WASM allocation trap: {}",
+                    e
+                );
+
+                // If requested allocation exceeds sandbox limit, return a clearer error
+                if (len as usize) > sandbox_limit {
+                    return Err(orbis_core::Error::plugin(format!(
+                        "Failed to allocate memory: requested {} bytes exceeds sandbox limit {}",
+                        len, sandbox_limit
+                    )));
+                }
+
+                // Try to grow memory as a fallback and retry allocation several times
+                let mut pages_needed = ((len as usize) + 65535) / 65536; // round up
+                if pages_needed == 0 {
+                    pages_needed = 1;
+                }
+
+                tracing::debug!(plugin = %plugin_name, requested = %len, pages_needed = %pages_needed, "Attempting to grow WASM memory as fallback");
+
+                // Compute how many pages we can still add under sandbox limit
+                let mut attempts = 0u8;
+                let mut pages_to_add = pages_needed;
+                loop {
+                    if attempts >= 4 {
+                        tracing::warn!(plugin = %plugin_name, "Reached maximum memory grow attempts (4)");
+                        break;
+                    }
+
+                    // Check sandbox limit in pages
+                    let mem_pages = memory.size(&*store) as usize;
+                    let mem_bytes = mem_pages * 65536usize;
+                    let remaining = if sandbox_limit > mem_bytes { sandbox_limit - mem_bytes } else { 0 };
+                    let max_addable = if remaining == 0 { 0 } else { (remaining + 65535) / 65536 };
+
+                    if max_addable == 0 {
+                        tracing::warn!(plugin = %plugin_name, "Cannot grow memory, sandbox limit reached ({} bytes)", sandbox_limit);
+                        break;
+                    }
+
+                    let add = std::cmp::min(pages_to_add, max_addable);
+
+                    match memory.grow(&mut *store, add as u64) {
+                        Ok(old_pages) => {
+                            tracing::info!(plugin = %plugin_name, old_pages = %old_pages, pages_added = %add, "Memory grow succeeded, retrying allocation");
+                            // Retry allocation
+                            match alloc_typed.call(&mut *store, len as i32) {
+                                Ok(p) => return Ok((p as u32, len)),
+                                Err(e2) => {
+                                    tracing::error!(plugin = %plugin_name, "Allocation after grow attempt failed: {}", e2);
+
+                                    // Dump useful globals for debugging
+                                    let heap_base = instance
+                                        .get_global(&mut *store, "__heap_base")
+                                        .and_then(|g: wasmtime::Global| g.get(&mut *store).i32())
+                                        .unwrap_or(-1);
+                                    let data_end = instance
+                                        .get_global(&mut *store, "__data_end")
+                                        .and_then(|g: wasmtime::Global| g.get(&mut *store).i32())
+                                        .unwrap_or(-1);
+                                    let current_pages = memory.size(&*store);
+
+                                    tracing::error!(
+                                        plugin = %plugin_name,
+                                        requested = %len,
+                                        heap_base = %heap_base,
+                                        data_end = %data_end,
+                                        memory_pages = %current_pages,
+                                        memory_bytes = %((current_pages as usize) * 65536usize),
+                                        "Allocation failed after grow with diagnostics"
+                                    );
+
+                                    // Prepare for next attempt: try to grow more (exponential)
+                                    attempts += 1;
+                                    pages_to_add = pages_to_add.saturating_mul(2);
+                                    continue;
+                                }
+                            }
+                        }
+                        Err(grow_err) => {
+                            tracing::warn!(plugin = %plugin_name, "Memory grow failed: {}", grow_err);
+                            break;
+                        }
+                    }
+                }
+
+                // If we get here, all grow+retry attempts failed
+                return Err(orbis_core::Error::plugin(format!(
+                    "Failed to allocate memory after grow attempts: {}",
+                    e
+                )));
+            }
+        };
+
+        // Write data to allocated memory
+        memory
+            .write(&mut *store, ptr as usize, data)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to write to memory: {}", e)))?;
+
+        Ok((ptr, len))
+    }
+
+    /// Allocate and write bytes (used by host functions)
+    fn allocate_and_write_bytes(
+        caller: &mut Caller<'_, StoreData>,
+        data: &[u8],
+    ) -> orbis_core::Result<(u32, u32)> {
+        let data_len = data.len() as u32;
+
+        if data.len() > MAX_ALLOCATION_SIZE {
+            return Err(orbis_core::Error::plugin(format!(
+                "Allocation too large: {} bytes",
+                data.len()
+            )));
+        }
+
+        let memory = Self::get_memory(caller)?;
+
+        // Get allocate function
+        let alloc_func = caller
+            .get_export("allocate")
+            .and_then(|e| e.into_func())
+            .ok_or_else(|| {
+                orbis_core::Error::plugin("allocate function not found in WASM module")
+            })?;
+
+        // Allocate space for length prefix (4 bytes) + data
+        let total_size = 4 + data_len;
+        let mut results = vec![Val::I32(0)];
+        alloc_func
+            .call(caller.as_context_mut(), &[Val::I32(total_size as i32)], &mut results)
+            .map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to call allocate: {}", e))
+            })?;
+
+        let ptr = match results[0] {
+            Val::I32(p) => p as u32,
+            _ => {
+                return Err(orbis_core::Error::plugin(
+                    "allocate returned wrong type".to_string(),
+                ))
+            }
+        };
+
+        // Write length prefix (little-endian u32)
+        let len_bytes = data_len.to_le_bytes();
+        memory
+            .write( caller.as_context_mut(), ptr as usize, &len_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to write length prefix: {}", e)))?;
+
+        // Write data after the length prefix
+        memory
+            .write(caller.as_context_mut(), (ptr + 4) as usize, data)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to write data: {}", e)))?;
+
+        // Recorded handlers keep what the host answered
+        if let Some(ref mut tape) = caller.data_mut().tape {
+            tape.wrote(data);
+        }
+
+        Ok((ptr, total_size))
+    }
+
+    /// Deallocate memory in WASM
+    fn deallocate(
+        store: &mut Store<StoreData>,
+        instance: &Instance,
+        ptr: u32,
+        len: u32,
+    ) -> orbis_core::Result<()> {
+        // Try to get deallocate function from WASM
+        if let Some(dealloc_func) = instance.get_func(&mut *store, "deallocate") {
+            let dealloc_typed: TypedFunc<(i32, i32), ()> = dealloc_func.typed(&*store).map_err(|e| {
+                orbis_core::Error::plugin(format!("deallocate function has wrong signature: {}", e))
+            })?;
+
+            dealloc_typed
+                .call(&mut *store, (ptr as i32, len as i32))
+                .map_err(|e| {
+                    orbis_core::Error::plugin(format!("Failed to deallocate memory: {}", e))
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Adapt a handler result from an older ABI minor version to the current one.
+    fn apply_abi_shims(version: AbiVersion, result: serde_json::Value) -> serde_json::Value {
+        // ABI 1.0 handlers may return a bare JSON body instead of a response envelope
+        if version < AbiVersion::new(1, 1) && !result.get("status").is_some_and(serde_json::Value::is_u64) {
+            return serde_json::json!({
+                "status": 200,
+                "body": result
+            });
+        }
+
+        result
+    }
+
+    /// Read result from WASM memory
+    fn read_result(
+        store: &mut Store<StoreData>,
+        memory: &Memory,
+        ptr: u32,
+    ) -> orbis_core::Result<serde_json::Value> {
+        if ptr == 0 {
+            return Ok(serde_json::Value::Null);
+        }
+
+        // First 4 bytes are the length
+        let mut len_bytes = [0u8; 4];
+        memory
+            .read(&mut *store, ptr as usize, &mut len_bytes)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read result length: {}", e)))?;
+
+        let len = u32::from_le_bytes(len_bytes);
+
+        if len as usize > MAX_ALLOCATION_SIZE {
+            return Err(orbis_core::Error::plugin(format!(
+                "Result too large: {} bytes",
+                len
+            )));
+        }
+
+        // Read the actual data
+        let mut data = vec![0u8; len as usize];
+        memory
+            .read(&mut *store, (ptr + 4) as usize, &mut data)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read result data: {}", e)))?;
+
+        let result: serde_json::Value = serde_json::from_slice(&data)
+            .map_err(|e| orbis_core::Error::plugin(format!("Failed to parse result: {}", e)))?;
+
+        Ok(result)
+    }
+}
+
+impl Default for PluginRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_state() {
+        let state = PluginState::new();
+
+        state.set("key1".to_string(), serde_json::json!("value1"));
+        state.set("key2".to_string(), serde_json::json!(42));
+        state.set(
+            "key3".to_string(),
+            serde_json::json!({"nested": "object"}),
+        );
+
+        assert_eq!(state.get("key1"), Some(serde_json::json!("value1")));
+        assert_eq!(state.get("key2"), Some(serde_json::json!(42)));
+        assert_eq!(
+            state.get("key3"),
+            Some(serde_json::json!({"nested": "object"}))
+        );
+        assert_eq!(state.get("nonexistent"), None);
+
+        state.remove("key2");
+        assert_eq!(state.get("key2"), None);
+
+        let keys = state.keys();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.contains(&"key1".to_string()));
+        assert!(keys.contains(&"key3".to_string()));
+
+        state.clear();
+        assert_eq!(state.keys().len(), 0);
+    }
+
+    #[test]
+    fn test_store_data_limits() {
+        let sandbox = Arc::new(SandboxConfig {
+            max_calls: 10,
+            time_limit_ms: 100,
+            ..SandboxConfig::minimal()
+        });
+
+        let state = PluginState::new();
+        let config = PluginConfig::new();
+        let mut store_data = StoreData::new("test".to_string(), sandbox, state, config);
+
+        // Should succeed for first 10 calls
+        for _ in 0..10 {
+            assert!(store_data.check_limits().is_ok());
+        }
+
+        // Should fail on 11th call
+        assert!(store_data.check_limits().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_request_is_sent_and_metered() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ping", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\nx-reply: yes\r\nconnection: close\r\n\r\npong")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(request.get(..read).unwrap_or_default()).into_owned()
+        });
+
+        let quotas = Arc::new(NetworkQuotas::new());
+        let mut store_data = StoreData::new(
+            "test".to_string(),
+            Arc::new(SandboxConfig::minimal()),
+            PluginState::new(),
+            PluginConfig::new(),
+        );
+        store_data.network_quotas = Some(Arc::clone(&quotas));
+
+        let headers = HashMap::from([("x-token".to_string(), "abc".to_string())]);
+        let response = tokio::task::spawn_blocking(move || {
+            store_data.send_http_request("POST", &url, &headers, b"ping".to_vec())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(response["status"], 200);
+        assert_eq!(response["headers"]["x-reply"], "yes");
+        assert_eq!(response["body"], serde_json::json!(b"pong".to_vec()));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /ping"));
+        assert!(request.contains("x-token: abc"));
+
+        let usage = quotas.usage("test").unwrap();
+        assert_eq!(usage.total_requests, 1);
+        assert!(usage.total_bytes > 0);
+    }
+
+    #[test]
+    fn test_host_info_and_env_redaction() {
+        let sandbox = Arc::new(SandboxConfig::minimal());
+        let mut store_data = StoreData::new("test".to_string(), sandbox, PluginState::new(), PluginConfig::new());
+
+        // Nothing requested, everything redacted
+        assert_eq!(store_data.requested_host_info(), HostInfo::default());
+        assert_eq!(store_data.env("PATH"), None);
+
+        store_data.requirements = Arc::new(PluginRequirements {
+            environment: vec!["PATH".to_string()],
+            host_info: vec![HostInfoField::Os, HostInfoField::AppVersion],
+            ..PluginRequirements::default()
+        });
+        let info = store_data.requested_host_info();
+        assert_eq!(info.os.as_deref(), Some(std::env::consts::OS));
+        assert_eq!(info.app_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(info.hostname, None);
+        assert_eq!(info.arch, None);
+        assert_eq!(store_data.env("PATH"), std::env::var("PATH").ok());
+        assert_eq!(store_data.env("HOME"), None);
+    }
+
+    /// Component whose `handle` reads the WASI monotonic clock and returns `{}`.
+    const CLOCK_COMPONENT: &str = r#"(component
+        (import "wasi:clocks/monotonic-clock@0.2.0" (instance $clock
+            (export "now" (func (result u64)))))
+        (core func $now (canon lower (func $clock "now")))
+        (core module $m
+            (import "wasi" "now" (func $now (result i64)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get 3)))
+                (local.get $ptr))
+            (func (export "handle") (param i32 i32 i32 i32) (result i32)
+                (drop (call $now))
+                (i32.const 16))
+            (data (i32.const 16) "\00\00\00\00\40\00\00\00\02\00\00\00")
+            (data (i32.const 64) "{}"))
+        (core instance $i (instantiate $m (with "wasi" (instance (export "now" (func $now))))))
+        (func (export "handle") (param "handler" string) (param "context" string)
+            (result (result string (error string)))
+            (canon lift (core func $i "handle") (memory $i "memory") (realloc (func $i "realloc")))))"#;
+
+    #[test]
+    fn test_plugin_code_detection() {
+        let engine = Engine::default();
+        let linker = component::linker(&engine).unwrap();
+
+        // Empty core module (version 1), and an empty component (version 0x0d,
+        // layer 1) which does not export `handle`
+        let module = b"\0asm\x01\0\0\0";
+        let empty = b"\0asm\x0d\0\x01\0";
+        let component = wat::parse_str(CLOCK_COMPONENT).unwrap();
+
+        assert!(matches!(PluginCode::compile(&engine, &linker, module, None), Ok(PluginCode::Module(_))));
+        assert!(matches!(PluginCode::compile(&engine, &linker, &component, None), Ok(PluginCode::Component(_))));
+        assert!(PluginCode::compile(&engine, &linker, empty, None).is_err());
+        assert!(PluginCode::compile(&engine, &linker, b"not wasm", None).is_err());
+    }
+
+    #[test]
+    fn test_component_execution() {
+        let runtime = PluginRuntime::new();
+        let code = wat::parse_str(CLOCK_COMPONENT).unwrap();
+        let linker = runtime.component_linker().unwrap();
+        let Ok(PluginCode::Component(pre)) = PluginCode::compile(&runtime.engine, linker, &code, None) else {
+            panic!("expected a component");
+        };
+
+        let store_data = StoreData::new(
+            "clock".to_string(),
+            Arc::new(SandboxConfig::minimal()),
+            PluginState::new(),
+            PluginConfig::new(),
+        );
+        let mut store = Store::new(&runtime.engine, store_data);
+        store.set_fuel(1_000_000).unwrap();
+        store.set_epoch_deadline(1_000);
+
+        let context = PluginContext {
+            method: "GET".to_string(),
+            path: "/now".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+
+        // Each call instantiates the component from the runtime's linker
+        for _ in 0..2 {
+            let result = component::execute(&mut store, &pre, "now", &context).unwrap();
+            assert_eq!(result, serde_json::json!({}));
+        }
+        assert!(store.data().wasi.is_some());
+    }
+
+    #[test]
+    fn test_emitted_events_are_published() {
+        let (sink, mut events) = tokio::sync::mpsc::unbounded_channel();
+        let mut store_data = StoreData::new(
+            "emitter".to_string(),
+            Arc::new(SandboxConfig::minimal()),
+            PluginState::new(),
+            PluginConfig::new(),
+        );
+        store_data.tenant = Some("acme".to_string());
+
+        // Without a sink, events are dropped
+        store_data.publish_event("order.created".to_string(), serde_json::json!({"id": 1})).unwrap();
+
+        store_data.events = Some(sink);
+        store_data.publish_event("order.created".to_string(), serde_json::json!({"id": 2})).unwrap();
+        assert!(store_data.publish_event(String::new(), serde_json::Value::Null).is_err());
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.plugin, "emitter");
+        assert_eq!(event.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(event.event, "order.created");
+        assert_eq!(event.payload, serde_json::json!({"id": 2}));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_legacy_abi_shims() {
+        let envelope = serde_json::json!({"status": 201, "body": {"id": 1}});
+        assert_eq!(
+            PluginRuntime::apply_abi_shims(AbiVersion::LEGACY, envelope.clone()),
+            envelope
+        );
+
+        assert_eq!(
+            PluginRuntime::apply_abi_shims(AbiVersion::LEGACY, serde_json::json!({"id": 1})),
+            serde_json::json!({"status": 200, "body": {"id": 1}})
+        );
+    }
+
+    #[test]
+    fn test_plugin_state_batch() {
+        let state = PluginState::new();
+        state.set("stale".to_string(), serde_json::json!(true));
+
+        state.apply(HashMap::from([
+            ("a".to_string(), StateWrite::Set(serde_json::json!(1))),
+            ("b".to_string(), StateWrite::Set(serde_json::json!("two"))),
+            ("null".to_string(), StateWrite::Set(serde_json::Value::Null)),
+            ("stale".to_string(), StateWrite::Remove),
+        ]));
+
+        let keys = ["a", "b", "null", "stale"].map(str::to_string);
+        let values = state.get_many(&keys);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get("a"), Some(&serde_json::json!(1)));
+        assert_eq!(values.get("null"), Some(&serde_json::Value::Null));
+        assert_eq!(state.get("stale"), None);
+
+        // Writes arrive from the SDK as explicit operations
+        let entries: HashMap<String, StateWrite> =
+            serde_json::from_str(r#"{"a": {"set": null}, "b": "remove"}"#).unwrap();
+        state.apply(entries);
+        assert_eq!(state.get("a"), Some(serde_json::Value::Null));
+        assert_eq!(state.get("b"), None);
+    }
+
+    #[test]
+    fn test_encrypted_plugin_state() {
+        let path = std::env::temp_dir().join(format!("orbis-state-{}.json", uuid::Uuid::new_v4()));
+        let key = Arc::new(EncryptionKey::derive("passphrase", b"orbis-test-salt!").unwrap());
+
+        let state = PluginState::with_encrypted_persistence(path.clone(), Some(Arc::clone(&key)));
+        state.set("secret".to_string(), serde_json::json!("value"));
+        let contents = std::fs::read(&path).unwrap();
+        assert!(EncryptionKey::is_sealed(&contents));
+        assert!(!String::from_utf8_lossy(&contents).contains("secret"));
+
+        let restored = PluginState::with_encrypted_persistence(path.clone(), Some(key));
+        assert_eq!(restored.get("secret"), Some(serde_json::json!("value")));
+
+        // Without the key the state is not loaded, and never overwritten
+        let locked = PluginState::with_persistence(path.clone());
+        assert_eq!(locked.get("secret"), None);
+        locked.set("other".to_string(), serde_json::json!(1));
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_memory_snapshot_restore() {
+        let runtime = PluginRuntime::new();
+        let module = Module::new(
+            &runtime.engine,
+            r#"(module
+                (memory (export "memory") 1)
+                (global $counter (export "counter") (mut i32) (i32.const 0))
+                (data (i32.const 32) "\05\00\00\00false")
+                (data (i32.const 48) "\04\00\00\00true")
+                (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+                (func (export "init") (result i32)
+                    (i32.store (i32.const 16) (i32.const 42))
+                    (global.set $counter (i32.const 7))
+                    (i32.const 1))
+                (func (export "initialized") (param i32 i32) (result i32)
+                    (select (i32.const 48) (i32.const 32) (global.get $counter))))"#,
+        )
+        .expect("compile module");
+
+        let instance = PluginInstance {
+            engine: runtime.engine.clone(),
+            code: PluginCode::Module(module.clone()),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(SandboxConfig::minimal()),
+            state: PluginState::new(),
+            config: PluginConfig::new(),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::default(),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+
+        let snapshot = runtime
+            .snapshot("snap", &instance, "hash".to_string())
+            .expect("take snapshot")
+            .expect("module has memory");
+        runtime.publish_snapshot("snap", Some(Arc::clone(&snapshot)));
+        assert_eq!(runtime.snapshot_info("snap").map(|info| info.size_bytes), Some(65536));
+
+        // Same code reuses the snapshot without re-running init
+        let reused = runtime.snapshot("snap", &instance, "hash".to_string()).expect("reuse snapshot");
+        assert!(reused.is_some_and(|reused| Arc::ptr_eq(&reused, &snapshot)));
+
+        let mut store = instance.new_store("snap", None).expect("create store");
+        let wasm_instance = Linker::new(&runtime.engine)
+            .instantiate(&mut store, &module)
+            .expect("instantiate");
+        let memory = wasm_instance.get_memory(&mut store, "memory").expect("memory");
+        snapshot.restore(&mut store, &wasm_instance, &memory).expect("restore snapshot");
+
+        assert_eq!(memory.data(&store)[16], 42);
+        let counter = wasm_instance.get_global(&mut store, "counter").expect("global");
+        assert_eq!(counter.get(&mut store).i32(), Some(7));
+
+        // Every run of a restarted instance starts from the post-init state
+        let restarted = PluginInstance {
+            snapshot: Some(Arc::clone(&snapshot)),
+            restore_snapshot: true,
+            ..instance
+        };
+        let context = PluginContext {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+        for _ in 0..2 {
+            let (result, _, _) = PluginRuntime::execute_blocking(&restarted, "snap", "initialized", &context, None);
+            assert_eq!(result.unwrap(), serde_json::json!(true));
+        }
+
+        runtime.clear_cache("snap");
+        assert!(runtime.snapshot_info("snap").is_none());
+    }
+
+    #[test]
+    fn test_state_migrations() {
+        let runtime = PluginRuntime::new();
+        let module = Module::new(&runtime.engine, r#"(module (memory (export "memory") 1))"#).expect("compile module");
+        let mut instance = PluginInstance {
+            engine: runtime.engine.clone(),
+            code: PluginCode::Module(module),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(SandboxConfig::minimal()),
+            state: PluginState::new(),
+            config: PluginConfig::new(),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::default(),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+        let manifest = |state_version: u32| -> PluginManifest {
+            serde_json::from_value(serde_json::json!({
+                "name": "notes",
+                "version": "2.0.0",
+                "state_version": state_version,
+            }))
+            .expect("valid manifest")
+        };
+
+        // New state starts at the declared version
+        let migrated = PluginRuntime::migrate_state(&manifest(2), &mut instance).expect("new state");
+        assert_eq!(migrated.and_then(|state| state.get(STATE_VERSION_KEY).cloned()), Some(serde_json::json!(2)));
+
+        // State without a version is at version 1
+        instance.state.set("count".to_string(), serde_json::json!(3));
+        assert!(PluginRuntime::migrate_state(&manifest(1), &mut instance).expect("up to date").is_none());
+
+        // A failed migration leaves the stored state untouched
+        let error = PluginRuntime::migrate_state(&manifest(2), &mut instance).expect_err("no migration handler");
+        assert!(error.to_string().contains("from version 1 to 2"));
+        assert!(instance.state.get(STATE_VERSION_KEY).is_none());
+        assert_eq!(instance.state.get("count"), Some(serde_json::json!(3)));
+
+        // State newer than the plugin is refused
+        instance.state.set(STATE_VERSION_KEY.to_string(), serde_json::json!(3));
+        assert!(PluginRuntime::migrate_state(&manifest(2), &mut instance).is_err());
+    }
+
+    #[test]
+    fn test_execution_deadline() {
+        let runtime = PluginRuntime::new();
+        let module = Module::new(
+            &runtime.engine,
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+                (func (export "spin") (param i32 i32) (result i32)
+                    (loop $forever (br $forever))
+                    (i32.const 0)))"#,
+        )
+        .expect("compile module");
+
+        // Generous time limit so the request deadline is what stops the plugin
+        let mut sandbox = SandboxConfig::minimal();
+        sandbox.time_limit_ms = 10_000_000;
+
+        let instance = PluginInstance {
+            engine: runtime.engine,
+            code: PluginCode::Module(module),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(sandbox),
+            state: PluginState::new(),
+            config: PluginConfig::new(),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::default(),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+
+        let context = PluginContext {
+            method: "GET".to_string(),
+            path: "/spin".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: Some(chrono::Utc::now() + chrono::Duration::milliseconds(50)),
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+
+        let started = Instant::now();
+        let (result, _, _) = PluginRuntime::execute_blocking(&instance, "spin", "spin", &context, None);
+        assert!(matches!(&result, Err(e) if e.to_string().contains("exceeded request deadline")));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let context = PluginContext {
+            deadline: None,
+            cancellation: CancellationFlag::new(),
+            ..context
+        };
+        context.cancellation.cancel();
+        let (result, _, _) = PluginRuntime::execute_blocking(&instance, "spin", "spin", &context, None);
+        assert!(matches!(&result, Err(e) if e.to_string().contains("execution cancelled")));
+    }
+
+    #[test]
+    fn test_trap_report() {
+        let runtime = PluginRuntime::new();
+        let module = Module::new(
+            &runtime.engine,
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+                (func $explode unreachable)
+                (func (export "crash") (param i32 i32) (result i32)
+                    (call $explode)
+                    (i32.const 0)))"#,
+        )
+        .expect("compile module");
+
+        let instance = PluginInstance {
+            engine: runtime.engine,
+            code: PluginCode::Module(module),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(SandboxConfig::minimal()),
+            state: PluginState::new(),
+            config: PluginConfig::new(),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::default(),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+
+        let context = PluginContext {
+            method: "POST".to_string(),
+            path: "/crash".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: Some("user-1".to_string()),
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+
+        let (result, peak_memory, _) = PluginRuntime::execute_blocking(&instance, "crasher", "crash", &context, None);
+        assert!(matches!(&result, Err(e) if e.to_string().contains("trapped in handler 'crash'")));
+        assert_eq!(peak_memory, 64 * 1024);
+
+        let report = instance.last_trap.lock().take().expect("trap report");
+        assert_eq!(report.plugin, "crasher");
+        assert_eq!(report.handler, "crash");
+        assert_eq!(report.request.path, "/crash");
+        assert_eq!(report.request.user_id.as_deref(), Some("user-1"));
+        assert!(report.trap_code.as_deref().is_some_and(|code| code.contains("unreachable")));
+        assert_eq!(report.frames[0].func_name.as_deref(), Some("explode"));
+        assert!(report.backtrace().contains("explode"));
+    }
+
+    #[test]
+    fn test_out_of_memory() {
+        let runtime = PluginRuntime::new();
+        // Grow past the 16MB limit of the minimal sandbox, aborting if denied
+        let module = Module::new(
+            &runtime.engine,
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+                (func (export "hog") (param i32 i32) (result i32)
+                    (if (i32.eq (memory.grow (i32.const 512)) (i32.const -1))
+                        (then unreachable))
+                    (i32.const 0)))"#,
+        )
+        .expect("compile module");
+
+        let instance = PluginInstance {
+            engine: runtime.engine,
+            code: PluginCode::Module(module),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(SandboxConfig::minimal()),
+            state: PluginState::new(),
+            config: PluginConfig::new(),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::default(),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+
+        let context = PluginContext {
+            method: "POST".to_string(),
+            path: "/hog".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+
+        let (result, peak_memory, _) = PluginRuntime::execute_blocking(&instance, "hog", "hog", &context, None);
+        assert!(matches!(&result, Err(orbis_core::Error::OutOfMemory(message)) if message.contains("handler 'hog'")));
+        assert_eq!(peak_memory, 64 * 1024);
+        assert!(instance.last_trap.lock().is_some());
+    }
+
+    #[test]
+    fn test_tenant_scoped_state_and_config() {
+        let runtime = PluginRuntime::new();
+        let module = Module::new(&runtime.engine, "(module)").expect("compile module");
+
+        let overrides = TenantOverrides::default();
+        overrides.insert(
+            "acme".to_string(),
+            HashMap::from([("theme".to_string(), serde_json::json!("dark"))]),
+        );
+
+        let instance = PluginInstance {
+            engine: runtime.engine,
+            code: PluginCode::Module(module),
+            abi_version: AbiVersion::CURRENT,
+            sandbox_config: Arc::new(SandboxConfig::minimal()),
+            state: PluginState::new(),
+            config: PluginConfig::from_settings(&HashMap::from([(
+                "theme".to_string(),
+                serde_json::json!("light"),
+            )])),
+            snapshot: None,
+            restore_snapshot: false,
+            last_trap: Arc::new(parking_lot::Mutex::new(None)),
+            tenants: Arc::new(TenantScopes::new(None, None, overrides)),
+            object_store: None,
+            files: None,
+            requirements: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            network_quotas: Arc::default(),
+            jobs: Arc::default(),
+            email: Arc::default(),
+            events: Arc::default(),
+            response_cache: Arc::default(),
+            query_cache: Arc::default(),
+            permissions: Arc::new([]),
+            policy: Arc::default(),
+        };
+
+        let acme = instance.new_store("scoped", Some("acme")).expect("acme store");
+        acme.data().state.set("counter".to_string(), serde_json::json!(1));
+        assert_eq!(acme.data().config.get("theme"), Some(serde_json::json!("dark")));
+
+        let globex = instance.new_store("scoped", Some("globex")).expect("globex store");
+        assert_eq!(globex.data().state.get("counter"), None);
+        assert_eq!(globex.data().config.get("theme"), Some(serde_json::json!("light")));
+        assert_eq!(instance.state.get("counter"), None);
+
+        let acme = instance.new_store("scoped", Some("acme")).expect("acme store");
+        assert_eq!(acme.data().state.get("counter"), Some(serde_json::json!(1)));
+
+        assert!(instance.new_store("scoped", Some("../escape")).is_err());
+    }
+
+    #[test]
+    fn test_allocate_via_runtime() {
+        // Load wasm
+        let bytes = std::fs::read("../../plugins/my-first-plugin/plugin.wasm").expect("wasm not found");
+
+        // Create engine and module
+        let engine = Engine::default();
+        let module = Module::new(&engine, &bytes).expect("compile module");
+
+        // Create store with StoreData
+        let sandbox = Arc::new(SandboxConfig::minimal());
+        let state = PluginState::new();
+        let config = PluginConfig::new();
+        let store_data = StoreData::new("my-first-plugin".to_string(), sandbox, state, config);
+
+        let mut store = Store::new(&engine, store_data);
+        store.limiter(|data| &mut data.limits);
+        // Set fuel (use set_fuel same as runtime.execute)
+        let _ = store.set_fuel(1_000_000).ok();
+
+        // Create linker and register host functions
+        let mut linker = Linker::new(&engine);
+        PluginRuntime::register_host_functions(&mut linker).expect("register hosts");
+
+        // Instantiate the module
+        let instance = linker.instantiate(&mut store, &module).expect("instantiate");
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .expect("memory not found");
+
+        // Prepare context JSON (small)
+        let context = PluginContext {
+            method: "POST".to_string(),
+            path: "/greeting".to_string(),
+            headers: std::collections::HashMap::new(),
+            query: std::collections::HashMap::new(),
+            body: serde_json::json!({"name": "Test"}),
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+
+        let data = serde_json::to_vec(&context).expect("serialize");
+
+        // Call allocate_and_write which mirrors runtime flow
+        let res = PluginRuntime::allocate_and_write(&mut store, &memory, &instance, &data);
+
+        match res {
+            Ok((ptr, len)) => {
+                assert!(ptr != 0);
+                assert_eq!(len as usize, data.len());
+            }
+            Err(e) => panic!("allocate_and_write failed: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_allocate_minimal() {
+        // Test with minimal setup - no host functions, no fuel, no limits
+        let bytes = std::fs::read("../../plugins/test-plugin/test_plugin.wasm")
+            .or_else(|_| std::fs::read("../../target/wasm32-unknown-unknown/release/test_plugin.wasm"))
+            .expect("test_plugin.wasm not found");
+
+        // Create minimal engine config
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(false); // Disable fuel consumption
+        let engine = Engine::new(&config).expect("create engine");
+        let module = Module::new(&engine, &bytes).expect("compile module");
+
+        // Create simple store with unit type (no StoreData)
+        let mut store: Store<()> = Store::new(&engine, ());
+
+        // Create linker and add minimal required imports
+        let mut linker: Linker<()> = Linker::new(&engine);
+
+        // Add required imports based on WASM module
+        linker.func_wrap("env", "state_get", |_caller: wasmtime::Caller<'_, ()>, _key_ptr: i32, _key_len: i32| -> i32 {
+            // Return 0 = no value found
+            0i32
+        }).expect("wrap state_get");
+        
+        linker.func_wrap("env", "state_set", |_caller: wasmtime::Caller<'_, ()>, _key_ptr: i32, _key_len: i32, _val_ptr: i32, _val_len: i32| -> i32 {
+            // Return 0 = success
+            0i32
+        }).expect("wrap state_set");
+
+        // Instantiate
+        let instance = linker.instantiate(&mut store, &module).expect("instantiate");
+
+        // Check memory info
+        let memory = instance.get_memory(&mut store, "memory").expect("get memory");
+        let pages = memory.size(&store);
+        println!("Memory: {} pages = {} bytes", pages, pages * 65536);
+
+        // Check heap_base
+        if let Some(g) = instance.get_global(&mut store, "__heap_base") {
+            let hb = g.get(&mut store).i32().unwrap_or(-1);
+            println!("__heap_base = {}", hb);
+            let available = (pages as i32 * 65536) - hb;
+            println!("Available heap: {} bytes", available);
+        }
+
+        // Get allocate function
+        let allocate: wasmtime::TypedFunc<i32, i32> = instance
+            .get_typed_func(&mut store, "allocate")
+            .expect("get allocate");
+
+        // Try allocation with small size
+        println!("Calling allocate(16)...");
+        match allocate.call(&mut store, 16) {
+            Ok(ptr) => {
+                println!("SUCCESS! Allocated 16 bytes at ptr = {}", ptr);
+                assert!(ptr > 0, "Pointer should be non-zero");
+            }
+            Err(e) => {
+                panic!("allocate(16) FAILED: {}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_allocate_with_fuel() {
+        // Same test but with fuel enabled
+        let bytes = std::fs::read("../../plugins/test-plugin/test_plugin.wasm")
+            .or_else(|_| std::fs::read("../../target/wasm32-unknown-unknown/release/test_plugin.wasm"))
+            .expect("test_plugin.wasm not found");
+
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true); // Enable fuel
+        let engine = Engine::new(&config).expect("create engine");
+        let module = Module::new(&engine, &bytes).expect("compile module");
+
+        let mut store: Store<()> = Store::new(&engine, ());
+        store.set_fuel(10_000_000).expect("set fuel"); // Lots of fuel
+
+        let mut linker: Linker<()> = Linker::new(&engine);
+
+        linker.func_wrap("env", "state_get", |_caller: wasmtime::Caller<'_, ()>, _key_ptr: i32, _key_len: i32| -> i32 {
+            0i32
+        }).expect("wrap state_get");
+        
+        linker.func_wrap("env", "state_set", |_caller: wasmtime::Caller<'_, ()>, _key_ptr: i32, _key_len: i32, _val_ptr: i32, _val_len: i32| -> i32 {
+            0i32
+        }).expect("wrap state_set");
+
+        let instance = linker.instantiate(&mut store, &module).expect("instantiate");
+
+        let allocate: wasmtime::TypedFunc<i32, i32> = instance
+            .get_typed_func(&mut store, "allocate")
+            .expect("get allocate");
+
+        println!("[WITH FUEL] Calling allocate(16)...");
+        match allocate.call(&mut store, 16) {
+            Ok(ptr) => {
+                println!("[WITH FUEL] SUCCESS! Allocated 16 bytes at ptr = {}", ptr);
+                assert!(ptr > 0);
+            }
+            Err(e) => {
+                panic!("[WITH FUEL] allocate(16) FAILED: {}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_allocate_with_limits() {
+        // Same test but with ResourceLimiter enabled
+        let bytes = std::fs::read("../../plugins/test-plugin/test_plugin.wasm")
+            .or_else(|_| std::fs::read("../../target/wasm32-unknown-unknown/release/test_plugin.wasm"))
+            .expect("test_plugin.wasm not found");
+
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("create engine");
+        let module = Module::new(&engine, &bytes).expect("compile module");
+
+        // Use a limiter that limits memory to 16MB
+        let limits = wasmtime::StoreLimitsBuilder::new()
+            .memory_size(16 * 1024 * 1024) // 16MB limit like sandbox
+            .build();
+
+        let mut store: Store<wasmtime::StoreLimits> = Store::new(&engine, limits);
+        store.limiter(|s| s);
+        store.set_fuel(10_000_000).expect("set fuel");
+
+        let mut linker: Linker<wasmtime::StoreLimits> = Linker::new(&engine);
+
+        linker.func_wrap("env", "state_get", |_caller: wasmtime::Caller<'_, wasmtime::StoreLimits>, _key_ptr: i32, _key_len: i32| -> i32 {
+            0i32
+        }).expect("wrap state_get");
+        
+        linker.func_wrap("env", "state_set", |_caller: wasmtime::Caller<'_, wasmtime::StoreLimits>, _key_ptr: i32, _key_len: i32, _val_ptr: i32, _val_len: i32| -> i32 {
+            0i32
+        }).expect("wrap state_set");
+
+        let instance = linker.instantiate(&mut store, &module).expect("instantiate");
+
+        let allocate: wasmtime::TypedFunc<i32, i32> = instance
+            .get_typed_func(&mut store, "allocate")
+            .expect("get allocate");
+
+        // Test with 16 bytes (small)
+        println!("[WITH LIMITS] Calling allocate(16)...");
+        match allocate.call(&mut store, 16) {
+            Ok(ptr) => {
+                println!("[WITH LIMITS] SUCCESS! Allocated 16 bytes at ptr = {}", ptr);
+                assert!(ptr > 0);
+            }
+            Err(e) => {
+                panic!("[WITH LIMITS] allocate(16) FAILED: {}", e);
+            }
+        }
+
+        // Test with 116 bytes (same as integration test context)
+        println!("[WITH LIMITS] Calling allocate(116)...");
+        match allocate.call(&mut store, 116) {
+            Ok(ptr) => {
+                println!("[WITH LIMITS] SUCCESS! Allocated 116 bytes at ptr = {}", ptr);
+                assert!(ptr > 0);
+            }
+            Err(e) => {
+                panic!("[WITH LIMITS] allocate(116) FAILED: {}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugin_queries_use_query_cache() {
+        let cache = Arc::new(QueryCache::new(std::time::Duration::from_secs(60), 10));
+        let mut store_data = StoreData::new(
+            "cached".to_string(),
+            Arc::new(SandboxConfig::minimal()),
+            PluginState::new(),
+            PluginConfig::new(),
+        );
+        store_data.query_cache = Some(Arc::clone(&cache));
+        let params = [serde_json::json!(1)];
+
+        store_data.run_query("SELECT * FROM notes WHERE id = ?", &params).unwrap();
+        store_data.run_query("select *\n  from notes where id = ?", &params).unwrap();
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().entries, 1);
+
+        // Another plugin never sees the cached rows
+        store_data.plugin_name = "other".to_string();
+        store_data.run_query("SELECT * FROM notes WHERE id = ?", &params).unwrap();
+        assert_eq!(cache.stats().hits, 1);
+
+        store_data.run_statement("UPDATE notes SET title = ?", &[]).unwrap();
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_allocate_exact_runtime_config() {
+        // Test with EXACT PluginRuntime configuration
+        let bytes = std::fs::read("../../plugins/test-plugin/test_plugin.wasm")
+            .or_else(|_| std::fs::read("../../target/wasm32-unknown-unknown/release/test_plugin.wasm"))
+            .expect("test_plugin.wasm not found");
+
+        // Exactly match PluginRuntime::new() config
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        // TEST: Enable epoch_interruption only
+        config.epoch_interruption(true);
+        // TEST: Comment out max_wasm_stack to see if that's the issue
+        // config.max_wasm_stack(512 * 1024);
+        let engine = Engine::new(&config).expect("create engine");
+        let module = Module::new(&engine, &bytes).expect("compile module");
+
+        // Match StoreData creation
+        let sandbox = Arc::new(SandboxConfig::minimal());
+        let state = PluginState::new();
+        let plugin_config = PluginConfig::new();
+        let store_data = StoreData::new("test-plugin".to_string(), sandbox.clone(), state, plugin_config);
+
+        let mut store = Store::new(&engine, store_data);
+        store.limiter(|data| &mut data.limits);
+        
+        // Set fuel exactly like execute() does
+        let fuel = u64::from(sandbox.time_limit_ms) * 1000;
+        println!("[EXACT CONFIG] Setting fuel to {}", fuel);
+        store.set_fuel(fuel).expect("set fuel");
+
+        let mut linker: Linker<StoreData> = Linker::new(&engine);
+        PluginRuntime::register_host_functions(&mut linker).expect("register hosts");
+
+        let instance = linker.instantiate(&mut store, &module).expect("instantiate");
+
+        let allocate: wasmtime::TypedFunc<i32, i32> = instance
+            .get_typed_func(&mut store, "allocate")
+            .expect("get allocate");
+
+        println!("[EXACT CONFIG] Calling allocate(116)...");
+        match allocate.call(&mut store, 116) {
+            Ok(ptr) => {
+                println!("[EXACT CONFIG] SUCCESS! Allocated 116 bytes at ptr = {}", ptr);
+                assert!(ptr > 0);
+            }
+            Err(e) => {
+                panic!("[EXACT CONFIG] allocate(116) FAILED: {}", e);
+            }
+        }
+    }
+}
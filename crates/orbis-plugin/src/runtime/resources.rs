@@ -0,0 +1,534 @@
+//! Per-plugin resource usage and alerts.
+//!
+//! Every handler execution reports the peak linear memory it used and
+//! whether it failed. [`PluginResourceMonitor::sample`] periodically drains
+//! these counters into one sample per plugin, kept in a bounded history the
+//! alert rules are evaluated against. Persisting samples is left to the host.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Number of samples kept per plugin (a day of samples taken every minute).
+pub const MAX_SAMPLE_HISTORY: usize = 1440;
+
+/// Capacity of the alert channel.
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+/// Calls an error rate rule needs in its period by default, so a single
+/// failed call does not fire it.
+const DEFAULT_MIN_CALLS: u64 = 20;
+
+/// Resource usage of a plugin over a sampling interval.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceSample {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// End of the interval.
+    pub at: DateTime<Utc>,
+
+    /// Peak linear memory of a single call, in bytes.
+    pub memory_bytes: u64,
+
+    /// Number of calls.
+    pub calls: u64,
+
+    /// Number of failed calls.
+    pub errors: u64,
+}
+
+/// Condition under which an alert rule fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "metric", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Peak memory stays above a limit in every sample of a period.
+    Memory {
+        /// Memory limit, in bytes.
+        above_bytes: u64,
+
+        /// Length of the period, in minutes.
+        minutes: u32,
+    },
+
+    /// The fraction of failed calls over a period exceeds a limit.
+    ErrorRate {
+        /// Error rate limit, between 0 and 1.
+        above: f64,
+
+        /// Length of the period, in minutes.
+        minutes: u32,
+
+        /// Calls needed in the period for the rule to fire.
+        #[serde(default = "default_min_calls")]
+        min_calls: u64,
+    },
+}
+
+/// Default calls an error rate rule needs.
+const fn default_min_calls() -> u64 {
+    DEFAULT_MIN_CALLS
+}
+
+impl AlertCondition {
+    /// Get the length of the period, in minutes.
+    #[must_use]
+    pub const fn minutes(&self) -> u32 {
+        match self {
+            Self::Memory { minutes, .. } | Self::ErrorRate { minutes, .. } => *minutes,
+        }
+    }
+}
+
+/// Alert rule evaluated against the sample history of plugins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Rule name, reported in alerts.
+    pub name: String,
+
+    /// Plugin the rule applies to (every plugin if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+
+    /// Condition firing the rule.
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+
+    /// Whether to disable the plugin when the rule fires.
+    #[serde(default)]
+    pub disable: bool,
+}
+
+impl AlertRule {
+    /// Validate the rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is missing or the condition is out of range.
+    pub fn validate(&self) -> orbis_core::Result<()> {
+        if self.name.is_empty() {
+            return Err(orbis_core::Error::validation("Alert rule name is required"));
+        }
+        if self.condition.minutes() == 0 {
+            return Err(orbis_core::Error::validation(format!(
+                "Alert rule '{}' must cover at least one minute",
+                self.name
+            )));
+        }
+        if let AlertCondition::ErrorRate { above, .. } = self.condition
+            && !(0.0..=1.0).contains(&above)
+        {
+            return Err(orbis_core::Error::validation(format!(
+                "Alert rule '{}' error rate must be between 0 and 1",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check if the rule applies to a plugin.
+    #[must_use]
+    pub fn applies_to(&self, plugin: &str) -> bool {
+        self.plugin.as_deref().is_none_or(|name| name == plugin)
+    }
+}
+
+/// A fired alert rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceAlert {
+    /// Name of the rule.
+    pub rule: String,
+
+    /// Plugin the rule fired for.
+    pub plugin: String,
+
+    /// Description of the alert.
+    pub message: String,
+
+    /// Observed value (bytes or error rate).
+    pub value: f64,
+
+    /// Limit the value exceeded.
+    pub threshold: f64,
+
+    /// Whether the rule asks for the plugin to be disabled.
+    pub disable: bool,
+
+    /// When the rule fired.
+    pub fired_at: DateTime<Utc>,
+}
+
+/// Usage of a plugin since the last sample.
+#[derive(Debug, Clone, Copy, Default)]
+struct Usage {
+    /// Peak linear memory of a single call, in bytes.
+    memory_bytes: u64,
+
+    /// Number of calls.
+    calls: u64,
+
+    /// Number of failed calls.
+    errors: u64,
+}
+
+/// Resource usage of all plugins, with alert rules.
+#[derive(Debug)]
+pub struct PluginResourceMonitor {
+    /// Usage since the last sample, by plugin.
+    current: DashMap<String, Usage>,
+
+    /// Recent samples, oldest first, by plugin.
+    history: DashMap<String, VecDeque<ResourceSample>>,
+
+    /// Alert rules.
+    rules: RwLock<Vec<AlertRule>>,
+
+    /// Rules currently firing, by rule and plugin name.
+    firing: Mutex<HashSet<(String, String)>>,
+
+    /// Alert sender.
+    alerts: broadcast::Sender<ResourceAlert>,
+}
+
+impl Default for PluginResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginResourceMonitor {
+    /// Create a monitor without samples or rules.
+    #[must_use]
+    pub fn new() -> Self {
+        let (alerts, _) = broadcast::channel(ALERT_CHANNEL_CAPACITY);
+        Self {
+            current: DashMap::new(),
+            history: DashMap::new(),
+            rules: RwLock::new(Vec::new()),
+            firing: Mutex::new(HashSet::new()),
+            alerts,
+        }
+    }
+
+    /// Replace the alert rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule is invalid; the previous rules are kept.
+    pub fn set_rules(&self, rules: Vec<AlertRule>) -> orbis_core::Result<()> {
+        for rule in &rules {
+            rule.validate()?;
+        }
+        *self.rules.write() = rules;
+        self.firing.lock().clear();
+        Ok(())
+    }
+
+    /// Get the alert rules.
+    #[must_use]
+    pub fn rules(&self) -> Vec<AlertRule> {
+        self.rules.read().clone()
+    }
+
+    /// Subscribe to fired alerts.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<ResourceAlert> {
+        self.alerts.subscribe()
+    }
+
+    /// Record a handler execution.
+    pub fn record(&self, plugin: &str, memory_bytes: usize, failed: bool) {
+        let mut usage = self.current.entry(plugin.to_owned()).or_default();
+        usage.memory_bytes = usage
+            .memory_bytes
+            .max(u64::try_from(memory_bytes).unwrap_or(u64::MAX));
+        usage.calls = usage.calls.saturating_add(1);
+        if failed {
+            usage.errors = usage.errors.saturating_add(1);
+        }
+    }
+
+    /// Take a sample of every known plugin, resetting the usage counters.
+    ///
+    /// Plugins without calls since the last sample get an empty sample, so
+    /// their history has no gaps.
+    pub fn sample(&self) -> Vec<ResourceSample> {
+        self.sample_at(Utc::now())
+    }
+
+    /// Take a sample of every known plugin at a given time.
+    fn sample_at(&self, at: DateTime<Utc>) -> Vec<ResourceSample> {
+        let mut usage: HashMap<String, Usage> = HashMap::new();
+        self.current.retain(|plugin, entry| {
+            usage.insert(plugin.clone(), *entry);
+            false
+        });
+        let known: Vec<String> = self.history.iter().map(|entry| entry.key().clone()).collect();
+        for plugin in known {
+            usage.entry(plugin).or_default();
+        }
+
+        let mut samples: Vec<ResourceSample> = usage
+            .into_iter()
+            .map(|(plugin, usage)| ResourceSample {
+                plugin,
+                at,
+                memory_bytes: usage.memory_bytes,
+                calls: usage.calls,
+                errors: usage.errors,
+            })
+            .collect();
+        samples.sort_by(|a, b| a.plugin.cmp(&b.plugin));
+
+        for sample in &samples {
+            let mut history = self.history.entry(sample.plugin.clone()).or_default();
+            if history.len() >= MAX_SAMPLE_HISTORY {
+                history.pop_front();
+            }
+            history.push_back(sample.clone());
+        }
+        samples
+    }
+
+    /// Get the recent samples of a plugin, oldest first.
+    #[must_use]
+    pub fn history(&self, plugin: &str) -> Vec<ResourceSample> {
+        self.history
+            .get(plugin)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Evaluate the alert rules and publish the alerts that start firing.
+    ///
+    /// A rule fires once per plugin until its condition clears.
+    pub fn check(&self) -> Vec<ResourceAlert> {
+        self.check_at(Utc::now())
+    }
+
+    /// Evaluate the alert rules at a given time.
+    fn check_at(&self, now: DateTime<Utc>) -> Vec<ResourceAlert> {
+        let rules = self.rules();
+        if rules.is_empty() {
+            return Vec::new();
+        }
+
+        let mut histories: Vec<(String, Vec<ResourceSample>)> = self
+            .history
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().iter().cloned().collect()))
+            .collect();
+        histories.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut alerts = Vec::new();
+        for (plugin, samples) in &histories {
+            for rule in rules.iter().filter(|rule| rule.applies_to(plugin)) {
+                let key = (rule.name.clone(), plugin.clone());
+                let Some((value, threshold)) = evaluate(&rule.condition, samples, now) else {
+                    self.firing.lock().remove(&key);
+                    continue;
+                };
+                if !self.firing.lock().insert(key) {
+                    continue;
+                }
+
+                let alert = ResourceAlert {
+                    rule: rule.name.clone(),
+                    plugin: plugin.clone(),
+                    message: describe(&rule.condition, plugin, value),
+                    value,
+                    threshold,
+                    disable: rule.disable,
+                    fired_at: now,
+                };
+                tracing::warn!("Resource alert '{}': {}", alert.rule, alert.message);
+                if self.alerts.send(alert.clone()).is_err() {
+                    tracing::trace!("No subscribers for resource alert '{}'", alert.rule);
+                }
+                alerts.push(alert);
+            }
+        }
+        alerts
+    }
+
+    /// Forget the usage, history and firing alerts of a plugin.
+    pub fn clear_plugin(&self, plugin: &str) {
+        self.current.remove(plugin);
+        self.history.remove(plugin);
+        self.firing.lock().retain(|(_, name)| name != plugin);
+    }
+}
+
+/// Check a condition against a plugin's samples, returning the observed
+/// value and the threshold if it holds.
+fn evaluate(condition: &AlertCondition, samples: &[ResourceSample], now: DateTime<Utc>) -> Option<(f64, f64)> {
+    let start = now.checked_sub_signed(chrono::Duration::minutes(i64::from(condition.minutes())))?;
+
+    // Only judge periods the history fully covers
+    if samples.first()?.at > start {
+        return None;
+    }
+    let window: Vec<&ResourceSample> = samples.iter().filter(|sample| sample.at > start).collect();
+
+    match *condition {
+        AlertCondition::Memory { above_bytes, .. } => {
+            let lowest = window.iter().map(|sample| sample.memory_bytes).min()?;
+            (lowest > above_bytes).then_some((lowest as f64, above_bytes as f64))
+        }
+        AlertCondition::ErrorRate { above, min_calls, .. } => {
+            let calls: u64 = window.iter().map(|sample| sample.calls).sum();
+            let errors: u64 = window.iter().map(|sample| sample.errors).sum();
+            if calls == 0 || calls < min_calls {
+                return None;
+            }
+            let rate = errors as f64 / calls as f64;
+            (rate > above).then_some((rate, above))
+        }
+    }
+}
+
+/// Describe a fired condition.
+fn describe(condition: &AlertCondition, plugin: &str, value: f64) -> String {
+    match condition {
+        AlertCondition::Memory { above_bytes, minutes } => format!(
+            "Plugin {} used more than {} bytes of memory for {} minutes",
+            plugin, above_bytes, minutes
+        ),
+        AlertCondition::ErrorRate { minutes, .. } => format!(
+            "Plugin {} failed {:.1}% of calls in the last {} minutes",
+            plugin,
+            value * 100.0,
+            minutes
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(start: DateTime<Utc>, minutes: i64) -> DateTime<Utc> {
+        start
+            .checked_add_signed(chrono::Duration::minutes(minutes))
+            .expect("valid time")
+    }
+
+    fn rule(value: serde_json::Value) -> AlertRule {
+        serde_json::from_value(value).expect("valid rule")
+    }
+
+    #[test]
+    fn test_sample_drains_usage() {
+        let monitor = PluginResourceMonitor::new();
+        monitor.record("notes", 2 << 20, false);
+        monitor.record("notes", 1 << 20, true);
+        monitor.record("reports", 4 << 20, false);
+
+        let samples = monitor.sample();
+        assert_eq!(samples.len(), 2);
+        let notes = samples.first().expect("notes sample");
+        assert_eq!((notes.memory_bytes, notes.calls, notes.errors), (2 << 20, 2, 1));
+
+        // Idle plugins keep getting samples
+        let samples = monitor.sample();
+        assert_eq!(samples.iter().map(|sample| sample.calls).sum::<u64>(), 0);
+        assert_eq!(monitor.history("notes").len(), 2);
+
+        monitor.clear_plugin("notes");
+        assert!(monitor.history("notes").is_empty());
+        assert_eq!(monitor.sample().len(), 1);
+    }
+
+    #[test]
+    fn test_memory_alert_fires_once_per_episode() {
+        let monitor = PluginResourceMonitor::new();
+        monitor
+            .set_rules(vec![rule(serde_json::json!({
+                "name": "memory",
+                "metric": "memory",
+                "above_bytes": 1_000_000,
+                "minutes": 3,
+                "disable": true
+            }))])
+            .expect("valid rules");
+        let mut alerts = monitor.subscribe();
+        let start = Utc::now();
+
+        for minute in 0..=3 {
+            monitor.record("reports", 2_000_000, false);
+            monitor.sample_at(minutes(start, minute));
+            // The period is not covered until three minutes of samples exist
+            let fired = monitor.check_at(minutes(start, minute));
+            assert_eq!(fired.len(), usize::from(minute == 3));
+        }
+        let alert = alerts.try_recv().expect("published alert");
+        assert_eq!((alert.rule.as_str(), alert.plugin.as_str()), ("memory", "reports"));
+        assert!(alert.disable);
+
+        // Still firing
+        monitor.record("reports", 2_000_000, false);
+        monitor.sample_at(minutes(start, 4));
+        assert!(monitor.check_at(minutes(start, 4)).is_empty());
+
+        // Cleared, then firing again
+        monitor.record("reports", 10, false);
+        monitor.sample_at(minutes(start, 5));
+        assert!(monitor.check_at(minutes(start, 5)).is_empty());
+        for minute in 6..=8 {
+            monitor.record("reports", 2_000_000, false);
+            monitor.sample_at(minutes(start, minute));
+        }
+        assert_eq!(monitor.check_at(minutes(start, 8)).len(), 1);
+    }
+
+    #[test]
+    fn test_error_rate_alert() {
+        let monitor = PluginResourceMonitor::new();
+        monitor
+            .set_rules(vec![rule(serde_json::json!({
+                "name": "errors",
+                "plugin": "notes",
+                "metric": "error_rate",
+                "above": 0.5,
+                "minutes": 1,
+                "min_calls": 10
+            }))])
+            .expect("valid rules");
+        let start = Utc::now();
+        monitor.record("notes", 0, false);
+        monitor.sample_at(start);
+
+        // Too few calls
+        for _ in 0..5 {
+            monitor.record("notes", 0, true);
+            monitor.record("reports", 0, true);
+        }
+        monitor.sample_at(minutes(start, 1));
+        assert!(monitor.check_at(minutes(start, 1)).is_empty());
+
+        for i in 0u32..12 {
+            monitor.record("notes", 0, i < 9);
+            monitor.record("reports", 0, true);
+        }
+        monitor.sample_at(minutes(start, 2));
+        let fired = monitor.check_at(minutes(start, 2));
+        assert_eq!(fired.len(), 1);
+        let alert = fired.first().expect("alert");
+        assert_eq!(alert.plugin, "notes");
+        assert!((alert.value - 0.75).abs() < f64::EPSILON);
+
+        monitor
+            .set_rules(vec![rule(serde_json::json!({
+                "name": "errors",
+                "metric": "error_rate",
+                "above": 2.0,
+                "minutes": 1
+            }))])
+            .unwrap_err();
+        assert_eq!(monitor.rules().len(), 1);
+    }
+}
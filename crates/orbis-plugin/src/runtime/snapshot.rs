@@ -0,0 +1,135 @@
+//! Memory snapshots of initialized plugin instances.
+//!
+//! After a plugin's `init` export succeeds, its linear memory and exported
+//! mutable globals are captured. When the instance restarts (after a trap
+//! or idle unloading), `init` is not run again: each per-request
+//! instantiation of the restarted instance restores the snapshot instead.
+//! Other instances start from the module's own memory.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use wasmtime::{ExternType, Instance, Memory, Module, Mutability, Store, Val};
+
+use super::StoreData;
+
+/// WASM page size in bytes.
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// Linear memory and global state captured after `init`.
+pub(super) struct MemorySnapshot {
+    /// SHA-256 of the code the snapshot was taken from.
+    code_hash: String,
+
+    /// Contents of the `memory` export.
+    memory: Vec<u8>,
+
+    /// Values of exported mutable globals.
+    globals: Vec<(String, Val)>,
+
+    /// When the snapshot was taken.
+    taken_at: DateTime<Utc>,
+}
+
+/// Snapshot metadata exposed for observability.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Size of the captured linear memory in bytes.
+    pub size_bytes: usize,
+
+    /// When the snapshot was taken.
+    pub taken_at: DateTime<Utc>,
+}
+
+impl SnapshotInfo {
+    /// Get the snapshot age in seconds.
+    #[must_use]
+    pub fn age_seconds(&self) -> i64 {
+        (Utc::now() - self.taken_at).num_seconds()
+    }
+}
+
+impl MemorySnapshot {
+    /// Get the SHA-256 of the code the snapshot was taken from.
+    pub(super) fn code_hash(&self) -> &str {
+        &self.code_hash
+    }
+
+    /// Get metadata about this snapshot.
+    pub(super) const fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            size_bytes: self.memory.len(),
+            taken_at: self.taken_at,
+        }
+    }
+
+    /// Run the instance's `init` export (if any) and capture its state.
+    ///
+    /// Returns `None` for modules without an exported memory.
+    pub(super) fn capture(
+        store: &mut Store<StoreData>,
+        module: &Module,
+        instance: &Instance,
+        code_hash: String,
+    ) -> orbis_core::Result<Option<Self>> {
+        if let Ok(init) = instance.get_typed_func::<(), i32>(&mut *store, "init") {
+            let status = init.call(&mut *store, ()).map_err(|e| {
+                orbis_core::Error::plugin(format!("Plugin init failed: {}", e))
+            })?;
+            if status == 0 {
+                return Err(orbis_core::Error::plugin("Plugin init returned failure"));
+            }
+        }
+
+        let Some(memory) = instance.get_memory(&mut *store, "memory") else {
+            return Ok(None);
+        };
+
+        let globals = module
+            .exports()
+            .filter(|export| {
+                matches!(export.ty(), ExternType::Global(global) if global.mutability() == Mutability::Var)
+            })
+            .filter_map(|export| {
+                let global = instance.get_global(&mut *store, export.name())?;
+                Some((export.name().to_string(), global.get(&mut *store)))
+            })
+            .collect();
+
+        Ok(Some(Self {
+            code_hash,
+            memory: memory.data(&*store).to_vec(),
+            globals,
+            taken_at: Utc::now(),
+        }))
+    }
+
+    /// Restore the snapshot into a freshly created instance.
+    pub(super) fn restore(
+        &self,
+        store: &mut Store<StoreData>,
+        instance: &Instance,
+        memory: &Memory,
+    ) -> orbis_core::Result<()> {
+        let current = memory.data_size(&*store);
+        if self.memory.len() > current {
+            let pages = (self.memory.len() - current).div_ceil(WASM_PAGE_SIZE);
+            memory.grow(&mut *store, pages as u64).map_err(|e| {
+                orbis_core::Error::plugin(format!("Failed to grow memory for snapshot: {}", e))
+            })?;
+        }
+
+        memory.write(&mut *store, 0, &self.memory).map_err(|e| {
+            orbis_core::Error::plugin(format!("Failed to restore memory snapshot: {}", e))
+        })?;
+
+        for (name, value) in &self.globals {
+            if let Some(global) = instance.get_global(&mut *store, name) {
+                global.set(&mut *store, *value).map_err(|e| {
+                    orbis_core::Error::plugin(format!("Failed to restore global '{}': {}", name, e))
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,364 @@
+//! Per-handler execution statistics.
+//!
+//! Every handler execution is timed. The most recent durations of each
+//! handler are kept to compute latency percentiles, along with call and
+//! error counts, peak memory and the last slow invocations. Handlers whose
+//! p95 latency or error rate exceed the configured thresholds are flagged.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use super::{PluginContext, RequestSummary};
+
+/// Number of recent durations kept per handler for percentiles.
+pub const LATENCY_SAMPLES: usize = 1024;
+
+/// Number of slow invocations kept per handler.
+pub const MAX_SLOW_INVOCATIONS: usize = 10;
+
+/// Calls a handler needs before it can be flagged, so a single slow or
+/// failed call does not flag it.
+const MIN_CALLS_TO_FLAG: u64 = 20;
+
+/// Thresholds above which handlers are flagged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HandlerThresholds {
+    /// Invocations taking longer than this are recorded as slow, and
+    /// handlers whose p95 latency exceeds it are flagged.
+    pub slow_ms: u64,
+
+    /// Handlers failing more often than this fraction of calls are flagged.
+    pub error_rate: f64,
+}
+
+impl Default for HandlerThresholds {
+    fn default() -> Self {
+        Self {
+            slow_ms: 1000,
+            error_rate: 0.05,
+        }
+    }
+}
+
+/// Why a handler was flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandlerFlag {
+    /// The p95 latency exceeds the slow threshold.
+    Slow,
+
+    /// The error rate exceeds the error rate threshold.
+    Failing,
+}
+
+/// A handler invocation that took longer than the slow threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowInvocation {
+    /// Execution time, in milliseconds.
+    pub duration_ms: u64,
+
+    /// Whether the invocation failed.
+    pub failed: bool,
+
+    /// Request being handled.
+    pub request: RequestSummary,
+
+    /// When the invocation finished.
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Statistics of a handler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandlerStatsReport {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler name.
+    pub handler: String,
+
+    /// Total number of calls.
+    pub calls: u64,
+
+    /// Number of failed calls.
+    pub errors: u64,
+
+    /// Fraction of calls that failed.
+    pub error_rate: f64,
+
+    /// Median latency of recent calls, in milliseconds.
+    pub p50_ms: f64,
+
+    /// 95th percentile latency of recent calls, in milliseconds.
+    pub p95_ms: f64,
+
+    /// 99th percentile latency of recent calls, in milliseconds.
+    pub p99_ms: f64,
+
+    /// Slowest recent call, in milliseconds.
+    pub max_ms: f64,
+
+    /// Peak linear memory of a single call, in bytes.
+    pub peak_memory_bytes: u64,
+
+    /// Most recent slow invocations, oldest first.
+    pub slow_invocations: Vec<SlowInvocation>,
+
+    /// Thresholds the handler exceeds.
+    pub flags: Vec<HandlerFlag>,
+}
+
+/// Recorded executions of a handler.
+#[derive(Debug, Default)]
+struct HandlerRecord {
+    /// Total number of calls.
+    calls: u64,
+
+    /// Number of failed calls.
+    errors: u64,
+
+    /// Peak linear memory of a single call, in bytes.
+    peak_memory_bytes: u64,
+
+    /// Most recent durations, in microseconds, oldest first.
+    samples: VecDeque<u64>,
+
+    /// Most recent slow invocations, oldest first.
+    slow: VecDeque<SlowInvocation>,
+}
+
+/// Execution statistics of all handlers.
+#[derive(Debug, Default)]
+pub struct HandlerStats {
+    /// Records by plugin and handler name.
+    handlers: DashMap<(String, String), HandlerRecord>,
+
+    /// Flagging thresholds.
+    thresholds: RwLock<HandlerThresholds>,
+}
+
+impl HandlerStats {
+    /// Create empty statistics with the default thresholds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the flagging thresholds.
+    pub fn set_thresholds(&self, thresholds: HandlerThresholds) {
+        *self.thresholds.write() = thresholds;
+    }
+
+    /// Get the flagging thresholds.
+    #[must_use]
+    pub fn thresholds(&self) -> HandlerThresholds {
+        *self.thresholds.read()
+    }
+
+    /// Record an execution of a handler and the peak linear memory it used,
+    /// in bytes.
+    pub fn record(
+        &self,
+        plugin: &str,
+        handler: &str,
+        duration: Duration,
+        peak_memory: usize,
+        failed: bool,
+        context: &PluginContext,
+    ) {
+        let slow_ms = self.thresholds().slow_ms;
+        let mut record = self
+            .handlers
+            .entry((plugin.to_owned(), handler.to_owned()))
+            .or_default();
+
+        record.calls = record.calls.saturating_add(1);
+        if failed {
+            record.errors = record.errors.saturating_add(1);
+        }
+        record.peak_memory_bytes = record
+            .peak_memory_bytes
+            .max(u64::try_from(peak_memory).unwrap_or(u64::MAX));
+
+        if record.samples.len() >= LATENCY_SAMPLES {
+            record.samples.pop_front();
+        }
+        record
+            .samples
+            .push_back(u64::try_from(duration.as_micros()).unwrap_or(u64::MAX));
+
+        let duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        if duration_ms > slow_ms {
+            tracing::warn!("Slow plugin handler {}::{} took {} ms", plugin, handler, duration_ms);
+            if record.slow.len() >= MAX_SLOW_INVOCATIONS {
+                record.slow.pop_front();
+            }
+            record.slow.push_back(SlowInvocation {
+                duration_ms,
+                failed,
+                request: RequestSummary {
+                    method: context.method.clone(),
+                    path: context.path.clone(),
+                    user_id: context.user_id.clone(),
+                    tenant_id: context.tenant_id.clone(),
+                },
+                occurred_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Get the statistics of a plugin's handlers, sorted by handler name.
+    #[must_use]
+    pub fn plugin(&self, plugin: &str) -> Vec<HandlerStatsReport> {
+        let thresholds = self.thresholds();
+        let mut reports: Vec<HandlerStatsReport> = self
+            .handlers
+            .iter()
+            .filter(|entry| entry.key().0 == plugin)
+            .map(|entry| report(&entry.key().0, &entry.key().1, entry.value(), &thresholds))
+            .collect();
+        reports.sort_by(|a, b| a.handler.cmp(&b.handler));
+        reports
+    }
+
+    /// Get the statistics of every handler exceeding a threshold.
+    #[must_use]
+    pub fn flagged(&self) -> Vec<HandlerStatsReport> {
+        let thresholds = self.thresholds();
+        let mut reports: Vec<HandlerStatsReport> = self
+            .handlers
+            .iter()
+            .map(|entry| report(&entry.key().0, &entry.key().1, entry.value(), &thresholds))
+            .filter(|report| !report.flags.is_empty())
+            .collect();
+        reports.sort_by(|a, b| (&a.plugin, &a.handler).cmp(&(&b.plugin, &b.handler)));
+        reports
+    }
+
+    /// Forget the statistics of a plugin's handlers.
+    pub fn clear_plugin(&self, plugin: &str) {
+        self.handlers.retain(|(name, _), _| name != plugin);
+    }
+}
+
+/// Build the report of a handler.
+fn report(plugin: &str, handler: &str, record: &HandlerRecord, thresholds: &HandlerThresholds) -> HandlerStatsReport {
+    let mut samples: Vec<u64> = record.samples.iter().copied().collect();
+    samples.sort_unstable();
+    let ms = |micros: u64| micros as f64 / 1000.0;
+
+    let error_rate = if record.calls == 0 {
+        0.0
+    } else {
+        record.errors as f64 / record.calls as f64
+    };
+    let p95_ms = ms(percentile(&samples, 95));
+
+    let mut flags = Vec::new();
+    if record.calls >= MIN_CALLS_TO_FLAG {
+        if p95_ms > thresholds.slow_ms as f64 {
+            flags.push(HandlerFlag::Slow);
+        }
+        if error_rate > thresholds.error_rate {
+            flags.push(HandlerFlag::Failing);
+        }
+    }
+
+    HandlerStatsReport {
+        plugin: plugin.to_owned(),
+        handler: handler.to_owned(),
+        calls: record.calls,
+        errors: record.errors,
+        error_rate,
+        p50_ms: ms(percentile(&samples, 50)),
+        p95_ms,
+        p99_ms: ms(percentile(&samples, 99)),
+        max_ms: ms(samples.last().copied().unwrap_or_default()),
+        peak_memory_bytes: record.peak_memory_bytes,
+        slow_invocations: record.slow.iter().cloned().collect(),
+        flags,
+    }
+}
+
+/// Nearest-rank percentile of sorted samples (0 without samples).
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    let rank = sorted.len().saturating_mul(percent).div_ceil(100);
+    sorted.get(rank.saturating_sub(1)).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> PluginContext {
+        PluginContext {
+            method: "GET".to_owned(),
+            path: "/api/plugins/reports/export".to_owned(),
+            headers: std::collections::HashMap::new(),
+            query: std::collections::HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: None,
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: super::super::CancellationFlag::new(),
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 50), 50);
+        assert_eq!(percentile(&samples, 95), 95);
+        assert_eq!(percentile(&samples, 99), 99);
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[7], 99), 7);
+    }
+
+    #[test]
+    fn test_slow_and_failing_handlers_are_flagged() {
+        let stats = HandlerStats::new();
+        stats.set_thresholds(HandlerThresholds {
+            slow_ms: 100,
+            error_rate: 0.1,
+        });
+        let context = context();
+
+        for i in 0u32..20 {
+            stats.record("reports", "export", Duration::from_millis(150), 65_536 * (i as usize + 1), false, &context);
+            stats.record("reports", "list", Duration::from_millis(5), 0, i.is_multiple_of(4), &context);
+        }
+        stats.record("notes", "list", Duration::from_millis(500), 0, true, &context);
+
+        let reports = stats.plugin("reports");
+        assert_eq!(reports.len(), 2);
+        let export = reports.first().expect("export handler");
+        assert_eq!(export.handler, "export");
+        assert_eq!(export.calls, 20);
+        assert_eq!(export.slow_invocations.len(), MAX_SLOW_INVOCATIONS);
+        assert_eq!(export.flags, [HandlerFlag::Slow]);
+        assert_eq!(export.peak_memory_bytes, 20 * 65_536);
+
+        let list = reports.get(1).expect("list handler");
+        assert_eq!(list.errors, 5);
+        assert!(list.slow_invocations.is_empty());
+        assert_eq!(list.flags, [HandlerFlag::Failing]);
+
+        // Too few calls to flag
+        let flagged: Vec<_> = stats.flagged().into_iter().map(|r| (r.plugin, r.handler)).collect();
+        assert_eq!(
+            flagged,
+            [("reports".to_owned(), "export".to_owned()), ("reports".to_owned(), "list".to_owned())]
+        );
+
+        stats.clear_plugin("reports");
+        assert!(stats.plugin("reports").is_empty());
+        assert_eq!(stats.plugin("notes").len(), 1);
+    }
+}
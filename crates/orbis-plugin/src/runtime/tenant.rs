@@ -0,0 +1,127 @@
+//! Tenant-scoped plugin state and configuration.
+//!
+//! In multi-tenant deployments each tenant gets its own plugin state,
+//! persisted under `.plugin_data/tenants/<tenant>/`, and its own
+//! configuration: the manifest config overlaid with the tenant's overrides.
+
+use dashmap::DashMap;
+use orbis_core::EncryptionKey;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{PluginConfig, PluginState};
+use crate::archive::StateData;
+
+/// Configuration overrides of a plugin, keyed by tenant ID.
+pub(super) type TenantOverrides = Arc<DashMap<String, HashMap<String, serde_json::Value>>>;
+
+/// State and configuration of a plugin for each tenant.
+#[derive(Default)]
+pub(super) struct TenantScopes {
+    /// Directory tenant state is persisted under, if persistence is enabled.
+    state_dir: Option<PathBuf>,
+
+    /// Key sealing persisted tenant state, for encrypted profiles.
+    key: Option<Arc<EncryptionKey>>,
+
+    /// Configuration overrides, shared with the runtime so they survive reloads.
+    overrides: TenantOverrides,
+
+    /// State of each tenant seen so far.
+    states: DashMap<String, PluginState>,
+}
+
+impl TenantScopes {
+    /// Create tenant scopes for a plugin instance.
+    pub(super) fn new(state_dir: Option<PathBuf>, key: Option<Arc<EncryptionKey>>, overrides: TenantOverrides) -> Self {
+        Self {
+            state_dir,
+            key,
+            overrides,
+            states: DashMap::new(),
+        }
+    }
+
+    /// Get the state of a tenant, loading it on first use.
+    pub(super) fn state(&self, plugin_name: &str, tenant: &str) -> orbis_core::Result<PluginState> {
+        if tenant.is_empty() || !tenant.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(orbis_core::Error::plugin(format!("Invalid tenant ID '{}'", tenant)));
+        }
+
+        let state = self
+            .states
+            .entry(tenant.to_owned())
+            .or_insert_with(|| {
+                self.state_dir.as_ref().map_or_else(PluginState::new, |dir| {
+                    PluginState::with_encrypted_persistence(
+                        dir.join(tenant).join(format!("{}.json", plugin_name)),
+                        self.key.clone(),
+                    )
+                })
+            })
+            .clone();
+
+        Ok(state)
+    }
+
+    /// Get the configuration of a tenant.
+    pub(super) fn config(&self, tenant: &str, base: &PluginConfig) -> PluginConfig {
+        self.overrides
+            .get(tenant)
+            .map_or_else(|| base.clone(), |overrides| base.with_overrides(overrides.value()))
+    }
+
+    /// Get the state of every tenant, including tenants persisted but not loaded yet.
+    pub(super) fn export(&self, plugin_name: &str) -> BTreeMap<String, StateData> {
+        self.tenant_ids(plugin_name)
+            .into_iter()
+            .filter_map(|tenant| {
+                let state = self.state(plugin_name, &tenant).ok()?;
+                Some((tenant, state.entries()))
+            })
+            .collect()
+    }
+
+    /// Replace the state of every tenant; tenants missing from `tenants` are cleared.
+    pub(super) fn import(&self, plugin_name: &str, tenants: &BTreeMap<String, StateData>) -> orbis_core::Result<()> {
+        for tenant in self.tenant_ids(plugin_name) {
+            if !tenants.contains_key(&tenant)
+                && let Ok(state) = self.state(plugin_name, &tenant)
+            {
+                state.clear();
+            }
+        }
+
+        for (tenant, data) in tenants {
+            self.state(plugin_name, tenant)?.replace(data.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Get the IDs of tenants with loaded or persisted state.
+    fn tenant_ids(&self, plugin_name: &str) -> BTreeSet<String> {
+        let mut tenants: BTreeSet<String> = self.states.iter().map(|entry| entry.key().clone()).collect();
+
+        if let Some(dir) = &self.state_dir
+            && let Ok(entries) = std::fs::read_dir(dir)
+        {
+            tenants.extend(
+                entries
+                    .flatten()
+                    .filter(|entry| entry.path().join(format!("{}.json", plugin_name)).is_file())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned()),
+            );
+        }
+
+        tenants
+    }
+
+    /// Clear the state of every tenant.
+    pub(super) fn clear(&self) {
+        for state in &self.states {
+            state.clear();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Structured reports for plugin traps.
+//!
+//! When a handler traps, the wasmtime backtrace is captured and each frame
+//! is mapped to a function name (from the name section) and, when the plugin
+//! was built with debug info, a source location (from DWARF).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use wasmtime::{Trap, WasmBacktrace};
+
+use super::PluginContext;
+
+/// A frame of a plugin trap backtrace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrapFrame {
+    /// Index of the function in the module.
+    pub func_index: u32,
+
+    /// Function name from the name section, if present.
+    pub func_name: Option<String>,
+
+    /// Offset of the trapping instruction in the module.
+    pub module_offset: Option<usize>,
+
+    /// Source file from DWARF debug info, if present.
+    pub file: Option<String>,
+
+    /// Source line from DWARF debug info, if present.
+    pub line: Option<u32>,
+}
+
+/// Summary of the request that was being handled when a plugin trapped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestSummary {
+    /// Request method.
+    pub method: String,
+
+    /// Request path.
+    pub path: String,
+
+    /// User ID (if authenticated).
+    pub user_id: Option<String>,
+
+    /// Tenant the request was made in.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
+
+/// Channel trap reports are sent to, drained by the host's audit log.
+pub type TrapSink = tokio::sync::mpsc::UnboundedSender<TrapReport>;
+
+/// Report of a plugin trap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrapReport {
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Handler being executed.
+    pub handler: String,
+
+    /// Error message.
+    pub message: String,
+
+    /// Trap code (e.g. `unreachable`, `out of bounds memory access`), if the error was a trap.
+    pub trap_code: Option<String>,
+
+    /// Backtrace frames, innermost first.
+    pub frames: Vec<TrapFrame>,
+
+    /// Request being handled.
+    pub request: RequestSummary,
+
+    /// When the trap occurred.
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl TrapReport {
+    /// Build a report from a handler execution error.
+    ///
+    /// Returns `None` if the error carries neither a trap code nor a backtrace
+    /// (e.g. host-side errors such as a malformed result).
+    pub(super) fn from_error(
+        plugin: &str,
+        handler: &str,
+        context: &PluginContext,
+        error: &wasmtime::Error,
+    ) -> Option<Self> {
+        let trap_code = error.downcast_ref::<Trap>().map(ToString::to_string);
+        let backtrace = error.downcast_ref::<WasmBacktrace>();
+        if trap_code.is_none() && backtrace.is_none() {
+            return None;
+        }
+
+        let frames = backtrace
+            .map(|backtrace| backtrace.frames().iter().map(Self::frame).collect())
+            .unwrap_or_default();
+
+        Some(Self {
+            plugin: plugin.to_string(),
+            handler: handler.to_string(),
+            message: format!("{:#}", error),
+            trap_code,
+            frames,
+            request: RequestSummary {
+                method: context.method.clone(),
+                path: context.path.clone(),
+                user_id: context.user_id.clone(),
+                tenant_id: context.tenant_id.clone(),
+            },
+            occurred_at: Utc::now(),
+        })
+    }
+
+    /// Convert a wasmtime frame, preferring DWARF symbols over the name section.
+    fn frame(frame: &wasmtime::FrameInfo) -> TrapFrame {
+        let symbol = frame.symbols().first();
+
+        TrapFrame {
+            func_index: frame.func_index(),
+            func_name: symbol
+                .and_then(|symbol| symbol.name())
+                .or_else(|| frame.func_name())
+                .map(String::from),
+            module_offset: frame.module_offset(),
+            file: symbol.and_then(|symbol| symbol.file()).map(String::from),
+            line: symbol.and_then(wasmtime::FrameSymbol::line),
+        }
+    }
+
+    /// Format the backtrace as one line per frame.
+    #[must_use]
+    pub fn backtrace(&self) -> String {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                let name = frame.func_name.as_deref().unwrap_or("<unknown>");
+                match (&frame.file, frame.line) {
+                    (Some(file), Some(line)) => format!("{:>3}: {} at {}:{}", index, name, file, line),
+                    _ => format!("{:>3}: {} (func #{})", index, name, frame.func_index),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -1,6 +1,6 @@
 //! Sandbox configuration for plugin security.
 
-use super::PluginPermission;
+use super::{FilesystemGrants, PluginPermission};
 use serde::{Deserialize, Serialize};
 
 /// Sandbox configuration for controlling plugin capabilities.
@@ -30,6 +30,9 @@ pub struct SandboxConfig {
     /// Allow environment variable access.
     pub allow_environment: bool,
 
+    /// Allow sending emails.
+    pub allow_email: bool,
+
     /// Memory limit in bytes.
     pub memory_limit: usize,
 
@@ -59,6 +62,7 @@ impl SandboxConfig {
             allow_system: false,
             allow_shell: false,
             allow_environment: false,
+            allow_email: false,
             memory_limit: 16 * 1024 * 1024, // 16MB
             time_limit_ms: 5000,            // 5 seconds
             max_calls: 10000,
@@ -82,6 +86,7 @@ impl SandboxConfig {
                 PluginPermission::System => config.allow_system = true,
                 PluginPermission::Shell => config.allow_shell = true,
                 PluginPermission::Environment => config.allow_environment = true,
+                PluginPermission::Email => config.allow_email = true,
                 PluginPermission::Custom(_) => {}
             }
         }
@@ -101,6 +106,7 @@ impl SandboxConfig {
             PluginPermission::System => self.allow_system,
             PluginPermission::Shell => self.allow_shell,
             PluginPermission::Environment => self.allow_environment,
+            PluginPermission::Email => self.allow_email,
             PluginPermission::Custom(_) => true, // Custom permissions are app-specific
         }
     }
@@ -126,6 +132,19 @@ impl SandboxConfig {
         self
     }
 
+    /// Allow the paths of filesystem grants.
+    #[must_use]
+    pub fn with_filesystem(mut self, grants: &FilesystemGrants) -> Self {
+        self.allowed_paths.extend(
+            grants
+                .read_only
+                .iter()
+                .chain(&grants.read_write)
+                .map(|path| path.to_string_lossy().into_owned()),
+        );
+        self
+    }
+
     /// Add allowed host.
     #[must_use]
     pub fn with_allowed_host(mut self, host: impl Into<String>) -> Self {
@@ -145,6 +164,7 @@ impl SandboxConfig {
             "system" => self.allow_system,
             "shell" => self.allow_shell,
             "environment" | "env" => self.allow_environment,
+            "email" => self.allow_email,
             _ => false,
         }
     }
@@ -0,0 +1,167 @@
+//! Global search across plugin search providers.
+
+use orbis_plugin_api::{SearchResult, ViewerAccess};
+use serde::Serialize;
+
+/// Default number of search results returned.
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Maximum number of search results returned.
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Maximum search query length, in characters.
+pub const MAX_SEARCH_QUERY_LENGTH: usize = 256;
+
+/// Global search request.
+#[derive(Debug, Clone, Default)]
+pub struct SearchRequest {
+    /// Search text.
+    pub query: String,
+
+    /// Maximum number of results to return.
+    pub limit: usize,
+
+    /// Roles and permissions of the searching user (`None` for anonymous users).
+    pub viewer: Option<ViewerAccess>,
+
+    /// ID of the searching user.
+    pub user_id: Option<String>,
+
+    /// Whether the searching user is an admin.
+    pub is_admin: bool,
+
+    /// Tenant the search runs in.
+    pub tenant_id: Option<String>,
+}
+
+impl SearchRequest {
+    /// Validate and normalize the query and limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty or too long.
+    pub fn normalize(&mut self) -> orbis_core::Result<()> {
+        self.query = self.query.trim().to_string();
+        if self.query.is_empty() {
+            return Err(orbis_core::Error::validation("Search query cannot be empty"));
+        }
+        if self.query.chars().count() > MAX_SEARCH_QUERY_LENGTH {
+            return Err(orbis_core::Error::validation(format!(
+                "Search query cannot be longer than {} characters",
+                MAX_SEARCH_QUERY_LENGTH
+            )));
+        }
+
+        self.limit = if self.limit == 0 {
+            DEFAULT_SEARCH_LIMIT
+        } else {
+            self.limit.min(MAX_SEARCH_LIMIT)
+        };
+        Ok(())
+    }
+}
+
+/// Search result attributed to the plugin that returned it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    /// Plugin that returned the result.
+    pub plugin: String,
+
+    /// Title shown in the result list.
+    pub title: String,
+
+    /// Short description or excerpt.
+    pub description: Option<String>,
+
+    /// Full route of the result (`/plugins/<plugin>/...`).
+    pub route: Option<String>,
+
+    /// Icon name.
+    pub icon: Option<String>,
+
+    /// Category used to group results.
+    pub category: Option<String>,
+
+    /// Relevance between 0 and 1.
+    pub score: f64,
+}
+
+impl SearchHit {
+    /// Attribute a provider result to its plugin, scoring it against the query.
+    #[must_use]
+    pub fn new(plugin: &str, query: &str, result: SearchResult) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            score: result.relevance(query),
+            route: result.route.map(|route| format!("/plugins/{}{}", plugin, route)),
+            title: result.title,
+            description: result.description,
+            icon: result.icon,
+            category: result.category,
+        }
+    }
+}
+
+/// Merged results of a global search.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchResponse {
+    /// Search text.
+    pub query: String,
+
+    /// Results, most relevant first.
+    pub results: Vec<SearchHit>,
+
+    /// Plugins whose provider failed or timed out.
+    pub failed: Vec<String>,
+}
+
+impl SearchResponse {
+    /// Rank the results by relevance (then title) and keep the best `limit`.
+    pub fn rank(&mut self, limit: usize) {
+        self.results
+            .sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+        self.results.truncate(limit);
+        self.failed.sort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_ranking() {
+        let results = SearchResult::parse_response(&json!([
+            { "title": "Invoices archive", "route": "/archive" },
+            { "title": "Invoices" },
+            { "title": "Old invoices" },
+            { "title": "Pinned", "score": 0.9 }
+        ]));
+
+        let mut response = SearchResponse {
+            query: "invoices".to_string(),
+            results: results
+                .into_iter()
+                .map(|result| SearchHit::new("billing", "invoices", result))
+                .collect(),
+            failed: vec!["slow".to_string()],
+        };
+        response.rank(3);
+
+        let titles: Vec<_> = response.results.iter().map(|hit| hit.title.as_str()).collect();
+        assert_eq!(titles, ["Invoices", "Pinned", "Invoices archive"]);
+        assert_eq!(response.results[2].route.as_deref(), Some("/plugins/billing/archive"));
+
+        let mut request = SearchRequest {
+            query: "  ".to_string(),
+            ..SearchRequest::default()
+        };
+        request.normalize().unwrap_err();
+
+        request.query = " invoices ".to_string();
+        request.normalize().unwrap();
+        assert_eq!(request.query, "invoices");
+        assert_eq!(request.limit, DEFAULT_SEARCH_LIMIT);
+    }
+}
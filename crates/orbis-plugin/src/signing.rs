@@ -0,0 +1,545 @@
+//! Plugin signing and signature verification.
+//!
+//! A signed standalone plugin carries two custom sections: [`HASH_SECTION`]
+//! with the SHA-256 hash of the module (hex), and [`SIGNATURE_SECTION`] with
+//! an Ed25519 signature of that hash and the ID of the signing key (JSON).
+//! Both sections are excluded from the hash, so signing a plugin again
+//! replaces its signature.
+//!
+//! A signed unpacked plugin carries a [`DIRECTORY_SIGNATURE_FILE`] with the
+//! [`MerkleManifest`] of its files and a signature of its root hash. The
+//! signature is checked when the plugin is loaded, and each file against its
+//! hash when it is read.
+//!
+//! Keys are stored as hex: 32-byte secret keys for signing, and 32-byte
+//! public keys in the [`Keyring`] of keys the host trusts.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use orbis_plugin_api::{HASH_SECTION, SIGNATURE_SECTION};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::merkle::{hash_dir, MerkleManifest};
+
+/// Length of the module header (magic and version).
+const HEADER_LEN: usize = 8;
+
+/// Signature file of a signed unpacked plugin.
+pub const DIRECTORY_SIGNATURE_FILE: &str = "signature.json";
+
+/// Extension of public key files in a keyring directory.
+pub const PUBLIC_KEY_EXTENSION: &str = "pub";
+
+/// What to do with standalone plugins that are unsigned or fail verification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// Don't check signatures.
+    #[default]
+    Off,
+
+    /// Log a warning and load the plugin anyway.
+    Warn,
+
+    /// Refuse to load the plugin.
+    Require,
+}
+
+impl std::str::FromStr for SignaturePolicy {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "require" => Ok(Self::Require),
+            _ => Err(orbis_core::Error::config(format!(
+                "Invalid signature policy: '{}'. Expected 'off', 'warn' or 'require'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Contents of the signature section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginSignature {
+    /// ID of the signing key.
+    pub key_id: String,
+
+    /// Ed25519 signature of the module hash, as hex.
+    pub signature: String,
+}
+
+/// Contents of the signature file of an unpacked plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectorySignature {
+    /// Hashes of the plugin's files.
+    pub manifest: MerkleManifest,
+
+    /// Signature of the root hash.
+    pub signature: PluginSignature,
+}
+
+/// Result of verifying a plugin's signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The plugin has no signature.
+    Unsigned,
+
+    /// The plugin is signed by a trusted key and unmodified.
+    Verified {
+        /// ID of the signing key.
+        key_id: String,
+    },
+}
+
+/// Public keys trusted to sign plugins, by key ID.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    /// Trusted keys.
+    keys: HashMap<String, VerifyingKey>,
+}
+
+impl Keyring {
+    /// Create an empty keyring.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a keyring with the public key files (`*.pub`) in a directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a key cannot be read.
+    pub fn load(dir: &Path) -> orbis_core::Result<Self> {
+        let mut keyring = Self::new();
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(PUBLIC_KEY_EXTENSION) {
+                continue;
+            }
+
+            let key = decode_public_key(&std::fs::read_to_string(&path)?)
+                .map_err(|e| orbis_core::Error::config(format!("Invalid public key {}: {}", path.display(), e)))?;
+            tracing::debug!("Trusting plugin signing key {} from {}", key_id(&key), path.display());
+            keyring.insert(key);
+        }
+
+        Ok(keyring)
+    }
+
+    /// Trust a public key, returning its ID.
+    pub fn insert(&mut self, key: VerifyingKey) -> String {
+        let id = key_id(&key);
+        self.keys.insert(id.clone(), key);
+        id
+    }
+
+    /// Number of trusted keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no keys are trusted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Verify the signature embedded in a module.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed, made by an untrusted
+    /// key, or does not match the module.
+    pub fn verify(&self, wasm: &[u8]) -> orbis_core::Result<SignatureStatus> {
+        let sections = SignatureSections::read(wasm)?;
+        let Some(signature) = sections.signature else {
+            return Ok(SignatureStatus::Unsigned);
+        };
+
+        let signature: PluginSignature = serde_json::from_slice(&signature)
+            .map_err(|e| orbis_core::Error::plugin(format!("Malformed plugin signature: {}", e)))?;
+        let hash = module_hash(wasm)?;
+        if sections.hash.is_some_and(|embedded| embedded != hash.as_bytes()) {
+            return Err(orbis_core::Error::plugin("Plugin was modified after signing"));
+        }
+
+        self.verify_hash(&hash, signature)
+    }
+
+    /// Verify the signature of an unpacked plugin.
+    ///
+    /// Only the root hash is checked against the file hashes; the files
+    /// themselves are checked when they are read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed, made by an untrusted
+    /// key, or does not match the file hashes.
+    pub fn verify_directory(&self, signature: &DirectorySignature) -> orbis_core::Result<SignatureStatus> {
+        signature.manifest.verify_root()?;
+        self.verify_hash(&signature.manifest.root, signature.signature.clone())
+    }
+
+    /// Verify a signature of a hash.
+    fn verify_hash(&self, hash: &str, signature: PluginSignature) -> orbis_core::Result<SignatureStatus> {
+        let key = self.keys.get(&signature.key_id).ok_or_else(|| {
+            orbis_core::Error::plugin(format!("Plugin is signed by untrusted key {}", signature.key_id))
+        })?;
+        let bytes: [u8; 64] = hex::decode(&signature.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| orbis_core::Error::plugin("Malformed plugin signature"))?;
+        key.verify(hash.as_bytes(), &Signature::from_bytes(&bytes))
+            .map_err(|_invalid| orbis_core::Error::plugin("Plugin signature does not match its code"))?;
+
+        Ok(SignatureStatus::Verified {
+            key_id: signature.key_id,
+        })
+    }
+}
+
+/// Sign a module, replacing any previous signature.
+///
+/// # Errors
+///
+/// Returns an error if the input is not a WASM binary.
+pub fn sign(wasm: &[u8], key: &SigningKey) -> orbis_core::Result<Vec<u8>> {
+    let mut signed = strip_signature(wasm)?;
+    let hash = hex::encode(Sha256::digest(&signed));
+    let signature = PluginSignature {
+        key_id: key_id(&key.verifying_key()),
+        signature: hex::encode(key.sign(hash.as_bytes()).to_bytes()),
+    };
+
+    push_custom_section(&mut signed, HASH_SECTION, hash.as_bytes());
+    push_custom_section(&mut signed, SIGNATURE_SECTION, &serde_json::to_vec(&signature)?);
+    Ok(signed)
+}
+
+/// Sign an unpacked plugin, writing its signature file and replacing any
+/// previous one.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be hashed or the signature file
+/// cannot be written.
+pub fn sign_directory(dir: &Path, key: &SigningKey) -> orbis_core::Result<DirectorySignature> {
+    let manifest = hash_dir(dir)?;
+    let signature = DirectorySignature {
+        signature: PluginSignature {
+            key_id: key_id(&key.verifying_key()),
+            signature: hex::encode(key.sign(manifest.root.as_bytes()).to_bytes()),
+        },
+        manifest,
+    };
+
+    std::fs::write(dir.join(DIRECTORY_SIGNATURE_FILE), serde_json::to_vec_pretty(&signature)?)?;
+    Ok(signature)
+}
+
+/// Read the signature file of an unpacked plugin, if it has one.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is malformed.
+pub fn read_directory_signature(dir: &Path) -> orbis_core::Result<Option<DirectorySignature>> {
+    let path = dir.join(DIRECTORY_SIGNATURE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    serde_json::from_slice(&std::fs::read(path)?)
+        .map(Some)
+        .map_err(|e| orbis_core::Error::plugin(format!("Malformed plugin signature: {}", e)))
+}
+
+/// Generate a new signing key.
+#[must_use]
+pub fn generate_key() -> SigningKey {
+    SigningKey::from_bytes(&rand::random())
+}
+
+/// ID of a public key: the first 8 bytes of its SHA-256 hash, as hex.
+#[must_use]
+pub fn key_id(key: &VerifyingKey) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()).get(..8).unwrap_or_default())
+}
+
+/// Encode a signing key as hex.
+#[must_use]
+pub fn encode_signing_key(key: &SigningKey) -> String {
+    hex::encode(key.to_bytes())
+}
+
+/// Encode a public key as hex.
+#[must_use]
+pub fn encode_public_key(key: &VerifyingKey) -> String {
+    hex::encode(key.as_bytes())
+}
+
+/// Decode a hex signing key.
+///
+/// # Errors
+///
+/// Returns an error if the key is not 32 bytes of hex.
+pub fn decode_signing_key(hex: &str) -> orbis_core::Result<SigningKey> {
+    Ok(SigningKey::from_bytes(&decode_key_bytes(hex)?))
+}
+
+/// Decode a hex public key.
+///
+/// # Errors
+///
+/// Returns an error if the key is not a valid 32-byte Ed25519 key in hex.
+pub fn decode_public_key(hex: &str) -> orbis_core::Result<VerifyingKey> {
+    VerifyingKey::from_bytes(&decode_key_bytes(hex)?)
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid public key: {}", e)))
+}
+
+/// Decode 32 bytes of hex.
+fn decode_key_bytes(text: &str) -> orbis_core::Result<[u8; 32]> {
+    hex::decode(text.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| orbis_core::Error::validation("Key must be 32 bytes of hex"))
+}
+
+/// SHA-256 hash of a module without its signature sections, as hex.
+fn module_hash(wasm: &[u8]) -> orbis_core::Result<String> {
+    Ok(hex::encode(Sha256::digest(strip_signature(wasm)?)))
+}
+
+/// Signature sections found in a module.
+#[derive(Default)]
+struct SignatureSections {
+    /// Contents of the hash section.
+    hash: Option<Vec<u8>>,
+
+    /// Contents of the signature section.
+    signature: Option<Vec<u8>>,
+}
+
+impl SignatureSections {
+    /// Read the signature sections of a module.
+    fn read(wasm: &[u8]) -> orbis_core::Result<Self> {
+        let mut sections = Self::default();
+        for section in sections_of(wasm)? {
+            match section.custom_name {
+                Some(HASH_SECTION) => sections.hash = Some(section.data.to_vec()),
+                Some(SIGNATURE_SECTION) => sections.signature = Some(section.data.to_vec()),
+                Some(_) | None => {}
+            }
+        }
+        Ok(sections)
+    }
+}
+
+/// Copy a module without its signature sections.
+fn strip_signature(wasm: &[u8]) -> orbis_core::Result<Vec<u8>> {
+    let mut stripped = wasm.get(..HEADER_LEN).unwrap_or_default().to_vec();
+    for section in sections_of(wasm)? {
+        if !matches!(section.custom_name, Some(HASH_SECTION | SIGNATURE_SECTION)) {
+            stripped.extend_from_slice(section.bytes);
+        }
+    }
+    Ok(stripped)
+}
+
+/// Append a custom section to a module.
+fn push_custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut contents = Vec::with_capacity(name.len().saturating_add(data.len()).saturating_add(5));
+    push_leb128(&mut contents, name.len());
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(data);
+
+    wasm.push(0);
+    push_leb128(wasm, contents.len());
+    wasm.extend_from_slice(&contents);
+}
+
+/// Append an unsigned LEB128 integer.
+fn push_leb128(output: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+/// A top-level section of a module or component.
+struct Section<'a> {
+    /// The whole section, including its ID and size.
+    bytes: &'a [u8],
+
+    /// Name of a custom section.
+    custom_name: Option<&'a str>,
+
+    /// Contents of a custom section after its name.
+    data: &'a [u8],
+}
+
+/// Split a module or component into its top-level sections.
+fn sections_of(wasm: &[u8]) -> orbis_core::Result<Vec<Section<'_>>> {
+    let malformed = || orbis_core::Error::plugin("Malformed WASM binary");
+    if wasm.get(..4) != Some(b"\0asm".as_slice()) || wasm.len() < HEADER_LEN {
+        return Err(orbis_core::Error::plugin("Not a WASM binary"));
+    }
+
+    let mut sections = Vec::new();
+    let mut offset = HEADER_LEN;
+    while let Some((&id, rest)) = wasm.get(offset..).and_then(<[u8]>::split_first) {
+        let (size, size_len) = read_leb128(rest)?;
+        let start = offset.saturating_add(1).saturating_add(size_len);
+        let end = start.checked_add(size).ok_or_else(malformed)?;
+        let contents = wasm.get(start..end).ok_or_else(malformed)?;
+
+        let (custom_name, data) = if id == 0 {
+            let (name_len, name_len_size) = read_leb128(contents)?;
+            let name_end = name_len_size.checked_add(name_len).ok_or_else(malformed)?;
+            let name = contents.get(name_len_size..name_end).ok_or_else(malformed)?;
+            let name = std::str::from_utf8(name).map_err(|_invalid| malformed())?;
+            (Some(name), contents.get(name_end..).unwrap_or_default())
+        } else {
+            (None, contents)
+        };
+
+        sections.push(Section {
+            bytes: wasm.get(offset..end).unwrap_or_default(),
+            custom_name,
+            data,
+        });
+        offset = end;
+    }
+    Ok(sections)
+}
+
+/// Read an unsigned LEB128 integer, returning it and its length in bytes.
+fn read_leb128(bytes: &[u8]) -> orbis_core::Result<(usize, usize)> {
+    let mut value: usize = 0;
+    for (index, byte) in bytes.iter().take(5).enumerate() {
+        value |= usize::from(byte & 0x7f) << (index * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(orbis_core::Error::plugin("Malformed WASM binary"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty module with a custom section.
+    fn module() -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        push_custom_section(&mut wasm, "manifest", br#"{"name":"test"}"#);
+        wasm
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = generate_key();
+        let mut keyring = Keyring::new();
+        assert_eq!(keyring.verify(&module()).unwrap(), SignatureStatus::Unsigned);
+
+        let key_id = keyring.insert(key.verifying_key());
+        let signed = sign(&module(), &key).unwrap();
+        assert_eq!(keyring.verify(&signed).unwrap(), SignatureStatus::Verified { key_id });
+
+        // Signing again replaces the signature
+        let resigned = sign(&signed, &key).unwrap();
+        assert_eq!(resigned.len(), signed.len());
+        keyring.verify(&resigned).unwrap();
+
+        // Other sections are kept
+        let sections = sections_of(&signed).unwrap();
+        assert_eq!(sections[0].custom_name, Some("manifest"));
+        assert_eq!(sections[0].data, br#"{"name":"test"}"#);
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering_and_untrusted_keys() {
+        let key = generate_key();
+        let signed = sign(&module(), &key).unwrap();
+
+        // Untrusted key
+        Keyring::new().verify(&signed).unwrap_err();
+
+        let mut keyring = Keyring::new();
+        keyring.insert(key.verifying_key());
+
+        // Modified code
+        let mut tampered = signed;
+        let index = tampered.iter().position(|byte| *byte == b't').unwrap();
+        tampered[index] = b'T';
+        keyring.verify(&tampered).unwrap_err();
+
+        // Signature by another key claiming a trusted key ID
+        let forged = sign(&module(), &generate_key()).unwrap();
+        let mut forged_signature: PluginSignature =
+            serde_json::from_slice(&SignatureSections::read(&forged).unwrap().signature.unwrap()).unwrap();
+        forged_signature.key_id = key_id(&key.verifying_key());
+        let mut forged = strip_signature(&forged).unwrap();
+        push_custom_section(&mut forged, SIGNATURE_SECTION, &serde_json::to_vec(&forged_signature).unwrap());
+        keyring.verify(&forged).unwrap_err();
+    }
+
+    #[test]
+    fn test_sign_and_verify_directory() {
+        let dir = std::env::temp_dir().join(format!("orbis-signing-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("plugin.wasm"), module()).unwrap();
+        std::fs::write(dir.join("assets/logo.svg"), b"<svg/>").unwrap();
+        assert!(read_directory_signature(&dir).unwrap().is_none());
+
+        let key = generate_key();
+        let mut keyring = Keyring::new();
+        let key_id = keyring.insert(key.verifying_key());
+        sign_directory(&dir, &key).unwrap();
+
+        // Signing again replaces the signature without hashing it
+        let signature = sign_directory(&dir, &key).unwrap();
+        assert_eq!(read_directory_signature(&dir).unwrap(), Some(signature.clone()));
+        assert_eq!(
+            keyring.verify_directory(&signature).unwrap(),
+            SignatureStatus::Verified { key_id }
+        );
+        Keyring::new().verify_directory(&signature).unwrap_err();
+
+        // File hashes changed after signing
+        let mut tampered = signature.clone();
+        tampered.manifest = MerkleManifest::from_files(
+            [("plugin.wasm".to_string(), hex::encode(Sha256::digest(b"other")))].into_iter().collect(),
+        );
+        keyring.verify_directory(&tampered).unwrap_err();
+        tampered.manifest.root = signature.manifest.root;
+        keyring.verify_directory(&tampered).unwrap_err();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_key_encoding() {
+        let key = generate_key();
+        let decoded = decode_signing_key(&encode_signing_key(&key)).unwrap();
+        assert_eq!(decoded.to_bytes(), key.to_bytes());
+
+        let public = decode_public_key(&format!("{}\n", encode_public_key(&key.verifying_key()))).unwrap();
+        assert_eq!(public, key.verifying_key());
+
+        decode_public_key("not hex").unwrap_err();
+        sign(b"not wasm", &key).unwrap_err();
+    }
+}
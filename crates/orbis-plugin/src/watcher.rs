@@ -11,8 +11,12 @@ use notify::{
     RecursiveMode,
     Watcher,
 };
+use glob::{
+    MatchOptions,
+    Pattern,
+};
 use std::{
-    collections::HashSet,
+    collections::HashMap,
     path::{
         Path,
         PathBuf,
@@ -24,9 +28,19 @@ use std::{
             Ordering,
         },
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
+use serde::Serialize;
 use tokio::sync::mpsc;
+
+use crate::reload::{
+    ReloadEvent,
+    ReloadEvents,
+    ReloadStage,
+};
 use tracing::{
     debug,
     error,
@@ -35,7 +49,8 @@ use tracing::{
 };
 
 /// Plugin change event types.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PluginChangeKind {
     /// Plugin was added or installed.
     Added,
@@ -101,6 +116,10 @@ impl PluginChangeEvent {
     }
 }
 
+/// Default ignore patterns: build output, dependencies, temporary files and
+/// fetched remote plugins.
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &["target/**", "node_modules/**", "*.tmp", ".cache/**"];
+
 /// Plugin watcher configuration.
 #[derive(Debug, Clone)]
 pub struct WatcherConfig {
@@ -110,6 +129,14 @@ pub struct WatcherConfig {
     pub debounce_duration: Duration,
     /// Whether to watch recursively.
     pub recursive: bool,
+    /// Glob patterns of paths to ignore, relative to the watch directory.
+    ///
+    /// Patterns match from any directory level, so `target/**` ignores every
+    /// `target` directory and `*.tmp` every temporary file.
+    pub ignore: Vec<String>,
+    /// How long a changed file's size must stay the same before the change
+    /// is reported, so builds still writing a plugin are not loaded.
+    pub settle_duration: Duration,
 }
 
 impl Default for WatcherConfig {
@@ -118,10 +145,78 @@ impl Default for WatcherConfig {
             watch_dir: PathBuf::from("./plugins"),
             debounce_duration: Duration::from_millis(500),
             recursive: true,
+            ignore: DEFAULT_IGNORE_PATTERNS.iter().map(|&p| p.to_owned()).collect(),
+            settle_duration: Duration::from_millis(500),
         }
     }
 }
 
+/// Compiled ignore patterns.
+#[derive(Debug, Clone, Default)]
+struct IgnoreSet {
+    /// Patterns matched against path suffixes.
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreSet {
+    /// Compile ignore patterns.
+    fn new(patterns: &[String]) -> orbis_core::Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                Pattern::new(p).map_err(|e| {
+                    orbis_core::Error::plugin(format!("Invalid watcher ignore pattern '{}': {}", p, e))
+                })
+            })
+            .collect::<orbis_core::Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Check if a path, relative to the watch directory, is ignored.
+    ///
+    /// Every suffix of the path is tried, and a directory pattern
+    /// (`dir/**`) also matches the directory itself.
+    fn is_ignored(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let components: Vec<String> = path
+            .components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                std::path::Component::Prefix(_)
+                | std::path::Component::RootDir
+                | std::path::Component::CurDir
+                | std::path::Component::ParentDir => None,
+            })
+            .collect();
+
+        (0..components.len()).any(|start| {
+            let suffix = components.get(start..).unwrap_or_default().join("/");
+            let as_dir = format!("{}/", suffix);
+            self.patterns
+                .iter()
+                .any(|p| p.matches_with(&suffix, options) || p.matches_with(&as_dir, options))
+        })
+    }
+}
+
+/// A change waiting for its debounce and settle periods.
+#[derive(Debug)]
+struct PendingChange {
+    /// Plugin ID derived from the path.
+    plugin_id: Option<String>,
+    /// Whether the path was created during the period.
+    added: bool,
+    /// When the last file event for the path arrived.
+    last_event: Instant,
+    /// File size at the last check, if the path is a file.
+    size: Option<u64>,
+    /// When the file size last changed.
+    size_changed: Instant,
+}
+
 /// Plugin file system watcher.
 ///
 /// Watches the plugins directory and emits events when plugins change.
@@ -132,6 +227,8 @@ pub struct PluginWatcher {
     running: Arc<AtomicBool>,
     /// Shutdown signal sender.
     shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    /// Channel reporting detected changes and files still being written.
+    events: Option<ReloadEvents>,
 }
 
 impl PluginWatcher {
@@ -142,9 +239,18 @@ impl PluginWatcher {
             config,
             running: Arc::new(AtomicBool::new(false)),
             shutdown_tx: None,
+            events: None,
         }
     }
 
+    /// Report detected changes and files still being written on a reload
+    /// event channel.
+    #[must_use]
+    pub fn with_events(mut self, events: ReloadEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     /// Create with default configuration.
     #[must_use]
     pub fn with_default_config() -> Self {
@@ -163,7 +269,8 @@ impl PluginWatcher {
     ///
     /// # Errors
     ///
-    /// Returns an error if the watcher cannot be started.
+    /// Returns an error if the watcher cannot be started or an ignore
+    /// pattern is invalid.
     pub fn start(
         &mut self,
     ) -> orbis_core::Result<mpsc::UnboundedReceiver<PluginChangeEvent>> {
@@ -171,6 +278,8 @@ impl PluginWatcher {
             return Err(orbis_core::Error::plugin("Plugin watcher is already running"));
         }
 
+        let ignore = IgnoreSet::new(&self.config.ignore)?;
+
         // Ensure watch directory exists
         if !self.config.watch_dir.exists() {
             std::fs::create_dir_all(&self.config.watch_dir).map_err(|e| {
@@ -184,21 +293,14 @@ impl PluginWatcher {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
 
-        let watch_dir = self.config.watch_dir.clone();
-        let debounce_duration = self.config.debounce_duration;
-        let recursive = self.config.recursive;
+        let config = self.config.clone();
         let running = self.running.clone();
+        let events = self.events.clone();
 
         // Spawn watcher in a blocking task since notify uses std channels
         std::thread::spawn(move || {
-            let result = Self::run_watcher(
-                watch_dir,
-                debounce_duration,
-                recursive,
-                event_tx,
-                shutdown_rx,
-                running,
-            );
+            let result =
+                Self::run_watcher(&config, &ignore, &event_tx, events.as_ref(), shutdown_rx, &running);
 
             if let Err(e) = result {
                 error!("Plugin watcher error: {}", e);
@@ -225,12 +327,12 @@ impl PluginWatcher {
     /// Run the file watcher (blocking).
     #[allow(clippy::cognitive_complexity)]
     fn run_watcher(
-        watch_dir: PathBuf,
-        debounce_duration: Duration,
-        recursive: bool,
-        event_tx: mpsc::UnboundedSender<PluginChangeEvent>,
+        config: &WatcherConfig,
+        ignore: &IgnoreSet,
+        event_tx: &mpsc::UnboundedSender<PluginChangeEvent>,
+        events: Option<&ReloadEvents>,
         mut shutdown_rx: tokio::sync::oneshot::Receiver<()>,
-        running: Arc<AtomicBool>,
+        running: &AtomicBool,
     ) -> orbis_core::Result<()> {
         // Create a channel for notify events
         let (tx, rx) = std::sync::mpsc::channel();
@@ -242,26 +344,29 @@ impl PluginWatcher {
                     let _ = tx.send(event);
                 }
             },
-            NotifyConfig::default().with_poll_interval(debounce_duration),
+            NotifyConfig::default().with_poll_interval(config.debounce_duration),
         )
         .map_err(|e| orbis_core::Error::plugin(format!("Failed to create file watcher: {}", e)))?;
 
         // Start watching
-        let mode = if recursive {
+        let mode = if config.recursive {
             RecursiveMode::Recursive
         } else {
             RecursiveMode::NonRecursive
         };
 
-        watcher.watch(&watch_dir, mode).map_err(|e| {
+        watcher.watch(&config.watch_dir, mode).map_err(|e| {
             orbis_core::Error::plugin(format!("Failed to watch directory: {}", e))
         })?;
 
+        // Event paths are absolute, so match ignore patterns from the absolute root
+        let watch_dir = config
+            .watch_dir
+            .canonicalize()
+            .unwrap_or_else(|_err| config.watch_dir.clone());
         debug!("Watching directory: {:?}", watch_dir);
 
-        // Track seen events for debouncing
-        let mut pending_events: HashSet<PathBuf> = HashSet::new();
-        let mut last_event_time = std::time::Instant::now();
+        let mut pending: HashMap<PathBuf, PendingChange> = HashMap::new();
 
         // Process events
         loop {
@@ -274,75 +379,121 @@ impl PluginWatcher {
             // Wait for file events with timeout
             match rx.recv_timeout(Duration::from_millis(100)) {
                 Ok(event) => {
-                    // Process the notify event
                     for path in &event.paths {
-                        if Self::is_plugin_file(path) {
-                            pending_events.insert(path.clone());
-                            last_event_time = std::time::Instant::now();
-                        }
-                    }
-
-                    // Debounce: only emit after quiet period
-                    if last_event_time.elapsed() >= debounce_duration && !pending_events.is_empty()
-                    {
-                        for path in pending_events.drain() {
-                            if let Some(change_event) =
-                                Self::convert_event(&event.kind, &path)
-                            {
-                                debug!("Plugin change: {:?}", change_event);
-                                if event_tx.send(change_event).is_err() {
-                                    // Receiver dropped, stop watching
-                                    warn!("Event receiver dropped, stopping watcher");
-                                    running.store(false, Ordering::SeqCst);
-                                    return Ok(());
-                                }
-                            }
+                        let relative = path.strip_prefix(&watch_dir).unwrap_or(path);
+                        if ignore.is_ignored(relative) {
+                            continue;
                         }
-                    }
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    // Check for debounced events
-                    if last_event_time.elapsed() >= debounce_duration && !pending_events.is_empty()
-                    {
-                        for path in pending_events.drain() {
-                            // Determine kind based on file existence since we lost the original event kind
-                            let kind = if path.exists() {
-                                PluginChangeKind::Modified
-                            } else {
-                                PluginChangeKind::Removed
-                            };
-
-                            let change_event = PluginChangeEvent::new(kind, path);
-                            debug!("Plugin change (debounced): {:?}", change_event);
-                            if event_tx.send(change_event).is_err() {
-                                warn!("Event receiver dropped, stopping watcher");
-                                running.store(false, Ordering::SeqCst);
-                                return Ok(());
-                            }
+                        if Self::is_plugin_file(path) {
+                            Self::record_event(&mut pending, &event.kind, path, events);
                         }
                     }
                 }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
                 Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
                     warn!("Watcher channel disconnected");
                     break;
                 }
             }
+
+            for change_event in Self::take_settled(&mut pending, config, events) {
+                debug!("Plugin change: {:?}", change_event);
+                if event_tx.send(change_event).is_err() {
+                    // Receiver dropped, stop watching
+                    warn!("Event receiver dropped, stopping watcher");
+                    running.store(false, Ordering::SeqCst);
+                    return Ok(());
+                }
+            }
         }
 
         running.store(false, Ordering::SeqCst);
         Ok(())
     }
 
-    /// Convert a notify event to a plugin change event.
-    fn convert_event(kind: &EventKind, path: &Path) -> Option<PluginChangeEvent> {
-        let change_kind = match kind {
-            EventKind::Create(_) => PluginChangeKind::Added,
-            EventKind::Modify(_) => PluginChangeKind::Modified,
-            EventKind::Remove(_) => PluginChangeKind::Removed,
-            _ => return None,
-        };
+    /// Record a file event, restarting the path's debounce period.
+    fn record_event(
+        pending: &mut HashMap<PathBuf, PendingChange>,
+        kind: &EventKind,
+        path: &Path,
+        events: Option<&ReloadEvents>,
+    ) {
+        let now = Instant::now();
+        let change = pending.entry(path.to_path_buf()).or_insert_with(|| {
+            let plugin_id = PluginChangeEvent::extract_plugin_id(path);
+            if let Some(events) = events {
+                let kind = match kind {
+                    EventKind::Create(_) => PluginChangeKind::Added,
+                    EventKind::Remove(_) => PluginChangeKind::Removed,
+                    EventKind::Any | EventKind::Access(_) | EventKind::Modify(_) | EventKind::Other => {
+                        PluginChangeKind::Modified
+                    }
+                };
+                events.emit(ReloadEvent::new(
+                    ReloadStage::ChangeDetected { kind },
+                    plugin_id.clone(),
+                    path.to_path_buf(),
+                ));
+            }
+            PendingChange {
+                plugin_id,
+                added: false,
+                last_event: now,
+                size: None,
+                size_changed: now,
+            }
+        });
+        change.last_event = now;
+        change.added |= matches!(kind, EventKind::Create(_));
+    }
+
+    /// Take the changes whose debounce period is over and whose file size
+    /// has settled.
+    ///
+    /// A file still growing (a build writing the WASM module or archive) is
+    /// kept pending until its size stays the same for the settle duration.
+    fn take_settled(
+        pending: &mut HashMap<PathBuf, PendingChange>,
+        config: &WatcherConfig,
+        events: Option<&ReloadEvents>,
+    ) -> Vec<PluginChangeEvent> {
+        let mut settled = Vec::new();
+        pending.retain(|path, change| {
+            if change.last_event.elapsed() < config.debounce_duration {
+                return true;
+            }
+
+            let size = std::fs::metadata(path).ok().filter(std::fs::Metadata::is_file).map(|m| m.len());
+            if size != change.size {
+                // A file that grew since the last check is still being written
+                if change.size.is_some()
+                    && let Some(events) = events
+                {
+                    events.emit(ReloadEvent::new(ReloadStage::Debouncing, change.plugin_id.clone(), path.clone()));
+                }
+                change.size = size;
+                change.size_changed = Instant::now();
+                // Wait for another check before trusting a file's size
+                if size.is_some() {
+                    return true;
+                }
+            }
+            if size.is_some() && change.size_changed.elapsed() < config.settle_duration {
+                return true;
+            }
 
-        Some(PluginChangeEvent::new(change_kind, path.to_path_buf()))
+            // Determine kind based on file existence since events may have been merged
+            let kind = if !path.exists() {
+                PluginChangeKind::Removed
+            } else if change.added {
+                PluginChangeKind::Added
+            } else {
+                PluginChangeKind::Modified
+            };
+            settled.push(PluginChangeEvent::new(kind, path.clone()));
+            false
+        });
+        settled
     }
 
     /// Check if a path is a plugin file.
@@ -394,4 +545,62 @@ mod tests {
         let id = PluginChangeEvent::extract_plugin_id(&path);
         assert_eq!(id, Some("another-plugin".to_string()));
     }
+
+    #[test]
+    fn test_default_ignore_patterns() {
+        let ignore = IgnoreSet::new(&WatcherConfig::default().ignore).expect("valid patterns");
+
+        assert!(ignore.is_ignored(Path::new("hello-plugin/target/wasm32-unknown-unknown/release/hello.wasm")));
+        assert!(ignore.is_ignored(Path::new("hello-plugin/target")));
+        assert!(ignore.is_ignored(Path::new("ui-plugin/node_modules/pkg/package.json")));
+        assert!(ignore.is_ignored(Path::new("my-plugin.wasm.tmp")));
+        assert!(ignore.is_ignored(Path::new(".cache/0123abcd.wasm")));
+        assert!(!ignore.is_ignored(Path::new("hello-plugin/manifest.json")));
+        assert!(!ignore.is_ignored(Path::new("my-plugin.wasm")));
+        assert!(!ignore.is_ignored(Path::new("targets/plugin.wasm")));
+    }
+
+    #[test]
+    fn test_invalid_ignore_pattern() {
+        assert!(matches!(IgnoreSet::new(&["[".to_string()]), Err(orbis_core::Error::Plugin(_))));
+    }
+
+    #[test]
+    fn test_growing_file_is_held_until_settled() {
+        let dir = std::env::temp_dir().join(format!("orbis-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("plugin.wasm");
+        std::fs::write(&path, b"\0asm").expect("write");
+
+        let config = WatcherConfig {
+            debounce_duration: Duration::ZERO,
+            settle_duration: Duration::from_millis(50),
+            ..WatcherConfig::default()
+        };
+        let mut pending = HashMap::new();
+        let events = ReloadEvents::new();
+        let mut rx = events.subscribe();
+        let create = EventKind::Create(notify::event::CreateKind::File);
+        PluginWatcher::record_event(&mut pending, &create, &path, Some(&events));
+        assert!(matches!(
+            rx.try_recv().map(|e| e.stage),
+            Ok(ReloadStage::ChangeDetected { kind: PluginChangeKind::Added })
+        ));
+
+        // First check records the size
+        assert!(PluginWatcher::take_settled(&mut pending, &config, Some(&events)).is_empty());
+
+        // The build is still writing
+        std::fs::write(&path, b"\0asm\x01\0\0\0").expect("write");
+        assert!(PluginWatcher::take_settled(&mut pending, &config, Some(&events)).is_empty());
+        assert!(matches!(rx.try_recv().map(|e| e.stage), Ok(ReloadStage::Debouncing)));
+
+        std::thread::sleep(Duration::from_millis(60));
+        let settled = PluginWatcher::take_settled(&mut pending, &config, Some(&events));
+        assert_eq!(settled.len(), 1);
+        assert_eq!(settled.first().map(|e| &e.kind), Some(&PluginChangeKind::Added));
+        assert!(pending.is_empty());
+
+        std::fs::remove_dir_all(&dir).expect("cleanup");
+    }
 }
@@ -2,7 +2,9 @@
 
 #[cfg(test)]
 mod integration_tests {
-    use orbis_plugin::{PluginContext, PluginInfo, PluginManifest, PluginRuntime, PluginSource};
+    use orbis_plugin::{
+        CancellationFlag, PluginActivation, PluginContext, PluginInfo, PluginManifest, PluginRuntime, PluginSource,
+    };
     use std::collections::HashMap;
     use std::path::PathBuf;
 
@@ -19,11 +21,32 @@ mod integration_tests {
             author: Some("Orbis Team".to_string()),
             homepage: None,
             license: None,
+            tags: Vec::new(),
+            category: None,
+            icon: None,
+            deprecated: None,
+            changelog_url: None,
+            release_notes: None,
             min_orbis_version: None,
+            core_version: None,
+            api_version: None,
             dependencies: vec![],
             permissions: vec![],
             routes: vec![],
             pages: vec![],
+            layouts: vec![],
+            theme: None,
+            settings: vec![],
+            features: vec![],
+            search_provider: None,
+            hooks: Vec::new(),
+            activation: PluginActivation::Eager,
+            idle_unload_seconds: None,
+            state_version: None,
+            max_body_size: None,
+            tables: Vec::new(),
+            requirements: Default::default(),
+            limits: Default::default(),
             wasm_entry: Some("test_plugin.wasm".to_string()),
             config: serde_json::Value::Null,
         }
@@ -44,11 +67,12 @@ mod integration_tests {
         let source = PluginSource::Standalone(wasm_path.clone());
 
         let info = PluginInfo {
-            id: uuid::Uuid::new_v4(),
+            id: orbis_core::PluginId::generate(),
             manifest: manifest.clone(),
             source: source.clone(),
             state: orbis_plugin::PluginState::Loaded,
             loaded_at: chrono::Utc::now(),
+            dependency_issues: Vec::new(),
         };
 
         // Initialize
@@ -89,11 +113,12 @@ mod integration_tests {
         let source = PluginSource::Standalone(wasm_path);
 
         let info = PluginInfo {
-            id: uuid::Uuid::new_v4(),
+            id: orbis_core::PluginId::generate(),
             manifest,
             source: source.clone(),
             state: orbis_plugin::PluginState::Loaded,
             loaded_at: chrono::Utc::now(),
+            dependency_issues: Vec::new(),
         };
 
         // Initialize and start
@@ -116,6 +141,11 @@ mod integration_tests {
             body: serde_json::json!({"test": "data"}),
             user_id: Some("user123".to_string()),
             is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
         };
 
         let result = runtime
@@ -150,11 +180,12 @@ mod integration_tests {
         let source = PluginSource::Standalone(wasm_path);
 
         let info = PluginInfo {
-            id: uuid::Uuid::new_v4(),
+            id: orbis_core::PluginId::generate(),
             manifest,
             source: source.clone(),
             state: orbis_plugin::PluginState::Loaded,
             loaded_at: chrono::Utc::now(),
+            dependency_issues: Vec::new(),
         };
 
         runtime
@@ -176,6 +207,11 @@ mod integration_tests {
             body: serde_json::json!({}),
             user_id: None,
             is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
         };
 
         // First execution
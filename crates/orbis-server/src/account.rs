@@ -0,0 +1,108 @@
+//! Account deletion.
+//!
+//! Users asking to delete their account keep it for a grace period
+//! (`account_deletion_grace_days`), during which they can log in again and
+//! cancel. An `account.delete` job scheduled for the end of the grace period
+//! then deletes the account and its avatar, unless the request was cancelled.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Days, Utc};
+use orbis_auth::AuthService;
+use serde::{Deserialize, Serialize};
+use orbis_core::UserId;
+
+use crate::jobs::{Job, JobHandler, JobQueue, NewJob};
+use crate::storage::FileStorage;
+
+/// Job kind deleting an account at the end of its grace period (the payload
+/// is an [`AccountDeletion`]).
+pub const ACCOUNT_DELETION_JOB: &str = "account.delete";
+
+/// An account to delete.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDeletion {
+    /// User whose account is deleted.
+    pub user_id: UserId,
+}
+
+/// Get the storage key of a user's avatar.
+#[must_use]
+pub fn avatar_key(user_id: UserId) -> String {
+    format!("avatars/{}", user_id)
+}
+
+/// Get when an account whose deletion was requested at `requested_at` is deleted.
+#[must_use]
+pub fn deletion_at(requested_at: DateTime<Utc>, grace_days: u64) -> DateTime<Utc> {
+    requested_at
+        .checked_add_days(Days::new(grace_days))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Register the account deletion job.
+pub fn register(jobs: &JobQueue, auth: AuthService, files: FileStorage, grace_days: u64) {
+    jobs.register(
+        ACCOUNT_DELETION_JOB,
+        Arc::new(AccountDeletionHandler {
+            auth,
+            files,
+            grace_days,
+        }),
+    );
+}
+
+/// Schedule the deletion of an account at the end of the grace period.
+///
+/// # Errors
+///
+/// Returns an error if the job cannot be queued.
+pub async fn schedule_deletion(jobs: &JobQueue, user_id: UserId, grace_days: u64) -> orbis_core::Result<Job> {
+    let payload = serde_json::to_value(AccountDeletion { user_id })
+        .map_err(|e| orbis_core::Error::serialization(e.to_string()))?;
+    let delay = Duration::from_secs(grace_days.saturating_mul(24 * 3600));
+    jobs.enqueue(NewJob::new(ACCOUNT_DELETION_JOB, payload).with_delay(delay)).await
+}
+
+/// Deletes the account in the job payload if its grace period has passed.
+struct AccountDeletionHandler {
+    /// Authentication service owning the accounts.
+    auth: AuthService,
+
+    /// Storage holding avatars.
+    files: FileStorage,
+
+    /// Grace period, in days.
+    grace_days: u64,
+}
+
+#[async_trait]
+impl JobHandler for AccountDeletionHandler {
+    async fn run(&self, job: &Job) -> orbis_core::Result<()> {
+        let deletion: AccountDeletion = serde_json::from_value(job.payload.clone())
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid account deletion job payload: {}", e)))?;
+
+        let cutoff = Utc::now()
+            .checked_sub_days(Days::new(self.grace_days))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let avatar = self.auth.account().avatar(deletion.user_id).await?;
+
+        // Cancelled (or renewed) requests leave the account in place
+        if !self
+            .auth
+            .account()
+            .delete_if_requested_before(deletion.user_id, cutoff)
+            .await?
+        {
+            return Ok(());
+        }
+
+        if avatar.is_some() && self.files.is_available() {
+            self.files.delete(&avatar_key(deletion.user_id)).await?;
+        }
+        tracing::info!("Deleted account {} at the end of its grace period", deletion.user_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+//! Headless administration commands.
+//!
+//! Runs the CLI subcommands against the same services the server uses, so a
+//! server can be administered over SSH without the desktop app. Commands
+//! write their results to the given output; logs go to the tracing
+//! subscriber.
+
+use orbis_auth::{CreateUser, PasswordService, SessionService, TenantService, UserSeeds, UserService};
+use orbis_config::{BackupCommands, Commands, Config, DatabaseBackend, DbCommands, PluginCommands, UserCommands};
+use orbis_db::{Database, MigrationRunner, SeedEnvironment, Seeder};
+use orbis_plugin::{PluginManager, RemoteSource, PUBLIC_KEY_EXTENSION};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::Server;
+
+/// Run a CLI subcommand.
+///
+/// # Errors
+///
+/// Returns an error if the command fails or is not supported by the server.
+pub async fn run_command<W: Write>(config: Config, command: Commands, out: &mut W) -> orbis_core::Result<()> {
+    match command {
+        Commands::Serve { daemon } => {
+            if daemon {
+                tracing::warn!("Daemon mode is not supported; run the server under a service manager instead");
+            }
+            Server::new(config).await?.run().await
+        }
+        Commands::Migrate => migrate(&config, out).await,
+        Commands::User { action } => run_user_command(&config, action, out).await,
+        Commands::Db { action } => run_db_command(&config, action, out).await,
+        Commands::Plugin { action } => run_plugin_command(&config, action, out).await,
+        Commands::Backup { action } => run_backup_command(&config, action, out).await,
+        Commands::Profile { .. } => Err(orbis_core::Error::config(
+            "Profiles are managed by the desktop app",
+        )),
+        Commands::Config { output } => {
+            config.save_to_file(&output)?;
+            writeln!(out, "Wrote configuration to {}", output.display())?;
+            Ok(())
+        }
+    }
+}
+
+/// Run pending migrations.
+async fn migrate<W: Write>(config: &Config, out: &mut W) -> orbis_core::Result<()> {
+    let db = Database::new(config.database.clone()).await?;
+    db.migrate().await?;
+
+    let version = MigrationRunner::new(db.pool()).current_version().await?;
+    writeln!(out, "Database is at migration {}", version.unwrap_or_default())?;
+    Ok(())
+}
+
+/// Run a user command.
+async fn run_user_command<W: Write>(config: &Config, action: UserCommands, out: &mut W) -> orbis_core::Result<()> {
+    let db = Database::new(config.database.clone()).await?;
+    let users = UserService::new(db.clone());
+    let password_service = PasswordService::new();
+
+    match action {
+        UserCommands::Create {
+            username,
+            email,
+            password,
+            display_name,
+            admin,
+            tenant,
+        } => {
+            let tenant_id = tenant_id(&db, tenant.as_deref()).await?;
+            let password = read_password(password)?;
+
+            if users.username_exists(&username, tenant_id).await? {
+                return Err(orbis_core::Error::conflict("Username already exists"));
+            }
+            if users.email_exists(&email, tenant_id).await? {
+                return Err(orbis_core::Error::conflict("Email already exists"));
+            }
+
+            let password_hash = password_service.hash(&password)?;
+            let user = users
+                .create(
+                    CreateUser {
+                        username,
+                        email,
+                        password,
+                        display_name,
+                        is_admin: admin,
+                        tenant_id,
+                    },
+                    password_hash,
+                )
+                .await?;
+
+            writeln!(out, "Created user {} ({})", user.username, user.id)?;
+        }
+        UserCommands::ResetPassword {
+            username,
+            password,
+            tenant,
+        } => {
+            let tenant_id = tenant_id(&db, tenant.as_deref()).await?;
+            let user = users
+                .find_by_username_or_email(&username, tenant_id)
+                .await?
+                .ok_or_else(|| orbis_core::Error::not_found(format!("User '{}' not found", username)))?;
+            let password = read_password(password)?;
+
+            users.set_password(user.id, &password_service.hash(&password)?).await?;
+            SessionService::new(db).delete_all_for_user(user.id).await?;
+
+            writeln!(out, "Reset the password of {}", user.username)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run a database command.
+async fn run_db_command<W: Write>(config: &Config, action: DbCommands, out: &mut W) -> orbis_core::Result<()> {
+    match action {
+        DbCommands::Migrate => migrate(config, out).await,
+        DbCommands::Status => {
+            let db = Database::new(config.database.clone()).await?;
+            for migration in MigrationRunner::new(db.pool()).list_applied().await? {
+                writeln!(
+                    out,
+                    "{} {} ({})",
+                    migration.version,
+                    migration.description,
+                    if migration.success { migration.installed_on.to_rfc3339() } else { "failed".to_owned() }
+                )?;
+            }
+            Ok(())
+        }
+        DbCommands::Seed { environment, sets } => {
+            let environment: SeedEnvironment = environment.parse()?;
+            let db = Database::new(config.database.clone()).await?;
+            db.migrate().await?;
+
+            let report = seeder().run(&db, environment, &sets).await?;
+            for name in &report.applied {
+                writeln!(out, "Seeded {}", name)?;
+            }
+            for name in &report.skipped {
+                writeln!(out, "Skipped {} (not seeded in {})", name, environment)?;
+            }
+            Ok(())
+        }
+        DbCommands::Revert | DbCommands::Create { .. } => Err(orbis_core::Error::config(
+            "Migrations are embedded in the server and cannot be reverted or created from the CLI",
+        )),
+    }
+}
+
+/// Get the built-in seed sets.
+#[must_use]
+pub fn seeder() -> Seeder {
+    Seeder::new().with_set(UserSeeds)
+}
+
+/// Run a plugin command.
+async fn run_plugin_command<W: Write>(config: &Config, action: PluginCommands, out: &mut W) -> orbis_core::Result<()> {
+    match action {
+        PluginCommands::List => {
+            let mut list = loaded_plugins(config).await?.registry().list();
+            list.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+            for info in list {
+                writeln!(out, "{} {} {:?}", info.manifest.name, info.manifest.version, info.state)?;
+            }
+        }
+        PluginCommands::Install { source, sha256, version } => {
+            let plugins = plugin_manager(config).await?;
+            let remote = remote_source(&plugins, &source, sha256, version.as_deref()).await?;
+            let path = match remote.as_ref() {
+                Some(remote) => crate::plugins_dir(config).join(remote.file_name()?),
+                None => install_source(&source, &crate::plugins_dir(config))?,
+            };
+            if remote.is_some() && path.exists() {
+                return Err(orbis_core::Error::conflict(format!("{} already exists", path.display())));
+            }
+            let loaded = match remote.as_ref() {
+                Some(remote) => plugins.load_remote(remote).await,
+                None => plugins.load_plugin(&path).await,
+            };
+            let info = match loaded {
+                Ok(info) => info,
+                Err(e) => {
+                    // Don't leave a broken plugin behind for the next start
+                    if path.exists() {
+                        remove_path(&path)?;
+                    }
+                    return Err(e);
+                }
+            };
+            writeln!(out, "Installed {} {}", info.manifest.name, info.manifest.version)?;
+        }
+        PluginCommands::Uninstall { name } => {
+            let plugins = loaded_plugins(config).await?;
+            let info = plugins
+                .registry()
+                .get(&name)
+                .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+            plugins.unload_plugin(&name).await?;
+            if let Some(path) = info.source.path() {
+                remove_path(path)?;
+            }
+            writeln!(out, "Uninstalled {}", name)?;
+        }
+        PluginCommands::Enable { name } => {
+            loaded_plugins(config).await?.enable_plugin(&name).await?;
+            writeln!(out, "Enabled {}", name)?;
+        }
+        PluginCommands::Disable { name } => {
+            loaded_plugins(config).await?.disable_plugin(&name).await?;
+            writeln!(out, "Disabled {}", name)?;
+        }
+        PluginCommands::Info { name } => {
+            let info = loaded_plugins(config)
+                .await?
+                .registry()
+                .get(&name)
+                .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+            writeln!(out, "{}", serde_json::to_string_pretty(&info)?)?;
+        }
+        PluginCommands::ClearCache => {
+            let removed = plugin_manager(config).await?.clear_module_cache()?;
+            writeln!(out, "Removed {} cached modules", removed)?;
+        }
+        PluginCommands::Keygen { output } => keygen(&output, out)?,
+        PluginCommands::Sign { wasm, key, output } => sign(&wasm, &key, output.as_deref(), out)?,
+        PluginCommands::Dev { dir } => crate::dev::run(config.clone(), &dir, out).await?,
+        PluginCommands::Doctor { dir, registry, json } => {
+            crate::doctor::run(config, &dir, registry.as_deref(), json, out).await?;
+        }
+        PluginCommands::Replay { file } => {
+            let outcome = loaded_plugins(config).await?.replay_recording(&file).await?;
+            writeln!(out, "{}", serde_json::to_string_pretty(&outcome)?)?;
+        }
+        PluginCommands::Analyze {
+            wasm,
+            manifest,
+            baseline,
+            max_growth,
+            top,
+            json,
+        } => {
+            let options = crate::analyze::AnalyzeOptions {
+                wasm,
+                manifest,
+                baseline,
+                max_growth,
+                top,
+                json,
+            };
+            crate::analyze::run(&options, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create the plugin manager the server would use.
+async fn plugin_manager(config: &Config) -> orbis_core::Result<PluginManager> {
+    let db = Database::new(config.database.clone()).await?;
+    crate::create_plugin_manager(config, db)
+}
+
+/// Create the plugin manager and load the installed plugins.
+async fn loaded_plugins(config: &Config) -> orbis_core::Result<Arc<PluginManager>> {
+    let plugins = Arc::new(plugin_manager(config).await?);
+    plugins.load_all().await?;
+    Ok(plugins)
+}
+
+/// Generate a signing key pair.
+fn keygen<W: Write>(output: &Path, out: &mut W) -> orbis_core::Result<()> {
+    let public_path = output.with_extension(PUBLIC_KEY_EXTENSION);
+    if output.exists() || public_path.exists() {
+        return Err(orbis_core::Error::conflict(format!("{} already exists", output.display())));
+    }
+
+    let key = orbis_plugin::generate_key();
+    std::fs::write(output, orbis_plugin::encode_signing_key(&key))?;
+    std::fs::write(&public_path, orbis_plugin::encode_public_key(&key.verifying_key()))?;
+
+    writeln!(
+        out,
+        "Wrote signing key {} to {} and its public key to {}",
+        orbis_plugin::key_id(&key.verifying_key()),
+        output.display(),
+        public_path.display()
+    )?;
+    Ok(())
+}
+
+/// Sign a standalone or unpacked plugin.
+fn sign<W: Write>(wasm: &Path, key: &Path, output: Option<&Path>, out: &mut W) -> orbis_core::Result<()> {
+    let key = orbis_plugin::decode_signing_key(&std::fs::read_to_string(key)?)?;
+    if wasm.is_dir() {
+        if output.is_some() {
+            return Err(orbis_core::Error::validation("Unpacked plugins can only be signed in place"));
+        }
+        let signature = orbis_plugin::sign_directory(wasm, &key)?;
+        writeln!(
+            out,
+            "Signed {} ({} files, root {}) with key {}",
+            wasm.display(),
+            signature.manifest.files.len(),
+            signature.manifest.root,
+            signature.signature.key_id
+        )?;
+        return Ok(());
+    }
+
+    let signed = orbis_plugin::sign(&std::fs::read(wasm)?, &key)?;
+
+    let output = output.unwrap_or(wasm);
+    std::fs::write(output, signed)?;
+    writeln!(
+        out,
+        "Signed {} with key {}",
+        output.display(),
+        orbis_plugin::key_id(&key.verifying_key())
+    )?;
+    Ok(())
+}
+
+/// Run a backup command.
+async fn run_backup_command<W: Write>(config: &Config, action: BackupCommands, out: &mut W) -> orbis_core::Result<()> {
+    match action {
+        BackupCommands::Run { output } => {
+            let db = Database::new(config.database.clone()).await?;
+            let path = match output {
+                Some(path) => path,
+                None => default_backup_path(config)?,
+            };
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            db.backup(&path).await?;
+            writeln!(out, "Wrote backup to {}", path.display())?;
+            Ok(())
+        }
+    }
+}
+
+/// Resolve a tenant slug to its ID.
+async fn tenant_id(db: &Database, slug: Option<&str>) -> orbis_core::Result<Option<Uuid>> {
+    let Some(slug) = slug else {
+        return Ok(None);
+    };
+
+    let tenant = TenantService::new(db.clone())
+        .find_by_slug(slug)
+        .await?
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Tenant '{}' not found", slug)))?;
+    Ok(Some(tenant.id))
+}
+
+/// Use the given password, or read it from the first line of stdin.
+fn read_password(password: Option<String>) -> orbis_core::Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_owned()
+        }
+    };
+
+    if !PasswordService::validate_password_strength(&password).is_valid() {
+        return Err(orbis_core::Error::validation("Password must be at least 8 characters long"));
+    }
+    Ok(password)
+}
+
+/// Get the remote plugin file to install: a URL with its hash, or a plugin
+/// published in the configured registry.
+///
+/// Returns `None` for a local path.
+async fn remote_source(
+    plugins: &PluginManager,
+    source: &str,
+    sha256: Option<String>,
+    version: Option<&str>,
+) -> orbis_core::Result<Option<RemoteSource>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let sha256 = sha256
+            .ok_or_else(|| orbis_core::Error::validation("Installing from a URL requires --sha256"))?;
+        let remote = RemoteSource::new(source, sha256);
+        remote.validate()?;
+        return Ok(Some(remote));
+    }
+    if Path::new(source).exists() || plugins.registry_client().is_none() {
+        return Ok(None);
+    }
+
+    let (resolved, remote) = plugins.resolve_from_registry(source, version).await?;
+    tracing::info!("Installing {} {} from the registry", source, resolved);
+    Ok(Some(remote))
+}
+
+/// Copy a plugin file or directory into the plugins directory.
+fn install_source(source: &str, plugins_dir: &Path) -> orbis_core::Result<PathBuf> {
+    let source = Path::new(source);
+    let name = source
+        .file_name()
+        .ok_or_else(|| orbis_core::Error::validation(format!("Invalid plugin path: {}", source.display())))?;
+    let target = plugins_dir.join(name);
+    if target.exists() {
+        return Err(orbis_core::Error::conflict(format!("{} already exists", target.display())));
+    }
+
+    std::fs::create_dir_all(plugins_dir)?;
+    copy_path(source, &target)?;
+    Ok(target)
+}
+
+/// Copy a file or directory recursively.
+fn copy_path(source: &Path, target: &Path) -> orbis_core::Result<()> {
+    if source.is_dir() {
+        std::fs::create_dir_all(target)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &target.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(source, target)?;
+    }
+    Ok(())
+}
+
+/// Remove a file or directory.
+fn remove_path(path: &Path) -> orbis_core::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Timestamped backup file in the data directory.
+fn default_backup_path(config: &Config) -> orbis_core::Result<PathBuf> {
+    let data_dir = config
+        .data_dir
+        .as_ref()
+        .ok_or_else(|| orbis_core::Error::config("Pass --output or set a data directory for backups"))?;
+    let extension = if config.database.backend == DatabaseBackend::Postgres { "sql" } else { "db" };
+
+    Ok(data_dir
+        .join("backups")
+        .join(format!("orbis-{}.{}", chrono::Utc::now().format("%Y%m%d%H%M%S"), extension)))
+}
@@ -0,0 +1,167 @@
+//! Plugin module analysis command.
+//!
+//! `plugin analyze <wasm>` reports where the bytes of a plugin module go (by
+//! section, crate and function), the host functions it imports, and how the
+//! permissions they require compare with those its manifest declares. With
+//! a baseline build, it also reports size changes, and can fail when the
+//! module grew too much.
+
+use orbis_plugin::{ModuleAnalysis, PermissionUsage, PluginLoader, PluginManifest, PluginPermission, PluginSource};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// What to analyze and how to report it.
+#[derive(Debug)]
+pub struct AnalyzeOptions {
+    /// Plugin WASM file.
+    pub wasm: PathBuf,
+
+    /// Manifest declaring the plugin's permissions (the embedded one if unset).
+    pub manifest: Option<PathBuf>,
+
+    /// Previous build to compare sizes with.
+    pub baseline: Option<PathBuf>,
+
+    /// Growth since the baseline, in bytes, above which the command fails.
+    pub max_growth: Option<u64>,
+
+    /// Number of largest functions to list.
+    pub top: usize,
+
+    /// Print the analysis as JSON.
+    pub json: bool,
+}
+
+/// Analyze a plugin module and print the report.
+///
+/// # Errors
+///
+/// Returns an error if a module cannot be read or parsed, or if the module
+/// grew more than allowed since the baseline.
+pub fn run<W: Write>(options: &AnalyzeOptions, out: &mut W) -> orbis_core::Result<()> {
+    let analysis = ModuleAnalysis::new(&std::fs::read(&options.wasm)?)?;
+    let permissions = declared_permissions(&options.wasm, options.manifest.as_deref())?
+        .map(|declared| analysis.permissions(&declared));
+    let diff = match options.baseline {
+        Some(ref baseline) => Some(analysis.diff(&ModuleAnalysis::new(&std::fs::read(baseline)?)?)),
+        None => None,
+    };
+
+    if options.json {
+        let mut report = serde_json::to_value(&analysis)?;
+        if let Some(functions) = report.get_mut("functions").and_then(serde_json::Value::as_array_mut) {
+            functions.truncate(options.top);
+        }
+        if let Some(fields) = report.as_object_mut() {
+            fields.insert("permissions".to_owned(), serde_json::to_value(&permissions)?);
+            fields.insert("diff".to_owned(), serde_json::to_value(&diff)?);
+        }
+        writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+    } else {
+        write_report(&analysis, permissions.as_ref(), options, out)?;
+        if let Some(ref diff) = diff {
+            writeln!(out, "\nChanges since the baseline: {:+} bytes", diff.total.change)?;
+            for change in &diff.sections {
+                writeln!(out, "  section {:<24} {:>+10}", change.name, change.change)?;
+            }
+            for change in &diff.crates {
+                writeln!(out, "  crate   {:<24} {:>+10}", change.name, change.change)?;
+            }
+        }
+    }
+
+    let growth = diff.as_ref().map_or(0, |diff| diff.total.change);
+    if let Some(max_growth) = options.max_growth
+        && growth > i64::try_from(max_growth).unwrap_or(i64::MAX)
+    {
+        return Err(orbis_core::Error::validation(format!(
+            "{} grew by {} bytes, more than the {} allowed",
+            options.wasm.display(),
+            growth,
+            max_growth
+        )));
+    }
+    Ok(())
+}
+
+/// Get the permissions declared by the given manifest, or else by the one
+/// embedded in the module (`None` if it has none).
+fn declared_permissions(wasm: &Path, manifest: Option<&Path>) -> orbis_core::Result<Option<Vec<PluginPermission>>> {
+    if let Some(path) = manifest {
+        let manifest: PluginManifest = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        return Ok(Some(manifest.permissions));
+    }
+
+    let source = PluginSource::from_path(&wasm.to_path_buf())?;
+    Ok(PluginLoader::new()
+        .load_manifest(&source)
+        .ok()
+        .map(|manifest| manifest.permissions))
+}
+
+/// Print the analysis as text.
+fn write_report<W: Write>(
+    analysis: &ModuleAnalysis,
+    permissions: Option<&PermissionUsage>,
+    options: &AnalyzeOptions,
+    out: &mut W,
+) -> orbis_core::Result<()> {
+    let share = |size: u64| {
+        let tenths = size.saturating_mul(1000).checked_div(analysis.size).unwrap_or(0);
+        format!("{}.{}%", tenths.checked_div(10).unwrap_or(0), tenths.checked_rem(10).unwrap_or(0))
+    };
+
+    writeln!(out, "{}: {} bytes", options.wasm.display(), analysis.size)?;
+    writeln!(out, "\nSections:")?;
+    for section in &analysis.sections {
+        writeln!(out, "  {:<32} {:>10} {:>7}", section.name, section.size, share(section.size))?;
+    }
+    writeln!(out, "\nCrates:")?;
+    for krate in &analysis.crates {
+        writeln!(
+            out,
+            "  {:<32} {:>10} {:>7}  ({} functions)",
+            krate.name,
+            krate.size,
+            share(krate.size),
+            krate.functions
+        )?;
+    }
+    writeln!(out, "\nLargest functions:")?;
+    for function in analysis.functions.iter().take(options.top) {
+        writeln!(out, "  {:>10}  {}", function.size, function.name)?;
+    }
+
+    writeln!(out, "\nHost functions: {}", list(&analysis.host_functions))?;
+    match permissions {
+        Some(usage) => {
+            writeln!(out, "Permissions used: {}", permission_list(&usage.used))?;
+            writeln!(out, "Declared but unused: {}", permission_list(&usage.unused))?;
+            writeln!(out, "Used but not declared: {}", permission_list(&usage.missing))?;
+        }
+        None => writeln!(out, "Permissions used: {} (no manifest found)", permission_list(&analysis.used_permissions()))?,
+    }
+    Ok(())
+}
+
+/// Join names, or `none`.
+fn list(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_owned()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Join permissions by their manifest names, or `none`.
+fn permission_list(permissions: &[PluginPermission]) -> String {
+    let names: Vec<String> = permissions
+        .iter()
+        .map(|permission| match serde_json::to_value(permission) {
+            Ok(serde_json::Value::String(name)) => name,
+            Ok(value) => value.to_string(),
+            Err(_) => format!("{:?}", permission),
+        })
+        .collect();
+    list(&names)
+}
@@ -1,9 +1,13 @@
 //! Application router and middleware setup.
 
-use crate::middleware::{with_auth, cors_layer, compression_layer, logging_layer};
+use crate::compression::{compression_layer, compression_stats_middleware, count_original_bytes_middleware};
+use crate::csrf::csrf_middleware;
+use crate::limits::body_limit_middleware;
+use crate::middleware::{with_auth, audit_middleware, cors_layer, deadline_middleware, hook_middleware, localize_middleware, logging_layer, tenant_middleware};
 use crate::routes;
+use crate::versioning::api_version_middleware;
 use crate::state::AppState;
-use axum::{http::StatusCode, Router};
+use axum::{extract::DefaultBodyLimit, http::StatusCode, Router};
 use tower::ServiceBuilder;
 use tower_http::timeout::TimeoutLayer;
 use std::time::Duration;
@@ -34,34 +38,71 @@ pub fn create_app(state: AppState) -> Router {
     let config = state.config();
 
     // Build middleware stack
+    let request_timeout = Duration::from_secs(config.server.request_timeout_seconds);
     let middleware = ServiceBuilder::new()
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(config.server.request_timeout_seconds),
-        ));
+            request_timeout,
+        ))
+        .layer(axum::middleware::from_fn_with_state(request_timeout, deadline_middleware));
 
     // Create the main router
     let mut app = Router::new()
         // Health check
         .merge(routes::health::router())
-        // API routes (protected by auth middleware)
+        // Discovery routes
+        .merge(routes::well_known::router())
+        // API routes (protected by auth middleware), under each version;
+        // unversioned paths serve the negotiated version
+        .nest("/api/v1", api_routes(state.clone()))
         .nest("/api", api_routes(state.clone()))
         // Plugin routes
+        .nest("/api/v1/plugins", routes::plugins::router(state.clone()))
         .nest("/api/plugins", routes::plugins::router(state.clone()))
         // Static files and SPA fallback
         .merge(routes::static_files::router())
         // Apply middleware
         .layer(middleware)
+        .layer(DefaultBodyLimit::max(config.server.max_body_size))
         .with_state(state.clone());
 
+    // Negotiate the API version
+    app = app.layer(axum::middleware::from_fn(api_version_middleware));
+
+    // Notify plugins subscribed to `before_request`
+    app = app.layer(axum::middleware::from_fn_with_state(state.clone(), hook_middleware));
+
+    // Resolve the tenant before auth runs
+    if config.tenancy.mode.is_enabled() {
+        app = app.layer(axum::middleware::from_fn_with_state(state.clone(), tenant_middleware));
+    }
+
+    // Check the CSRF tokens of requests authenticated by the session cookie
+    if config.cookie_sessions.enabled {
+        app = app.layer(axum::middleware::from_fn_with_state(state.clone(), csrf_middleware));
+    }
+
+    // Read request bodies within the size limit and body read timeout,
+    // before the request timeout starts
+    app = app.layer(axum::middleware::from_fn_with_state(state.clone(), body_limit_middleware));
+
+    // Translate error messages into the requested locale
+    app = app.layer(axum::middleware::from_fn_with_state(state.clone(), localize_middleware));
+
     // Add logging if enabled
     if config.server.request_logging {
         app = app.layer(logging_layer());
     }
 
-    // Add compression if enabled
+    // Add compression if enabled, counting the bytes on both sides of it
     if config.server.compression {
-        app = app.layer(compression_layer());
+        app = app
+            .layer(axum::middleware::from_fn(count_original_bytes_middleware))
+            .layer(compression_layer(&config.server))
+            .layer(axum::middleware::from_fn_with_state(
+                state.compression_stats(),
+                compression_stats_middleware,
+            ));
     }
 
     // Add CORS if enabled
@@ -79,12 +120,38 @@ fn api_routes(state: AppState) -> Router<AppState> {
         .merge(routes::auth::router())
         // User routes
         .merge(routes::users::router())
+        // Group routes
+        .merge(routes::groups::router())
+        // Impersonation routes
+        .merge(routes::impersonation::router())
         // Profile routes
         .merge(routes::profiles::router())
         // Settings routes
         .merge(routes::settings::router())
+        // Theme routes
+        .merge(routes::theme::router())
+        // Navigation routes
+        .merge(routes::navigation::router())
         // Plugin management routes
-        .merge(routes::plugin_management::router());
+        .merge(routes::plugin_management::router())
+        // Plugin development routes
+        .merge(routes::plugin_dev::router())
+        // Server event routes
+        .merge(routes::events::router())
+        // Tenant routes
+        .merge(routes::tenants::router())
+        // Job queue routes
+        .merge(routes::jobs::router())
+        // Global search routes
+        .merge(routes::search::router())
+        // Plugin object storage routes
+        .merge(routes::storage::router())
+        // System status routes
+        .merge(routes::system::router())
+        // Audit log routes
+        .merge(routes::audit::router())
+        // Audit mutating requests, once authenticated and matched to a route
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), audit_middleware));
 
     // Apply auth middleware to all API routes
     // The middleware itself handles public route exceptions (login, register, etc.)
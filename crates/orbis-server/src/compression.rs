@@ -0,0 +1,267 @@
+//! Response compression.
+//!
+//! Responses are compressed with the enabled algorithm the client prefers,
+//! when their content type is allowed and their body is not smaller than the
+//! minimum size. This covers core API routes, plugin routes and static
+//! assets alike. Compressed responses and the bytes they saved are counted
+//! in [`CompressionStats`].
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::State,
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+use http_body::{Frame, SizeHint};
+use orbis_config::{CompressionAlgorithm, ServerConfig};
+use serde::Serialize;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_http::compression::{CompressionLayer, Predicate};
+
+/// Counters of compressed responses.
+#[derive(Debug, Default)]
+pub struct CompressionStats {
+    /// Responses sent compressed.
+    compressed_responses: AtomicU64,
+
+    /// Body bytes of compressed responses before compression.
+    original_bytes: AtomicU64,
+
+    /// Body bytes of compressed responses after compression.
+    compressed_bytes: AtomicU64,
+}
+
+/// Snapshot of [`CompressionStats`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CompressionCounts {
+    /// Responses sent compressed.
+    pub compressed_responses: u64,
+
+    /// Body bytes of compressed responses before compression.
+    pub original_bytes: u64,
+
+    /// Body bytes of compressed responses after compression.
+    pub compressed_bytes: u64,
+
+    /// Bytes not sent thanks to compression.
+    pub bytes_saved: u64,
+}
+
+impl CompressionStats {
+    /// Create zeroed counters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the counters.
+    #[must_use]
+    pub fn counts(&self) -> CompressionCounts {
+        let original_bytes = self.original_bytes.load(Ordering::Relaxed);
+        let compressed_bytes = self.compressed_bytes.load(Ordering::Relaxed);
+
+        CompressionCounts {
+            compressed_responses: self.compressed_responses.load(Ordering::Relaxed),
+            original_bytes,
+            compressed_bytes,
+            bytes_saved: original_bytes.saturating_sub(compressed_bytes),
+        }
+    }
+
+    /// Count a compressed response body.
+    fn record(&self, original: u64, compressed: u64) {
+        self.compressed_responses.fetch_add(1, Ordering::Relaxed);
+        self.original_bytes.fetch_add(original, Ordering::Relaxed);
+        self.compressed_bytes.fetch_add(compressed, Ordering::Relaxed);
+    }
+}
+
+/// Decides which responses are compressed.
+#[derive(Debug, Clone)]
+pub struct CompressionPredicate {
+    /// Smallest body compressed, in bytes.
+    min_size: u64,
+
+    /// Allowed content types; entries ending in `/` match a whole type.
+    content_types: Arc<[String]>,
+}
+
+impl CompressionPredicate {
+    /// Create the predicate configured for a server.
+    #[must_use]
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            min_size: config.compression_min_size,
+            content_types: config
+                .compression_content_types
+                .iter()
+                .map(|content_type| content_type.to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Check if a content type is in the allowlist.
+    ///
+    /// Event streams are never compressed, since compressors buffer events.
+    fn allows(&self, content_type: &str) -> bool {
+        let essence = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        essence != "text/event-stream"
+            && self.content_types.iter().any(|allowed| {
+                if allowed.ends_with('/') {
+                    essence.starts_with(allowed.as_str())
+                } else {
+                    essence == *allowed
+                }
+            })
+    }
+}
+
+impl Predicate for CompressionPredicate {
+    fn should_compress<B>(&self, response: &axum::http::Response<B>) -> bool
+    where
+        B: HttpBody,
+    {
+        let allowed = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| self.allows(content_type));
+
+        // Streamed bodies of unknown size are compressed
+        let size = response.body().size_hint().exact().or_else(|| {
+            response
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+        });
+
+        allowed && size.is_none_or(|size| size >= self.min_size)
+    }
+}
+
+/// Create the compression layer configured for a server.
+#[must_use]
+pub fn compression_layer(config: &ServerConfig) -> CompressionLayer<CompressionPredicate> {
+    let enabled = |algorithm| config.compression_algorithms.contains(&algorithm);
+
+    CompressionLayer::new()
+        .br(enabled(CompressionAlgorithm::Brotli))
+        .gzip(enabled(CompressionAlgorithm::Gzip))
+        .no_deflate()
+        .no_zstd()
+        .compress_when(CompressionPredicate::new(config))
+}
+
+/// Bytes read from a response body before compression.
+#[derive(Debug, Clone, Default)]
+struct OriginalBytes(Arc<AtomicU64>);
+
+/// Count the body bytes of responses entering the compression layer.
+///
+/// Must run inside the compression layer; responses already carrying a
+/// `Content-Encoding` are left alone, since they are not compressed again.
+pub async fn count_original_bytes_middleware(request: Request<Body>, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+
+    let original = OriginalBytes::default();
+    let (mut parts, body) = response.into_parts();
+    parts.extensions.insert(original.clone());
+    Response::from_parts(parts, Body::new(CountingBody::new(body, original.0, None)))
+}
+
+/// Record the responses compressed by the compression layer.
+///
+/// Must run outside the compression layer. A response is recorded once its
+/// body is dropped, after it was sent or the client went away.
+pub async fn compression_stats_middleware(
+    State(stats): State<Arc<CompressionStats>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let Some(original) = response.extensions().get::<OriginalBytes>().cloned() else {
+        return response;
+    };
+    if !response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let recorder = Recorder { stats, original: original.0 };
+    Response::from_parts(parts, Body::new(CountingBody::new(body, Arc::default(), Some(recorder))))
+}
+
+/// Records a compressed body in [`CompressionStats`].
+struct Recorder {
+    /// Counters to record in.
+    stats: Arc<CompressionStats>,
+
+    /// Bytes of the body before compression.
+    original: Arc<AtomicU64>,
+}
+
+/// Response body counting the bytes read from it.
+struct CountingBody {
+    /// Wrapped body.
+    inner: Body,
+
+    /// Bytes read so far.
+    bytes: Arc<AtomicU64>,
+
+    /// Records the body when dropped, if it was compressed.
+    recorder: Option<Recorder>,
+}
+
+impl CountingBody {
+    /// Wrap a body.
+    const fn new(inner: Body, bytes: Arc<AtomicU64>, recorder: Option<Recorder>) -> Self {
+        Self { inner, bytes, recorder }
+    }
+}
+
+impl HttpBody for CountingBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll
+            && let Some(data) = frame.data_ref()
+        {
+            let len = u64::try_from(data.len()).unwrap_or(u64::MAX);
+            self.bytes.fetch_add(len, Ordering::Relaxed);
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for CountingBody {
+    fn drop(&mut self) {
+        let compressed = self.bytes.load(Ordering::Relaxed);
+        // Bodies never read, like those of HEAD responses, were not sent
+        if let Some(recorder) = &self.recorder
+            && compressed > 0
+        {
+            recorder.stats.record(recorder.original.load(Ordering::Relaxed), compressed);
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Cookie sessions and their CSRF protection.
+//!
+//! With cookie sessions enabled, the web client can log in with
+//! `session_cookie: true` to keep its access token in an `HttpOnly` session
+//! cookie rather than in script-readable storage. Browsers attach that
+//! cookie to requests other sites trigger too, so unsafe requests
+//! authenticated by it must carry a CSRF token in a header, checked by
+//! [`csrf_middleware`] with the protection of their route group:
+//!
+//! - double-submit: the header repeats the CSRF cookie, which other sites
+//!   can neither read nor set;
+//! - signed: the header holds the token signed for the session token.
+//!
+//! Both check the same token, the signature of the session token, so the
+//! client sends one header whatever the groups' protections. Requests with
+//! an `Authorization` header, public routes, and routes of plugins declaring
+//! themselves API-only are exempt: they never authenticate by cookie. With
+//! cookie sessions disabled, no request does, and none is checked.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, Extensions, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use orbis_config::{CookieSessionConfig, CsrfProtection, SameSitePolicy};
+use orbis_core::hasher::{constant_time_eq, hmac_sha256, verify_hmac_sha256};
+use sha2::{Digest as _, Sha256};
+
+use crate::error::ServerError;
+use crate::middleware::is_public_route;
+use crate::state::AppState;
+use crate::versioning::unversioned_path;
+
+/// Marks requests that must not authenticate with the session cookie.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderCredentialsOnly;
+
+/// Signs CSRF tokens for session tokens.
+pub struct CsrfSigner {
+    /// HMAC-SHA256 key.
+    key: [u8; 32],
+}
+
+impl CsrfSigner {
+    /// Create a signer whose key derives from the JWT secret, so every
+    /// server sharing the secret accepts the same tokens, or a random key
+    /// if there is no secret.
+    #[must_use]
+    pub fn new(jwt_secret: Option<&str>) -> Self {
+        let key = jwt_secret.map_or_else(rand::random, |secret| {
+            hmac_sha256(&Sha256::digest(secret.as_bytes()), b"orbis-csrf")
+        });
+        Self { key }
+    }
+
+    /// Get the CSRF token of a session token.
+    #[must_use]
+    pub fn sign(&self, session: &str) -> String {
+        hex::encode(hmac_sha256(&self.key, session.as_bytes()))
+    }
+
+    /// Check a CSRF token was signed for a session token.
+    #[must_use]
+    pub fn verify(&self, session: &str, token: &str) -> bool {
+        hex::decode(token).is_ok_and(|tag| verify_hmac_sha256(&self.key, session.as_bytes(), &tag))
+    }
+}
+
+/// Reject unsafe requests authenticated by the session cookie without a
+/// valid CSRF token.
+pub async fn csrf_middleware(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let config = &state.config().cookie_sessions;
+    if !config.enabled {
+        return next.run(request).await;
+    }
+
+    let path = unversioned_path(request.uri().path()).into_owned();
+    let Some(api_path) = path.strip_prefix("/api").filter(|rest| rest.starts_with('/')) else {
+        return next.run(request).await;
+    };
+
+    if is_api_only_plugin_route(&state, api_path, request.method()) {
+        request.extensions_mut().insert(HeaderCredentialsOnly);
+        return next.run(request).await;
+    }
+
+    let exempt = request.method().is_safe()
+        || request.headers().contains_key(header::AUTHORIZATION)
+        || is_public_route(&path);
+    if exempt {
+        return next.run(request).await;
+    }
+
+    let jar = CookieJar::from_headers(request.headers());
+    let Some(session) = jar.get(&config.cookie).map(Cookie::value) else {
+        return next.run(request).await;
+    };
+    let token = request
+        .headers()
+        .get(config.csrf_header.as_str())
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let valid = match config.protection_for(api_path) {
+        CsrfProtection::Off => true,
+        CsrfProtection::DoubleSubmit => jar
+            .get(&config.csrf_cookie)
+            .is_some_and(|cookie| !token.is_empty() && constant_time_eq(cookie.value().as_bytes(), token.as_bytes())),
+        CsrfProtection::Signed => state.csrf().verify(session, token),
+    };
+    if !valid {
+        tracing::debug!("Rejected {} {} without a valid CSRF token", request.method(), path);
+        return ServerError(orbis_core::Error::unauthorized("Missing or invalid CSRF token")).into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Check if an API path is a route of a plugin declaring itself API-only.
+fn is_api_only_plugin_route(state: &AppState, api_path: &str, method: &Method) -> bool {
+    let Some((plugin, path)) = api_path.strip_prefix("/plugins/").and_then(|rest| rest.split_once('/')) else {
+        return false;
+    };
+    state.plugins().registry().get(plugin).is_some_and(|info| {
+        info.manifest.routes.iter().any(|route| {
+            route.api_only
+                && route.path.strip_prefix('/') == Some(path)
+                && route.method.eq_ignore_ascii_case(method.as_str())
+        })
+    })
+}
+
+/// Get the session token of a request from its session cookie.
+///
+/// Returns `None` if cookie sessions are disabled or the request is marked
+/// [`HeaderCredentialsOnly`].
+#[must_use]
+pub fn session_token(headers: &HeaderMap, extensions: &Extensions, config: &CookieSessionConfig) -> Option<String> {
+    if !config.enabled || extensions.get::<HeaderCredentialsOnly>().is_some() {
+        return None;
+    }
+    CookieJar::from_headers(headers)
+        .get(&config.cookie)
+        .map(|cookie| cookie.value().to_owned())
+}
+
+/// Get the `Set-Cookie` headers starting a cookie session, and its CSRF
+/// token.
+#[must_use]
+pub fn session_cookies(config: &CookieSessionConfig, signer: &CsrfSigner, access_token: &str) -> (HeaderMap, String) {
+    let csrf_token = signer.sign(access_token);
+    let session = Cookie::build((config.cookie.clone(), access_token.to_owned())).http_only(true);
+    let csrf = Cookie::build((config.csrf_cookie.clone(), csrf_token.clone()));
+
+    let mut headers = HeaderMap::new();
+    for cookie in [session, csrf] {
+        let cookie = cookie
+            .path("/")
+            .secure(config.secure)
+            .same_site(same_site(config.same_site))
+            .build();
+        append_cookie(&mut headers, &cookie);
+    }
+    (headers, csrf_token)
+}
+
+/// Get the `Set-Cookie` headers ending a cookie session.
+#[must_use]
+pub fn clear_session_cookies(config: &CookieSessionConfig) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if config.enabled {
+        for name in [&config.cookie, &config.csrf_cookie] {
+            let mut cookie = Cookie::build((name.clone(), "")).path("/").build();
+            cookie.make_removal();
+            append_cookie(&mut headers, &cookie);
+        }
+    }
+    headers
+}
+
+/// Append a `Set-Cookie` header.
+fn append_cookie(headers: &mut HeaderMap, cookie: &Cookie<'_>) {
+    match HeaderValue::from_str(&cookie.to_string()) {
+        Ok(value) => {
+            headers.append(header::SET_COOKIE, value);
+        }
+        Err(e) => tracing::warn!("Could not set cookie '{}': {}", cookie.name(), e),
+    }
+}
+
+/// Convert a configured `SameSite` policy.
+const fn same_site(policy: SameSitePolicy) -> SameSite {
+    match policy {
+        SameSitePolicy::Strict => SameSite::Strict,
+        SameSitePolicy::Lax => SameSite::Lax,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::any, Router};
+    use orbis_config::Config;
+    use orbis_core::Localizer;
+    use orbis_db::Database;
+    use orbis_plugin::PluginManager;
+    use std::sync::Arc;
+    use tower::ServiceExt as _;
+
+    /// Plugin with one API-only route.
+    const API_PLUGIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+        (func (export "hook") (param i32 i32) (result i32) (i32.const 0)))"#;
+
+    /// Session token sent in the session cookie.
+    const SESSION: &str = "session-token";
+
+    /// Create a router answering every request behind the CSRF middleware,
+    /// with `/admin` protected by signed tokens and an API-only plugin
+    /// route.
+    async fn router(dir: &std::path::Path, cookie_sessions: bool) -> (Router, AppState) {
+        let plugin_dir = dir.join("plugins/hooks");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join("plugin.wasm"), wat::parse_str(API_PLUGIN).unwrap()).unwrap();
+        std::fs::write(
+            plugin_dir.join("manifest.json"),
+            serde_json::json!({
+                "name": "hooks",
+                "version": "1.0.0",
+                "routes": [{ "method": "POST", "path": "/hook", "handler": "hook", "api_only": true }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let db = Database::new(orbis_config::DatabaseConfig {
+            path: Some(dir.join("orbis.db")),
+            ..orbis_config::DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        db.migrate().await.unwrap();
+        let plugins = PluginManager::new(dir.join("plugins"), db.clone()).unwrap();
+        plugins.load_plugin(&plugin_dir).await.unwrap();
+
+        let mut config = Config::default();
+        config.jwt_secret = Some("secret".to_string());
+        config.cookie_sessions.enabled = cookie_sessions;
+        config.cookie_sessions.csrf = CsrfProtection::DoubleSubmit;
+        config
+            .cookie_sessions
+            .groups
+            .insert("/admin".to_string(), CsrfProtection::Signed);
+
+        let state = AppState::new(Arc::new(config), db, None, Arc::new(plugins), Localizer::new("en"));
+        let router = Router::new()
+            .route("/{*path}", any(|| async { StatusCode::OK }))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), csrf_middleware));
+        (router, state)
+    }
+
+    /// Send a request with the given headers and get its status.
+    async fn send(router: &Router, method: Method, path: &str, headers: &[(&str, String)]) -> StatusCode {
+        let mut request = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_csrf_middleware() {
+        let dir = std::env::temp_dir().join(format!("orbis-csrf-{}", uuid::Uuid::new_v4()));
+        let (router, state) = router(&dir, true).await;
+        let config = state.config().cookie_sessions.clone();
+        let token = state.csrf().sign(SESSION);
+        let session = (header::COOKIE.as_str(), format!("{}={}", config.cookie, SESSION));
+        let both = (
+            header::COOKIE.as_str(),
+            format!("{}={}; {}={}", config.cookie, SESSION, config.csrf_cookie, token),
+        );
+        let csrf = (config.csrf_header.as_str(), token.clone());
+
+        // Missing token
+        assert_eq!(send(&router, Method::POST, "/api/items", &[session.clone()]).await, StatusCode::FORBIDDEN);
+        assert_eq!(send(&router, Method::GET, "/api/items", &[session.clone()]).await, StatusCode::OK);
+        assert_eq!(send(&router, Method::POST, "/api/items", &[]).await, StatusCode::OK);
+
+        // Double-submit: the header must repeat the CSRF cookie
+        assert_eq!(send(&router, Method::POST, "/api/items", &[both.clone(), csrf.clone()]).await, StatusCode::OK);
+        assert_eq!(
+            send(&router, Method::POST, "/api/items", &[session.clone(), csrf.clone()]).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(&router, Method::POST, "/api/items", &[both.clone(), (csrf.0, "forged".to_string())]).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Signed: the header must hold the token signed for the session
+        assert_eq!(
+            send(&router, Method::POST, "/api/v1/admin/users", &[session.clone(), csrf.clone()]).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(&router, Method::POST, "/api/admin/users", &[session.clone(), (csrf.0, state.csrf().sign("other"))])
+                .await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Exemptions
+        let bearer = (header::AUTHORIZATION.as_str(), "Bearer token".to_string());
+        assert_eq!(send(&router, Method::POST, "/api/items", &[session.clone(), bearer]).await, StatusCode::OK);
+        assert_eq!(send(&router, Method::POST, "/api/auth/login", &[session.clone()]).await, StatusCode::OK);
+        assert_eq!(send(&router, Method::POST, "/api/plugins/hooks/hook", &[session.clone()]).await, StatusCode::OK);
+        assert_eq!(
+            send(&router, Method::PUT, "/api/plugins/hooks/hook", &[session]).await,
+            StatusCode::FORBIDDEN
+        );
+
+        drop(std::fs::remove_dir_all(&dir));
+    }
+
+    #[tokio::test]
+    async fn test_csrf_middleware_without_cookie_sessions() {
+        let dir = std::env::temp_dir().join(format!("orbis-csrf-disabled-{}", uuid::Uuid::new_v4()));
+        let (router, state) = router(&dir, false).await;
+        let config = state.config().cookie_sessions.clone();
+
+        // The session cookie authenticates nothing, so there is nothing to forge
+        let session = (header::COOKIE.as_str(), format!("{}={}", config.cookie, SESSION));
+        assert_eq!(send(&router, Method::POST, "/api/items", &[session.clone()]).await, StatusCode::OK);
+        assert_eq!(send(&router, Method::POST, "/api/admin/users", &[session]).await, StatusCode::OK);
+
+        drop(std::fs::remove_dir_all(&dir));
+    }
+
+    #[test]
+    fn test_csrf_signer() {
+        let signer = CsrfSigner::new(Some("secret"));
+        let token = signer.sign(SESSION);
+        assert!(signer.verify(SESSION, &token));
+        assert!(!signer.verify("other", &token));
+        assert!(!signer.verify(SESSION, "not hex"));
+        assert_eq!(CsrfSigner::new(Some("secret")).sign(SESSION), token);
+        assert_ne!(CsrfSigner::new(Some("other")).sign(SESSION), token);
+    }
+}
@@ -0,0 +1,216 @@
+//! Plugin development loop.
+//!
+//! `plugin dev <dir>` serves the plugin crate in `dir` from a temporary
+//! plugins directory, with hot reload and plugin development mode on. The
+//! plugin is built for WASM and copied there on start, then again whenever
+//! its sources change, and the server reloads it. Cargo's output is shown
+//! along with the server logs; a failed rebuild keeps the last build loaded.
+
+use orbis_config::Config;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use crate::Server;
+
+/// Interval at which plugin sources are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Target plugins are built for.
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Files and directories of a plugin crate that trigger a rebuild.
+const WATCHED_PATHS: &[&str] = &["src", "build.rs", "Cargo.toml", "manifest.json"];
+
+/// Serve a plugin under development until interrupted.
+///
+/// # Errors
+///
+/// Returns an error if the first build fails or the server cannot start.
+pub async fn run<W: Write>(mut config: Config, dir: &Path, out: &mut W) -> orbis_core::Result<()> {
+    let dir = dir.canonicalize()?;
+    let name = dir
+        .file_name()
+        .ok_or_else(|| orbis_core::Error::validation(format!("Invalid plugin directory: {}", dir.display())))?;
+    let plugins_dir = std::env::temp_dir().join(format!("orbis-dev-{}", std::process::id()));
+    let target = plugins_dir.join(name);
+
+    let wasm = build(&dir).await?;
+    copy_plugin(&dir, &wasm, &target)?;
+    writeln!(
+        out,
+        "Serving {} from {}; it is rebuilt when its sources change",
+        dir.display(),
+        plugins_dir.display()
+    )?;
+
+    config.plugins_dir = Some(plugins_dir.clone());
+    config.plugin_hot_reload = true;
+    config.plugin_dev_mode = true;
+    let result = async {
+        let server = Server::new(config).await?;
+        tokio::select! {
+            result = server.run() => result,
+            result = rebuild_on_change(&dir, &target) => result,
+        }
+    }
+    .await;
+
+    if let Err(e) = std::fs::remove_dir_all(&plugins_dir) {
+        tracing::warn!("Failed to remove {}: {}", plugins_dir.display(), e);
+    }
+    result
+}
+
+/// Rebuild the plugin and copy it to `target` whenever its sources change.
+///
+/// Failed updates are logged and the last good build keeps being served, so
+/// this only stops with dev mode.
+#[allow(clippy::infinite_loop, reason = "watches the sources until dev mode stops")]
+async fn rebuild_on_change(dir: &Path, target: &Path) -> orbis_core::Result<()> {
+    let mut built = latest_change(dir);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let changed = latest_change(dir);
+        if changed <= built {
+            continue;
+        }
+        built = changed;
+
+        tracing::info!("Sources of {} changed, rebuilding", dir.display());
+        if let Err(e) = rebuild(dir, target).await {
+            tracing::error!("Failed to update {}: {}; still watching", target.display(), e);
+        }
+    }
+}
+
+/// Rebuild the plugin and copy it to `target`, keeping the last build if
+/// the plugin does not build.
+async fn rebuild(dir: &Path, target: &Path) -> orbis_core::Result<()> {
+    match build(dir).await {
+        Ok(wasm) => {
+            copy_plugin(dir, &wasm, target)?;
+            tracing::info!("Rebuilt {}", dir.display());
+        }
+        Err(e) => tracing::error!("{}; keeping the last build", e),
+    }
+    Ok(())
+}
+
+/// Get the time the plugin's sources last changed.
+fn latest_change(dir: &Path) -> Option<SystemTime> {
+    fn latest(path: &Path) -> Option<SystemTime> {
+        let metadata = std::fs::metadata(path).ok()?;
+        if !metadata.is_dir() {
+            return metadata.modified().ok();
+        }
+        std::fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| latest(&entry.ok()?.path()))
+            .max()
+    }
+
+    WATCHED_PATHS.iter().filter_map(|path| latest(&dir.join(path))).max()
+}
+
+/// Build the plugin crate in `dir` for WASM, returning the built module.
+async fn build(dir: &Path) -> orbis_core::Result<PathBuf> {
+    let output = tokio::process::Command::new("cargo")
+        .args([
+            "build",
+            "--release",
+            "--target",
+            WASM_TARGET,
+            "--message-format=json-render-diagnostics",
+            "--color=always",
+        ])
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| orbis_core::Error::plugin(format!("Failed to run cargo: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(orbis_core::Error::plugin(format!("Building {} failed", dir.display())));
+    }
+    wasm_artifact(&output.stdout)
+        .ok_or_else(|| orbis_core::Error::plugin(format!("{} does not build a cdylib WASM module", dir.display())))
+}
+
+/// Message printed by `cargo build --message-format=json`.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    /// Message kind.
+    reason: String,
+
+    /// Target an artifact was built for.
+    #[serde(default)]
+    target: Option<CargoTarget>,
+
+    /// Files of an artifact.
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+}
+
+/// Cargo build target.
+#[derive(Debug, Deserialize)]
+struct CargoTarget {
+    /// Crate types of the target.
+    kind: Vec<String>,
+}
+
+/// Find the WASM module in cargo's JSON messages.
+fn wasm_artifact(messages: &[u8]) -> Option<PathBuf> {
+    String::from_utf8_lossy(messages)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| {
+            message.reason == "compiler-artifact"
+                && message
+                    .target
+                    .as_ref()
+                    .is_some_and(|target| target.kind.iter().any(|kind| kind == "cdylib"))
+        })
+        .filter_map(|message| {
+            message
+                .filenames
+                .into_iter()
+                .find(|file| file.extension().is_some_and(|extension| extension == "wasm"))
+        })
+        .next_back()
+}
+
+/// Copy a built plugin, with its manifest if it has one, to `target`.
+fn copy_plugin(dir: &Path, wasm: &Path, target: &Path) -> orbis_core::Result<()> {
+    std::fs::create_dir_all(target)?;
+
+    let manifest = dir.join("manifest.json");
+    if manifest.exists() {
+        copy_staged(&manifest, &target.join("manifest.json"))?;
+    }
+    let file_name = wasm
+        .file_name()
+        .ok_or_else(|| orbis_core::Error::plugin(format!("Invalid module path: {}", wasm.display())))?;
+    copy_staged(wasm, &target.join(file_name))
+}
+
+/// Copy a file under an ignored name first, so the watcher only sees the
+/// finished file.
+fn copy_staged(source: &Path, target: &Path) -> orbis_core::Result<()> {
+    let mut staging = target.as_os_str().to_owned();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+
+    std::fs::copy(source, &staging)?;
+    std::fs::rename(&staging, target).inspect_err(|_| {
+        if let Err(e) = std::fs::remove_file(&staging) {
+            tracing::debug!("Failed to remove {}: {}", staging.display(), e);
+        }
+    })?;
+    Ok(())
+}
@@ -0,0 +1,309 @@
+//! Plugin development environment diagnostics.
+//!
+//! `plugin doctor [dir]` checks what building, signing and publishing a
+//! plugin needs: the Rust toolchain and its WASM target, `wasm-opt`, the
+//! layout of the plugin crate, the signing and trusted keys, and a registry
+//! if one is given. Each failed check comes with the step fixing it.
+
+use axum::http::Uri;
+use orbis_config::Config;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Target plugins are built for.
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Environment variable holding the plugin signing key file.
+const SIGNING_KEY_ENV: &str = "ORBIS_PLUGIN_SIGNING_KEY";
+
+/// Time allowed to connect to a registry.
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Passed.
+    Ok,
+    /// Not needed by every plugin, or not configured.
+    Skipped,
+    /// Works, with a limitation.
+    Warning,
+    /// Failed.
+    Error,
+}
+
+impl CheckStatus {
+    /// Label of the status in text output.
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Skipped => "skip",
+            Self::Warning => "warn",
+            Self::Error => "FAIL",
+        }
+    }
+}
+
+/// Result of a diagnostic check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// Check name.
+    pub name: &'static str,
+
+    /// Outcome.
+    pub status: CheckStatus,
+
+    /// What was found.
+    pub message: String,
+
+    /// How to fix a failed check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remedy: Option<String>,
+}
+
+impl Check {
+    /// Create a check result.
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            remedy: None,
+        }
+    }
+
+    /// Add how to fix the check.
+    fn with_remedy(mut self, remedy: impl Into<String>) -> Self {
+        self.remedy = Some(remedy.into());
+        self
+    }
+}
+
+/// Run the checks and print them, as text or as JSON.
+///
+/// # Errors
+///
+/// Returns an error if a check fails or the report cannot be written.
+pub async fn run<W: Write>(
+    config: &Config,
+    dir: &Path,
+    registry: Option<&str>,
+    json: bool,
+    out: &mut W,
+) -> orbis_core::Result<()> {
+    let checks = vec![
+        check_toolchain().await,
+        check_wasm_target().await,
+        check_wasm_opt().await,
+        check_crate(dir),
+        check_manifest(dir),
+        check_signing_key(),
+        check_trusted_keys(config),
+        check_registry(registry).await,
+    ];
+    let failed = checks.iter().filter(|check| check.status == CheckStatus::Error).count();
+
+    if json {
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "ok": failed == 0, "checks": checks }))?
+        )?;
+    } else {
+        for check in &checks {
+            writeln!(out, "[{:>4}] {}: {}", check.status.label(), check.name, check.message)?;
+            if let Some(ref remedy) = check.remedy {
+                writeln!(out, "       fix: {}", remedy)?;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(orbis_core::Error::validation(format!("{} of {} checks failed", failed, checks.len())));
+    }
+    Ok(())
+}
+
+/// Run a program, returning its standard output if it succeeds.
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = tokio::process::Command::new(program).args(args).output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Check cargo is installed.
+async fn check_toolchain() -> Check {
+    let Some(version) = command_output("cargo", &["--version"]).await else {
+        return Check::new("toolchain", CheckStatus::Error, "cargo was not found")
+            .with_remedy("Install Rust with rustup from https://rustup.rs");
+    };
+    Check::new("toolchain", CheckStatus::Ok, version)
+}
+
+/// Check the WASM target is installed.
+async fn check_wasm_target() -> Check {
+    let Some(targets) = command_output("rustup", &["target", "list", "--installed"]).await else {
+        return Check::new(
+            "wasm-target",
+            CheckStatus::Warning,
+            "rustup was not found, so installed targets cannot be listed",
+        )
+        .with_remedy(format!("Make sure your toolchain includes the {} target", WASM_TARGET));
+    };
+
+    if targets.lines().any(|target| target.trim() == WASM_TARGET) {
+        Check::new("wasm-target", CheckStatus::Ok, format!("{} is installed", WASM_TARGET))
+    } else {
+        Check::new("wasm-target", CheckStatus::Error, format!("{} is not installed", WASM_TARGET))
+            .with_remedy(format!("rustup target add {}", WASM_TARGET))
+    }
+}
+
+/// Check `wasm-opt` is installed.
+async fn check_wasm_opt() -> Check {
+    let Some(version) = command_output("wasm-opt", &["--version"]).await else {
+        return Check::new(
+            "wasm-opt",
+            CheckStatus::Warning,
+            "wasm-opt was not found; plugins build, but are larger",
+        )
+        .with_remedy("Install binaryen (for example `cargo install wasm-opt`)");
+    };
+    Check::new("wasm-opt", CheckStatus::Ok, version)
+}
+
+/// Check the directory is a crate building a WASM module.
+fn check_crate(dir: &Path) -> Check {
+    let cargo_toml = dir.join("Cargo.toml");
+    let Ok(contents) = std::fs::read_to_string(&cargo_toml) else {
+        return Check::new("crate", CheckStatus::Error, format!("{} was not found", cargo_toml.display()))
+            .with_remedy("Run the doctor from a plugin crate, or pass its directory");
+    };
+
+    if contents.contains("cdylib") {
+        Check::new("crate", CheckStatus::Ok, format!("{} builds a cdylib", cargo_toml.display()))
+    } else {
+        Check::new(
+            "crate",
+            CheckStatus::Error,
+            format!("{} does not build a cdylib", cargo_toml.display()),
+        )
+        .with_remedy("Add `crate-type = [\"cdylib\"]` to the [lib] section")
+    }
+}
+
+/// Check the plugin manifest is valid.
+fn check_manifest(dir: &Path) -> Check {
+    let path = dir.join("manifest.json");
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Check::new(
+            "manifest",
+            CheckStatus::Warning,
+            format!("{} was not found", path.display()),
+        )
+        .with_remedy("Add a manifest.json, or embed the manifest in the WASM module");
+    };
+
+    let manifest = serde_json::from_str::<orbis_plugin::PluginManifest>(&contents)
+        .map_err(|e| e.to_string())
+        .and_then(|manifest| manifest.validate().map(|()| manifest).map_err(|e| e.to_string()));
+    match manifest {
+        Ok(manifest) => Check::new(
+            "manifest",
+            CheckStatus::Ok,
+            format!("{} {} is valid", manifest.name, manifest.version),
+        ),
+        Err(e) => Check::new("manifest", CheckStatus::Error, e).with_remedy(format!("Fix {}", path.display())),
+    }
+}
+
+/// Check the signing key, if one is set, can be read.
+fn check_signing_key() -> Check {
+    let Some(path) = std::env::var_os(SIGNING_KEY_ENV) else {
+        return Check::new(
+            "signing-key",
+            CheckStatus::Skipped,
+            format!("{} is not set", SIGNING_KEY_ENV),
+        )
+        .with_remedy(format!(
+            "To sign plugins, run `orbis-server plugin keygen <file>` and set {}",
+            SIGNING_KEY_ENV
+        ));
+    };
+
+    let key = std::fs::read_to_string(&path)
+        .map_err(orbis_core::Error::from)
+        .and_then(|hex| orbis_plugin::decode_signing_key(&hex));
+    match key {
+        Ok(key) => Check::new(
+            "signing-key",
+            CheckStatus::Ok,
+            format!("Signing with key {}", orbis_plugin::key_id(&key.verifying_key())),
+        ),
+        Err(e) => Check::new(
+            "signing-key",
+            CheckStatus::Error,
+            format!("Cannot use {}: {}", Path::new(&path).display(), e),
+        )
+        .with_remedy(format!("Point {} at a key generated by `orbis-server plugin keygen`", SIGNING_KEY_ENV)),
+    }
+}
+
+/// Check the trusted keys directory, if one is configured, can be loaded.
+fn check_trusted_keys(config: &Config) -> Check {
+    let Some(ref dir) = config.plugin_trusted_keys_dir else {
+        return Check::new("trusted-keys", CheckStatus::Skipped, "No trusted keys directory is configured");
+    };
+
+    match orbis_plugin::Keyring::load(dir) {
+        Ok(keyring) if keyring.is_empty() => Check::new(
+            "trusted-keys",
+            CheckStatus::Warning,
+            format!("{} has no public keys", dir.display()),
+        )
+        .with_remedy("Copy the .pub files of trusted signing keys into it"),
+        Ok(keyring) => Check::new(
+            "trusted-keys",
+            CheckStatus::Ok,
+            format!("{} trusted keys in {}", keyring.len(), dir.display()),
+        ),
+        Err(e) => Check::new("trusted-keys", CheckStatus::Error, e.to_string())
+            .with_remedy("Fix ORBIS_PLUGIN_TRUSTED_KEYS_DIR or the keys in it"),
+    }
+}
+
+/// Check the registry, if one is given, accepts connections.
+async fn check_registry(registry: Option<&str>) -> Check {
+    let Some(url) = registry else {
+        return Check::new("registry", CheckStatus::Skipped, "No registry given");
+    };
+    let Some((host, port)) = registry_address(url) else {
+        return Check::new("registry", CheckStatus::Error, format!("Invalid registry URL '{}'", url))
+            .with_remedy("Pass an http or https URL");
+    };
+
+    match tokio::time::timeout(REGISTRY_TIMEOUT, tokio::net::TcpStream::connect((host.as_str(), port))).await {
+        Ok(Ok(_)) => Check::new("registry", CheckStatus::Ok, format!("Connected to {}", url)),
+        Ok(Err(e)) => Check::new("registry", CheckStatus::Error, format!("Cannot connect to {}: {}", url, e))
+            .with_remedy("Check the URL, your network and proxy settings"),
+        Err(_) => Check::new("registry", CheckStatus::Error, format!("Connecting to {} timed out", url))
+            .with_remedy("Check the URL, your network and proxy settings"),
+    }
+}
+
+/// Get the host and port of a registry URL.
+fn registry_address(url: &str) -> Option<(String, u16)> {
+    let uri: Uri = url.parse().ok()?;
+    let default_port = match uri.scheme_str()? {
+        "https" => 443,
+        "http" => 80,
+        _ => return None,
+    };
+    let host = uri.host()?.trim_start_matches('[').trim_end_matches(']').to_owned();
+    Some((host, uri.port_u16().unwrap_or(default_port)))
+}
@@ -0,0 +1,401 @@
+//! Email sending service.
+//!
+//! Messages are rendered up front and delivered by an `email.send` job, so
+//! failed deliveries are retried (and eventually dead-lettered) by the job
+//! queue. Delivery goes through an [`EmailTransport`]: SMTP when a host is
+//! configured, the log otherwise.
+//!
+//! Subjects and bodies are templates with `{{ name }}` placeholders; values
+//! substituted into HTML bodies are escaped.
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use orbis_config::{EmailConfig, SmtpSecurity};
+use orbis_core::shutdown;
+use orbis_plugin::{PluginEmail, PluginManager};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::jobs::{Job, JobHandler, JobQueue, NewJob};
+
+/// Job kind delivering an email (the payload is an [`EmailMessage`]).
+pub const EMAIL_JOB: &str = "email.send";
+
+/// Built-in template for password reset emails.
+///
+/// Variables: `name`, `reset_url`, `expires_in`.
+pub const PASSWORD_RESET_TEMPLATE: &str = "password_reset";
+
+/// Built-in template for two-factor enrollment emails.
+///
+/// Variables: `name`, `code`.
+pub const TWO_FACTOR_ENROLLMENT_TEMPLATE: &str = "two_factor_enrollment";
+
+/// Built-in template confirming a new email address.
+///
+/// Variables: `name`, `email`, `code`, `expires_in`.
+pub const EMAIL_CHANGE_TEMPLATE: &str = "email_change";
+
+/// A rendered email.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmailMessage {
+    /// Recipient addresses.
+    pub to: Vec<String>,
+
+    /// Subject.
+    pub subject: String,
+
+    /// Plain text body.
+    #[serde(default)]
+    pub text: Option<String>,
+
+    /// HTML body.
+    #[serde(default)]
+    pub html: Option<String>,
+}
+
+/// An email template.
+#[derive(Debug, Clone, Copy)]
+struct EmailTemplate {
+    /// Subject template.
+    subject: &'static str,
+
+    /// Plain text body template.
+    text: &'static str,
+
+    /// HTML body template.
+    html: &'static str,
+}
+
+impl EmailTemplate {
+    /// Look up a built-in template.
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            PASSWORD_RESET_TEMPLATE => Some(Self {
+                subject: "Reset your password",
+                text: "Hi {{ name }},\n\n\
+                       Use the link below to reset your password. It expires in {{ expires_in }}.\n\n\
+                       {{ reset_url }}\n\n\
+                       If you did not request a password reset, you can ignore this email.\n",
+                html: "<p>Hi {{ name }},</p>\
+                       <p>Use the link below to reset your password. It expires in {{ expires_in }}.</p>\
+                       <p><a href=\"{{ reset_url }}\">Reset password</a></p>\
+                       <p>If you did not request a password reset, you can ignore this email.</p>",
+            }),
+            TWO_FACTOR_ENROLLMENT_TEMPLATE => Some(Self {
+                subject: "Your two-factor enrollment code",
+                text: "Hi {{ name }},\n\n\
+                       Enter this code to finish enabling two-factor authentication:\n\n\
+                       {{ code }}\n\n\
+                       If you did not start two-factor enrollment, change your password.\n",
+                html: "<p>Hi {{ name }},</p>\
+                       <p>Enter this code to finish enabling two-factor authentication:</p>\
+                       <p><strong>{{ code }}</strong></p>\
+                       <p>If you did not start two-factor enrollment, change your password.</p>",
+            }),
+            EMAIL_CHANGE_TEMPLATE => Some(Self {
+                subject: "Confirm your new email address",
+                text: "Hi {{ name }},\n\n\
+                       Enter this code to confirm {{ email }} as your email address. It expires in {{ expires_in }}:\n\n\
+                       {{ code }}\n\n\
+                       If you did not change your email address, you can ignore this email.\n",
+                html: "<p>Hi {{ name }},</p>\
+                       <p>Enter this code to confirm {{ email }} as your email address. It expires in {{ expires_in }}:</p>\
+                       <p><strong>{{ code }}</strong></p>\
+                       <p>If you did not change your email address, you can ignore this email.</p>",
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Render a template, replacing `{{ name }}` placeholders with variables.
+///
+/// Missing variables render as empty strings; values are HTML-escaped when
+/// `html` is set.
+#[must_use]
+pub fn render_template(template: &str, variables: &Value, html: bool) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+
+        let name = rest[start + 2..start + end].trim();
+        let value = variables.get(name).map_or_else(String::new, |value| {
+            value.as_str().map_or_else(|| value.to_string(), ToOwned::to_owned)
+        });
+        if html {
+            output.push_str(&escape_html(&value));
+        } else {
+            output.push_str(&value);
+        }
+
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Escape text for inclusion in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Delivers emails.
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    /// Deliver a message from the given sender.
+    async fn send(&self, message: &EmailMessage, from: &str) -> orbis_core::Result<()>;
+}
+
+/// Delivers emails through an SMTP server.
+pub struct SmtpTransport {
+    /// SMTP transport.
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    /// Create an SMTP transport from the email configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no SMTP host is configured or the relay cannot be set up.
+    pub fn new(config: &EmailConfig) -> orbis_core::Result<Self> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .ok_or_else(|| orbis_core::Error::config("SMTP host is not configured"))?;
+
+        let builder = match config.smtp_security {
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+        }
+        .map_err(|e| orbis_core::Error::config(format!("Invalid SMTP relay '{}': {}", host, e)))?
+        .port(config.smtp_port);
+
+        let builder = if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder.credentials(Credentials::new(username.clone(), password.clone()))
+        } else {
+            builder
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, message: &EmailMessage, from: &str) -> orbis_core::Result<()> {
+        let mailbox = |address: &str| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|e| orbis_core::Error::validation(format!("Invalid email address '{}': {}", address, e)))
+        };
+
+        let mut builder = Message::builder().from(mailbox(from)?).subject(message.subject.clone());
+        for to in &message.to {
+            builder = builder.to(mailbox(to)?);
+        }
+
+        let email = match (&message.text, &message.html) {
+            (Some(text), Some(html)) => {
+                builder.multipart(MultiPart::alternative_plain_html(text.clone(), html.clone()))
+            }
+            (None, Some(html)) => builder.singlepart(SinglePart::html(html.clone())),
+            (text, None) => builder
+                .header(ContentType::TEXT_PLAIN)
+                .body(text.clone().unwrap_or_default()),
+        }
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid email: {}", e)))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| orbis_core::Error::internal(format!("Failed to send email: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Writes emails to the log instead of sending them.
+pub struct LogTransport;
+
+#[async_trait]
+impl EmailTransport for LogTransport {
+    async fn send(&self, message: &EmailMessage, from: &str) -> orbis_core::Result<()> {
+        tracing::info!(
+            "Email from {} to {}: {}\n{}",
+            from,
+            message.to.join(", "),
+            message.subject,
+            message.text.as_deref().or(message.html.as_deref()).unwrap_or_default()
+        );
+        Ok(())
+    }
+}
+
+/// Email sending service.
+#[derive(Clone)]
+pub struct EmailService {
+    /// Job queue delivering the emails.
+    jobs: JobQueue,
+
+    /// Emails sent by plugins, until the service is started.
+    plugin_emails: Arc<parking_lot::Mutex<Option<mpsc::UnboundedReceiver<PluginEmail>>>>,
+}
+
+impl EmailService {
+    /// Create the email service, register its delivery job and route emails
+    /// sent by plugins to it.
+    ///
+    /// Falls back to logging emails if the SMTP transport cannot be set up.
+    #[must_use]
+    pub fn new(config: &EmailConfig, jobs: JobQueue, plugins: &PluginManager) -> Self {
+        let transport: Arc<dyn EmailTransport> = if config.is_smtp_enabled() {
+            match SmtpTransport::new(config) {
+                Ok(transport) => Arc::new(transport),
+                Err(e) => {
+                    tracing::error!("Failed to set up SMTP, logging emails instead: {}", e);
+                    Arc::new(LogTransport)
+                }
+            }
+        } else {
+            Arc::new(LogTransport)
+        };
+
+        Self::with_transport(config, jobs, plugins, transport)
+    }
+
+    /// Create the email service with a custom transport.
+    #[must_use]
+    pub fn with_transport(
+        config: &EmailConfig,
+        jobs: JobQueue,
+        plugins: &PluginManager,
+        transport: Arc<dyn EmailTransport>,
+    ) -> Self {
+        jobs.register(
+            EMAIL_JOB,
+            Arc::new(EmailDeliveryHandler {
+                transport,
+                from: config.from.clone(),
+            }),
+        );
+
+        let (sink, plugin_emails) = mpsc::unbounded_channel();
+        plugins.runtime().set_email_sink(sink);
+
+        Self {
+            jobs,
+            plugin_emails: Arc::new(parking_lot::Mutex::new(Some(plugin_emails))),
+        }
+    }
+
+    /// Start the intake of emails sent by plugins, until the workers phase
+    /// of the shutdown.
+    ///
+    /// Does nothing after the first call.
+    pub fn start(&self, shutdown: &shutdown::Coordinator) {
+        let Some(mut plugin_emails) = self.plugin_emails.lock().take() else {
+            return;
+        };
+
+        let stop = shutdown.token(shutdown::Phase::Workers);
+        let service = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let email = tokio::select! {
+                    () = stop.cancelled() => break,
+                    email = plugin_emails.recv() => email,
+                };
+                let Some(email) = email else { break };
+                let plugin = email.plugin.clone();
+                let message = EmailMessage {
+                    to: email.to,
+                    subject: render_template(&email.subject, &email.variables, false),
+                    text: email.text.map(|text| render_template(&text, &email.variables, false)),
+                    html: email.html.map(|html| render_template(&html, &email.variables, true)),
+                };
+                if let Err(e) = service.send(message).await {
+                    tracing::error!("Failed to queue email of plugin {}: {}", plugin, e);
+                }
+            }
+        });
+    }
+
+    /// Queue an email for delivery.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message is invalid or cannot be queued.
+    pub async fn send(&self, message: EmailMessage) -> orbis_core::Result<Job> {
+        if message.to.is_empty() {
+            return Err(orbis_core::Error::validation("Email needs at least one recipient"));
+        }
+        if message.text.is_none() && message.html.is_none() {
+            return Err(orbis_core::Error::validation("Email needs a text or HTML body"));
+        }
+
+        let payload = serde_json::to_value(&message)
+            .map_err(|e| orbis_core::Error::serialization(e.to_string()))?;
+        self.jobs.enqueue(NewJob::new(EMAIL_JOB, payload)).await
+    }
+
+    /// Render a built-in template and queue it for delivery.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template does not exist or the email cannot be queued.
+    pub async fn send_template(&self, template: &str, to: &[String], variables: &Value) -> orbis_core::Result<Job> {
+        let template = EmailTemplate::builtin(template)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Email template '{}' not found", template)))?;
+
+        self.send(EmailMessage {
+            to: to.to_vec(),
+            subject: render_template(template.subject, variables, false),
+            text: Some(render_template(template.text, variables, false)),
+            html: Some(render_template(template.html, variables, true)),
+        })
+        .await
+    }
+}
+
+/// Delivers the email in the job payload.
+struct EmailDeliveryHandler {
+    /// Transport delivering the email.
+    transport: Arc<dyn EmailTransport>,
+
+    /// Sender address.
+    from: String,
+}
+
+#[async_trait]
+impl JobHandler for EmailDeliveryHandler {
+    async fn run(&self, job: &Job) -> orbis_core::Result<()> {
+        let message: EmailMessage = serde_json::from_value(job.payload.clone())
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid email job payload: {}", e)))?;
+        self.transport.send(&message, &self.from).await
+    }
+}
@@ -53,6 +53,15 @@ impl IntoResponse for ServerError {
             orbis_core::Error::Conflict(msg) => {
                 (StatusCode::CONFLICT, "CONFLICT", msg.clone())
             }
+            orbis_core::Error::PayloadTooLarge(msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE", msg.clone())
+            }
+            orbis_core::Error::Timeout(msg) => {
+                (StatusCode::REQUEST_TIMEOUT, "REQUEST_TIMEOUT", msg.clone())
+            }
+            orbis_core::Error::OutOfMemory(msg) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "OUT_OF_MEMORY", msg.clone())
+            }
             orbis_core::Error::Internal(msg) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", msg.clone())
             }
@@ -0,0 +1,274 @@
+//! Event hub shared by server-sent event streams and long polling.
+//!
+//! Plugin reload events, state changes and the events plugins emit are
+//! numbered in order and kept in
+//! a bounded buffer, so clients that cannot keep a stream open (such as
+//! behind proxies buffering responses) can poll for the events after the
+//! last one they received.
+
+use chrono::{DateTime, Utc};
+use orbis_config::EventsConfig;
+use orbis_core::shutdown;
+use orbis_plugin::PluginManager;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, watch};
+
+/// Capacity of the live event channel of server-sent event streams.
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+/// Name of plugin hot reload events.
+pub const PLUGIN_RELOAD_EVENT: &str = "plugin-reload";
+
+/// Name of plugin state change events.
+pub const PLUGIN_STATE_EVENT: &str = "plugin-state";
+
+/// Name of the events plugins emit through the `emit_event` host function.
+pub const PLUGIN_EVENT: &str = "plugin-event";
+
+/// An event published on the hub.
+#[derive(Debug, Clone, Serialize)]
+pub struct HubEvent {
+    /// Cursor of the event, to resume polling after it.
+    pub cursor: String,
+
+    /// Event name.
+    pub event: &'static str,
+
+    /// Event payload.
+    pub data: Value,
+
+    /// When the event was published.
+    pub at: DateTime<Utc>,
+
+    /// Sequence number of the event in this server run.
+    #[serde(skip)]
+    seq: u64,
+}
+
+/// Events returned by a poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventBatch {
+    /// Events after the cursor, oldest first.
+    pub events: Vec<HubEvent>,
+
+    /// Cursor to poll from next.
+    pub cursor: String,
+
+    /// Whether events after the cursor were dropped before they could be
+    /// returned, because the client fell further behind than the buffer or
+    /// the cursor is from a previous server run.
+    pub reset: bool,
+}
+
+/// Event hub of a profile.
+#[derive(Clone)]
+pub struct EventHub {
+    /// Shared hub state.
+    inner: Arc<HubInner>,
+}
+
+/// Shared state of an event hub.
+struct HubInner {
+    /// Delivery limits.
+    config: EventsConfig,
+
+    /// Identifier of this server run, so cursors of previous runs are detected.
+    run: i64,
+
+    /// Most recent events, oldest first.
+    buffer: Mutex<VecDeque<HubEvent>>,
+
+    /// Sequence number of the last published event, watched by pending polls.
+    latest: watch::Sender<u64>,
+
+    /// Live events, for server-sent event streams.
+    live: broadcast::Sender<HubEvent>,
+}
+
+impl EventHub {
+    /// Create an event hub with delivery limits.
+    #[must_use]
+    pub fn new(config: EventsConfig) -> Self {
+        let (latest, _) = watch::channel(0);
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self {
+            inner: Arc::new(HubInner {
+                config,
+                run: Utc::now().timestamp_millis(),
+                buffer: Mutex::new(VecDeque::new()),
+                latest,
+                live,
+            }),
+        }
+    }
+
+    /// Get the delivery limits.
+    #[must_use]
+    pub fn config(&self) -> &EventsConfig {
+        &self.inner.config
+    }
+
+    /// Publish an event to streams and pollers.
+    pub fn publish(&self, event: &'static str, data: Value) {
+        let mut buffer = self.inner.buffer.lock();
+        let seq = self.inner.latest.borrow().saturating_add(1);
+        let event = HubEvent {
+            cursor: self.cursor_at(seq),
+            event,
+            data,
+            at: Utc::now(),
+            seq,
+        };
+
+        while buffer.len() >= self.inner.config.buffer_size {
+            buffer.pop_front();
+        }
+        buffer.push_back(event.clone());
+        self.inner.latest.send_replace(seq);
+
+        // The buffer stays locked so streams get events in order
+        if self.inner.live.send(event).is_err() {
+            tracing::trace!("No event stream subscribers");
+        }
+        drop(buffer);
+    }
+
+    /// Subscribe to live events, for server-sent event streams.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<HubEvent> {
+        self.inner.live.subscribe()
+    }
+
+    /// Wait up to `wait` for events after a cursor, returning at most `limit`
+    /// of them (and never more than the configured batch size).
+    ///
+    /// Without a cursor, only events published from now on are returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cursor is malformed.
+    pub async fn poll(&self, cursor: Option<&str>, limit: usize, wait: Duration) -> orbis_core::Result<EventBatch> {
+        let (after, stale) = match cursor {
+            Some(cursor) => self.parse_cursor(cursor)?,
+            None => (*self.inner.latest.borrow(), false),
+        };
+
+        // The current value is checked first, so events published since the
+        // cursor are returned without waiting
+        let mut latest = self.inner.latest.subscribe();
+        if !stale && tokio::time::timeout(wait, latest.wait_for(|seq| *seq > after)).await.is_err() {
+            tracing::trace!("Event poll timed out");
+        }
+
+        let limit = limit.clamp(1, self.inner.config.poll_max_events);
+        Ok(self.since(after, stale, limit))
+    }
+
+    /// Get the buffered events after a sequence number.
+    fn since(&self, after: u64, stale: bool, limit: usize) -> EventBatch {
+        let buffer = self.inner.buffer.lock();
+        let dropped = buffer.front().is_some_and(|oldest| oldest.seq > after.saturating_add(1));
+        let events: Vec<HubEvent> = buffer
+            .iter()
+            .filter(|event| event.seq > after)
+            .take(limit)
+            .cloned()
+            .collect();
+        drop(buffer);
+
+        let last = events.last().map_or(after, |event| event.seq);
+
+        EventBatch {
+            events,
+            cursor: self.cursor_at(last),
+            reset: stale || dropped,
+        }
+    }
+
+    /// Format the cursor of a sequence number.
+    fn cursor_at(&self, seq: u64) -> String {
+        format!("{}-{}", self.inner.run, seq)
+    }
+
+    /// Parse a cursor into the sequence number to resume after, and whether
+    /// it is from a previous server run (resuming from the oldest event kept).
+    fn parse_cursor(&self, cursor: &str) -> orbis_core::Result<(u64, bool)> {
+        let parsed = cursor
+            .split_once('-')
+            .and_then(|(run, seq)| Some((run.parse::<i64>().ok()?, seq.parse::<u64>().ok()?)));
+        let Some((run, seq)) = parsed else {
+            return Err(orbis_core::Error::validation(format!("Invalid event cursor '{}'", cursor)));
+        };
+
+        let latest = *self.inner.latest.borrow();
+        if run != self.inner.run || seq > latest {
+            return Ok((0, true));
+        }
+        Ok((seq, false))
+    }
+
+    /// Publish the plugin reload events, state changes and emitted events of
+    /// a profile until it shuts down, revalidating the page data emitted
+    /// events invalidate.
+    pub fn start(&self, plugins: &Arc<PluginManager>, shutdown: &shutdown::Coordinator) {
+        self.forward(PLUGIN_RELOAD_EVENT, plugins.reload_events().subscribe(), shutdown);
+        self.forward(PLUGIN_STATE_EVENT, plugins.registry().history().subscribe(), shutdown);
+
+        let (sink, mut emitted) = mpsc::unbounded_channel();
+        plugins.runtime().set_event_sink(sink);
+        let hub = self.clone();
+        let plugins = Arc::clone(plugins);
+        let stop = shutdown.token(shutdown::Phase::Plugins);
+        tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    () = stop.cancelled() => break,
+                    event = emitted.recv() => event,
+                };
+                let Some(event) = event else {
+                    break;
+                };
+
+                // Cached page data goes stale before clients refetch on the event
+                plugins.revalidate_event(&event.event);
+                match serde_json::to_value(&event) {
+                    Ok(data) => hub.publish(PLUGIN_EVENT, data),
+                    Err(e) => tracing::error!("Failed to serialize {} event: {}", PLUGIN_EVENT, e),
+                }
+            }
+        });
+    }
+
+    /// Publish the events of a channel under a name.
+    fn forward<T>(&self, event: &'static str, mut events: broadcast::Receiver<T>, shutdown: &shutdown::Coordinator)
+    where
+        T: Serialize + Clone + Send + 'static,
+    {
+        let hub = self.clone();
+        let stop = shutdown.token(shutdown::Phase::Plugins);
+        tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    () = stop.cancelled() => break,
+                    received = events.recv() => received,
+                };
+                match received {
+                    Ok(data) => match serde_json::to_value(&data) {
+                        Ok(data) => hub.publish(event, data),
+                        Err(e) => tracing::error!("Failed to serialize {} event: {}", event, e),
+                    },
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event hub lagged, skipped {} {} events", skipped, event);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
@@ -1,37 +1,187 @@
 //! Request extractors.
+//!
+//! Users are authenticated once per request, by [`AuthUser::authenticate`]:
+//! from a bearer token, including impersonation tokens, the session cookie
+//! of the web client, or the client certificate of a mutual TLS connection. The user is then kept in the
+//! request extensions, so the auth middleware and every extractor of a
+//! request share it. Routes take [`AuthUser`] when a user is required,
+//! [`OptionalAuthUser`] when not, and [`RequireRole`] to require a role.
+//! Routes acting on the whole platform take [`RequirePlatformAdmin`].
+
+use std::marker::PhantomData;
+use std::ops::Deref;
 
 use axum::{
-    extract::{FromRef, FromRequestParts},
-    http::{header, request::Parts, StatusCode},
+    extract::{FromRef, FromRequestParts, OriginalUri},
+    http::{header, request::Parts, HeaderMap, StatusCode},
     Json,
     response::{IntoResponse, Response},
 };
-use orbis_auth::Claims;
+use orbis_auth::{impersonation_allows, AuthService, Claims, Impersonation, NewAuditEntry, Tenant};
+use orbis_plugin::ViewerAccess;
 
+use crate::csrf::session_token;
+use crate::middleware::ResolvedTenant;
 use crate::state::AppState;
+use crate::tls::ClientCertificate;
+use crate::versioning::unversioned_path;
 
-/// Authenticated user extractor.
-pub struct AuthenticatedUser {
+/// Authenticated user extractor, rejecting requests without valid credentials.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
     /// User claims from JWT.
     claims: Claims,
 
     /// User ID.
-    pub user_id: uuid::Uuid,
+    pub user_id: orbis_core::UserId,
 
     /// Username.
     pub username: String,
 
     /// Is admin.
     pub is_admin: bool,
+
+    /// Tenant the user belongs to (`None` for platform users).
+    pub tenant_id: Option<uuid::Uuid>,
+
+    /// Admin impersonating the user (impersonation tokens only).
+    pub impersonator: Option<orbis_core::UserId>,
+
+    /// Client certificate the user authenticated with (mutual TLS only).
+    pub certificate: Option<ClientCertificate>,
 }
 
-impl AuthenticatedUser {
+impl AuthUser {
+    /// Authenticate the user of a request.
+    ///
+    /// Returns `None` if the request carries neither an `Authorization`
+    /// header, a session cookie, nor a client certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credentials are invalid, belong to another
+    /// tenant, or are an impersonation token not allowed to make the request.
+    pub async fn authenticate(parts: &Parts, state: &AppState) -> Result<Option<Self>, AuthError> {
+        // Get auth service
+        let auth = state.auth().ok_or(AuthError::AuthNotConfigured)?;
+
+        // Tokens and certificates are only valid for the tenant of their user
+        let tenant_id = parts.extensions.get::<ResolvedTenant>().map(|tenant| tenant.0.id);
+
+        // Extract token from Authorization header or the session cookie, or
+        // fall back to the client certificate of a mutual TLS connection
+        let cookie = session_token(&parts.headers, &parts.extensions, &state.config().cookie_sessions);
+        let Some(token) = bearer_token(&parts.headers)?.or(cookie.as_deref()) else {
+            return match parts.extensions.get::<ClientCertificate>() {
+                Some(certificate) => Self::from_certificate(auth, certificate, tenant_id).await.map(Some),
+                None => Ok(None),
+            };
+        };
+
+        // Validate token
+        let claims = auth.validate_token(token).map_err(|e| {
+            tracing::debug!("Rejected token: {}", e);
+            AuthError::InvalidToken
+        })?;
+
+        // Parse user ID
+        let user_id = claims.sub.parse().map_err(|e| {
+            tracing::debug!("Rejected token with subject '{}': {}", claims.sub, e);
+            AuthError::InvalidToken
+        })?;
+
+        if claims.tenant_id != tenant_id.map(|id| id.to_string()) {
+            return Err(AuthError::InvalidToken);
+        }
+
+        // Impersonation tokens must still be active and are limited by policy
+        let impersonation = match auth.check_impersonation(&claims).await {
+            Ok(impersonation) => impersonation,
+            Err(e) => {
+                tracing::debug!("Rejected impersonation token: {}", e);
+                return Err(AuthError::InvalidToken);
+            }
+        };
+        if let Some(impersonation) = &impersonation {
+            authorize_impersonation(auth, impersonation, parts, state.config().impersonation_allow_writes).await?;
+        }
+
+        Ok(Some(Self {
+            username: claims.username.clone(),
+            is_admin: claims.is_admin,
+            claims,
+            user_id,
+            tenant_id,
+            impersonator: impersonation.map(|impersonation| impersonation.admin_id),
+            certificate: None,
+        }))
+    }
+
+    /// Get the user of a request, authenticating it unless done already.
+    ///
+    /// The user is kept in the request extensions for later extractors.
+    async fn resolve(parts: &mut Parts, state: &AppState) -> Result<Option<Self>, AuthError> {
+        if let Some(user) = parts.extensions.get::<Self>() {
+            return Ok(Some(user.clone()));
+        }
+
+        let user = Self::authenticate(parts, state).await?;
+        if let Some(user) = &user {
+            parts.extensions.insert(user.clone());
+        }
+        Ok(user)
+    }
+
+    /// Check if the user has a role.
+    #[must_use]
+    pub fn has_role(&self, role: &str) -> bool {
+        self.access().roles.iter().any(|granted| granted == role)
+    }
+
     /// Get the JWT claims.
     #[must_use]
     pub const fn claims(&self) -> &Claims {
         &self.claims
     }
 
+    /// Get the roles, permissions and groups of the user, for page and
+    /// plugin route access checks.
+    #[must_use]
+    pub fn access(&self) -> ViewerAccess {
+        ViewerAccess::for_user(self.is_admin).with_groups(self.claims.groups.clone())
+    }
+
+    /// Get the groups the user is a member of, directly or through a subgroup.
+    #[must_use]
+    pub fn groups(&self) -> &[String] {
+        &self.claims.groups
+    }
+
+    /// Authenticate the user a verified client certificate belongs to.
+    async fn from_certificate(
+        auth: &AuthService,
+        certificate: &ClientCertificate,
+        tenant_id: Option<uuid::Uuid>,
+    ) -> Result<Self, AuthError> {
+        let (user, claims) = auth
+            .authenticate_certificate(certificate.identities(), &certificate.fingerprint, tenant_id)
+            .await
+            .map_err(|e| {
+                tracing::debug!("Rejected client certificate '{}': {}", certificate.subject, e);
+                AuthError::UnknownCertificate
+            })?;
+
+        Ok(Self {
+            claims,
+            user_id: user.id,
+            username: user.username,
+            is_admin: user.is_admin,
+            tenant_id,
+            impersonator: None,
+            certificate: Some(certificate.clone()),
+        })
+    }
+
     /// Get the token expiration time.
     #[must_use]
     pub fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
@@ -40,7 +190,7 @@ impl AuthenticatedUser {
     }
 }
 
-impl<S> FromRequestParts<S> for AuthenticatedUser
+impl<S> FromRequestParts<S> for AuthUser
 where
     AppState: FromRef<S>,
     S: Send + Sync,
@@ -52,45 +202,72 @@ where
         state: &S,
     ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
         async move {
-            let app_state = AppState::from_ref(state);
-
-            // Get auth service
-            let auth = app_state.auth().ok_or(AuthError::AuthNotConfigured)?;
-
-            // Extract token from Authorization header
-            let auth_header = parts
-                .headers
-                .get(header::AUTHORIZATION)
-                .and_then(|value| value.to_str().ok())
-                .ok_or(AuthError::MissingToken)?;
-
-            let token = auth_header
-                .strip_prefix("Bearer ")
-                .ok_or(AuthError::InvalidHeader)?;
-
-            // Validate token
-            let claims = auth.validate_token(token).map_err(|_| AuthError::InvalidToken)?;
-
-            // Parse user ID
-            let user_id = claims
-                .sub
-                .parse()
-                .map_err(|_| AuthError::InvalidToken)?;
-
-            Ok(Self {
-                username: claims.username.clone(),
-                is_admin: claims.is_admin,
-                claims,
-                user_id,
-            })
+            let state = AppState::from_ref(state);
+            Self::resolve(parts, &state).await?.ok_or(AuthError::MissingToken)
         }
     }
 }
 
-/// Optional authenticated user extractor.
-pub struct OptionalUser(pub Option<AuthenticatedUser>);
+/// Get the bearer token of a request, if it has an `Authorization` header.
+///
+/// # Errors
+///
+/// Returns an error if the header is not a bearer token.
+pub fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, AuthError> {
+    let Some(value) = headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(Some)
+        .ok_or(AuthError::InvalidHeader)
+}
+
+/// Check an impersonation token may make a request, and audit it.
+async fn authorize_impersonation(
+    auth: &AuthService,
+    impersonation: &Impersonation,
+    parts: &Parts,
+    allow_writes: bool,
+) -> Result<(), AuthError> {
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or_else(|| parts.uri.path(), |uri| uri.0.path());
+    let allowed = impersonation_allows(parts.method.as_str(), &unversioned_path(path), allow_writes);
+    let action = if allowed { "impersonation.request" } else { "impersonation.blocked" };
+
+    let entry = NewAuditEntry {
+        user_agent: parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from),
+        ..NewAuditEntry::new(impersonation.admin_id, action, "impersonation", impersonation.id).details(
+            serde_json::json!({
+                "user_id": impersonation.user_id,
+                "method": parts.method.as_str(),
+                "path": path,
+            }),
+        )
+    };
+    if let Err(e) = auth.audit().record(entry).await {
+        tracing::error!("Failed to audit impersonated request: {}", e);
+        return Err(AuthError::AuditFailed);
+    }
+
+    if allowed { Ok(()) } else { Err(AuthError::ImpersonationForbidden) }
+}
 
-impl<S> FromRequestParts<S> for OptionalUser
+/// Optional authenticated user extractor, for routes open to anonymous users.
+///
+/// Invalid credentials are treated as missing.
+pub struct OptionalAuthUser(pub Option<AuthUser>);
+
+impl<S> FromRequestParts<S> for OptionalAuthUser
 where
     AppState: FromRef<S>,
     S: Send + Sync,
@@ -102,22 +279,93 @@ where
         state: &S,
     ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
         async move {
-            Ok(Self(
-                AuthenticatedUser::from_request_parts(parts, state)
-                    .await
-                    .ok(),
-            ))
+            let state = AppState::from_ref(state);
+            match AuthUser::resolve(parts, &state).await {
+                Ok(user) => Ok(Self(user)),
+                Err(e) => {
+                    tracing::debug!("Ignoring invalid credentials: {:?}", e);
+                    Ok(Self(None))
+                }
+            }
         }
     }
 }
 
-/// Admin user extractor (requires admin role).
-pub struct AdminUser(pub AuthenticatedUser);
+/// Client certificate of a mutual TLS connection, identifying API clients
+/// that are not users.
+pub struct ClientIdentity(pub ClientCertificate);
 
-impl<S> FromRequestParts<S> for AdminUser
+impl<S> FromRequestParts<S> for ClientIdentity
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
+        let certificate = parts.extensions.get::<ClientCertificate>().cloned();
+        async move { certificate.map(Self).ok_or(AuthError::MissingCertificate) }
+    }
+}
+
+/// Current tenant extractor (`None` outside multi-tenant mode or in platform scope).
+pub struct CurrentTenant(pub Option<Tenant>);
+
+impl CurrentTenant {
+    /// Get the tenant ID.
+    #[must_use]
+    pub fn id(&self) -> Option<uuid::Uuid> {
+        self.0.as_ref().map(|tenant| tenant.id)
+    }
+}
+
+impl<S> FromRequestParts<S> for CurrentTenant
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
+        let tenant = parts.extensions.get::<ResolvedTenant>().map(|tenant| tenant.0.clone());
+        async move { Ok(Self(tenant)) }
+    }
+}
+
+/// Role a route can require with [`RequireRole`].
+pub trait Role {
+    /// Name of the role, as in [`ViewerAccess::roles`].
+    const NAME: &'static str;
+}
+
+/// The role of administrators.
+#[derive(Debug, Clone, Copy)]
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = ViewerAccess::ADMIN_ROLE;
+}
+
+/// Authenticated user extractor requiring a role, e.g. `RequireRole<Admin>`.
+pub struct RequireRole<R: Role>(pub AuthUser, PhantomData<R>);
+
+impl<R: Role> Deref for RequireRole<R> {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
 where
     AppState: FromRef<S>,
     S: Send + Sync,
+    R: Role,
 {
     type Rejection = AuthError;
 
@@ -126,14 +374,43 @@ where
         state: &S,
     ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
         async move {
-            let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+            let user = AuthUser::from_request_parts(parts, state).await?;
 
-            if !user.is_admin {
-                return Err(AuthError::NotAdmin);
+            if !user.has_role(R::NAME) {
+                return Err(if R::NAME == Admin::NAME { AuthError::NotAdmin } else { AuthError::MissingRole });
             }
 
-            Ok(Self(user))
+            Ok(Self(user, PhantomData))
+        }
+    }
+}
+
+/// Admin extractor rejecting tenant admins, for routes acting on every
+/// tenant: plugins, their security policy, jobs and tenants themselves.
+pub struct RequirePlatformAdmin(pub AuthUser);
+
+impl Deref for RequirePlatformAdmin {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S> FromRequestParts<S> for RequirePlatformAdmin
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let RequireRole(user, _) = RequireRole::<Admin>::from_request_parts(parts, state).await?;
+        if user.tenant_id.is_some() {
+            return Err(AuthError::NotPlatformAdmin);
         }
+
+        Ok(Self(user))
     }
 }
 
@@ -144,7 +421,13 @@ pub enum AuthError {
     InvalidHeader,
     InvalidToken,
     NotAdmin,
+    NotPlatformAdmin,
+    MissingRole,
     AuthNotConfigured,
+    ImpersonationForbidden,
+    AuditFailed,
+    MissingCertificate,
+    UnknownCertificate,
 }
 
 impl IntoResponse for AuthError {
@@ -170,11 +453,41 @@ impl IntoResponse for AuthError {
                 "NOT_ADMIN",
                 "Admin privileges required",
             ),
+            Self::NotPlatformAdmin => (
+                StatusCode::FORBIDDEN,
+                "NOT_PLATFORM_ADMIN",
+                "Platform admin privileges required",
+            ),
+            Self::MissingRole => (
+                StatusCode::FORBIDDEN,
+                "MISSING_ROLE",
+                "You do not have the role required",
+            ),
             Self::AuthNotConfigured => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "AUTH_NOT_CONFIGURED",
                 "Authentication is not configured",
             ),
+            Self::ImpersonationForbidden => (
+                StatusCode::FORBIDDEN,
+                "IMPERSONATION_FORBIDDEN",
+                "This action is not allowed while impersonating a user",
+            ),
+            Self::AuditFailed => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "AUDIT_FAILED",
+                "Failed to record the request in the audit log",
+            ),
+            Self::MissingCertificate => (
+                StatusCode::UNAUTHORIZED,
+                "MISSING_CERTIFICATE",
+                "A client certificate is required",
+            ),
+            Self::UnknownCertificate => (
+                StatusCode::UNAUTHORIZED,
+                "UNKNOWN_CERTIFICATE",
+                "Client certificate does not belong to a user",
+            ),
         };
 
         let body = Json(serde_json::json!({
@@ -0,0 +1,883 @@
+//! Persistent background job queue.
+//!
+//! Jobs live in the `jobs` table and are claimed by workers with a visibility
+//! timeout: a claimed job is hidden from other workers until the timeout
+//! passes, so jobs of a crashed worker are picked up again. Failed jobs are
+//! retried with exponential backoff and moved to the `dead` state (the
+//! dead-letter queue) once they run out of attempts.
+//!
+//! Core tasks register a [`JobHandler`] for their job kind; jobs enqueued by
+//! plugins run one of the plugin's handlers.
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use orbis_config::JobsConfig;
+use orbis_core::{shutdown, CancellationToken};
+use orbis_db::{Database, DatabasePool};
+use orbis_plugin::{PluginContext, PluginJob, PluginManager};
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+use uuid::Uuid;
+
+/// Queue jobs are put on when none is given.
+pub const DEFAULT_QUEUE: &str = "default";
+
+/// Job kind installing a plugin from a local path (`{"path": "..."}`) or a
+/// remote source (`{"remote": {...}}`).
+pub const PLUGIN_INSTALL_JOB: &str = "plugin.install";
+
+/// How long idle workers wait before polling for due jobs again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time running jobs get to finish at shutdown.
+const JOB_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest delay between retries.
+const MAX_BACKOFF_SECONDS: i64 = 3600;
+
+/// Maximum number of jobs returned when listing.
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Columns selected for a job.
+const JOB_COLUMNS: &str = "id, queue, kind, plugin_name, tenant_id, payload, status, attempts, max_attempts, \
+                           run_at, locked_until, last_error, created_at, updated_at, completed_at";
+
+/// Status of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting to run (including failed jobs waiting for a retry).
+    Pending,
+
+    /// Claimed by a worker.
+    Running,
+
+    /// Finished successfully.
+    Completed,
+
+    /// Out of attempts (dead-lettered).
+    Dead,
+}
+
+impl JobStatus {
+    /// Get the status name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = orbis_core::Error;
+
+    fn from_str(s: &str) -> orbis_core::Result<Self> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "dead" => Ok(Self::Dead),
+            _ => Err(orbis_core::Error::validation(format!(
+                "Invalid job status '{}'. Expected 'pending', 'running', 'completed', or 'dead'",
+                s
+            ))),
+        }
+    }
+}
+
+/// A background job.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    /// Job ID.
+    pub id: Uuid,
+
+    /// Queue the job is on.
+    pub queue: String,
+
+    /// Job kind (the handler name for plugin jobs).
+    pub kind: String,
+
+    /// Plugin whose handler runs the job, if it is a plugin job.
+    pub plugin_name: Option<String>,
+
+    /// Tenant the job belongs to.
+    pub tenant_id: Option<Uuid>,
+
+    /// Job payload.
+    pub payload: Value,
+
+    /// Current status.
+    pub status: JobStatus,
+
+    /// Number of times the job has been claimed.
+    pub attempts: u32,
+
+    /// Attempts before the job is dead-lettered.
+    pub max_attempts: u32,
+
+    /// Earliest time the job may run.
+    pub run_at: DateTime<Utc>,
+
+    /// Time the current claim expires, while running.
+    pub locked_until: Option<DateTime<Utc>>,
+
+    /// Error of the last failed attempt.
+    pub last_error: Option<String>,
+
+    /// Creation time.
+    pub created_at: DateTime<Utc>,
+
+    /// Last update time.
+    pub updated_at: DateTime<Utc>,
+
+    /// Completion time.
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A job to enqueue.
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    /// Job kind.
+    pub kind: String,
+
+    /// Job payload.
+    pub payload: Value,
+
+    /// Queue to put the job on.
+    pub queue: String,
+
+    /// Delay before the job may run.
+    pub delay: Duration,
+
+    /// Attempts before the job is dead-lettered (the configured default if unset).
+    pub max_attempts: Option<u32>,
+
+    /// Plugin whose handler runs the job.
+    pub plugin_name: Option<String>,
+
+    /// Tenant the job belongs to.
+    pub tenant_id: Option<Uuid>,
+}
+
+impl NewJob {
+    /// Create a job of a kind with a payload, on the default queue.
+    #[must_use]
+    pub fn new(kind: &str, payload: Value) -> Self {
+        Self {
+            kind: kind.to_owned(),
+            payload,
+            queue: DEFAULT_QUEUE.to_owned(),
+            delay: Duration::ZERO,
+            max_attempts: None,
+            plugin_name: None,
+            tenant_id: None,
+        }
+    }
+
+    /// Put the job on a queue.
+    #[must_use]
+    pub fn on_queue(mut self, queue: &str) -> Self {
+        self.queue = queue.to_owned();
+        self
+    }
+
+    /// Delay the job.
+    #[must_use]
+    pub const fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the number of attempts before the job is dead-lettered.
+    #[must_use]
+    pub const fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+}
+
+impl From<PluginJob> for NewJob {
+    fn from(job: PluginJob) -> Self {
+        Self {
+            kind: job.handler,
+            payload: job.payload,
+            queue: job.queue.unwrap_or_else(|| DEFAULT_QUEUE.to_owned()),
+            delay: Duration::from_secs(job.delay_seconds),
+            max_attempts: job.max_attempts,
+            plugin_name: Some(job.plugin),
+            tenant_id: job.tenant_id.and_then(|tenant| tenant.parse().ok()),
+        }
+    }
+}
+
+/// Handler running jobs of one kind.
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// Run a job; an error fails the attempt.
+    async fn run(&self, job: &Job) -> orbis_core::Result<()>;
+}
+
+/// Query parameter, bound according to the database backend.
+enum Param {
+    /// ID.
+    Id(Uuid),
+
+    /// Optional ID.
+    OptionalId(Option<Uuid>),
+
+    /// Timestamp.
+    Time(DateTime<Utc>),
+
+    /// Integer.
+    Int(i32),
+
+    /// Text.
+    Text(Option<String>),
+
+    /// JSON value.
+    Json(Value),
+}
+
+/// Persistent job queue.
+#[derive(Clone)]
+pub struct JobQueue {
+    /// Database connection.
+    db: Database,
+
+    /// Queue configuration.
+    config: JobsConfig,
+
+    /// Plugin manager, for running plugin jobs.
+    plugins: Arc<PluginManager>,
+
+    /// Handlers of core job kinds.
+    handlers: Arc<RwLock<HashMap<String, Arc<dyn JobHandler>>>>,
+
+    /// Wakes idle workers when a job is enqueued.
+    notify: Arc<Notify>,
+
+    /// Jobs enqueued by plugins, until the queue is started.
+    plugin_jobs: Arc<parking_lot::Mutex<Option<mpsc::UnboundedReceiver<PluginJob>>>>,
+}
+
+impl JobQueue {
+    /// Create a job queue and route jobs enqueued by plugins to it.
+    #[must_use]
+    pub fn new(db: Database, config: JobsConfig, plugins: Arc<PluginManager>) -> Self {
+        let (sink, plugin_jobs) = mpsc::unbounded_channel();
+        plugins.runtime().set_job_sink(sink);
+
+        let queue = Self {
+            db,
+            config,
+            plugins,
+            handlers: Arc::new(RwLock::new(HashMap::new())),
+            notify: Arc::new(Notify::new()),
+            plugin_jobs: Arc::new(parking_lot::Mutex::new(Some(plugin_jobs))),
+        };
+        queue.register(PLUGIN_INSTALL_JOB, Arc::new(PluginInstallHandler(Arc::clone(&queue.plugins))));
+        queue
+    }
+
+    /// Register the handler of a job kind, replacing any previous one.
+    pub fn register(&self, kind: &str, handler: Arc<dyn JobHandler>) {
+        self.handlers.write().insert(kind.to_owned(), handler);
+    }
+
+    /// Start the workers and the intake of plugin jobs.
+    ///
+    /// Workers stop taking jobs when the workers phase of the shutdown
+    /// starts, and the shutdown waits for running jobs. Does nothing after
+    /// the first call.
+    pub fn start(&self, shutdown: &shutdown::Coordinator) {
+        let Some(mut plugin_jobs) = self.plugin_jobs.lock().take() else {
+            return;
+        };
+
+        let stop = shutdown.token(shutdown::Phase::Workers);
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let job = tokio::select! {
+                    () = stop.cancelled() => break,
+                    job = plugin_jobs.recv() => job,
+                };
+                let Some(job) = job else { break };
+                let (id, plugin) = (job.id, job.plugin.clone());
+                if let Err(e) = queue.insert(id, job.into()).await {
+                    tracing::error!("Failed to enqueue job of plugin {}: {}", plugin, e);
+                }
+            }
+        });
+
+        let workers: Vec<_> = (0..self.config.workers)
+            .map(|worker| {
+                let queue = self.clone();
+                let stop = shutdown.token(shutdown::Phase::Workers);
+                tokio::spawn(async move {
+                    while !stop.is_cancelled() {
+                        queue.work(worker, &stop).await;
+                    }
+                })
+            })
+            .collect();
+
+        // Running jobs finish; jobs still running at the timeout are retried
+        // once their claim expires
+        shutdown.register("jobs", shutdown::Phase::Workers, JOB_DRAIN_TIMEOUT, move || async move {
+            for worker in workers {
+                if let Err(e) = worker.await {
+                    tracing::error!("Job worker failed: {}", e);
+                }
+            }
+        });
+
+        tracing::info!("Started {} job workers", self.config.workers);
+    }
+
+    /// Enqueue a job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job is invalid or cannot be stored.
+    pub async fn enqueue(&self, job: NewJob) -> orbis_core::Result<Job> {
+        let id = orbis_core::new_id();
+        self.insert(id, job).await?;
+        self.get(id).await
+    }
+
+    /// Get a job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job does not exist or the query fails.
+    pub async fn get(&self, id: Uuid) -> orbis_core::Result<Job> {
+        self.fetch(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS), vec![Param::Id(id)])
+            .await?
+            .pop()
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Job '{}' not found", id)))
+    }
+
+    /// List the most recent jobs, optionally filtered by status and queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn list(
+        &self,
+        status: Option<JobStatus>,
+        queue: Option<&str>,
+        limit: i64,
+    ) -> orbis_core::Result<Vec<Job>> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(status) = status {
+            params.push(Param::Text(Some(status.as_str().to_owned())));
+            conditions.push(format!("status = ${}", params.len()));
+        }
+        if let Some(queue) = queue {
+            params.push(Param::Text(Some(queue.to_owned())));
+            conditions.push(format!("queue = ${}", params.len()));
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let limit = limit.clamp(1, MAX_LIST_LIMIT);
+
+        self.fetch(
+            &format!("SELECT {} FROM jobs {} ORDER BY created_at DESC LIMIT {}", JOB_COLUMNS, filter, limit),
+            params,
+        )
+        .await
+    }
+
+    /// Count jobs by status.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the jobs cannot be counted.
+    pub async fn counts(&self) -> orbis_core::Result<HashMap<String, i64>> {
+        let sql = "SELECT status, COUNT(*) FROM jobs GROUP BY status";
+        let rows: Vec<(String, i64)> = match self.db.pool() {
+            DatabasePool::Postgres(pool) => sqlx::query_as(sql).fetch_all(pool).await,
+            DatabasePool::Sqlite(pool) => sqlx::query_as(sql).fetch_all(pool).await,
+        }
+        .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Make a pending or dead job run again as soon as possible.
+    ///
+    /// Dead jobs get a fresh set of attempts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job does not exist, is running or has completed.
+    pub async fn retry(&self, id: Uuid) -> orbis_core::Result<Job> {
+        let now = Utc::now();
+        let updated = self
+            .execute(
+                "UPDATE jobs SET status = $1, run_at = $2, updated_at = $2, locked_until = NULL, \
+                 attempts = CASE WHEN status = $3 THEN 0 ELSE attempts END \
+                 WHERE id = $4 AND status IN ($1, $3)",
+                vec![
+                    Param::Text(Some(JobStatus::Pending.as_str().to_owned())),
+                    Param::Time(now),
+                    Param::Text(Some(JobStatus::Dead.as_str().to_owned())),
+                    Param::Id(id),
+                ],
+            )
+            .await?;
+
+        if updated == 0 {
+            let job = self.get(id).await?;
+            return Err(orbis_core::Error::conflict(format!(
+                "Job '{}' is {} and cannot be retried",
+                id,
+                job.status.as_str()
+            )));
+        }
+
+        tracing::info!("Job {} scheduled for retry", id);
+        self.notify.notify_one();
+        self.get(id).await
+    }
+
+    /// Store a new job.
+    async fn insert(&self, id: Uuid, job: NewJob) -> orbis_core::Result<()> {
+        if job.kind.is_empty() {
+            return Err(orbis_core::Error::validation("Job kind cannot be empty"));
+        }
+        if job.queue.is_empty() {
+            return Err(orbis_core::Error::validation("Job queue cannot be empty"));
+        }
+        if job.plugin_name.is_none() && !self.handlers.read().contains_key(&job.kind) {
+            return Err(orbis_core::Error::validation(format!("Unknown job kind '{}'", job.kind)));
+        }
+
+        let now = Utc::now();
+        let delay = chrono::Duration::from_std(job.delay)
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid job delay: {}", e)))?;
+        let max_attempts = job.max_attempts.unwrap_or(self.config.max_attempts).max(1);
+
+        self.execute(
+            "INSERT INTO jobs (id, queue, kind, plugin_name, tenant_id, payload, status, attempts, max_attempts, \
+             run_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, 0, $8, $9, $10, $10)",
+            vec![
+                Param::Id(id),
+                Param::Text(Some(job.queue.clone())),
+                Param::Text(Some(job.kind.clone())),
+                Param::Text(job.plugin_name.clone()),
+                Param::OptionalId(job.tenant_id),
+                Param::Json(job.payload),
+                Param::Text(Some(JobStatus::Pending.as_str().to_owned())),
+                Param::Int(i32::try_from(max_attempts).unwrap_or(i32::MAX)),
+                Param::Time(now.checked_add_signed(delay).unwrap_or(now)),
+                Param::Time(now),
+            ],
+        )
+        .await?;
+
+        tracing::debug!("Enqueued job {} ({}) on queue {}", id, job.kind, job.queue);
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Run the next due job, or wait for one.
+    async fn work(&self, worker: usize, stop: &CancellationToken) {
+        match self.claim().await {
+            Ok(Some(job)) => self.run(job).await,
+            Ok(None) => {
+                // Wait for a new job, or poll again for delayed and expired ones
+                tokio::select! {
+                    () = stop.cancelled() => {}
+                    () = self.notify.notified() => {}
+                    () = tokio::time::sleep(POLL_INTERVAL) => {
+                        tracing::trace!("Job worker {} polling", worker);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Job worker {} failed to claim a job: {}", worker, e);
+                tokio::select! {
+                    () = stop.cancelled() => {}
+                    () = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        }
+    }
+
+    /// Claim the next due job, hiding it from other workers for the visibility timeout.
+    async fn claim(&self) -> orbis_core::Result<Option<Job>> {
+        let now = Utc::now();
+        let timeout = i64::try_from(self.config.visibility_timeout_seconds).unwrap_or(i64::MAX);
+        let locked_until = now
+            .checked_add_signed(chrono::Duration::seconds(timeout))
+            .unwrap_or(now);
+
+        // Jobs whose claim expired on their last attempt are dead-lettered
+        self.execute(
+            "UPDATE jobs SET status = $1, last_error = $2, locked_until = NULL, updated_at = $3 \
+             WHERE status = $4 AND locked_until <= $3 AND attempts >= max_attempts",
+            vec![
+                Param::Text(Some(JobStatus::Dead.as_str().to_owned())),
+                Param::Text(Some("Visibility timeout expired".to_owned())),
+                Param::Time(now),
+                Param::Text(Some(JobStatus::Running.as_str().to_owned())),
+            ],
+        )
+        .await?;
+
+        let candidates = self
+            .fetch(
+                &format!(
+                    "SELECT {} FROM jobs WHERE (status = $1 AND run_at <= $2) OR (status = $3 AND locked_until <= $2) \
+                     ORDER BY run_at LIMIT 8",
+                    JOB_COLUMNS
+                ),
+                vec![
+                    Param::Text(Some(JobStatus::Pending.as_str().to_owned())),
+                    Param::Time(now),
+                    Param::Text(Some(JobStatus::Running.as_str().to_owned())),
+                ],
+            )
+            .await?;
+
+        for mut job in candidates {
+            // Only one worker wins: the update fails once another worker bumped the attempts
+            let claimed = self
+                .execute(
+                    "UPDATE jobs SET status = $1, attempts = attempts + 1, locked_until = $2, updated_at = $3 \
+                     WHERE id = $4 AND attempts = $5",
+                    vec![
+                        Param::Text(Some(JobStatus::Running.as_str().to_owned())),
+                        Param::Time(locked_until),
+                        Param::Time(now),
+                        Param::Id(job.id),
+                        Param::Int(i32::try_from(job.attempts).unwrap_or(i32::MAX)),
+                    ],
+                )
+                .await?;
+
+            if claimed > 0 {
+                job.status = JobStatus::Running;
+                job.attempts = job.attempts.saturating_add(1);
+                job.locked_until = Some(locked_until);
+                return Ok(Some(job));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Run a claimed job and record the outcome.
+    async fn run(&self, job: Job) {
+        let timeout = Duration::from_secs(self.config.visibility_timeout_seconds);
+        let result = tokio::time::timeout(timeout, self.dispatch(&job))
+            .await
+            .unwrap_or_else(|_| Err(orbis_core::Error::internal("Job timed out")));
+
+        if let Err(e) = self.record(&job, result).await {
+            tracing::error!("Failed to record outcome of job {}: {}", job.id, e);
+        }
+    }
+
+    /// Record the outcome of a job run.
+    async fn record(&self, job: &Job, result: orbis_core::Result<()>) -> orbis_core::Result<()> {
+        match result {
+            Ok(()) => {
+                tracing::debug!("Job {} ({}) completed", job.id, job.kind);
+                self.complete(job).await
+            }
+            Err(e) => {
+                tracing::warn!("Job {} ({}) failed on attempt {}: {}", job.id, job.kind, job.attempts, e);
+                self.fail(job, &e.to_string()).await
+            }
+        }
+    }
+
+    /// Run a job with its handler.
+    async fn dispatch(&self, job: &Job) -> orbis_core::Result<()> {
+        let Some(plugin) = &job.plugin_name else {
+            let handler = self.handlers.read().get(&job.kind).cloned();
+            return match handler {
+                Some(handler) => handler.run(job).await,
+                None => Err(orbis_core::Error::internal(format!("No handler for job kind '{}'", job.kind))),
+            };
+        };
+
+        let deadline = Duration::from_secs(self.config.visibility_timeout_seconds);
+        let context = PluginContext {
+            method: "POST".to_owned(),
+            path: format!("/jobs/{}", job.kind),
+            headers: HashMap::from([
+                ("x-orbis-job-id".to_owned(), job.id.to_string()),
+                ("x-orbis-job-attempt".to_owned(), job.attempts.to_string()),
+            ]),
+            query: HashMap::new(),
+            body: job.payload.clone(),
+            user_id: None,
+            is_admin: false,
+            tenant_id: job.tenant_id.map(|tenant| tenant.to_string()),
+            deadline: chrono::Duration::from_std(deadline)
+                .ok()
+                .and_then(|deadline| Utc::now().checked_add_signed(deadline)),
+            groups: Vec::new(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: orbis_plugin::CancellationFlag::new(),
+        };
+
+        self.plugins.execute_route(plugin, &job.kind, context).await.map(|_| ())
+    }
+
+    /// Mark a job completed.
+    async fn complete(&self, job: &Job) -> orbis_core::Result<()> {
+        let now = Utc::now();
+        self.execute(
+            "UPDATE jobs SET status = $1, completed_at = $2, updated_at = $2, locked_until = NULL, last_error = NULL \
+             WHERE id = $3 AND attempts = $4",
+            vec![
+                Param::Text(Some(JobStatus::Completed.as_str().to_owned())),
+                Param::Time(now),
+                Param::Id(job.id),
+                Param::Int(i32::try_from(job.attempts).unwrap_or(i32::MAX)),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Schedule a retry of a failed job, or dead-letter it when out of attempts.
+    async fn fail(&self, job: &Job, error: &str) -> orbis_core::Result<()> {
+        let now = Utc::now();
+        let (status, run_at) = if job.attempts >= job.max_attempts {
+            tracing::error!("Job {} ({}) is out of attempts and was dead-lettered", job.id, job.kind);
+            (JobStatus::Dead, job.run_at)
+        } else {
+            let backoff = 1i64.checked_shl(job.attempts).unwrap_or(i64::MAX).min(MAX_BACKOFF_SECONDS);
+            let run_at = now
+                .checked_add_signed(chrono::Duration::seconds(backoff))
+                .unwrap_or(now);
+            (JobStatus::Pending, run_at)
+        };
+
+        self.execute(
+            "UPDATE jobs SET status = $1, run_at = $2, last_error = $3, locked_until = NULL, updated_at = $4 \
+             WHERE id = $5 AND attempts = $6",
+            vec![
+                Param::Text(Some(status.as_str().to_owned())),
+                Param::Time(run_at),
+                Param::Text(Some(error.to_owned())),
+                Param::Time(now),
+                Param::Id(job.id),
+                Param::Int(i32::try_from(job.attempts).unwrap_or(i32::MAX)),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Execute a statement, returning the number of affected rows.
+    async fn execute(&self, sql: &str, params: Vec<Param>) -> orbis_core::Result<u64> {
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Id(id) => query.bind(id),
+                        Param::OptionalId(id) => query.bind(id),
+                        Param::Time(time) => query.bind(time),
+                        Param::Int(value) => query.bind(value),
+                        Param::Text(text) => query.bind(text),
+                        Param::Json(value) => query.bind(value),
+                    };
+                }
+                query
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Id(id) => query.bind(id.to_string()),
+                        Param::OptionalId(id) => query.bind(id.map(|id| id.to_string())),
+                        Param::Time(time) => query.bind(sqlite_time(time)),
+                        Param::Int(value) => query.bind(value),
+                        Param::Text(text) => query.bind(text),
+                        Param::Json(value) => query.bind(value.to_string()),
+                    };
+                }
+                query
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))
+            }
+        }
+    }
+
+    /// Fetch jobs.
+    async fn fetch(&self, sql: &str, params: Vec<Param>) -> orbis_core::Result<Vec<Job>> {
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Id(id) => query.bind(id),
+                        Param::OptionalId(id) => query.bind(id),
+                        Param::Time(time) => query.bind(time),
+                        Param::Int(value) => query.bind(value),
+                        Param::Text(text) => query.bind(text),
+                        Param::Json(value) => query.bind(value),
+                    };
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(job_from_pg_row).collect()
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Id(id) => query.bind(id.to_string()),
+                        Param::OptionalId(id) => query.bind(id.map(|id| id.to_string())),
+                        Param::Time(time) => query.bind(sqlite_time(time)),
+                        Param::Int(value) => query.bind(value),
+                        Param::Text(text) => query.bind(text),
+                        Param::Json(value) => query.bind(value.to_string()),
+                    };
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter().map(job_from_sqlite_row).collect()
+            }
+        }
+    }
+}
+
+/// Installs a plugin from the `path` in the job payload.
+struct PluginInstallHandler(Arc<PluginManager>);
+
+#[async_trait]
+impl JobHandler for PluginInstallHandler {
+    async fn run(&self, job: &Job) -> orbis_core::Result<()> {
+        let info = if let Some(remote) = job.payload.get("remote") {
+            let remote: orbis_plugin::RemoteSource = serde_json::from_value(remote.clone())?;
+            let info = self.0.load_remote(&remote).await?;
+            tracing::info!("Installed plugin {} from {}", info.manifest.name, remote.url);
+            info
+        } else {
+            let path = job
+                .payload
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| orbis_core::Error::validation("Plugin install job requires a 'path' or 'remote'"))?;
+
+            let info = self.0.load_plugin(&std::path::PathBuf::from(path)).await?;
+            tracing::info!("Installed plugin {} from {}", info.manifest.name, path);
+            info
+        };
+
+        let event = orbis_plugin::HookEvent::PluginInstalled {
+            plugin: info.manifest.name,
+            version: info.manifest.version,
+        };
+        self.0.run_hooks(&event, None).await;
+        Ok(())
+    }
+}
+
+/// Format a timestamp for SQLite so stored values compare in time order.
+pub fn sqlite_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Parse a timestamp stored by SQLite.
+pub fn parse_sqlite_time(time: &str) -> orbis_core::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| orbis_core::Error::database(format!("Invalid timestamp '{}': {}", time, e)))
+}
+
+/// Convert a database error.
+fn column_error(e: sqlx::Error) -> orbis_core::Error {
+    orbis_core::Error::database(e.to_string())
+}
+
+/// Read a job from a PostgreSQL row.
+fn job_from_pg_row(row: &sqlx::postgres::PgRow) -> orbis_core::Result<Job> {
+    Ok(Job {
+        id: row.try_get("id").map_err(column_error)?,
+        queue: row.try_get("queue").map_err(column_error)?,
+        kind: row.try_get("kind").map_err(column_error)?,
+        plugin_name: row.try_get("plugin_name").map_err(column_error)?,
+        tenant_id: row.try_get("tenant_id").map_err(column_error)?,
+        payload: row.try_get("payload").map_err(column_error)?,
+        status: row.try_get::<String, _>("status").map_err(column_error)?.parse()?,
+        attempts: u32::try_from(row.try_get::<i32, _>("attempts").map_err(column_error)?).unwrap_or_default(),
+        max_attempts: u32::try_from(row.try_get::<i32, _>("max_attempts").map_err(column_error)?)
+            .unwrap_or_default(),
+        run_at: row.try_get("run_at").map_err(column_error)?,
+        locked_until: row.try_get("locked_until").map_err(column_error)?,
+        last_error: row.try_get("last_error").map_err(column_error)?,
+        created_at: row.try_get("created_at").map_err(column_error)?,
+        updated_at: row.try_get("updated_at").map_err(column_error)?,
+        completed_at: row.try_get("completed_at").map_err(column_error)?,
+    })
+}
+
+/// Read a job from a SQLite row.
+fn job_from_sqlite_row(row: &sqlx::sqlite::SqliteRow) -> orbis_core::Result<Job> {
+    let text = |column: &str| row.try_get::<String, _>(column).map_err(column_error);
+    let optional_text = |column: &str| row.try_get::<Option<String>, _>(column).map_err(column_error);
+    let id = |value: String| {
+        Uuid::parse_str(&value).map_err(|e| orbis_core::Error::database(format!("Invalid job ID: {}", e)))
+    };
+
+    Ok(Job {
+        id: id(text("id")?)?,
+        queue: text("queue")?,
+        kind: text("kind")?,
+        plugin_name: optional_text("plugin_name")?,
+        tenant_id: optional_text("tenant_id")?.map(id).transpose()?,
+        payload: serde_json::from_str(&text("payload")?)
+            .map_err(|e| orbis_core::Error::database(format!("Invalid job payload: {}", e)))?,
+        status: text("status")?.parse()?,
+        attempts: u32::try_from(row.try_get::<i32, _>("attempts").map_err(column_error)?).unwrap_or_default(),
+        max_attempts: u32::try_from(row.try_get::<i32, _>("max_attempts").map_err(column_error)?)
+            .unwrap_or_default(),
+        run_at: parse_sqlite_time(&text("run_at")?)?,
+        locked_until: optional_text("locked_until")?
+            .as_deref()
+            .map(parse_sqlite_time)
+            .transpose()?,
+        last_error: optional_text("last_error")?,
+        created_at: parse_sqlite_time(&text("created_at")?)?,
+        updated_at: parse_sqlite_time(&text("updated_at")?)?,
+        completed_at: optional_text("completed_at")?
+            .as_deref()
+            .map(parse_sqlite_time)
+            .transpose()?,
+    })
+}
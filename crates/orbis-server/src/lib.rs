@@ -3,28 +3,78 @@
 //! Axum-based HTTP/HTTPS server for Orbis supporting authentication,
 //! plugin routes, and the REST API.
 
+mod account;
+mod admin;
+mod analyze;
 mod app;
+mod compression;
+mod csrf;
+mod dev;
+mod doctor;
+mod email;
 mod error;
+mod events;
 mod extractors;
+mod jobs;
+mod limits;
 mod middleware;
+mod monitoring;
 mod routes;
+mod settings;
 mod state;
+mod storage;
 mod tls;
+mod versioning;
+mod vhost;
+mod webhook;
 
+pub use account::{AccountDeletion, ACCOUNT_DELETION_JOB};
+pub use admin::run_command;
 pub use app::{create_app, OrbisApp};
+pub use compression::{CompressionCounts, CompressionStats};
+pub use email::{
+    render_template, EmailMessage, EmailService, EmailTransport, LogTransport, SmtpTransport, EMAIL_CHANGE_TEMPLATE,
+    EMAIL_JOB, PASSWORD_RESET_TEMPLATE, TWO_FACTOR_ENROLLMENT_TEMPLATE,
+};
 pub use error::ServerError;
-pub use extractors::AuthenticatedUser;
+pub use events::{EventHub, HubEvent, PLUGIN_EVENT};
+pub use extractors::{Admin, AuthError, AuthUser, ClientIdentity, OptionalAuthUser, RequireRole, Role};
+pub use jobs::{Job, JobHandler, JobQueue, JobStatus, NewJob, DEFAULT_QUEUE, PLUGIN_INSTALL_JOB};
+pub use limits::{LimitCounts, LimitStats};
+pub use monitoring::{AlertPolicy, MetricResolution, ResourceMonitorService, METRICS_SAMPLE_INTERVAL};
+pub use settings::{SettingChange, SettingsService};
 pub use state::AppState;
+pub use storage::FileStorage;
+pub use tls::ClientCertificate;
+pub use versioning::{deprecated, ApiVersion, Deprecation};
+pub use vhost::VirtualHosts;
+pub use webhook::{post_json, WebhookDelivery, WEBHOOK_JOB};
 
 use orbis_auth::AuthService;
 use orbis_config::Config;
+use orbis_core::{shutdown, CancellationToken, Localizer, DEFAULT_SHUTDOWN_TIMEOUT};
 use orbis_db::Database;
-use orbis_plugin::PluginManager;
+use orbis_plugin::{
+    CompatibilityPolicy, HandlerThresholds, HookEvent, Keyring, PluginManager, AccessPolicy, RegistryClient,
+    ReplayCapture, TrapReport, UrlSigner, DEFAULT_MODULE_CACHE_SIZE,
+};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
 use tower::Service;
 
+use limits::TimeoutStream;
+
+/// How often idle lazily activated plugins are checked for unloading.
+const PLUGIN_IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often the configuration file is checked for logging changes.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Server instance.
 pub struct Server {
     config: Arc<Config>,
@@ -32,60 +82,90 @@ pub struct Server {
 }
 
 impl Server {
-    /// Create a new server instance.
+    /// Create a new server instance, and start its background work (jobs,
+    /// email delivery, hot reload, ...) and shutdown registrations.
+    ///
+    /// The background work runs whether or not the server is then served, so
+    /// embedders that cannot bind still process jobs and shut down cleanly.
     ///
     /// # Errors
     ///
     /// Returns an error if initialization fails.
     pub async fn new(config: Config) -> orbis_core::Result<Self> {
         let config = Arc::new(config);
+        let state = create_state(config.clone(), Arc::new(shutdown::Coordinator::new())).await?;
+        start_background_tasks(&state)?;
+        start_log_reload(&config, &state);
+        Ok(Self { config, state })
+    }
 
-        // Initialize database
-        let db = Database::new(config.database.clone()).await?;
+    /// Run the server until interrupted (Ctrl+C), then shut it down
+    /// gracefully.
+    ///
+    /// Profiles served on virtual hosts are initialized on their first request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server fails to start.
+    pub async fn run(self) -> orbis_core::Result<()> {
+        let listener = self.bind().await?;
+        let shutdown = Arc::clone(self.state.shutdown());
 
-        // Run migrations if configured
-        if config.database.run_migrations {
-            db.migrate().await?;
+        tokio::select! {
+            result = self.serve(listener) => result,
+            signal = tokio::signal::ctrl_c() => {
+                signal.map_err(|e| orbis_core::Error::server(format!("Failed to listen for Ctrl+C: {}", e)))?;
+                tracing::info!("Shutting down");
+                let report = shutdown.shutdown().await;
+                if !report.timed_out.is_empty() {
+                    tracing::warn!("Subsystems did not shut down in time: {}", report.timed_out.join(", "));
+                }
+                Ok(())
+            }
         }
+    }
 
-        // Initialize auth service
-        let auth = if config.auth_enabled || config.mode.requires_auth() {
-            Some(AuthService::new(config.clone(), db.clone())?)
-        } else {
-            None
-        };
-
-        // Initialize plugin manager
-        let plugins_dir = config
-            .plugins_dir
-            .clone()
-            .unwrap_or_else(|| std::path::PathBuf::from("./plugins"));
-        let plugins = PluginManager::new(plugins_dir, db.clone())?;
-
-        // Load plugins
-        plugins.load_all().await?;
-
-        // Create app state
-        let state = AppState::new(config.clone(), db, auth, plugins);
-
-        Ok(Self { config, state })
+    /// Bind the configured address.
+    ///
+    /// Binding separately from [`Server::serve`] lets embedders report an
+    /// address in use before handing the server to a background task, while
+    /// keeping the state of [`Server::state`] shared with the served API.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address is invalid or cannot be bound.
+    pub async fn bind(&self) -> orbis_core::Result<TcpListener> {
+        let addr = self.config.server.socket_addr()?;
+        TcpListener::bind(addr).await.map_err(|e| {
+            orbis_core::Error::server(format!("Failed to bind to {}: {}", addr, e))
+        })
     }
 
-    /// Run the server.
+    /// Serve requests on a bound listener with the server's state.
+    ///
+    /// Returns once the listeners phase of the state's shutdown coordinator
+    /// starts.
     ///
     /// # Errors
     ///
     /// Returns an error if the server fails to start.
-    pub async fn run(self) -> orbis_core::Result<()> {
-        let addr = self.config.server.socket_addr()?;
+    pub async fn serve(self, listener: TcpListener) -> orbis_core::Result<()> {
         let app = create_app(self.state.clone());
+        let app = if self.config.virtual_hosts.is_empty() {
+            app
+        } else {
+            VirtualHosts::new(&self.config, app, Arc::clone(self.state.shutdown())).into_router()
+        };
 
+        let addr = listener.local_addr().map_err(|e| {
+            orbis_core::Error::server(format!("Failed to get listening address: {}", e))
+        })?;
         tracing::info!("Starting server on {}", addr);
 
         if self.config.is_tls_enabled() {
-            self.run_https(app, addr).await
+            self.run_https(app, listener, addr).await
         } else {
-            self.run_http(app, addr).await
+            self.run_http(app, listener, addr).await
         }
     }
 
@@ -93,58 +173,77 @@ impl Server {
     async fn run_http(
         self,
         app: axum::Router,
+        listener: TcpListener,
         addr: SocketAddr,
     ) -> orbis_core::Result<()> {
-        let listener = TcpListener::bind(addr).await.map_err(|e| {
-            orbis_core::Error::server(format!("Failed to bind to {}: {}", addr, e))
-        })?;
-
         tracing::info!("HTTP server listening on http://{}", addr);
+        notify_started(&self.state);
+        forward_state_changes(&self.state);
+
+        let stop = self.state.shutdown().token(shutdown::Phase::Listeners);
+        loop {
+            let Some(accepted) = accept(&listener, &stop).await else {
+                return Ok(());
+            };
+            let (stream, peer_addr) = accepted?;
+
+            let stream = self.timeout_stream(stream);
+            tokio::spawn(serve_connection(stream, peer_addr, None, app.clone(), self.state.clone()));
+        }
+    }
 
-        axum::serve(listener, app)
-            .await
-            .map_err(|e| orbis_core::Error::server(format!("Server error: {}", e)))
+    /// Wrap an accepted connection with the configured read and write timeouts.
+    fn timeout_stream(&self, stream: TcpStream) -> TimeoutStream<TcpStream> {
+        TimeoutStream::new(
+            stream,
+            Duration::from_secs(self.config.server.read_timeout_seconds),
+            Duration::from_secs(self.config.server.write_timeout_seconds),
+        )
     }
 
     /// Run HTTPS server.
     async fn run_https(
         self,
         app: axum::Router,
+        listener: TcpListener,
         addr: SocketAddr,
     ) -> orbis_core::Result<()> {
         let tls_config = tls::create_tls_config(&self.config.tls)?;
         let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls_config));
 
-        let listener = TcpListener::bind(addr).await.map_err(|e| {
-            orbis_core::Error::server(format!("Failed to bind to {}: {}", addr, e))
-        })?;
-
         tracing::info!("HTTPS server listening on https://{}", addr);
+        notify_started(&self.state);
+        forward_state_changes(&self.state);
 
+        let stop = self.state.shutdown().token(shutdown::Phase::Listeners);
         loop {
-            let (stream, peer_addr) = listener.accept().await.map_err(|e| {
-                orbis_core::Error::server(format!("Failed to accept connection: {}", e))
-            })?;
+            let Some(accepted) = accept(&listener, &stop).await else {
+                return Ok(());
+            };
+            let (stream, peer_addr) = accepted?;
 
+            // Time out the TLS handshake as well as the requests
+            let stream = self.timeout_stream(stream);
             let acceptor = acceptor.clone();
             let app = app.clone();
+            let state = self.state.clone();
 
             tokio::spawn(async move {
                 match acceptor.accept(stream).await {
                     Ok(tls_stream) => {
-                        let io = tokio_rustls::server::TlsStream::from(tls_stream);
-                        let tower_service = app.clone();
-                        
-                        if let Err(e) = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new())
-                            .serve_connection(hyper_util::rt::TokioIo::new(io), hyper::service::service_fn(move |req| {
-                                tower_service.clone().call(req)
-                            }))
-                            .await
-                        {
-                            tracing::error!("Error serving connection from {}: {}", peer_addr, e);
-                        }
+                        // Requests carry the client certificate verified during the handshake
+                        let client = tls_stream
+                            .get_ref()
+                            .1
+                            .peer_certificates()
+                            .and_then(|certs| certs.first())
+                            .and_then(|cert| ClientCertificate::from_der(cert));
+                        serve_connection(tls_stream, peer_addr, client, app, state).await;
                     }
                     Err(e) => {
+                        if limits::is_timeout(&e) {
+                            state.limit_stats().record_connection_timeout();
+                        }
                         tracing::error!("TLS handshake failed for {}: {}", peer_addr, e);
                     }
                 }
@@ -164,3 +263,524 @@ impl Server {
         &self.config
     }
 }
+
+/// Accept the next connection, or `None` once listeners are shut down.
+async fn accept(
+    listener: &TcpListener,
+    stop: &CancellationToken,
+) -> Option<orbis_core::Result<(TcpStream, SocketAddr)>> {
+    tokio::select! {
+        () = stop.cancelled() => {
+            tracing::info!("Stopped accepting connections");
+            None
+        }
+        accepted = listener.accept() => Some(accepted.map_err(|e| {
+            orbis_core::Error::server(format!("Failed to accept connection: {}", e))
+        })),
+    }
+}
+
+/// Initialize the state of a profile: its database, auth, plugins and
+/// catalogs. Its subsystems register with the given shutdown coordinator.
+///
+/// # Errors
+///
+/// Returns an error if initialization fails.
+async fn create_state(config: Arc<Config>, shutdown: Arc<shutdown::Coordinator>) -> orbis_core::Result<AppState> {
+    // Initialize database
+    let db = Database::new(config.database.clone()).await?;
+
+    // Run migrations if configured
+    if config.database.run_migrations {
+        db.migrate().await?;
+    }
+
+    // Initialize auth service
+    let auth = if config.auth_enabled || config.mode.requires_auth() {
+        Some(AuthService::new(config.clone(), db.clone())?)
+    } else {
+        None
+    };
+
+    // Initialize plugin manager
+    let plugins = Arc::new(create_plugin_manager(&config, db.clone())?);
+
+    // Tenant config overrides apply to plugins as they load
+    if config.tenancy.mode.is_enabled()
+        && let Some(auth) = &auth
+    {
+        for (tenant_id, plugin, overrides) in auth.tenant().plugin_configs().await? {
+            plugins.runtime().set_tenant_config(&plugin, &tenant_id.to_string(), overrides);
+        }
+    }
+
+    // Feature flag overrides apply to plugins as they load
+    plugins.load_feature_overrides().await?;
+
+    // Load plugins
+    plugins.load_all().await?;
+
+    // Load message catalogs for localized responses
+    let localizer = config.i18n.locales_dir.as_deref().map_or_else(
+        || Ok(Localizer::new(&config.i18n.default_locale)),
+        |dir| Localizer::load(&config.i18n.default_locale, dir),
+    )?;
+
+    // Create app state
+    let state = AppState::new(config.clone(), db, auth, plugins, localizer).with_shutdown(shutdown);
+
+    // Alert on plugins using too much memory or failing too often
+    if let Some(path) = &config.plugin_alerts_file {
+        state.monitoring().set_policy(AlertPolicy::load(path)?)?;
+    }
+
+    Ok(state)
+}
+
+/// Start the background work of a profile: idle plugin unloading, plugin
+/// hot reload, jobs, email delivery, resource monitoring and event publishing.
+///
+/// Each task stops with its shutdown phase; plugins and the database are
+/// released after them.
+///
+/// # Errors
+///
+/// Returns an error if plugin hot reload cannot be started.
+fn start_background_tasks(state: &AppState) -> orbis_core::Result<()> {
+    let shutdown = state.shutdown();
+
+    // Unload idle lazily activated plugins in the background
+    let plugins = state.plugins_arc();
+    let stop = shutdown.token(shutdown::Phase::Workers);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PLUGIN_IDLE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                () = stop.cancelled() => break,
+                _ = interval.tick() => {
+                    plugins.unload_idle_plugins();
+                }
+            }
+        }
+    });
+
+    if state.config().plugin_hot_reload {
+        start_hot_reload(state)?;
+    }
+
+    state.jobs().start(shutdown);
+    state.email().start(shutdown);
+    state.monitoring().start(shutdown);
+    state.events().start(&state.plugins_arc(), shutdown);
+    forward_traps(state);
+
+    let profile = state.config().active_profile.clone().unwrap_or_else(|| "default".to_string());
+    let plugins = state.plugins_arc();
+    shutdown.register(
+        &format!("{}: plugins", profile),
+        shutdown::Phase::Plugins,
+        DEFAULT_SHUTDOWN_TIMEOUT,
+        move || async move { plugins.shutdown() },
+    );
+    let db = state.db().clone();
+    shutdown.register(
+        &format!("{}: database", profile),
+        shutdown::Phase::Storage,
+        DEFAULT_SHUTDOWN_TIMEOUT,
+        move || async move { db.close().await },
+    );
+    Ok(())
+}
+
+/// Watch the plugins directory and apply changes as they settle.
+///
+/// Progress and failures are reported on the plugin manager's reload
+/// event channel.
+fn start_hot_reload(state: &AppState) -> orbis_core::Result<()> {
+    let plugins = state.plugins_arc();
+    let mut watcher = plugins.create_watcher();
+    let mut changes = watcher.start()?;
+
+    let stop = state.shutdown().token(shutdown::Phase::Watchers);
+    tokio::spawn(async move {
+        // Keep the watcher alive for as long as changes are processed
+        let _watcher = watcher;
+        loop {
+            let change = tokio::select! {
+                () = stop.cancelled() => break,
+                change = changes.recv() => change,
+            };
+            let Some(change) = change else { break };
+            if let Err(e) = plugins.apply_change(&change).await {
+                tracing::error!("Failed to hot reload {:?}: {}", change.path, e);
+            }
+        }
+    });
+
+    tracing::info!("Plugin hot reload enabled");
+    Ok(())
+}
+
+/// Watch the configuration file and apply changes to the logging settings.
+///
+/// The log file, its rotation and retention, and the subsystem levels are
+/// applied live; other settings still need a restart.
+fn start_log_reload(config: &Config, state: &AppState) {
+    let Some(path) = config.config_file.clone() else {
+        return;
+    };
+    let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let mut current = config.log.clone();
+    let mut last = modified(&path);
+    let stop = state.shutdown().token(shutdown::Phase::Watchers);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                () = stop.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            let seen = modified(&path);
+            if seen == last {
+                continue;
+            }
+            last = seen;
+
+            match current.with_file_settings(&path).and_then(|log| log.reload().map(|_| log)) {
+                Ok(log) => {
+                    tracing::info!("Reloaded logging settings from {:?}", path);
+                    current = log;
+                }
+                Err(e) => tracing::error!("Failed to reload logging settings from {:?}: {}", path, e),
+            }
+        }
+    });
+}
+
+/// Run the `server_started` hooks of plugins in the background.
+fn notify_started(state: &AppState) {
+    let plugins = state.plugins_arc();
+    tokio::spawn(async move {
+        let event = HookEvent::ServerStarted {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        plugins.run_hooks(&event, None).await;
+    });
+}
+
+/// Run the `plugin_state_changed` hooks of plugins for every plugin state
+/// change, in the background.
+fn forward_state_changes(state: &AppState) {
+    let plugins = state.plugins_arc();
+    let mut changes = plugins.registry().history().subscribe();
+    let stop = state.shutdown().token(shutdown::Phase::Plugins);
+    tokio::spawn(async move {
+        loop {
+            let change = tokio::select! {
+                () = stop.cancelled() => break,
+                change = changes.recv() => change,
+            };
+            match change {
+                Ok(transition) => {
+                    plugins.run_hooks(&transition.hook_event(), transition.cause.actor.clone()).await;
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Plugin state hooks lagged, skipped {} changes", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Record plugin traps in the audit log, in the background, until the
+/// workers phase of the shutdown.
+///
+/// Does nothing without authentication, which owns the audit log.
+fn forward_traps(state: &AppState) {
+    let Some(audit) = state.auth().map(|auth| auth.audit().clone()) else {
+        return;
+    };
+    let (sink, mut traps) = tokio::sync::mpsc::unbounded_channel();
+    state.plugins().set_trap_sink(sink);
+
+    let stop = state.shutdown().token(shutdown::Phase::Workers);
+    tokio::spawn(async move {
+        loop {
+            let report = tokio::select! {
+                () = stop.cancelled() => break,
+                report = traps.recv() => report,
+            };
+            let Some(report) = report else { break };
+            if let Err(e) = audit.record(trap_audit_entry(&report)).await {
+                tracing::error!("Failed to audit trap of plugin '{}': {}", report.plugin, e);
+            }
+        }
+    });
+}
+
+/// Build the audit log entry of a plugin trap.
+fn trap_audit_entry(report: &TrapReport) -> orbis_auth::NewAuditEntry {
+    orbis_auth::NewAuditEntry {
+        user_id: report.request.user_id.as_deref().and_then(|id| id.parse().ok()),
+        tenant_id: report.request.tenant_id.as_deref().and_then(|id| id.parse().ok()),
+        action: "plugin.trapped".to_owned(),
+        resource_type: Some("plugin".to_owned()),
+        details: serde_json::json!({
+            "plugin": report.plugin,
+            "handler": report.handler,
+            "message": report.message,
+            "trap_code": report.trap_code,
+            "method": report.request.method,
+            "path": report.request.path,
+            "backtrace": report.backtrace(),
+        }),
+        ..orbis_auth::NewAuditEntry::default()
+    }
+}
+
+/// Get the plugins directory.
+fn plugins_dir(config: &Config) -> PathBuf {
+    config
+        .plugins_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("./plugins"))
+}
+
+/// Create the plugin manager with the configured compatibility and signature policies and caches.
+fn create_plugin_manager(config: &Config, db: Database) -> orbis_core::Result<PluginManager> {
+    // Plugin queries share the database's query cache, if enabled
+    let query_cache = db.query_cache().cloned();
+    let plugins = PluginManager::new(plugins_dir(config), db)?;
+    if let Some(cache) = query_cache {
+        plugins.runtime().set_query_cache(cache);
+    }
+
+    // Keep plugin files in the configured object storage
+    let signer = UrlSigner::new(config.jwt_secret.as_deref());
+    let store = orbis_plugin::open_storage(&config.storage, &plugins_dir(config).join(".plugin_data"), Some(&signer))?;
+    plugins.runtime().set_object_store(store);
+
+    // Encrypted profiles also seal the state plugins persist
+    if let Some(key) = &config.database.encryption_key {
+        plugins.runtime().set_state_key(key.clone());
+    }
+    if config.allow_incompatible_plugins {
+        plugins.set_compatibility_policy(CompatibilityPolicy::Warn);
+    }
+
+    // Capture handled requests so developers can replay them
+    if config.plugin_dev_mode {
+        tracing::warn!("Plugin development mode is enabled; do not use it in production");
+        plugins.capture().set_enabled(true);
+    }
+
+    // Check standalone plugins are signed by a trusted key
+    plugins.set_signature_policy(config.plugin_signatures.parse()?);
+    if let Some(dir) = &config.plugin_trusted_keys_dir {
+        plugins.set_keyring(Keyring::load(dir)?);
+    }
+
+    // Apply the configured plugin policy and its per-plugin overrides
+    plugins.set_plugin_policy(config.plugins.clone());
+
+    // Install plugins by name from the configured registry
+    if let Some(url) = config.plugins.registry.as_deref() {
+        plugins.set_registry_client(RegistryClient::new(url)?);
+    }
+
+    // Check host calls against the administrator's security policy
+    if let Some(path) = &config.plugin_policy_file {
+        plugins.runtime().policy().set_policy(load_security_policy(path)?);
+    }
+
+    // Let admins record plugin handlers for replay
+    if let Some(dir) = &config.plugin_replay_dir {
+        plugins.runtime().set_replay_capture(ReplayCapture::new(dir.clone()));
+    }
+
+    // Flag slow and failing handlers
+    plugins.runtime().handler_stats().set_thresholds(HandlerThresholds {
+        slow_ms: config.plugin_slow_handler_ms,
+        error_rate: config.plugin_handler_error_rate,
+    });
+
+    // Cache precompiled plugin modules to speed up startup
+    if let Some(data_dir) = &config.data_dir {
+        plugins.set_module_cache_dir(data_dir.join("cache"), DEFAULT_MODULE_CACHE_SIZE);
+
+        if config.response_cache_on_disk {
+            plugins.set_response_cache_dir(data_dir.join("cache").join("responses"));
+        }
+    }
+    Ok(plugins)
+}
+
+/// Load the plugin security policy from a JSON file.
+fn load_security_policy(path: &std::path::Path) -> orbis_core::Result<AccessPolicy> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        orbis_core::Error::config(format!("Failed to read plugin policy file {}: {}", path.display(), e))
+    })?;
+    let policy: AccessPolicy = serde_json::from_str(&content).map_err(|e| {
+        orbis_core::Error::config(format!("Invalid plugin policy file {}: {}", path.display(), e))
+    })?;
+
+    tracing::info!(
+        "Loaded plugin security policy from {} ({} rules, {:?} mode)",
+        path.display(),
+        policy.rules.len(),
+        policy.mode
+    );
+    Ok(policy)
+}
+
+/// Serve HTTP on a connection, closing it when the client sends headers too slowly.
+///
+/// Requests are given the client certificate of the connection, if any.
+async fn serve_connection<S>(
+    stream: S,
+    peer_addr: SocketAddr,
+    client: Option<ClientCertificate>,
+    app: axum::Router,
+    state: AppState,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let header_timeout = Duration::from_secs(state.config().server.header_read_timeout_seconds);
+    let mut builder = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new());
+    builder
+        .http1()
+        .timer(hyper_util::rt::TokioTimer::new())
+        .header_read_timeout(header_timeout);
+
+    let service = hyper::service::service_fn(move |mut req: hyper::Request<hyper::body::Incoming>| {
+        if let Some(client) = &client {
+            req.extensions_mut().insert(client.clone());
+        }
+        app.clone().call(req)
+    });
+    if let Err(e) = builder
+        .serve_connection_with_upgrades(hyper_util::rt::TokioIo::new(stream), service)
+        .await
+    {
+        if limits::is_timeout(&*e) {
+            state.limit_stats().record_connection_timeout();
+            tracing::debug!("Closed slow connection from {}: {}", peer_addr, e);
+        } else {
+            tracing::error!("Error serving connection from {}: {}", peer_addr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orbis_auth::{AuditFilter, AuditService};
+    use orbis_plugin::{CancellationFlag, PluginContext};
+    use std::collections::{BTreeMap, HashMap};
+
+    /// Plugin whose `crash` handler traps.
+    const CRASHING_PLUGIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (func (export "allocate") (param i32) (result i32) (i32.const 1024))
+        (func (export "crash") (param i32 i32) (result i32) unreachable))"#;
+
+    #[tokio::test]
+    async fn test_trapping_handler_is_audited() {
+        let dir = std::env::temp_dir().join(format!("orbis-trap-audit-{}", uuid::Uuid::new_v4()));
+        let plugin_dir = dir.join("plugins/crasher");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join("plugin.wasm"), wat::parse_str(CRASHING_PLUGIN).unwrap()).unwrap();
+        std::fs::write(
+            plugin_dir.join("manifest.json"),
+            serde_json::json!({
+                "name": "crasher",
+                "version": "1.0.0",
+                "routes": [{ "method": "POST", "path": "/crash", "handler": "crash" }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let db = Database::new(orbis_config::DatabaseConfig {
+            path: Some(dir.join("orbis.db")),
+            ..orbis_config::DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        db.migrate().await.unwrap();
+        let audit = AuditService::new(db.clone());
+
+        let user_id = orbis_core::UserId::generate();
+        if let orbis_db::DatabasePool::Sqlite(pool) = db.pool() {
+            sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES ($1, 'user', 'user@test', '-')")
+                .bind(user_id.to_string())
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        let plugins = PluginManager::new(dir.join("plugins"), db).unwrap();
+        let (sink, mut traps) = tokio::sync::mpsc::unbounded_channel();
+        plugins.set_trap_sink(sink);
+        plugins.load_plugin(&plugin_dir).await.unwrap();
+
+        let context = PluginContext {
+            method: "POST".to_string(),
+            path: "/crash".to_string(),
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            body: serde_json::Value::Null,
+            user_id: Some(user_id.to_string()),
+            is_admin: false,
+            tenant_id: None,
+            deadline: None,
+            groups: Vec::new(),
+            features: BTreeMap::new(),
+            cancellation: CancellationFlag::new(),
+        };
+        plugins.execute_route("crasher", "crash", context).await.unwrap_err();
+
+        let report = traps.try_recv().unwrap();
+        audit.record(trap_audit_entry(&report)).await.unwrap();
+
+        let filter = AuditFilter {
+            action: Some("plugin.trapped".to_string()),
+            ..AuditFilter::default()
+        };
+        let entries = audit.list(None, &filter).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = entries.first().unwrap();
+        assert_eq!(entry.user_id, Some(user_id));
+        assert_eq!(entry.resource_type.as_deref(), Some("plugin"));
+        assert_eq!(entry.details["plugin"], "crasher");
+        assert_eq!(entry.details["handler"], "crash");
+        assert_eq!(entry.details["trap_code"], "wasm trap: wasm `unreachable` instruction executed");
+
+        drop(std::fs::remove_dir_all(&dir));
+    }
+
+    #[tokio::test]
+    async fn test_unserved_server_registers_shutdown() {
+        let dir = std::env::temp_dir().join(format!("orbis-unserved-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("plugins")).unwrap();
+        let config = Config {
+            database: orbis_config::DatabaseConfig {
+                path: Some(dir.join("orbis.db")),
+                ..orbis_config::DatabaseConfig::default()
+            },
+            plugins_dir: Some(dir.join("plugins")),
+            ..Config::default()
+        };
+
+        // Embedders that cannot bind never serve, but still shut the profile down
+        let server = Server::new(config).await.unwrap();
+        let report = server.state().shutdown().shutdown().await;
+        assert!(report.completed.contains(&"default: plugins".to_owned()));
+        assert!(report.completed.contains(&"default: database".to_owned()));
+        assert!(report.timed_out.is_empty());
+
+        drop(std::fs::remove_dir_all(&dir));
+    }
+}
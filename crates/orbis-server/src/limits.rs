@@ -0,0 +1,291 @@
+//! Request size limits and timeouts.
+//!
+//! Request bodies are read up front, within the body size limit and the body
+//! read timeout, so handlers never wait on a slow client. Connections are
+//! closed when the client takes too long to send headers, stops sending
+//! data, or stops reading the response. Rejections are counted in
+//! [`LimitStats`].
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use orbis_config::ServerConfig;
+use serde::Serialize;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::Sleep;
+
+use crate::error::ServerError;
+use crate::state::AppState;
+
+/// Counters of requests and connections rejected by limits.
+#[derive(Debug, Default)]
+pub struct LimitStats {
+    /// Requests rejected with 413 Payload Too Large.
+    payload_too_large: AtomicU64,
+
+    /// Requests whose body was not received in time.
+    body_timeouts: AtomicU64,
+
+    /// Requests that took longer than the request timeout.
+    request_timeouts: AtomicU64,
+
+    /// Connections closed for sending headers or data too slowly, or not
+    /// reading responses.
+    connection_timeouts: AtomicU64,
+}
+
+/// Snapshot of [`LimitStats`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LimitCounts {
+    /// Requests rejected with 413 Payload Too Large.
+    pub payload_too_large: u64,
+
+    /// Requests whose body was not received in time.
+    pub body_timeouts: u64,
+
+    /// Requests that took longer than the request timeout.
+    pub request_timeouts: u64,
+
+    /// Connections closed by header, read or write timeouts.
+    pub connection_timeouts: u64,
+}
+
+impl LimitStats {
+    /// Create zeroed counters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a connection closed by a timeout.
+    pub fn record_connection_timeout(&self) {
+        self.connection_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Read the counters.
+    #[must_use]
+    pub fn counts(&self) -> LimitCounts {
+        LimitCounts {
+            payload_too_large: self.payload_too_large.load(Ordering::Relaxed),
+            body_timeouts: self.body_timeouts.load(Ordering::Relaxed),
+            request_timeouts: self.request_timeouts.load(Ordering::Relaxed),
+            connection_timeouts: self.connection_timeouts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a rejected request by its error.
+    fn record_error(&self, error: &orbis_core::Error) {
+        if matches!(error, orbis_core::Error::PayloadTooLarge(_)) {
+            self.payload_too_large.fetch_add(1, Ordering::Relaxed);
+        } else if matches!(error, orbis_core::Error::Timeout(_)) {
+            self.body_timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a response rejected by a handler or inner layer.
+    fn record_status(&self, status: StatusCode) {
+        if status == StatusCode::PAYLOAD_TOO_LARGE {
+            self.payload_too_large.fetch_add(1, Ordering::Relaxed);
+        } else if status == StatusCode::REQUEST_TIMEOUT {
+            self.request_timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Body limit middleware function.
+///
+/// Reads the request body within the maximum body size and the body read
+/// timeout, answering 413 or 408 when the client exceeds them.
+pub async fn body_limit_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let config = &state.config().server;
+    let stats = state.limit_stats();
+
+    let request = match buffer_body(request, config).await {
+        Ok(request) => request,
+        Err(e) => {
+            stats.record_error(&e);
+            return ServerError(e).into_response();
+        }
+    };
+
+    let response = next.run(request).await;
+    stats.record_status(response.status());
+    response
+}
+
+/// Read a request body into memory within the configured limits.
+async fn buffer_body(request: Request<Body>, config: &ServerConfig) -> orbis_core::Result<Request<Body>> {
+    let limit = config.max_body_size;
+    let too_large = || {
+        orbis_core::Error::payload_too_large(format!("Request body is larger than {} bytes", limit))
+    };
+
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if length.is_some_and(|length| length > limit as u64) {
+        return Err(too_large());
+    }
+    if request.body().is_end_stream() || length == Some(0) {
+        return Ok(request);
+    }
+
+    let (parts, body) = request.into_parts();
+    let timeout = Duration::from_secs(config.body_read_timeout_seconds);
+    let bytes = tokio::time::timeout(timeout, read_body(body, limit))
+        .await
+        .map_err(|_elapsed| {
+            orbis_core::Error::timeout(format!("Request body was not received within {} seconds", timeout.as_secs()))
+        })??
+        .ok_or_else(too_large)?;
+
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+/// Read a body, returning `None` once it exceeds `limit` bytes.
+async fn read_body(mut body: Body, limit: usize) -> orbis_core::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = frame.map_err(|e| orbis_core::Error::validation(format!("Failed to read body: {}", e)))?;
+        if let Ok(data) = frame.into_data() {
+            if bytes.len().saturating_add(data.len()) > limit {
+                return Ok(None);
+            }
+            bytes.extend_from_slice(&data);
+        }
+    }
+    Ok(Some(bytes))
+}
+
+/// Stream failing reads or writes that stay blocked longer than a timeout.
+///
+/// A read timing out means the client sent nothing for that long (including
+/// between requests); a write timing out means the client stopped reading.
+pub struct TimeoutStream<S> {
+    /// Wrapped stream.
+    inner: S,
+
+    /// How long a read may stay blocked.
+    read_timeout: Duration,
+
+    /// How long a write may stay blocked.
+    write_timeout: Duration,
+
+    /// Deadline of the blocked read, if any.
+    read_deadline: Option<Pin<Box<Sleep>>>,
+
+    /// Deadline of the blocked write, if any.
+    write_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> TimeoutStream<S> {
+    /// Wrap a stream.
+    #[must_use]
+    pub const fn new(inner: S, read_timeout: Duration, write_timeout: Duration) -> Self {
+        Self {
+            inner,
+            read_timeout,
+            write_timeout,
+            read_deadline: None,
+            write_deadline: None,
+        }
+    }
+}
+
+/// Resolve a blocked operation against its deadline, starting the deadline
+/// when the operation first blocks.
+fn poll_deadline<T>(
+    result: Poll<io::Result<T>>,
+    deadline: &mut Option<Pin<Box<Sleep>>>,
+    timeout: Duration,
+    cx: &mut Context<'_>,
+    operation: &str,
+) -> Poll<io::Result<T>> {
+    if result.is_ready() {
+        *deadline = None;
+        return result;
+    }
+
+    let sleep = deadline.get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+    if sleep.as_mut().poll(cx).is_ready() {
+        *deadline = None;
+        return Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("{} timed out after {} seconds", operation, timeout.as_secs()),
+        )));
+    }
+    Poll::Pending
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TimeoutStream<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        poll_deadline(result, &mut this.read_deadline, this.read_timeout, cx, "Read")
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TimeoutStream<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_write(cx, buf);
+        poll_deadline(result, &mut this.write_deadline, this.write_timeout, cx, "Write")
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_flush(cx);
+        poll_deadline(result, &mut this.write_deadline, this.write_timeout, cx, "Write")
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_write_vectored(cx, bufs);
+        poll_deadline(result, &mut this.write_deadline, this.write_timeout, cx, "Write")
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+/// Check if a connection error was caused by a timeout.
+#[must_use]
+pub fn is_timeout(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if error.downcast_ref::<hyper::Error>().is_some_and(hyper::Error::is_timeout)
+            || error
+                .downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::TimedOut)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
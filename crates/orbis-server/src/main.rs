@@ -0,0 +1,42 @@
+//! Headless Orbis server binary.
+//!
+//! Serves the API without the desktop app (the default), or runs an
+//! administration subcommand and exits.
+
+use orbis_config::{Cli, Commands, Config, LogConfig};
+use std::io::Write;
+use std::process::ExitCode;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    // Load .env file if present
+    if let Err(e) = dotenvy::dotenv() {
+        tracing::trace!("No .env file loaded: {}", e);
+    }
+
+    let cli = Cli::parse_layered();
+    let config = match Config::from_cli(&cli) {
+        Ok(config) => config,
+        Err(e) => return fail(&e),
+    };
+    if let Err(e) = config.log.init() {
+        return fail(&e);
+    }
+
+    let command = cli.command.unwrap_or(Commands::Serve { daemon: false });
+    let result = orbis_server::run_command(config, command, &mut std::io::stdout().lock()).await;
+    LogConfig::finish();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => fail(&e),
+    }
+}
+
+/// Report an error on stderr.
+fn fail(error: &orbis_core::Error) -> ExitCode {
+    if writeln!(std::io::stderr(), "Error: {}", error).is_err() {
+        tracing::error!("{}", error);
+    }
+    ExitCode::FAILURE
+}
+
@@ -1,31 +1,33 @@
 //! Server middleware.
 
 use axum::{
-    body::Body,
-    extract::State,
-    http::{header, Method, Request, StatusCode},
+    body::{Body, HttpBody},
+    extract::{FromRequestParts, MatchedPath, Path, RawPathParams, State},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
     middleware::Next,
     response::Response,
     Router,
 };
+use orbis_config::TenancyMode;
+use orbis_plugin::{HookEvent, HookPoint, ResponseCache, MAX_CACHED_RESPONSE_BYTES};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::Duration;
 use tower_http::{
-    compression::CompressionLayer,
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
 
+use crate::csrf::session_token;
+use crate::extractors::{bearer_token, AuthError, AuthUser, CurrentTenant, OptionalAuthUser};
 use crate::state::AppState;
+use crate::versioning::unversioned_path;
 
 /// Create logging middleware layer.
 pub fn logging_layer() -> TraceLayer<tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsAsFailures>> {
     TraceLayer::new_for_http()
 }
 
-/// Create compression middleware layer.
-pub fn compression_layer() -> CompressionLayer {
-    CompressionLayer::new()
-}
-
 /// Create CORS middleware layer.
 pub fn cors_layer(origins: &[String]) -> CorsLayer {
     let cors = CorsLayer::new()
@@ -41,6 +43,7 @@ pub fn cors_layer(origins: &[String]) -> CorsLayer {
             header::AUTHORIZATION,
             header::CONTENT_TYPE,
             header::ACCEPT,
+            header::HeaderName::from_static(orbis_plugin::IDEMPOTENCY_KEY_HEADER),
         ]);
 
     if origins.iter().any(|o| o == "*") {
@@ -51,17 +54,501 @@ pub fn cors_layer(origins: &[String]) -> CorsLayer {
     }
 }
 
+/// Time by which a request must complete, derived from the request timeout.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestDeadline(pub chrono::DateTime<chrono::Utc>);
+
+/// Deadline middleware function.
+///
+/// Records the request deadline so handlers can propagate it to plugins.
+pub async fn deadline_middleware(
+    State(timeout): State<Duration>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+    let deadline = chrono::Utc::now().checked_add_signed(timeout).unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC);
+    request.extensions_mut().insert(RequestDeadline(deadline));
+    next.run(request).await
+}
+
+/// Tenant resolved for a request in multi-tenant mode.
+#[derive(Debug, Clone)]
+pub struct ResolvedTenant(pub orbis_auth::Tenant);
+
+/// Tenant middleware function.
+///
+/// Resolves the tenant from the subdomain or tenant header and records it for
+/// auth and handlers. Requests without a tenant run in platform scope; unknown
+/// or inactive tenants are rejected.
+pub async fn tenant_middleware(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let tenancy = &state.config().tenancy;
+    let slug = match tenancy.mode {
+        TenancyMode::Disabled => None,
+        TenancyMode::Subdomain => request
+            .headers()
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|host| tenancy.slug_from_host(host))
+            .map(str::to_lowercase),
+        TenancyMode::Header => request
+            .headers()
+            .get(tenancy.header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_lowercase),
+    };
+
+    if let Some(slug) = slug {
+        let Some(auth) = state.auth() else {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+
+        let tenant = auth
+            .tenant()
+            .find_by_slug(&slug)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to resolve tenant '{}': {}", slug, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+        let Some(tenant) = tenant.filter(|tenant| tenant.is_active) else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+
+        request.extensions_mut().insert(ResolvedTenant(tenant));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Response cache middleware function.
+///
+/// Serves `GET` plugin routes that declare caching from the response cache
+/// and caches their successful responses, marking responses with an
+/// `x-cache` header of `HIT` or `MISS`. Requests with `Cache-Control: no-cache`
+/// skip the lookup but refresh the cached response.
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    Path((plugin, path)): Path<(String, String)>,
+    user: OptionalAuthUser,
+    tenant: CurrentTenant,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let route = format!("/{}", path);
+    let Some(cache) = state.plugins().registry().get(&plugin).and_then(|info| {
+        info.manifest
+            .routes
+            .iter()
+            .find(|r| r.path == route && r.method.eq_ignore_ascii_case("GET"))
+            .and_then(|r| r.cache.clone())
+    }) else {
+        return next.run(request).await;
+    };
+
+    let vary: BTreeMap<String, String> = cache
+        .vary
+        .iter()
+        .map(|name| {
+            let value = request.headers().get(name.as_str()).and_then(|v| v.to_str().ok());
+            (name.to_lowercase(), value.unwrap_or_default().to_string())
+        })
+        .collect();
+    let key = ResponseCache::key(
+        &plugin,
+        &route,
+        tenant.id().map(|id| id.to_string()).as_deref(),
+        user.0.as_ref().map(|user| user.user_id.to_string()).as_deref(),
+        request.uri().query().unwrap_or_default(),
+        &vary,
+    );
+
+    let revalidate = request
+        .headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("no-cache"));
+    let responses = state.plugins().response_cache();
+    if !revalidate && let Some(body) = responses.get(&plugin, &route, &key) {
+        return cached_response(body, "HIT");
+    }
+
+    let response = next.run(request).await;
+    let cacheable = response.status() == StatusCode::OK
+        && response
+            .body()
+            .size_hint()
+            .upper()
+            .is_some_and(|size| size <= MAX_CACHED_RESPONSE_BYTES as u64);
+    if !cacheable {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_CACHED_RESPONSE_BYTES).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let Ok(body) = String::from_utf8(bytes.to_vec()) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    responses.insert(&plugin, &route, key, body.clone(), Duration::from_secs(cache.ttl_seconds));
+    let mut response = Response::from_parts(parts, Body::from(body));
+    response.headers_mut().insert("x-cache", HeaderValue::from_static("MISS"));
+    response
+}
+
+/// Build a response from a cached JSON body.
+fn cached_response(body: String, status: &'static str) -> Response {
+    let mut response = Response::new(Body::from(body));
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("x-cache", HeaderValue::from_static(status));
+    response
+}
+
+/// Largest response body given a computed ETag, in bytes.
+const MAX_ETAG_BODY: usize = 4 * 1024 * 1024;
+
+/// Response headers kept on `304 Not Modified` responses.
+const NOT_MODIFIED_HEADERS: &[header::HeaderName] = &[
+    header::ETAG,
+    header::LAST_MODIFIED,
+    header::CACHE_CONTROL,
+    header::EXPIRES,
+    header::VARY,
+];
+
+/// Conditional request middleware function.
+///
+/// Gives successful `GET` responses without an ETag a weak one hashed from
+/// their body, and answers requests whose `If-None-Match` matches the ETag
+/// (or, without `If-None-Match`, whose `If-Modified-Since` is not older than
+/// `Last-Modified`) with `304 Not Modified`.
+pub async fn conditional_middleware(request: Request<Body>, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let if_none_match = header_string(request.headers(), &header::IF_NONE_MATCH);
+    let if_modified_since = header_string(request.headers(), &header::IF_MODIFIED_SINCE);
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let response = if response.headers().contains_key(header::ETAG) {
+        response
+    } else {
+        with_weak_etag(response).await
+    };
+    if is_not_modified(response.headers(), if_none_match.as_deref(), if_modified_since.as_deref()) {
+        return not_modified(response.headers());
+    }
+    response
+}
+
+/// Get a header as a string.
+fn header_string(headers: &HeaderMap, name: &header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(String::from)
+}
+
+/// Add a weak ETag hashed from the body to a response.
+async fn with_weak_etag(response: Response) -> Response {
+    let small = response
+        .body()
+        .size_hint()
+        .upper()
+        .is_some_and(|size| size <= MAX_ETAG_BODY as u64);
+    if !small {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_ETAG_BODY).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    if let Ok(etag) = HeaderValue::from_str(&format!("W/\"{:x}\"", Sha256::digest(&bytes))) {
+        parts.headers.insert(header::ETAG, etag);
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Check if the client's copy of a response is still current.
+///
+/// ETags are compared weakly, as `If-None-Match` requires.
+fn is_not_modified(headers: &HeaderMap, if_none_match: Option<&str>, if_modified_since: Option<&str>) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+
+    if let Some(if_none_match) = if_none_match {
+        let Some(etag) = headers.get(header::ETAG).and_then(|v| v.to_str().ok()) else {
+            return false;
+        };
+        let etag = opaque(etag);
+        return if_none_match
+            .split(',')
+            .any(|tag| tag.trim() == "*" || opaque(tag) == etag);
+    }
+
+    let last_modified = headers
+        .get(header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok());
+    let since = if_modified_since.and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok());
+    matches!((last_modified, since), (Some(last_modified), Some(since)) if last_modified <= since)
+}
+
+/// Build a `304 Not Modified` response keeping a response's validators.
+fn not_modified(headers: &HeaderMap) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    for name in NOT_MODIFIED_HEADERS {
+        if let Some(value) = headers.get(name) {
+            response.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+/// Largest JSON request body whose field names are recorded in the audit log, in bytes.
+const MAX_AUDITED_BODY: u64 = 64 * 1024;
+
+/// Audit middleware function.
+///
+/// Records every mutating request to the core API in the audit log: the
+/// user, the route, a summary of its parameters and the response status.
+/// Path parameters are recorded; of the query string and JSON body only the
+/// field names are, so secrets sent in them stay out of the log.
+pub async fn audit_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(auth) = state.auth().cloned() else {
+        return next.run(request).await;
+    };
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let OptionalAuthUser(user) = OptionalAuthUser::from_request_parts(&mut parts, &state)
+        .await
+        .unwrap_or(OptionalAuthUser(None));
+    let params: BTreeMap<String, String> = RawPathParams::from_request_parts(&mut parts, &state)
+        .await
+        .map(|params| params.iter().map(|(name, value)| (name.to_owned(), value.to_owned())).collect())
+        .unwrap_or_default();
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map_or_else(|| parts.uri.path().to_owned(), |path| path.as_str().to_owned());
+    let query: Vec<String> = parts
+        .uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split('=').next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect();
+
+    let (body, fields) = body_fields(&parts.headers, body).await;
+
+    let method = parts.method.clone();
+    let path = parts.uri.path().to_owned();
+    let user_agent = header_string(&parts.headers, &header::USER_AGENT);
+    let tenant_id = user
+        .as_ref()
+        .and_then(|user| user.tenant_id)
+        .or_else(|| parts.extensions.get::<ResolvedTenant>().map(|tenant| tenant.0.id));
+    let resource_id = params.values().find_map(|value| value.parse().ok());
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let entry = orbis_auth::NewAuditEntry {
+        user_id: user.as_ref().map(|user| user.user_id),
+        tenant_id,
+        action: format!("api.{}", method.as_str().to_lowercase()),
+        resource_type: Some("route".to_owned()),
+        resource_id,
+        details: serde_json::json!({
+            "method": method.as_str(),
+            "route": route,
+            "path": path,
+            "params": params,
+            "query": query,
+            "fields": fields,
+            "status": response.status().as_u16(),
+            "impersonator": user.as_ref().and_then(|user| user.impersonator),
+        }),
+        ip_address: None,
+        user_agent,
+    };
+    if let Err(e) = auth.audit().record(entry).await {
+        tracing::error!("Failed to audit {} {}: {}", method, path, e);
+    }
+
+    response
+}
+
+/// Get the top-level field names of a small JSON request body.
+///
+/// The body is buffered to read them, so the body to forward is returned
+/// with them.
+async fn body_fields(headers: &HeaderMap, body: Body) -> (Body, Vec<String>) {
+    let is_json = header_string(headers, &header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let is_small = body.size_hint().upper().is_some_and(|size| size <= MAX_AUDITED_BODY);
+    if !is_json || !is_small {
+        return (body, Vec::new());
+    }
+
+    match axum::body::to_bytes(body, usize::try_from(MAX_AUDITED_BODY).unwrap_or(usize::MAX)).await {
+        Ok(bytes) => {
+            let fields = serde_json::from_slice::<serde_json::Value>(&bytes)
+                .ok()
+                .and_then(|value| value.as_object().map(|object| object.keys().cloned().collect()))
+                .unwrap_or_default();
+            (Body::from(bytes), fields)
+        }
+        Err(e) => {
+            tracing::debug!("Failed to read the body of an audited request: {}", e);
+            (Body::empty(), Vec::new())
+        }
+    }
+}
+
+/// Largest error response localized, in bytes.
+const MAX_LOCALIZED_BODY: u64 = 64 * 1024;
+
+/// Lifecycle hook middleware function.
+///
+/// Runs the `before_request` hooks of plugins before handling the request.
+/// Hook failures are logged by the plugin manager and never fail the request.
+pub async fn hook_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let plugins = state.plugins();
+    if plugins.hooks().has_subscribers(HookPoint::BeforeRequest) {
+        let event = HookEvent::BeforeRequest {
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+        };
+        plugins.run_hooks(&event, None).await;
+    }
+
+    next.run(request).await
+}
+
+/// Localization middleware function.
+///
+/// Translates the message of JSON error responses into the user's `ui.locale`
+/// setting or the `Accept-Language` locales, falling back to the default
+/// locale, and sets `Content-Language` to the locale used.
+pub async fn localize_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let accept_language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(orbis_core::i18n::parse_accept_language)
+        .unwrap_or_default();
+    let token = bearer_token(request.headers())
+        .ok()
+        .flatten()
+        .map(str::to_owned)
+        .or_else(|| session_token(request.headers(), request.extensions(), &state.config().cookie_sessions));
+
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let small = response.body().size_hint().upper().is_some_and(|size| size <= MAX_LOCALIZED_BODY);
+    if !(response.status().is_client_error() || response.status().is_server_error()) || !is_json || !small {
+        return response;
+    }
+
+    let mut requested = Vec::new();
+    if let Some(locale) = user_locale(&state, token.as_deref()).await {
+        requested.push(locale);
+    }
+    requested.extend(accept_language);
+    localize_error(&state, response, &requested).await
+}
+
+/// Get the `ui.locale` setting of the user a token belongs to, if set.
+async fn user_locale(state: &AppState, token: Option<&str>) -> Option<String> {
+    let claims = state.auth()?.validate_token(token?).ok()?;
+    match state.settings().get_stored("ui.locale", Some(&claims.sub)).await {
+        Ok(locale) => locale?.as_str().map(str::to_owned),
+        Err(e) => {
+            tracing::debug!("Failed to read locale of user {}: {}", claims.sub, e);
+            None
+        }
+    }
+}
+
+/// Translate the message of a JSON error response.
+async fn localize_error(state: &AppState, response: Response, requested: &[String]) -> Response {
+    let localizer = state.localizer();
+    let chain = localizer.fallback_chain(requested);
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_LOCALIZED_BODY as usize).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    let mut locale = localizer.default_locale().to_string();
+    let mut body = bytes.to_vec();
+    if let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&bytes)
+        && let Some(message) = value.pointer_mut("/error/message")
+        && let Some((translated_locale, translation)) =
+            message.as_str().and_then(|message| localizer.translate(message, &chain))
+    {
+        *message = serde_json::Value::String(translation);
+        locale = translated_locale;
+        body = value.to_string().into_bytes();
+        parts.headers.remove(header::CONTENT_LENGTH);
+    }
+
+    if let Ok(locale) = HeaderValue::from_str(&locale) {
+        parts.headers.insert(header::CONTENT_LANGUAGE, locale);
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
 /// Apply auth middleware to a router.
 pub fn with_auth(router: Router<AppState>, state: AppState) -> Router<AppState> {
     router.layer(axum::middleware::from_fn_with_state(state, auth_middleware))
 }
 
 /// Auth middleware function.
+///
+/// The authenticated user is kept for the extractors of the route.
 pub async fn auth_middleware(
     State(state): State<AppState>,
     request: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, AuthError> {
     // Skip auth for public routes
     let path = request.uri().path();
     if is_public_route(path) {
@@ -73,42 +560,24 @@ pub async fn auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Get auth header
-    let auth_header = request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok());
-
-    let Some(auth_header) = auth_header else {
-        return Err(StatusCode::UNAUTHORIZED);
-    };
-
-    // Extract token
-    let Some(token) = auth_header.strip_prefix("Bearer ") else {
-        return Err(StatusCode::UNAUTHORIZED);
-    };
-
-    // Validate token
-    let Some(auth) = state.auth() else {
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    };
-
-    if auth.validate_token(token).is_err() {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
-
-    Ok(next.run(request).await)
+    let (mut parts, body) = request.into_parts();
+    AuthUser::from_request_parts(&mut parts, &state).await?;
+    Ok(next.run(Request::from_parts(parts, body)).await)
 }
 
 /// Check if a route is public (no auth required).
-fn is_public_route(path: &str) -> bool {
+pub fn is_public_route(path: &str) -> bool {
     let public_routes = [
         "/health",
         "/api/auth/login",
         "/api/auth/register",
         "/api/auth/refresh",
         "/api/health",
+        "/api/theme",
+        // Signed plugin file downloads
+        "/api/storage/",
     ];
 
+    let path = unversioned_path(path);
     public_routes.iter().any(|r| path.starts_with(r))
 }
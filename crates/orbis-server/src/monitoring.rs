@@ -0,0 +1,471 @@
+//! Plugin resource monitoring.
+//!
+//! Every minute, the plugin resource monitor is sampled and the samples are
+//! stored in `plugin_metrics`. Minute samples older than a day are rolled up
+//! into hourly ones, which are kept for 30 days.
+//!
+//! Alerts fired by the monitor's rules are logged, posted to the configured
+//! webhooks, and disable the plugin when the rule asks for it.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use orbis_core::shutdown;
+use orbis_db::{Database, DatabasePool};
+use orbis_plugin::{AlertRule, PluginManager, ResourceAlert, ResourceSample, StateCause, StateTrigger};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+
+use crate::jobs::{parse_sqlite_time, sqlite_time, JobQueue};
+use crate::webhook;
+
+/// How often plugin resource usage is sampled.
+pub const METRICS_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Samples between two roll-ups of old minute samples.
+const ROLLUP_EVERY_SAMPLES: u64 = 60;
+
+/// Age after which minute samples are rolled up into hourly ones, in hours.
+const MINUTE_RETENTION_HOURS: i64 = 24;
+
+/// Age after which hourly samples are deleted, in days.
+const HOUR_RETENTION_DAYS: i64 = 30;
+
+/// Resolution of stored samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricResolution {
+    /// One sample per minute.
+    #[default]
+    Minute,
+
+    /// One sample per hour.
+    Hour,
+}
+
+impl MetricResolution {
+    /// Get the resolution name, as stored.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+        }
+    }
+
+    /// Get the length of a bucket.
+    const fn bucket(self) -> TimeDelta {
+        match self {
+            Self::Minute => TimeDelta::minutes(1),
+            Self::Hour => TimeDelta::hours(1),
+        }
+    }
+}
+
+/// Alert rules and where their alerts are posted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertPolicy {
+    /// Alert rules.
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+
+    /// URLs alerts are posted to.
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+}
+
+impl AlertPolicy {
+    /// Load an alert policy from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> orbis_core::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            orbis_core::Error::config(format!("Failed to read plugin alerts file {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            orbis_core::Error::config(format!("Invalid plugin alerts file {}: {}", path.display(), e))
+        })
+    }
+}
+
+/// Query parameter, bound according to the database backend.
+enum Param {
+    /// Text.
+    Text(String),
+
+    /// Timestamp.
+    Time(DateTime<Utc>),
+
+    /// Counter.
+    Count(u64),
+}
+
+/// Persists plugin resource samples and acts on resource alerts.
+#[derive(Clone)]
+pub struct ResourceMonitorService {
+    /// Database connection.
+    db: Database,
+
+    /// Plugin manager, whose runtime owns the monitor.
+    plugins: Arc<PluginManager>,
+
+    /// Job queue delivering webhooks.
+    jobs: JobQueue,
+
+    /// URLs alerts are posted to.
+    webhooks: Arc<RwLock<Vec<String>>>,
+
+    /// Whether sampling has started.
+    started: Arc<AtomicBool>,
+}
+
+impl ResourceMonitorService {
+    /// Create the service and register the webhook delivery job.
+    #[must_use]
+    pub fn new(db: Database, jobs: JobQueue, plugins: Arc<PluginManager>) -> Self {
+        webhook::register(&jobs);
+        Self {
+            db,
+            plugins,
+            jobs,
+            webhooks: Arc::new(RwLock::new(Vec::new())),
+            started: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Replace the alert rules and webhooks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule or webhook URL is invalid; the previous
+    /// policy is kept.
+    pub fn set_policy(&self, policy: AlertPolicy) -> orbis_core::Result<()> {
+        for url in &policy.webhooks {
+            webhook::validate_url(url)?;
+        }
+        let rules = policy.rules.len();
+        self.plugins.runtime().resource_monitor().set_rules(policy.rules)?;
+        *self.webhooks.write() = policy.webhooks;
+
+        tracing::info!(
+            "Plugin alert policy set: {} rules, {} webhooks",
+            rules,
+            self.webhooks.read().len()
+        );
+        Ok(())
+    }
+
+    /// Get the alert rules and webhooks.
+    #[must_use]
+    pub fn policy(&self) -> AlertPolicy {
+        AlertPolicy {
+            rules: self.plugins.runtime().resource_monitor().rules(),
+            webhooks: self.webhooks.read().clone(),
+        }
+    }
+
+    /// Start sampling resource usage in the background, until the workers
+    /// phase of the shutdown.
+    ///
+    /// Does nothing after the first call.
+    pub fn start(&self, shutdown: &shutdown::Coordinator) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let stop = shutdown.token(shutdown::Phase::Workers);
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(METRICS_SAMPLE_INTERVAL);
+            let mut samples: u64 = 0;
+            loop {
+                tokio::select! {
+                    () = stop.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                // Roll up right away too, to catch up after downtime
+                service.tick(samples.is_multiple_of(ROLLUP_EVERY_SAMPLES)).await;
+                samples = samples.wrapping_add(1);
+            }
+        });
+    }
+
+    /// Sample resource usage, store the samples and act on alerts.
+    async fn tick(&self, rollup: bool) {
+        let monitor = self.plugins.runtime().resource_monitor();
+        let samples = monitor.sample();
+        if let Err(e) = self.store(MetricResolution::Minute, &samples).await {
+            tracing::error!("Failed to store plugin resource samples: {}", e);
+        }
+
+        for alert in monitor.check() {
+            self.handle_alert(&alert).await;
+        }
+
+        if rollup && let Err(e) = self.downsample(Utc::now()).await {
+            tracing::error!("Failed to roll up plugin resource samples: {}", e);
+        }
+    }
+
+    /// Post an alert to the webhooks and disable the plugin if the rule asks for it.
+    async fn handle_alert(&self, alert: &ResourceAlert) {
+        let webhooks = self.webhooks.read().clone();
+        for url in webhooks {
+            let body = json!({ "event": "plugin.resource_alert", "alert": alert });
+            if let Err(e) = webhook::send(&self.jobs, &url, body).await {
+                tracing::error!("Failed to queue resource alert webhook to {}: {}", url, e);
+            }
+        }
+
+        if alert.disable {
+            self.disable_plugin(alert).await;
+        }
+    }
+
+    /// Disable the plugin an alert fired for.
+    async fn disable_plugin(&self, alert: &ResourceAlert) {
+        let cause = StateCause::new(StateTrigger::Alert).because(format!("resource alert '{}'", alert.rule));
+        match self.plugins.disable_plugin_with(&alert.plugin, cause).await {
+            Ok(()) => tracing::warn!("Disabled plugin {} after resource alert '{}'", alert.plugin, alert.rule),
+            Err(e) => tracing::error!("Failed to disable plugin {}: {}", alert.plugin, e),
+        }
+    }
+
+    /// Get the stored samples of a plugin since a time, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn history(
+        &self,
+        plugin: &str,
+        resolution: MetricResolution,
+        since: DateTime<Utc>,
+    ) -> orbis_core::Result<Vec<ResourceSample>> {
+        self.fetch(
+            "SELECT plugin_name, bucket_start, memory_bytes, calls, errors FROM plugin_metrics \
+             WHERE plugin_name = $1 AND resolution = $2 AND bucket_start >= $3 ORDER BY bucket_start",
+            vec![
+                Param::Text(plugin.to_string()),
+                Param::Text(resolution.as_str().to_string()),
+                Param::Time(since),
+            ],
+        )
+        .await
+    }
+
+    /// Store samples in the buckets of a resolution, merging them with
+    /// samples already in the same bucket.
+    async fn store(&self, resolution: MetricResolution, samples: &[ResourceSample]) -> orbis_core::Result<()> {
+        let greatest = match self.db.pool() {
+            DatabasePool::Postgres(_) => "GREATEST",
+            DatabasePool::Sqlite(_) => "MAX",
+        };
+        let sql = format!(
+            "INSERT INTO plugin_metrics (plugin_name, resolution, bucket_start, memory_bytes, calls, errors) \
+             VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (plugin_name, resolution, bucket_start) DO UPDATE SET \
+             memory_bytes = {}(plugin_metrics.memory_bytes, excluded.memory_bytes), \
+             calls = plugin_metrics.calls + excluded.calls, errors = plugin_metrics.errors + excluded.errors",
+            greatest
+        );
+
+        for sample in samples {
+            let bucket_start = sample
+                .at
+                .duration_trunc(resolution.bucket())
+                .map_err(|e| orbis_core::Error::internal(format!("Invalid sample time: {}", e)))?;
+            self.execute(
+                &sql,
+                vec![
+                    Param::Text(sample.plugin.clone()),
+                    Param::Text(resolution.as_str().to_string()),
+                    Param::Time(bucket_start),
+                    Param::Count(sample.memory_bytes),
+                    Param::Count(sample.calls),
+                    Param::Count(sample.errors),
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Roll up minute samples older than a day into hourly ones, and delete
+    /// expired hourly samples.
+    async fn downsample(&self, now: DateTime<Utc>) -> orbis_core::Result<()> {
+        // Only roll up complete hours
+        let cutoff = now
+            .checked_sub_signed(TimeDelta::hours(MINUTE_RETENTION_HOURS))
+            .and_then(|time| time.duration_trunc(TimeDelta::hours(1)).ok())
+            .ok_or_else(|| orbis_core::Error::internal("Invalid roll-up cutoff"))?;
+        let minutes = self
+            .fetch(
+                "SELECT plugin_name, bucket_start, memory_bytes, calls, errors FROM plugin_metrics \
+                 WHERE resolution = $1 AND bucket_start < $2",
+                vec![Param::Text(MetricResolution::Minute.as_str().to_string()), Param::Time(cutoff)],
+            )
+            .await?;
+
+        let mut hours: BTreeMap<(String, DateTime<Utc>), ResourceSample> = BTreeMap::new();
+        for sample in minutes {
+            let hour = sample
+                .at
+                .duration_trunc(TimeDelta::hours(1))
+                .map_err(|e| orbis_core::Error::internal(format!("Invalid sample time: {}", e)))?;
+            let total = hours
+                .entry((sample.plugin.clone(), hour))
+                .or_insert_with(|| ResourceSample {
+                    plugin: sample.plugin.clone(),
+                    at: hour,
+                    memory_bytes: 0,
+                    calls: 0,
+                    errors: 0,
+                });
+            total.memory_bytes = total.memory_bytes.max(sample.memory_bytes);
+            total.calls = total.calls.saturating_add(sample.calls);
+            total.errors = total.errors.saturating_add(sample.errors);
+        }
+        let hours: Vec<ResourceSample> = hours.into_values().collect();
+        self.store(MetricResolution::Hour, &hours).await?;
+
+        self.execute(
+            "DELETE FROM plugin_metrics WHERE resolution = $1 AND bucket_start < $2",
+            vec![Param::Text(MetricResolution::Minute.as_str().to_string()), Param::Time(cutoff)],
+        )
+        .await?;
+
+        let expired = now
+            .checked_sub_signed(TimeDelta::days(HOUR_RETENTION_DAYS))
+            .ok_or_else(|| orbis_core::Error::internal("Invalid retention cutoff"))?;
+        let deleted = self
+            .execute(
+                "DELETE FROM plugin_metrics WHERE resolution = $1 AND bucket_start < $2",
+                vec![Param::Text(MetricResolution::Hour.as_str().to_string()), Param::Time(expired)],
+            )
+            .await?;
+
+        tracing::debug!("Rolled up {} hourly plugin samples, deleted {} expired", hours.len(), deleted);
+        Ok(())
+    }
+
+    /// Execute a statement, returning the number of affected rows.
+    async fn execute(&self, sql: &str, params: Vec<Param>) -> orbis_core::Result<u64> {
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Text(text) => query.bind(text),
+                        Param::Time(time) => query.bind(time),
+                        Param::Count(count) => query.bind(to_db_count(count)),
+                    };
+                }
+                query
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Text(text) => query.bind(text),
+                        Param::Time(time) => query.bind(sqlite_time(time)),
+                        Param::Count(count) => query.bind(to_db_count(count)),
+                    };
+                }
+                query
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))
+            }
+        }
+    }
+
+    /// Fetch samples.
+    async fn fetch(&self, sql: &str, params: Vec<Param>) -> orbis_core::Result<Vec<ResourceSample>> {
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Text(text) => query.bind(text),
+                        Param::Time(time) => query.bind(time),
+                        Param::Count(count) => query.bind(to_db_count(count)),
+                    };
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter()
+                    .map(|row| {
+                        sample_from_row(row, row.try_get("bucket_start").map_err(column_error)?)
+                    })
+                    .collect()
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query(sql);
+                for param in params {
+                    query = match param {
+                        Param::Text(text) => query.bind(text),
+                        Param::Time(time) => query.bind(sqlite_time(time)),
+                        Param::Count(count) => query.bind(to_db_count(count)),
+                    };
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+                rows.iter()
+                    .map(|row| {
+                        let at: String = row.try_get("bucket_start").map_err(column_error)?;
+                        sample_from_row(row, parse_sqlite_time(&at)?)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Convert a counter to a database integer.
+fn to_db_count(count: u64) -> i64 {
+    i64::try_from(count).unwrap_or(i64::MAX)
+}
+
+/// Convert a database integer to a counter.
+fn from_db_count(count: i64) -> u64 {
+    u64::try_from(count).unwrap_or_default()
+}
+
+/// Convert a database error.
+fn column_error(e: sqlx::Error) -> orbis_core::Error {
+    orbis_core::Error::database(e.to_string())
+}
+
+/// Read a sample from a row of either backend.
+fn sample_from_row<R>(row: &R, at: DateTime<Utc>) -> orbis_core::Result<ResourceSample>
+where
+    R: Row,
+    for<'r> &'r str: sqlx::ColumnIndex<R>,
+    for<'r> String: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    for<'r> i64: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+{
+    Ok(ResourceSample {
+        plugin: row.try_get("plugin_name").map_err(column_error)?,
+        at,
+        memory_bytes: from_db_count(row.try_get("memory_bytes").map_err(column_error)?),
+        calls: from_db_count(row.try_get("calls").map_err(column_error)?),
+        errors: from_db_count(row.try_get("errors").map_err(column_error)?),
+    })
+}
@@ -0,0 +1,58 @@
+//! Audit log routes (admin).
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use orbis_auth::{AuditFilter, AuthService};
+use serde_json::{json, Value};
+
+use crate::error::ServerResult;
+use crate::extractors::{Admin, RequirePlatformAdmin, RequireRole};
+use crate::state::AppState;
+
+/// Create audit router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/audit", get(list_entries))
+        .route("/audit/verify", get(verify_chain))
+}
+
+/// Get the auth service.
+fn auth_service(state: &AppState) -> orbis_core::Result<&AuthService> {
+    state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::config("Authentication is not configured"))
+}
+
+/// List audit log entries, newest first.
+///
+/// Tenant admins only see the entries of their tenant.
+async fn list_entries(
+    admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+    Query(filter): Query<AuditFilter>,
+) -> ServerResult<Json<Value>> {
+    let entries = auth_service(&state)?
+        .audit()
+        .list(admin.0.tenant_id, &filter)
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": entries
+    })))
+}
+
+/// Verify the hash chain of the whole audit log.
+///
+/// The chain spans every tenant, so only platform admins can verify it.
+async fn verify_chain(_admin: RequirePlatformAdmin, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let verification = auth_service(&state)?.audit().verify().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": verification
+    })))
+}
@@ -2,14 +2,17 @@
 
 use axum::{
     extract::State,
+    http::{request::Parts, HeaderMap},
     routing::{get, post},
     Json, Router,
 };
+use orbis_plugin::HookEvent;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::csrf::{clear_session_cookies, session_cookies, session_token};
 use crate::error::ServerResult;
-use crate::extractors::AuthenticatedUser;
+use crate::extractors::{AuthUser, CurrentTenant};
 use crate::state::AppState;
 
 /// Create auth router.
@@ -20,6 +23,7 @@ pub fn router() -> Router<AppState> {
         .route("/auth/refresh", post(refresh))
         .route("/auth/logout", post(logout))
         .route("/auth/me", get(me))
+        .route("/auth/csrf", get(csrf_token))
 }
 
 /// Login request.
@@ -27,37 +31,79 @@ pub fn router() -> Router<AppState> {
 struct LoginRequest {
     username: String,
     password: String,
+
+    /// Keep the access token in the session cookie instead of the response.
+    #[serde(default)]
+    session_cookie: bool,
 }
 
 /// Login handler.
 async fn login(
     State(state): State<AppState>,
+    tenant: CurrentTenant,
     Json(req): Json<LoginRequest>,
-) -> ServerResult<Json<Value>> {
+) -> ServerResult<(HeaderMap, Json<Value>)> {
     let auth = state.auth().ok_or_else(|| {
         orbis_core::Error::config("Authentication is not configured")
     })?;
 
     let result = auth
-        .authenticate(&req.username, &req.password, None, None)
+        .authenticate(&req.username, &req.password, tenant.id(), None, None)
         .await
         .map_err(|e| orbis_core::Error::auth(e.to_string()))?;
 
-    Ok(Json(json!({
-        "success": true,
-        "data": {
-            "access_token": result.access_token,
-            "refresh_token": result.refresh_token,
-            "expires_in": result.expires_in,
-            "user": {
-                "id": result.user.id.to_string(),
-                "username": result.user.username,
-                "email": result.user.email,
-                "display_name": result.user.display_name,
-                "is_admin": result.user.is_admin
-            }
+    // Notify plugins without delaying the login
+    let plugins = state.plugins_arc();
+    let user_id = result.user.id.to_string();
+    let event = HookEvent::AfterAuth {
+        user_id: user_id.clone(),
+        username: result.user.username.clone(),
+    };
+    tokio::spawn(async move {
+        plugins.run_hooks(&event, Some(user_id)).await;
+    });
+
+    let mut data = json!({
+        "access_token": result.access_token,
+        "refresh_token": result.refresh_token,
+        "expires_in": result.expires_in,
+        "user": {
+            "id": result.user.id.to_string(),
+            "username": result.user.username,
+            "email": result.user.email,
+            "display_name": result.user.display_name,
+            "is_admin": result.user.is_admin
         }
-    })))
+    });
+    let headers = start_session(&state, req.session_cookie, &mut data)?;
+
+    Ok((headers, Json(json!({
+        "success": true,
+        "data": data
+    }))))
+}
+
+/// Move the access token of a login or refresh response into the session
+/// cookie, if requested, replacing it with the CSRF token of the session.
+///
+/// Returns the `Set-Cookie` headers of the session.
+fn start_session(state: &AppState, requested: bool, data: &mut Value) -> orbis_core::Result<HeaderMap> {
+    if !requested {
+        return Ok(HeaderMap::new());
+    }
+    let config = &state.config().cookie_sessions;
+    if !config.enabled {
+        return Err(orbis_core::Error::validation("Cookie sessions are not enabled"));
+    }
+
+    let Some(Value::String(access_token)) = data.as_object_mut().and_then(|data| data.remove("access_token")) else {
+        return Ok(HeaderMap::new());
+    };
+    let (headers, csrf_token) = session_cookies(config, state.csrf(), &access_token);
+    if let Some(data) = data.as_object_mut() {
+        data.insert("csrf_token".to_string(), Value::String(csrf_token));
+    }
+    Ok(headers)
 }
 
 /// Register request.
@@ -72,12 +118,22 @@ struct RegisterRequest {
 /// Register handler.
 async fn register(
     State(state): State<AppState>,
+    tenant: CurrentTenant,
     Json(req): Json<RegisterRequest>,
 ) -> ServerResult<Json<Value>> {
     let auth = state.auth().ok_or_else(|| {
         orbis_core::Error::config("Authentication is not configured")
     })?;
 
+    // Check that self-registration is allowed
+    let registration_enabled = state
+        .settings()
+        .get(crate::settings::REGISTRATION_ENABLED, None)
+        .await?;
+    if registration_enabled == Value::Bool(false) {
+        return Err(orbis_core::Error::unauthorized("Registration is disabled").into());
+    }
+
     // Check password strength
     let strength = orbis_auth::PasswordService::validate_password_strength(&req.password);
     if !strength.is_valid() {
@@ -86,13 +142,13 @@ async fn register(
         ).into());
     }
 
-    // Check if username exists
-    if auth.user().username_exists(&req.username).await? {
+    // Check if username exists in the tenant
+    if auth.user().username_exists(&req.username, tenant.id()).await? {
         return Err(orbis_core::Error::conflict("Username already exists").into());
     }
 
-    // Check if email exists
-    if auth.user().email_exists(&req.email).await? {
+    // Check if email exists in the tenant
+    if auth.user().email_exists(&req.email, tenant.id()).await? {
         return Err(orbis_core::Error::conflict("Email already exists").into());
     }
 
@@ -109,6 +165,7 @@ async fn register(
                 password: req.password,
                 display_name: req.display_name,
                 is_admin: false,
+                tenant_id: tenant.id(),
             },
             password_hash,
         )
@@ -130,30 +187,38 @@ async fn register(
 #[derive(Debug, Deserialize)]
 struct RefreshRequest {
     refresh_token: String,
+
+    /// Keep the new access token in the session cookie instead of the response.
+    #[serde(default)]
+    session_cookie: bool,
 }
 
 /// Refresh handler.
 async fn refresh(
     State(state): State<AppState>,
+    tenant: CurrentTenant,
     Json(req): Json<RefreshRequest>,
-) -> ServerResult<Json<Value>> {
+) -> ServerResult<(HeaderMap, Json<Value>)> {
     let auth = state.auth().ok_or_else(|| {
         orbis_core::Error::config("Authentication is not configured")
     })?;
 
     let result = auth
-        .refresh(&req.refresh_token)
+        .refresh(&req.refresh_token, tenant.id())
         .await
         .map_err(|e| orbis_core::Error::auth(e.to_string()))?;
 
-    Ok(Json(json!({
+    let mut data = json!({
+        "access_token": result.access_token,
+        "refresh_token": result.refresh_token,
+        "expires_in": result.expires_in
+    });
+    let headers = start_session(&state, req.session_cookie, &mut data)?;
+
+    Ok((headers, Json(json!({
         "success": true,
-        "data": {
-            "access_token": result.access_token,
-            "refresh_token": result.refresh_token,
-            "expires_in": result.expires_in
-        }
-    })))
+        "data": data
+    }))))
 }
 
 /// Logout request.
@@ -166,21 +231,21 @@ struct LogoutRequest {
 async fn logout(
     State(state): State<AppState>,
     Json(req): Json<LogoutRequest>,
-) -> ServerResult<Json<Value>> {
+) -> ServerResult<(HeaderMap, Json<Value>)> {
     let auth = state.auth().ok_or_else(|| {
         orbis_core::Error::config("Authentication is not configured")
     })?;
 
     auth.logout(&req.refresh_token).await?;
 
-    Ok(Json(json!({
+    Ok((clear_session_cookies(&state.config().cookie_sessions), Json(json!({
         "success": true,
         "message": "Logged out successfully"
-    })))
+    }))))
 }
 
 /// Get current user.
-async fn me(user: AuthenticatedUser) -> Json<Value> {
+async fn me(user: AuthUser) -> Json<Value> {
     Json(json!({
         "success": true,
         "data": {
@@ -190,3 +255,22 @@ async fn me(user: AuthenticatedUser) -> Json<Value> {
         }
     }))
 }
+
+/// Get the CSRF token of the session cookie, setting the session cookies again.
+async fn csrf_token(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    parts: Parts,
+) -> ServerResult<(HeaderMap, Json<Value>)> {
+    let config = &state.config().cookie_sessions;
+    let session = session_token(&parts.headers, &parts.extensions, config)
+        .ok_or_else(|| orbis_core::Error::validation("The request has no session cookie"))?;
+    let (headers, csrf_token) = session_cookies(config, state.csrf(), &session);
+
+    Ok((headers, Json(json!({
+        "success": true,
+        "data": {
+            "csrf_token": csrf_token
+        }
+    }))))
+}
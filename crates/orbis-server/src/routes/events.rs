@@ -0,0 +1,63 @@
+//! Server event routes (admin).
+//!
+//! Long polling serves the events of the server-sent event streams to
+//! clients behind proxies that buffer or cut streamed responses.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::error::ServerResult;
+use crate::extractors::{Admin, RequireRole};
+use crate::middleware::RequestDeadline;
+use crate::state::AppState;
+
+/// Time left for answering a poll before the request deadline.
+const POLL_DEADLINE_MARGIN: Duration = Duration::from_secs(1);
+
+/// Create events router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/events/poll", get(poll_events))
+}
+
+/// Poll query parameters.
+#[derive(Debug, Deserialize)]
+struct PollQuery {
+    /// Cursor returned by the previous poll; unset to start from now.
+    cursor: Option<String>,
+
+    /// Most events to return, up to the configured batch size.
+    limit: Option<usize>,
+}
+
+/// Wait for the events after a cursor, answering with none once the poll
+/// times out.
+async fn poll_events(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+    deadline: Option<Extension<RequestDeadline>>,
+    Query(query): Query<PollQuery>,
+) -> ServerResult<Json<Value>> {
+    let hub = state.events();
+    let config = hub.config();
+
+    // Answer before the request times out
+    let mut wait = Duration::from_secs(config.poll_timeout_seconds);
+    if let Some(Extension(RequestDeadline(deadline))) = deadline {
+        let left = deadline.signed_duration_since(chrono::Utc::now()).to_std().unwrap_or_default();
+        wait = wait.min(left.saturating_sub(POLL_DEADLINE_MARGIN));
+    }
+
+    let limit = query.limit.unwrap_or(config.poll_max_events);
+    let batch = hub.poll(query.cursor.as_deref(), limit, wait).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": batch
+    })))
+}
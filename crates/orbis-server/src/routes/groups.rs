@@ -0,0 +1,173 @@
+//! User group management routes.
+//!
+//! Membership changes show in the `groups` claim of the members' tokens once
+//! they are refreshed.
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use orbis_auth::{AuthService, Group, GroupData};
+use orbis_core::UserId;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::ServerResult;
+use crate::extractors::{Admin, RequireRole};
+use crate::state::AppState;
+
+/// Create groups router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/groups", get(list_groups).post(create_group))
+        .route("/groups/{id}", get(get_group).put(update_group).delete(delete_group))
+        .route("/groups/{id}/members", get(list_members))
+        .route("/groups/{id}/members/{user_id}", post(add_member).delete(remove_member))
+}
+
+/// Get the auth service.
+fn auth(state: &AppState) -> orbis_core::Result<&AuthService> {
+    state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::config("Authentication is not configured"))
+}
+
+/// Find a group of the admin's tenant.
+async fn find_group(auth: &AuthService, admin: &RequireRole<Admin>, id: Uuid) -> orbis_core::Result<Group> {
+    auth.group()
+        .find(id, admin.0.tenant_id)
+        .await?
+        .ok_or_else(|| orbis_core::Error::not_found("Group not found"))
+}
+
+/// List the groups of the admin's tenant.
+async fn list_groups(admin: RequireRole<Admin>, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let groups = auth(&state)?.group().list(admin.0.tenant_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "groups": groups,
+            "total": groups.len()
+        }
+    })))
+}
+
+/// Create a group.
+async fn create_group(
+    admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+    Json(data): Json<GroupData>,
+) -> ServerResult<Json<Value>> {
+    let group = auth(&state)?.group().create(admin.0.tenant_id, data).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": group
+    })))
+}
+
+/// Get a group.
+async fn get_group(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let group = find_group(auth(&state)?, &admin, id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": group
+    })))
+}
+
+/// Update a group.
+async fn update_group(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(data): Json<GroupData>,
+) -> ServerResult<Json<Value>> {
+    let group = auth(&state)?.group().update(id, admin.0.tenant_id, data).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": group
+    })))
+}
+
+/// Delete a group; its subgroups become top-level groups.
+async fn delete_group(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    if !auth(&state)?.group().delete(id, admin.0.tenant_id).await? {
+        return Err(orbis_core::Error::not_found("Group not found").into());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Group deleted"
+    })))
+}
+
+/// List the direct members of a group.
+async fn list_members(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    let group = find_group(auth, &admin, id).await?;
+    let members = auth.group().members(group.id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "members": members,
+            "total": members.len()
+        }
+    })))
+}
+
+/// Add a user of the admin's tenant to a group.
+async fn add_member(
+    admin: RequireRole<Admin>,
+    Path((id, user_id)): Path<(Uuid, UserId)>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    let group = find_group(auth, &admin, id).await?;
+    auth.user()
+        .find_by_id(user_id)
+        .await?
+        .filter(|user| user.tenant_id == admin.0.tenant_id)
+        .ok_or_else(|| orbis_core::Error::not_found("User not found"))?;
+
+    let membership = auth.group().add_member(group.id, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": membership
+    })))
+}
+
+/// Remove a user from a group.
+async fn remove_member(
+    admin: RequireRole<Admin>,
+    Path((id, user_id)): Path<(Uuid, UserId)>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    let group = find_group(auth, &admin, id).await?;
+    if !auth.group().remove_member(group.id, user_id).await? {
+        return Err(orbis_core::Error::not_found("User is not a member of the group").into());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Member removed"
+    })))
+}
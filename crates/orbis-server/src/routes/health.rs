@@ -27,20 +27,58 @@ async fn api_health_check(State(state): State<AppState>) -> Json<Value> {
     let plugins_count = state.plugins().registry().count();
     let plugins_running = state.plugins().registry().running_count();
 
+    // Health is public, so leave out the requests of slow invocations
+    let flagged_handlers: Vec<Value> = state
+        .plugins()
+        .runtime()
+        .handler_stats()
+        .flagged()
+        .into_iter()
+        .map(|report| {
+            json!({
+                "plugin": report.plugin,
+                "handler": report.handler,
+                "flags": report.flags,
+                "p95_ms": report.p95_ms,
+                "error_rate": report.error_rate
+            })
+        })
+        .collect();
+
+    // Running plugins admins should migrate away from
+    let deprecated_plugins: Vec<Value> = state
+        .plugins()
+        .registry()
+        .deprecated_running()
+        .into_iter()
+        .map(|info| {
+            json!({
+                "plugin": info.manifest.name,
+                "version": info.manifest.version,
+                "notice": info.manifest.deprecated
+            })
+        })
+        .collect();
+
     Json(json!({
         "status": if db_healthy { "ok" } else { "degraded" },
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "components": {
             "database": {
-                "status": if db_healthy { "ok" } else { "error" }
+                "status": if db_healthy { "ok" } else { "error" },
+                "query_cache": state.db().query_cache().map(|cache| cache.stats())
             },
             "plugins": {
                 "total": plugins_count,
-                "running": plugins_running
+                "running": plugins_running,
+                "flagged_handlers": flagged_handlers,
+                "deprecated": deprecated_plugins
             },
             "auth": {
                 "enabled": state.is_auth_required()
-            }
+            },
+            "limits": state.limit_stats().counts(),
+            "compression": state.compression_stats().counts()
         },
         "version": env!("CARGO_PKG_VERSION")
     }))
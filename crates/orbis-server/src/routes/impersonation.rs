@@ -0,0 +1,151 @@
+//! Admin impersonation routes.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use orbis_auth::{AuthService, Impersonation};
+use orbis_core::UserId;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::ServerResult;
+use crate::extractors::{Admin, RequireRole};
+use crate::state::AppState;
+
+/// Create impersonation router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/users/{id}/impersonate", post(impersonate))
+        .route("/impersonations", get(list_impersonations))
+        .route("/impersonations/{id}", delete(revoke_impersonation))
+        .route("/impersonations/{id}/audit", get(impersonation_audit))
+}
+
+/// Impersonation request.
+#[derive(Debug, Default, Deserialize)]
+struct ImpersonateRequest {
+    /// Why the user is impersonated, recorded in the audit log.
+    reason: Option<String>,
+}
+
+/// Get the auth service.
+fn auth(state: &AppState) -> orbis_core::Result<&AuthService> {
+    state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::config("Authentication is not configured"))
+}
+
+/// Get the user agent of a request.
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok())
+}
+
+/// Find an impersonation started in the admin's tenant.
+async fn find_impersonation(auth: &AuthService, admin: &RequireRole<Admin>, id: Uuid) -> orbis_core::Result<Impersonation> {
+    auth.impersonation()
+        .find(id)
+        .await?
+        .filter(|impersonation| impersonation.tenant_id == admin.0.tenant_id)
+        .ok_or_else(|| orbis_core::Error::not_found("Impersonation not found"))
+}
+
+/// Start impersonating a user.
+async fn impersonate(
+    admin: RequireRole<Admin>,
+    Path(id): Path<UserId>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Option<Json<ImpersonateRequest>>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    let Json(request) = request.unwrap_or_default();
+
+    let admin_user = auth
+        .user()
+        .find_by_id(admin.0.user_id)
+        .await?
+        .ok_or_else(|| orbis_core::Error::auth("User not found"))?;
+    let result = auth
+        .impersonate(&admin_user, id, request.reason.as_deref(), user_agent(&headers), None)
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "user": {
+                "id": result.user.id,
+                "username": result.user.username,
+                "email": result.user.email,
+                "display_name": result.user.display_name
+            },
+            "access_token": result.access_token,
+            "expires_at": result.impersonation.expires_at,
+            "impersonation": result.impersonation
+        }
+    })))
+}
+
+/// List the active impersonations of the admin's tenant.
+async fn list_impersonations(
+    admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let impersonations: Vec<_> = auth(&state)?
+        .impersonation()
+        .list_active()
+        .await?
+        .into_iter()
+        .filter(|impersonation| impersonation.tenant_id == admin.0.tenant_id)
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "impersonations": impersonations,
+            "total": impersonations.len()
+        }
+    })))
+}
+
+/// End an impersonation.
+async fn revoke_impersonation(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    find_impersonation(auth, &admin, id).await?;
+
+    let impersonation = auth
+        .revoke_impersonation(id, admin.0.user_id, user_agent(&headers), None)
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": impersonation
+    })))
+}
+
+/// Get the audit trail of an impersonation.
+async fn impersonation_audit(
+    admin: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth(&state)?;
+    let impersonation = find_impersonation(auth, &admin, id).await?;
+    let entries = auth.audit().for_resource("impersonation", id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "impersonation": impersonation,
+            "entries": entries
+        }
+    })))
+}
@@ -0,0 +1,88 @@
+//! Job queue routes (platform admin).
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::ServerResult;
+use crate::extractors::RequirePlatformAdmin;
+use crate::jobs::JobStatus;
+use crate::state::AppState;
+
+/// Default number of jobs listed.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Create jobs router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{id}", get(get_job))
+        .route("/jobs/{id}/retry", post(retry_job))
+}
+
+/// Jobs query parameters.
+#[derive(Debug, Deserialize)]
+struct JobsQuery {
+    /// Status to filter by (`pending`, `running`, `completed` or `dead`).
+    status: Option<String>,
+
+    /// Queue to filter by.
+    queue: Option<String>,
+
+    /// Maximum number of jobs to return.
+    limit: Option<i64>,
+}
+
+/// List the most recent jobs.
+async fn list_jobs(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+    Query(query): Query<JobsQuery>,
+) -> ServerResult<Json<Value>> {
+
+    let status = query.status.as_deref().map(str::parse::<JobStatus>).transpose()?;
+    let jobs = state
+        .jobs()
+        .list(status, query.queue.as_deref(), query.limit.unwrap_or(DEFAULT_LIMIT))
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": jobs
+    })))
+}
+
+/// Get a job.
+async fn get_job(
+    _admin: RequirePlatformAdmin,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+
+    let job = state.jobs().get(id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": job
+    })))
+}
+
+/// Retry a pending or dead-lettered job now.
+async fn retry_job(
+    _admin: RequirePlatformAdmin,
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+
+    let job = state.jobs().retry(id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": job
+    })))
+}
@@ -1,10 +1,23 @@
 //! Route handlers.
 
+pub mod audit;
 pub mod auth;
+pub mod events;
+pub mod groups;
 pub mod health;
+pub mod impersonation;
+pub mod jobs;
+pub mod navigation;
+pub mod plugin_dev;
 pub mod plugin_management;
 pub mod plugins;
 pub mod profiles;
+pub mod search;
 pub mod settings;
 pub mod static_files;
+pub mod storage;
+pub mod system;
+pub mod tenants;
+pub mod theme;
 pub mod users;
+pub mod well_known;
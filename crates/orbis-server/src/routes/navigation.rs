@@ -0,0 +1,54 @@
+//! Navigation routes.
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde_json::{json, Value};
+
+use crate::error::ServerResult;
+use crate::extractors::{AuthUser, OptionalAuthUser};
+use crate::state::AppState;
+
+/// Create navigation router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/navigation", get(get_navigation))
+}
+
+/// Get the menu entries of running plugins visible to the requesting user.
+///
+/// Pages the user's roles or permissions don't allow are left out, so the
+/// menu never advertises pages the server would refuse to serve data for.
+async fn get_navigation(
+    OptionalAuthUser(user): OptionalAuthUser,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let viewer = user.as_ref().map(AuthUser::access);
+
+    let mut pages: Vec<_> = state
+        .plugins()
+        .get_all_pages()
+        .into_iter()
+        .filter(|(_, page)| page.show_in_menu && page.is_accessible_by(viewer.as_ref()))
+        .collect();
+    pages.sort_by_key(|(_, page)| page.menu_order);
+
+    let items: Vec<Value> = pages
+        .iter()
+        .map(|(plugin, page)| {
+            json!({
+                "plugin": plugin,
+                "route": page.full_route(plugin),
+                "title": page.title,
+                "icon": page.icon,
+                "description": page.description,
+                "menu_order": page.menu_order,
+                "parent_route": page.parent_route.as_ref().map(|parent| format!("/plugins/{}{}", plugin, parent))
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "items": items
+        }
+    })))
+}
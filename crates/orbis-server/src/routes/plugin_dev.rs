@@ -0,0 +1,213 @@
+//! Plugin development routes (platform admin, development mode only).
+//!
+//! Handlers can be invoked directly with a crafted context, and the requests
+//! they handled can be listed and replayed after changing the plugin.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::error::ServerResult;
+use crate::extractors::RequirePlatformAdmin;
+use crate::state::AppState;
+
+/// Create plugin development router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/plugins/{name}/dev/invoke", post(invoke_handler))
+        .route("/plugins/{name}/dev/requests", get(list_requests).delete(clear_requests))
+        .route("/plugins/{name}/dev/requests/{id}/replay", post(replay_request))
+}
+
+/// User a handler is invoked as.
+#[derive(Debug, Default, Deserialize)]
+struct InvokeUser {
+    /// User ID.
+    id: Option<String>,
+
+    /// User is admin.
+    #[serde(default)]
+    is_admin: bool,
+
+    /// Tenant of the request.
+    tenant_id: Option<String>,
+
+    /// Groups the user is a member of.
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+/// Handler invocation request.
+#[derive(Debug, Deserialize)]
+struct InvokeRequest {
+    /// Handler to invoke.
+    handler: String,
+
+    /// Request method (defaults to `POST`).
+    method: Option<String>,
+
+    /// Request path (defaults to the path of the route using the handler).
+    path: Option<String>,
+
+    /// Query parameters.
+    #[serde(default)]
+    query: HashMap<String, String>,
+
+    /// Request headers.
+    #[serde(default)]
+    headers: HashMap<String, String>,
+
+    /// Request body.
+    #[serde(default)]
+    body: Value,
+
+    /// User to invoke the handler as (anonymous if unset).
+    #[serde(default)]
+    user: InvokeUser,
+}
+
+/// Captured requests query parameters.
+#[derive(Debug, Deserialize)]
+struct RequestsQuery {
+    /// Handler to list the requests of.
+    handler: Option<String>,
+}
+
+/// Replay request.
+#[derive(Debug, Default, Deserialize)]
+struct ReplayRequest {
+    /// Body replacing the captured one.
+    body: Option<Value>,
+}
+
+/// Development tools can act as any user of any tenant, so they are only
+/// for platform admins, and only in development mode.
+fn require_dev_mode(state: &AppState) -> orbis_core::Result<()> {
+    if !state.config().plugin_dev_mode {
+        return Err(orbis_core::Error::not_found("Plugin development mode is not enabled"));
+    }
+    Ok(())
+}
+
+/// Get a running plugin.
+fn running_plugin(state: &AppState, name: &str) -> orbis_core::Result<orbis_plugin::PluginInfo> {
+    let info = state
+        .plugins()
+        .registry()
+        .get(name)
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+    if info.state != orbis_plugin::PluginState::Running {
+        return Err(orbis_core::Error::plugin(format!("Plugin '{}' is not running", name)));
+    }
+    Ok(info)
+}
+
+/// Invoke a plugin handler directly with a crafted context.
+///
+/// Route schemas, authentication and idempotency are bypassed, so handlers
+/// can be exercised with requests their routes would reject.
+async fn invoke_handler(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    Json(request): Json<InvokeRequest>,
+) -> ServerResult<Json<Value>> {
+    require_dev_mode(&state)?;
+    let info = running_plugin(&state, &name)?;
+
+    let path = request.path.unwrap_or_else(|| {
+        info.manifest
+            .routes
+            .iter()
+            .find(|route| route.handler == request.handler)
+            .map_or_else(|| format!("/{}", request.handler), |route| route.path.clone())
+    });
+    let context = orbis_plugin::PluginContext {
+        method: request.method.unwrap_or_else(|| "POST".to_owned()).to_uppercase(),
+        path,
+        headers: request.headers,
+        query: request.query,
+        body: request.body,
+        user_id: request.user.id,
+        is_admin: request.user.is_admin,
+        tenant_id: request.user.tenant_id,
+        deadline: None,
+        groups: request.user.groups,
+        features: std::collections::BTreeMap::new(),
+        cancellation: orbis_plugin::CancellationFlag::new(),
+    };
+
+    let started = Instant::now();
+    let result = state.plugins().execute_route(&name, &request.handler, context).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "result": result,
+            "duration_ms": started.elapsed().as_millis()
+        }
+    })))
+}
+
+/// List the requests captured for a plugin, most recent first.
+async fn list_requests(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<RequestsQuery>,
+) -> ServerResult<Json<Value>> {
+    require_dev_mode(&state)?;
+
+    let requests = state.plugins().capture().list(&name, query.handler.as_deref());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": requests
+    })))
+}
+
+/// Drop the requests captured for a plugin.
+async fn clear_requests(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    require_dev_mode(&state)?;
+
+    state.plugins().capture().clear(&name);
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Captured requests cleared"
+    })))
+}
+
+/// Replay a captured request against the plugin as it is now.
+async fn replay_request(
+    _admin: RequirePlatformAdmin,
+    Path((name, id)): Path<(String, Uuid)>,
+    State(state): State<AppState>,
+    request: Option<Json<ReplayRequest>>,
+) -> ServerResult<Json<Value>> {
+    require_dev_mode(&state)?;
+    running_plugin(&state, &name)?;
+
+    let Json(request) = request.unwrap_or_default();
+    let started = Instant::now();
+    let result = state.plugins().replay(&name, id, request.body).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "result": result,
+            "duration_ms": started.elapsed().as_millis()
+        }
+    })))
+}
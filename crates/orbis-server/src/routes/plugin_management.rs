@@ -1,36 +1,104 @@
 //! Plugin management routes (admin).
 
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::header,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use orbis_plugin::{AbiVersion, BulkAction, PluginFilters, PluginState, AccessPolicy, StateCause, StateTrigger};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError};
 
 use crate::error::ServerResult;
-use crate::extractors::AdminUser;
+use crate::events::{HubEvent, PLUGIN_RELOAD_EVENT, PLUGIN_STATE_EVENT};
+use crate::extractors::{Admin, RequirePlatformAdmin, RequireRole};
+use crate::jobs::{NewJob, PLUGIN_INSTALL_JOB};
+use crate::monitoring::{AlertPolicy, MetricResolution};
 use crate::state::AppState;
 
 /// Create plugin management router.
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/plugins", get(list_plugins))
+        .route("/plugins/compatibility", get(get_compatibility_report))
+        .route("/plugins/install", post(install_plugin))
+        .route("/plugins/registry/{name}", get(get_registry_plugin))
+        .route("/plugins/policy", get(get_security_policy).put(set_security_policy))
+        .route("/plugins/alerts", get(get_alert_policy).put(set_alert_policy))
+        .route("/plugins/reload/events", get(stream_reload_events))
+        .route("/plugins/state/events", get(stream_state_events))
+        .route("/plugins/bulk/{action}", post(bulk_plugin_action))
         .route("/plugins/{name}", get(get_plugin))
+        .route("/plugins/{name}/traps", get(get_plugin_traps))
+        .route("/plugins/{name}/history", get(get_plugin_history))
+        .route("/plugins/{name}/release-notes", get(get_release_notes))
+        .route("/plugins/{name}/handlers/stats", get(get_handler_stats))
+        .route("/plugins/{name}/metrics", get(get_plugin_metrics))
+        .route("/plugins/{name}/network", get(get_network_usage))
+        .route("/plugins/{name}/network/reset", post(reset_network_usage))
+        .route(
+            "/plugins/{name}/recording",
+            get(get_recording).post(start_recording).delete(stop_recording),
+        )
+        .route("/plugins/{name}/features", get(get_plugin_features))
+        .route("/plugins/{name}/features/{flag}", put(set_plugin_feature))
         .route("/plugins/{name}/enable", post(enable_plugin))
         .route("/plugins/{name}/disable", post(disable_plugin))
+        .route("/plugins/{name}/data/export", get(export_plugin_data))
+        .route("/plugins/{name}/data/import", post(import_plugin_data))
         .route("/plugins/{name}", delete(uninstall_plugin))
+        .route("/plugins/cache/clear", post(clear_module_cache))
 }
 
-/// List all plugins.
+/// Plugin list query params.
+#[derive(Debug, Deserialize)]
+struct PluginListQuery {
+    /// Text searched in names, descriptions and tags.
+    q: Option<String>,
+    /// Category filter.
+    category: Option<String>,
+    /// Tag filter.
+    tag: Option<String>,
+    /// State filter.
+    state: Option<PluginState>,
+    /// Page number, starting at 1.
+    page: Option<u32>,
+    /// Plugins per page.
+    limit: Option<u32>,
+}
+
+/// List plugins, optionally searched and filtered by category, tag and state.
 async fn list_plugins(
-    _admin: AdminUser,
+    _admin: RequireRole<Admin>,
     State(state): State<AppState>,
+    Query(query): Query<PluginListQuery>,
 ) -> ServerResult<Json<Value>> {
-    let plugins: Vec<_> = state
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let offset = (page - 1).saturating_mul(limit);
+
+    let filters = PluginFilters {
+        category: query.category,
+        tag: query.tag,
+        state: query.state,
+    };
+    let matching = state
         .plugins()
         .registry()
-        .list()
+        .search(query.q.as_deref().unwrap_or_default(), &filters);
+    let total = matching.len();
+
+    let plugins: Vec<_> = matching
         .iter()
+        .skip(offset as usize)
+        .take(limit as usize)
         .map(|info| {
             json!({
                 "id": info.id.to_string(),
@@ -38,10 +106,17 @@ async fn list_plugins(
                 "version": info.manifest.version,
                 "description": info.manifest.description,
                 "author": info.manifest.author,
+                "homepage": info.manifest.homepage,
+                "icon": info.manifest.icon,
+                "category": info.manifest.category,
+                "tags": info.manifest.tags,
+                "deprecated": info.manifest.deprecated,
+                "api_version": info.manifest.target_api_version(),
                 "state": format!("{:?}", info.state),
                 "routes_count": info.manifest.routes.len(),
                 "pages_count": info.manifest.pages.len(),
-                "loaded_at": info.loaded_at.to_rfc3339()
+                "loaded_at": info.loaded_at.to_rfc3339(),
+                "dependency_issues": info.dependency_issues
             })
         })
         .collect();
@@ -50,14 +125,127 @@ async fn list_plugins(
         "success": true,
         "data": {
             "plugins": plugins,
-            "total": plugins.len()
+            "total": total,
+            "page": page,
+            "limit": limit,
+            "load_report": state.plugins().registry().load_report()
+        }
+    })))
+}
+
+/// Stream hot reload lifecycle events as server-sent events.
+async fn stream_reload_events(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> Sse<impl futures_util::Stream<Item = Result<Event, axum::Error>>> {
+    stream_hub_events(state.events().subscribe(), PLUGIN_RELOAD_EVENT)
+}
+
+/// Stream plugin state changes as server-sent events.
+async fn stream_state_events(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> Sse<impl futures_util::Stream<Item = Result<Event, axum::Error>>> {
+    stream_hub_events(state.events().subscribe(), PLUGIN_STATE_EVENT)
+}
+
+/// Stream the events of the event hub with a name, with their cursor as event ID.
+fn stream_hub_events(
+    rx: broadcast::Receiver<HubEvent>,
+    name: &'static str,
+) -> Sse<impl futures_util::Stream<Item = Result<Event, axum::Error>>> {
+    let stream = futures_util::stream::unfold(rx, move |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) if event.event == name => {
+                    let data = Event::default().event(name).id(event.cursor).json_data(&event.data);
+                    return Some((data, rx));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event stream lagged, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Get the host API compatibility of every plugin checked, including refused ones.
+async fn get_compatibility_report(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "host_api_version": AbiVersion::CURRENT.to_semver().to_string(),
+            "plugins": state.plugins().registry().compatibility_report()
         }
     })))
 }
 
+/// Get the security policy host calls of plugins are checked against.
+async fn get_security_policy(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    Ok(Json(json!({
+        "success": true,
+        "data": state.plugins().runtime().policy().policy()
+    })))
+}
+
+/// Replace the security policy until the server restarts.
+async fn set_security_policy(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+    Json(policy): Json<AccessPolicy>,
+) -> ServerResult<Json<Value>> {
+    tracing::info!(
+        "Plugin security policy replaced: {} rules, {} tenant overrides, {:?} mode",
+        policy.rules.len(),
+        policy.tenants.len(),
+        policy.mode
+    );
+    state.plugins().runtime().policy().set_policy(policy);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": state.plugins().runtime().policy().policy()
+    })))
+}
+
+/// Get the resource alert rules and webhooks.
+async fn get_alert_policy(
+    _admin: RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    Ok(Json(json!({
+        "success": true,
+        "data": state.monitoring().policy()
+    })))
+}
+
+/// Replace the resource alert rules and webhooks until the server restarts.
+async fn set_alert_policy(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+    Json(policy): Json<AlertPolicy>,
+) -> ServerResult<Json<Value>> {
+    state.monitoring().set_policy(policy)?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": state.monitoring().policy()
+    })))
+}
+
 /// Get plugin details.
 async fn get_plugin(
-    _admin: AdminUser,
+    _admin: RequireRole<Admin>,
     Path(name): Path<String>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
@@ -75,22 +263,411 @@ async fn get_plugin(
             "author": info.manifest.author,
             "homepage": info.manifest.homepage,
             "license": info.manifest.license,
+            "deprecated": info.manifest.deprecated,
+            "api_version": info.manifest.target_api_version(),
+            "changelog_url": info.manifest.changelog_url,
             "state": format!("{:?}", info.state),
             "permissions": info.manifest.permissions,
             "routes": info.manifest.routes,
             "pages": info.manifest.pages,
-            "loaded_at": info.loaded_at.to_rfc3339()
+            "loaded_at": info.loaded_at.to_rfc3339(),
+            "dependency_issues": info.dependency_issues,
+            "compatibility": state.plugins().registry().compatibility(&name),
+            "snapshot": state.plugins().registry().snapshot(&name).map(|snapshot| json!({
+                "size_bytes": snapshot.size_bytes,
+                "taken_at": snapshot.taken_at.to_rfc3339(),
+                "age_seconds": snapshot.age_seconds()
+            }))
+        }
+    })))
+}
+
+/// Get recent trap reports of a plugin, most recent first.
+async fn get_plugin_traps(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    if state.plugins().registry().get(&name).is_none() {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "traps": state.plugins().registry().trap_reports(&name)
+        }
+    })))
+}
+
+/// Get the state changes of a plugin, most recent first, including changes
+/// recorded before it was unloaded.
+async fn get_plugin_history(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let history = state.plugins().registry().history();
+    if state.plugins().registry().get(&name).is_none() && !history.contains(&name) {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "history": history.get(&name)
+        }
+    })))
+}
+
+/// Get the release notes of a plugin's current version, with its last
+/// version change if it was installed or reloaded as a new version.
+async fn get_release_notes(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let info = state
+        .plugins()
+        .registry()
+        .get(&name)
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "version": info.manifest.version,
+            "release_notes": info.manifest.release_notes,
+            "changelog_url": info.manifest.changelog_url,
+            "deprecated": info.manifest.deprecated,
+            "api_version": info.manifest.target_api_version(),
+            "last_change": state.plugins().registry().version_change(&name)
+        }
+    })))
+}
+
+/// Get the network quotas of a plugin and its usage since the last reset.
+async fn get_network_usage(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let info = state
+        .plugins()
+        .registry()
+        .get(&name)
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Plugin '{}' not found", name)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "limits": info.manifest.limits,
+            "usage": state.plugins().registry().network_usage(&name)
+        }
+    })))
+}
+
+/// Reset the network usage of a plugin, restoring its full quotas.
+async fn reset_network_usage(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    if state.plugins().registry().get(&name).is_none() {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    state.plugins().registry().reset_network_usage(&name);
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "reset": true
+        }
+    })))
+}
+
+/// Most invocations recorded per request to record a plugin.
+const MAX_RECORDED_INVOCATIONS: usize = 100;
+
+/// Start recording request.
+#[derive(Debug, Default, Deserialize)]
+struct StartRecordingRequest {
+    /// Handler to record (defaults to all handlers of the plugin).
+    handler: Option<String>,
+
+    /// Number of invocations to record (defaults to 1).
+    invocations: Option<usize>,
+}
+
+/// Get the replay capture, if recording is enabled.
+fn replay_capture(state: &AppState) -> ServerResult<std::sync::Arc<orbis_plugin::ReplayCapture>> {
+    state.plugins().runtime().replay_capture().ok_or_else(|| {
+        orbis_core::Error::validation("Recording plugin handlers requires a plugin replay directory").into()
+    })
+}
+
+/// Get the handlers of a plugin being recorded for replay.
+async fn get_recording(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let capture = replay_capture(&state)?;
+    let targets: Vec<Value> = capture
+        .targets()
+        .into_iter()
+        .filter(|target| target.0 == name)
+        .map(|(_, handler, invocations)| json!({ "handler": handler, "invocations": invocations }))
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "directory": capture.dir(),
+            "recording": targets
+        }
+    })))
+}
+
+/// Record the next invocations of a plugin's handlers for replay.
+async fn start_recording(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    request: Option<Json<StartRecordingRequest>>,
+) -> ServerResult<Json<Value>> {
+    let capture = replay_capture(&state)?;
+    if state.plugins().registry().get(&name).is_none() {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    let Json(request) = request.unwrap_or_default();
+    let invocations = request.invocations.unwrap_or(1);
+    if !(1..=MAX_RECORDED_INVOCATIONS).contains(&invocations) {
+        return Err(orbis_core::Error::validation(format!(
+            "Invocations to record must be between 1 and {}",
+            MAX_RECORDED_INVOCATIONS
+        ))
+        .into());
+    }
+    capture.record(&name, request.handler.as_deref(), invocations);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "handler": request.handler,
+            "invocations": invocations
+        }
+    })))
+}
+
+/// Stop recording a plugin's handlers.
+async fn stop_recording(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    replay_capture(&state)?.stop(&name);
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Recording stopped"
+    })))
+}
+
+/// Get the feature flags of a plugin, with their overrides.
+async fn get_plugin_features(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let features = state.plugins().feature_flags(&name)?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "features": features
+        }
+    })))
+}
+
+/// Set feature flag request.
+#[derive(Debug, Deserialize)]
+struct SetFeatureRequest {
+    /// New value; `null` goes back to the manifest default.
+    enabled: Option<bool>,
+}
+
+/// Override a feature flag of a plugin, without reloading it.
+async fn set_plugin_feature(
+    admin: RequirePlatformAdmin,
+    Path((name, flag)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Json(request): Json<SetFeatureRequest>,
+) -> ServerResult<Json<Value>> {
+    let feature = state
+        .plugins()
+        .set_feature(&name, &flag, request.enabled, Some(admin.user_id))
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": feature
+    })))
+}
+
+/// Get the latency percentiles, error rates and recent slow invocations of a plugin's handlers.
+async fn get_handler_stats(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    if state.plugins().registry().get(&name).is_none() {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    let stats = state.plugins().runtime().handler_stats();
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "thresholds": stats.thresholds(),
+            "handlers": stats.plugin(&name)
+        }
+    })))
+}
+
+/// Plugin metrics query params.
+#[derive(Debug, Deserialize)]
+struct MetricsQuery {
+    /// Sample resolution.
+    #[serde(default)]
+    resolution: MetricResolution,
+    /// Hours of history to return.
+    hours: Option<u32>,
+}
+
+/// Longest history returned by the metrics route, in hours.
+const MAX_METRICS_HOURS: u32 = 30 * 24;
+
+/// Get the stored resource samples of a plugin, with its recent in-memory samples.
+async fn get_plugin_metrics(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    Query(query): Query<MetricsQuery>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    if state.plugins().registry().get(&name).is_none() {
+        return Err(orbis_core::Error::not_found(format!("Plugin '{}' not found", name)).into());
+    }
+
+    let hours = query.hours.unwrap_or(24).clamp(1, MAX_METRICS_HOURS);
+    let since = chrono::Utc::now()
+        .checked_sub_signed(chrono::TimeDelta::hours(i64::from(hours)))
+        .unwrap_or_default();
+    let samples = state.monitoring().history(&name, query.resolution, since).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "resolution": query.resolution,
+            "samples": samples
+        }
+    })))
+}
+
+/// Install plugin request.
+#[derive(Debug, Deserialize)]
+struct InstallPluginRequest {
+    /// Path of the plugin file or directory on the server.
+    #[serde(default)]
+    path: Option<String>,
+
+    /// Remote plugin file to fetch instead.
+    #[serde(default)]
+    remote: Option<orbis_plugin::RemoteSource>,
+
+    /// Plugin to install from the configured registry instead.
+    #[serde(default)]
+    registry: Option<RegistryPluginRequest>,
+}
+
+/// Plugin published in the registry.
+#[derive(Debug, Deserialize)]
+struct RegistryPluginRequest {
+    /// Plugin name.
+    name: String,
+
+    /// Version requirement, such as `^1.2`; the newest version when unset.
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Install a plugin in the background.
+async fn install_plugin(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+    Json(req): Json<InstallPluginRequest>,
+) -> ServerResult<Json<Value>> {
+    let payload = match (req.path, req.remote, req.registry) {
+        (Some(path), None, None) => json!({ "path": path }),
+        (None, Some(remote), None) => {
+            remote.validate()?;
+            json!({ "remote": remote })
+        }
+        (None, None, Some(plugin)) => {
+            // Resolved now, so the job installs the version reported here
+            let (version, remote) = state
+                .plugins()
+                .resolve_from_registry(&plugin.name, plugin.version.as_deref())
+                .await?;
+            json!({ "remote": remote, "version": version })
+        }
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) | (_, Some(_), Some(_)) | (None, None, None) => {
+            return Err(orbis_core::Error::validation(
+                "Provide one of a 'path', 'remote' or 'registry' plugin source",
+            )
+            .into());
+        }
+    };
+    let job = state.jobs().enqueue(NewJob::new(PLUGIN_INSTALL_JOB, payload)).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": job
+    })))
+}
+
+/// Get the versions of a plugin published in the configured registry.
+async fn get_registry_plugin(
+    _admin: RequireRole<Admin>,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let client = state
+        .plugins()
+        .registry_client()
+        .ok_or_else(|| orbis_core::Error::config("No plugin registry is configured"))?;
+    let index = client.index(&name).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "registry": client.url(),
+            "name": index.name,
+            "latest": index.resolve(None).ok().map(|version| &version.version),
+            "versions": index.versions
         }
     })))
 }
 
 /// Enable a plugin.
 async fn enable_plugin(
-    _admin: AdminUser,
+    admin: RequirePlatformAdmin,
     Path(name): Path<String>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
-    state.plugins().enable_plugin(&name).await?;
+    let cause = StateCause::new(StateTrigger::Manual).by(admin.user_id.to_string());
+    state.plugins().enable_plugin_with(&name, cause).await?;
 
     Ok(Json(json!({
         "success": true,
@@ -100,11 +677,12 @@ async fn enable_plugin(
 
 /// Disable a plugin.
 async fn disable_plugin(
-    _admin: AdminUser,
+    admin: RequirePlatformAdmin,
     Path(name): Path<String>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
-    state.plugins().disable_plugin(&name).await?;
+    let cause = StateCause::new(StateTrigger::Manual).by(admin.user_id.to_string());
+    state.plugins().disable_plugin_with(&name, cause).await?;
 
     Ok(Json(json!({
         "success": true,
@@ -112,16 +690,102 @@ async fn disable_plugin(
     })))
 }
 
+/// Bulk plugin action request.
+#[derive(Debug, Default, Deserialize)]
+struct BulkActionRequest {
+    /// Plugins to apply the action to; all plugins when unset.
+    #[serde(default)]
+    plugins: Option<Vec<String>>,
+}
+
+/// Enable, disable or reload many plugins in dependency order.
+///
+/// Failures don't stop the operation; the outcome of every plugin is
+/// reported.
+async fn bulk_plugin_action(
+    admin: RequirePlatformAdmin,
+    Path(action): Path<BulkAction>,
+    State(state): State<AppState>,
+    request: Option<Json<BulkActionRequest>>,
+) -> ServerResult<Json<Value>> {
+    let Json(request) = request.unwrap_or_default();
+    let cause = StateCause::new(match action {
+        BulkAction::Enable | BulkAction::Disable => StateTrigger::Manual,
+        BulkAction::Reload => StateTrigger::Reload,
+    })
+    .by(admin.user_id.to_string());
+    let report = state
+        .plugins()
+        .run_bulk(action, request.plugins.as_deref(), cause)
+        .await;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": report
+    })))
+}
+
+/// Export all data of a plugin as a ZIP archive.
+async fn export_plugin_data(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> ServerResult<impl IntoResponse> {
+    let archive = state.plugins().export_plugin_data(&name).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}-data.zip\"", name),
+            ),
+        ],
+        archive,
+    ))
+}
+
+/// Replace all data of a plugin with an exported ZIP archive sent as the request body.
+async fn import_plugin_data(
+    _admin: RequirePlatformAdmin,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    body: Bytes,
+) -> ServerResult<Json<Value>> {
+    state.plugins().import_plugin_data(&name, &body).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": format!("Data of plugin '{}' imported", name)
+    })))
+}
+
 /// Uninstall a plugin.
 async fn uninstall_plugin(
-    _admin: AdminUser,
+    admin: RequirePlatformAdmin,
     Path(name): Path<String>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
-    state.plugins().unload_plugin(&name).await?;
+    let cause = StateCause::new(StateTrigger::Unload).by(admin.user_id.to_string());
+    state.plugins().unload_plugin_with(&name, cause).await?;
 
     Ok(Json(json!({
         "success": true,
         "message": format!("Plugin '{}' uninstalled", name)
     })))
 }
+
+/// Clear the precompiled plugin module cache.
+async fn clear_module_cache(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let removed = state.plugins().clear_module_cache()?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "removed": removed
+        }
+    })))
+}
@@ -3,23 +3,34 @@
 use axum::{
     body::Body,
     extract::{Path, State},
-    http::{Method, Request, Uri},
+    http::{header, HeaderName, HeaderValue, Method, Request, StatusCode, Uri},
+    response::{IntoResponse, Response},
     routing::any,
     Json, Router,
 };
+use orbis_plugin::{IdempotencyStatus, IdempotencyStore};
 use serde_json::{json, Value};
+use std::time::Duration;
 
 use crate::error::ServerResult;
-use crate::extractors::OptionalUser;
+use crate::extractors::{AuthUser, CurrentTenant, OptionalAuthUser};
+use crate::middleware::{conditional_middleware, response_cache_middleware, RequestDeadline};
 use crate::state::AppState;
 
 /// Create plugin routes router.
-pub fn router(_state: AppState) -> Router<AppState> {
+pub fn router(state: AppState) -> Router<AppState> {
     Router::new()
-        // Dynamic plugin route handler
-        .route("/{plugin}/{*path}", any(handle_plugin_route))
-        // Plugin pages/UI endpoint
+        // Dynamic plugin route handler, with cached responses for routes declaring caching
+        // and conditional GET requests
+        .route(
+            "/{plugin}/{*path}",
+            any(handle_plugin_route)
+                .layer(axum::middleware::from_fn_with_state(state, response_cache_middleware))
+                .layer(axum::middleware::from_fn(conditional_middleware)),
+        )
+        // Plugin pages/UI endpoints
         .route("/{plugin}/pages", axum::routing::get(get_plugin_pages))
+        .route("/{plugin}/pages/{*route}", axum::routing::get(get_plugin_page))
 }
 
 /// Parse query string into HashMap.
@@ -80,10 +91,11 @@ fn percent_decode(input: &str) -> String {
 async fn handle_plugin_route(
     Path((plugin_name, path)): Path<(String, String)>,
     State(state): State<AppState>,
-    user: OptionalUser,
+    user: OptionalAuthUser,
+    tenant: CurrentTenant,
     method: Method,
     request: Request<Body>,
-) -> ServerResult<Json<Value>> {
+) -> ServerResult<Response> {
     // Find the plugin
     let info = state.plugins().registry().get(&plugin_name).ok_or_else(|| {
         orbis_core::Error::not_found(format!("Plugin '{}' not found", plugin_name))
@@ -116,8 +128,26 @@ async fn handle_plugin_route(
         return Err(orbis_core::Error::auth("Authentication required").into());
     }
 
+    // Routes restricted to groups are only available to their members
+    let viewer = user.0.as_ref().map(AuthUser::access);
+    if !route.is_accessible_by(viewer.as_ref()) {
+        return Err(orbis_core::Error::unauthorized("Access to this route is not allowed").into());
+    }
+
+    // Data loaded by pages is only available to viewers allowed on one of them
+    let pages: Vec<_> = info
+        .manifest
+        .pages
+        .iter()
+        .filter(|page| page.fetches(&route.handler))
+        .collect();
+    if !pages.is_empty() && !pages.iter().any(|page| page.is_accessible_by(viewer.as_ref())) {
+        return Err(orbis_core::Error::unauthorized("Access to this page is not allowed").into());
+    }
+
     // Parse query parameters
     let query_params = parse_query_string(request.uri());
+    let deadline = request.extensions().get::<RequestDeadline>().map(|deadline| deadline.0);
 
     // Collect headers before consuming request
     let headers: std::collections::HashMap<String, String> = request
@@ -130,11 +160,19 @@ async fn handle_plugin_route(
 
     // Parse body for POST/PUT/PATCH requests
     let body = if matches!(method, Method::POST | Method::PUT | Method::PATCH) {
+        // Routes and plugins may set their own limit, within the server's
+        let server = &state.config().server;
+        let limit = route
+            .max_body_size
+            .or(info.manifest.max_body_size)
+            .unwrap_or(server.plugin_max_body_size)
+            .min(server.max_body_size);
+
         // Try to parse body as JSON
         let (_parts, body) = request.into_parts();
-        let bytes = axum::body::to_bytes(body, 1024 * 1024) // 1MB limit
-            .await
-            .map_err(|e| orbis_core::Error::plugin(format!("Failed to read body: {}", e)))?;
+        let bytes = axum::body::to_bytes(body, limit).await.map_err(|_too_large| {
+            orbis_core::Error::payload_too_large(format!("Request body is larger than {} bytes", limit))
+        })?;
         
         if bytes.is_empty() {
             serde_json::Value::Null
@@ -149,6 +187,51 @@ async fn handle_plugin_route(
         serde_json::Value::Null
     };
 
+    // Reject requests not matching the route's schemas before invoking the handler
+    if let Some(schema) = &route.request {
+        let violations = schema.check(&query_params, &body);
+        if !violations.is_empty() {
+            return Ok(validation_failed(&violations));
+        }
+    }
+
+    // Retries of requests with an idempotency key get the first response
+    let idempotency_key = match headers.get(orbis_plugin::IDEMPOTENCY_KEY_HEADER) {
+        Some(key) if !key.is_empty() && matches!(method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE) => {
+            if key.len() > orbis_plugin::MAX_IDEMPOTENCY_KEY_LEN {
+                return Err(orbis_core::Error::validation(format!(
+                    "Idempotency key is longer than {} bytes",
+                    orbis_plugin::MAX_IDEMPOTENCY_KEY_LEN
+                ))
+                .into());
+            }
+
+            let key = IdempotencyStore::key(
+                &plugin_name,
+                &format!("{} {}", method, route_path),
+                tenant.id().map(|id| id.to_string()).as_deref(),
+                user.0.as_ref().map(|u| u.user_id.to_string()).as_deref(),
+                key,
+            );
+            let fingerprint = IdempotencyStore::fingerprint(method.as_str(), &query_params, &body);
+            let lease = Duration::from_secs(state.config().server.request_timeout_seconds);
+            match state.plugins().idempotency().begin(&key, &fingerprint, lease) {
+                IdempotencyStatus::Started => Some(key),
+                IdempotencyStatus::Replay(body) => return Ok(replayed_response(body)),
+                IdempotencyStatus::InFlight => {
+                    return Err(orbis_core::Error::conflict("A request with this idempotency key is in progress").into());
+                }
+                IdempotencyStatus::Mismatch => {
+                    return Err(orbis_core::Error::conflict(
+                        "Idempotency key was already used for a different request",
+                    )
+                    .into());
+                }
+            }
+        }
+        _ => None,
+    };
+
     // Build plugin context
     let context = orbis_plugin::PluginContext {
         method: method.to_string(),
@@ -158,55 +241,168 @@ async fn handle_plugin_route(
         body,
         user_id: user.0.as_ref().map(|u| u.user_id.to_string()),
         is_admin: user.0.as_ref().is_some_and(|u| u.is_admin),
+        tenant_id: tenant.id().map(|id| id.to_string()),
+        deadline,
+        groups: user.0.as_ref().map(|u| u.groups().to_vec()).unwrap_or_default(),
+        features: std::collections::BTreeMap::new(),
+        cancellation: orbis_plugin::CancellationFlag::new(),
     };
 
     // Execute plugin handler
     let result = state
         .plugins()
         .execute_route(&plugin_name, &route.handler, context)
-        .await?;
+        .await;
+    let mut result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            if let Some(key) = &idempotency_key {
+                state.plugins().idempotency().release(key);
+            }
+            return Err(e.into());
+        }
+    };
 
-    Ok(Json(json!({
+    // Handler errors are answered like core errors
+    if let Some(response) = problem_response(&result) {
+        if let Some(key) = &idempotency_key {
+            state.plugins().idempotency().release(key);
+        }
+        return Ok(response);
+    }
+
+    let idempotent = result
+        .as_object_mut()
+        .and_then(|result| result.remove("idempotent"))
+        .is_some_and(|idempotent| idempotent == Value::Bool(true));
+    let body = json!({
         "success": true,
-        "data": result
-    })))
+        "data": &result
+    });
+    if let Some(key) = &idempotency_key {
+        let store = state.plugins().idempotency();
+        if idempotent {
+            let ttl = Duration::from_secs(state.config().server.idempotency_ttl_seconds);
+            store.complete(key, body.to_string(), ttl);
+        } else {
+            store.release(key);
+        }
+    }
+
+    let mut response = Json(body).into_response();
+    if method == Method::GET {
+        copy_validators(&result, response.headers_mut());
+    }
+    Ok(response)
+}
+
+/// Turn a handler's RFC 7807 problem response into the server's error response.
+fn problem_response(result: &Value) -> Option<Response> {
+    let status = result
+        .get("status")
+        .and_then(Value::as_u64)
+        .and_then(|status| u16::try_from(status).ok())
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .filter(|status| status.is_client_error() || status.is_server_error())?;
+    let is_problem = result
+        .get("headers")
+        .and_then(Value::as_object)
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str()))
+        })
+        .and_then(|(_, value)| value.as_str())
+        .is_some_and(|value| value.starts_with("application/problem+json"));
+    if !is_problem {
+        return None;
+    }
+
+    let problem = result.get("body").cloned().unwrap_or_default();
+    let message = problem
+        .get("detail")
+        .or_else(|| problem.get("title"))
+        .and_then(Value::as_str)
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("Plugin error"));
+    let mut error = json!({
+        "code": problem.get("code").and_then(Value::as_str).unwrap_or("PLUGIN_ERROR"),
+        "message": message
+    });
+    if let Some(details) = problem.get("details") {
+        error["details"] = details.clone();
+    }
+
+    let body = Json(json!({
+        "success": false,
+        "error": error
+    }));
+    Some((status, body).into_response())
+}
+
+/// Build the response to a retry from the body of the first response.
+fn replayed_response(body: String) -> Response {
+    let mut response = Response::new(Body::from(body));
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("idempotency-replayed", HeaderValue::from_static("true"));
+    response
+}
+
+/// Answer a request not matching its route's schemas with every violation.
+fn validation_failed(violations: &[orbis_plugin::RequestViolation]) -> Response {
+    let body = Json(json!({
+        "success": false,
+        "error": {
+            "code": "VALIDATION_ERROR",
+            "message": "Request does not match the route's schema",
+            "details": violations
+        }
+    }));
+    (StatusCode::BAD_REQUEST, body).into_response()
+}
+
+/// Copy the `ETag` and `Last-Modified` headers a handler's response sets to
+/// the HTTP response.
+fn copy_validators(result: &Value, headers: &mut axum::http::HeaderMap) {
+    let Some(plugin_headers) = result.get("headers").and_then(Value::as_object) else {
+        return;
+    };
+
+    for (name, value) in plugin_headers {
+        let Some(value) = value.as_str() else {
+            continue;
+        };
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        if (name == header::ETAG || name == header::LAST_MODIFIED)
+            && let Ok(value) = HeaderValue::from_str(value)
+        {
+            headers.insert(name, value);
+        }
+    }
 }
 
 /// Get plugin pages for UI rendering.
 async fn get_plugin_pages(
     Path(plugin_name): Path<String>,
     State(state): State<AppState>,
-    user: OptionalUser,
+    user: OptionalAuthUser,
 ) -> ServerResult<Json<Value>> {
     let info = state.plugins().registry().get(&plugin_name).ok_or_else(|| {
         orbis_core::Error::not_found(format!("Plugin '{}' not found", plugin_name))
     })?;
 
-    // Filter pages based on auth requirements
+    // Filter pages based on auth, role and permission requirements, and
+    // feature flags
+    let viewer = user.0.as_ref().map(AuthUser::access);
+    let features = state.plugins().resolved_features(&plugin_name);
     let pages: Vec<_> = info
         .manifest
         .pages
         .iter()
-        .filter(|page| !page.requires_auth || user.0.is_some())
-        .map(|page| {
-            json!({
-                "route": page.full_route(&plugin_name),
-                "title": page.title,
-                "icon": page.icon,
-                "description": page.description,
-                "show_in_menu": page.show_in_menu,
-                "menu_order": page.menu_order,
-                "sections": page.sections,
-                "state": page.state,
-                "computed": page.computed,
-                "actions": page.actions,
-                "hooks": page.hooks,
-                "dialogs": page.dialogs,
-                "requires_auth": page.requires_auth,
-                "permissions": page.permissions,
-                "roles": page.roles
-            })
-        })
+        .filter(|page| page.is_accessible_by(viewer.as_ref()) && page.is_enabled_by(&features))
+        .map(|page| page_json(&page.with_features(&features), &plugin_name))
         .collect();
 
     Ok(Json(json!({
@@ -216,3 +412,167 @@ async fn get_plugin_pages(
         }
     })))
 }
+
+/// Get a plugin page, with the data of its prefetch calls.
+///
+/// Lazily activated plugins are activated when one of their pages is opened.
+///
+/// Prefetch calls run concurrently, with the query parameters of this
+/// request mapped onto theirs. Results are returned under `prefetched`, by
+/// state field; failed calls are reported under `prefetch_errors` instead, so
+/// the client can load them itself.
+async fn get_plugin_page(
+    Path((plugin_name, route)): Path<(String, String)>,
+    State(state): State<AppState>,
+    user: OptionalAuthUser,
+    tenant: CurrentTenant,
+    request: Request<Body>,
+) -> ServerResult<Json<Value>> {
+    let info = state.plugins().registry().get(&plugin_name).ok_or_else(|| {
+        orbis_core::Error::not_found(format!("Plugin '{}' not found", plugin_name))
+    })?;
+
+    let route = format!("/{}", route);
+    let features = state.plugins().resolved_features(&plugin_name);
+    let page = info
+        .manifest
+        .pages
+        .iter()
+        .find(|page| page.route == route && page.is_enabled_by(&features))
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Page '{}' not found in plugin '{}'", route, plugin_name)))?;
+
+    let viewer = user.0.as_ref().map(AuthUser::access);
+    if !page.is_accessible_by(viewer.as_ref()) {
+        return Err(if user.0.is_none() {
+            orbis_core::Error::auth("Authentication required")
+        } else {
+            orbis_core::Error::unauthorized("Access to this page is not allowed")
+        }
+        .into());
+    }
+
+    // Opening a page counts as using its plugin, so lazily activated plugins
+    // are instantiated before the page loads its data
+    if info.state == orbis_plugin::PluginState::Running
+        && let Err(e) = state.plugins().activate(&plugin_name).await
+    {
+        tracing::warn!("Failed to activate plugin {} for page {}: {}", plugin_name, page.route, e);
+    }
+
+    let mut prefetched = serde_json::Map::new();
+    let mut errors = serde_json::Map::new();
+    if !page.prefetch.is_empty() {
+        if info.state != orbis_plugin::PluginState::Running {
+            return Err(orbis_core::Error::plugin(format!("Plugin '{}' is not running", plugin_name)).into());
+        }
+
+        // Calls are made as if the client made them along with this request
+        let context = orbis_plugin::PluginContext {
+            method: Method::GET.to_string(),
+            path: route,
+            headers: request
+                .headers()
+                .iter()
+                .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), v.to_string())))
+                .collect(),
+            query: parse_query_string(request.uri()),
+            body: Value::Null,
+            user_id: user.0.as_ref().map(|u| u.user_id.to_string()),
+            is_admin: user.0.as_ref().is_some_and(|u| u.is_admin),
+            tenant_id: tenant.id().map(|id| id.to_string()),
+            deadline: request.extensions().get::<RequestDeadline>().map(|deadline| deadline.0),
+            groups: user.0.as_ref().map(|u| u.groups().to_vec()).unwrap_or_default(),
+            features: std::collections::BTreeMap::new(),
+            cancellation: orbis_plugin::CancellationFlag::new(),
+        };
+
+        let calls = page
+            .prefetch
+            .iter()
+            .map(|call| prefetch(&state, &info.manifest, &plugin_name, call, &context));
+        for (call, result) in page.prefetch.iter().zip(futures_util::future::join_all(calls).await) {
+            match result {
+                Ok(data) => {
+                    prefetched.insert(call.state.clone(), data);
+                }
+                Err(e) => {
+                    tracing::debug!("Prefetching {} of page {} failed: {}", call.handler, page.route, e);
+                    errors.insert(call.state.clone(), Value::String(e.to_string()));
+                }
+            }
+        }
+    }
+
+    let mut page = page_json(&page.with_features(&features), &plugin_name);
+    if let Some(fields) = page.as_object_mut() {
+        fields.insert("prefetched".to_owned(), Value::Object(prefetched));
+        fields.insert("prefetch_errors".to_owned(), Value::Object(errors));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": page
+    })))
+}
+
+/// Make a prefetch call of a page through the `GET` route of its handler.
+async fn prefetch(
+    state: &AppState,
+    manifest: &orbis_plugin::PluginManifest,
+    plugin_name: &str,
+    call: &orbis_plugin::PrefetchCall,
+    page_context: &orbis_plugin::PluginContext,
+) -> orbis_core::Result<Value> {
+    let route = manifest
+        .routes
+        .iter()
+        .find(|r| r.handler == call.handler && r.method.eq_ignore_ascii_case("GET"))
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Handler '{}' has no GET route", call.handler)))?;
+
+    if route.requires_auth && page_context.user_id.is_none() {
+        return Err(orbis_core::Error::auth("Authentication required"));
+    }
+
+    let query = call
+        .map_args
+        .iter()
+        .filter_map(|arg| page_context.query.get(&arg.from).map(|value| (arg.to.clone(), value.clone())))
+        .collect();
+    if let Some(schema) = &route.request {
+        let violations = schema.check(&query, &Value::Null);
+        if let Some(violation) = violations.first() {
+            return Err(orbis_core::Error::validation(format!("{}: {}", violation.path, violation.message)));
+        }
+    }
+
+    let context = orbis_plugin::PluginContext {
+        path: route.path.clone(),
+        query,
+        cancellation: orbis_plugin::CancellationFlag::new(),
+        ..page_context.clone()
+    };
+    state.plugins().execute_route(plugin_name, &route.handler, context).await
+}
+
+/// Describe a page for UI rendering.
+fn page_json(page: &orbis_plugin::PageDefinition, plugin_name: &str) -> Value {
+    json!({
+        "route": page.full_route(plugin_name),
+        "title": page.title,
+        "icon": page.icon,
+        "description": page.description,
+        "show_in_menu": page.show_in_menu,
+        "menu_order": page.menu_order,
+        "sections": page.sections,
+        "state": page.state,
+        "computed": page.computed,
+        "actions": page.actions,
+        "hooks": page.hooks,
+        "dialogs": page.dialogs,
+        "cache": page.cache,
+        "prefetch": page.prefetch,
+        "requires_auth": page.requires_auth,
+        "permissions": page.permissions,
+        "roles": page.roles
+    })
+}
@@ -5,13 +5,14 @@ use axum::{
     routing::{delete, get, post, put},
     Json, Router,
 };
+use orbis_core::ProfileId;
+use orbis_plugin::HookEvent;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use sqlx::Row;
-use uuid::Uuid;
 
 use crate::error::ServerResult;
-use crate::extractors::AuthenticatedUser;
+use crate::extractors::AuthUser;
 use crate::state::AppState;
 
 /// Create profiles router.
@@ -36,7 +37,7 @@ struct CreateProfileRequest {
 
 /// List user's profiles.
 async fn list_profiles(
-    user: AuthenticatedUser,
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     let db = state.db();
@@ -55,7 +56,7 @@ async fn list_profiles(
             rows.into_iter()
                 .map(|row| {
                     json!({
-                        "id": row.get::<Uuid, _>("id").to_string(),
+                        "id": row.get::<ProfileId, _>("id").to_string(),
                         "name": row.get::<String, _>("name"),
                         "server_url": row.get::<Option<String>, _>("server_url"),
                         "is_default": row.get::<bool, _>("is_default"),
@@ -103,12 +104,12 @@ async fn list_profiles(
 
 /// Create a new profile.
 async fn create_profile(
-    user: AuthenticatedUser,
+    user: AuthUser,
     State(state): State<AppState>,
     Json(req): Json<CreateProfileRequest>,
 ) -> ServerResult<Json<Value>> {
     let db = state.db();
-    let profile_id = Uuid::new_v4();
+    let profile_id = ProfileId::generate();
     let is_default = req.is_default.unwrap_or(false);
     let use_tls = req.use_tls.unwrap_or(true);
 
@@ -179,8 +180,8 @@ async fn create_profile(
 
 /// Get a profile by ID.
 async fn get_profile(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<ProfileId>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     let db = state.db();
@@ -199,7 +200,7 @@ async fn get_profile(
 
             row.map(|r| {
                 json!({
-                    "id": r.get::<Uuid, _>("id").to_string(),
+                    "id": r.get::<ProfileId, _>("id").to_string(),
                     "name": r.get::<String, _>("name"),
                     "server_url": r.get::<Option<String>, _>("server_url"),
                     "is_default": r.get::<bool, _>("is_default"),
@@ -253,8 +254,8 @@ struct UpdateProfileRequest {
 
 /// Update a profile.
 async fn update_profile(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<ProfileId>,
     State(state): State<AppState>,
     Json(req): Json<UpdateProfileRequest>,
 ) -> ServerResult<Json<Value>> {
@@ -353,8 +354,8 @@ async fn update_profile(
 
 /// Delete a profile.
 async fn delete_profile(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<ProfileId>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     let db = state.db();
@@ -392,8 +393,8 @@ async fn delete_profile(
 
 /// Set a profile as default.
 async fn set_default_profile(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<ProfileId>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     let db = state.db();
@@ -441,6 +442,13 @@ async fn set_default_profile(
         }
     }
 
+    let plugins = state.plugins_arc();
+    let user_id = user.user_id.to_string();
+    let event = HookEvent::ProfileSwitched { profile: id.to_string() };
+    tokio::spawn(async move {
+        plugins.run_hooks(&event, Some(user_id)).await;
+    });
+
     Ok(Json(json!({
         "success": true,
         "message": "Default profile updated"
@@ -0,0 +1,59 @@
+//! Global search routes.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use orbis_plugin::SearchRequest;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::ServerResult;
+use crate::extractors::{AuthUser, CurrentTenant};
+use crate::state::AppState;
+
+/// Create search router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/search", get(search))
+}
+
+/// Search query parameters.
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    /// Search text.
+    #[serde(default)]
+    q: String,
+
+    /// Maximum number of results to return.
+    #[serde(default)]
+    limit: usize,
+}
+
+/// Search all plugins with a search provider the user may access.
+///
+/// Results are merged and sorted by relevance; providers that fail or time
+/// out are listed in `failed`.
+async fn search(
+    user: AuthUser,
+    tenant: CurrentTenant,
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> ServerResult<Json<Value>> {
+    let response = state
+        .plugins_arc()
+        .search(SearchRequest {
+            query: query.q,
+            limit: query.limit,
+            viewer: Some(user.access()),
+            user_id: Some(user.user_id.to_string()),
+            is_admin: user.is_admin,
+            tenant_id: tenant.id().map(|id| id.to_string()),
+        })
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response
+    })))
+}
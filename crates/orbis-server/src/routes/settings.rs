@@ -1,99 +1,100 @@
 //! Settings routes.
 
 use axum::{
-    extract::State,
-    routing::{get, put},
+    extract::{Query, State},
+    routing::get,
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
+use orbis_plugin::SettingScope;
+use serde::Deserialize;
 use serde_json::{json, Value};
-use sqlx::Row;
+use std::collections::BTreeMap;
+use orbis_core::{ProfileId, UserId};
 
 use crate::error::ServerResult;
-use crate::extractors::AdminUser;
+use crate::extractors::AuthUser;
 use crate::state::AppState;
 
-/// Setting value from database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Setting {
-    pub key: String,
-    pub value: Value,
-    pub description: Option<String>,
-    pub is_secret: bool,
-}
-
 /// Create settings router.
 pub fn router() -> Router<AppState> {
     Router::new()
-        .route("/settings", get(get_settings))
-        .route("/settings", put(update_settings))
+        .route("/settings", get(get_settings).patch(patch_settings))
+        .route("/settings/definitions", get(get_definitions))
 }
 
-/// Get application settings (admin only).
-async fn get_settings(
-    _admin: AdminUser,
+/// Settings query parameters.
+#[derive(Debug, Deserialize)]
+struct SettingsQuery {
+    /// Scope to read (defaults to system).
+    #[serde(default)]
+    scope: SettingScope,
+
+    /// Profile to read (profile scope only).
+    profile: Option<ProfileId>,
+
+    /// Comma-separated keys to read (defaults to every setting of the scope).
+    keys: Option<String>,
+}
+
+/// Patch settings request.
+#[derive(Debug, Deserialize)]
+struct PatchSettingsRequest {
+    /// Scope to write (defaults to system).
+    #[serde(default)]
+    scope: SettingScope,
+
+    /// Profile to write (profile scope only).
+    profile: Option<ProfileId>,
+
+    /// New values by key; `null` resets a setting to its default.
+    values: BTreeMap<String, Value>,
+}
+
+/// Get all setting definitions.
+async fn get_definitions(
+    _user: AuthUser,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
-    let db = state.db();
-    
-    let settings = match db.pool() {
-        orbis_db::DatabasePool::Postgres(pool) => {
-            let rows = sqlx::query(
-                "SELECT key, value, description, is_secret FROM settings ORDER BY key"
-            )
-            .fetch_all(pool)
-            .await
-            .map_err(|e| orbis_core::Error::database(e.to_string()))?;
-
-            rows.into_iter()
-                .map(|row| Setting {
-                    key: row.get("key"),
-                    value: row.get("value"),
-                    description: row.get("description"),
-                    is_secret: row.get("is_secret"),
-                })
-                .collect::<Vec<_>>()
-        }
-        orbis_db::DatabasePool::Sqlite(pool) => {
-            let rows = sqlx::query(
-                "SELECT key, value, description, is_secret FROM settings ORDER BY key"
-            )
-            .fetch_all(pool)
-            .await
-            .map_err(|e| orbis_core::Error::database(e.to_string()))?;
-
-            rows.into_iter()
-                .map(|row| {
-                    let value_str: String = row.get("value");
-                    Setting {
-                        key: row.get("key"),
-                        value: serde_json::from_str(&value_str).unwrap_or(Value::Null),
-                        description: row.get("description"),
-                        is_secret: row.get("is_secret"),
-                    }
-                })
-                .collect::<Vec<_>>()
+    let definitions: Vec<_> = state.settings().definitions().into_values().collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "definitions": definitions
         }
-    };
+    })))
+}
+
+/// Get settings of a scope.
+///
+/// System settings are platform admin only; profile settings require owning the profile.
+async fn get_settings(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<SettingsQuery>,
+) -> ServerResult<Json<Value>> {
+    let scope_id = resolve_scope(&user, &state, query.scope, query.profile).await?;
+    let keys: Option<Vec<String>> = query.keys.map(|keys| {
+        keys.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(String::from)
+            .collect()
+    });
 
-    // Filter out secret values, only show that they exist
-    let filtered_settings: Vec<Value> = settings
+    let settings = state.settings();
+    let definitions = settings.definitions();
+    let values: BTreeMap<String, Value> = settings
+        .get_many(query.scope, scope_id.as_deref(), keys.as_deref())
+        .await?
         .into_iter()
-        .map(|s| {
-            if s.is_secret {
-                json!({
-                    "key": s.key,
-                    "value": "[REDACTED]",
-                    "description": s.description,
-                    "is_secret": true
-                })
+        .map(|(key, value)| {
+            // Secrets are only shown to exist
+            let secret = definitions.get(&key).is_some_and(|definition| definition.secret);
+            if secret && !value.is_null() {
+                (key, json!("[REDACTED]"))
             } else {
-                json!({
-                    "key": s.key,
-                    "value": s.value,
-                    "description": s.description,
-                    "is_secret": false
-                })
+                (key, value)
             }
         })
         .collect();
@@ -101,83 +102,79 @@ async fn get_settings(
     Ok(Json(json!({
         "success": true,
         "data": {
-            "settings": filtered_settings
+            "scope": query.scope,
+            "values": values
         }
     })))
 }
 
-/// Update settings request.
-#[derive(Debug, Deserialize)]
-struct UpdateSettingsRequest {
-    settings: Vec<SettingUpdate>,
-}
+/// Update several settings of a scope at once.
+async fn patch_settings(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<PatchSettingsRequest>,
+) -> ServerResult<Json<Value>> {
+    let scope_id = resolve_scope(&user, &state, request.scope, request.profile).await?;
 
-/// Setting update.
-#[derive(Debug, Deserialize)]
-struct SettingUpdate {
-    key: String,
-    value: Value,
-    description: Option<String>,
-    is_secret: Option<bool>,
+    let changes = state
+        .settings()
+        .patch(request.scope, scope_id.as_deref(), request.values, Some(user.user_id))
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "updated": changes.iter().map(|change| &change.key).collect::<Vec<_>>()
+        }
+    })))
 }
 
-/// Update application settings (admin only).
-async fn update_settings(
-    _admin: AdminUser,
-    State(state): State<AppState>,
-    Json(request): Json<UpdateSettingsRequest>,
-) -> ServerResult<Json<Value>> {
-    let db = state.db();
-
-    match db.pool() {
-        orbis_db::DatabasePool::Postgres(pool) => {
-            for setting in request.settings {
-                let is_secret = setting.is_secret.unwrap_or(false);
-                sqlx::query(
-                    r#"
-                    INSERT INTO settings (key, value, description, is_secret, updated_at)
-                    VALUES ($1, $2, $3, $4, NOW())
-                    ON CONFLICT (key) DO UPDATE SET
-                        value = EXCLUDED.value,
-                        description = COALESCE(EXCLUDED.description, settings.description),
-                        is_secret = COALESCE(EXCLUDED.is_secret, settings.is_secret),
-                        updated_at = NOW()
-                    "#
-                )
-                .bind(&setting.key)
-                .bind(&setting.value)
-                .bind(&setting.description)
-                .bind(is_secret)
-                .execute(pool)
-                .await
-                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+/// Check access to a scope and get the ID its values are stored under.
+async fn resolve_scope(
+    user: &AuthUser,
+    state: &AppState,
+    scope: SettingScope,
+    profile: Option<ProfileId>,
+) -> orbis_core::Result<Option<String>> {
+    match scope {
+        SettingScope::System => {
+            // System settings apply to every tenant
+            if !user.is_admin || user.tenant_id.is_some() {
+                return Err(orbis_core::Error::unauthorized("Platform admin privileges required"));
             }
+            Ok(None)
         }
-        orbis_db::DatabasePool::Sqlite(pool) => {
-            for setting in request.settings {
-                let is_secret = setting.is_secret.unwrap_or(false);
-                let value_str = serde_json::to_string(&setting.value)
-                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
-                    
-                sqlx::query(
-                    r#"
-                    INSERT OR REPLACE INTO settings (key, value, description, is_secret, updated_at)
-                    VALUES ($1, $2, $3, $4, datetime('now'))
-                    "#
-                )
-                .bind(&setting.key)
-                .bind(&value_str)
-                .bind(&setting.description)
-                .bind(is_secret)
-                .execute(pool)
-                .await
-                .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+        SettingScope::User => Ok(Some(user.user_id.to_string())),
+        SettingScope::Profile => {
+            let profile = profile
+                .ok_or_else(|| orbis_core::Error::validation("Profile settings require a profile"))?;
+
+            if !owns_profile(state, profile, user.user_id).await? {
+                return Err(orbis_core::Error::not_found("Profile not found"));
             }
+            Ok(Some(profile.to_string()))
         }
     }
+}
 
-    Ok(Json(json!({
-        "success": true,
-        "message": "Settings updated"
-    })))
+/// Check whether a profile belongs to a user.
+async fn owns_profile(state: &AppState, profile: ProfileId, user_id: UserId) -> orbis_core::Result<bool> {
+    let query = "SELECT COUNT(*) FROM profiles WHERE id = $1 AND user_id = $2";
+
+    let count: i64 = match state.db().pool() {
+        orbis_db::DatabasePool::Postgres(pool) => sqlx::query_scalar(query)
+            .bind(profile)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| orbis_core::Error::database(e.to_string()))?,
+        orbis_db::DatabasePool::Sqlite(pool) => sqlx::query_scalar(query)
+            .bind(profile.to_string())
+            .bind(user_id.to_string())
+            .fetch_one(pool)
+            .await
+            .map_err(|e| orbis_core::Error::database(e.to_string()))?,
+    };
+
+    Ok(count > 0)
 }
@@ -0,0 +1,92 @@
+//! Plugin object storage routes.
+//!
+//! Download URLs of objects in local storage point here, carrying their
+//! expiry and signature, so users can download large plugin files without
+//! credentials or going through the plugin. S3 download URLs point at the
+//! bucket instead.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use orbis_plugin::{ObjectStore, PLUGIN_FILES_PREFIX};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::error::ServerResult;
+use crate::extractors::RequirePlatformAdmin;
+use crate::state::AppState;
+
+/// Create storage router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/storage/{*key}", get(download))
+        .route("/system/storage/migrate", post(migrate))
+}
+
+/// Signed download query parameters.
+#[derive(Debug, Deserialize)]
+struct DownloadQuery {
+    /// Unix time the URL expires at.
+    expires: i64,
+
+    /// Signature of the key and expiry.
+    signature: String,
+}
+
+/// Get the object storage of plugin files.
+fn store(state: &AppState) -> orbis_core::Result<Arc<dyn ObjectStore>> {
+    state
+        .plugins()
+        .runtime()
+        .object_store()
+        .ok_or_else(|| orbis_core::Error::config("Object storage is not configured"))
+}
+
+/// Download an object with a signed URL.
+async fn download(
+    Path(key): Path<String>,
+    Query(query): Query<DownloadQuery>,
+    State(state): State<AppState>,
+) -> ServerResult<impl IntoResponse> {
+    let store = store(&state)?;
+    if !store.verify_url(&key, query.expires, &query.signature) {
+        return Err(orbis_core::Error::unauthorized("Invalid or expired download link").into());
+    }
+
+    let data = store
+        .get(&key)
+        .await?
+        .ok_or_else(|| orbis_core::Error::not_found("File not found"))?;
+    let name = key.rsplit('/').next().unwrap_or_default().replace('"', "");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+        ],
+        data,
+    ))
+}
+
+/// Copy the plugin files still in the previous storage backend to the current one.
+///
+/// Files already copied are skipped, so an interrupted migration can be run
+/// again. Once done, `storage.migrate_from` can be removed.
+async fn migrate(_admin: RequirePlatformAdmin, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let report = store(&state)?
+        .migrate_remaining(PLUGIN_FILES_PREFIX)
+        .await?
+        .ok_or_else(|| {
+            orbis_core::Error::validation("Storage is not being migrated: set storage.migrate_from to the previous backend")
+        })?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": report
+    })))
+}
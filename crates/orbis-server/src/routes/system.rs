@@ -0,0 +1,195 @@
+//! System status routes.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use orbis_plugin::{PluginState, TrapReport};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::error::ServerResult;
+use crate::extractors::AuthUser;
+use crate::jobs::JobStatus;
+use crate::state::AppState;
+
+/// Number of recent errors listed in verbose mode.
+const RECENT_ERRORS: usize = 20;
+
+/// Errors newer than this count as recent, in minutes.
+const RECENT_ERROR_WINDOW_MINUTES: i64 = 60;
+
+/// Create system router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/system/status", get(system_status))
+}
+
+/// Status query parameters.
+#[derive(Debug, Deserialize)]
+struct StatusQuery {
+    /// Include per-plugin details and recent errors (admins only).
+    #[serde(default)]
+    verbose: bool,
+}
+
+/// Get the aggregated status of the server.
+async fn system_status(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<StatusQuery>,
+) -> ServerResult<Json<Value>> {
+    if query.verbose && !user.is_admin {
+        return Err(orbis_core::Error::unauthorized("Admin privileges required").into());
+    }
+
+    let db_healthy = state.db().health_check().await.is_ok();
+    let job_counts = state.jobs().counts().await;
+    let plugins = state.plugins().registry().list();
+    let traps: Vec<TrapReport> = plugins
+        .iter()
+        .flat_map(|plugin| state.plugins().registry().trap_reports(&plugin.manifest.name))
+        .collect();
+
+    let mut plugin_states: HashMap<PluginState, usize> = HashMap::new();
+    for plugin in &plugins {
+        *plugin_states.entry(plugin.state).or_default() += 1;
+    }
+    let plugin_errors = plugin_states.get(&PluginState::Error).copied().unwrap_or(0);
+
+    let recent_since = Utc::now() - chrono::Duration::minutes(RECENT_ERROR_WINDOW_MINUTES);
+    let recent_traps = traps.iter().filter(|trap| trap.occurred_at >= recent_since).count();
+
+    let healthy = db_healthy && job_counts.is_ok() && plugin_errors == 0;
+    let now = Utc::now();
+    let reload_events = state.plugins().reload_events();
+
+    let mut status = json!({
+        "status": if healthy { "ok" } else { "degraded" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "timestamp": now.to_rfc3339(),
+        "started_at": state.started_at().to_rfc3339(),
+        "uptime_secs": (now - state.started_at()).num_seconds().max(0),
+        "database": {
+            "status": if db_healthy { "ok" } else { "error" },
+            "backend": state.db().pool().backend()
+        },
+        "plugins": {
+            "total": plugins.len(),
+            "states": plugin_states,
+            "flagged_handlers": state.plugins().runtime().handler_stats().flagged().len()
+        },
+        "watcher": {
+            "enabled": state.config().plugin_hot_reload,
+            "last_event": reload_events.last()
+        },
+        "jobs": job_status(job_counts.as_ref()),
+        "errors": {
+            "window_minutes": RECENT_ERROR_WINDOW_MINUTES,
+            "plugin_traps": recent_traps,
+            "dead_jobs": job_counts.as_ref().ok().and_then(|counts| counts.get(JobStatus::Dead.as_str()).copied())
+        }
+    });
+
+    if query.verbose {
+        let details = plugin_details(&state, &plugins);
+        let recent = recent_errors(&state, traps).await;
+        if let Some(section) = status.get_mut("plugins").and_then(Value::as_object_mut) {
+            section.insert("details".to_owned(), details);
+        }
+        if let Some(section) = status.get_mut("errors").and_then(Value::as_object_mut) {
+            section.insert("recent".to_owned(), recent);
+        }
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": status
+    })))
+}
+
+/// Summarize the job queue.
+fn job_status(counts: Result<&HashMap<String, i64>, &orbis_core::Error>) -> Value {
+    match counts {
+        Ok(counts) => {
+            let count = |status: JobStatus| counts.get(status.as_str()).copied().unwrap_or(0);
+            json!({
+                "status": "ok",
+                "depth": count(JobStatus::Pending) + count(JobStatus::Running),
+                "counts": counts
+            })
+        }
+        Err(e) => json!({
+            "status": "error",
+            "error": e.to_string()
+        }),
+    }
+}
+
+/// Get the state, resource usage and network usage of each plugin.
+fn plugin_details(state: &AppState, plugins: &[orbis_plugin::PluginInfo]) -> Value {
+    let registry = state.plugins().registry();
+    let monitor = state.plugins().runtime().resource_monitor();
+
+    plugins
+        .iter()
+        .map(|plugin| {
+            let name = &plugin.manifest.name;
+            json!({
+                "name": name,
+                "version": plugin.manifest.version,
+                "state": plugin.state,
+                "loaded_at": plugin.loaded_at,
+                "resources": monitor.history(name).last(),
+                "network": registry.network_usage(name),
+                "traps": registry.trap_reports(name).len()
+            })
+        })
+        .collect()
+}
+
+/// Get the most recent plugin traps and dead jobs, newest first.
+async fn recent_errors(state: &AppState, mut traps: Vec<TrapReport>) -> Value {
+    traps.sort_unstable_by_key(|trap| std::cmp::Reverse(trap.occurred_at));
+    traps.truncate(RECENT_ERRORS);
+    let traps: Vec<Value> = traps
+        .into_iter()
+        .map(|trap| {
+            json!({
+                "plugin": trap.plugin,
+                "handler": trap.handler,
+                "message": trap.message,
+                "trap_code": trap.trap_code,
+                "occurred_at": trap.occurred_at
+            })
+        })
+        .collect();
+
+    let limit = i64::try_from(RECENT_ERRORS).unwrap_or(i64::MAX);
+    let jobs: Vec<Value> = match state.jobs().list(Some(JobStatus::Dead), None, limit).await {
+        Ok(jobs) => jobs
+            .into_iter()
+            .map(|job| {
+                json!({
+                    "id": job.id,
+                    "kind": job.kind,
+                    "queue": job.queue,
+                    "plugin": job.plugin_name,
+                    "error": job.last_error,
+                    "failed_at": job.updated_at
+                })
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to list dead jobs: {}", e);
+            Vec::new()
+        }
+    };
+
+    json!({
+        "plugin_traps": traps,
+        "dead_jobs": jobs
+    })
+}
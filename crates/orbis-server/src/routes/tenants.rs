@@ -0,0 +1,84 @@
+//! Tenant management routes (platform admin).
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::ServerResult;
+use crate::extractors::RequirePlatformAdmin;
+use crate::state::AppState;
+
+/// Create tenants router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/tenants", get(list_tenants).post(create_tenant))
+        .route("/tenants/{id}/plugins/{name}/config", put(set_plugin_config))
+}
+
+/// Get the auth service managing tenants.
+///
+/// Tenants are managed from platform scope only (see [`RequirePlatformAdmin`]),
+/// so tenant admins cannot see or configure other organizations.
+fn platform_auth(state: &AppState) -> orbis_core::Result<&orbis_auth::AuthService> {
+    state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::config("Authentication is not configured"))
+}
+
+/// List all tenants.
+async fn list_tenants(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let tenants = platform_auth(&state)?.tenant().list().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "tenants": tenants,
+            "total": tenants.len()
+        }
+    })))
+}
+
+/// Create a tenant.
+async fn create_tenant(
+    _admin: RequirePlatformAdmin,
+    State(state): State<AppState>,
+    Json(req): Json<orbis_auth::CreateTenant>,
+) -> ServerResult<Json<Value>> {
+    let tenant = platform_auth(&state)?.tenant().create(req).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": tenant
+    })))
+}
+
+/// Set a tenant's configuration overrides for a plugin.
+async fn set_plugin_config(
+    _admin: RequirePlatformAdmin,
+    Path((id, name)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    Json(config): Json<HashMap<String, Value>>,
+) -> ServerResult<Json<Value>> {
+    platform_auth(&state)?
+        .tenant()
+        .set_plugin_config(id, &name, &config)
+        .await?;
+
+    state
+        .plugins()
+        .runtime()
+        .set_tenant_config(&name, &id.to_string(), config);
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Tenant plugin configuration updated"
+    })))
+}
@@ -0,0 +1,167 @@
+//! Theme routes.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use orbis_plugin::ThemeDefinition;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::Row;
+use orbis_core::{ProfileId, UserId};
+
+use crate::error::ServerResult;
+use crate::extractors::OptionalAuthUser;
+use crate::state::AppState;
+
+/// Create theme router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/theme", get(get_theme))
+}
+
+/// Theme query parameters.
+#[derive(Debug, Deserialize)]
+struct ThemeQuery {
+    /// Profile to resolve overrides for (defaults to the user's default profile).
+    profile: Option<ProfileId>,
+}
+
+/// Get the merged theme.
+///
+/// The core theme is overlaid with the profile's `theme` custom setting.
+/// Plugin contributions are returned separately, keyed by plugin and scoped
+/// to the plugin's page prefix, so they can never restyle the core UI.
+async fn get_theme(
+    OptionalAuthUser(user): OptionalAuthUser,
+    Query(query): Query<ThemeQuery>,
+    State(state): State<AppState>,
+) -> ServerResult<Json<Value>> {
+    let mut core = core_theme();
+
+    let overrides = match &user {
+        Some(user) => load_profile_theme(&state, user.user_id, query.profile).await?,
+        None => None,
+    };
+
+    if let Some(overrides) = overrides {
+        match overrides.validate() {
+            Ok(()) => core.merge(&overrides),
+            Err(e) => tracing::warn!("Ignoring invalid profile theme: {}", e),
+        }
+    }
+
+    let plugins: serde_json::Map<String, Value> = state
+        .plugins()
+        .get_all_themes()
+        .into_iter()
+        .filter_map(|(plugin, theme)| match theme.validate() {
+            Ok(()) => Some((
+                plugin.clone(),
+                json!({
+                    "scope": format!("/plugins/{}", plugin),
+                    "theme": theme,
+                }),
+            )),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid theme from plugin '{}': {}", plugin, e);
+                None
+            }
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "core": core,
+            "plugins": plugins
+        }
+    })))
+}
+
+/// Load theme overrides from a profile's custom settings.
+async fn load_profile_theme(
+    state: &AppState,
+    user_id: UserId,
+    profile_id: Option<ProfileId>,
+) -> ServerResult<Option<ThemeDefinition>> {
+    let db = state.db();
+
+    let settings: Option<Value> = match db.pool() {
+        orbis_db::DatabasePool::Postgres(pool) => {
+            let row = if let Some(id) = profile_id {
+                sqlx::query("SELECT custom_settings FROM profiles WHERE id = $1 AND user_id = $2")
+                    .bind(id)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+            } else {
+                sqlx::query("SELECT custom_settings FROM profiles WHERE user_id = $1 AND is_default = TRUE")
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+            }
+            .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+            row.and_then(|r| r.get::<Option<Value>, _>("custom_settings"))
+        }
+        orbis_db::DatabasePool::Sqlite(pool) => {
+            let row = if let Some(id) = profile_id {
+                sqlx::query("SELECT custom_settings FROM profiles WHERE id = $1 AND user_id = $2")
+                    .bind(id.to_string())
+                    .bind(user_id.to_string())
+                    .fetch_optional(pool)
+                    .await
+            } else {
+                sqlx::query("SELECT custom_settings FROM profiles WHERE user_id = $1 AND is_default = 1")
+                    .bind(user_id.to_string())
+                    .fetch_optional(pool)
+                    .await
+            }
+            .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+            row.and_then(|r| r.get::<Option<String>, _>("custom_settings"))
+                .and_then(|s| serde_json::from_str(&s).ok())
+        }
+    };
+
+    Ok(settings
+        .and_then(|mut s| s.get_mut("theme").map(Value::take))
+        .and_then(|theme| serde_json::from_value(theme).ok()))
+}
+
+/// Core theme tokens shipped with Orbis.
+fn core_theme() -> ThemeDefinition {
+    let mut theme = ThemeDefinition::default();
+
+    for (name, value) in [
+        ("background", "oklch(1 0 0)"),
+        ("foreground", "oklch(0.145 0 0)"),
+        ("primary", "oklch(0.205 0 0)"),
+        ("primary-foreground", "oklch(0.985 0 0)"),
+        ("muted", "oklch(0.97 0 0)"),
+        ("border", "oklch(0.922 0 0)"),
+        ("destructive", "oklch(0.577 0.245 27.325)"),
+    ] {
+        theme.colors.insert(name.to_owned(), value.to_owned());
+    }
+
+    for (name, value) in [
+        ("background", "oklch(0.145 0 0)"),
+        ("foreground", "oklch(0.985 0 0)"),
+        ("primary", "oklch(0.922 0 0)"),
+        ("primary-foreground", "oklch(0.205 0 0)"),
+        ("muted", "oklch(0.269 0 0)"),
+        ("border", "oklch(1 0 0 / 10%)"),
+        ("destructive", "oklch(0.704 0.191 22.216)"),
+    ] {
+        theme.dark_colors.insert(name.to_owned(), value.to_owned());
+    }
+
+    theme.spacing.insert("radius".to_owned(), "0.625rem".to_owned());
+    theme
+        .typography
+        .insert("font-sans".to_owned(), "-apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif".to_owned());
+
+    theme
+}
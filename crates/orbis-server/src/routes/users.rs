@@ -1,23 +1,49 @@
 //! User management routes.
+//!
+//! Besides user management by admins, users manage their own account under
+//! `/users/me`: profile, email address (applied once the new address is
+//! confirmed), password, avatar, and deletion after a grace period.
 
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    routing::{delete, get, put},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use chrono::{DateTime, Duration, Utc};
+use orbis_auth::{AuthService, NewAuditEntry};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use sqlx::Row;
-use uuid::Uuid;
+use orbis_core::UserId;
 
+use crate::account::{self, avatar_key};
+use crate::email::EMAIL_CHANGE_TEMPLATE;
 use crate::error::ServerResult;
-use crate::extractors::{AdminUser, AuthenticatedUser};
+use crate::extractors::{Admin, AuthUser, RequireRole};
 use crate::state::AppState;
 
+/// Hours a new email address has to be confirmed.
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+
+/// Largest avatar, in bytes.
+const MAX_AVATAR_BYTES: usize = 1024 * 1024;
+
+/// Content types accepted for avatars.
+const AVATAR_CONTENT_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
 /// Create users router.
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/users", get(list_users))
+        .route("/users/me", get(get_me).patch(update_me).delete(delete_me))
+        .route("/users/me/email/confirm", post(confirm_email))
+        .route("/users/me/password", post(change_password))
+        .route("/users/me/avatar", put(upload_avatar).delete(delete_avatar))
+        .route("/users/me/deletion", delete(cancel_deletion))
+        .route("/users/{id}/avatar", get(get_avatar))
         .route("/users/{id}", get(get_user))
         .route("/users/{id}", put(update_user))
         .route("/users/{id}", delete(delete_user))
@@ -30,9 +56,9 @@ struct PaginationQuery {
     limit: Option<u32>,
 }
 
-/// List all users (admin only).
+/// List all users of the admin's tenant (admin only).
 async fn list_users(
-    _admin: AdminUser,
+    admin: RequireRole<Admin>,
     State(state): State<AppState>,
     Query(query): Query<PaginationQuery>,
 ) -> ServerResult<Json<Value>> {
@@ -44,7 +70,8 @@ async fn list_users(
     let (users, total) = match db.pool() {
         orbis_db::DatabasePool::Postgres(pool) => {
             // Get total count
-            let count_row = sqlx::query("SELECT COUNT(*) as count FROM users")
+            let count_row = sqlx::query("SELECT COUNT(*) as count FROM users WHERE tenant_id IS NOT DISTINCT FROM $1")
+                .bind(admin.0.tenant_id)
                 .fetch_one(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
@@ -53,8 +80,9 @@ async fn list_users(
             // Get paginated users
             let rows = sqlx::query(
                 "SELECT id, username, email, display_name, is_active, is_admin, created_at, updated_at 
-                 FROM users ORDER BY created_at DESC LIMIT $1 OFFSET $2"
+                 FROM users WHERE tenant_id IS NOT DISTINCT FROM $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
             )
+            .bind(admin.0.tenant_id)
             .bind(limit as i64)
             .bind(offset as i64)
             .fetch_all(pool)
@@ -64,7 +92,7 @@ async fn list_users(
             let users: Vec<Value> = rows.into_iter()
                 .map(|row| {
                     json!({
-                        "id": row.get::<Uuid, _>("id").to_string(),
+                        "id": row.get::<UserId, _>("id").to_string(),
                         "username": row.get::<String, _>("username"),
                         "email": row.get::<String, _>("email"),
                         "display_name": row.get::<Option<String>, _>("display_name"),
@@ -80,7 +108,8 @@ async fn list_users(
         }
         orbis_db::DatabasePool::Sqlite(pool) => {
             // Get total count
-            let count_row = sqlx::query("SELECT COUNT(*) as count FROM users")
+            let count_row = sqlx::query("SELECT COUNT(*) as count FROM users WHERE tenant_id IS $1")
+                .bind(admin.0.tenant_id.map(|id| id.to_string()))
                 .fetch_one(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?;
@@ -89,8 +118,9 @@ async fn list_users(
             // Get paginated users
             let rows = sqlx::query(
                 "SELECT id, username, email, display_name, is_active, is_admin, created_at, updated_at 
-                 FROM users ORDER BY created_at DESC LIMIT $1 OFFSET $2"
+                 FROM users WHERE tenant_id IS $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
             )
+            .bind(admin.0.tenant_id.map(|id| id.to_string()))
             .bind(limit as i64)
             .bind(offset as i64)
             .fetch_all(pool)
@@ -130,8 +160,8 @@ async fn list_users(
 
 /// Get a user by ID.
 async fn get_user(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<UserId>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     // Users can only view themselves unless admin
@@ -143,9 +173,12 @@ async fn get_user(
         orbis_core::Error::config("Authentication is not configured")
     })?;
 
-    let found_user = auth.user().find_by_id(id).await?.ok_or_else(|| {
-        orbis_core::Error::not_found("User not found")
-    })?;
+    let found_user = auth
+        .user()
+        .find_by_id(id)
+        .await?
+        .filter(|found| found.tenant_id == user.tenant_id)
+        .ok_or_else(|| orbis_core::Error::not_found("User not found"))?;
 
     Ok(Json(json!({
         "success": true,
@@ -172,8 +205,8 @@ struct UpdateUserRequest {
 
 /// Update a user.
 async fn update_user(
-    user: AuthenticatedUser,
-    Path(id): Path<Uuid>,
+    user: AuthUser,
+    Path(id): Path<UserId>,
     State(state): State<AppState>,
     Json(req): Json<UpdateUserRequest>,
 ) -> ServerResult<Json<Value>> {
@@ -187,6 +220,11 @@ async fn update_user(
         return Err(orbis_core::Error::unauthorized("Cannot modify admin or active status").into());
     }
 
+    // Users change their own email address through /users/me, which confirms it
+    if !user.is_admin && req.email.is_some() {
+        return Err(orbis_core::Error::validation("Change your email address through /api/users/me").into());
+    }
+
     let db = state.db();
 
     match db.pool() {
@@ -211,14 +249,14 @@ async fn update_user(
                 param_idx += 1;
             }
             
-            query.push_str(&format!(" WHERE id = ${}", param_idx));
+            query.push_str(&format!(" WHERE id = ${} AND tenant_id IS NOT DISTINCT FROM ${}", param_idx, param_idx + 1));
             
             let mut q = sqlx::query(&query);
             if let Some(ref dn) = req.display_name { q = q.bind(dn); }
             if let Some(ref email) = req.email { q = q.bind(email); }
             if let Some(active) = req.is_active { q = q.bind(active); }
             if let Some(admin) = req.is_admin { q = q.bind(admin); }
-            q = q.bind(id);
+            q = q.bind(id).bind(user.tenant_id);
             
             q.execute(pool)
                 .await
@@ -245,14 +283,14 @@ async fn update_user(
                 param_idx += 1;
             }
             
-            query.push_str(&format!(" WHERE id = ${}", param_idx));
+            query.push_str(&format!(" WHERE id = ${} AND tenant_id IS ${}", param_idx, param_idx + 1));
             
             let mut q = sqlx::query(&query);
             if let Some(ref dn) = req.display_name { q = q.bind(dn); }
             if let Some(ref email) = req.email { q = q.bind(email); }
             if let Some(active) = req.is_active { q = q.bind(active); }
             if let Some(admin) = req.is_admin { q = q.bind(admin); }
-            q = q.bind(id.to_string());
+            q = q.bind(id.to_string()).bind(user.tenant_id.map(|id| id.to_string()));
             
             q.execute(pool)
                 .await
@@ -268,8 +306,8 @@ async fn update_user(
 
 /// Delete a user (admin only).
 async fn delete_user(
-    admin: AdminUser,
-    Path(id): Path<Uuid>,
+    admin: RequireRole<Admin>,
+    Path(id): Path<UserId>,
     State(state): State<AppState>,
 ) -> ServerResult<Json<Value>> {
     // Prevent self-deletion
@@ -281,16 +319,18 @@ async fn delete_user(
 
     let rows_affected = match db.pool() {
         orbis_db::DatabasePool::Postgres(pool) => {
-            sqlx::query("DELETE FROM users WHERE id = $1")
+            sqlx::query("DELETE FROM users WHERE id = $1 AND tenant_id IS NOT DISTINCT FROM $2")
                 .bind(id)
+                .bind(admin.0.tenant_id)
                 .execute(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?
                 .rows_affected()
         }
         orbis_db::DatabasePool::Sqlite(pool) => {
-            sqlx::query("DELETE FROM users WHERE id = $1")
+            sqlx::query("DELETE FROM users WHERE id = $1 AND tenant_id IS $2")
                 .bind(id.to_string())
+                .bind(admin.0.tenant_id.map(|id| id.to_string()))
                 .execute(pool)
                 .await
                 .map_err(|e| orbis_core::Error::database(e.to_string()))?
@@ -302,8 +342,339 @@ async fn delete_user(
         return Err(orbis_core::Error::not_found("User not found").into());
     }
 
+    let removed = if state.files().is_available() {
+        state.files().delete(&avatar_key(id)).await
+    } else {
+        Ok(())
+    };
+    if let Err(e) = removed {
+        tracing::warn!("Failed to remove the avatar of deleted user {}: {}", id, e);
+    }
+
     Ok(Json(json!({
         "success": true,
         "message": "User deleted"
     })))
 }
+
+/// Get the auth service, if authentication is configured.
+fn auth_service(state: &AppState) -> orbis_core::Result<&AuthService> {
+    state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::config("Authentication is not configured"))
+}
+
+/// Find the authenticated user.
+async fn current_user(auth: &AuthService, user: &AuthUser) -> orbis_core::Result<orbis_auth::User> {
+    auth.user()
+        .find_by_id(user.user_id)
+        .await?
+        .ok_or_else(|| orbis_core::Error::not_found("User not found"))
+}
+
+/// Record a change a user made to their own account.
+async fn audit(auth: &AuthService, user: &AuthUser, action: &str, details: Value) -> orbis_core::Result<()> {
+    auth.audit()
+        .record(NewAuditEntry::new(user.user_id, action, "user", user.user_id.into()).details(details))
+        .await
+}
+
+/// Get the authenticated user's profile.
+async fn get_me(user: AuthUser, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+    let found_user = current_user(auth, &user).await?;
+    let pending_email = auth.account().pending_email(user.user_id).await?;
+    let avatar = auth.account().avatar(user.user_id).await?;
+    let grace_days = state.config().account_deletion_grace_days;
+    let deletion_at = auth
+        .account()
+        .deletion_requested_at(user.user_id)
+        .await?
+        .map(|requested_at| account::deletion_at(requested_at, grace_days).to_rfc3339());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "id": found_user.id.to_string(),
+            "username": found_user.username,
+            "email": found_user.email,
+            "pending_email": pending_email,
+            "display_name": found_user.display_name,
+            "has_avatar": avatar.is_some(),
+            "is_admin": found_user.is_admin,
+            "deletion_at": deletion_at,
+            "created_at": found_user.created_at.to_rfc3339()
+        }
+    })))
+}
+
+/// Profile update request.
+#[derive(Debug, Deserialize)]
+struct UpdateProfileRequest {
+    display_name: Option<String>,
+    email: Option<String>,
+}
+
+/// Update the authenticated user's profile.
+///
+/// A new email address is applied once confirmed with the code sent to it.
+async fn update_me(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<UpdateProfileRequest>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+    let found_user = current_user(auth, &user).await?;
+
+    if let Some(ref display_name) = req.display_name {
+        let display_name = display_name.trim();
+        let display_name = (!display_name.is_empty()).then_some(display_name);
+        auth.account().set_display_name(user.user_id, display_name).await?;
+    }
+
+    let mut pending_email = None;
+    let new_email = req
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| *email != found_user.email);
+    if let Some(email) = new_email {
+        if !email.contains('@') {
+            return Err(orbis_core::Error::validation("Invalid email address").into());
+        }
+        if auth.user().email_exists(email, found_user.tenant_id).await? {
+            return Err(orbis_core::Error::conflict("Email already exists").into());
+        }
+
+        let expires_at = Utc::now()
+            .checked_add_signed(Duration::hours(EMAIL_CHANGE_TTL_HOURS))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let code = auth.account().request_email_change(user.user_id, email, expires_at).await?;
+        state
+            .email()
+            .send_template(
+                EMAIL_CHANGE_TEMPLATE,
+                &[email.to_owned()],
+                &json!({
+                    "name": found_user.display_name.as_deref().unwrap_or(&found_user.username),
+                    "email": email,
+                    "code": code,
+                    "expires_in": format!("{} hours", EMAIL_CHANGE_TTL_HOURS),
+                }),
+            )
+            .await?;
+        audit(auth, &user, "account.email_change_requested", json!({ "email": email })).await?;
+        pending_email = Some(email.to_owned());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": if pending_email.is_some() {
+            "Profile updated; confirm the new email address with the code sent to it"
+        } else {
+            "Profile updated"
+        },
+        "data": {
+            "pending_email": pending_email
+        }
+    })))
+}
+
+/// Email confirmation request.
+#[derive(Debug, Deserialize)]
+struct ConfirmEmailRequest {
+    code: String,
+}
+
+/// Confirm a new email address with the code sent to it.
+async fn confirm_email(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmEmailRequest>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+    let email = auth.account().confirm_email_change(user.user_id, req.code.trim()).await?;
+    audit(auth, &user, "account.email_changed", json!({ "email": email })).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Email address changed",
+        "data": {
+            "email": email
+        }
+    })))
+}
+
+/// Password change request.
+#[derive(Debug, Deserialize)]
+struct ChangePasswordRequest {
+    current_password: String,
+    new_password: String,
+}
+
+/// Change the authenticated user's password, signing out all their sessions.
+async fn change_password(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<ChangePasswordRequest>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+    let found_user = current_user(auth, &user).await?;
+
+    if !auth.password().verify(&req.current_password, &found_user.password_hash)? {
+        return Err(orbis_core::Error::unauthorized("Current password is incorrect").into());
+    }
+    if !orbis_auth::PasswordService::validate_password_strength(&req.new_password).is_valid() {
+        return Err(orbis_core::Error::validation("Password must be at least 8 characters long").into());
+    }
+
+    let password_hash = auth.password().hash(&req.new_password)?;
+    auth.user().set_password(user.user_id, &password_hash).await?;
+    auth.session().delete_all_for_user(user.user_id).await?;
+    audit(auth, &user, "account.password_changed", json!({})).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Password changed; sign in again on your devices"
+    })))
+}
+
+/// Store the authenticated user's avatar, sent as the request body.
+async fn upload_avatar(
+    user: AuthUser,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim)
+        .filter(|value| AVATAR_CONTENT_TYPES.contains(value))
+        .ok_or_else(|| {
+            orbis_core::Error::validation(format!("Avatars must be one of: {}", AVATAR_CONTENT_TYPES.join(", ")))
+        })?;
+    if body.is_empty() {
+        return Err(orbis_core::Error::validation("Avatar is empty").into());
+    }
+    if body.len() > MAX_AVATAR_BYTES {
+        return Err(orbis_core::Error::payload_too_large(format!(
+            "Avatar is {} bytes (max {})",
+            body.len(),
+            MAX_AVATAR_BYTES
+        ))
+        .into());
+    }
+
+    state.files().put(&avatar_key(user.user_id), &body).await?;
+    auth.account().set_avatar(user.user_id, Some(content_type)).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Avatar updated"
+    })))
+}
+
+/// Remove the authenticated user's avatar.
+async fn delete_avatar(user: AuthUser, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+
+    auth.account().set_avatar(user.user_id, None).await?;
+    if state.files().is_available() {
+        state.files().delete(&avatar_key(user.user_id)).await?;
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Avatar removed"
+    })))
+}
+
+/// Get the avatar of a user of the same tenant.
+async fn get_avatar(
+    user: AuthUser,
+    Path(id): Path<UserId>,
+    State(state): State<AppState>,
+) -> ServerResult<impl IntoResponse> {
+    let auth = auth_service(&state)?;
+    let not_found = || orbis_core::Error::not_found("Avatar not found");
+
+    auth.user()
+        .find_by_id(id)
+        .await?
+        .filter(|found| found.tenant_id == user.tenant_id)
+        .ok_or_else(not_found)?;
+    let content_type = auth.account().avatar(id).await?.ok_or_else(not_found)?;
+    let avatar = state.files().get(&avatar_key(id)).await?.ok_or_else(not_found)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "private, max-age=300".to_owned()),
+        ],
+        avatar,
+    ))
+}
+
+/// Account deletion request.
+#[derive(Debug, Deserialize)]
+struct DeleteAccountRequest {
+    password: String,
+}
+
+/// Ask for the authenticated user's account to be deleted.
+///
+/// The account is deleted at the end of the grace period unless the user
+/// signs in again and cancels; all sessions are signed out meanwhile.
+async fn delete_me(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<DeleteAccountRequest>,
+) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+    let found_user = current_user(auth, &user).await?;
+
+    if !auth.password().verify(&req.password, &found_user.password_hash)? {
+        return Err(orbis_core::Error::unauthorized("Password is incorrect").into());
+    }
+
+    let grace_days = state.config().account_deletion_grace_days;
+    let requested_at = Utc::now();
+    let deletion_at = account::deletion_at(requested_at, grace_days);
+    auth.account().request_deletion(user.user_id, requested_at).await?;
+    account::schedule_deletion(state.jobs(), user.user_id, grace_days).await?;
+    auth.session().delete_all_for_user(user.user_id).await?;
+    audit(
+        auth,
+        &user,
+        "account.deletion_requested",
+        json!({ "deletion_at": deletion_at.to_rfc3339() }),
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": format!("Account will be deleted in {} days unless you cancel", grace_days),
+        "data": {
+            "deletion_at": deletion_at.to_rfc3339()
+        }
+    })))
+}
+
+/// Cancel the deletion of the authenticated user's account.
+async fn cancel_deletion(user: AuthUser, State(state): State<AppState>) -> ServerResult<Json<Value>> {
+    let auth = auth_service(&state)?;
+
+    if !auth.account().cancel_deletion(user.user_id).await? {
+        return Err(orbis_core::Error::not_found("No account deletion is pending").into());
+    }
+    audit(auth, &user, "account.deletion_cancelled", json!({})).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Account deletion cancelled"
+    })))
+}
@@ -0,0 +1,30 @@
+//! Well-known discovery routes.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+use crate::error::ServerResult;
+use crate::state::AppState;
+
+/// Create well-known router.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/.well-known/jwks.json", get(jwks))
+}
+
+/// Public keys external services can validate Orbis tokens with.
+async fn jwks(State(state): State<AppState>) -> ServerResult<Response> {
+    let auth = state
+        .auth()
+        .ok_or_else(|| orbis_core::Error::not_found("Authentication is not configured"))?;
+
+    Ok((
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(auth.jwt().jwks()),
+    )
+        .into_response())
+}
@@ -0,0 +1,413 @@
+//! Typed, scoped settings.
+//!
+//! Setting definitions come from the core and from running plugins (namespaced
+//! as `<plugin>.<key>`). System values live in the `settings` table; profile
+//! and user values live in `scoped_settings`. Unset values fall back to the
+//! definition's default.
+
+use chrono::{DateTime, Utc};
+use orbis_db::{Database, DatabasePool};
+use orbis_plugin::{PluginManager, SettingDefinition, SettingScope, SettingType};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use orbis_core::UserId;
+
+/// Capacity of the setting change channel.
+const CHANGE_CHANNEL_CAPACITY: usize = 64;
+
+/// Whether new accounts may register themselves.
+pub const REGISTRATION_ENABLED: &str = "auth.registration_enabled";
+
+/// A change to a setting value.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChange {
+    /// Setting key.
+    pub key: String,
+
+    /// Scope of the changed value.
+    pub scope: SettingScope,
+
+    /// Profile or user ID (`None` for system settings).
+    pub scope_id: Option<String>,
+
+    /// New value (the default if the value was reset).
+    pub value: Value,
+
+    /// User who made the change.
+    pub changed_by: Option<UserId>,
+
+    /// When the change was made.
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Settings service.
+#[derive(Clone)]
+pub struct SettingsService {
+    /// Database connection.
+    db: Database,
+
+    /// Plugin manager, for plugin-contributed definitions.
+    plugins: Arc<PluginManager>,
+
+    /// Change event channel.
+    changes: broadcast::Sender<SettingChange>,
+}
+
+impl SettingsService {
+    /// Create a new settings service.
+    #[must_use]
+    pub fn new(db: Database, plugins: Arc<PluginManager>) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
+        Self { db, plugins, changes }
+    }
+
+    /// Subscribe to setting changes.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingChange> {
+        self.changes.subscribe()
+    }
+
+    /// Get all setting definitions, keyed by full key.
+    #[must_use]
+    pub fn definitions(&self) -> BTreeMap<String, SettingDefinition> {
+        let mut definitions: BTreeMap<_, _> = core_definitions()
+            .into_iter()
+            .map(|definition| (definition.key.clone(), definition))
+            .collect();
+
+        for info in self.plugins.registry().list_by_state(orbis_plugin::PluginState::Running) {
+            for definition in &info.manifest.settings {
+                let key = format!("{}.{}", info.manifest.name, definition.key);
+                definitions.insert(key.clone(), SettingDefinition { key, ..definition.clone() });
+            }
+        }
+
+        definitions
+    }
+
+    /// Get the values of a scope, falling back to defaults.
+    ///
+    /// `keys` limits the result to the given settings; `None` returns every
+    /// setting of the scope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key is unknown or belongs to another scope, or the query fails.
+    pub async fn get_many(
+        &self,
+        scope: SettingScope,
+        scope_id: Option<&str>,
+        keys: Option<&[String]>,
+    ) -> orbis_core::Result<BTreeMap<String, Value>> {
+        let definitions = self.definitions();
+        let selected: Vec<&SettingDefinition> = match keys {
+            Some(keys) => keys
+                .iter()
+                .map(|key| lookup(&definitions, key, scope))
+                .collect::<orbis_core::Result<_>>()?,
+            None => definitions.values().filter(|definition| definition.scope == scope).collect(),
+        };
+
+        let stored = self.load(scope, scope_id.unwrap_or_default()).await?;
+
+        Ok(selected
+            .into_iter()
+            .map(|definition| {
+                let value = stored
+                    .get(&definition.key)
+                    .cloned()
+                    .unwrap_or_else(|| definition.default.clone());
+                (definition.key.clone(), value)
+            })
+            .collect())
+    }
+
+    /// Get a single value, falling back to its default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is unknown or the query fails.
+    pub async fn get(&self, key: &str, scope_id: Option<&str>) -> orbis_core::Result<Value> {
+        let scope = self
+            .definitions()
+            .get(key)
+            .map(|definition| definition.scope)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Unknown setting '{}'", key)))?;
+
+        let mut values = self.get_many(scope, scope_id, Some(&[key.to_string()])).await?;
+        Ok(values.remove(key).unwrap_or_default())
+    }
+
+    /// Get a value only if it was set, without falling back to its default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is unknown or the query fails.
+    pub async fn get_stored(&self, key: &str, scope_id: Option<&str>) -> orbis_core::Result<Option<Value>> {
+        let scope = self
+            .definitions()
+            .get(key)
+            .map(|definition| definition.scope)
+            .ok_or_else(|| orbis_core::Error::not_found(format!("Unknown setting '{}'", key)))?;
+
+        Ok(self.load(scope, scope_id.unwrap_or_default()).await?.remove(key))
+    }
+
+    /// Set several values of a scope at once.
+    ///
+    /// Every value is validated before anything is written, and all values
+    /// are written in one transaction. A `null` value resets the setting to
+    /// its default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key is unknown, a value is invalid, or the write fails.
+    pub async fn patch(
+        &self,
+        scope: SettingScope,
+        scope_id: Option<&str>,
+        values: BTreeMap<String, Value>,
+        changed_by: Option<UserId>,
+    ) -> orbis_core::Result<Vec<SettingChange>> {
+        let definitions = self.definitions();
+        for (key, value) in &values {
+            let definition = lookup(&definitions, key, scope)?;
+            if !value.is_null() {
+                definition
+                    .check_value(value)
+                    .map_err(|e| orbis_core::Error::validation(e.to_string()))?;
+            }
+        }
+
+        self.store(scope, scope_id.unwrap_or_default(), &definitions, &values)
+            .await?;
+
+        let changed_at = Utc::now();
+        let changes: Vec<SettingChange> = values
+            .into_iter()
+            .map(|(key, value)| {
+                let value = if value.is_null() {
+                    definitions.get(&key).map(|definition| definition.default.clone()).unwrap_or_default()
+                } else {
+                    value
+                };
+                SettingChange {
+                    key,
+                    scope,
+                    scope_id: scope_id.map(String::from),
+                    value,
+                    changed_by,
+                    changed_at,
+                }
+            })
+            .collect();
+
+        for change in &changes {
+            tracing::info!(key = %change.key, scope = change.scope.as_str(), "Setting changed");
+            if self.changes.send(change.clone()).is_err() {
+                tracing::trace!("No setting change subscribers");
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Load the stored values of a scope.
+    async fn load(&self, scope: SettingScope, scope_id: &str) -> orbis_core::Result<BTreeMap<String, Value>> {
+        let system = scope == SettingScope::System;
+        let query = if system {
+            "SELECT key, value FROM settings"
+        } else {
+            "SELECT key, value FROM scoped_settings WHERE scope = $1 AND scope_id = $2"
+        };
+
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut query = sqlx::query_as::<_, (String, Value)>(query);
+                if !system {
+                    query = query.bind(scope.as_str()).bind(scope_id);
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows.into_iter().collect())
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut query = sqlx::query_as::<_, (String, String)>(query);
+                if !system {
+                    query = query.bind(scope.as_str()).bind(scope_id);
+                }
+                let rows = query
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| orbis_core::Error::database(e.to_string()))?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(key, value)| (key, serde_json::from_str(&value).unwrap_or(Value::Null)))
+                    .collect())
+            }
+        }
+    }
+
+    /// Write values of a scope in one transaction.
+    async fn store(
+        &self,
+        scope: SettingScope,
+        scope_id: &str,
+        definitions: &BTreeMap<String, SettingDefinition>,
+        values: &BTreeMap<String, Value>,
+    ) -> orbis_core::Result<()> {
+        let db_err = |e: sqlx::Error| orbis_core::Error::database(e.to_string());
+
+        match self.db.pool() {
+            DatabasePool::Postgres(pool) => {
+                let mut tx = pool.begin().await.map_err(db_err)?;
+                for (key, value) in values {
+                    let definition = definitions.get(key);
+                    let query = match (scope, value.is_null()) {
+                        (SettingScope::System, true) => sqlx::query("DELETE FROM settings WHERE key = $1").bind(key),
+                        (SettingScope::System, false) => sqlx::query(
+                            "INSERT INTO settings (key, value, description, is_secret, updated_at)
+                             VALUES ($1, $2, $3, $4, NOW())
+                             ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()",
+                        )
+                        .bind(key)
+                        .bind(value)
+                        .bind(definition.and_then(|definition| definition.description.clone()))
+                        .bind(definition.is_some_and(|definition| definition.secret)),
+                        (SettingScope::Profile | SettingScope::User, true) => sqlx::query(
+                            "DELETE FROM scoped_settings WHERE scope = $1 AND scope_id = $2 AND key = $3",
+                        )
+                        .bind(scope.as_str())
+                        .bind(scope_id)
+                        .bind(key),
+                        (SettingScope::Profile | SettingScope::User, false) => sqlx::query(
+                            "INSERT INTO scoped_settings (scope, scope_id, key, value)
+                             VALUES ($1, $2, $3, $4)
+                             ON CONFLICT (scope, scope_id, key) DO UPDATE SET value = EXCLUDED.value",
+                        )
+                        .bind(scope.as_str())
+                        .bind(scope_id)
+                        .bind(key)
+                        .bind(value),
+                    };
+
+                    query.execute(&mut *tx).await.map_err(db_err)?;
+                }
+                tx.commit().await.map_err(db_err)?;
+            }
+            DatabasePool::Sqlite(pool) => {
+                let mut tx = pool.begin().await.map_err(db_err)?;
+                for (key, value) in values {
+                    let definition = definitions.get(key);
+                    let query = match (scope, value.is_null()) {
+                        (SettingScope::System, true) => sqlx::query("DELETE FROM settings WHERE key = $1").bind(key),
+                        (SettingScope::System, false) => sqlx::query(
+                            "INSERT OR REPLACE INTO settings (key, value, description, is_secret, updated_at)
+                             VALUES ($1, $2, $3, $4, datetime('now'))",
+                        )
+                        .bind(key)
+                        .bind(value.to_string())
+                        .bind(definition.and_then(|definition| definition.description.clone()))
+                        .bind(definition.is_some_and(|definition| definition.secret)),
+                        (SettingScope::Profile | SettingScope::User, true) => sqlx::query(
+                            "DELETE FROM scoped_settings WHERE scope = $1 AND scope_id = $2 AND key = $3",
+                        )
+                        .bind(scope.as_str())
+                        .bind(scope_id)
+                        .bind(key),
+                        (SettingScope::Profile | SettingScope::User, false) => sqlx::query(
+                            "INSERT OR REPLACE INTO scoped_settings (scope, scope_id, key, value, updated_at)
+                             VALUES ($1, $2, $3, $4, datetime('now'))",
+                        )
+                        .bind(scope.as_str())
+                        .bind(scope_id)
+                        .bind(key)
+                        .bind(value.to_string()),
+                    };
+
+                    query.execute(&mut *tx).await.map_err(db_err)?;
+                }
+                tx.commit().await.map_err(db_err)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Look up a definition and check that it belongs to `scope`.
+fn lookup<'a>(
+    definitions: &'a BTreeMap<String, SettingDefinition>,
+    key: &str,
+    scope: SettingScope,
+) -> orbis_core::Result<&'a SettingDefinition> {
+    let definition = definitions
+        .get(key)
+        .ok_or_else(|| orbis_core::Error::not_found(format!("Unknown setting '{}'", key)))?;
+
+    if definition.scope != scope {
+        return Err(orbis_core::Error::validation(format!(
+            "Setting '{}' is a {} setting",
+            key,
+            definition.scope.as_str()
+        )));
+    }
+
+    Ok(definition)
+}
+
+/// Settings defined by the core.
+fn core_definitions() -> Vec<SettingDefinition> {
+    let definition = |key: &str, setting_type, scope, default, description: &str| SettingDefinition {
+        key: key.to_string(),
+        setting_type,
+        scope,
+        default,
+        description: Some(description.to_string()),
+        options: vec![],
+        min: None,
+        max: None,
+        secret: false,
+    };
+
+    vec![
+        definition(
+            "app.name",
+            SettingType::String,
+            SettingScope::System,
+            json!("Orbis"),
+            "Application name shown in the UI",
+        ),
+        definition(
+            REGISTRATION_ENABLED,
+            SettingType::Boolean,
+            SettingScope::System,
+            json!(true),
+            "Allow new users to register themselves",
+        ),
+        SettingDefinition {
+            options: vec![json!("light"), json!("dark"), json!("system")],
+            ..definition(
+                "ui.theme",
+                SettingType::String,
+                SettingScope::User,
+                json!("system"),
+                "Color scheme",
+            )
+        },
+        definition(
+            "ui.locale",
+            SettingType::String,
+            SettingScope::User,
+            json!("en"),
+            "Interface language",
+        ),
+    ]
+}
@@ -1,11 +1,24 @@
 //! Application state shared across handlers.
 
+use chrono::{DateTime, Utc};
 use orbis_auth::AuthService;
 use orbis_config::Config;
+use orbis_core::{shutdown, Localizer};
 use orbis_db::Database;
 use orbis_plugin::PluginManager;
 use std::sync::Arc;
 
+use crate::account;
+use crate::compression::CompressionStats;
+use crate::csrf::CsrfSigner;
+use crate::email::EmailService;
+use crate::events::EventHub;
+use crate::jobs::JobQueue;
+use crate::limits::LimitStats;
+use crate::monitoring::ResourceMonitorService;
+use crate::settings::SettingsService;
+use crate::storage::FileStorage;
+
 /// Application state shared across all handlers.
 #[derive(Clone)]
 pub struct AppState {
@@ -20,6 +33,42 @@ pub struct AppState {
 
     /// Plugin manager.
     plugins: Arc<PluginManager>,
+
+    /// Settings service.
+    settings: SettingsService,
+
+    /// Background job queue.
+    jobs: JobQueue,
+
+    /// Email service.
+    email: EmailService,
+
+    /// Hub of the events streamed and polled by clients.
+    events: EventHub,
+
+    /// Storage of files kept for users.
+    files: FileStorage,
+
+    /// Plugin resource sample persistence and alerts.
+    monitoring: ResourceMonitorService,
+
+    /// Message catalogs for localized responses.
+    localizer: Arc<Localizer>,
+
+    /// Counters of requests rejected by size limits and timeouts.
+    limit_stats: Arc<LimitStats>,
+
+    /// Counters of compressed responses.
+    compression_stats: Arc<CompressionStats>,
+
+    /// Signer of the CSRF tokens of cookie sessions.
+    csrf: Arc<CsrfSigner>,
+
+    /// When the state was created, for uptime reporting.
+    started_at: DateTime<Utc>,
+
+    /// Shutdown coordinator the state's subsystems register with.
+    shutdown: Arc<shutdown::Coordinator>,
 }
 
 impl AppState {
@@ -28,16 +77,48 @@ impl AppState {
         config: Arc<Config>,
         db: Database,
         auth: Option<AuthService>,
-        plugins: PluginManager,
+        plugins: Arc<PluginManager>,
+        localizer: Localizer,
     ) -> Self {
+        let settings = SettingsService::new(db.clone(), Arc::clone(&plugins));
+        let jobs = JobQueue::new(db.clone(), config.jobs.clone(), Arc::clone(&plugins));
+        let email = EmailService::new(&config.email, jobs.clone(), &plugins);
+        let monitoring = ResourceMonitorService::new(db.clone(), jobs.clone(), Arc::clone(&plugins));
+        let files = FileStorage::in_data_dir(config.data_dir.as_deref());
+        let events = EventHub::new(config.events.clone());
+        let csrf = Arc::new(CsrfSigner::new(config.jwt_secret.as_deref()));
+        if let Some(ref auth) = auth {
+            account::register(&jobs, auth.clone(), files.clone(), config.account_deletion_grace_days);
+        }
+
         Self {
             config,
             db,
             auth,
-            plugins: Arc::new(plugins),
+            plugins,
+            settings,
+            jobs,
+            email,
+            events,
+            files,
+            monitoring,
+            localizer: Arc::new(localizer),
+            limit_stats: Arc::new(LimitStats::new()),
+            compression_stats: Arc::new(CompressionStats::new()),
+            csrf,
+            started_at: Utc::now(),
+            shutdown: Arc::new(shutdown::Coordinator::new()),
         }
     }
 
+    /// Register the state's subsystems with a shared shutdown coordinator,
+    /// such as the one of the server hosting this profile.
+    #[must_use]
+    pub fn with_shutdown(mut self, shutdown: Arc<shutdown::Coordinator>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
     /// Get the configuration.
     #[must_use]
     pub fn config(&self) -> &Config {
@@ -68,6 +149,78 @@ impl AppState {
         Arc::clone(&self.plugins)
     }
 
+    /// Get the settings service.
+    #[must_use]
+    pub const fn settings(&self) -> &SettingsService {
+        &self.settings
+    }
+
+    /// Get the job queue.
+    #[must_use]
+    pub const fn jobs(&self) -> &JobQueue {
+        &self.jobs
+    }
+
+    /// Get the email service.
+    #[must_use]
+    pub const fn email(&self) -> &EmailService {
+        &self.email
+    }
+
+    /// Get the event hub.
+    #[must_use]
+    pub const fn events(&self) -> &EventHub {
+        &self.events
+    }
+
+    /// Get the file storage.
+    #[must_use]
+    pub const fn files(&self) -> &FileStorage {
+        &self.files
+    }
+
+    /// Get the plugin resource monitoring service.
+    #[must_use]
+    pub const fn monitoring(&self) -> &ResourceMonitorService {
+        &self.monitoring
+    }
+
+    /// Get the message catalogs.
+    #[must_use]
+    pub fn localizer(&self) -> &Localizer {
+        &self.localizer
+    }
+
+    /// Get the counters of requests rejected by limits.
+    #[must_use]
+    pub fn limit_stats(&self) -> &LimitStats {
+        &self.limit_stats
+    }
+
+    /// Get the counters of compressed responses.
+    #[must_use]
+    pub fn compression_stats(&self) -> Arc<CompressionStats> {
+        Arc::clone(&self.compression_stats)
+    }
+
+    /// Get the signer of the CSRF tokens of cookie sessions.
+    #[must_use]
+    pub fn csrf(&self) -> &CsrfSigner {
+        &self.csrf
+    }
+
+    /// Get when the server started.
+    #[must_use]
+    pub const fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Get the shutdown coordinator.
+    #[must_use]
+    pub const fn shutdown(&self) -> &Arc<shutdown::Coordinator> {
+        &self.shutdown
+    }
+
     /// Check if authentication is required.
     #[must_use]
     pub fn is_auth_required(&self) -> bool {
@@ -0,0 +1,104 @@
+//! File storage.
+//!
+//! Files the server keeps on behalf of users, such as avatars, are stored by
+//! key under `files` in the data directory. Keys are `/`-separated paths of
+//! plain names, so they cannot escape the storage directory. Without a data
+//! directory, storing files is not available.
+
+use std::path::{Path, PathBuf};
+
+/// Stores files by key.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    /// Directory the files are stored in, if storage is available.
+    root: Option<PathBuf>,
+}
+
+impl FileStorage {
+    /// Create a storage keeping files under `root`, or an unavailable
+    /// storage if there is none.
+    #[must_use]
+    pub const fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    /// Create the storage of a data directory.
+    #[must_use]
+    pub fn in_data_dir(data_dir: Option<&Path>) -> Self {
+        Self::new(data_dir.map(|dir| dir.join("files")))
+    }
+
+    /// Check if files can be stored.
+    #[must_use]
+    pub const fn is_available(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// Store a file, replacing any file with the same key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage is unavailable, the key is invalid, or the
+    /// file cannot be written.
+    pub async fn put(&self, key: &str, contents: &[u8]) -> orbis_core::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write next to the target and rename, so readers never see a partial file
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, contents).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    /// Read a file, if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage is unavailable, the key is invalid, or the
+    /// file cannot be read.
+    pub async fn get(&self, key: &str) -> orbis_core::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove a file; removing a missing file succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage is unavailable, the key is invalid, or the
+    /// file cannot be removed.
+    pub async fn delete(&self, key: &str) -> orbis_core::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Resolve the path of a key.
+    fn path(&self, key: &str) -> orbis_core::Result<PathBuf> {
+        let root = self
+            .root
+            .as_ref()
+            .ok_or_else(|| orbis_core::Error::config("Set a data directory to store files"))?;
+
+        let valid = !key.is_empty()
+            && key.split('/').all(|segment| {
+                !segment.is_empty()
+                    && !segment.starts_with('.')
+                    && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            });
+        if !valid {
+            return Err(orbis_core::Error::validation(format!("Invalid file key '{}'", key)));
+        }
+
+        Ok(root.join(key))
+    }
+}
+
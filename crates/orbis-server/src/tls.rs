@@ -1,9 +1,138 @@
 //! TLS configuration.
+//!
+//! With client authentication enabled, clients authenticate with
+//! certificates chaining to the configured CA (mutual TLS). The
+//! verified certificate of a connection is attached to each of its requests
+//! as a [`ClientCertificate`].
 
-use orbis_config::TlsConfig;
-use rustls::ServerConfig;
+use orbis_config::{ClientAuthMode, TlsConfig};
+use rustls::pki_types::CertificateDer;
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use simple_asn1::ASN1Block;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+/// OID of the common name attribute.
+const COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+
+/// OID of the PKCS #9 email address attribute.
+const EMAIL_ADDRESS: &[u64] = &[1, 2, 840, 113_549, 1, 9, 1];
+
+/// Short names of subject attributes, by OID.
+const ATTRIBUTE_NAMES: &[(&[u64], &str)] = &[
+    (COMMON_NAME, "CN"),
+    (&[2, 5, 4, 6], "C"),
+    (&[2, 5, 4, 7], "L"),
+    (&[2, 5, 4, 8], "ST"),
+    (&[2, 5, 4, 10], "O"),
+    (&[2, 5, 4, 11], "OU"),
+    (EMAIL_ADDRESS, "emailAddress"),
+];
+
+/// Verified client certificate of a mutual TLS connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientCertificate {
+    /// Subject distinguished name, e.g. `O=Example, CN=alice`.
+    pub subject: String,
+
+    /// Subject common name.
+    pub common_name: Option<String>,
+
+    /// Subject email address.
+    pub email: Option<String>,
+
+    /// SHA-256 fingerprint of the certificate, in hex.
+    pub fingerprint: String,
+}
+
+impl ClientCertificate {
+    /// Read the subject of a DER-encoded certificate.
+    ///
+    /// Returns `None` if the certificate cannot be parsed.
+    #[must_use]
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let blocks = simple_asn1::from_der(der).ok()?;
+        let ASN1Block::Sequence(_, certificate) = blocks.first()? else {
+            return None;
+        };
+        let ASN1Block::Sequence(_, tbs) = certificate.first()? else {
+            return None;
+        };
+
+        // The version is only present, explicitly tagged, in v2 and v3 certificates
+        let offset = usize::from(matches!(tbs.first(), Some(ASN1Block::Explicit(..))));
+        let ASN1Block::Sequence(_, rdns) = tbs.get(offset + 4)? else {
+            return None;
+        };
+
+        let attributes: Vec<(Vec<u64>, String)> = rdns
+            .iter()
+            .filter_map(|rdn| if let ASN1Block::Set(_, attributes) = rdn { Some(attributes) } else { None })
+            .flatten()
+            .filter_map(attribute)
+            .collect();
+        let find = |oid: &[u64]| {
+            attributes
+                .iter()
+                .find(|(attribute, _)| attribute == oid)
+                .map(|(_, value)| value.clone())
+        };
+
+        Some(Self {
+            subject: attributes
+                .iter()
+                .map(|(oid, value)| format!("{}={}", attribute_name(oid), value))
+                .collect::<Vec<_>>()
+                .join(", "),
+            common_name: find(COMMON_NAME),
+            email: find(EMAIL_ADDRESS),
+            fingerprint: format!("{:x}", Sha256::digest(der)),
+        })
+    }
+
+    /// Get the names the certificate may identify a user by: its common
+    /// name, then its email address.
+    pub fn identities(&self) -> impl Iterator<Item = &str> {
+        self.common_name.iter().chain(&self.email).map(String::as_str)
+    }
+}
+
+/// Read a subject attribute: its OID and string value.
+fn attribute(block: &ASN1Block) -> Option<(Vec<u64>, String)> {
+    let ASN1Block::Sequence(_, pair) = block else {
+        return None;
+    };
+    let (Some(ASN1Block::ObjectIdentifier(_, oid)), Some(value)) = (pair.first(), pair.get(1)) else {
+        return None;
+    };
+
+    let (ASN1Block::UTF8String(_, value)
+    | ASN1Block::PrintableString(_, value)
+    | ASN1Block::TeletexString(_, value)
+    | ASN1Block::IA5String(_, value)
+    | ASN1Block::UniversalString(_, value)
+    | ASN1Block::BMPString(_, value)) = value
+    else {
+        return None;
+    };
+    Some((oid.as_vec::<u64>().ok()?, value.clone()))
+}
+
+/// Get the short name of an attribute, or its dotted OID.
+fn attribute_name(oid: &[u64]) -> String {
+    ATTRIBUTE_NAMES
+        .iter()
+        .find(|(known, _)| *known == oid)
+        .map_or_else(
+            || oid.iter().map(u64::to_string).collect::<Vec<_>>().join("."),
+            |(_, name)| (*name).to_owned(),
+        )
+}
 
 /// Create TLS server configuration.
 ///
@@ -20,13 +149,7 @@ pub fn create_tls_config(config: &TlsConfig) -> orbis_core::Result<ServerConfig>
     })?;
 
     // Load certificate chain
-    let cert_file = File::open(cert_path).map_err(|e| {
-        orbis_core::Error::config(format!("Failed to open certificate file: {}", e))
-    })?;
-    let mut cert_reader = BufReader::new(cert_file);
-    let certs: Vec<_> = rustls_pemfile::certs(&mut cert_reader)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| orbis_core::Error::config(format!("Failed to parse certificates: {}", e)))?;
+    let certs = load_certificates(cert_path)?;
 
     // Load private key
     let key_file = File::open(key_path).map_err(|e| {
@@ -38,11 +161,154 @@ pub fn create_tls_config(config: &TlsConfig) -> orbis_core::Result<ServerConfig>
         .map_err(|e| orbis_core::Error::config(format!("Failed to parse private key: {}", e)))?
         .ok_or_else(|| orbis_core::Error::config("No private key found in file"))?;
 
-    // Build server config
-    let server_config = ServerConfig::builder()
-        .with_no_client_auth()
+    // Build server config, verifying client certificates for mutual TLS
+    let builder = ServerConfig::builder();
+    let builder = match (config.client_auth, &config.ca_path) {
+        (ClientAuthMode::None, _) | (_, None) => builder.with_no_client_auth(),
+        (mode, Some(ca_path)) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certificates(ca_path)? {
+                roots.add(cert).map_err(|e| {
+                    orbis_core::Error::config(format!("Invalid client CA certificate: {}", e))
+                })?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+            let verifier = if mode == ClientAuthMode::Optional {
+                verifier.allow_unauthenticated().build()
+            } else {
+                verifier.build()
+            };
+            let verifier = verifier.map_err(|e| {
+                orbis_core::Error::config(format!("Failed to create client certificate verifier: {}", e))
+            })?;
+            builder.with_client_cert_verifier(verifier)
+        }
+    };
+
+    let server_config = builder
         .with_single_cert(certs, key)
         .map_err(|e| orbis_core::Error::config(format!("Failed to create TLS config: {}", e)))?;
 
     Ok(server_config)
 }
+
+/// Load the certificates of a PEM file.
+fn load_certificates(path: &Path) -> orbis_core::Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).map_err(|e| {
+        orbis_core::Error::config(format!("Failed to open certificate file {}: {}", path.display(), e))
+    })?;
+    let mut reader = BufReader::new(file);
+    rustls_pemfile::certs(&mut reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| orbis_core::Error::config(format!("Failed to parse certificates: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_asn1::{ASN1Class, BigInt, BigUint, OID};
+
+    /// Build an object identifier block.
+    fn oid(arcs: &[u64]) -> ASN1Block {
+        ASN1Block::ObjectIdentifier(0, OID::new(arcs.iter().copied().map(BigUint::from).collect()))
+    }
+
+    /// Build a UTC time block, encoded as is.
+    fn time(value: &str) -> ASN1Block {
+        ASN1Block::Unknown(ASN1Class::Universal, false, 0, BigUint::from(23u8), value.as_bytes().to_vec())
+    }
+
+    /// Build the DER of a certificate with the given subject attributes,
+    /// with an explicit version for v3 certificates.
+    fn certificate(v3: bool, subject: Vec<(&[u64], ASN1Block)>) -> Vec<u8> {
+        let algorithm = ASN1Block::Sequence(0, vec![oid(&[1, 3, 101, 112])]);
+        let rdns = subject
+            .into_iter()
+            .map(|(attribute, value)| ASN1Block::Set(0, vec![ASN1Block::Sequence(0, vec![oid(attribute), value])]))
+            .collect();
+
+        let mut tbs = Vec::new();
+        if v3 {
+            tbs.push(ASN1Block::Explicit(
+                ASN1Class::ContextSpecific,
+                0,
+                BigUint::from(0u8),
+                Box::new(ASN1Block::Integer(0, BigInt::from(2u8))),
+            ));
+        }
+        tbs.extend([
+            ASN1Block::Integer(0, BigInt::from(1u8)),
+            algorithm.clone(),
+            ASN1Block::Sequence(
+                0,
+                vec![ASN1Block::Set(
+                    0,
+                    vec![ASN1Block::Sequence(
+                        0,
+                        vec![oid(COMMON_NAME), ASN1Block::PrintableString(0, "Example CA".to_owned())],
+                    )],
+                )],
+            ),
+            ASN1Block::Sequence(0, vec![time("250101000000Z"), time("350101000000Z")]),
+            ASN1Block::Sequence(0, rdns),
+            ASN1Block::Sequence(0, vec![algorithm.clone(), ASN1Block::BitString(0, 256, vec![0; 32])]),
+        ]);
+
+        let certificate = ASN1Block::Sequence(
+            0,
+            vec![ASN1Block::Sequence(0, tbs), algorithm, ASN1Block::BitString(0, 512, vec![0; 64])],
+        );
+        simple_asn1::to_der(&certificate).unwrap()
+    }
+
+    #[test]
+    fn test_v1_certificate_subject() {
+        let der = certificate(
+            false,
+            vec![
+                (&[2, 5, 4, 10], ASN1Block::PrintableString(0, "Example".to_owned())),
+                (COMMON_NAME, ASN1Block::PrintableString(0, "alice".to_owned())),
+            ],
+        );
+
+        let certificate = ClientCertificate::from_der(&der).unwrap();
+        assert_eq!(certificate.subject, "O=Example, CN=alice");
+        assert_eq!(certificate.common_name.as_deref(), Some("alice"));
+        assert_eq!(certificate.email, None);
+        assert_eq!(certificate.fingerprint, format!("{:x}", Sha256::digest(&der)));
+        assert_eq!(certificate.identities().collect::<Vec<_>>(), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_v3_certificate_subject() {
+        let der = certificate(
+            true,
+            vec![
+                (&[2, 5, 4, 6], ASN1Block::PrintableString(0, "IT".to_owned())),
+                (COMMON_NAME, ASN1Block::UTF8String(0, "Bob Rossi".to_owned())),
+                (EMAIL_ADDRESS, ASN1Block::IA5String(0, "bob@example.com".to_owned())),
+                (&[1, 3, 6, 1, 4, 1, 99_999, 1], ASN1Block::UTF8String(0, "custom".to_owned())),
+            ],
+        );
+
+        let certificate = ClientCertificate::from_der(&der).unwrap();
+        assert_eq!(
+            certificate.subject,
+            "C=IT, CN=Bob Rossi, emailAddress=bob@example.com, 1.3.6.1.4.1.99999.1=custom"
+        );
+        assert_eq!(certificate.common_name.as_deref(), Some("Bob Rossi"));
+        assert_eq!(certificate.email.as_deref(), Some("bob@example.com"));
+        assert_eq!(
+            certificate.identities().collect::<Vec<_>>(),
+            vec!["Bob Rossi", "bob@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_certificate() {
+        assert_eq!(ClientCertificate::from_der(b"not a certificate"), None);
+        let der = simple_asn1::to_der(&ASN1Block::Sequence(0, vec![ASN1Block::Null(0)])).unwrap();
+        assert_eq!(ClientCertificate::from_der(&der), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Core API versioning.
+//!
+//! The API is served under `/api/v{N}` for every version in [`API_VERSIONS`],
+//! and unversioned `/api` paths serve the version asked for in the
+//! `Accept-Version` header, or else [`CURRENT_API_VERSION`]. Every API
+//! response names the version that served it in the `API-Version` header.
+//!
+//! Routes on their way out are marked with [`deprecated`], which adds the
+//! `Deprecation`, `Sunset` and `Link` headers to their responses so clients
+//! can move on before they are removed.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use chrono::{DateTime, Utc};
+use orbis_plugin::{API_VERSIONS, CURRENT_API_VERSION};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::error::ServerError;
+
+/// Header naming the API version a client asks for on unversioned paths.
+pub const ACCEPT_VERSION_HEADER: HeaderName = HeaderName::from_static("accept-version");
+
+/// Header naming the API version that served a response.
+pub const API_VERSION_HEADER: HeaderName = HeaderName::from_static("api-version");
+
+/// Header marking a deprecated route (RFC 9745).
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Header giving when a deprecated route stops working (RFC 8594).
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// API version a request is served with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersion(pub u32);
+
+/// API version middleware function.
+///
+/// Records the requested API version for handlers, refusing versions this
+/// host does not serve, and names it in the response.
+pub async fn api_version_middleware(mut request: Request<Body>, next: Next) -> Response {
+    let version = match requested_version(request.uri().path(), request.headers()) {
+        Some(Ok(version)) => version,
+        Some(Err(e)) => return ServerError(e).into_response(),
+        None => return next.run(request).await,
+    };
+
+    request.extensions_mut().insert(ApiVersion(version));
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(API_VERSION_HEADER, HeaderValue::from(version));
+    response
+}
+
+/// Get the API version requested by a path and its headers (`None` outside
+/// the API).
+///
+/// # Errors
+///
+/// Returns an error if the version is malformed or not served.
+fn requested_version(path: &str, headers: &HeaderMap) -> Option<orbis_core::Result<u32>> {
+    let rest = path.strip_prefix("/api")?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+
+    // `/api/v{N}/...` names the version in the path
+    let segment = rest.trim_start_matches('/').split('/').next().unwrap_or_default();
+    if let Some(digits) = segment.strip_prefix('v')
+        && !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Some(
+            digits
+                .parse()
+                .ok()
+                .filter(|version| API_VERSIONS.contains(version))
+                .ok_or_else(|| orbis_core::Error::not_found(unsupported(digits))),
+        );
+    }
+
+    let Some(value) = headers.get(ACCEPT_VERSION_HEADER) else {
+        return Some(Ok(CURRENT_API_VERSION));
+    };
+    let requested = value.to_str().unwrap_or_default().trim();
+    let digits = requested.strip_prefix('v').unwrap_or(requested);
+    Some(
+        digits
+            .parse()
+            .ok()
+            .filter(|version| API_VERSIONS.contains(version))
+            .ok_or_else(|| orbis_core::Error::validation(unsupported(requested))),
+    )
+}
+
+/// Get a path without its API version, e.g. `/api/users` for `/api/v1/users`.
+///
+/// Checks on API paths go through this so versioned paths cannot get around
+/// them.
+#[must_use]
+pub fn unversioned_path(path: &str) -> Cow<'_, str> {
+    let Some(rest) = path.strip_prefix("/api/v") else {
+        return Cow::Borrowed(path);
+    };
+    let (digits, tail) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Cow::Borrowed(path);
+    }
+    Cow::Owned(format!("/api{}", tail))
+}
+
+/// Describe an API version this host does not serve.
+fn unsupported(requested: &str) -> String {
+    let served: Vec<String> = API_VERSIONS.iter().map(|version| format!("v{}", version)).collect();
+    format!("API version '{}' is not supported; this server serves {}", requested, served.join(", "))
+}
+
+/// Deprecation of a route.
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    /// When the route was deprecated.
+    pub since: DateTime<Utc>,
+
+    /// When the route stops working, if planned.
+    pub sunset: Option<DateTime<Utc>>,
+
+    /// Page describing the deprecation and what to use instead.
+    pub link: Option<String>,
+}
+
+impl Deprecation {
+    /// Deprecate a route from the given time.
+    #[must_use]
+    pub const fn since(since: DateTime<Utc>) -> Self {
+        Self {
+            since,
+            sunset: None,
+            link: None,
+        }
+    }
+
+    /// Set when the route stops working.
+    #[must_use]
+    pub const fn with_sunset(mut self, sunset: DateTime<Utc>) -> Self {
+        self.sunset = Some(sunset);
+        self
+    }
+
+    /// Set the page describing the deprecation.
+    #[must_use]
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    /// Add the deprecation headers to a response.
+    fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&format!("@{}", self.since.timestamp())) {
+            headers.insert(DEPRECATION_HEADER, value);
+        }
+        if let Some(sunset) = self.sunset
+            && let Ok(value) = HeaderValue::from_str(&sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        {
+            headers.insert(SUNSET_HEADER, value);
+        }
+        if let Some(ref link) = self.link
+            && let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"deprecation\"", link))
+        {
+            headers.append(header::LINK, value);
+        }
+    }
+}
+
+/// Mark a route as deprecated, adding the deprecation headers to its responses.
+pub fn deprecated<S>(route: MethodRouter<S>, deprecation: Deprecation) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    route.layer(axum::middleware::from_fn_with_state(Arc::new(deprecation), deprecation_middleware))
+}
+
+/// Deprecation middleware function.
+async fn deprecation_middleware(
+    State(deprecation): State<Arc<Deprecation>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    deprecation.apply(response.headers_mut());
+    response
+}
@@ -0,0 +1,163 @@
+//! Virtual hosts.
+//!
+//! Requests are routed to the profile whose hostnames include the `Host` of
+//! the request or whose path prefix starts its path, and to the default
+//! profile otherwise. Each profile has its own [`AppState`](crate::AppState), initialized on
+//! its first request.
+
+use crate::error::ServerError;
+use crate::{create_app, create_state, notify_started, start_background_tasks};
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, uri::PathAndQuery, Request, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use orbis_config::{Config, VirtualHostConfig};
+use orbis_core::shutdown;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tower::ServiceExt;
+
+/// Profiles served on one port.
+pub struct VirtualHosts {
+    /// App of the default profile.
+    default: Router,
+
+    /// Profiles selected by hostname or path prefix.
+    hosts: Vec<VirtualHost>,
+}
+
+/// A profile served on a virtual host.
+struct VirtualHost {
+    /// Virtual host configuration.
+    host: VirtualHostConfig,
+
+    /// Configuration of the profile.
+    config: Arc<Config>,
+
+    /// App of the profile, once initialized.
+    app: OnceCell<Router>,
+
+    /// Shutdown coordinator of the server, shared by all profiles.
+    shutdown: Arc<shutdown::Coordinator>,
+}
+
+impl VirtualHosts {
+    /// Create the virtual hosts configured for a server, around the app of
+    /// its default profile. Profiles register with the server's shutdown
+    /// coordinator.
+    #[must_use]
+    pub fn new(config: &Config, default: Router, shutdown: Arc<shutdown::Coordinator>) -> Self {
+        let hosts = config
+            .virtual_hosts
+            .iter()
+            .map(|host| VirtualHost {
+                host: host.clone(),
+                config: Arc::new(config.for_virtual_host(host)),
+                app: OnceCell::new(),
+                shutdown: Arc::clone(&shutdown),
+            })
+            .collect();
+
+        Self { default, hosts }
+    }
+
+    /// Create the router dispatching requests to profiles.
+    pub fn into_router(self) -> Router {
+        Router::new().fallback(dispatch).with_state(Arc::new(self))
+    }
+
+    /// Find the profile selecting a request, by hostname then path prefix.
+    ///
+    /// Returns the profile and the request path without its prefix, if one matched.
+    fn select(&self, request: &Request<Body>) -> Option<(&VirtualHost, Option<String>)> {
+        let hostname = request
+            .headers()
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| request.uri().host())
+            .map(strip_port);
+
+        if let Some(hostname) = hostname
+            && let Some(host) = self.hosts.iter().find(|host| host.host.matches_hostname(hostname))
+        {
+            return Some((host, None));
+        }
+
+        let path = request.uri().path();
+        self.hosts.iter().find_map(|host| {
+            host.host
+                .strip_prefix(path)
+                .map(|rest| (host, Some(rest.to_owned())))
+        })
+    }
+}
+
+impl VirtualHost {
+    /// Get the app of the profile, initializing the profile on first use.
+    ///
+    /// Requests arriving during initialization wait for it; a failed
+    /// initialization is retried by the next request.
+    async fn app(&self) -> orbis_core::Result<Router> {
+        self.app
+            .get_or_try_init(|| async {
+                tracing::info!("Initializing profile '{}'", self.host.profile);
+                let state = create_state(self.config.clone(), Arc::clone(&self.shutdown)).await?;
+                start_background_tasks(&state)?;
+                notify_started(&state);
+                Ok::<_, orbis_core::Error>(create_app(state))
+            })
+            .await
+            .cloned()
+    }
+}
+
+/// Route a request to the app of its profile.
+async fn dispatch(State(hosts): State<Arc<VirtualHosts>>, mut request: Request<Body>) -> Response {
+    let Some((host, path)) = hosts.select(&request) else {
+        return call(hosts.default.clone(), request).await;
+    };
+
+    let app = match host.app().await {
+        Ok(app) => app,
+        Err(e) => {
+            tracing::error!("Failed to initialize profile '{}': {}", host.host.profile, e);
+            return ServerError::from(e).into_response();
+        }
+    };
+
+    if let Some(path) = path {
+        *request.uri_mut() = strip_uri(request.uri(), &path);
+    }
+    call(app, request).await
+}
+
+/// Call an app with a request.
+async fn call(app: Router, request: Request<Body>) -> Response {
+    match app.oneshot(request).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    }
+}
+
+/// Replace the path of a request URI, keeping its query.
+fn strip_uri(uri: &Uri, path: &str) -> Uri {
+    let path_and_query = uri
+        .query()
+        .map_or_else(|| path.to_owned(), |query| format!("{}?{}", path, query));
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = path_and_query.parse::<PathAndQuery>().ok();
+    Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+}
+
+/// Remove the port from a `Host` header value.
+fn strip_port(host: &str) -> &str {
+    // IPv6 literals are bracketed, e.g. `[::1]:8080`
+    if let Some(end) = host.find(']') {
+        return host.get(..=end).unwrap_or(host);
+    }
+    host.split(':').next().unwrap_or(host)
+}
@@ -0,0 +1,201 @@
+//! Outgoing webhooks.
+//!
+//! Webhooks are JSON `POST` requests delivered by a `webhook.deliver` job, so
+//! failed deliveries are retried (and eventually dead-lettered) by the job
+//! queue. HTTPS endpoints are verified against the bundled Mozilla roots.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use hyper::header::{CONTENT_TYPE, HOST, USER_AGENT};
+use hyper::{Request, StatusCode, Uri};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+use crate::jobs::{Job, JobHandler, JobQueue, NewJob};
+
+/// Job kind delivering a webhook (the payload is a [`WebhookDelivery`]).
+pub const WEBHOOK_JOB: &str = "webhook.deliver";
+
+/// Time a webhook endpoint has to respond.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A webhook request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Endpoint URL.
+    pub url: String,
+
+    /// JSON body.
+    pub body: Value,
+}
+
+/// Register the webhook delivery job.
+pub fn register(jobs: &JobQueue) {
+    jobs.register(WEBHOOK_JOB, Arc::new(WebhookHandler));
+}
+
+/// Queue a webhook for delivery.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid or the job cannot be queued.
+pub async fn send(jobs: &JobQueue, url: &str, body: Value) -> orbis_core::Result<Job> {
+    validate_url(url)?;
+    let payload = serde_json::to_value(WebhookDelivery {
+        url: url.to_string(),
+        body,
+    })
+    .map_err(|e| orbis_core::Error::serialization(e.to_string()))?;
+    jobs.enqueue(NewJob::new(WEBHOOK_JOB, payload)).await
+}
+
+/// Check a webhook URL is an absolute HTTP or HTTPS URL.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid.
+pub fn validate_url(url: &str) -> orbis_core::Result<()> {
+    Target::parse(url).map(|_| ())
+}
+
+/// Where a webhook is sent.
+struct Target {
+    /// Parsed URL.
+    uri: Uri,
+
+    /// Host name.
+    host: String,
+
+    /// Port.
+    port: u16,
+
+    /// Whether to use TLS.
+    https: bool,
+}
+
+impl Target {
+    /// Parse a webhook URL.
+    fn parse(url: &str) -> orbis_core::Result<Self> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid webhook URL '{}': {}", url, e)))?;
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => {
+                return Err(orbis_core::Error::validation(format!(
+                    "Webhook URL '{}' must use http or https",
+                    url
+                )));
+            }
+        };
+        let host = uri
+            .host()
+            .ok_or_else(|| orbis_core::Error::validation(format!("Webhook URL '{}' has no host", url)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+        Ok(Self { uri, host, port, https })
+    }
+}
+
+/// Post a JSON body to a URL.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, times out, or the endpoint does
+/// not respond with a success status.
+pub async fn post_json(url: &str, body: &Value) -> orbis_core::Result<()> {
+    let target = Target::parse(url)?;
+    let payload = serde_json::to_vec(body).map_err(|e| orbis_core::Error::serialization(e.to_string()))?;
+    let request = Request::post(target.uri.path_and_query().map_or("/", |path| path.as_str()))
+        .header(HOST, target.uri.authority().map_or(target.host.as_str(), |authority| authority.as_str()))
+        .header(CONTENT_TYPE, "application/json")
+        .header(USER_AGENT, concat!("orbis/", env!("CARGO_PKG_VERSION")))
+        .body(Body::from(payload))
+        .map_err(|e| orbis_core::Error::internal(format!("Failed to build webhook request: {}", e)))?;
+
+    let status = tokio::time::timeout(WEBHOOK_TIMEOUT, deliver(&target, request))
+        .await
+        .map_err(|e| orbis_core::Error::timeout(format!("Webhook {} timed out: {}", url, e)))??;
+    if !status.is_success() {
+        return Err(orbis_core::Error::internal(format!("Webhook {} responded with {}", url, status)));
+    }
+    Ok(())
+}
+
+/// Connect to a webhook target and send the request.
+async fn deliver(target: &Target, request: Request<Body>) -> orbis_core::Result<StatusCode> {
+    let stream = TcpStream::connect((target.host.as_str(), target.port))
+        .await
+        .map_err(|e| orbis_core::Error::internal(format!("Failed to connect to {}: {}", target.host, e)))?;
+    if !target.https {
+        return send_request(stream, request).await;
+    }
+
+    let server_name = rustls::pki_types::ServerName::try_from(target.host.clone())
+        .map_err(|e| orbis_core::Error::validation(format!("Invalid webhook host '{}': {}", target.host, e)))?;
+    let stream = tls_connector()
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| orbis_core::Error::internal(format!("TLS handshake with {} failed: {}", target.host, e)))?;
+    send_request(stream, request).await
+}
+
+/// Send a request over an HTTP/1.1 connection.
+async fn send_request<S>(stream: S, request: Request<Body>) -> orbis_core::Result<StatusCode>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| orbis_core::Error::internal(format!("Webhook handshake failed: {}", e)))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            tracing::debug!("Webhook connection closed: {}", e);
+        }
+    });
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| orbis_core::Error::internal(format!("Webhook request failed: {}", e)))?;
+    Ok(response.status())
+}
+
+/// TLS connector trusting the bundled Mozilla roots.
+fn tls_connector() -> TlsConnector {
+    static CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+    CONNECTOR
+        .get_or_init(|| {
+            let roots = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            let config = rustls::ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            TlsConnector::from(Arc::new(config))
+        })
+        .clone()
+}
+
+/// Delivers the webhook in the job payload.
+struct WebhookHandler;
+
+#[async_trait]
+impl JobHandler for WebhookHandler {
+    async fn run(&self, job: &Job) -> orbis_core::Result<()> {
+        let delivery: WebhookDelivery = serde_json::from_value(job.payload.clone())
+            .map_err(|e| orbis_core::Error::validation(format!("Invalid webhook job payload: {}", e)))?;
+        post_json(&delivery.url, &delivery.body).await
+    }
+}
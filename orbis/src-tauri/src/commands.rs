@@ -1,7 +1,7 @@
 //! Tauri commands for IPC.
 
 use crate::{OrbisState, state::AuthSession};
-use orbis_core::AppMode;
+use orbis_core::{AppMode, KeyDerivation};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::path::PathBuf;
@@ -35,11 +35,15 @@ struct ServerUserInfo {
 }
 
 /// Login command - authenticates user and creates session
+///
+/// The session is shared by all windows, which are notified with a
+/// `session-changed` event.
 #[tauri::command]
 pub async fn login(
     username: String,
     password: String,
     state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
 ) -> Result<LoginResponse, String> {
     if username.is_empty() || password.is_empty() {
         return Ok(LoginResponse {
@@ -49,13 +53,26 @@ pub async fn login(
         });
     }
 
-    if state.is_standalone() || state.is_server() {
+    if state.is_locked() {
+        return Ok(LoginResponse {
+            success: false,
+            message: "Profile is locked. Unlock it with its passphrase first".to_string(),
+            session: None,
+        });
+    }
+
+    let response = if state.is_standalone() || state.is_server() {
         // Use local auth service
-        login_standalone(&username, &password, &state).await
+        login_standalone(&username, &password, &state).await?
     } else {
         // Client mode: authenticate against remote server
-        login_client(&username, &password, &state).await
+        login_client(&username, &password, &state).await?
+    };
+
+    if response.success {
+        let _ = app.emit("session-changed", &response.session);
     }
+    Ok(response)
 }
 
 /// Authenticate using local auth service (standalone/server mode)
@@ -92,7 +109,7 @@ async fn login_standalone(
     };
 
     // Authenticate using orbis-auth
-    match auth.authenticate(username, password, None, None).await {
+    match auth.authenticate(username, password, None, None, None).await {
         Ok(result) => {
             // Build roles from user info
             let mut roles = Vec::new();
@@ -211,11 +228,12 @@ async fn login_client(
     })
 }
 
-/// Logout command - destroys current session
+/// Logout command - destroys current session in all windows
 #[tauri::command]
-pub async fn logout(state: State<'_, OrbisState>) -> Result<Value, String> {
+pub async fn logout(state: State<'_, OrbisState>, app: tauri::AppHandle) -> Result<Value, String> {
     // Clear session
     state.set_session(None);
+    let _ = app.emit("session-changed", Option::<AuthSession>::None);
 
     Ok(json!({
         "success": true,
@@ -269,22 +287,28 @@ pub fn get_mode(state: State<'_, OrbisState>) -> Value {
 #[tauri::command]
 pub fn get_profile(state: State<'_, OrbisState>) -> Result<Value, String> {
     let profile_name = state.config().active_profile.as_deref().unwrap_or("default");
+    let encrypted = active_profile(state.config()).is_some_and(|profile| profile.encryption.is_some());
     
     Ok(json!({
         "name": profile_name,
         "server_url": state.server_url(),
         "is_default": profile_name == "default",
+        "encrypted": encrypted,
+        "locked": state.is_locked(),
     }))
 }
 
 /// Profile data structure for storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct StoredProfile {
-    name: String,
+pub struct StoredProfile {
+    pub name: String,
     server_url: Option<String>,
     is_default: bool,
     use_tls: bool,
     created_at: String,
+    /// Key derivation parameters, if the profile's data is encrypted at rest
+    #[serde(default)]
+    pub encryption: Option<KeyDerivation>,
 }
 
 impl Default for StoredProfile {
@@ -295,12 +319,30 @@ impl Default for StoredProfile {
             is_default: true,
             use_tls: true,
             created_at: chrono::Utc::now().to_rfc3339(),
+            encryption: None,
         }
     }
 }
 
-/// Get profiles file path
-fn get_profiles_path() -> PathBuf {
+/// Get the active profile.
+pub fn active_profile(config: &orbis_config::Config) -> Option<StoredProfile> {
+    let name = config.active_profile.as_deref().unwrap_or("default");
+    load_profiles().into_iter().find(|p| p.name == name)
+}
+
+/// Get the database path of an encrypted profile.
+///
+/// Encrypted profiles keep their own database, so an existing plaintext
+/// database is never opened with a key.
+pub fn encrypted_database_path(name: &str) -> PathBuf {
+    get_profiles_path()
+        .with_file_name("profiles")
+        .join(name)
+        .join("orbis.db")
+}
+
+/// Get the Orbis data directory, creating it if missing.
+pub fn data_dir() -> PathBuf {
     // Use platform-specific data directory
     let data_dir = if cfg!(target_os = "windows") {
         std::env::var("APPDATA")
@@ -319,7 +361,12 @@ fn get_profiles_path() -> PathBuf {
     
     let orbis_dir = data_dir.join("orbis");
     std::fs::create_dir_all(&orbis_dir).ok();
-    orbis_dir.join("profiles.json")
+    orbis_dir
+}
+
+/// Get profiles file path
+fn get_profiles_path() -> PathBuf {
+    data_dir().join("profiles.json")
 }
 
 /// Load profiles from file
@@ -361,6 +408,7 @@ pub async fn list_profiles(state: State<'_, OrbisState>) -> Result<Value, String
                 "is_default": p.is_default,
                 "use_tls": p.use_tls,
                 "created_at": p.created_at,
+                "encrypted": p.encryption.is_some(),
             })
         })
         .collect();
@@ -372,11 +420,15 @@ pub async fn list_profiles(state: State<'_, OrbisState>) -> Result<Value, String
 }
 
 /// Create a new profile
+///
+/// With a passphrase, the profile's database and plugin state are encrypted
+/// at rest, and the passphrase is asked for each time the profile is opened.
 #[tauri::command]
 pub async fn create_profile(
     name: String,
     server_url: Option<String>,
     use_tls: Option<bool>,
+    passphrase: Option<String>,
 ) -> Result<Value, String> {
     if name.is_empty() {
         return Err("Profile name cannot be empty".to_string());
@@ -389,12 +441,19 @@ pub async fn create_profile(
         return Err(format!("Profile '{}' already exists", name));
     }
 
+    // The key is derived again when the profile is unlocked
+    let encryption = passphrase
+        .map(|passphrase| KeyDerivation::create(&passphrase).map(|(encryption, _key)| encryption))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
     let new_profile = StoredProfile {
         name: name.clone(),
         server_url,
         is_default: false,
         use_tls: use_tls.unwrap_or(true),
         created_at: chrono::Utc::now().to_rfc3339(),
+        encryption,
     };
 
     profiles.push(new_profile.clone());
@@ -408,10 +467,59 @@ pub async fn create_profile(
             "server_url": new_profile.server_url,
             "is_default": new_profile.is_default,
             "use_tls": new_profile.use_tls,
+            "encrypted": new_profile.encryption.is_some(),
         }
     }))
 }
 
+/// Unlock the active encrypted profile, opening its database and plugins.
+#[tauri::command]
+pub async fn unlock_profile(
+    passphrase: String,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    if !state.is_locked() {
+        return Err("Profile is not locked".to_string());
+    }
+
+    let profile = active_profile(state.config()).ok_or("Active profile not found")?;
+    let encryption = profile.encryption.as_ref().ok_or("Profile is not encrypted")?;
+    let key = encryption.unlock(&passphrase).map_err(|e| e.to_string())?;
+
+    let mut config = state.config().clone();
+    config.database.backend = orbis_config::DatabaseBackend::Sqlite;
+    config.database.url = None;
+    config.database.path = Some(encrypted_database_path(&profile.name));
+    config.database.encryption_key = Some(key);
+
+    crate::open_profile(&state, config, &app).await.map_err(|e| e.to_string())?;
+    tracing::info!("Unlocked profile '{}'", profile.name);
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Profile '{}' unlocked", profile.name)
+    }))
+}
+
+/// Lock the active encrypted profile, closing its database and session.
+#[tauri::command]
+pub async fn lock_profile(state: State<'_, OrbisState>, app: tauri::AppHandle) -> Result<Value, String> {
+    let profile = active_profile(state.config()).ok_or("Active profile not found")?;
+    if profile.encryption.is_none() {
+        return Err("Only encrypted profiles can be locked".to_string());
+    }
+
+    state.lock().await?;
+    let _ = app.emit("session-changed", Option::<AuthSession>::None);
+    tracing::info!("Locked profile '{}'", profile.name);
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Profile '{}' locked", profile.name)
+    }))
+}
+
 /// Delete a profile
 #[tauri::command]
 pub async fn delete_profile(name: String) -> Result<Value, String> {
@@ -439,7 +547,7 @@ pub async fn delete_profile(name: String) -> Result<Value, String> {
 #[tauri::command]
 pub async fn switch_profile(
     name: String,
-    _state: State<'_, OrbisState>,
+    state: State<'_, OrbisState>,
 ) -> Result<Value, String> {
     let profiles = load_profiles();
     
@@ -452,7 +560,12 @@ pub async fn switch_profile(
     // Note: Actually switching the profile would require app restart
     // or dynamic reconfiguration which isn't implemented yet.
     // For now, we return success and the app should restart to apply changes.
-    
+
+    if let Some(pm) = state.plugins() {
+        let event = orbis_plugin::HookEvent::ProfileSwitched { profile: name.clone() };
+        pm.run_hooks(&event, None).await;
+    }
+
     Ok(json!({
         "success": true,
         "message": format!("Switched to profile: {}. Restart the app to apply changes.", name),
@@ -503,10 +616,18 @@ pub fn get_plugin_pages(state: State<'_, OrbisState>) -> Result<Value, String> {
             .iter()
             .map(|info| info.manifest.name.clone())
             .collect();
+
+        // With auth enabled, only pages the session's roles and permissions allow are shown
+        let viewer = state.get_session().map(|session| orbis_plugin::ViewerAccess {
+            roles: session.roles,
+            permissions: session.permissions,
+        });
+        let enforce_access = state.auth().is_some();
         
         pm.get_all_pages()
             .iter()
             .filter(|(plugin, _)| running_plugins.contains(plugin))
+            .filter(|(_, page)| !enforce_access || page.is_accessible_by(viewer.as_ref()))
             .map(|(plugin, page)| {
                 json!({
                     "plugin": plugin,
@@ -519,6 +640,7 @@ pub fn get_plugin_pages(state: State<'_, OrbisState>) -> Result<Value, String> {
                     "sections": page.sections,
                     "state": page.state,
                     "hooks": page.hooks,
+                    "cache": page.cache,
                 })
             })
             .collect::<Vec<_>>()
@@ -533,7 +655,18 @@ pub fn get_plugin_pages(state: State<'_, OrbisState>) -> Result<Value, String> {
     }))
 }
 
+/// Create a plugin operation emitting its progress to the frontend.
+fn plugin_operation(kind: orbis_plugin::OperationKind, app: &tauri::AppHandle) -> orbis_plugin::PluginOperation {
+    let app = app.clone();
+    orbis_plugin::PluginOperation::new(kind, move |progress| {
+        let _ = app.emit("plugin-operation-progress", progress);
+    })
+}
+
 /// Reload a specific plugin (hot reload).
+///
+/// Returns immediately with an operation ID; progress is emitted as
+/// `plugin-operation-progress` events.
 #[tauri::command]
 pub async fn reload_plugin(
     name: String,
@@ -541,24 +674,27 @@ pub async fn reload_plugin(
     app: tauri::AppHandle,
 ) -> Result<Value, String> {
     let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+    if pm.registry().get(&name).is_none() {
+        return Err(format!("Plugin '{}' not found", name));
+    }
 
-    let info = pm.reload_plugin(&name).await.map_err(|e| e.to_string())?;
+    let operation = plugin_operation(orbis_plugin::OperationKind::Reload, &app);
+    let operation_id = operation.id();
 
-    // Emit event to notify frontend of reload
-    let _ = app.emit("plugin-state-changed", json!({
-        "plugin": name,
-        "state": format!("{:?}", info.state)
-    }));
+    tauri::async_runtime::spawn(async move {
+        if let Ok(info) = pm.reload_plugin_tracked(&name, &operation).await {
+            // Emit event to notify frontend of reload
+            let _ = app.emit("plugin-state-changed", json!({
+                "plugin": name,
+                "state": format!("{:?}", info.state)
+            }));
+        }
+    });
 
     Ok(json!({
         "success": true,
-        "message": format!("Plugin '{}' reloaded successfully", name),
-        "plugin": {
-            "id": info.id.to_string(),
-            "name": info.manifest.name,
-            "version": info.manifest.version,
-            "state": format!("{:?}", info.state),
-        }
+        "message": format!("Reloading plugin '{}'", name),
+        "operation_id": operation_id.to_string()
     }))
 }
 
@@ -608,6 +744,70 @@ pub async fn disable_plugin(
     }))
 }
 
+/// Apply a bulk action to the given plugins, or to all plugins, and notify
+/// the frontend of every plugin it applied to.
+async fn run_bulk_action(
+    action: orbis_plugin::BulkAction,
+    plugins: Option<Vec<String>>,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+
+    let cause = orbis_plugin::StateCause::new(match action {
+        orbis_plugin::BulkAction::Enable | orbis_plugin::BulkAction::Disable => orbis_plugin::StateTrigger::Manual,
+        orbis_plugin::BulkAction::Reload => orbis_plugin::StateTrigger::Reload,
+    });
+    let report = pm.run_bulk(action, plugins.as_deref(), cause).await;
+
+    // Emit events to notify frontend of state changes
+    for name in &report.succeeded {
+        let state = pm
+            .registry()
+            .get(name)
+            .map_or_else(|| "Unloaded".to_string(), |info| format!("{:?}", info.state));
+        let _ = app.emit("plugin-state-changed", json!({
+            "plugin": name,
+            "state": state
+        }));
+    }
+
+    Ok(json!({
+        "success": true,
+        "data": report
+    }))
+}
+
+/// Enable the given plugins, or all plugins, dependencies first.
+#[tauri::command]
+pub async fn enable_all_plugins(
+    plugins: Option<Vec<String>>,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    run_bulk_action(orbis_plugin::BulkAction::Enable, plugins, state, app).await
+}
+
+/// Disable the given plugins, or all plugins, dependencies last.
+#[tauri::command]
+pub async fn disable_all_plugins(
+    plugins: Option<Vec<String>>,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    run_bulk_action(orbis_plugin::BulkAction::Disable, plugins, state, app).await
+}
+
+/// Reload the given plugins, or all plugins, dependencies first.
+#[tauri::command]
+pub async fn reload_all_plugins(
+    plugins: Option<Vec<String>>,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    run_bulk_action(orbis_plugin::BulkAction::Reload, plugins, state, app).await
+}
+
 /// Uninstall a plugin.
 #[tauri::command]
 pub async fn uninstall_plugin(
@@ -632,8 +832,15 @@ pub async fn uninstall_plugin(
 }
 
 /// Install a plugin from a local path.
+///
+/// Returns immediately with an operation ID; progress is emitted as
+/// `plugin-operation-progress` events.
 #[tauri::command]
-pub async fn install_plugin(path: String, state: State<'_, OrbisState>) -> Result<Value, String> {
+pub async fn install_plugin(
+    path: String,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
     let pm = state.plugins().ok_or("Plugins not available in client mode")?;
 
     let plugin_path = PathBuf::from(&path);
@@ -641,18 +848,194 @@ pub async fn install_plugin(path: String, state: State<'_, OrbisState>) -> Resul
         return Err(format!("Plugin path does not exist: {}", path));
     }
 
-    let info = pm.load_plugin(&plugin_path).await.map_err(|e| e.to_string())?;
+    let operation = plugin_operation(orbis_plugin::OperationKind::Install, &app);
+    let operation_id = operation.id();
+
+    tauri::async_runtime::spawn(async move {
+        let Ok(info) = pm.install_plugin(&plugin_path, &operation).await else {
+            return;
+        };
+
+        let event = orbis_plugin::HookEvent::PluginInstalled {
+            plugin: info.manifest.name.clone(),
+            version: info.manifest.version.clone(),
+        };
+        pm.run_hooks(&event, None).await;
+
+        let _ = app.emit("plugin-state-changed", json!({
+            "plugin": info.manifest.name,
+            "state": format!("{:?}", info.state)
+        }));
+    });
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Installing plugin from {}", path),
+        "operation_id": operation_id.to_string()
+    }))
+}
+
+/// Cancel a plugin install or reload in progress.
+///
+/// The operation stops before its next stage and reports itself cancelled.
+#[tauri::command]
+pub async fn cancel_plugin_operation(
+    operation_id: String,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+    let id = uuid::Uuid::parse_str(&operation_id).map_err(|e| format!("Invalid operation ID: {}", e))?;
+
+    if !pm.operations().cancel(id) {
+        return Err(format!("No operation {} in progress", operation_id));
+    }
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Cancelling operation {}", operation_id)
+    }))
+}
+
+/// Export all data of a plugin to a ZIP archive at a local path.
+#[tauri::command]
+pub async fn export_plugin_data(
+    name: String,
+    path: String,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+
+    let archive = pm.export_plugin_data(&name).await.map_err(|e| e.to_string())?;
+    std::fs::write(&path, &archive).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Data of plugin '{}' exported to {}", name, path),
+        "size": archive.len()
+    }))
+}
+
+/// Replace all data of a plugin with an exported ZIP archive at a local path.
+#[tauri::command]
+pub async fn import_plugin_data(
+    name: String,
+    path: String,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+
+    let archive = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    pm.import_plugin_data(&name, &archive).await.map_err(|e| e.to_string())?;
 
     Ok(json!({
         "success": true,
-        "message": format!("Plugin '{}' installed successfully", info.manifest.name),
-        "plugin": {
-            "id": info.id.to_string(),
-            "name": info.manifest.name,
-            "version": info.manifest.version,
-            "description": info.manifest.description,
-            "state": format!("{:?}", info.state),
+        "message": format!("Data of plugin '{}' imported", name)
+    }))
+}
+
+/// Open a plugin page in its own window.
+///
+/// The window shares the session of the main window and remembers its
+/// position and size per page. Opening a page that already has a window
+/// focuses that window.
+#[tauri::command]
+pub async fn open_plugin_window(
+    plugin: String,
+    page: String,
+    params: Option<std::collections::HashMap<String, String>>,
+    state: State<'_, OrbisState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    // Plugins are served by the remote server in client mode
+    if let Some(pm) = state.plugins() {
+        let viewer = state.get_session().map(|session| orbis_plugin::ViewerAccess {
+            roles: session.roles,
+            permissions: session.permissions,
+        });
+        let enforce_access = state.auth().is_some();
+
+        let definition = pm
+            .get_all_pages()
+            .into_iter()
+            .find(|(name, definition)| {
+                *name == plugin && definition.route.trim_start_matches('/') == page.trim_start_matches('/')
+            })
+            .map(|(_, definition)| definition)
+            .ok_or_else(|| format!("Page '{}' of plugin '{}' not found", page, plugin))?;
+
+        if enforce_access && !definition.is_accessible_by(viewer.as_ref()) {
+            return Err(format!("Access to page '{}' of plugin '{}' denied", page, plugin));
         }
+    }
+
+    let label = crate::windows::open_plugin_window(&app, &state, &plugin, &page, params.unwrap_or_default())?;
+
+    Ok(json!({
+        "success": true,
+        "label": label
+    }))
+}
+
+/// Close the window of a plugin page.
+#[tauri::command]
+pub async fn close_plugin_window(plugin: String, page: String, app: tauri::AppHandle) -> Result<Value, String> {
+    let label = crate::windows::window_label(&plugin, &page);
+    if !crate::windows::close_plugin_window(&app, &label)? {
+        return Err(format!("Page '{}' of plugin '{}' has no window", page, plugin));
+    }
+
+    Ok(json!({
+        "success": true,
+        "message": format!("Closed window of page '{}' of plugin '{}'", page, plugin)
+    }))
+}
+
+/// List the plugin pages open in their own windows.
+#[tauri::command]
+pub async fn list_plugin_windows(state: State<'_, OrbisState>) -> Result<Value, String> {
+    let windows = state.plugin_windows();
+
+    Ok(json!({
+        "windows": windows,
+        "count": windows.len()
+    }))
+}
+
+/// Search all running plugins with a search provider.
+#[tauri::command]
+pub async fn search(
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = state.plugins().ok_or("Plugins not available in client mode")?;
+
+    // Without auth the local user may search every plugin
+    let session = state.get_session();
+    let viewer = if state.auth().is_some() {
+        session.as_ref().map(|session| orbis_plugin::ViewerAccess {
+            roles: session.roles.clone(),
+            permissions: session.permissions.clone(),
+        })
+    } else {
+        Some(orbis_plugin::ViewerAccess::for_user(true))
+    };
+
+    let response = pm
+        .search(orbis_plugin::SearchRequest {
+            query,
+            limit: limit.unwrap_or_default(),
+            viewer,
+            user_id: session.as_ref().map(|session| session.user_id.clone()),
+            is_admin: session.as_ref().is_some_and(|session| session.is_admin),
+            tenant_id: None,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "success": true,
+        "data": response
     }))
 }
 
@@ -679,11 +1062,14 @@ pub fn get_plugin_info(name: String, state: State<'_, OrbisState>) -> Result<Val
 }
 
 /// Call a plugin API endpoint.
+///
+/// With `revalidate`, cached page data is bypassed and refreshed.
 #[tauri::command]
 pub async fn call_plugin_api(
     command: String,
     method: Option<String>,
     args: Option<Value>,
+    revalidate: Option<bool>,
     state: State<'_, OrbisState>,
 ) -> Result<Value, String> {
     let pm = state.plugins().ok_or("Plugins not available in client mode")?;
@@ -715,6 +1101,9 @@ pub async fn call_plugin_api(
     // Build plugin context
     let mut headers = std::collections::HashMap::new();
     headers.insert("content-type".to_string(), "application/json".to_string());
+    if revalidate.unwrap_or(false) {
+        headers.insert("cache-control".to_string(), "no-cache".to_string());
+    }
 
     let query = std::collections::HashMap::new();
     
@@ -734,6 +1123,10 @@ pub async fn call_plugin_api(
         body: args.unwrap_or(serde_json::json!({})),
         user_id,
         is_admin,
+        tenant_id: None,
+        deadline: None,
+        features: std::collections::BTreeMap::new(),
+        cancellation: orbis_plugin::CancellationFlag::new(),
     };
 
     // Execute the plugin route
@@ -742,6 +1135,126 @@ pub async fn call_plugin_api(
         .map_err(|e| format!("Plugin execution failed: {}", e))
 }
 
+/// Get the plugin manager for the plugin development commands.
+fn dev_plugins(state: &OrbisState) -> Result<std::sync::Arc<orbis_plugin::PluginManager>, String> {
+    if !state.config().plugin_dev_mode {
+        return Err("Plugin development mode is not enabled".to_string());
+    }
+    state.plugins().ok_or_else(|| "Plugins not available in client mode".to_string())
+}
+
+/// Plugin handler invocation (development mode only).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HandlerInvocation {
+    /// Request method (defaults to `POST`).
+    pub method: Option<String>,
+
+    /// Request path (defaults to the path of the route using the handler).
+    pub path: Option<String>,
+
+    /// Query parameters.
+    pub query: std::collections::HashMap<String, String>,
+
+    /// Request headers.
+    pub headers: std::collections::HashMap<String, String>,
+
+    /// Request body.
+    pub body: Value,
+
+    /// User to invoke the handler as (anonymous if unset).
+    pub user_id: Option<String>,
+
+    /// User is admin.
+    pub is_admin: bool,
+
+    /// Tenant of the request.
+    pub tenant_id: Option<String>,
+}
+
+/// Invoke a plugin handler directly with a crafted context (development mode only).
+#[tauri::command]
+pub async fn invoke_plugin_handler(
+    plugin: String,
+    handler: String,
+    invocation: Option<HandlerInvocation>,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = dev_plugins(&state)?;
+    let info = pm.registry().get(&plugin).ok_or_else(|| format!("Plugin '{}' not found", plugin))?;
+    if info.state != orbis_plugin::PluginState::Running {
+        return Err(format!("Plugin '{}' is not running (state: {:?})", plugin, info.state));
+    }
+
+    let invocation = invocation.unwrap_or_default();
+    let path = invocation.path.unwrap_or_else(|| {
+        info.manifest
+            .routes
+            .iter()
+            .find(|route| route.handler == handler)
+            .map_or_else(|| format!("/{}", handler), |route| route.path.clone())
+    });
+    let context = orbis_plugin::PluginContext {
+        method: invocation.method.unwrap_or_else(|| "POST".to_string()).to_uppercase(),
+        path,
+        headers: invocation.headers,
+        query: invocation.query,
+        body: invocation.body,
+        user_id: invocation.user_id,
+        is_admin: invocation.is_admin,
+        tenant_id: invocation.tenant_id,
+        deadline: None,
+        features: std::collections::BTreeMap::new(),
+        cancellation: orbis_plugin::CancellationFlag::new(),
+    };
+
+    let started = std::time::Instant::now();
+    let result = pm
+        .execute_route(&plugin, &handler, context)
+        .await
+        .map_err(|e| format!("Plugin execution failed: {}", e))?;
+
+    Ok(json!({
+        "result": result,
+        "duration_ms": started.elapsed().as_millis()
+    }))
+}
+
+/// List the requests captured for a plugin, most recent first (development mode only).
+#[tauri::command]
+pub fn list_captured_plugin_requests(
+    plugin: String,
+    handler: Option<String>,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = dev_plugins(&state)?;
+    let requests = pm.capture().list(&plugin, handler.as_deref());
+    serde_json::to_value(requests).map_err(|e| e.to_string())
+}
+
+/// Replay a captured plugin request, optionally with another body (development mode only).
+#[tauri::command]
+pub async fn replay_plugin_request(
+    plugin: String,
+    id: String,
+    body: Option<Value>,
+    state: State<'_, OrbisState>,
+) -> Result<Value, String> {
+    let pm = dev_plugins(&state)?;
+    let id = uuid::Uuid::parse_str(&id).map_err(|e| format!("Invalid request ID: {}", e))?;
+
+    let started = std::time::Instant::now();
+    let result = pm
+        .replay(&plugin, id, body)
+        .await
+        .map_err(|e| format!("Plugin execution failed: {}", e))?;
+
+    Ok(json!({
+        "result": result,
+        "duration_ms": started.elapsed().as_millis()
+    }))
+}
+
 /// Start watching plugins directory for changes.
 #[tauri::command]
 pub async fn start_plugin_watcher(
@@ -765,22 +1278,40 @@ pub async fn start_plugin_watcher(
     let rx = watcher.start()
         .map_err(|e| format!("Failed to start watcher: {}", e))?;
 
-    // Spawn a task to process watcher events and emit to frontend
+    let plugins = state.plugins();
+    let mut reload_rx = plugins.as_ref().map(|pm| pm.reload_events().subscribe());
+
+    // Spawn a task to apply watcher events and emit them and reload progress to frontend
     let app_handle_clone = app_handle.clone();
     tauri::async_runtime::spawn(async move {
         let mut rx = rx;
-        while let Some(event) = rx.recv().await {
-            let kind = match event.kind {
-                PluginChangeKind::Added => "Added",
-                PluginChangeKind::Modified => "Modified",
-                PluginChangeKind::Removed => "Removed",
-            };
-
-            let _ = app_handle_clone.emit("plugin-changed", json!({
-                "kind": kind,
-                "path": event.path.to_string_lossy(),
-                "plugin_id": event.plugin_id,
-            }));
+        loop {
+            tokio::select! {
+                event = rx.recv() => {
+                    // Watcher stopped
+                    let Some(event) = event else { break };
+
+                    let kind = match event.kind {
+                        PluginChangeKind::Added => "Added",
+                        PluginChangeKind::Modified => "Modified",
+                        PluginChangeKind::Removed => "Removed",
+                    };
+
+                    // Failures are reported as reload events
+                    if let Some(pm) = &plugins {
+                        let _ = pm.apply_change(&event).await;
+                    }
+
+                    let _ = app_handle_clone.emit("plugin-changed", json!({
+                        "kind": kind,
+                        "path": event.path.to_string_lossy(),
+                        "plugin_id": event.plugin_id,
+                    }));
+                }
+                Some(reload) = recv_reload_event(&mut reload_rx) => {
+                    let _ = app_handle_clone.emit("plugin-reload", reload);
+                }
+            }
         }
     });
 
@@ -790,6 +1321,23 @@ pub async fn start_plugin_watcher(
     }))
 }
 
+/// Receive the next reload event, waiting forever without a plugin manager.
+async fn recv_reload_event(
+    rx: &mut Option<tokio::sync::broadcast::Receiver<orbis_plugin::ReloadEvent>>,
+) -> Option<orbis_plugin::ReloadEvent> {
+    let Some(rx) = rx else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        match rx.recv().await {
+            Ok(event) => return Some(event),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
 /// Stop watching plugins directory.
 #[tauri::command]
 pub async fn stop_plugin_watcher(state: State<'_, OrbisState>) -> Result<Value, String> {
@@ -6,14 +6,15 @@
 
 mod commands;
 mod state;
+mod windows;
 
 use orbis_config::{init_config, Config};
 use orbis_core::AppMode;
 use orbis_server::Server;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 /// Application state shared across Tauri commands.
-pub use state::{OrbisState, AuthSession};
+pub use state::{OrbisState, AuthSession, PluginWindow};
 
 /// Initialize logging.
 fn init_logging(config: &Config) {
@@ -72,7 +73,7 @@ pub fn run() {
                 let state = match config.mode {
                     AppMode::Standalone => {
                         tracing::info!("Running in standalone mode");
-                        init_standalone(&config).await
+                        init_standalone(&config, &app_handle).await
                     }
                     AppMode::ClientServer => {
                         if config.run_mode.is_server() {
@@ -90,8 +91,9 @@ pub fn run() {
                         app_handle.manage(state);
                     }
                     Err(e) => {
+                        // Exit through the event loop so whatever started is shut down
                         tracing::error!("Failed to initialize: {}", e);
-                        std::process::exit(1);
+                        app_handle.exit(1);
                     }
                 }
             });
@@ -106,15 +108,30 @@ pub fn run() {
             commands::create_profile,
             commands::delete_profile,
             commands::switch_profile,
+            commands::unlock_profile,
+            commands::lock_profile,
             commands::get_plugins,
             commands::get_plugin_pages,
             commands::get_plugin_info,
             commands::call_plugin_api,
+            commands::invoke_plugin_handler,
+            commands::list_captured_plugin_requests,
+            commands::replay_plugin_request,
             commands::reload_plugin,
             commands::enable_plugin,
             commands::disable_plugin,
+            commands::enable_all_plugins,
+            commands::disable_all_plugins,
+            commands::reload_all_plugins,
             commands::install_plugin,
+            commands::cancel_plugin_operation,
             commands::uninstall_plugin,
+            commands::export_plugin_data,
+            commands::search,
+            commands::open_plugin_window,
+            commands::close_plugin_window,
+            commands::list_plugin_windows,
+            commands::import_plugin_data,
             commands::start_plugin_watcher,
             commands::stop_plugin_watcher,
             commands::login,
@@ -122,42 +139,96 @@ pub fn run() {
             commands::get_session,
             commands::verify_session,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Stop the server, jobs, plugins and database in order before exiting
+            if let tauri::RunEvent::Exit = event
+                && let Some(state) = app.try_state::<OrbisState>()
+            {
+                tauri::async_runtime::block_on(state.shutdown());
+            }
+        });
 }
 
 /// Initialize standalone mode (local database + embedded server).
-async fn init_standalone(config: &Config) -> orbis_core::Result<OrbisState> {
-    // Create the server (handles database, auth, plugins)
-    let server = Server::new(config.clone()).await?;
+async fn init_standalone(config: &Config, app: &tauri::AppHandle) -> orbis_core::Result<OrbisState> {
+    let state = OrbisState::new_locked(config.clone());
 
-    // In standalone mode, run the HTTP server in background for API access
+    // Encrypted profiles stay locked until `unlock_profile` gets their passphrase
+    if let Some(profile) = commands::active_profile(config).filter(|profile| profile.encryption.is_some()) {
+        tracing::info!("Profile '{}' is encrypted, waiting for it to be unlocked", profile.name);
+        return Ok(state);
+    }
+
+    open_profile(&state, config.clone(), app).await?;
+    Ok(state)
+}
+
+/// Open the database, plugins and embedded server of a profile, and hand
+/// them to the application state.
+async fn open_profile(state: &OrbisState, config: Config, app: &tauri::AppHandle) -> orbis_core::Result<()> {
+    // Create the server (handles database, auth, plugins and background jobs)
+    let server = Server::new(config).await?;
+
+    // The commands and the embedded HTTP API share the server's state, so
+    // they see the same database pool and plugin instances
     let server_state = server.state().clone();
+    relay_plugin_events(&server_state, app);
+
+    // In standalone mode, run the HTTP server in background for API access;
+    // the app keeps working through its commands, and its background jobs keep
+    // running, if the address is taken
+    let task = match server.bind().await {
+        Ok(listener) => Some(tauri::async_runtime::spawn(async move {
+            if let Err(e) = server.serve(listener).await {
+                tracing::error!("Server error: {}", e);
+            }
+        })),
+        Err(e) => {
+            tracing::error!("Server error: {}", e);
+            None
+        }
+    };
+
+    state
+        .unlock(
+            server_state.db().clone(),
+            server_state.auth().cloned(),
+            server_state.plugins_arc(),
+            Some(std::sync::Arc::clone(server_state.shutdown())),
+            task,
+        )
+        .map_err(orbis_core::Error::internal)
+}
+
+/// Relay the events plugins emit to the frontend, so pages can revalidate
+/// their data on them.
+fn relay_plugin_events(server_state: &orbis_server::AppState, app: &tauri::AppHandle) {
+    use tokio::sync::broadcast::error::RecvError;
 
-    // Start server in background
-    let server_config = config.clone();
+    let mut events = server_state.events().subscribe();
+    let app = app.clone();
     tauri::async_runtime::spawn(async move {
-        let server = Server::new(server_config).await.expect("Failed to create server");
-        if let Err(e) = server.run().await {
-            tracing::error!("Server error: {}", e);
+        loop {
+            match events.recv().await {
+                Ok(event) if event.event == orbis_server::PLUGIN_EVENT => {
+                    let _ = app.emit(orbis_server::PLUGIN_EVENT, &event.data);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
         }
     });
-
-    Ok(OrbisState::new_standalone(
-        server_state.db().clone(),
-        server_state.auth().cloned(),
-        server_state.plugins_arc(),
-        config.clone(),
-    ))
 }
 
 /// Initialize server mode (full server with UI).
 async fn init_server(
     config: &Config,
-    _app_handle: &tauri::AppHandle,
+    app_handle: &tauri::AppHandle,
 ) -> orbis_core::Result<OrbisState> {
     // Same as standalone but emphasizes server role
-    init_standalone(config).await
+    init_standalone(config, app_handle).await
 }
 
 /// Initialize client mode (connect to remote server).
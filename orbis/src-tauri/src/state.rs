@@ -2,9 +2,10 @@
 
 use orbis_auth::AuthService;
 use orbis_config::Config;
-use orbis_core::AppMode;
+use orbis_core::{shutdown, AppMode};
 use orbis_db::Database;
 use orbis_plugin::{PluginManager, PluginWatcher, WatcherConfig};
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
@@ -24,19 +25,51 @@ pub struct AuthSession {
     pub expires_at: Option<String>,
 }
 
+/// A plugin page open in its own window.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginWindow {
+    /// Window label.
+    pub label: String,
+
+    /// Plugin name.
+    pub plugin: String,
+
+    /// Page route, relative to the plugin.
+    pub page: String,
+
+    /// Query parameters passed to the page.
+    pub params: HashMap<String, String>,
+
+    /// When the window was opened.
+    pub opened_at: String,
+}
+
+/// Services of an open profile (standalone/server only).
+struct ProfileServices {
+    /// Database connection.
+    db: Database,
+
+    /// Auth service.
+    auth: Option<AuthService>,
+
+    /// Plugin manager.
+    plugins: Arc<PluginManager>,
+
+    /// Shutdown coordinator of the embedded server's subsystems.
+    shutdown: Option<Arc<shutdown::Coordinator>>,
+
+    /// Embedded HTTP server, stopped when the profile is locked.
+    server: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
 /// Orbis application state.
 pub struct OrbisState {
     /// Application mode.
     mode: AppMode,
 
-    /// Database connection (standalone/server only).
-    db: Option<Database>,
-
-    /// Auth service (standalone/server only).
-    auth: Option<AuthService>,
-
-    /// Plugin manager (standalone/server only).
-    plugins: Option<Arc<PluginManager>>,
+    /// Services of the open profile; `None` in client mode and while an
+    /// encrypted profile is locked.
+    services: Arc<RwLock<Option<ProfileServices>>>,
 
     /// Plugin watcher for hot reload (standalone/server only).
     plugin_watcher: Arc<RwLock<Option<PluginWatcher>>>,
@@ -50,9 +83,12 @@ pub struct OrbisState {
     /// Application configuration.
     config: Config,
 
-    /// Current authentication session.
+    /// Current authentication session, shared by all windows.
     session: Arc<RwLock<Option<AuthSession>>>,
 
+    /// Plugin pages open in their own windows, by window label.
+    plugin_windows: Arc<RwLock<HashMap<String, PluginWindow>>>,
+
     /// HTTP client for client mode.
     http_client: reqwest::Client,
 }
@@ -68,14 +104,19 @@ impl OrbisState {
         let plugins_dir = config.plugins_dir.clone();
         Self {
             mode: AppMode::Standalone,
-            db: Some(db),
-            auth,
-            plugins: Some(plugins),
+            services: Arc::new(RwLock::new(Some(ProfileServices {
+                db,
+                auth,
+                plugins,
+                shutdown: None,
+                server: None,
+            }))),
             plugin_watcher: Arc::new(RwLock::new(None)),
             plugins_dir,
             server_url: None,
             config,
             session: Arc::new(RwLock::new(None)),
+            plugin_windows: Arc::new(RwLock::new(HashMap::new())),
             http_client: reqwest::Client::new(),
         }
     }
@@ -90,14 +131,38 @@ impl OrbisState {
     ) -> Self {
         Self {
             mode: AppMode::Standalone,
-            db: Some(db),
-            auth,
-            plugins: Some(plugins),
+            services: Arc::new(RwLock::new(Some(ProfileServices {
+                db,
+                auth,
+                plugins,
+                shutdown: None,
+                server: None,
+            }))),
             plugin_watcher: Arc::new(RwLock::new(None)),
             plugins_dir: Some(plugins_dir),
             server_url: None,
             config,
             session: Arc::new(RwLock::new(None)),
+            plugin_windows: Arc::new(RwLock::new(HashMap::new())),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create state for standalone mode with an encrypted profile that is
+    /// not unlocked yet.
+    ///
+    /// Services are available once [`OrbisState::unlock`] is called.
+    pub fn new_locked(config: Config) -> Self {
+        let plugins_dir = config.plugins_dir.clone();
+        Self {
+            mode: AppMode::Standalone,
+            services: Arc::new(RwLock::new(None)),
+            plugin_watcher: Arc::new(RwLock::new(None)),
+            plugins_dir,
+            server_url: None,
+            config,
+            session: Arc::new(RwLock::new(None)),
+            plugin_windows: Arc::new(RwLock::new(HashMap::new())),
             http_client: reqwest::Client::new(),
         }
     }
@@ -106,14 +171,13 @@ impl OrbisState {
     pub fn new_client(server_url: String, config: Config) -> Self {
         Self {
             mode: AppMode::ClientServer,
-            db: None,
-            auth: None,
-            plugins: None,
+            services: Arc::new(RwLock::new(None)),
             plugin_watcher: Arc::new(RwLock::new(None)),
             plugins_dir: None,
             server_url: Some(server_url),
             config,
             session: Arc::new(RwLock::new(None)),
+            plugin_windows: Arc::new(RwLock::new(HashMap::new())),
             http_client: reqwest::Client::builder()
                 .timeout(std::time::Duration::from_secs(30))
                 .build()
@@ -129,20 +193,107 @@ impl OrbisState {
 
     /// Get the database (if available).
     #[must_use]
-    pub fn db(&self) -> Option<&Database> {
-        self.db.as_ref()
+    pub fn db(&self) -> Option<Database> {
+        self.services.read().ok()?.as_ref().map(|services| services.db.clone())
     }
 
     /// Get the auth service (if available).
     #[must_use]
-    pub fn auth(&self) -> Option<&AuthService> {
-        self.auth.as_ref()
+    pub fn auth(&self) -> Option<AuthService> {
+        self.services.read().ok()?.as_ref()?.auth.clone()
     }
 
     /// Get the plugin manager (if available).
     #[must_use]
-    pub fn plugins(&self) -> Option<&Arc<PluginManager>> {
-        self.plugins.as_ref()
+    pub fn plugins(&self) -> Option<Arc<PluginManager>> {
+        self.services.read().ok()?.as_ref().map(|services| Arc::clone(&services.plugins))
+    }
+
+    /// Check if the profile is encrypted and waiting to be unlocked.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.is_standalone() && self.services.read().map(|services| services.is_none()).unwrap_or(true)
+    }
+
+    /// Unlock the profile with the services opened with its key.
+    pub fn unlock(
+        &self,
+        db: Database,
+        auth: Option<AuthService>,
+        plugins: Arc<PluginManager>,
+        shutdown: Option<Arc<shutdown::Coordinator>>,
+        server: Option<tauri::async_runtime::JoinHandle<()>>,
+    ) -> Result<(), String> {
+        let mut services = self.services.write()
+            .map_err(|_| "Failed to acquire services lock")?;
+
+        if services.is_some() {
+            return Err("Profile is already unlocked".to_string());
+        }
+
+        *services = Some(ProfileServices { db, auth, plugins, shutdown, server });
+        Ok(())
+    }
+
+    /// Lock the profile: stop its services, close its database and end the
+    /// session, so nothing decrypted stays available.
+    pub async fn lock(&self) -> Result<(), String> {
+        self.stop_plugin_watcher();
+
+        let services = self.services.write()
+            .map_err(|_| "Failed to acquire services lock")?
+            .take()
+            .ok_or("Profile is already locked")?;
+
+        // Stop the embedded server's subsystems in order before dropping them
+        if let Some(shutdown) = &services.shutdown {
+            shutdown.shutdown().await;
+        }
+        if let Some(server) = &services.server {
+            server.abort();
+        }
+        services.db.close().await;
+        self.set_session(None);
+        Ok(())
+    }
+
+    /// Shut down the profile's services when the app exits: stop the
+    /// watcher, then the embedded server's subsystems in order.
+    pub async fn shutdown(&self) {
+        self.stop_plugin_watcher();
+
+        let shutdown = self.services.read().ok().and_then(|services| {
+            services.as_ref().and_then(|services| services.shutdown.clone())
+        });
+        if let Some(shutdown) = shutdown {
+            let report = shutdown.shutdown().await;
+            if !report.timed_out.is_empty() {
+                tracing::warn!("Subsystems did not shut down in time: {}", report.timed_out.join(", "));
+            }
+        }
+    }
+
+    /// Track a plugin page window.
+    pub fn add_plugin_window(&self, window: PluginWindow) {
+        if let Ok(mut windows) = self.plugin_windows.write() {
+            windows.insert(window.label.clone(), window);
+        }
+    }
+
+    /// Stop tracking a closed plugin page window.
+    pub fn remove_plugin_window(&self, label: &str) {
+        if let Ok(mut windows) = self.plugin_windows.write() {
+            windows.remove(label);
+        }
+    }
+
+    /// List the plugin page windows.
+    #[must_use]
+    pub fn plugin_windows(&self) -> Vec<PluginWindow> {
+        self.plugin_windows
+            .read()
+            .map(|windows| windows.values().cloned().collect())
+            .unwrap_or_default()
     }
 
     /// Get the plugins directory path.
@@ -173,11 +324,14 @@ impl OrbisState {
 
         let config = WatcherConfig {
             watch_dir: plugins_dir.clone(),
-            debounce_duration: std::time::Duration::from_millis(500),
-            recursive: true,
+            ..WatcherConfig::default()
         };
 
-        let watcher = PluginWatcher::new(config);
+        // Report reload progress on the plugin manager's event channel
+        let mut watcher = PluginWatcher::new(config);
+        if let Some(plugins) = self.plugins() {
+            watcher = watcher.with_events(plugins.reload_events().clone());
+        }
         *watcher_guard = Some(watcher);
 
         Ok(())
@@ -225,7 +379,7 @@ impl OrbisState {
     /// Check if running in server mode.
     #[must_use]
     pub fn is_server(&self) -> bool {
-        matches!(self.mode, AppMode::ClientServer) && self.db.is_some()
+        matches!(self.mode, AppMode::ClientServer) && self.db().is_some()
     }
 
     /// Get current session (read-only).
@@ -0,0 +1,184 @@
+//! Plugin pages in their own windows.
+//!
+//! Each plugin page opens in at most one window, labelled after the plugin
+//! and page. Windows share the session of [`OrbisState`], and remember their
+//! geometry per page in `window_geometry.json` in the data directory.
+
+use crate::state::{OrbisState, PluginWindow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::{Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+/// Prefix of plugin window labels.
+pub const PLUGIN_WINDOW_PREFIX: &str = "plugin-";
+
+/// Size of windows opened for the first time.
+const DEFAULT_SIZE: (f64, f64) = (1024.0, 768.0);
+
+/// Position and size of a window, in logical pixels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct WindowGeometry {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    #[serde(default)]
+    maximized: bool,
+}
+
+/// Get the geometry file path.
+fn geometry_path() -> PathBuf {
+    crate::commands::data_dir().join("window_geometry.json")
+}
+
+/// Load the remembered geometry of all pages.
+fn load_geometries() -> BTreeMap<String, WindowGeometry> {
+    std::fs::read_to_string(geometry_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Read the current geometry of a window.
+fn read_geometry(window: &WebviewWindow) -> tauri::Result<WindowGeometry> {
+    let scale = window.scale_factor()?;
+    let position = window.outer_position()?.to_logical::<f64>(scale);
+    let size = window.inner_size()?.to_logical::<f64>(scale);
+    Ok(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized()?,
+    })
+}
+
+/// Remember the geometry of a window.
+fn save_geometry(key: &str, window: &WebviewWindow) {
+    let geometry = match read_geometry(window) {
+        Ok(geometry) => geometry,
+        Err(e) => {
+            tracing::warn!("Failed to read geometry of window '{}': {}", window.label(), e);
+            return;
+        }
+    };
+
+    let mut geometries = load_geometries();
+    geometries.insert(key.to_string(), geometry);
+    let result = serde_json::to_string_pretty(&geometries)
+        .map_err(|e| e.to_string())
+        .and_then(|content| std::fs::write(geometry_path(), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to save window geometry: {}", e);
+    }
+}
+
+/// Get the label of the window of a plugin page.
+///
+/// Labels may only hold alphanumerics, `-` and `_`; other characters of the
+/// plugin name and page route become `_`.
+pub fn window_label(plugin: &str, page: &str) -> String {
+    let sanitize = |value: &str| -> String {
+        value
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect()
+    };
+    format!("{}{}--{}", PLUGIN_WINDOW_PREFIX, sanitize(plugin), sanitize(page))
+}
+
+/// Build the app URL of a plugin page with its query parameters.
+fn page_url(route: &str, params: &HashMap<String, String>) -> Result<String, String> {
+    let mut url = tauri::Url::parse("orbis://localhost").map_err(|e| e.to_string())?;
+    url.set_path(route);
+    if !params.is_empty() {
+        let mut params: Vec<_> = params.iter().collect();
+        params.sort_unstable();
+        url.query_pairs_mut().extend_pairs(params);
+    }
+
+    Ok(match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    })
+}
+
+/// Open a plugin page in its own window, or focus its window if it is
+/// already open.
+///
+/// Returns the window label.
+pub fn open_plugin_window(
+    app: &tauri::AppHandle,
+    state: &OrbisState,
+    plugin: &str,
+    page: &str,
+    params: HashMap<String, String>,
+) -> Result<String, String> {
+    let label = window_label(plugin, page);
+    if let Some(window) = app.get_webview_window(&label) {
+        focus(&window)?;
+        return Ok(label);
+    }
+
+    let route = format!("/plugins/{}/{}", plugin, page.trim_start_matches('/'));
+    let key = format!("{}/{}", plugin, page.trim_start_matches('/'));
+    let url = page_url(&route, &params)?;
+
+    let mut builder = WebviewWindowBuilder::new(app, &label, WebviewUrl::App(url.into()))
+        .title(format!("{} - {}", plugin, page.trim_start_matches('/')));
+    builder = match load_geometries().get(&key) {
+        Some(geometry) => builder
+            .inner_size(geometry.width, geometry.height)
+            .position(geometry.x, geometry.y)
+            .maximized(geometry.maximized),
+        None => builder.inner_size(DEFAULT_SIZE.0, DEFAULT_SIZE.1),
+    };
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+
+    // Each window has its own lifecycle: it remembers its geometry when
+    // closed and leaves the main window untouched
+    let handle = app.clone();
+    let window_label = label.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::CloseRequested { .. } => {
+            if let Some(window) = handle.get_webview_window(&window_label) {
+                save_geometry(&key, &window);
+            }
+        }
+        WindowEvent::Destroyed => {
+            handle.state::<OrbisState>().remove_plugin_window(&window_label);
+        }
+        _ => {}
+    });
+
+    state.add_plugin_window(PluginWindow {
+        label: label.clone(),
+        plugin: plugin.to_string(),
+        page: page.to_string(),
+        params,
+        opened_at: chrono::Utc::now().to_rfc3339(),
+    });
+    Ok(label)
+}
+
+/// Bring a window to the front.
+fn focus(window: &WebviewWindow) -> Result<(), String> {
+    window.unminimize().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+/// Close the window of a plugin page, if open.
+///
+/// Returns `false` if the page has no window.
+pub fn close_plugin_window(app: &tauri::AppHandle, label: &str) -> Result<bool, String> {
+    let Some(window) = app.get_webview_window(label) else {
+        return Ok(false);
+    };
+
+    window.close().map_err(|e| format!("Failed to close window: {}", e))?;
+    Ok(true)
+}
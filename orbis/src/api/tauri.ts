@@ -283,7 +283,7 @@ export async function getPluginInfo(name: string): Promise<{
 /**
  * Reload a plugin
  */
-export async function reloadPlugin(name: string): Promise<{ success: boolean; message: string }> {
+export async function reloadPlugin(name: string): Promise<{ success: boolean; message: string; operation_id: string }> {
   return invokeWithRetry('reload_plugin', { name });
 }
 
@@ -301,13 +301,85 @@ export async function disablePlugin(name: string): Promise<{ success: boolean; m
   return invokeWithRetry('disable_plugin', { name });
 }
 
+/**
+ * Outcome of enabling, disabling or reloading many plugins
+ */
+export interface BulkPluginReport {
+  action: 'enable' | 'disable' | 'reload';
+  succeeded: string[];
+  failed: { plugin: string; error: string }[];
+  skipped: { plugin: string; error: string }[];
+}
+
+/**
+ * Enable the given plugins, or all plugins, dependencies first
+ */
+export async function enableAllPlugins(plugins?: string[]): Promise<{ success: boolean; data: BulkPluginReport }> {
+  return invokeWithRetry('enable_all_plugins', { plugins });
+}
+
+/**
+ * Disable the given plugins, or all plugins, dependencies last
+ */
+export async function disableAllPlugins(plugins?: string[]): Promise<{ success: boolean; data: BulkPluginReport }> {
+  return invokeWithRetry('disable_all_plugins', { plugins });
+}
+
+/**
+ * Reload the given plugins, or all plugins, dependencies first
+ */
+export async function reloadAllPlugins(plugins?: string[]): Promise<{ success: boolean; data: BulkPluginReport }> {
+  return invokeWithRetry('reload_all_plugins', { plugins });
+}
+
 /**
  * Install a plugin from path
  */
-export async function installPlugin(path: string): Promise<{ success: boolean; message: string }> {
+export async function installPlugin(path: string): Promise<{ success: boolean; message: string; operation_id: string }> {
   return invokeWithRetry('install_plugin', { path });
 }
 
+/**
+ * Open a plugin page in its own window, or focus its window
+ */
+export async function openPluginWindow(
+  plugin: string,
+  page: string,
+  params?: Record<string, string>
+): Promise<{ success: boolean; label: string }> {
+  return invokeWithRetry('open_plugin_window', { plugin, page, params });
+}
+
+/**
+ * Close the window of a plugin page
+ */
+export async function closePluginWindow(plugin: string, page: string): Promise<{ success: boolean; message: string }> {
+  return invokeWithRetry('close_plugin_window', { plugin, page });
+}
+
+/**
+ * List the plugin pages open in their own windows
+ */
+export async function listPluginWindows(): Promise<{
+  windows: Array<{
+    label: string;
+    plugin: string;
+    page: string;
+    params: Record<string, string>;
+    opened_at: string;
+  }>;
+  count: number;
+}> {
+  return invokeWithRetry('list_plugin_windows');
+}
+
+/**
+ * Cancel a plugin install or reload in progress
+ */
+export async function cancelPluginOperation(operationId: string): Promise<{ success: boolean; message: string }> {
+  return invokeWithRetry('cancel_plugin_operation', { operationId });
+}
+
 /**
  * Uninstall a plugin
  */
@@ -315,6 +387,74 @@ export async function uninstallPlugin(name: string): Promise<{ success: boolean;
   return invokeWithRetry('uninstall_plugin', { name });
 }
 
+// ============================================================================
+// Plugin Development
+// ============================================================================
+
+/** Context a plugin handler is invoked with in development mode */
+export interface HandlerInvocation {
+  method?: string;
+  path?: string;
+  query?: Record<string, string>;
+  headers?: Record<string, string>;
+  body?: unknown;
+  user_id?: string;
+  is_admin?: boolean;
+  tenant_id?: string;
+}
+
+/** Outcome of invoking or replaying a plugin handler */
+export interface HandlerInvocationResult {
+  result: unknown;
+  duration_ms: number;
+}
+
+/** Request handled by a plugin handler, captured in development mode */
+export interface CapturedPluginRequest {
+  id: string;
+  plugin: string;
+  handler: string;
+  context: {
+    method: string;
+    path: string;
+    headers: Record<string, string>;
+    query: Record<string, string>;
+    body: unknown;
+    user_id?: string;
+    is_admin: boolean;
+    tenant_id?: string;
+  };
+  result?: unknown;
+  error?: string;
+  duration_ms: number;
+  captured_at: string;
+}
+
+/**
+ * Invoke a plugin handler directly with a crafted context (development mode only)
+ */
+export async function invokePluginHandler(
+  plugin: string,
+  handler: string,
+  invocation?: HandlerInvocation
+): Promise<HandlerInvocationResult> {
+  return invoke('invoke_plugin_handler', { plugin, handler, invocation });
+}
+
+/**
+ * List the requests captured for a plugin, most recent first (development mode only)
+ */
+export async function listCapturedPluginRequests(plugin: string, handler?: string): Promise<CapturedPluginRequest[]> {
+  return invokeWithRetry('list_captured_plugin_requests', { plugin, handler });
+}
+
+/**
+ * Replay a captured plugin request, optionally with another body (development mode only)
+ */
+export async function replayPluginRequest(plugin: string, id: string, body?: unknown): Promise<HandlerInvocationResult> {
+  return invoke('replay_plugin_request', { plugin, id, body });
+}
+
 // ============================================================================
 // Plugin Watcher
 // ============================================================================
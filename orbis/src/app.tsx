@@ -162,8 +162,9 @@ function App(): React.ReactElement {
         ],
     }), []);
 
-    // Create a plugin-aware API client factory
-    const createPluginApiClient = useCallback((pluginName?: string): ApiClient => ({
+    // Create a plugin-aware API client factory; revalidating clients bypass
+    // cached page data
+    const createPluginApiClient = useCallback((pluginName?: string, revalidate?: boolean): ApiClient => ({
         call: async(api: string, method: string, args?: Record<string, unknown>) => {
             // Parse API path: "plugin.handler_name" or "core.command_name" or "plugin.plugin_name.handler_name"
             const parts = api.split(`.`);
@@ -196,6 +197,7 @@ function App(): React.ReactElement {
                     command,
                     method,
                     args,
+                    revalidate,
                 });
             }
 
@@ -276,7 +278,7 @@ function App(): React.ReactElement {
 // Plugin page renderer component
 interface PluginPageRendererProps {
     page:      PluginPage
-    apiClient: (pluginName?: string) => ApiClient
+    apiClient: (pluginName?: string, revalidate?: boolean) => ApiClient
 }
 
 function PluginPageRenderer({
@@ -337,6 +339,55 @@ function PluginPageRenderer({
         }
     }, [ page, stateStore, apiClient, navigate ]);
 
+    // Reload the page data when the window regains focus or a plugin emits
+    // one of the page's revalidation events, bypassing cached data
+    useEffect(() => {
+        const onMount = page.hooks?.onMount;
+        const cache = page.cache;
+        if (!onMount || !cache) {
+            return;
+        }
+
+        const revalidate = (): void => {
+            const actionContext = {
+                state:     stateStore,
+                apiClient: createApiClient(page.plugin, true),
+                navigate,
+            };
+            executeActions(onMount, actionContext).catch((error) => {
+                console.error(`Error revalidating page data:`, error);
+            });
+        };
+
+        if (cache.revalidate_on_focus) {
+            window.addEventListener(`focus`, revalidate);
+        }
+
+        let unlisten: (() => void) | undefined;
+        let cancelled = false;
+        const events = cache.revalidate_on_events ?? [];
+        if (events.length > 0) {
+            void listen<{ event: string }>(`plugin-event`, (event) => {
+                if (events.includes(event.payload.event)) {
+                    revalidate();
+                }
+            }).then((stop) => {
+                if (cancelled) {
+                    stop();
+                }
+                else {
+                    unlisten = stop;
+                }
+            });
+        }
+
+        return () => {
+            cancelled = true;
+            window.removeEventListener(`focus`, revalidate);
+            unlisten?.();
+        };
+    }, [ page, stateStore, createApiClient, navigate ]);
+
     // Execute onUnmount hook when page unmounts
     useEffect(() => () => {
         if (page.hooks?.onUnmount) {
@@ -46,11 +46,36 @@ export interface PluginsResponse {
  * Plugin operation result
  */
 export interface PluginOperationResult {
-    success: boolean
-    message: string
-    plugin?: PluginInfo
+    success:       boolean
+    message:       string
+    plugin?:       PluginInfo
+    operation_id?: string
 }
 
+/**
+ * Stage of a plugin install or reload, in order
+ */
+export type PluginOperationStage = `downloading` | `verifying_signature` | `extracting` | `compiling_wasm` | `migrating` | `starting`;
+
+/**
+ * Plugin install or reload progress event
+ */
+export type PluginOperationProgress = {
+    operation_id: string
+    kind:         `install` | `reload`
+    plugin:       string | null
+    at:           string
+} & (
+    | { status:      `running`
+        stage:       PluginOperationStage
+        step:        number
+        total_steps: number }
+    | { status: `completed` }
+    | { status: `failed`
+        error:  string }
+    | { status: `cancelled` }
+);
+
 /**
  * Plugin change event (from file watcher)
  */
@@ -60,6 +85,26 @@ export interface PluginChangeEvent {
     plugin_id?: string
 }
 
+/**
+ * Hot reload lifecycle event (from file watcher and plugin manager)
+ */
+export type PluginReloadEvent = {
+    plugin: string | null
+    path:   string
+    at:     string
+} & (
+    | { stage: `change_detected`
+        kind:  `added` | `modified` | `removed` }
+    | { stage: `debouncing` }
+    | { stage: `reloading` }
+    | { stage:       `reloaded`
+        version:     string
+        duration_ms: number }
+    | { stage: `unloaded` }
+    | { stage: `reload_failed`
+        error: string }
+);
+
 /**
  * Hook for managing plugins
  */
@@ -73,6 +118,7 @@ export interface UsePluginManagementReturn {
     disablePlugin:   (name: string) => Promise<PluginOperationResult>
     installPlugin:   (path: string) => Promise<PluginOperationResult>
     uninstallPlugin: (name: string) => Promise<PluginOperationResult>
+    cancelOperation: (operationId: string) => Promise<PluginOperationResult>
     getPluginInfo:   (name: string) => Promise<PluginInfo | null>
 }
 
@@ -209,6 +255,22 @@ export function usePluginManagement(): UsePluginManagementReturn {
         }
     }, [ refresh ]);
 
+    // Cancel an install or reload in progress
+    const cancelOperation = useCallback(async(operationId: string): Promise<PluginOperationResult> => {
+        try {
+            return await invoke<PluginOperationResult>(`cancel_plugin_operation`, {
+                operationId,
+            });
+        }
+        catch (err) {
+            const message = err instanceof Error ? err.message : String(err);
+            return {
+                success: false,
+                message,
+            };
+        }
+    }, []);
+
     // Get detailed plugin info
     const getPluginInfo = useCallback(async(name: string): Promise<PluginInfo | null> => {
         try {
@@ -232,6 +294,7 @@ export function usePluginManagement(): UsePluginManagementReturn {
         disablePlugin,
         installPlugin,
         uninstallPlugin,
+        cancelOperation,
         getPluginInfo,
     };
 }
@@ -288,6 +351,82 @@ export function usePluginWatcher(
     };
 }
 
+/**
+ * Hook for listening to hot reload progress and errors
+ */
+export function usePluginReloadEvents(
+    onReloadEvent?: (event: PluginReloadEvent) => void
+): void {
+    useEffect(() => {
+        let unlisten: UnlistenFn | null = null;
+        let cancelled = false;
+
+        const startListening = async(): Promise<void> => {
+            try {
+                const stop = await listen<PluginReloadEvent>(`plugin-reload`, (event) => {
+                    onReloadEvent?.(event.payload);
+                });
+                if (cancelled) {
+                    stop();
+                }
+                else {
+                    unlisten = stop;
+                }
+            }
+            catch (err) {
+                console.error(`Failed to start plugin reload listener:`, err);
+            }
+        };
+
+        void startListening();
+
+        return (): void => {
+            cancelled = true;
+            if (unlisten) {
+                unlisten();
+            }
+        };
+    }, [ onReloadEvent ]);
+}
+
+/**
+ * Hook for listening to plugin install and reload progress
+ */
+export function usePluginOperationProgress(
+    onProgress?: (event: PluginOperationProgress) => void
+): void {
+    useEffect(() => {
+        let unlisten: UnlistenFn | null = null;
+        let cancelled = false;
+
+        const startListening = async(): Promise<void> => {
+            try {
+                const stop = await listen<PluginOperationProgress>(`plugin-operation-progress`, (event) => {
+                    onProgress?.(event.payload);
+                });
+                if (cancelled) {
+                    stop();
+                }
+                else {
+                    unlisten = stop;
+                }
+            }
+            catch (err) {
+                console.error(`Failed to start plugin operation listener:`, err);
+            }
+        };
+
+        void startListening();
+
+        return (): void => {
+            cancelled = true;
+            if (unlisten) {
+                unlisten();
+            }
+        };
+    }, [ onProgress ]);
+}
+
 /**
  * Plugin state badge color mapping
  */
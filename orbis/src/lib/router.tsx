@@ -17,6 +17,7 @@ import {
     useLocation
 } from 'react-router-dom';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { toast } from 'sonner';
 import type { PageDefinition } from '../types/schema';
 
@@ -72,34 +73,50 @@ export function AuthProvider({
         roles:           [],
     });
 
-    // Check for existing session on mount
+    // Check for existing session on mount, and follow logins and logouts
+    // from other windows (the session is shared by all windows)
     useEffect(() => {
+        type Session = {
+            user_id: string;
+            username: string;
+            email: string;
+            token: string;
+            refresh_token: string | null;
+            permissions: string[];
+            roles: string[];
+            is_admin: boolean;
+            created_at: string;
+            expires_at: string | null;
+        };
+
+        const applySession = (session: Session | null) => {
+            if (session) {
+                setState({
+                    isAuthenticated: true,
+                    user: {
+                        id: session.user_id,
+                        name: session.username,
+                        email: session.email,
+                    },
+                    permissions: session.permissions,
+                    roles: session.roles,
+                });
+            } else {
+                setState({
+                    isAuthenticated: false,
+                    user: null,
+                    permissions: [],
+                    roles: [],
+                });
+            }
+        };
+
         const checkSession = async() => {
             try {
-                const session = await invoke<{
-                    user_id: string;
-                    username: string;
-                    email: string;
-                    token: string;
-                    refresh_token: string | null;
-                    permissions: string[];
-                    roles: string[];
-                    is_admin: boolean;
-                    created_at: string;
-                    expires_at: string | null;
-                } | null>('get_session');
+                const session = await invoke<Session | null>('get_session');
 
                 if (session) {
-                    setState({
-                        isAuthenticated: true,
-                        user: {
-                            id: session.user_id,
-                            name: session.username,
-                            email: session.email,
-                        },
-                        permissions: session.permissions,
-                        roles: session.roles,
-                    });
+                    applySession(session);
                 }
             } catch (error) {
                 console.error('Failed to check session:', error);
@@ -107,6 +124,22 @@ export function AuthProvider({
         };
 
         checkSession();
+
+        let unlisten: (() => void) | undefined;
+        let cancelled = false;
+        void listen<Session | null>('session-changed', (event) => applySession(event.payload))
+            .then((stop) => {
+                if (cancelled) {
+                    stop();
+                } else {
+                    unlisten = stop;
+                }
+            });
+
+        return () => {
+            cancelled = true;
+            unlisten?.();
+        };
     }, []);
 
     const value = useMemo<AuthContextValue>(() => ({
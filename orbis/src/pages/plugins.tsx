@@ -58,6 +58,7 @@ import {
 import {
     usePluginManagement,
     usePluginWatcher,
+    usePluginReloadEvents,
     type PluginInfo,
     type PluginState,
     getPluginStateColor,
@@ -539,6 +540,23 @@ export function PluginsPage(): React.ReactElement {
         }, [ refresh ])
     );
 
+    // Show hot reload outcomes, including errors that would otherwise only be logged
+    usePluginReloadEvents(
+        useCallback((event) => {
+            const name = event.plugin ?? event.path;
+            if (event.stage === `reload_failed`) {
+                toast.error(`Reload of ${ name } failed`, {
+                    description: event.error,
+                });
+            }
+            else if (event.stage === `reloaded`) {
+                toast.success(`Reloaded ${ name } v${ event.version }`, {
+                    description: `Took ${ event.duration_ms } ms`,
+                });
+            }
+        }, [])
+    );
+
     // Plugin operation handlers
     const handleReload = useCallback(async(name: string): Promise<void> => {
         setOperatingPlugin(name);
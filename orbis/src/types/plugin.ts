@@ -14,20 +14,29 @@ export interface PluginInfo {
     state:       `Loaded` | `Running` | `Disabled` | `Error`
 }
 
+// Page-level caching and revalidation hints
+export interface PageCacheHints {
+    ttl_seconds?:          number
+    revalidate_on_focus?:  boolean
+    revalidate_on_events?: Array<string>
+}
+
 export interface PluginPage {
-    plugin:        string
-    route:         string
-    title:         string
-    icon?:         string
-    description?:  string
-    show_in_menu:  boolean
-    menu_order?:   number
-    sections:      Array<ComponentSchema>
-    state?:        Record<string, StateFieldDefinition>
-    computed?:     Record<string, string>
-    actions?:      Record<string, Action>
-    hooks?:        PageLifecycleHooks
-    dialogs?:      Array<{
+    plugin:                 string
+    route:                  string
+    title:                  string
+    icon?:                  string
+    description?:           string
+    show_in_menu:           boolean
+    menu_order?:            number
+    sections:               Array<ComponentSchema>
+    state?:                 Record<string, StateFieldDefinition>
+    computed?:              Record<string, string>
+    computed_dependencies?: Record<string, Array<string>>
+    actions?:               Record<string, Action>
+    hooks?:                 PageLifecycleHooks
+    cache?:                 PageCacheHints
+    dialogs?:               Array<{
         id:           string
         title?:       string
         description?: string
@@ -35,9 +44,9 @@ export interface PluginPage {
         footer?:      ComponentSchema
         size?:        `sm` | `md` | `lg` | `xl` | `full`
     }>
-    requires_auth: boolean
-    permissions?:  Array<string>
-    roles?:        Array<string>
+    requires_auth:          boolean
+    permissions?:           Array<string>
+    roles?:                 Array<string>
 }
 
 // API Response types
@@ -0,0 +1,11 @@
+/**
+ * Plugin configuration from the manifest's `config` section.
+ */
+
+import { getConfig } from "orbis:plugin/host@1.0.0";
+
+/** Get a configuration value. */
+export function get<T>(key: string): T | undefined {
+    const raw = getConfig(key);
+    return raw === undefined ? undefined : (JSON.parse(raw) as T);
+}
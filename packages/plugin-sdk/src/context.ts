@@ -0,0 +1,124 @@
+/**
+ * Request context passed to plugin handlers.
+ */
+
+import { isCancelled } from "orbis:plugin/host@1.0.0";
+import { OrbisError } from "./error";
+
+/** Raw context as serialized by the host. */
+export interface RawContext {
+    method: string;
+    path: string;
+    params?: Record<string, string>;
+    headers?: Record<string, string>;
+    query?: Record<string, string>;
+    body?: unknown;
+    user_id?: string | null;
+    is_admin?: boolean;
+    request_id?: string | null;
+    tenant_id?: string | null;
+    deadline?: string | null;
+}
+
+/** Request context for plugin handlers. */
+export class Context {
+    readonly method: string;
+    readonly path: string;
+    readonly params: Record<string, string>;
+    readonly headers: Record<string, string>;
+    readonly query: Record<string, string>;
+    readonly body: unknown;
+    readonly userId: string | null;
+    readonly isAdmin: boolean;
+    readonly requestId: string | null;
+    /** Tenant the request belongs to (multi-tenant deployments only). */
+    readonly tenantId: string | null;
+    private readonly deadlineAt: Date | null;
+
+    constructor(raw: RawContext) {
+        this.method = raw.method;
+        this.path = raw.path;
+        this.params = raw.params ?? {};
+        this.headers = raw.headers ?? {};
+        this.query = raw.query ?? {};
+        this.body = raw.body ?? null;
+        this.userId = raw.user_id ?? null;
+        this.isAdmin = raw.is_admin ?? false;
+        this.requestId = raw.request_id ?? null;
+        this.tenantId = raw.tenant_id ?? null;
+        this.deadlineAt = raw.deadline ? new Date(raw.deadline) : null;
+    }
+
+    /** Parse the JSON context string handed to `handle`. */
+    static fromJson(json: string): Context {
+        return new Context(JSON.parse(json) as RawContext);
+    }
+
+    /** Time by which the request must complete, if the host set one. */
+    deadline(): Date | null {
+        return this.deadlineAt;
+    }
+
+    /**
+     * Check whether the request was cancelled or has passed its deadline.
+     *
+     * Long-running handlers should check this and stop early; the host
+     * interrupts the plugin shortly after either happens anyway.
+     */
+    isCancelled(): boolean {
+        return isCancelled();
+    }
+
+    /** Get a path parameter. */
+    param(name: string): string | undefined {
+        return this.params[name];
+    }
+
+    /** Get a required path parameter. */
+    paramRequired(name: string): string {
+        const value = this.params[name];
+        if (value === undefined) {
+            throw OrbisError.invalidInput(`Missing path parameter: ${name}`);
+        }
+        return value;
+    }
+
+    /** Get a query parameter. */
+    queryParam(name: string): string | undefined {
+        return this.query[name];
+    }
+
+    /** Get a header value (case-insensitive). */
+    header(name: string): string | undefined {
+        const lower = name.toLowerCase();
+        const entry = Object.entries(this.headers).find(([key]) => key.toLowerCase() === lower);
+        return entry?.[1];
+    }
+
+    /** Get the request body as a typed value. */
+    bodyAs<T>(): T {
+        return this.body as T;
+    }
+
+    /** Require an authenticated user and return their id. */
+    requireAuth(): string {
+        if (this.userId === null) {
+            throw OrbisError.permissionDenied("Authentication required");
+        }
+        return this.userId;
+    }
+
+    /** Require an admin user. */
+    requireAdmin(): void {
+        if (!this.isAdmin) {
+            throw OrbisError.permissionDenied("Admin access required");
+        }
+    }
+
+    /** Get pagination parameters (`page`, `per_page`) with defaults. */
+    pagination(): { page: number; perPage: number } {
+        const page = Math.max(Number.parseInt(this.query.page ?? "1", 10) || 1, 1);
+        const perPage = Math.min(Math.max(Number.parseInt(this.query.per_page ?? "20", 10) || 20, 1), 100);
+        return { page, perPage };
+    }
+}
@@ -0,0 +1,51 @@
+/**
+ * Database access.
+ *
+ * Requires the `database:read` / `database:write` permissions.
+ */
+
+import { dbExecute, dbQuery, dbQueryBatch } from "orbis:plugin/host@1.0.0";
+
+import { OrbisError } from "./error";
+
+/** Parameter value bound to a statement. */
+export type DbValue = string | number | boolean | null;
+
+/** Run a query and return all rows. */
+export function query<T = Record<string, unknown>>(sql: string, params: DbValue[] = []): T[] {
+    try {
+        return JSON.parse(dbQuery(sql, JSON.stringify(params))) as T[];
+    } catch (e) {
+        throw OrbisError.database(String(e));
+    }
+}
+
+/** A query to run as part of a batch. */
+export interface BatchQuery {
+    sql: string;
+    params?: DbValue[];
+}
+
+/** Run several queries in a single host call and return the rows of each, in order. */
+export function queryBatch<T = Record<string, unknown>>(queries: BatchQuery[]): T[][] {
+    const batch = queries.map(({ sql, params }) => ({ sql, params: params ?? [] }));
+    try {
+        return JSON.parse(dbQueryBatch(JSON.stringify(batch))) as T[][];
+    } catch (e) {
+        throw OrbisError.database(String(e));
+    }
+}
+
+/** Run a query and return the first row, if any. */
+export function queryOne<T = Record<string, unknown>>(sql: string, params: DbValue[] = []): T | undefined {
+    return query<T>(sql, params)[0];
+}
+
+/** Execute a statement and return the number of affected rows. */
+export function execute(sql: string, params: DbValue[] = []): number {
+    try {
+        return Number(dbExecute(sql, JSON.stringify(params)));
+    } catch (e) {
+        throw OrbisError.database(String(e));
+    }
+}
@@ -0,0 +1,42 @@
+/**
+ * SDK error type.
+ */
+
+/** Error raised by SDK helpers; mapped to an error response by `definePlugin`. */
+export class OrbisError extends Error {
+    readonly status: number;
+
+    constructor(status: number, message: string) {
+        super(message);
+        this.name = "OrbisError";
+        this.status = status;
+    }
+
+    static state(message: string): OrbisError {
+        return new OrbisError(500, `State error: ${message}`);
+    }
+
+    static database(message: string): OrbisError {
+        return new OrbisError(500, `Database error: ${message}`);
+    }
+
+    static http(message: string): OrbisError {
+        return new OrbisError(500, `HTTP error: ${message}`);
+    }
+
+    static permissionDenied(message: string): OrbisError {
+        return new OrbisError(403, `Permission denied: ${message}`);
+    }
+
+    static invalidInput(message: string): OrbisError {
+        return new OrbisError(400, `Invalid input: ${message}`);
+    }
+
+    static notFound(message: string): OrbisError {
+        return new OrbisError(404, `Not found: ${message}`);
+    }
+
+    static internal(message: string): OrbisError {
+        return new OrbisError(500, `Internal error: ${message}`);
+    }
+}
@@ -0,0 +1,12 @@
+/**
+ * Event emission.
+ *
+ * Requires the `events:emit` permission.
+ */
+
+import { emitEvent } from "orbis:plugin/host@1.0.0";
+
+/** Emit an event with a JSON payload. */
+export function emit(event: string, payload: unknown = null): void {
+    emitEvent(event, JSON.stringify(payload));
+}
@@ -0,0 +1,23 @@
+/**
+ * Host interface imported by component plugins (`orbis:plugin/host`).
+ *
+ * Generated bindings follow the componentize-js conventions: `option<T>`
+ * maps to `T | undefined` and `result<T, E>` returns `T` or throws the error.
+ */
+declare module "orbis:plugin/host@1.0.0" {
+    export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+    export function log(level: LogLevel, message: string): void;
+    export function stateGet(key: string): string | undefined;
+    export function stateSet(key: string, value: string): void;
+    export function stateRemove(key: string): void;
+    export function stateGetMany(keys: string[]): [string, string][];
+    export function stateSetMany(entries: [string, string | undefined][]): void;
+    export function getConfig(key: string): string | undefined;
+    export function dbQuery(sql: string, params: string): string;
+    export function dbQueryBatch(queries: string): string;
+    export function dbExecute(sql: string, params: string): bigint;
+    export function httpRequest(method: string, url: string, headers: string, body: Uint8Array): string;
+    export function emitEvent(event: string, payload: string): void;
+    export function isCancelled(): boolean;
+}
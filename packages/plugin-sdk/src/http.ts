@@ -0,0 +1,65 @@
+/**
+ * Outbound HTTP requests.
+ *
+ * Requires the `network:http` permission and the target host in the
+ * manifest's allowed network hosts.
+ */
+
+import { httpRequest } from "orbis:plugin/host@1.0.0";
+
+import { OrbisError } from "./error";
+
+export type Method = "GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" | "OPTIONS";
+
+/** Response returned by the host. */
+export interface HttpResponse {
+    status: number;
+    headers: Record<string, string>;
+    body: unknown;
+}
+
+/** Request options. */
+export interface RequestOptions {
+    headers?: Record<string, string>;
+    /** JSON-serialized when not a string. */
+    body?: unknown;
+}
+
+/** Make an HTTP request. */
+export function request(method: Method, url: string, options: RequestOptions = {}): HttpResponse {
+    const headers = { ...options.headers };
+    let body = "";
+    if (typeof options.body === "string") {
+        body = options.body;
+    } else if (options.body !== undefined) {
+        body = JSON.stringify(options.body);
+        headers["Content-Type"] ??= "application/json";
+    }
+
+    try {
+        const raw = httpRequest(method, url, JSON.stringify(headers), new TextEncoder().encode(body));
+        return JSON.parse(raw) as HttpResponse;
+    } catch (e) {
+        throw OrbisError.http(String(e));
+    }
+}
+
+export function get(url: string, options?: RequestOptions): HttpResponse {
+    return request("GET", url, options);
+}
+
+export function post(url: string, options?: RequestOptions): HttpResponse {
+    return request("POST", url, options);
+}
+
+export function put(url: string, options?: RequestOptions): HttpResponse {
+    return request("PUT", url, options);
+}
+
+export function patch(url: string, options?: RequestOptions): HttpResponse {
+    return request("PATCH", url, options);
+}
+
+export function del(url: string, options?: RequestOptions): HttpResponse {
+    return request("DELETE", url, options);
+}
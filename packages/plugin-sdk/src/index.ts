@@ -0,0 +1,69 @@
+/**
+ * Orbis plugin SDK for JavaScript and TypeScript.
+ *
+ * Plugins are compiled to WASM components implementing the
+ * `orbis:plugin/plugin` world (see `wit/plugin.wit`) and are loaded through
+ * the same unpacked, standalone and packed flavors as Rust plugins.
+ *
+ * ```ts
+ * import { definePlugin, Response, state } from "@orbis/plugin-sdk";
+ *
+ * export const handle = definePlugin({
+ *     greet(ctx) {
+ *         const count = state.increment("counter");
+ *         return Response.json({ message: `Hello, ${ctx.userId ?? "guest"}!`, count });
+ *     },
+ * });
+ * ```
+ */
+
+import { Context } from "./context";
+import { OrbisError } from "./error";
+import { Response } from "./response";
+import { flush } from "./state";
+
+export * as config from "./config";
+export * as db from "./db";
+export * as events from "./events";
+export * as http from "./http";
+export * as log from "./log";
+export * as state from "./state";
+export { Context, OrbisError, Response };
+export type { RawContext } from "./context";
+export type { BatchQuery, DbValue } from "./db";
+export type { HttpResponse, Method, RequestOptions } from "./http";
+
+/** Route handler. Plain values are wrapped in a 200 JSON response. */
+export type Handler = (ctx: Context) => unknown;
+
+/**
+ * Build the `handle` export from a map of handler names to functions.
+ *
+ * Thrown `OrbisError`s become error responses; unknown handlers and other
+ * exceptions are reported to the host as handler failures.
+ */
+export function definePlugin(handlers: Record<string, Handler>): (handler: string, context: string) => string {
+    return (handler, context) => {
+        const fn = handlers[handler];
+        if (fn === undefined) {
+            throw `Handler '${handler}' not found`;
+        }
+
+        let response: Response;
+        try {
+            const result = fn(Context.fromJson(context));
+            response = result instanceof Response ? result : Response.json(result);
+        } catch (e) {
+            if (e instanceof OrbisError) {
+                response = Response.fromError(e);
+            } else {
+                throw e instanceof Error ? e.message : String(e);
+            }
+        } finally {
+            // Write out state changes buffered during the handler
+            flush();
+        }
+
+        return JSON.stringify(response);
+    };
+}
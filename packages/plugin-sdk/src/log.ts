@@ -0,0 +1,25 @@
+/**
+ * Logging to the host log, prefixed with the plugin name.
+ */
+
+import { log } from "orbis:plugin/host@1.0.0";
+
+export function error(message: string): void {
+    log("error", message);
+}
+
+export function warn(message: string): void {
+    log("warn", message);
+}
+
+export function info(message: string): void {
+    log("info", message);
+}
+
+export function debug(message: string): void {
+    log("debug", message);
+}
+
+export function trace(message: string): void {
+    log("trace", message);
+}
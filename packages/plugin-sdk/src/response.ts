@@ -0,0 +1,91 @@
+/**
+ * Response builder for plugin handlers.
+ */
+
+import type { OrbisError } from "./error";
+
+/** HTTP response returned by plugin handlers. */
+export class Response {
+    status: number;
+    headers: Record<string, string>;
+    body: unknown;
+
+    constructor(status: number, body: unknown) {
+        this.status = status;
+        this.headers = {};
+        this.body = body;
+    }
+
+    /** Create a 200 OK response with a JSON body. */
+    static json(data: unknown): Response {
+        return new Response(200, data);
+    }
+
+    /** Create a 201 Created response. */
+    static created(data: unknown): Response {
+        return new Response(201, data);
+    }
+
+    /** Create a 204 No Content response. */
+    static noContent(): Response {
+        return new Response(204, null);
+    }
+
+    /** Create an error response. */
+    static error(status: number, message: string): Response {
+        return new Response(status, { error: true, message });
+    }
+
+    static badRequest(message: string): Response {
+        return Response.error(400, message);
+    }
+
+    static unauthorized(message: string): Response {
+        return Response.error(401, message);
+    }
+
+    static forbidden(message: string): Response {
+        return Response.error(403, message);
+    }
+
+    static notFound(message: string): Response {
+        return Response.error(404, message);
+    }
+
+    static internalError(message: string): Response {
+        return Response.error(500, message);
+    }
+
+    /** Create a response from an SDK error. */
+    static fromError(err: OrbisError): Response {
+        return Response.error(err.status, err.message);
+    }
+
+    /** Add a header to the response. */
+    withHeader(name: string, value: string): Response {
+        this.headers[name] = value;
+        return this;
+    }
+
+    /** Set the `Content-Type` header. */
+    contentType(value: string): Response {
+        return this.withHeader("Content-Type", value);
+    }
+
+    /** Set the `Cache-Control` header. */
+    cacheControl(value: string): Response {
+        return this.withHeader("Cache-Control", value);
+    }
+
+    /** Disable caching. */
+    noCache(): Response {
+        return this.cacheControl("no-store, no-cache, must-revalidate");
+    }
+
+    /** Serialize the response for the host. */
+    toJSON(): { status: number; headers?: Record<string, string>; body: unknown } {
+        return Object.keys(this.headers).length === 0
+            ? { status: this.status, body: this.body }
+            : { status: this.status, headers: this.headers, body: this.body };
+    }
+}
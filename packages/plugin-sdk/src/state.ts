@@ -0,0 +1,119 @@
+/**
+ * Plugin key-value state, persisted by the host.
+ */
+
+import { stateGet, stateGetMany, stateRemove, stateSet, stateSetMany } from "orbis:plugin/host@1.0.0";
+
+import { OrbisError } from "./error";
+
+/** Writes buffered by `setBuffered`/`removeBuffered`; `undefined` marks a removal. */
+const pending = new Map<string, string | undefined>();
+
+/** Get a value from state. Buffered writes that have not been flushed are visible. */
+export function get<T>(key: string): T | undefined {
+    const raw = pending.has(key) ? pending.get(key) : stateGet(key);
+    return raw === undefined ? undefined : (JSON.parse(raw) as T);
+}
+
+/** Get several values in a single host call; missing keys are omitted. */
+export function getMany<T>(keys: string[]): Record<string, T> {
+    const values: Record<string, T> = {};
+    const missing: string[] = [];
+
+    for (const key of keys) {
+        if (!pending.has(key)) {
+            missing.push(key);
+            continue;
+        }
+        const raw = pending.get(key);
+        if (raw !== undefined) {
+            values[key] = JSON.parse(raw) as T;
+        }
+    }
+
+    if (missing.length > 0) {
+        for (const [key, raw] of stateGetMany(missing)) {
+            values[key] = JSON.parse(raw) as T;
+        }
+    }
+
+    return values;
+}
+
+/** Get a value from state, or a default. */
+export function getOr<T>(key: string, fallback: T): T {
+    return get<T>(key) ?? fallback;
+}
+
+/** Store a value in state. */
+export function set(key: string, value: unknown): void {
+    pending.delete(key);
+    try {
+        stateSet(key, JSON.stringify(value));
+    } catch (e) {
+        throw OrbisError.state(String(e));
+    }
+}
+
+/** Remove a value from state. */
+export function remove(key: string): void {
+    pending.delete(key);
+    stateRemove(key);
+}
+
+/**
+ * Buffer a write to state.
+ *
+ * Buffered writes are sent to the host in a single call by `flush`, which
+ * runs automatically when the handler returns.
+ */
+export function setBuffered(key: string, value: unknown): void {
+    pending.set(key, JSON.stringify(value));
+}
+
+/** Buffer a removal from state. See `setBuffered`. */
+export function removeBuffered(key: string): void {
+    pending.set(key, undefined);
+}
+
+/** Send buffered writes to the host in a single call. */
+export function flush(): void {
+    if (pending.size === 0) {
+        return;
+    }
+
+    const entries = [...pending.entries()];
+    pending.clear();
+    try {
+        stateSetMany(entries);
+    } catch (e) {
+        throw OrbisError.state(String(e));
+    }
+}
+
+/** Check whether a key exists. */
+export function exists(key: string): boolean {
+    return get(key) !== undefined;
+}
+
+/** Update a value in place. */
+export function update<T>(key: string, fallback: T, f: (current: T) => T): T {
+    const next = f(getOr(key, fallback));
+    set(key, next);
+    return next;
+}
+
+/** Increment a numeric value. */
+export function increment(key: string): number {
+    return update(key, 0, (n) => n + 1);
+}
+
+/** Decrement a numeric value. */
+export function decrement(key: string): number {
+    return update(key, 0, (n) => n - 1);
+}
+
+/** Append to a list. */
+export function push(key: string, value: unknown): void {
+    update<unknown[]>(key, [], (list) => [...list, value]);
+}
@@ -7,6 +7,12 @@ cd "$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 # Ensure we go back on any exit
 trap 'cd "$ORIG_DIR"' EXIT
 
+# Optional: --sign-key <file> signs the standalone plugin (see `orbis-server plugin keygen`)
+SIGN_KEY=""
+if [ "${1:-}" = "--sign-key" ]; then
+    SIGN_KEY=$(cd "$ORIG_DIR" && realpath "${2:?--sign-key requires a key file}")
+fi
+
 echo "Building Hello Plugin..."
 
 # Build the WASM module
@@ -31,6 +37,9 @@ cat manifest.json | python3 ../add_custom_section.py hello_plugin.wasm -s manife
 # 3. Standalone WASM with embedded manifest
 echo "  - standalone.wasm"
 cat manifest.json | python3 ../add_custom_section.py hello_plugin.wasm -s manifest -o standalone.wasm
+if [ -n "$SIGN_KEY" ]; then
+    cargo run --quiet -p orbis-server -- plugin sign standalone.wasm --key "$SIGN_KEY"
+fi
 # 4. Packed ZIP with external manifest
 echo "  - packed-external.zip"
 cd unpacked-external